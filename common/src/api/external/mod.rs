@@ -533,7 +533,10 @@ pub enum ResourceType {
     SiloGroup,
     IdentityProvider,
     SamlIdentityProvider,
+    OidcIdentityProvider,
+    ScimClientBearerToken,
     SshKey,
+    ApiToken,
     ConsoleSession,
     DeviceAuthRequest,
     DeviceAccessToken,
@@ -542,6 +545,7 @@ pub enum ResourceType {
     Project,
     Dataset,
     Disk,
+    FloatingIp,
     Image,
     Instance,
     IpPool,
@@ -556,6 +560,8 @@ pub enum ResourceType {
     VpcFirewallRule,
     VpcSubnet,
     VpcRouter,
+    VpcPeering,
+    InternetGateway,
     RouterRoute,
     Oximeter,
     MetricProducer,
@@ -774,6 +780,8 @@ pub struct Instance {
     pub memory: ByteCount,
     /// RFC1035-compliant hostname for the Instance.
     pub hostname: String, // TODO-cleanup different type?
+    /// tags attached to this Instance, used to select it in firewall rules
+    pub tags: Vec<Name>,
 
     #[serde(flatten)]
     pub runtime: InstanceRuntimeState,
@@ -1569,8 +1577,8 @@ pub enum VpcFirewallRuleTarget {
     Ip(IpAddr),
     /// The rule applies to a specific IP subnet
     IpNet(IpNet),
-    // Tags not yet implemented
-    // Tag(Name),
+    /// The rule applies to all instances carrying this tag
+    Tag(Name),
 }
 
 /// The `VpcFirewallRuleHostFilter` is used to filter traffic on the basis of
@@ -1594,8 +1602,8 @@ pub enum VpcFirewallRuleHostFilter {
     Subnet(Name),
     /// The rule applies to traffic from/to this specific instance
     Instance(Name),
-    // Tags not yet implemented
-    // Tag(Name),
+    /// The rule applies to traffic from/to all instances carrying this tag
+    Tag(Name),
     /// The rule applies to traffic from/to a specific IP address
     Ip(IpAddr),
     /// The rule applies to traffic from/to a specific IP subnet
@@ -2419,9 +2427,13 @@ mod test {
             "subnet:foo".parse().unwrap()
         );
         assert_eq!(
-            VpcFirewallRuleTarget::Instance(name),
+            VpcFirewallRuleTarget::Instance(name.clone()),
             "instance:foo".parse().unwrap()
         );
+        assert_eq!(
+            VpcFirewallRuleTarget::Tag(name),
+            "tag:foo".parse().unwrap()
+        );
         assert_eq!(
             VpcFirewallRuleTarget::Ip(address),
             "ip:192.168.0.10".parse().unwrap()
@@ -2448,9 +2460,13 @@ mod test {
             "subnet:foo".parse().unwrap()
         );
         assert_eq!(
-            VpcFirewallRuleHostFilter::Instance(name),
+            VpcFirewallRuleHostFilter::Instance(name.clone()),
             "instance:foo".parse().unwrap()
         );
+        assert_eq!(
+            VpcFirewallRuleHostFilter::Tag(name),
+            "tag:foo".parse().unwrap()
+        );
         assert_eq!(
             VpcFirewallRuleHostFilter::Ip(address),
             "ip:192.168.0.10".parse().unwrap()
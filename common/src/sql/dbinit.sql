@@ -307,12 +307,14 @@ CREATE INDEX on omicron.public.volume (
 
 CREATE TYPE omicron.public.authentication_mode AS ENUM (
   'local',
-  'saml'
+  'saml',
+  'oidc'
 );
 
 CREATE TYPE omicron.public.user_provision_type AS ENUM (
   'api_only',
-  'jit'
+  'jit',
+  'scim'
 );
 
 CREATE TABLE omicron.public.silo (
@@ -347,7 +349,12 @@ CREATE TABLE omicron.public.silo_user (
     time_deleted TIMESTAMPTZ,
 
     silo_id UUID NOT NULL,
-    external_id TEXT NOT NULL
+    external_id TEXT NOT NULL,
+    /*
+     * Deactivated users (in Silos provisioned through SCIM) may not log in,
+     * but are kept around so that they can be reactivated.
+     */
+    active BOOL NOT NULL
 );
 
 /* This index lets us quickly find users for a given silo. */
@@ -357,6 +364,22 @@ CREATE UNIQUE INDEX ON omicron.public.silo_user (
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Password hashes of users in a Silo's local identity provider
+ */
+CREATE TABLE omicron.public.silo_user_password_hash (
+    silo_user_id UUID PRIMARY KEY,
+    /* argon2 hash, in PHC string format */
+    hash TEXT NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+
+    /* consecutive failed login attempts since the last success or lockout */
+    failed_attempts INT8 NOT NULL,
+    /* if set, logins are refused until this time */
+    time_locked_until TIMESTAMPTZ
+);
+
 /*
  * Silo groups
  */
@@ -398,7 +421,8 @@ CREATE INDEX ON omicron.public.silo_group_membership (
  */
 
 CREATE TYPE omicron.public.provider_type AS ENUM (
-  'saml'
+  'saml',
+  'oidc'
 );
 
 CREATE TABLE omicron.public.identity_provider (
@@ -460,6 +484,39 @@ CREATE INDEX ON omicron.public.saml_identity_provider (
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Silo OpenID Connect identity provider
+ */
+CREATE TABLE omicron.public.oidc_identity_provider (
+    /* Identity metadata */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL,
+
+    issuer TEXT NOT NULL,
+    client_id TEXT NOT NULL,
+    client_secret TEXT NOT NULL,
+    scopes STRING[] NOT NULL,
+    redirect_url TEXT NOT NULL,
+    group_claim_name TEXT,
+
+    /* Endpoints from the issuer's discovery document */
+    authorization_endpoint TEXT NOT NULL,
+    token_endpoint TEXT NOT NULL,
+    jwks_uri TEXT NOT NULL
+);
+
+CREATE INDEX ON omicron.public.oidc_identity_provider (
+    id,
+    silo_id
+) WHERE
+    time_deleted IS NULL;
+
 /*
  * Users' public SSH keys, per RFD 44
  */
@@ -539,6 +596,65 @@ CREATE UNIQUE INDEX ON omicron.public.project (
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Project-level defaults for resources created in a Project. A Project without
+ * a row here, or with a NULL setting, uses the VPC and VPC Subnet named
+ * "default" and any IP Pool available to it.
+ */
+CREATE TABLE omicron.public.project_settings (
+    /* The project these settings apply to */
+    project_id UUID PRIMARY KEY,
+    time_modified TIMESTAMPTZ NOT NULL,
+
+    default_vpc_id UUID,
+    default_vpc_subnet_id UUID,
+    default_ip_pool_id UUID
+);
+
+/*
+ * These indexes are used to prevent the deletion of resources which are the
+ * default of a project.
+ */
+CREATE INDEX ON omicron.public.project_settings (
+    default_vpc_id
+);
+
+CREATE INDEX ON omicron.public.project_settings (
+    default_vpc_subnet_id
+);
+
+CREATE INDEX ON omicron.public.project_settings (
+    default_ip_pool_id
+);
+
+/*
+ * Quotas on the resources allocated within a Silo or Project, along with the
+ * resources currently counted against them.  A row is created the first time
+ * a resource is charged to the Silo or Project, when its quotas are set, or
+ * when Nexus recounts the resources of Silos and Projects at startup.
+ * A NULL limit means that resource is unlimited.
+ */
+CREATE TABLE omicron.public.resource_quota (
+    /* The Silo or Project these quotas apply to */
+    id UUID PRIMARY KEY,
+    time_modified TIMESTAMPTZ NOT NULL,
+
+    cpus_limit INT8 CHECK (cpus_limit >= 0),
+    memory_limit INT8 CHECK (memory_limit >= 0),
+    disk_limit INT8 CHECK (disk_limit >= 0),
+    snapshots_limit INT8 CHECK (snapshots_limit >= 0),
+    external_ips_limit INT8 CHECK (external_ips_limit >= 0),
+
+    cpus_used INT8 NOT NULL CHECK (cpus_used >= 0),
+    /* Memory of instances, in bytes */
+    memory_used INT8 NOT NULL CHECK (memory_used >= 0),
+    /* Size of disks, in bytes */
+    disk_used INT8 NOT NULL CHECK (disk_used >= 0),
+    snapshots_used INT8 NOT NULL CHECK (snapshots_used >= 0),
+    /* Ephemeral and Floating IPs; source NAT addresses are not counted */
+    external_ips_used INT8 NOT NULL CHECK (external_ips_used >= 0)
+);
+
 /*
  * Instances
  */
@@ -609,7 +725,10 @@ CREATE TABLE omicron.public.instance (
     /* Instance configuration */
     ncpus INT NOT NULL,
     memory INT NOT NULL,
-    hostname STRING(63) NOT NULL
+    hostname STRING(63) NOT NULL,
+
+    /* Tags used to select this Instance as a firewall rule target or host */
+    tags STRING(63)[] NOT NULL
 );
 
 CREATE UNIQUE INDEX ON omicron.public.instance (
@@ -889,7 +1008,12 @@ CREATE TABLE omicron.public.vpc_subnet (
     /* Child resource creation generation number */
     rcgen INT8 NOT NULL,
     ipv4_block INET NOT NULL,
-    ipv6_block INET NOT NULL
+    ipv6_block INET NOT NULL,
+    /*
+     * The custom VPC router associated with this subnet, if any. Subnets
+     * without a custom router use only the VPC's system router.
+     */
+    custom_router_id UUID
 );
 
 /* Subnet and network interface names are unique per VPC, not project */
@@ -1089,6 +1213,78 @@ CREATE UNIQUE INDEX ON omicron.public.router_route (
 ) WHERE
     time_deleted IS NULL;
 
+CREATE TYPE omicron.public.vpc_peering_state AS ENUM (
+    'pending',
+    'active'
+);
+
+/*
+ * One side of a peering between two VPCs in the same Silo.  Each VPC records
+ * its own side, and the peering becomes active once both sides exist.
+ */
+CREATE TABLE omicron.public.vpc_peering (
+    /* Identity metadata (resource) */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /* Indicates that the object has been deleted */
+    time_deleted TIMESTAMPTZ,
+
+    vpc_id UUID NOT NULL,
+    peer_vpc_id UUID NOT NULL,
+    state omicron.public.vpc_peering_state NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.vpc_peering (
+    vpc_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+/* A VPC may have at most one peering with any other VPC. */
+CREATE UNIQUE INDEX ON omicron.public.vpc_peering (
+    vpc_id,
+    peer_vpc_id
+) WHERE
+    time_deleted IS NULL;
+
+/* Used to find the peerings of a VPC's peers. */
+CREATE INDEX ON omicron.public.vpc_peering (
+    peer_vpc_id
+) WHERE
+    time_deleted IS NULL;
+
+/*
+ * A VPC's gateway to destinations outside the rack.  Routes direct traffic to
+ * a gateway by name, and instances in the VPC are given source NAT addresses
+ * from the gateway's IP Pools.
+ */
+CREATE TABLE omicron.public.internet_gateway (
+    /* Identity metadata (resource) */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /* Indicates that the object has been deleted */
+    time_deleted TIMESTAMPTZ,
+
+    vpc_id UUID NOT NULL,
+    /*
+     * The IP Pools from which addresses are allocated, in order of
+     * preference.  If empty, the project's default IP Pool is used.
+     */
+    ip_pool_ids UUID[] NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.internet_gateway (
+    vpc_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
 /*
  * An IP Pool, a collection of zero or more IP ranges for external IPs.
  */
@@ -1313,6 +1509,39 @@ CREATE UNIQUE INDEX ON omicron.public.external_ip (
 )
     WHERE instance_id IS NOT NULL AND time_deleted IS NULL;
 
+/*
+ * Index used to enforce uniqueness of Floating IP names within a project.
+ */
+CREATE UNIQUE INDEX ON omicron.public.external_ip (
+    project_id,
+    name
+)
+    WHERE kind = 'floating' AND time_deleted IS NULL;
+
+/*
+ * Floating IPs are named API resources scoped to a project, which the other
+ * kinds of external IPs are not.  This view presents them with a non-NULL
+ * name, description, and project, so that they can be looked up and
+ * authorized like any other resource below a project.
+ */
+CREATE VIEW omicron.public.floating_ip AS
+SELECT
+    id,
+    name,
+    description,
+    time_created,
+    time_modified,
+    time_deleted,
+    ip_pool_id,
+    ip_pool_range_id,
+    project_id,
+    instance_id,
+    ip
+FROM
+    omicron.public.external_ip
+WHERE
+    kind = 'floating' AND project_id IS NOT NULL;
+
 /*******************************************************************/
 
 /*
@@ -1401,6 +1630,37 @@ CREATE TABLE omicron.public.saga_node_event (
     PRIMARY KEY (saga_id, node_id, event_type)
 );
 
+/*
+ * Operator interventions on sagas
+ *
+ * Sagas can get stuck, either because an action never completes or because
+ * the SEC that owned them went away.  Operators can abandon such a saga (so
+ * that no SEC will resume it) or retry it (so that this Nexus adopts it and
+ * resumes it from its log).  Each such intervention is recorded here so that
+ * we know who did what, when, and why.
+ */
+CREATE TYPE omicron.public.saga_intervention_kind AS ENUM (
+    'abandon',
+    'retry'
+);
+
+CREATE TABLE omicron.public.saga_intervention (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    saga_id UUID NOT NULL,
+    kind omicron.public.saga_intervention_kind NOT NULL,
+    /* the user (built-in or silo user) that requested the intervention */
+    actor_id UUID NOT NULL,
+    /* for a retry, the SEC that adopted the saga */
+    new_sec UUID,
+    reason STRING(512) NOT NULL
+);
+
+CREATE INDEX ON omicron.public.saga_intervention (
+    saga_id,
+    time_created
+);
+
 /*******************************************************************/
 
 /*
@@ -1410,7 +1670,17 @@ CREATE TABLE omicron.public.console_session (
     token STRING(40) PRIMARY KEY,
     time_created TIMESTAMPTZ NOT NULL,
     time_last_used TIMESTAMPTZ NOT NULL,
-    silo_user_id UUID NOT NULL
+    silo_user_id UUID NOT NULL,
+    /* Identifies the session without revealing its token, e.g. to revoke it */
+    id UUID NOT NULL,
+    /* User-Agent of the client that logged in, if it sent one */
+    user_agent STRING(512),
+    /* Address of the client that logged in, if known */
+    client_address INET
+);
+
+CREATE UNIQUE INDEX ON omicron.public.console_session (
+    id
 );
 
 -- to be used for cleaning up old tokens
@@ -1418,7 +1688,8 @@ CREATE INDEX ON omicron.public.console_session (
     time_created
 );
 
--- This index is used to remove sessions for a user that's being deleted.
+-- This index is used to list a user's sessions and to remove sessions for a
+-- user that's being deleted or logged out everywhere.
 CREATE INDEX ON omicron.public.console_session (
     silo_user_id
 );
@@ -1520,9 +1791,11 @@ CREATE TABLE omicron.public.device_auth_request (
 );
 
 -- Access tokens granted in response to successful device authorization flows.
--- TODO-security: expire tokens.
+-- Tokens stop being accepted at `time_expires`, if it's set.
 CREATE TABLE omicron.public.device_access_token (
     token STRING(40) PRIMARY KEY,
+    /* Identifies the token without revealing it, e.g. to revoke it */
+    id UUID NOT NULL,
     client_id UUID NOT NULL,
     device_code STRING(40) NOT NULL,
     silo_user_id UUID NOT NULL,
@@ -1537,11 +1810,65 @@ CREATE UNIQUE INDEX ON omicron.public.device_access_token (
     client_id, device_code
 );
 
--- This index is used to remove tokens for a user that's being deleted.
+CREATE UNIQUE INDEX ON omicron.public.device_access_token (
+    id
+);
+
+-- This index is used to list a user's tokens and to remove tokens for a user
+-- that's being deleted.
 CREATE INDEX ON omicron.public.device_access_token (
     silo_user_id
 );
 
+-- Long-lived API tokens that users create for automation.  Only a hash of
+-- each token is stored.
+CREATE TABLE omicron.public.api_token (
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    /* FK into silo_user table */
+    silo_user_id UUID NOT NULL,
+
+    /* hex-encoded SHA-256 hash of the token */
+    token_hash STRING(64) NOT NULL,
+    time_expires TIMESTAMPTZ,
+    time_last_used TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX ON omicron.public.api_token (
+    silo_user_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+CREATE UNIQUE INDEX ON omicron.public.api_token (
+    token_hash
+) WHERE
+    time_deleted IS NULL;
+
+-- Bearer tokens with which identity providers authenticate to a Silo's SCIM
+-- API.
+CREATE TABLE omicron.public.scim_client_bearer_token (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    silo_id UUID NOT NULL,
+    /* hex-encoded SHA-256 hash of the token */
+    bearer_token_hash STRING(64) NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.scim_client_bearer_token (
+    bearer_token_hash
+);
+
+-- This index is used to list and remove the tokens of a Silo.
+CREATE INDEX ON omicron.public.scim_client_bearer_token (
+    silo_id
+);
+
 /*
  * Roles built into the system
  *
@@ -1653,6 +1980,54 @@ CREATE TABLE omicron.public.role_assignment (
 
 /*******************************************************************/
 
+/*
+ * Audit log
+ *
+ * Nexus appends one row to this table for each mutating request (POST, PUT,
+ * PATCH, or DELETE) made to the external API, whether or not the request
+ * succeeded.  Rows are never modified or removed through the API.
+ */
+CREATE TABLE omicron.public.audit_log (
+    id UUID PRIMARY KEY,
+    /* when the request started and finished */
+    time_started TIMESTAMPTZ NOT NULL,
+    time_completed TIMESTAMPTZ NOT NULL,
+    /* the request id assigned by the HTTP server, as reported to the client */
+    request_id STRING(63) NOT NULL,
+    /*
+     * The authenticated actor, if any, and the Silo it belongs to.  Built-in
+     * users have no Silo.  SCIM clients have a Silo but no actor.
+     */
+    actor_id UUID,
+    actor_silo_id UUID,
+    /* what was requested */
+    http_method STRING(15) NOT NULL,
+    request_uri STRING(4096) NOT NULL,
+    /*
+     * ids of the resources that the request was authorized to create things
+     * in, modify, or delete
+     */
+    resource_ids UUID[] NOT NULL,
+    /* the outcome */
+    http_status_code INT4 NOT NULL,
+    error_message STRING(4096)
+);
+
+/* This index is used to page through the whole audit log. */
+CREATE INDEX ON omicron.public.audit_log (
+    time_completed,
+    id
+);
+
+/* This index is used to page through the audit log of one Silo. */
+CREATE INDEX ON omicron.public.audit_log (
+    actor_silo_id,
+    time_completed,
+    id
+);
+
+/*******************************************************************/
+
 /*
  * Metadata for the schema itself.  This version number isn't great, as there's
  * nothing to ensure it gets bumped when it should be, but it's a start.
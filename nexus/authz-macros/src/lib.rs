@@ -38,12 +38,12 @@ use serde_tokenstream::ParseWrapper;
 ///
 /// ## Resource within a Project
 ///
-/// We do not yet support assigning roles to resources below the Project level.
-/// For these, we use an auto-generated "in-project" Polar snippet:
+/// Most resources below the Project level do not support assigning roles.  For
+/// these, we use an auto-generated "in-project" Polar snippet:
 ///
 /// ```ignore
 /// authz_resource! {
-///     name = "Instance",
+///     name = "Snapshot",
 ///     parent = "Project",
 ///     primary_key = Uuid,
 ///     roles_allowed = false,
@@ -266,6 +266,16 @@ fn do_authz_resource(
         ),
     };
 
+    // Resources identified by a uuid report it so that requests acting on them
+    // can be recorded in the audit log.  Other keys may be secret (e.g.,
+    // tokens), so we don't report them.
+    let resource_uuid_body = match primary_key_type {
+        syn::Type::Path(p) if p.path.is_ident("Uuid") => {
+            quote! { Some(self.key) }
+        }
+        _ => quote! { None },
+    };
+
     let doc_struct = format!(
         "`authz` type for a resource of type {}\
         \
@@ -354,6 +364,10 @@ fn do_authz_resource(
                 &self.lookup_type
             }
 
+            fn resource_uuid(&self) -> Option<Uuid> {
+                #resource_uuid_body
+            }
+
             fn as_resource_with_roles(
                 &self,
             ) -> Option<&dyn ApiResourceWithRoles> {
@@ -0,0 +1,54 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::api_token;
+use chrono::{DateTime, Utc};
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+/// A long-lived API token that a user created for automation
+///
+/// Only a hash of the token itself is stored.  The token is shown to the user
+/// once, when it's created.
+#[derive(Clone, Debug, Insertable, Queryable, Resource, Selectable)]
+#[diesel(table_name = api_token)]
+pub struct ApiToken {
+    #[diesel(embed)]
+    identity: ApiTokenIdentity,
+
+    pub silo_user_id: Uuid,
+    pub token_hash: String,
+    pub time_expires: Option<DateTime<Utc>>,
+    pub time_last_used: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    pub fn new(
+        silo_user_id: Uuid,
+        params: params::ApiTokenCreate,
+        token_hash: String,
+    ) -> Self {
+        Self {
+            identity: ApiTokenIdentity::new(Uuid::new_v4(), params.identity),
+            silo_user_id,
+            token_hash,
+            time_expires: params.time_expires,
+            time_last_used: None,
+        }
+    }
+}
+
+impl From<ApiToken> for views::ApiToken {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            identity: token.identity(),
+            silo_user_id: token.silo_user_id,
+            time_expires: token.time_expires,
+            time_last_used: token.time_last_used,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::audit_log;
+use crate::SqlU16;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::views;
+use uuid::Uuid;
+
+/// Record of a mutating request made to the external API
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = audit_log)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    pub time_started: DateTime<Utc>,
+    pub time_completed: DateTime<Utc>,
+    pub request_id: String,
+    pub actor_id: Option<Uuid>,
+    pub actor_silo_id: Option<Uuid>,
+    pub http_method: String,
+    pub request_uri: String,
+    pub resource_ids: Vec<Uuid>,
+    pub http_status_code: SqlU16,
+    pub error_message: Option<String>,
+}
+
+impl From<AuditLogEntry> for views::AuditLogEntry {
+    fn from(entry: AuditLogEntry) -> Self {
+        Self {
+            id: entry.id,
+            time_started: entry.time_started,
+            time_completed: entry.time_completed,
+            request_id: entry.request_id,
+            actor_id: entry.actor_id,
+            actor_silo_id: entry.actor_silo_id,
+            http_method: entry.http_method,
+            request_uri: entry.request_uri,
+            resource_ids: entry.resource_ids,
+            http_status_code: *entry.http_status_code,
+            error_message: entry.error_message,
+        }
+    }
+}
@@ -4,6 +4,9 @@
 
 use crate::schema::console_session;
 use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
+use nexus_types::external_api::views;
+use std::net::IpAddr;
 use uuid::Uuid;
 
 // TODO: `struct SessionToken(String)` for session token
@@ -15,15 +18,43 @@ pub struct ConsoleSession {
     pub time_created: DateTime<Utc>,
     pub time_last_used: DateTime<Utc>,
     pub silo_user_id: Uuid,
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub client_address: Option<IpNetwork>,
 }
 
 impl ConsoleSession {
-    pub fn new(token: String, silo_user_id: Uuid) -> Self {
+    pub fn new(
+        token: String,
+        silo_user_id: Uuid,
+        user_agent: Option<String>,
+        client_address: Option<IpAddr>,
+    ) -> Self {
         let now = Utc::now();
-        Self { token, silo_user_id, time_last_used: now, time_created: now }
+        Self {
+            token,
+            silo_user_id,
+            time_last_used: now,
+            time_created: now,
+            id: Uuid::new_v4(),
+            user_agent,
+            client_address: client_address.map(IpNetwork::from),
+        }
     }
 
     pub fn id(&self) -> String {
         self.token.clone()
     }
 }
+
+impl From<ConsoleSession> for views::ConsoleSession {
+    fn from(session: ConsoleSession) -> Self {
+        Self {
+            id: session.id,
+            time_created: session.time_created,
+            time_last_used: session.time_last_used,
+            user_agent: session.user_agent,
+            client_address: session.client_address.map(|net| net.ip()),
+        }
+    }
+}
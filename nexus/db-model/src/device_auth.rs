@@ -62,7 +62,7 @@ const TOKEN_LENGTH: usize = 20;
 /// Generate a random token/device code.
 // TODO: this should be merged with session::generate_session_token,
 // and probably also the key generation in the disk creation saga.
-fn generate_token() -> String {
+pub fn generate_token() -> String {
     let mut bytes: [u8; TOKEN_LENGTH] = [0; TOKEN_LENGTH];
     let mut rng = StdRng::from_entropy();
     rng.fill_bytes(&mut bytes);
@@ -123,6 +123,7 @@ impl DeviceAuthRequest {
 #[diesel(table_name = device_access_token)]
 pub struct DeviceAccessToken {
     pub token: String,
+    pub id: Uuid,
     pub client_id: Uuid,
     pub device_code: String,
     pub silo_user_id: Uuid,
@@ -142,6 +143,7 @@ impl DeviceAccessToken {
         assert!(time_requested <= now);
         Self {
             token: generate_token(),
+            id: Uuid::new_v4(),
             client_id,
             device_code,
             silo_user_id,
@@ -170,6 +172,17 @@ impl From<DeviceAccessToken> for views::DeviceAccessTokenGrant {
     }
 }
 
+impl From<DeviceAccessToken> for views::DeviceAccessToken {
+    fn from(access_token: DeviceAccessToken) -> Self {
+        Self {
+            id: access_token.id,
+            client_id: access_token.client_id,
+            time_created: access_token.time_created,
+            time_expires: access_token.time_expires,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -7,15 +7,18 @@
 
 use crate::impl_enum_type;
 use crate::schema::external_ip;
+use crate::schema::floating_ip;
 use crate::Name;
 use crate::SqlU16;
 use chrono::DateTime;
 use chrono::Utc;
+use db_macros::Resource;
 use diesel::Queryable;
 use diesel::Selectable;
 use ipnetwork::IpNetwork;
 use nexus_types::external_api::shared;
 use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
 use omicron_common::api::external::Error;
 use std::convert::TryFrom;
 use uuid::Uuid;
@@ -70,6 +73,25 @@ pub struct ExternalIp {
     pub last_port: SqlU16,
 }
 
+/// A Floating IP, as seen through the `floating_ip` view of the `external_ip`
+/// table
+///
+/// Floating IPs are the only kind of external IP that is a named API resource
+/// within a project.  This type represents the identity and the project as
+/// non-nullable, so that Floating IPs can be looked up and authorized like any
+/// other project-scoped resource.
+#[derive(Debug, Clone, Selectable, Queryable, Resource)]
+#[diesel(table_name = floating_ip)]
+pub struct FloatingIp {
+    #[diesel(embed)]
+    pub identity: FloatingIpIdentity,
+    pub ip_pool_id: Uuid,
+    pub ip_pool_range_id: Uuid,
+    pub project_id: Uuid,
+    pub instance_id: Option<Uuid>,
+    pub ip: IpNetwork,
+}
+
 impl From<ExternalIp> for sled_agent_client::types::SourceNatConfig {
     fn from(eip: ExternalIp) -> Self {
         Self {
@@ -221,3 +243,50 @@ impl TryFrom<ExternalIp> for views::ExternalIp {
         Ok(views::ExternalIp { kind, ip: ip.ip.ip() })
     }
 }
+
+impl TryFrom<ExternalIp> for FloatingIp {
+    type Error = Error;
+
+    fn try_from(ip: ExternalIp) -> Result<Self, Self::Error> {
+        let (name, description, project_id) =
+            match (ip.kind, ip.name, ip.description, ip.project_id) {
+                (
+                    IpKind::Floating,
+                    Some(name),
+                    Some(description),
+                    Some(project_id),
+                ) => (name, description, project_id),
+                _ => {
+                    return Err(Error::internal_error(
+                        "external IP is not a Floating IP",
+                    ))
+                }
+            };
+        Ok(FloatingIp {
+            identity: FloatingIpIdentity {
+                id: ip.id,
+                name,
+                description,
+                time_created: ip.time_created,
+                time_modified: ip.time_modified,
+                time_deleted: ip.time_deleted,
+            },
+            ip_pool_id: ip.ip_pool_id,
+            ip_pool_range_id: ip.ip_pool_range_id,
+            project_id,
+            instance_id: ip.instance_id,
+            ip: ip.ip,
+        })
+    }
+}
+
+impl From<FloatingIp> for views::FloatingIp {
+    fn from(ip: FloatingIp) -> Self {
+        views::FloatingIp {
+            identity: ip.identity(),
+            ip: ip.ip.ip(),
+            project_id: ip.project_id,
+            instance_id: ip.instance_id,
+        }
+    }
+}
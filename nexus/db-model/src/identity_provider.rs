@@ -3,7 +3,9 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::impl_enum_type;
-use crate::schema::{identity_provider, saml_identity_provider};
+use crate::schema::{
+    identity_provider, oidc_identity_provider, saml_identity_provider,
+};
 use db_macros::Resource;
 use nexus_types::identity::Resource;
 
@@ -22,12 +24,14 @@ impl_enum_type!(
 
     // Enum values
     Saml => b"saml"
+    Oidc => b"oidc"
 );
 
 impl From<IdentityProviderType> for views::IdentityProviderType {
     fn from(idp_type: IdentityProviderType) -> Self {
         match idp_type {
             IdentityProviderType::Saml => views::IdentityProviderType::Saml,
+            IdentityProviderType::Oidc => views::IdentityProviderType::Oidc,
         }
     }
 }
@@ -100,3 +104,50 @@ impl From<SamlIdentityProvider> for views::SamlIdentityProvider {
         }
     }
 }
+
+#[derive(Queryable, Insertable, Clone, Debug, Selectable, Resource)]
+#[diesel(table_name = oidc_identity_provider)]
+pub struct OidcIdentityProvider {
+    #[diesel(embed)]
+    pub identity: OidcIdentityProviderIdentity,
+
+    pub silo_id: Uuid,
+
+    /// the issuer identifier of the OpenID provider
+    pub issuer: String,
+
+    /// the client id registered with the OpenID provider
+    pub client_id: String,
+
+    /// the client secret registered with the OpenID provider
+    pub client_secret: String,
+
+    /// scopes requested in addition to "openid"
+    pub scopes: Vec<String>,
+
+    /// service provider endpoint to which the OpenID provider redirects the
+    /// user after authentication
+    pub redirect_url: String,
+
+    /// if set, this claim of the ID token will be considered to denote a
+    /// user's group membership, where the values will be the group names.
+    pub group_claim_name: Option<String>,
+
+    /// endpoints read from the OpenID provider's discovery document
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+impl From<OidcIdentityProvider> for views::OidcIdentityProvider {
+    fn from(oidc_idp: OidcIdentityProvider) -> Self {
+        Self {
+            identity: oidc_idp.identity(),
+            issuer: oidc_idp.issuer,
+            client_id: oidc_idp.client_id,
+            scopes: oidc_idp.scopes,
+            redirect_url: oidc_idp.redirect_url,
+            group_claim_name: oidc_idp.group_claim_name,
+        }
+    }
+}
@@ -2,7 +2,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::{ByteCount, Disk, Generation, InstanceCpuCount, InstanceState};
+use super::{
+    ByteCount, Disk, Generation, InstanceCpuCount, InstanceState, Name,
+};
 use crate::collection::DatastoreAttachTargetConfig;
 use crate::schema::{disk, instance};
 use chrono::{DateTime, Utc};
@@ -31,6 +33,9 @@ pub struct Instance {
     /// runtime state of the Instance
     #[diesel(embed)]
     pub runtime_state: InstanceRuntimeState,
+
+    /// tags used to select this Instance in firewall rules
+    pub tags: Vec<Name>,
 }
 
 impl Instance {
@@ -47,6 +52,7 @@ impl Instance {
             project_id,
             user_data: params.user_data.clone(),
             runtime_state: runtime,
+            tags: Vec::new(),
         }
     }
 
@@ -64,6 +70,7 @@ impl Into<external::Instance> for Instance {
             ncpus: self.runtime().ncpus.into(),
             memory: self.runtime().memory.into(),
             hostname: self.runtime().hostname.clone(),
+            tags: self.tags.iter().map(|t| t.0.clone()).collect(),
             runtime: self.runtime().clone().into(),
         }
     }
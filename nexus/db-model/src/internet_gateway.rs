@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::Name;
+use crate::schema::internet_gateway;
+use chrono::{DateTime, Utc};
+use db_macros::Resource;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use omicron_common::api::external;
+use uuid::Uuid;
+
+/// A VPC's gateway to destinations outside the rack
+#[derive(Queryable, Insertable, Clone, Debug, Selectable, Resource)]
+#[diesel(table_name = internet_gateway)]
+pub struct InternetGateway {
+    #[diesel(embed)]
+    identity: InternetGatewayIdentity,
+
+    pub vpc_id: Uuid,
+    pub ip_pool_ids: Vec<Uuid>,
+}
+
+impl InternetGateway {
+    pub fn new(
+        gateway_id: Uuid,
+        vpc_id: Uuid,
+        identity: external::IdentityMetadataCreateParams,
+        ip_pool_ids: Vec<Uuid>,
+    ) -> Self {
+        let identity = InternetGatewayIdentity::new(gateway_id, identity);
+        Self { identity, vpc_id, ip_pool_ids }
+    }
+
+    /// Returns the "outbound" gateway targeted by a new VPC's default route
+    pub fn new_outbound(gateway_id: Uuid, vpc_id: Uuid) -> Self {
+        Self::new(
+            gateway_id,
+            vpc_id,
+            external::IdentityMetadataCreateParams {
+                name: "outbound".parse().unwrap(),
+                description: "The default internet gateway of a vpc".into(),
+            },
+            vec![],
+        )
+    }
+}
+
+impl From<InternetGateway> for views::InternetGateway {
+    fn from(gateway: InternetGateway) -> Self {
+        Self {
+            identity: gateway.identity(),
+            vpc_id: gateway.vpc_id,
+            ip_pool_ids: gateway.ip_pool_ids,
+        }
+    }
+}
+
+/// Describes a set of updates for the [`InternetGateway`] model.
+///
+/// The IP Pools are given by ID, so this is built by Nexus once it has looked
+/// up the pools named in the request.
+#[derive(AsChangeset)]
+#[diesel(table_name = internet_gateway)]
+pub struct InternetGatewayUpdate {
+    pub name: Option<Name>,
+    pub description: Option<String>,
+    pub time_modified: DateTime<Utc>,
+    pub ip_pool_ids: Option<Vec<Uuid>>,
+}
@@ -34,6 +34,11 @@ impl Ipv4Net {
                 addr != self.broadcast()
             )
     }
+
+    /// Return whether this and `other` have any addresses in common
+    pub fn overlaps(&self, other: &Ipv4Net) -> bool {
+        self.contains(other.network()) || other.contains(self.network())
+    }
 }
 
 impl ToSql<sql_types::Inet, Pg> for Ipv4Net {
@@ -9,6 +9,8 @@ extern crate diesel;
 #[macro_use]
 extern crate newtype_derive;
 
+mod api_token;
+mod audit_log;
 mod block_size;
 mod bytecount;
 mod collection;
@@ -27,6 +29,7 @@ mod image;
 mod instance;
 mod instance_cpu_count;
 mod instance_state;
+mod internet_gateway;
 mod ip_pool;
 mod ipv4net;
 mod ipv6;
@@ -39,6 +42,7 @@ mod organization;
 mod oximeter_info;
 mod producer_endpoint;
 mod project;
+mod project_settings;
 // These actually represent subqueries, not real table.
 // However, they must be defined in the same crate as our tables
 // for join-based marker trait generation.
@@ -46,15 +50,18 @@ pub mod queries;
 mod rack;
 mod region;
 mod region_snapshot;
+mod resource_quota;
 mod role_assignment;
 mod role_builtin;
 pub mod saga_types;
 pub mod schema;
+mod scim_client_bearer_token;
 mod service;
 mod service_kind;
 mod silo;
 mod silo_group;
 mod silo_user;
+mod silo_user_password_hash;
 mod sled;
 mod snapshot;
 mod ssh_key;
@@ -65,6 +72,7 @@ mod vni;
 mod volume;
 mod vpc;
 mod vpc_firewall_rule;
+mod vpc_peering;
 mod vpc_route;
 mod vpc_router;
 mod vpc_subnet;
@@ -80,6 +88,8 @@ mod db {
 
 pub use self::macaddr::*;
 pub use self::u16::*;
+pub use api_token::*;
+pub use audit_log::*;
 pub use block_size::*;
 pub use bytecount::*;
 pub use collection::*;
@@ -98,6 +108,7 @@ pub use image::*;
 pub use instance::*;
 pub use instance_cpu_count::*;
 pub use instance_state::*;
+pub use internet_gateway::*;
 pub use ip_pool::*;
 pub use ipv4net::*;
 pub use ipv6::*;
@@ -109,16 +120,20 @@ pub use organization::*;
 pub use oximeter_info::*;
 pub use producer_endpoint::*;
 pub use project::*;
+pub use project_settings::*;
 pub use rack::*;
 pub use region::*;
 pub use region_snapshot::*;
+pub use resource_quota::*;
 pub use role_assignment::*;
 pub use role_builtin::*;
+pub use scim_client_bearer_token::*;
 pub use service::*;
 pub use service_kind::*;
 pub use silo::*;
 pub use silo_group::*;
 pub use silo_user::*;
+pub use silo_user_password_hash::*;
 pub use sled::*;
 pub use snapshot::*;
 pub use ssh_key::*;
@@ -128,6 +143,7 @@ pub use vni::*;
 pub use volume::*;
 pub use vpc::*;
 pub use vpc_firewall_rule::*;
+pub use vpc_peering::*;
 pub use vpc_route::*;
 pub use vpc_router::*;
 pub use vpc_subnet::*;
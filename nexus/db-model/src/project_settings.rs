@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::project_settings;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::views;
+use uuid::Uuid;
+
+/// Describes the settings of a project within the database.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug)]
+#[diesel(table_name = project_settings)]
+pub struct ProjectSettings {
+    pub project_id: Uuid,
+    pub time_modified: DateTime<Utc>,
+
+    pub default_vpc_id: Option<Uuid>,
+    pub default_vpc_subnet_id: Option<Uuid>,
+    pub default_ip_pool_id: Option<Uuid>,
+}
+
+impl ProjectSettings {
+    /// Creates the settings of a project which hasn't set any of them
+    pub fn new(project_id: Uuid) -> Self {
+        Self {
+            project_id,
+            time_modified: Utc::now(),
+            default_vpc_id: None,
+            default_vpc_subnet_id: None,
+            default_ip_pool_id: None,
+        }
+    }
+}
+
+impl From<ProjectSettings> for views::ProjectSettings {
+    fn from(settings: ProjectSettings) -> Self {
+        Self {
+            project_id: settings.project_id,
+            default_vpc_id: settings.default_vpc_id,
+            default_vpc_subnet_id: settings.default_vpc_subnet_id,
+            default_ip_pool_id: settings.default_ip_pool_id,
+        }
+    }
+}
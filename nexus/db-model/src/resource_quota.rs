@@ -0,0 +1,227 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::resource_quota;
+use crate::{Disk, ExternalIp, Instance, IpKind};
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::{params, views};
+use omicron_common::api::external;
+use omicron_common::api::external::Error;
+use std::ops::{AddAssign, Neg};
+use uuid::Uuid;
+
+/// Describes the quotas of a Silo or Project within the database, along with
+/// the resources allocated within it
+#[derive(Queryable, Insertable, Selectable, Clone, Debug)]
+#[diesel(table_name = resource_quota)]
+pub struct ResourceQuota {
+    pub id: Uuid,
+    pub time_modified: DateTime<Utc>,
+
+    pub cpus_limit: Option<i64>,
+    pub memory_limit: Option<i64>,
+    pub disk_limit: Option<i64>,
+    pub snapshots_limit: Option<i64>,
+    pub external_ips_limit: Option<i64>,
+
+    pub cpus_used: i64,
+    pub memory_used: i64,
+    pub disk_used: i64,
+    pub snapshots_used: i64,
+    pub external_ips_used: i64,
+}
+
+impl ResourceQuota {
+    /// Creates the quotas of a Silo or Project which has no limits and no
+    /// resources allocated
+    pub fn new(id: Uuid) -> Self {
+        Self {
+            id,
+            time_modified: Utc::now(),
+            cpus_limit: None,
+            memory_limit: None,
+            disk_limit: None,
+            snapshots_limit: None,
+            external_ips_limit: None,
+            cpus_used: 0,
+            memory_used: 0,
+            disk_used: 0,
+            snapshots_used: 0,
+            external_ips_used: 0,
+        }
+    }
+
+    /// Checks that allocating `usage` on top of the resources already
+    /// allocated would not exceed any limit
+    ///
+    /// `scope` describes the Silo or Project in the error returned otherwise.
+    pub fn check_allocation(
+        &self,
+        scope: &str,
+        usage: &ResourceUsage,
+    ) -> Result<(), Error> {
+        let checks = [
+            (self.cpus_limit, self.cpus_used, usage.cpus, "vCPUs"),
+            (
+                self.memory_limit,
+                self.memory_used,
+                usage.memory,
+                "bytes of memory",
+            ),
+            (self.disk_limit, self.disk_used, usage.disk, "bytes of disk"),
+            (
+                self.snapshots_limit,
+                self.snapshots_used,
+                usage.snapshots,
+                "snapshots",
+            ),
+            (
+                self.external_ips_limit,
+                self.external_ips_used,
+                usage.external_ips,
+                "external IPs",
+            ),
+        ];
+        for (limit, used, requested, units) in checks {
+            // Freeing resources is always allowed, even when the limit has
+            // been lowered below what's already allocated.
+            if requested <= 0 {
+                continue;
+            }
+            if let Some(limit) = limit {
+                if used.saturating_add(requested) > limit {
+                    return Err(Error::invalid_request(&format!(
+                        "{} quota exceeded: {} {} requested, but only {} of \
+                        the limit of {} are available",
+                        scope,
+                        requested,
+                        units,
+                        (limit - used).max(0),
+                        limit,
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the resources counted as allocated with `usage`
+    pub fn set_usage(&mut self, usage: &ResourceUsage) {
+        self.time_modified = Utc::now();
+        self.cpus_used = usage.cpus;
+        self.memory_used = usage.memory;
+        self.disk_used = usage.disk;
+        self.snapshots_used = usage.snapshots;
+        self.external_ips_used = usage.external_ips;
+    }
+
+    /// Replaces all the limits with those in `params`
+    pub fn set_limits(&mut self, params: &params::ResourceQuotasUpdate) {
+        self.time_modified = Utc::now();
+        self.cpus_limit = params.cpus.map(i64::from);
+        self.memory_limit = params.memory.map(i64::from);
+        self.disk_limit = params.disk.map(i64::from);
+        self.snapshots_limit = params.snapshots.map(i64::from);
+        self.external_ips_limit = params.external_ips.map(i64::from);
+    }
+}
+
+impl From<ResourceQuota> for views::ResourceQuotas {
+    fn from(quota: ResourceQuota) -> Self {
+        // The database ensures that none of these are negative.
+        let count = |n: i64| n as u64;
+        let bytes = |n: i64| external::ByteCount::try_from(n).unwrap();
+        Self {
+            id: quota.id,
+            limits: views::QuotaLimits {
+                cpus: quota.cpus_limit.map(count),
+                memory: quota.memory_limit.map(bytes),
+                disk: quota.disk_limit.map(bytes),
+                snapshots: quota.snapshots_limit.map(count),
+                external_ips: quota.external_ips_limit.map(count),
+            },
+            usage: views::QuotaUsage {
+                cpus: count(quota.cpus_used),
+                memory: bytes(quota.memory_used),
+                disk: bytes(quota.disk_used),
+                snapshots: count(quota.snapshots_used),
+                external_ips: count(quota.external_ips_used),
+            },
+        }
+    }
+}
+
+/// Resources allocated to a single object, which count against the quotas of
+/// the Project containing it and of that Project's Silo
+///
+/// A negative amount describes resources being freed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpus: i64,
+    pub memory: i64,
+    pub disk: i64,
+    pub snapshots: i64,
+    pub external_ips: i64,
+}
+
+impl ResourceUsage {
+    pub fn for_instance(instance: &Instance) -> Self {
+        let runtime = instance.runtime();
+        Self {
+            cpus: i64::from(runtime.ncpus.0 .0),
+            memory: i64::from(runtime.memory.0),
+            ..Default::default()
+        }
+    }
+
+    pub fn for_disk(disk: &Disk) -> Self {
+        Self { disk: i64::from(disk.size.0), ..Default::default() }
+    }
+
+    pub fn for_snapshot() -> Self {
+        Self { snapshots: 1, ..Default::default() }
+    }
+
+    /// Returns the resources used by an external IP address
+    ///
+    /// Only Ephemeral and Floating IPs count against quotas.  Every instance
+    /// needs a source NAT address, so those are bounded by the quotas on
+    /// instances instead.
+    pub fn for_external_ip(ip: &ExternalIp) -> Self {
+        match ip.kind {
+            IpKind::Ephemeral | IpKind::Floating => {
+                Self { external_ips: 1, ..Default::default() }
+            }
+            IpKind::SNat | IpKind::Service => Self::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Neg for ResourceUsage {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            cpus: -self.cpus,
+            memory: -self.memory,
+            disk: -self.disk,
+            snapshots: -self.snapshots,
+            external_ips: -self.external_ips,
+        }
+    }
+}
+
+impl AddAssign for ResourceUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.cpus += other.cpus;
+        self.memory += other.memory;
+        self.disk += other.disk;
+        self.snapshots += other.snapshots;
+        self.external_ips += other.external_ips;
+    }
+}
@@ -12,14 +12,17 @@
 //! actually serialize them to and from SQL take care of the necessary
 //! conversions.
 
-use super::schema::{saga, saga_node_event};
+use super::schema::{saga, saga_intervention, saga_node_event};
+use crate::impl_enum_type;
 use diesel::backend::{Backend, RawValue};
 use diesel::deserialize::{self, FromSql};
 use diesel::pg::Pg;
 use diesel::serialize::{self, ToSql};
 use diesel::sql_types;
+use nexus_types::external_api::views;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::Generation;
+use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::io::Write;
 use std::sync::Arc;
@@ -295,3 +298,75 @@ impl TryFrom<SagaNodeEvent> for steno::SagaNodeEvent {
         })
     }
 }
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "saga_intervention_kind"))]
+    pub struct SagaInterventionKindEnum;
+
+    #[derive(Clone, Copy, Debug, AsExpression, FromSqlRow, Serialize, Deserialize, PartialEq)]
+    #[diesel(sql_type = SagaInterventionKindEnum)]
+    pub enum SagaInterventionKind;
+
+    // Enum values
+    Abandon => b"abandon"
+    Retry => b"retry"
+);
+
+impl From<SagaInterventionKind> for views::SagaInterventionKind {
+    fn from(kind: SagaInterventionKind) -> Self {
+        match kind {
+            SagaInterventionKind::Abandon => {
+                views::SagaInterventionKind::Abandon
+            }
+            SagaInterventionKind::Retry => views::SagaInterventionKind::Retry,
+        }
+    }
+}
+
+/// Represents a row in the "SagaIntervention" table
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = saga_intervention)]
+pub struct SagaIntervention {
+    pub id: Uuid,
+    pub time_created: chrono::DateTime<chrono::Utc>,
+    pub saga_id: SagaId,
+    pub kind: SagaInterventionKind,
+    pub actor_id: Uuid,
+    pub new_sec: Option<SecId>,
+    pub reason: String,
+}
+
+impl SagaIntervention {
+    pub fn new(
+        saga_id: steno::SagaId,
+        kind: SagaInterventionKind,
+        actor_id: Uuid,
+        new_sec: Option<SecId>,
+        reason: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            time_created: chrono::Utc::now(),
+            saga_id: saga_id.into(),
+            kind,
+            actor_id,
+            new_sec,
+            reason,
+        }
+    }
+}
+
+impl From<SagaIntervention> for views::SagaIntervention {
+    fn from(intervention: SagaIntervention) -> Self {
+        Self {
+            id: intervention.id,
+            time_created: intervention.time_created,
+            saga_id: Uuid::from(intervention.saga_id.0),
+            kind: intervention.kind.into(),
+            actor_id: intervention.actor_id,
+            new_sec: intervention.new_sec.map(|sec| sec.0),
+            reason: intervention.reason,
+        }
+    }
+}
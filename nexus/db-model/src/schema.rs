@@ -107,6 +107,7 @@ table! {
         ncpus -> Int8,
         memory -> Int8,
         hostname -> Text,
+        tags -> Array<Text>,
     }
 }
 
@@ -188,6 +189,22 @@ table! {
     }
 }
 
+table! {
+    floating_ip (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        ip_pool_id -> Uuid,
+        ip_pool_range_id -> Uuid,
+        project_id -> Uuid,
+        instance_id -> Nullable<Uuid>,
+        ip -> Inet,
+    }
+}
+
 table! {
     silo (id) {
         id -> Uuid,
@@ -214,6 +231,18 @@ table! {
 
         silo_id -> Uuid,
         external_id -> Text,
+        active -> Bool,
+    }
+}
+
+table! {
+    silo_user_password_hash (silo_user_id) {
+        silo_user_id -> Uuid,
+        hash -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        failed_attempts -> Int8,
+        time_locked_until -> Nullable<Timestamptz>,
     }
 }
 
@@ -237,6 +266,7 @@ table! {
 }
 
 allow_tables_to_appear_in_same_query!(silo_group, silo_group_membership);
+allow_tables_to_appear_in_same_query!(silo_user, silo_group_membership);
 allow_tables_to_appear_in_same_query!(role_assignment, silo_group_membership);
 
 table! {
@@ -277,6 +307,30 @@ table! {
     }
 }
 
+table! {
+    oidc_identity_provider (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+
+        silo_id -> Uuid,
+
+        issuer -> Text,
+        client_id -> Text,
+        client_secret -> Text,
+        scopes -> Array<Text>,
+        redirect_url -> Text,
+        group_claim_name -> Nullable<Text>,
+
+        authorization_endpoint -> Text,
+        token_endpoint -> Text,
+        jwks_uri -> Text,
+    }
+}
+
 table! {
     ssh_key (id) {
         id -> Uuid,
@@ -325,6 +379,33 @@ table! {
     }
 }
 
+table! {
+    project_settings (project_id) {
+        project_id -> Uuid,
+        time_modified -> Timestamptz,
+        default_vpc_id -> Nullable<Uuid>,
+        default_vpc_subnet_id -> Nullable<Uuid>,
+        default_ip_pool_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    resource_quota (id) {
+        id -> Uuid,
+        time_modified -> Timestamptz,
+        cpus_limit -> Nullable<Int8>,
+        memory_limit -> Nullable<Int8>,
+        disk_limit -> Nullable<Int8>,
+        snapshots_limit -> Nullable<Int8>,
+        external_ips_limit -> Nullable<Int8>,
+        cpus_used -> Int8,
+        memory_used -> Int8,
+        disk_used -> Int8,
+        snapshots_used -> Int8,
+        external_ips_used -> Int8,
+    }
+}
+
 table! {
     saga (id) {
         id -> Uuid,
@@ -350,6 +431,18 @@ table! {
     }
 }
 
+table! {
+    saga_intervention (id) {
+        id -> Uuid,
+        time_created -> Timestamptz,
+        saga_id -> Uuid,
+        kind -> crate::saga_types::SagaInterventionKindEnum,
+        actor_id -> Uuid,
+        new_sec -> Nullable<Uuid>,
+        reason -> Text,
+    }
+}
+
 table! {
     rack (id) {
         id -> Uuid,
@@ -366,6 +459,25 @@ table! {
         time_created -> Timestamptz,
         time_last_used -> Timestamptz,
         silo_user_id -> Uuid,
+        id -> Uuid,
+        user_agent -> Nullable<Text>,
+        client_address -> Nullable<Inet>,
+    }
+}
+
+table! {
+    audit_log (id) {
+        id -> Uuid,
+        time_started -> Timestamptz,
+        time_completed -> Timestamptz,
+        request_id -> Text,
+        actor_id -> Nullable<Uuid>,
+        actor_silo_id -> Nullable<Uuid>,
+        http_method -> Text,
+        request_uri -> Text,
+        resource_ids -> Array<Uuid>,
+        http_status_code -> Int4,
+        error_message -> Nullable<Text>,
     }
 }
 
@@ -502,6 +614,7 @@ table! {
         rcgen -> Int8,
         ipv4_block -> Inet,
         ipv6_block -> Inet,
+        custom_router_id -> Nullable<Uuid>,
     }
 }
 
@@ -534,6 +647,33 @@ table! {
     }
 }
 
+table! {
+    internet_gateway (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        vpc_id -> Uuid,
+        ip_pool_ids -> Array<Uuid>,
+    }
+}
+
+table! {
+    vpc_peering (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        vpc_id -> Uuid,
+        peer_vpc_id -> Uuid,
+        state -> crate::VpcPeeringStateEnum,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
 
@@ -580,6 +720,7 @@ table! {
 table! {
     device_access_token (token) {
         token -> Text,
+        id -> Uuid,
         client_id -> Uuid,
         device_code -> Text,
         silo_user_id -> Uuid,
@@ -589,6 +730,30 @@ table! {
     }
 }
 
+table! {
+    api_token (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        silo_user_id -> Uuid,
+        token_hash -> Text,
+        time_expires -> Nullable<Timestamptz>,
+        time_last_used -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    scim_client_bearer_token (id) {
+        id -> Uuid,
+        time_created -> Timestamptz,
+        silo_id -> Uuid,
+        bearer_token_hash -> Text,
+    }
+}
+
 table! {
     role_builtin (resource_type, role_name) {
         resource_type -> Text,
@@ -626,23 +791,35 @@ table! {
     }
 }
 
+table! {
+    db_metadata (name) {
+        name -> Text,
+        value -> Text,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(ip_pool_range, ip_pool);
 joinable!(ip_pool_range -> ip_pool (ip_pool_id));
 
 allow_tables_to_appear_in_same_query!(
     dataset,
     disk,
+    external_ip,
+    floating_ip,
     instance,
     metric_producer,
     network_interface,
     organization,
     oximeter,
     project,
+    project_settings,
     rack,
     region,
     region_snapshot,
+    resource_quota,
     saga,
     saga_node_event,
+    saga_intervention,
     silo,
     identity_provider,
     console_session,
@@ -654,6 +831,8 @@ allow_tables_to_appear_in_same_query!(
     vpc_subnet,
     vpc_router,
     vpc_firewall_rule,
+    vpc_peering,
+    internet_gateway,
     user_builtin,
     role_builtin,
     role_assignment,
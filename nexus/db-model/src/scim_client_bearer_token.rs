@@ -0,0 +1,39 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::scim_client_bearer_token;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::views;
+use uuid::Uuid;
+
+/// A bearer token with which an identity provider authenticates to a Silo's
+/// SCIM API
+///
+/// Only a hash of the token itself is stored.  The token is shown to the
+/// Silo's administrators once, when it's created.
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = scim_client_bearer_token)]
+pub struct ScimClientBearerToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub silo_id: Uuid,
+    pub bearer_token_hash: String,
+}
+
+impl ScimClientBearerToken {
+    pub fn new(silo_id: Uuid, bearer_token_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            time_created: Utc::now(),
+            silo_id,
+            bearer_token_hash,
+        }
+    }
+}
+
+impl From<ScimClientBearerToken> for views::ScimClientBearerToken {
+    fn from(token: ScimClientBearerToken) -> Self {
+        Self { id: token.id, time_created: token.time_created }
+    }
+}
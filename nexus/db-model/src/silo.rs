@@ -26,6 +26,7 @@ impl_enum_type!(
     // Enum values
     Local => b"local"
     Saml => b"saml"
+    Oidc => b"oidc"
 );
 
 impl From<shared::AuthenticationMode> for AuthenticationMode {
@@ -33,6 +34,7 @@ impl From<shared::AuthenticationMode> for AuthenticationMode {
         match params {
             shared::AuthenticationMode::Local => AuthenticationMode::Local,
             shared::AuthenticationMode::Saml => AuthenticationMode::Saml,
+            shared::AuthenticationMode::Oidc => AuthenticationMode::Oidc,
         }
     }
 }
@@ -42,6 +44,7 @@ impl From<AuthenticationMode> for shared::AuthenticationMode {
         match model {
             AuthenticationMode::Local => Self::Local,
             AuthenticationMode::Saml => Self::Saml,
+            AuthenticationMode::Oidc => Self::Oidc,
         }
     }
 }
@@ -58,6 +61,7 @@ impl_enum_type!(
     // Enum values
     ApiOnly => b"api_only"
     Jit => b"jit"
+    Scim => b"scim"
 );
 
 impl From<shared::UserProvisionType> for UserProvisionType {
@@ -65,6 +69,7 @@ impl From<shared::UserProvisionType> for UserProvisionType {
         match params {
             shared::UserProvisionType::ApiOnly => UserProvisionType::ApiOnly,
             shared::UserProvisionType::Jit => UserProvisionType::Jit,
+            shared::UserProvisionType::Scim => UserProvisionType::Scim,
         }
     }
 }
@@ -74,6 +79,7 @@ impl From<UserProvisionType> for shared::UserProvisionType {
         match model {
             UserProvisionType::ApiOnly => Self::ApiOnly,
             UserProvisionType::Jit => Self::Jit,
+            UserProvisionType::Scim => Self::Scim,
         }
     }
 }
@@ -126,11 +132,22 @@ impl TryFrom<Silo> for views::Silo {
             (AuthenticationMode::Saml, UserProvisionType::Jit) => {
                 Some(SiloIdentityMode::SamlJit)
             }
+            (AuthenticationMode::Saml, UserProvisionType::Scim) => {
+                Some(SiloIdentityMode::SamlScim)
+            }
             (AuthenticationMode::Saml, UserProvisionType::ApiOnly) => None,
+            (AuthenticationMode::Oidc, UserProvisionType::Jit) => {
+                Some(SiloIdentityMode::OidcJit)
+            }
+            (AuthenticationMode::Oidc, UserProvisionType::Scim) => {
+                Some(SiloIdentityMode::OidcScim)
+            }
+            (AuthenticationMode::Oidc, UserProvisionType::ApiOnly) => None,
             (AuthenticationMode::Local, UserProvisionType::ApiOnly) => {
                 Some(SiloIdentityMode::LocalOnly)
             }
             (AuthenticationMode::Local, UserProvisionType::Jit) => None,
+            (AuthenticationMode::Local, UserProvisionType::Scim) => None,
         }
         .ok_or_else(|| {
             Error::internal_error(&format!(
@@ -3,6 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::schema::{silo_group, silo_group_membership};
+use chrono::{DateTime, Utc};
 use db_macros::Asset;
 use nexus_types::external_api::views;
 use nexus_types::identity::Asset;
@@ -27,6 +28,14 @@ impl SiloGroup {
     }
 }
 
+/// Describes a change to a silo group (made through SCIM)
+#[derive(AsChangeset)]
+#[diesel(table_name = silo_group)]
+pub struct SiloGroupUpdate {
+    pub external_id: String,
+    pub time_modified: DateTime<Utc>,
+}
+
 /// Describe which silo users belong to which silo groups
 #[derive(Queryable, Insertable, Debug, Selectable)]
 #[diesel(table_name = silo_group_membership)]
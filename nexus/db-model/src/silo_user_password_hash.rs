@@ -0,0 +1,37 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::silo_user_password_hash;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// The password hash of a user in a Silo's local identity provider, along
+/// with the state used to lock out repeated failed logins
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = silo_user_password_hash)]
+pub struct SiloUserPasswordHash {
+    pub silo_user_id: Uuid,
+    /// argon2 hash of the password, in PHC string format
+    pub hash: String,
+    pub time_created: DateTime<Utc>,
+    pub time_modified: DateTime<Utc>,
+    /// consecutive failed logins since the last success or lockout
+    pub failed_attempts: i64,
+    /// logins are refused until this time
+    pub time_locked_until: Option<DateTime<Utc>>,
+}
+
+impl SiloUserPasswordHash {
+    pub fn new(silo_user_id: Uuid, hash: String) -> Self {
+        let now = Utc::now();
+        Self {
+            silo_user_id,
+            hash,
+            time_created: now,
+            time_modified: now,
+            failed_attempts: 0,
+            time_locked_until: None,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::impl_enum_type;
+use crate::schema::vpc_peering;
+use db_macros::Resource;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use omicron_common::api::external;
+use uuid::Uuid;
+
+impl_enum_type!(
+    #[derive(SqlType, Debug)]
+    #[diesel(postgres_type(name = "vpc_peering_state"))]
+    pub struct VpcPeeringStateEnum;
+
+    #[derive(Clone, Copy, Debug, AsExpression, FromSqlRow, PartialEq)]
+    #[diesel(sql_type = VpcPeeringStateEnum)]
+    pub enum VpcPeeringState;
+
+    // Enum values
+    Pending => b"pending"
+    Active => b"active"
+);
+
+impl From<VpcPeeringState> for views::VpcPeeringState {
+    fn from(state: VpcPeeringState) -> Self {
+        match state {
+            VpcPeeringState::Pending => Self::Pending,
+            VpcPeeringState::Active => Self::Active,
+        }
+    }
+}
+
+/// One VPC's side of a peering with another VPC
+#[derive(Queryable, Insertable, Clone, Debug, Selectable, Resource)]
+#[diesel(table_name = vpc_peering)]
+pub struct VpcPeering {
+    #[diesel(embed)]
+    identity: VpcPeeringIdentity,
+
+    pub vpc_id: Uuid,
+    pub peer_vpc_id: Uuid,
+    pub state: VpcPeeringState,
+}
+
+impl VpcPeering {
+    /// Create a new, pending side of a peering.
+    ///
+    /// The datastore decides whether the peering is actually active when it's
+    /// inserted.
+    pub fn new(
+        peering_id: Uuid,
+        vpc_id: Uuid,
+        peer_vpc_id: Uuid,
+        identity: external::IdentityMetadataCreateParams,
+    ) -> Self {
+        let identity = VpcPeeringIdentity::new(peering_id, identity);
+        Self { identity, vpc_id, peer_vpc_id, state: VpcPeeringState::Pending }
+    }
+}
+
+impl From<VpcPeering> for views::VpcPeering {
+    fn from(peering: VpcPeering) -> Self {
+        Self {
+            identity: peering.identity(),
+            vpc_id: peering.vpc_id,
+            peer_vpc_id: peering.peer_vpc_id,
+            state: peering.state.into(),
+        }
+    }
+}
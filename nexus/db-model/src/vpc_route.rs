@@ -126,6 +126,82 @@ impl RouterRoute {
             destination: RouteDestination::new(params.destination),
         }
     }
+
+    /// Create the route in a VPC's system router that directs traffic
+    /// destined for a VPC Subnet to that subnet.
+    ///
+    /// These routes are maintained by the system as subnets are created,
+    /// renamed, and deleted. They are named after the subnet's ID rather than
+    /// its name, so that they can't collide with the system router's default
+    /// route (the default subnet and the default route are both "default").
+    pub fn for_vpc_subnet(
+        route_id: Uuid,
+        system_router_id: Uuid,
+        subnet_id: Uuid,
+        subnet_name: external::Name,
+    ) -> Self {
+        Self::new(
+            route_id,
+            system_router_id,
+            external::RouterRouteKind::VpcSubnet,
+            external::RouterRouteCreateParams {
+                identity: external::IdentityMetadataCreateParams {
+                    name: Self::vpc_subnet_route_name(subnet_id),
+                    description: vpc_subnet_route_description(&subnet_name),
+                },
+                target: external::RouteTarget::Subnet(subnet_name.clone()),
+                destination: external::RouteDestination::Subnet(subnet_name),
+            },
+        )
+    }
+
+    /// Name of the system router route for the VPC Subnet with `subnet_id`
+    pub fn vpc_subnet_route_name(subnet_id: Uuid) -> external::Name {
+        format!("sn-{}", subnet_id.simple())
+            .parse()
+            .expect("route name derived from a UUID should be valid")
+    }
+
+    /// Create the route in a VPC's system router that directs traffic
+    /// destined for a peered VPC to that VPC.
+    ///
+    /// These routes exist while the peering with ID `peering_id` is active,
+    /// and are named after it. The peer VPC's name is recorded for display
+    /// only: the route is resolved through the peering.
+    pub fn for_vpc_peering(
+        route_id: Uuid,
+        system_router_id: Uuid,
+        peering_id: Uuid,
+        peer_vpc_name: external::Name,
+    ) -> Self {
+        Self::new(
+            route_id,
+            system_router_id,
+            external::RouterRouteKind::VpcPeering,
+            external::RouterRouteCreateParams {
+                identity: external::IdentityMetadataCreateParams {
+                    name: Self::vpc_peering_route_name(peering_id),
+                    description: format!(
+                        "VPC peering route for '{}'",
+                        peer_vpc_name
+                    ),
+                },
+                target: external::RouteTarget::Vpc(peer_vpc_name.clone()),
+                destination: external::RouteDestination::Vpc(peer_vpc_name),
+            },
+        )
+    }
+
+    /// Name of the system router route for the VPC peering with `peering_id`
+    pub fn vpc_peering_route_name(peering_id: Uuid) -> external::Name {
+        format!("peer-{}", peering_id.simple())
+            .parse()
+            .expect("route name derived from a UUID should be valid")
+    }
+}
+
+fn vpc_subnet_route_description(subnet_name: &external::Name) -> String {
+    format!("VPC Subnet route for '{}'", subnet_name)
 }
 
 impl Into<external::RouterRoute> for RouterRoute {
@@ -150,6 +226,24 @@ pub struct RouterRouteUpdate {
     pub destination: RouteDestination,
 }
 
+impl RouterRouteUpdate {
+    /// Update a VPC Subnet route (see [`RouterRoute::for_vpc_subnet`]) to
+    /// follow its subnet being renamed.
+    pub fn for_vpc_subnet(subnet_name: external::Name) -> Self {
+        Self {
+            name: None,
+            description: Some(vpc_subnet_route_description(&subnet_name)),
+            time_modified: Utc::now(),
+            target: RouteTarget(external::RouteTarget::Subnet(
+                subnet_name.clone(),
+            )),
+            destination: RouteDestination::new(
+                external::RouteDestination::Subnet(subnet_name),
+            ),
+        }
+    }
+}
+
 impl From<external::RouterRouteUpdateParams> for RouterRouteUpdate {
     fn from(params: external::RouterRouteUpdateParams) -> Self {
         Self {
@@ -0,0 +1,168 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Long-lived API tokens
+//!
+//! Users create API tokens for automation, like CI jobs, that can't complete
+//! an interactive login or the device authorization flow.  API tokens are
+//! presented the same way as device access tokens (see
+//! [`crate::authn::external::token`]), but unlike those, only a hash of each
+//! API token is stored.  The token itself is revealed once, when it's created.
+
+use crate::authn::{Actor, Reason};
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::identity::Resource;
+use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use crate::external_api::params;
+use crate::external_api::views;
+use anyhow::anyhow;
+use chrono::Utc;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use ring::digest;
+use uuid::Uuid;
+
+/// Returns the hash of an API token, as stored in the database
+fn api_token_hash(token: &str) -> String {
+    hex::encode(digest::digest(&digest::SHA256, token.as_bytes()))
+}
+
+impl super::Nexus {
+    pub async fn api_token_create(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        params: params::ApiTokenCreate,
+    ) -> CreateResult<views::ApiTokenValue> {
+        if let Some(time_expires) = params.time_expires {
+            if time_expires <= Utc::now() {
+                return Err(Error::invalid_request(
+                    "API token expiration time must be in the future",
+                ));
+            }
+        }
+
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::CreateChild)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+
+        let token = db::model::generate_token();
+        let api_token = db::model::ApiToken::new(
+            silo_user_id,
+            params,
+            api_token_hash(&token),
+        );
+        let api_token = self
+            .db_datastore
+            .api_token_create(opctx, &authz_user, api_token)
+            .await?;
+        Ok(views::ApiTokenValue {
+            token: api_token.into(),
+            access_token: format!("oxide-token-{}", token),
+        })
+    }
+
+    pub async fn api_tokens_list(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        page_params: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<db::model::ApiToken> {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore.api_tokens_list(opctx, &authz_user, page_params).await
+    }
+
+    pub async fn api_token_fetch(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        api_token_name: &Name,
+    ) -> LookupResult<db::model::ApiToken> {
+        let (.., api_token) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .api_token_name(api_token_name)
+            .fetch()
+            .await?;
+        assert_eq!(api_token.name(), &api_token_name.0);
+        Ok(api_token)
+    }
+
+    pub async fn api_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        api_token_name: &Name,
+    ) -> DeleteResult {
+        let (.., authz_user, authz_api_token) =
+            LookupPath::new(opctx, &self.datastore())
+                .silo_user_id(silo_user_id)
+                .api_token_name(api_token_name)
+                .lookup_for(authz::Action::Delete)
+                .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore.api_token_delete(opctx, &authz_api_token).await
+    }
+
+    /// Look up the actor for which an API token was created, recording that
+    /// the token was used.
+    pub async fn api_token_actor(
+        &self,
+        opctx: &OpContext,
+        token: String,
+    ) -> Result<Actor, Reason> {
+        let db_api_token = self
+            .db_datastore
+            .api_token_fetch_by_hash(opctx, api_token_hash(&token))
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => {
+                    Reason::UnknownActor { actor: "from API token".to_string() }
+                }
+                e => Reason::UnknownError { source: e },
+            })?;
+
+        let silo_user_id = db_api_token.silo_user_id;
+        let (.., db_silo_user) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_user_id(silo_user_id)
+            .fetch()
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => {
+                    Reason::UnknownActor { actor: silo_user_id.to_string() }
+                }
+                e => Reason::UnknownError { source: e },
+            })?;
+        let actor =
+            Actor::SiloUser { silo_user_id, silo_id: db_silo_user.silo_id };
+
+        if let Some(time_expires) = db_api_token.time_expires {
+            if time_expires < Utc::now() {
+                return Err(Reason::BadCredentials {
+                    actor,
+                    source: anyhow!("API token expired"),
+                });
+            }
+        }
+
+        self.db_datastore
+            .api_token_update_last_used(opctx, db_api_token.id())
+            .await
+            .map_err(|e| Reason::UnknownError { source: e })?;
+
+        Ok(actor)
+    }
+}
@@ -0,0 +1,67 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Audit log of requests made to the external API
+//!
+//! Entries are recorded as requests complete (see
+//! [`crate::context::ServerContext::instrument_external_handler()`]).  Only
+//! requests that may change the state of the system are recorded.
+
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use crate::external_api::params;
+use chrono::{DateTime, Utc};
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::ListResultVec;
+use uuid::Uuid;
+
+impl super::Nexus {
+    /// Append an entry to the audit log
+    ///
+    /// This is done on behalf of Nexus itself rather than the client that made
+    /// the request being recorded, which may not even have authenticated.
+    pub async fn audit_log_entry_create(
+        &self,
+        entry: db::model::AuditLogEntry,
+    ) -> CreateResult<()> {
+        self.db_datastore
+            .audit_log_entry_create(self.opctx_external_authn(), entry)
+            .await
+    }
+
+    pub async fn audit_log_list(
+        &self,
+        opctx: &OpContext,
+        filter: &params::AuditLogFilter,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<db::model::AuditLogEntry> {
+        self.db_datastore.audit_log_list(opctx, filter, pagparams).await
+    }
+
+    pub async fn silo_audit_log_list(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        filter: &params::AuditLogFilter,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<db::model::AuditLogEntry> {
+        let (authz_silo,) = LookupPath::new(opctx, self.datastore())
+            .silo_name(silo_name)
+            .lookup_for(authz::Action::Read)
+            .await?;
+        let authz_silo_audit_log = authz::SiloAuditLog::new(authz_silo);
+        self.db_datastore
+            .silo_audit_log_list(
+                opctx,
+                &authz_silo_audit_log,
+                filter,
+                pagparams,
+            )
+            .await
+    }
+}
@@ -41,8 +41,10 @@
 //! flow is not specified by the standard. It may be a long-lived token,
 //! or a refresh token which may in turn be used to request a short-lived
 //! access token; it may also be structured (like a JWT) or random.
-//! In the current implementation, we use long-lived random tokens,
-//! but that may change in the future.
+//! In the current implementation, we use random tokens that expire after
+//! a configurable lifetime (see `device_token_ttl_minutes` in the
+//! `authn` section of the Nexus config), but that may change in the future.
+//! Users can list and revoke their own tokens.
 
 use crate::authn::{Actor, Reason};
 use crate::authz;
@@ -51,8 +53,11 @@ use crate::db::lookup::LookupPath;
 use crate::db::model::{DeviceAccessToken, DeviceAuthRequest};
 use crate::external_api::device_auth::DeviceAccessTokenResponse;
 
-use omicron_common::api::external::{CreateResult, Error};
+use omicron_common::api::external::{
+    CreateResult, DataPageParams, DeleteResult, Error, ListResultVec,
+};
 
+use anyhow::anyhow;
 use chrono::Utc;
 use uuid::Uuid;
 
@@ -118,7 +123,7 @@ impl super::Nexus {
                 message: "device authorization request expired".to_string(),
             })
         } else {
-            // TODO-security: set an expiration time for the valid token.
+            let token = token.expires(Utc::now() + self.device_token_ttl);
             self.db_datastore
                 .device_access_token_create(
                     opctx,
@@ -144,9 +149,13 @@ impl super::Nexus {
             .device_access_token_fetch(opctx, client_id, device_code)
             .await
         {
-            Ok(token) => Ok(Granted(token)),
+            Ok(token) => match token.time_expires {
+                // The flow was not completed in time, or the token
+                // has since expired.
+                Some(time_expires) if time_expires < Utc::now() => Ok(Denied),
+                _ => Ok(Granted(token)),
+            },
             Err(_) => Ok(Pending),
-            // TODO: TokenResponse::Denied
         }
     }
 
@@ -180,7 +189,51 @@ impl super::Nexus {
                 e => Reason::UnknownError { source: e },
             })?;
         let silo_id = db_silo_user.silo_id;
+        let actor = Actor::SiloUser { silo_user_id, silo_id };
 
-        Ok(Actor::SiloUser { silo_user_id, silo_id })
+        if let Some(time_expires) = db_access_token.time_expires {
+            if time_expires < Utc::now() {
+                return Err(Reason::BadCredentials {
+                    actor,
+                    source: anyhow!("device access token expired"),
+                });
+            }
+        }
+
+        Ok(actor)
+    }
+
+    /// List the unexpired device access tokens granted to a user.
+    pub async fn device_access_tokens_list(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<DeviceAccessToken> {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore
+            .device_access_tokens_list(opctx, &authz_user, pagparams)
+            .await
+    }
+
+    /// Revoke one of the device access tokens granted to a user.
+    pub async fn device_access_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore
+            .device_access_token_delete(opctx, &authz_user, token_id)
+            .await
     }
 }
@@ -4,13 +4,32 @@
 
 //! External IP addresses for instances
 
+use super::MAX_EXTERNAL_IPS_PER_INSTANCE;
+use crate::app::sagas;
+use crate::app::sagas::ephemeral_ip_attach::SagaEphemeralIpAttach;
+use crate::app::sagas::ephemeral_ip_detach::SagaEphemeralIpDetach;
+use crate::authn;
 use crate::authz;
 use crate::context::OpContext;
+use crate::db;
+use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
+use crate::db::model::FloatingIp;
 use crate::db::model::IpKind;
 use crate::db::model::Name;
+use crate::external_api::params;
 use crate::external_api::views::ExternalIp;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::InstanceState;
+use omicron_common::api::external::InternalContext;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::UpdateResult;
+use std::sync::Arc;
+use uuid::Uuid;
 
 impl super::Nexus {
     pub async fn instance_list_external_ips(
@@ -40,4 +59,319 @@ impl super::Nexus {
             })
             .collect::<Vec<_>>())
     }
+
+    /// Allocate an Ephemeral IP address for an existing instance.
+    ///
+    /// If the instance is running, the sled it's running on is told about the
+    /// new address.  If that fails, the address is released.
+    pub async fn instance_attach_ephemeral_ip(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        instance_name: &Name,
+        params: &params::EphemeralIpCreate,
+    ) -> CreateResult<ExternalIp> {
+        let (.., authz_project, authz_instance, db_instance) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .instance_name(instance_name)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+
+        Self::check_external_ip_change_allowed(&db_instance)?;
+
+        let saga_params = sagas::ephemeral_ip_attach::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            project_id: authz_project.id(),
+            instance_id: authz_instance.id(),
+            pool_name: params.pool_name.clone(),
+        };
+        let saga_outputs =
+            self.execute_saga::<SagaEphemeralIpAttach>(saga_params).await?;
+        saga_outputs
+            .lookup_node_output::<ExternalIp>("external_ip")
+            .map_err(|e| Error::internal_error(&format!("{:#}", &e)))
+            .internal_context("looking up output from ephemeral IP attach saga")
+    }
+
+    /// Release the Ephemeral IP address of an instance.
+    ///
+    /// If the instance is running, the sled it's running on stops using the
+    /// address before it's released.
+    pub async fn instance_detach_ephemeral_ip(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        instance_name: &Name,
+    ) -> DeleteResult {
+        let (.., authz_instance, db_instance) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .instance_name(instance_name)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+
+        Self::check_external_ip_change_allowed(&db_instance)?;
+        let ip = self
+            .db_datastore
+            .instance_lookup_external_ips(opctx, authz_instance.id())
+            .await?
+            .into_iter()
+            .find(|ip| ip.kind == IpKind::Ephemeral)
+            .ok_or_else(|| {
+                Error::invalid_request("instance has no Ephemeral IP address")
+            })?;
+
+        let saga_params = sagas::ephemeral_ip_detach::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            instance_id: authz_instance.id(),
+            ip_id: ip.id,
+        };
+        self.execute_saga::<SagaEphemeralIpDetach>(saga_params).await?;
+        Ok(())
+    }
+
+    // Floating IPs
+
+    pub async fn floating_ip_create(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        params: &params::FloatingIpCreate,
+    ) -> CreateResult<FloatingIp> {
+        let (.., authz_project) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .lookup_for(authz::Action::CreateChild)
+            .await?;
+        self.db_datastore
+            .allocate_floating_ip(
+                opctx,
+                &authz_project,
+                Uuid::new_v4(),
+                &Name(params.identity.name.clone()),
+                &params.identity.description,
+                params.pool_name.clone().map(Name),
+            )
+            .await
+    }
+
+    pub async fn floating_ips_list(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        pagparams: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<FloatingIp> {
+        let (.., authz_project) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        self.db_datastore
+            .floating_ips_list(opctx, &authz_project, pagparams)
+            .await
+    }
+
+    pub async fn floating_ip_fetch(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        floating_ip_name: &Name,
+    ) -> LookupResult<FloatingIp> {
+        let (.., db_fip) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .floating_ip_name(floating_ip_name)
+            .fetch()
+            .await?;
+        Ok(db_fip)
+    }
+
+    pub async fn floating_ip_fetch_by_id(
+        &self,
+        opctx: &OpContext,
+        floating_ip_id: &Uuid,
+    ) -> LookupResult<FloatingIp> {
+        let (.., db_fip) = LookupPath::new(opctx, &self.db_datastore)
+            .floating_ip_id(*floating_ip_id)
+            .fetch()
+            .await?;
+        Ok(db_fip)
+    }
+
+    pub async fn floating_ip_delete(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        floating_ip_name: &Name,
+    ) -> DeleteResult {
+        let (.., authz_fip) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .floating_ip_name(floating_ip_name)
+            .lookup_for(authz::Action::Delete)
+            .await?;
+        self.db_datastore.floating_ip_delete(opctx, &authz_fip).await
+    }
+
+    /// Attach a Floating IP to an instance in the same project.
+    ///
+    /// If the instance is running, the sled it's running on is told about the
+    /// new address.  If that fails, the attachment is undone.
+    pub async fn floating_ip_attach(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        floating_ip_name: &Name,
+        params: &params::FloatingIpAttach,
+    ) -> UpdateResult<FloatingIp> {
+        let (.., authz_project, authz_fip, db_fip) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .floating_ip_name(floating_ip_name)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+        let (.., authz_instance, db_instance) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .project_id(authz_project.id())
+                .instance_name(&Name(params.instance.clone()))
+                .fetch_for(authz::Action::Modify)
+                .await?;
+
+        // Attaching an address that's already attached to this instance is a
+        // no-op, whatever state the instance is in.
+        if db_fip.instance_id == Some(authz_instance.id()) {
+            return Ok(db_fip);
+        }
+
+        Self::check_external_ip_change_allowed(&db_instance)?;
+
+        let fip = self
+            .db_datastore
+            .floating_ip_attach(
+                opctx,
+                &authz_fip,
+                &authz_instance,
+                MAX_EXTERNAL_IPS_PER_INSTANCE,
+            )
+            .await?;
+        if let Err(e) =
+            self.instance_push_external_ips(opctx, &db_instance, None).await
+        {
+            self.db_datastore
+                .floating_ip_detach(opctx, &authz_fip, &authz_instance)
+                .await?;
+            return Err(e);
+        }
+        Ok(fip)
+    }
+
+    /// Detach a Floating IP from the instance it's attached to, if any.
+    ///
+    /// If the instance is running, the sled it's running on is told that the
+    /// address has been removed.  If that fails, the address is reattached.
+    pub async fn floating_ip_detach(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        floating_ip_name: &Name,
+    ) -> UpdateResult<FloatingIp> {
+        let (.., authz_fip, db_fip) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .floating_ip_name(floating_ip_name)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+        let instance_id = match db_fip.instance_id {
+            Some(id) => id,
+            None => return Ok(db_fip),
+        };
+        let (.., authz_instance, db_instance) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .instance_id(instance_id)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+
+        Self::check_external_ip_change_allowed(&db_instance)?;
+
+        let fip = self
+            .db_datastore
+            .floating_ip_detach(opctx, &authz_fip, &authz_instance)
+            .await?;
+        if let Err(e) =
+            self.instance_push_external_ips(opctx, &db_instance, None).await
+        {
+            self.db_datastore
+                .floating_ip_attach(
+                    opctx,
+                    &authz_fip,
+                    &authz_instance,
+                    MAX_EXTERNAL_IPS_PER_INSTANCE,
+                )
+                .await?;
+            return Err(e);
+        }
+        Ok(fip)
+    }
+
+    /// External IP addresses may only be attached to or detached from
+    /// instances that are running or stopped, i.e., not in the middle of a
+    /// state transition.
+    fn check_external_ip_change_allowed(
+        db_instance: &db::model::Instance,
+    ) -> Result<(), Error> {
+        let state = db_instance.runtime().state.state();
+        match state {
+            InstanceState::Running | InstanceState::Stopped => Ok(()),
+            _ => Err(Error::invalid_request(&format!(
+                "cannot change external IP addresses of an instance in \
+                state \"{}\"",
+                state,
+            ))),
+        }
+    }
+
+    /// Send the current set of external IP addresses of an instance, from the
+    /// database, to the sled it's running on, leaving out `exclude_ip` if
+    /// provided.
+    ///
+    /// This does nothing if the instance is not running.
+    pub(crate) async fn instance_push_external_ips(
+        &self,
+        opctx: &OpContext,
+        db_instance: &db::model::Instance,
+        exclude_ip: Option<Uuid>,
+    ) -> Result<(), Error> {
+        if *db_instance.runtime().state.state() != InstanceState::Running {
+            return Ok(());
+        }
+        let external_ips = self
+            .db_datastore
+            .instance_lookup_external_ips(opctx, db_instance.id())
+            .await?
+            .into_iter()
+            .filter(|ip| ip.kind != IpKind::SNat && Some(ip.id) != exclude_ip)
+            .map(|ip| ip.ip.ip())
+            .collect();
+        let sa = self.instance_sled(db_instance).await?;
+        sa.instance_put_external_ips(
+            &db_instance.id(),
+            &sled_agent_client::types::InstanceExternalIpBody { external_ips },
+        )
+        .await
+        .map_err(Error::from)?;
+        Ok(())
+    }
 }
@@ -109,8 +109,10 @@ impl super::Nexus {
             .authn
             .silo_required()
             .internal_context("listing current silo's groups")?;
+        let authz_silo_group_list =
+            authz::SiloGroupList::new(authz_silo.clone());
         self.db_datastore
-            .silo_groups_list_by_id(opctx, &authz_silo, pagparams)
+            .silo_groups_list_by_id(opctx, &authz_silo_group_list, pagparams)
             .await
     }
 
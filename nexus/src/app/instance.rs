@@ -18,6 +18,8 @@ use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::queries::network_interface;
 use crate::external_api::params;
+use crate::external_api::shared;
+use anyhow::Context;
 use futures::future::Fuse;
 use futures::{FutureExt, SinkExt, StreamExt};
 use nexus_db_model::IpKind;
@@ -40,6 +42,7 @@ use sled_agent_client::types::InstanceRuntimeStateRequested;
 use sled_agent_client::types::InstanceStateRequested;
 use sled_agent_client::types::SourceNatConfig;
 use sled_agent_client::Client as SledAgentClient;
+use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -185,10 +188,21 @@ impl super::Nexus {
         //
         // TODO Even worse, post-authz, we do two lookups here instead of one.
         // Maybe sagas should be able to emit `authz::Instance`-type objects.
-        let (.., db_instance) = LookupPath::new(opctx, &self.db_datastore)
-            .instance_id(instance_id)
-            .fetch()
-            .await?;
+        let (.., authz_instance, db_instance) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .instance_id(instance_id)
+                .fetch()
+                .await?;
+
+        // Rules naming this instance, its subnet or its tags may now resolve
+        // differently.
+        if let Some(vpc) = self
+            .db_datastore
+            .instance_resolve_to_vpc(opctx, &authz_instance)
+            .await?
+        {
+            self.vpc_membership_changed(opctx, &vpc).await;
+        }
         Ok(db_instance)
     }
 
@@ -237,6 +251,104 @@ impl super::Nexus {
         Ok(db_instance)
     }
 
+    /// Replace the tags attached to an instance
+    ///
+    /// Firewall rules may select instances by tag, so the rules of the VPC
+    /// the instance is attached to are re-resolved and sent to the sleds
+    /// hosting that VPC.
+    pub async fn instance_update_tags(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        instance_name: &Name,
+        params: &params::InstanceTagsUpdate,
+    ) -> UpdateResult<db::model::Instance> {
+        let (.., authz_instance) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .instance_name(instance_name)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+
+        // Store tags sorted and without duplicates.
+        let tags = params
+            .tags
+            .iter()
+            .cloned()
+            .map(Name::from)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let db_instance = self
+            .db_datastore
+            .instance_update_tags(opctx, &authz_instance, tags)
+            .await?;
+
+        if let Some(vpc) = self
+            .db_datastore
+            .instance_resolve_to_vpc(opctx, &authz_instance)
+            .await?
+        {
+            self.vpc_resend_firewall_rules(opctx, &vpc).await?;
+        }
+
+        Ok(db_instance)
+    }
+
+    pub async fn instance_fetch_policy(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        instance_name: &Name,
+    ) -> LookupResult<shared::Policy<authz::InstanceRole>> {
+        let (.., authz_instance) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .instance_name(instance_name)
+            .lookup_for(authz::Action::ReadPolicy)
+            .await?;
+        let role_assignments = self
+            .db_datastore
+            .role_assignment_fetch_visible(opctx, &authz_instance)
+            .await?
+            .into_iter()
+            .map(|r| r.try_into().context("parsing database role assignment"))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| Error::internal_error(&format!("{:#}", error)))?;
+        Ok(shared::Policy { role_assignments })
+    }
+
+    pub async fn instance_update_policy(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        instance_name: &Name,
+        policy: &shared::Policy<authz::InstanceRole>,
+    ) -> UpdateResult<shared::Policy<authz::InstanceRole>> {
+        let (.., authz_instance) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .instance_name(instance_name)
+            .lookup_for(authz::Action::ModifyPolicy)
+            .await?;
+
+        let role_assignments = self
+            .db_datastore
+            .role_assignment_replace_visible(
+                opctx,
+                &authz_instance,
+                &policy.role_assignments,
+            )
+            .await?
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(shared::Policy { role_assignments })
+    }
+
     // This operation may only occur on stopped instances, which implies that
     // the attached disks do not have any running "upstairs" process running
     // within the sled.
@@ -258,6 +370,12 @@ impl super::Nexus {
                 .fetch()
                 .await?;
 
+        // Find the instance's VPC before its interfaces are deleted.
+        let vpc = self
+            .db_datastore
+            .instance_resolve_to_vpc(opctx, &authz_instance)
+            .await?;
+
         self.db_datastore
             .project_delete_instance(opctx, &authz_instance)
             .await?;
@@ -268,6 +386,12 @@ impl super::Nexus {
         self.db_datastore
             .deallocate_external_ip_by_instance_id(opctx, authz_instance.id())
             .await?;
+        self.db_datastore
+            .detach_floating_ips_by_instance_id(opctx, authz_instance.id())
+            .await?;
+        if let Some(vpc) = vpc {
+            self.vpc_membership_changed(opctx, &vpc).await;
+        }
         Ok(())
     }
 
@@ -297,6 +421,16 @@ impl super::Nexus {
         )
         .await?;
 
+        // The instance is now on a different sled, which needs the rules of
+        // its VPC.
+        if let Some(vpc) = self
+            .db_datastore
+            .instance_resolve_to_vpc(opctx, &authz_instance)
+            .await?
+        {
+            self.vpc_membership_changed(opctx, &vpc).await;
+        }
+
         // TODO correctness TODO robustness TODO design
         // Should we lookup the instance again here?
         // See comment in project_create_instance.
@@ -863,15 +997,17 @@ impl super::Nexus {
         // children, and then use `VpcSubnet::insert_resource` inside the
         // `instance_create_network_interface` method. See
         // https://github.com/oxidecomputer/omicron/issues/738.
-        let vpc_name = db::model::Name(params.vpc_name.clone());
-        let subnet_name = db::model::Name(params.subnet_name.clone());
-        let (.., authz_vpc, authz_subnet, db_subnet) =
-            LookupPath::new(opctx, &self.db_datastore)
-                .project_id(authz_project.id())
-                .vpc_name(&vpc_name)
-                .vpc_subnet_name(&subnet_name)
-                .fetch()
-                .await?;
+        let vpc_name = params.vpc_name.clone().map(db::model::Name);
+        let subnet_name = params.subnet_name.clone().map(db::model::Name);
+        let (authz_vpc, authz_subnet, db_subnet) = self
+            .db_datastore
+            .project_lookup_interface_subnet(
+                opctx,
+                authz_project.id(),
+                vpc_name.as_ref(),
+                subnet_name.as_ref(),
+            )
+            .await?;
         let interface_id = Uuid::new_v4();
         let interface = db::model::IncompleteNetworkInterface::new(
             interface_id,
@@ -881,7 +1017,8 @@ impl super::Nexus {
             params.identity.clone(),
             params.ip,
         )?;
-        self.db_datastore
+        let interface = self
+            .db_datastore
             .instance_create_network_interface(
                 opctx,
                 &authz_subnet,
@@ -909,7 +1046,15 @@ impl super::Nexus {
                     // Convert other errors into an appropriate client error
                     network_interface::InsertError::into_external(e)
                 }
-            })
+            })?;
+        if let Some(vpc) = self
+            .db_datastore
+            .instance_resolve_to_vpc(opctx, &authz_instance)
+            .await?
+        {
+            self.vpc_membership_changed(opctx, &vpc).await;
+        }
+        Ok(interface)
     }
 
     /// Lists network interfaces attached to the instance.
@@ -1017,6 +1162,13 @@ impl super::Nexus {
             .network_interface_name(interface_name)
             .lookup_for(authz::Action::Delete)
             .await?;
+
+        // Find the instance's VPC before the interface, which may be its
+        // last, is deleted.
+        let vpc = self
+            .db_datastore
+            .instance_resolve_to_vpc(opctx, &authz_instance)
+            .await?;
         self.db_datastore
             .instance_delete_network_interface(
                 opctx,
@@ -1044,7 +1196,11 @@ impl super::Nexus {
                     // Convert other errors into an appropriate client error
                     network_interface::DeleteError::into_external(e)
                 }
-            })
+            })?;
+        if let Some(vpc) = vpc {
+            self.vpc_membership_changed(opctx, &vpc).await;
+        }
+        Ok(())
     }
 
     /// Invoked by a sled agent to publish an updated runtime state for an
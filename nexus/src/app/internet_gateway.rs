@@ -0,0 +1,237 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Internet gateways
+
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use crate::external_api::params;
+use chrono::Utc;
+use omicron_common::api::external;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::RouteTarget;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+impl super::Nexus {
+    pub async fn vpc_list_internet_gateways(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        pagparams: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<db::model::InternetGateway> {
+        let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        self.db_datastore
+            .vpc_list_internet_gateways(opctx, &authz_vpc, pagparams)
+            .await
+    }
+
+    pub async fn internet_gateway_fetch(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        gateway_name: &Name,
+    ) -> LookupResult<db::model::InternetGateway> {
+        let (.., db_gateway) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .internet_gateway_name(gateway_name)
+            .fetch()
+            .await?;
+        Ok(db_gateway)
+    }
+
+    pub async fn internet_gateway_fetch_by_id(
+        &self,
+        opctx: &OpContext,
+        gateway_id: &Uuid,
+    ) -> LookupResult<db::model::InternetGateway> {
+        let (.., db_gateway) = LookupPath::new(opctx, &self.db_datastore)
+            .internet_gateway_id(*gateway_id)
+            .fetch()
+            .await?;
+        Ok(db_gateway)
+    }
+
+    pub async fn vpc_create_internet_gateway(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        params: &params::InternetGatewayCreate,
+    ) -> CreateResult<db::model::InternetGateway> {
+        let (.., authz_project, authz_vpc) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .lookup_for(authz::Action::CreateChild)
+                .await?;
+        let ip_pool_ids = self
+            .internet_gateway_ip_pool_ids(
+                opctx,
+                authz_project.id(),
+                &params.ip_pools,
+            )
+            .await?;
+        let gateway = db::model::InternetGateway::new(
+            Uuid::new_v4(),
+            authz_vpc.id(),
+            params.identity.clone(),
+            ip_pool_ids,
+        );
+        let gateway = self
+            .db_datastore
+            .vpc_create_internet_gateway(opctx, &authz_vpc, gateway)
+            .await?;
+        // Routes may already refer to the new gateway by name.
+        self.vpc_routes_changed(opctx, &authz_vpc).await?;
+        Ok(gateway)
+    }
+
+    pub async fn vpc_update_internet_gateway(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        gateway_name: &Name,
+        params: &params::InternetGatewayUpdate,
+    ) -> UpdateResult<db::model::InternetGateway> {
+        let (.., authz_project, _, authz_gateway, db_gateway) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .internet_gateway_name(gateway_name)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+        let ip_pool_ids = match &params.ip_pools {
+            Some(ip_pools) => Some(
+                self.internet_gateway_ip_pool_ids(
+                    opctx,
+                    authz_project.id(),
+                    ip_pools,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        let updates = db::model::InternetGatewayUpdate {
+            name: params.identity.name.clone().map(Name),
+            description: params.identity.description.clone(),
+            time_modified: Utc::now(),
+            ip_pool_ids,
+        };
+        self.db_datastore
+            .vpc_update_internet_gateway(
+                opctx,
+                &authz_gateway,
+                &db_gateway,
+                updates,
+            )
+            .await
+    }
+
+    pub async fn vpc_delete_internet_gateway(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        gateway_name: &Name,
+    ) -> DeleteResult {
+        let (.., authz_gateway, db_gateway) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .internet_gateway_name(gateway_name)
+                .fetch_for(authz::Action::Delete)
+                .await?;
+        self.db_datastore
+            .vpc_delete_internet_gateway(opctx, &authz_gateway, &db_gateway)
+            .await
+    }
+
+    /// Look up the IP Pools named for an internet gateway, which must be
+    /// available to the gateway's project
+    async fn internet_gateway_ip_pool_ids(
+        &self,
+        opctx: &OpContext,
+        project_id: Uuid,
+        ip_pools: &[external::Name],
+    ) -> LookupResult<Vec<Uuid>> {
+        let mut ip_pool_ids = Vec::with_capacity(ip_pools.len());
+        for pool_name in ip_pools {
+            let pool_id = self
+                .db_datastore
+                .ip_pool_for_project(
+                    opctx,
+                    project_id,
+                    Some(Name(pool_name.clone())),
+                )
+                .await?
+                .expect("looking up an IP Pool by name returns its id");
+            if ip_pool_ids.contains(&pool_id) {
+                return Err(Error::invalid_request(&format!(
+                    "IP Pool \"{}\" is given more than once",
+                    pool_name
+                )));
+            }
+            ip_pool_ids.push(pool_id);
+        }
+        Ok(ip_pool_ids)
+    }
+
+    /// Check that a route's target exists, if it's an internet gateway.
+    ///
+    /// Routes to other kinds of targets are resolved as the targets come and
+    /// go, but the gateway targeted by a route can't be deleted, so it must
+    /// exist when the route is created.
+    pub(crate) async fn validate_route_target(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        target: &RouteTarget,
+    ) -> Result<(), Error> {
+        if let RouteTarget::InternetGateway(name) = target {
+            LookupPath::new(opctx, &self.db_datastore)
+                .vpc_id(authz_vpc.id())
+                .internet_gateway_name(&Name(name.clone()))
+                .lookup_for(authz::Action::Read)
+                .await
+                .map_err(|e| match e {
+                    Error::ObjectNotFound { .. } => {
+                        Error::invalid_request(&format!(
+                            "route target internet gateway \"{}\" does not \
+                            exist",
+                            name
+                        ))
+                    }
+                    e => e,
+                })?;
+        }
+        Ok(())
+    }
+}
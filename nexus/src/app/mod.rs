@@ -18,22 +18,29 @@ use anyhow::anyhow;
 use omicron_common::api::external::Error;
 use slog::Logger;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 // The implementation of Nexus is large, and split into a number of submodules
 // by resource.
+mod api_token;
+mod audit_log;
 mod device_auth;
 mod disk;
 mod external_ip;
 mod iam;
 mod image;
 mod instance;
+mod internet_gateway;
 mod ip_pool;
 mod organization;
 mod oximeter;
 mod project;
 mod rack;
 mod saga;
+#[cfg(feature = "saga-fault-injection")]
+mod saga_fault;
+mod scim;
 mod session;
 mod silo;
 mod sled;
@@ -41,6 +48,7 @@ pub mod test_interfaces;
 mod update;
 mod volume;
 mod vpc;
+mod vpc_peering;
 mod vpc_router;
 mod vpc_subnet;
 
@@ -48,6 +56,14 @@ mod vpc_subnet;
 // application logic.
 mod sagas;
 
+#[cfg(feature = "saga-fault-injection")]
+pub use saga_fault::SagaFault;
+#[cfg(feature = "saga-fault-injection")]
+pub use saga_fault::SagaFaultKind;
+#[cfg(feature = "saga-fault-injection")]
+pub use saga_fault::SagaFaultReport;
+pub use sagas::saga_names;
+
 // TODO: When referring to API types, we should try to include
 // the prefix unless it is unambiguous.
 
@@ -58,6 +74,10 @@ pub(crate) const MAX_NICS_PER_INSTANCE: usize = 8;
 // TODO-completness: Support multiple external IPs
 pub(crate) const MAX_EXTERNAL_IPS_PER_INSTANCE: usize = 1;
 
+/// How often every VPC's firewall rules are re-sent to the sleds hosting it,
+/// to catch updates missed as instances come and go.
+const FIREWALL_RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Manages an Oxide fleet -- the heart of the control plane
 pub struct Nexus {
     /// uuid for this nexus instance.
@@ -93,6 +113,9 @@ pub struct Nexus {
     /// The tunable parameters from a configuration file
     tunables: config::Tunables,
 
+    /// How long a device access token is valid after it's granted
+    device_token_ttl: chrono::Duration,
+
     /// Operational context used for Instance allocation
     opctx_alloc: OpContext,
 
@@ -106,6 +129,19 @@ pub struct Nexus {
     // in order for our integration tests that POST static SAML responses to
     // Nexus to not all fail.
     samael_max_issue_delay: std::sync::Mutex<Option<chrono::Duration>>,
+
+    /// Cache of the keys with which OpenID providers sign ID tokens
+    oidc_jwks_cache: authn::silos::OidcJwksCache,
+
+    /// Fault to inject into the next saga with a matching name - used only
+    /// for testing
+    #[cfg(feature = "saga-fault-injection")]
+    saga_fault: std::sync::Mutex<Option<SagaFault>>,
+
+    /// Report about the last saga selected for fault injection - used only
+    /// for testing
+    #[cfg(feature = "saga-fault-injection")]
+    saga_fault_report: std::sync::Mutex<Option<SagaFaultReport>>,
 }
 
 // TODO Is it possible to make some of these operations more generic?  A
@@ -114,7 +150,6 @@ pub struct Nexus {
 // types.
 //
 // TODO update and delete need to accommodate both with-etag and don't-care
-// TODO audit logging ought to be part of this structure and its functions
 impl Nexus {
     /// Create a new Nexus instance for the given rack id `rack_id`
     // TODO-polish revisit rack metadata
@@ -182,6 +217,9 @@ impl Nexus {
             timeseries_client,
             updates_config: config.pkg.updates.clone(),
             tunables: config.pkg.tunables.clone(),
+            device_token_ttl: chrono::Duration::minutes(
+                config.pkg.authn.device_token_ttl_minutes.into(),
+            ),
             opctx_alloc: OpContext::for_background(
                 log.new(o!("component" => "InstanceAllocator")),
                 Arc::clone(&authz),
@@ -195,6 +233,11 @@ impl Nexus {
                 Arc::clone(&db_datastore),
             ),
             samael_max_issue_delay: std::sync::Mutex::new(None),
+            oidc_jwks_cache: authn::silos::OidcJwksCache::new(),
+            #[cfg(feature = "saga-fault-injection")]
+            saga_fault: std::sync::Mutex::new(None),
+            #[cfg(feature = "saga-fault-injection")]
+            saga_fault_report: std::sync::Mutex::new(None),
         };
 
         // TODO-cleanup all the extra Arcs here seems wrong
@@ -220,6 +263,35 @@ impl Nexus {
         );
 
         *nexus.recovery_task.lock().unwrap() = Some(recovery_task);
+
+        // Hold only a weak reference, so that this task doesn't keep Nexus
+        // alive on its own.
+        let reconciler = Arc::downgrade(&nexus);
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(FIREWALL_RECONCILE_INTERVAL);
+            interval.set_missed_tick_behavior(
+                tokio::time::MissedTickBehavior::Delay,
+            );
+            // The first tick completes immediately. There's nothing to
+            // reconcile right at startup, so skip it.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let nexus = match reconciler.upgrade() {
+                    Some(nexus) => nexus,
+                    None => break,
+                };
+                if let Err(error) = nexus.vpc_firewall_rules_reconcile().await {
+                    warn!(
+                        nexus.log,
+                        "failed to reconcile firewall rules";
+                        "error" => %error,
+                    );
+                }
+            }
+        });
+
         nexus
     }
 
@@ -260,6 +332,20 @@ impl Nexus {
         )
     }
 
+    /// Returns an [`OpContext`] used for reconciling VPC firewall rules with
+    /// the sleds hosting each VPC.
+    ///
+    /// The "internal-read" user holds the "network-reconciler" role needed to
+    /// read VPCs in every Silo.
+    fn opctx_for_firewall_reconciler(&self) -> OpContext {
+        OpContext::for_background(
+            self.log.new(o!("component" => "FirewallReconciler")),
+            Arc::clone(&self.authz),
+            authn::Context::internal_read(),
+            Arc::clone(&self.db_datastore),
+        )
+    }
+
     /// Used as the body of a "stub" endpoint -- one that's currently
     /// unimplemented but that we eventually intend to implement
     ///
@@ -456,6 +542,10 @@ impl Nexus {
         *mid
     }
 
+    pub fn oidc_jwks_cache(&self) -> &authn::silos::OidcJwksCache {
+        &self.oidc_jwks_cache
+    }
+
     // Convenience function that exists solely because writing
     // LookupPath::new(&opctx, &nexus.datastore()) in an endpoint handler feels
     // like too much
@@ -161,6 +161,114 @@ impl super::Nexus {
         self.db_datastore.project_delete(opctx, &authz_project).await
     }
 
+    // Settings
+
+    pub async fn project_fetch_settings(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+    ) -> LookupResult<db::model::ProjectSettings> {
+        let (.., authz_project) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .lookup_for(authz::Action::Read)
+            .await?;
+        self.db_datastore.project_settings_fetch(opctx, &authz_project).await
+    }
+
+    pub async fn project_update_settings(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        params: &params::ProjectSettingsUpdate,
+    ) -> UpdateResult<db::model::ProjectSettings> {
+        let (.., authz_project) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        let mut settings = db::model::ProjectSettings::new(authz_project.id());
+
+        if let Some(vpc_name) = &params.default_vpc {
+            let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+                .project_id(authz_project.id())
+                .vpc_name(&Name::from(vpc_name.clone()))
+                .lookup_for(authz::Action::Read)
+                .await?;
+            settings.default_vpc_id = Some(authz_vpc.id());
+        }
+
+        // The default VPC Subnet must be in the default VPC, so that it's used
+        // whenever the VPC of a new network interface isn't specified.
+        if let Some(subnet_name) = &params.default_vpc_subnet {
+            let vpc_name = params
+                .default_vpc
+                .clone()
+                .unwrap_or_else(|| "default".parse().unwrap());
+            let (.., authz_subnet) = LookupPath::new(opctx, &self.db_datastore)
+                .project_id(authz_project.id())
+                .vpc_name(&Name::from(vpc_name))
+                .vpc_subnet_name(&Name::from(subnet_name.clone()))
+                .lookup_for(authz::Action::Read)
+                .await?;
+            settings.default_vpc_subnet_id = Some(authz_subnet.id());
+        }
+
+        if let Some(pool_name) = &params.default_ip_pool {
+            settings.default_ip_pool_id = self
+                .db_datastore
+                .ip_pool_for_project(
+                    opctx,
+                    authz_project.id(),
+                    Some(Name::from(pool_name.clone())),
+                )
+                .await?;
+        }
+
+        self.db_datastore
+            .project_settings_update(opctx, &authz_project, settings)
+            .await
+    }
+
+    // Quotas
+
+    pub async fn project_fetch_quotas(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+    ) -> LookupResult<db::model::ResourceQuota> {
+        let (.., authz_project) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .lookup_for(authz::Action::Read)
+            .await?;
+        let authz_project_quotas = authz::ProjectQuotas::new(authz_project);
+        self.db_datastore
+            .project_quotas_fetch(opctx, &authz_project_quotas)
+            .await
+    }
+
+    pub async fn project_update_quotas(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        params: &params::ResourceQuotasUpdate,
+    ) -> UpdateResult<db::model::ResourceQuota> {
+        let (.., authz_project) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .lookup_for(authz::Action::Read)
+            .await?;
+        let authz_project_quotas = authz::ProjectQuotas::new(authz_project);
+        self.db_datastore
+            .project_quotas_update(opctx, &authz_project_quotas, params)
+            .await
+    }
+
     // Role assignments
 
     pub async fn project_fetch_policy(
@@ -9,24 +9,37 @@ use super::sagas::SagaInitError;
 use super::sagas::ACTION_REGISTRY;
 use crate::authz;
 use crate::context::OpContext;
+use crate::db;
+use crate::db::saga_types::SagaIntervention;
+use crate::db::saga_types::SagaInterventionKind;
+use crate::external_api::params;
+use crate::external_api::views;
 use crate::saga_interface::SagaContext;
 use anyhow::Context;
 use futures::StreamExt;
 use omicron_common::api::external;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::InternalContext;
 use omicron_common::api::external::ListResult;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use omicron_common::bail_unless;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use steno::DagBuilder;
 use steno::SagaDag;
 use steno::SagaId;
 use steno::SagaName;
+use steno::SagaResult;
 use steno::SagaResultOk;
 use uuid::Uuid;
 
+/// Maximum length of the operator-provided reason for intervening in a saga
+/// (this matches the size of the column in the database)
+const MAX_INTERVENTION_REASON_BYTES: usize = 512;
+
 impl super::Nexus {
     pub async fn sagas_list(
         &self,
@@ -66,6 +79,204 @@ impl super::Nexus {
             })?
     }
 
+    /// Returns the persistent log of a saga: its state, every event recorded
+    /// for its nodes, and any operator interventions
+    ///
+    /// Unlike [`Nexus::saga_get()`], this works for sagas owned by any SEC
+    /// (or none at all), since it only consults the database.
+    pub async fn saga_log_view(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+    ) -> LookupResult<views::SagaLog> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        let saga_id = SagaId::from(id);
+        let saga = self.db_datastore.saga_fetch(opctx, saga_id).await?;
+        let node_names = saga_dag_node_names(&saga.saga_dag);
+        let events = self
+            .db_datastore
+            .saga_node_event_list_all(opctx, saga_id)
+            .await?
+            .into_iter()
+            .map(|event| {
+                let node_id = u32::from(event.node_id.0);
+                Ok(views::SagaNodeEvent {
+                    node_id,
+                    node_name: node_names.get(&node_id).cloned(),
+                    event_type: saga_node_event_type(&event.event_type)?,
+                    data: event.data,
+                    time: event.event_time,
+                    sec_id: event.creator.0,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let interventions = self
+            .db_datastore
+            .saga_intervention_list(opctx, saga_id)
+            .await?
+            .into_iter()
+            .map(views::SagaIntervention::from)
+            .collect();
+
+        Ok(views::SagaLog {
+            id,
+            name: saga.name,
+            time_created: saga.time_created,
+            state: match saga.saga_state.0 {
+                steno::SagaCachedState::Running => views::SagaLogState::Running,
+                steno::SagaCachedState::Unwinding => {
+                    views::SagaLogState::Unwinding
+                }
+                steno::SagaCachedState::Done => views::SagaLogState::Done,
+            },
+            current_sec: saga.current_sec.map(|sec| sec.0),
+            adopt_time: saga.adopt_time,
+            events,
+            interventions,
+        })
+    }
+
+    /// Abandons an unfinished saga so that no Nexus will resume it
+    ///
+    /// Steno provides no way to stop a saga that's currently executing, so if
+    /// this Nexus (or another one) is executing the saga, it will keep going.
+    /// However, the SEC refuses to record any further progress (see
+    /// [`db::CockroachDbSecStore`]) and the saga will not be recovered when
+    /// that Nexus restarts.
+    pub async fn saga_abandon(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+        params: &params::SagaIntervene,
+    ) -> UpdateResult<()> {
+        let intervention = self
+            .saga_intervention(
+                opctx,
+                id,
+                SagaInterventionKind::Abandon,
+                None,
+                params,
+            )
+            .await?;
+        let saga = self.db_datastore.saga_abandon(opctx, intervention).await?;
+        info!(self.log, "saga abandoned by operator";
+            "saga_id" => id.to_string(),
+            "saga_name" => &saga.name,
+            "actor" => ?opctx.authn.actor(),
+            "reason" => &params.reason,
+        );
+        Ok(())
+    }
+
+    /// Adopts an unfinished saga into this Nexus and resumes executing it from
+    /// its persistent log
+    ///
+    /// Only a saga that has been abandoned (or that already belongs to this
+    /// Nexus) can be retried.  If the Nexus that owns a saga has gone away,
+    /// the operator must abandon the saga first, which ensures that the old
+    /// owner can't record any more progress should it come back.  It's not
+    /// possible to retry a saga that this Nexus is still executing, since
+    /// Steno provides no way to stop it first.
+    pub async fn saga_retry(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        id: Uuid,
+        params: &params::SagaIntervene,
+    ) -> UpdateResult<()> {
+        let saga_id = SagaId::from(id);
+        let my_sec_id = db::SecId::from(self.id);
+        let intervention = self
+            .saga_intervention(
+                opctx,
+                id,
+                SagaInterventionKind::Retry,
+                Some(my_sec_id),
+                params,
+            )
+            .await?;
+        if self.sec_client.saga_get(saga_id).await.is_ok() {
+            return Err(Error::invalid_request(
+                "saga is currently being executed by this Nexus",
+            ));
+        }
+
+        let saga = self
+            .db_datastore
+            .saga_adopt(opctx, intervention, my_sec_id)
+            .await?;
+        info!(self.log, "saga adopted for retry by operator";
+            "saga_id" => id.to_string(),
+            "saga_name" => &saga.name,
+            "actor" => ?opctx.authn.actor(),
+            "reason" => &params.reason,
+        );
+
+        let saga_logger = self.log.new(o!(
+            "saga_name" => saga.name.clone(),
+            "saga_id" => id.to_string()
+        ));
+        let saga_context = Arc::new(Arc::new(SagaContext::new(
+            Arc::clone(self),
+            saga_logger,
+            Arc::clone(&self.authz),
+        )));
+        let completion = db::recover_saga(
+            opctx,
+            saga_context,
+            &self.db_datastore,
+            &self.sec_client,
+            ACTION_REGISTRY.clone(),
+            saga,
+        )
+        .await?;
+
+        // The caller only waits for the saga to be resumed, not for it to
+        // finish.  Its outcome is visible in the saga's log.
+        let log = self.log.new(o!("saga_id" => id.to_string()));
+        tokio::spawn(async move {
+            match completion.await {
+                Ok(()) => info!(log, "retried saga completed"),
+                Err(error) => {
+                    warn!(log, "retried saga failed"; "error" => ?error)
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Builds the audit record for an operator intervention on a saga
+    async fn saga_intervention(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+        kind: SagaInterventionKind,
+        new_sec: Option<db::SecId>,
+        params: &params::SagaIntervene,
+    ) -> Result<SagaIntervention, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        let actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("intervening in saga")?;
+        if params.reason.len() > MAX_INTERVENTION_REASON_BYTES {
+            return Err(Error::InvalidValue {
+                label: String::from("reason"),
+                message: format!(
+                    "must be at most {} bytes long",
+                    MAX_INTERVENTION_REASON_BYTES
+                ),
+            });
+        }
+
+        Ok(SagaIntervention::new(
+            SagaId::from(id),
+            kind,
+            actor.actor_id(),
+            new_sec,
+            params.reason.clone(),
+        ))
+    }
+
     /// Given a saga type and parameters, create a new saga and execute it.
     pub(crate) async fn execute_saga<N: NexusSaga>(
         self: &Arc<Self>,
@@ -90,14 +301,20 @@ impl super::Nexus {
             saga_logger,
             Arc::clone(&self.authz),
         )));
+        let saga = Arc::new(saga);
+
+        // The test suite may have asked for a fault to be injected into this
+        // saga.
+        #[cfg(feature = "saga-fault-injection")]
+        if let Some(fault) = self.saga_fault_take(saga_id, &saga) {
+            return self
+                .saga_execute_with_fault(saga_id, saga_context, saga, fault)
+                .await;
+        }
+
         let future = self
             .sec_client
-            .saga_create(
-                saga_id,
-                saga_context,
-                Arc::new(saga),
-                ACTION_REGISTRY.clone(),
-            )
+            .saga_create(saga_id, saga_context, saga, ACTION_REGISTRY.clone())
             .await
             .context("creating saga")
             .map_err(|error| {
@@ -113,16 +330,147 @@ impl super::Nexus {
             .context("starting saga")
             .map_err(|error| Error::internal_error(&format!("{:#}", error)))?;
 
-        let result = future.await;
-        result.kind.map_err(|saga_error| {
-            saga_error
-                .error_source
-                .convert::<Error>()
-                .unwrap_or_else(|e| Error::internal_error(&e.to_string()))
-                .internal_context(format!(
-                    "saga error at node {:?}",
-                    saga_error.error_node_name
-                ))
+        saga_result(future.await)
+    }
+}
+
+/// Converts the result of executing a saga into the result of the operation
+/// that executed it
+pub(super) fn saga_result(result: SagaResult) -> Result<SagaResultOk, Error> {
+    result.kind.map_err(|saga_error| {
+        saga_error
+            .error_source
+            .convert::<Error>()
+            .unwrap_or_else(|e| Error::internal_error(&e.to_string()))
+            .internal_context(format!(
+                "saga error at node {:?}",
+                saga_error.error_node_name
+            ))
+    })
+}
+
+/// Maps the event type stored in the saga log to its external representation
+fn saga_node_event_type(
+    label: &str,
+) -> Result<views::SagaNodeEventType, Error> {
+    match label {
+        "started" => Ok(views::SagaNodeEventType::Started),
+        "succeeded" => Ok(views::SagaNodeEventType::Succeeded),
+        "failed" => Ok(views::SagaNodeEventType::Failed),
+        "undo_started" => Ok(views::SagaNodeEventType::UndoStarted),
+        "undo_finished" => Ok(views::SagaNodeEventType::UndoFinished),
+        _ => Err(Error::internal_error(&format!(
+            "unexpected saga node event type: {:?}",
+            label
+        ))),
+    }
+}
+
+/// Describes one node of a serialized saga DAG
+pub(super) struct SagaDagNode {
+    /// kind of node (e.g., "Start" or "Action")
+    // This is only needed to find the action nodes for fault injection.
+    #[cfg_attr(not(feature = "saga-fault-injection"), allow(dead_code))]
+    pub(super) kind: String,
+    /// name of the node's output, or its kind for nodes with no output
+    pub(super) name: String,
+}
+
+/// Returns the names of the nodes in a serialized saga DAG, keyed by node id
+fn saga_dag_node_names(dag: &serde_json::Value) -> BTreeMap<u32, String> {
+    saga_dag_nodes(dag)
+        .into_iter()
+        .map(|(node_id, node)| (node_id, node.name))
+        .collect()
+}
+
+/// Returns the nodes in a serialized saga DAG, keyed by node id
+///
+/// Steno does not expose the structure of a serialized DAG, so this is
+/// best-effort: it understands the serialized graph well enough to find each
+/// node's kind and name, and nodes that can't be understood are simply
+/// omitted.
+pub(super) fn saga_dag_nodes(
+    dag: &serde_json::Value,
+) -> BTreeMap<u32, SagaDagNode> {
+    let nodes = match dag
+        .get("graph")
+        .and_then(|graph| graph.get("nodes"))
+        .and_then(|nodes| nodes.as_array())
+    {
+        Some(nodes) => nodes,
+        None => return BTreeMap::new(),
+    };
+
+    nodes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, node)| {
+            // Each node is an externally-tagged enum variant.  Nodes that
+            // produce output (actions and constants) have a "name"; other
+            // nodes (like the start and end nodes) are described by their
+            // variant.
+            let node = match node {
+                serde_json::Value::String(variant) => {
+                    SagaDagNode { kind: variant.clone(), name: variant.clone() }
+                }
+                serde_json::Value::Object(map) if map.len() == 1 => {
+                    let (variant, contents) = map.iter().next().unwrap();
+                    let name = contents
+                        .get("name")
+                        .and_then(|name| name.as_str())
+                        .map(String::from)
+                        .unwrap_or_else(|| variant.clone());
+                    SagaDagNode { kind: variant.clone(), name }
+                }
+                _ => return None,
+            };
+            Some((u32::try_from(i).ok()?, node))
         })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::saga_dag_node_names;
+    use super::saga_dag_nodes;
+
+    #[test]
+    fn test_saga_dag_node_names() {
+        let dag = serde_json::json!({
+            "saga_name": "test-saga",
+            "graph": {
+                "nodes": [
+                    { "Start": { "params": {} } },
+                    { "Action": {
+                        "name": "instance_id",
+                        "label": "GenerateInstanceId",
+                        "action_name": "instance_create.instance_id",
+                    } },
+                    { "Constant": { "name": "c", "value": 1 } },
+                    "End",
+                    17,
+                ],
+                "node_holes": [],
+                "edge_property": "directed",
+                "edges": [],
+            },
+        });
+
+        let names = saga_dag_node_names(&dag);
+        assert_eq!(names.len(), 4);
+        assert_eq!(names[&0], "Start");
+        assert_eq!(names[&1], "instance_id");
+        assert_eq!(names[&2], "c");
+        assert_eq!(names[&3], "End");
+
+        assert!(saga_dag_node_names(&serde_json::json!({})).is_empty());
+
+        let actions = saga_dag_nodes(&dag)
+            .into_iter()
+            .filter(|(_, node)| node.kind == "Action")
+            .map(|(node_id, _)| node_id)
+            .collect::<Vec<_>>();
+        assert_eq!(actions, vec![1]);
     }
 }
@@ -0,0 +1,299 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Injecting faults into sagas, used by the test suite to check how sagas
+//! behave when their actions fail or are repeated
+//!
+//! This is only built with the "saga-fault-injection" feature, which the test
+//! suite (via `nexus-test-utils`) enables.  See
+//! [`crate::TestInterfaces::saga_fault_inject()`].
+
+use super::saga::saga_dag_nodes;
+use super::saga::saga_result;
+use super::sagas::ACTION_REGISTRY;
+use crate::db;
+use crate::saga_interface::SagaContext;
+use anyhow::Context;
+use omicron_common::api::external::Error;
+use std::sync::Arc;
+use steno::SagaDag;
+use steno::SagaId;
+use steno::SagaResultOk;
+use uuid::Uuid;
+
+/// A fault to inject into a saga
+#[derive(Clone, Debug)]
+pub struct SagaFault {
+    /// name of the saga to inject the fault into (e.g., "disk-create")
+    pub saga_name: String,
+    /// index of the action node in the saga DAG to inject the fault at
+    pub node_index: u32,
+    /// what kind of fault to inject
+    pub kind: SagaFaultKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SagaFaultKind {
+    /// Fail the node without running its action, causing the saga to unwind
+    /// everything that ran before it
+    Error,
+    /// Run the node's action twice, as would happen if Nexus crashed after
+    /// the action completed but before its completion was recorded
+    Repeat,
+}
+
+/// Describes a saga that was selected for fault injection
+#[derive(Clone, Debug)]
+pub struct SagaFaultReport {
+    /// id of the saga
+    pub saga_id: Uuid,
+    /// indexes of all of the action nodes in the saga's DAG
+    pub action_nodes: Vec<u32>,
+    /// name of the node at which the fault was injected, or `None` if the
+    /// requested node was not an action node (in which case the saga ran
+    /// without any fault)
+    pub injected_at: Option<String>,
+}
+
+impl super::Nexus {
+    /// If a fault has been requested for sagas named like `saga`, consumes
+    /// the request and returns the fault to inject
+    ///
+    /// This also records a [`SagaFaultReport`] describing the saga so that
+    /// the test suite can find out what happened.  If the requested node is
+    /// not an action node of this saga, no fault is returned and the saga
+    /// runs normally.
+    pub(super) fn saga_fault_take(
+        &self,
+        saga_id: SagaId,
+        saga: &SagaDag,
+    ) -> Option<SagaFault> {
+        let fault = {
+            let mut saga_fault = self.saga_fault.lock().unwrap();
+            let saga_name = saga.saga_name().to_string();
+            match &*saga_fault {
+                Some(fault) if fault.saga_name == saga_name => (),
+                _ => return None,
+            }
+            saga_fault.take()?
+        };
+
+        // If the DAG can't be serialized, we'll report that there are no
+        // action nodes, which the test suite will notice.
+        let dag = serde_json::to_value(saga).unwrap_or_default();
+        let nodes = saga_dag_nodes(&dag);
+        let action_nodes: Vec<u32> = nodes
+            .iter()
+            .filter(|(_, node)| node.kind == "Action")
+            .map(|(index, _)| *index)
+            .collect();
+        let injected_at = if action_nodes.contains(&fault.node_index) {
+            nodes.get(&fault.node_index).map(|node| node.name.clone())
+        } else {
+            None
+        };
+        info!(self.log, "injecting fault into saga";
+            "saga_id" => saga_id.to_string(),
+            "saga_name" => &fault.saga_name,
+            "fault" => ?fault.kind,
+            "node_index" => fault.node_index,
+            "node_name" => ?injected_at,
+        );
+
+        let fault = if injected_at.is_some() { Some(fault) } else { None };
+        *self.saga_fault_report.lock().unwrap() = Some(SagaFaultReport {
+            saga_id: saga_id.0,
+            action_nodes,
+            injected_at,
+        });
+        fault
+    }
+
+    /// Executes a new saga with `fault` injected into it
+    ///
+    /// The saga's log is recorded in the database, just like that of any
+    /// other saga.
+    pub(super) async fn saga_execute_with_fault(
+        &self,
+        saga_id: SagaId,
+        saga_context: Arc<Arc<SagaContext>>,
+        saga: Arc<SagaDag>,
+        fault: SagaFault,
+    ) -> Result<SagaResultOk, Error> {
+        let result = match fault.kind {
+            SagaFaultKind::Error => {
+                self.saga_execute_failing_node(
+                    saga_id,
+                    saga_context,
+                    saga,
+                    fault.node_index,
+                )
+                .await
+            }
+            SagaFaultKind::Repeat => {
+                self.saga_execute_repeating_node(
+                    saga_id,
+                    saga_context,
+                    saga,
+                    fault.node_index,
+                )
+                .await
+            }
+        };
+        let result = result
+            .map_err(|error| Error::internal_error(&format!("{:#}", error)))?;
+        saga_result(result)
+    }
+
+    /// Executes `saga` in this Nexus's SEC, failing node `node_index` without
+    /// running its action
+    async fn saga_execute_failing_node(
+        &self,
+        saga_id: SagaId,
+        saga_context: Arc<Arc<SagaContext>>,
+        saga: Arc<SagaDag>,
+        node_index: u32,
+    ) -> Result<steno::SagaResult, anyhow::Error> {
+        let future = self
+            .sec_client
+            .saga_create(saga_id, saga_context, saga, ACTION_REGISTRY.clone())
+            .await
+            .context("creating saga")?;
+        self.sec_client
+            .saga_inject_error(saga_id, node_index.into())
+            .await
+            .context("injecting error into saga")?;
+        self.sec_client.saga_start(saga_id).await.context("starting saga")?;
+        Ok(future.await)
+    }
+
+    /// Executes `saga` such that the action for node `node_index` runs twice
+    ///
+    /// Steno only re-runs an action when it resumes a saga whose log shows
+    /// that the action started but not that it finished.  So that's what we
+    /// set up, the same way a crash would: the saga first runs in a private
+    /// SEC whose store records the log in the database like any other, but
+    /// stops the saga forever right when it would record that the node
+    /// finished.  Then this Nexus's SEC resumes the saga from the log in the
+    /// database, exactly as it does when recovering sagas after a restart.
+    async fn saga_execute_repeating_node(
+        &self,
+        saga_id: SagaId,
+        saga_context: Arc<Arc<SagaContext>>,
+        saga: Arc<SagaDag>,
+        node_index: u32,
+    ) -> Result<steno::SagaResult, anyhow::Error> {
+        let (frozen_tx, frozen_rx) = tokio::sync::oneshot::channel();
+        let store = Arc::new(FreezingSecStore {
+            inner: db::CockroachDbSecStore::new(
+                db::SecId::from(self.id),
+                Arc::clone(&self.db_datastore),
+                self.log.new(o!("component" => "SecStore")),
+            ),
+            node_index,
+            frozen_tx: std::sync::Mutex::new(Some(frozen_tx)),
+        });
+        let first_sec =
+            steno::sec(self.log.new(o!("component" => "SEC")), store);
+        let first_run = first_sec
+            .saga_create(
+                saga_id,
+                Arc::clone(&saga_context),
+                saga,
+                ACTION_REGISTRY.clone(),
+            )
+            .await
+            .context("creating saga")?;
+        first_sec.saga_start(saga_id).await.context("starting saga")?;
+
+        tokio::select! {
+            // If the saga finishes without the node completing (e.g., because
+            // an earlier node failed), there's nothing to repeat.
+            result = first_run => return Ok(result),
+            frozen = frozen_rx => frozen.context("waiting for saga to stop")?,
+        };
+
+        // The first SEC never makes progress on this saga again, so we just
+        // leave it behind.
+        let saga = self
+            .db_datastore
+            .saga_fetch_unauthorized(saga_id)
+            .await
+            .context("fetching saga")?;
+        let events = db::load_saga_log(&self.db_datastore, &saga)
+            .await
+            .context("loading saga log")?;
+        let second_run = self
+            .sec_client
+            .saga_resume(
+                saga_id,
+                saga_context,
+                saga.saga_dag,
+                ACTION_REGISTRY.clone(),
+                events,
+            )
+            .await
+            .context("resuming saga")?;
+        self.sec_client.saga_start(saga_id).await.context("starting saga")?;
+        Ok(second_run.await)
+    }
+}
+
+/// Implementation of [`steno::SecStore`] that records a saga's log in the
+/// database until a particular node finishes, then tells
+/// [`super::Nexus::saga_execute_repeating_node()`] and blocks the saga forever
+/// without recording that event
+struct FreezingSecStore {
+    inner: db::CockroachDbSecStore,
+    node_index: u32,
+    frozen_tx: std::sync::Mutex<Option<tokio::sync::oneshot::Sender<()>>>,
+}
+
+impl std::fmt::Debug for FreezingSecStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FreezingSecStore")
+            .field("node_index", &self.node_index)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl steno::SecStore for FreezingSecStore {
+    async fn saga_create(
+        &self,
+        create_params: steno::SagaCreateParams,
+    ) -> Result<(), anyhow::Error> {
+        self.inner.saga_create(create_params).await
+    }
+
+    async fn record_event(&self, event: steno::SagaNodeEvent) {
+        let (frozen, frozen_tx) = {
+            let mut frozen_tx = self.frozen_tx.lock().unwrap();
+            let freeze = u32::from(event.node_id) == self.node_index
+                && matches!(
+                    event.event_type,
+                    steno::SagaNodeEventType::Succeeded(_)
+                );
+            (frozen_tx.is_none(), if freeze { frozen_tx.take() } else { None })
+        };
+
+        if frozen {
+            // Once frozen, nothing else about this saga gets recorded.
+            futures::future::pending::<()>().await
+        } else if let Some(frozen_tx) = frozen_tx {
+            // If nobody's waiting, there's nothing else to do anyway.
+            let _ = frozen_tx.send(());
+            futures::future::pending::<()>().await
+        } else {
+            self.inner.record_event(event).await
+        }
+    }
+
+    async fn saga_update(&self, id: SagaId, update: steno::SagaCachedState) {
+        let frozen = self.frozen_tx.lock().unwrap().is_none();
+        if !frozen {
+            self.inner.saga_update(id, update).await
+        }
+    }
+}
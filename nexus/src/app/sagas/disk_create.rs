@@ -3,6 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use super::{
+    common_storage::delete_crucible_regions,
     common_storage::ensure_all_datasets_and_regions, ActionRegistry,
     NexusActionContext, NexusSaga, SagaInitError, ACTION_GENERATE_ID,
 };
@@ -42,10 +43,16 @@ lazy_static! {
         sdc_create_disk_record,
         sdc_create_disk_record_undo
     );
-    static ref REGIONS_ALLOC: NexusAction =
-        new_action_noop_undo("disk-create.regions-alloc", sdc_alloc_regions,);
-    static ref REGIONS_ENSURE: NexusAction =
-        new_action_noop_undo("disk-create.regions-ensure", sdc_regions_ensure,);
+    static ref REGIONS_ALLOC: NexusAction = ActionFunc::new_action(
+        "disk-create.regions-alloc",
+        sdc_alloc_regions,
+        sdc_alloc_regions_undo,
+    );
+    static ref REGIONS_ENSURE: NexusAction = ActionFunc::new_action(
+        "disk-create.regions-ensure",
+        sdc_regions_ensure,
+        sdc_regions_ensure_undo,
+    );
     static ref CREATE_VOLUME_RECORD: NexusAction = ActionFunc::new_action(
         "disk-create.create-volume-record",
         sdc_create_volume_record,
@@ -242,6 +249,24 @@ async fn sdc_alloc_regions(
     Ok(datasets_and_regions)
 }
 
+async fn sdc_alloc_regions_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+
+    // If the volume record was created, undoing that has already deleted
+    // these regions, in which case this does nothing.
+    let region_ids = sagactx
+        .lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?
+        .into_iter()
+        .map(|(_, region)| region.id())
+        .collect::<Vec<Uuid>>();
+    osagactx.datastore().regions_hard_delete(region_ids).await?;
+    Ok(())
+}
+
 /// Call out to Crucible agent and perform region creation.
 async fn sdc_regions_ensure(
     sagactx: NexusActionContext,
@@ -432,6 +457,18 @@ async fn sdc_regions_ensure(
     Ok(volume_data)
 }
 
+async fn sdc_regions_ensure_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    delete_crucible_regions(
+        sagactx.lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?,
+    )
+    .await?;
+    Ok(())
+}
+
 async fn sdc_create_volume_record(
     sagactx: NexusActionContext,
 ) -> Result<db::model::Volume, ActionError> {
@@ -6,12 +6,14 @@ use super::ActionRegistry;
 use super::NexusActionContext;
 use super::NexusSaga;
 use crate::app::sagas::NexusAction;
+use crate::db;
 use lazy_static::lazy_static;
 use serde::Deserialize;
 use serde::Serialize;
 use std::sync::Arc;
 use steno::new_action_noop_undo;
 use steno::ActionError;
+use steno::ActionFunc;
 use steno::Node;
 use uuid::Uuid;
 
@@ -25,17 +27,13 @@ pub struct Params {
 // disk delete saga: actions
 
 lazy_static! {
-    static ref DELETE_DISK_RECORD: NexusAction = new_action_noop_undo(
+    static ref DELETE_DISK_RECORD: NexusAction = ActionFunc::new_action(
         "disk-delete.delete-disk-record",
-        // TODO: See the comment on the "DeleteRegions" step,
-        // we may want to un-delete the disk if we cannot remove
-        // underlying regions.
-        sdd_delete_disk_record
-    );
-    static ref DELETE_VOLUME: NexusAction = new_action_noop_undo(
-        "disk-delete.delete-volume",
-        sdd_delete_volume
+        sdd_delete_disk_record,
+        sdd_delete_disk_record_undo
     );
+    static ref DELETE_VOLUME: NexusAction =
+        new_action_noop_undo("disk-delete.delete-volume", sdd_delete_volume);
 }
 
 // disk delete saga: definition
@@ -56,7 +54,7 @@ impl NexusSaga for SagaDiskDelete {
         mut builder: steno::DagBuilder,
     ) -> Result<steno::Dag, super::SagaInitError> {
         builder.append(Node::action(
-            "volume_id",
+            "deleted_disk",
             "DeleteDiskRecord",
             DELETE_DISK_RECORD.as_ref(),
         ));
@@ -73,23 +71,35 @@ impl NexusSaga for SagaDiskDelete {
 
 async fn sdd_delete_disk_record(
     sagactx: NexusActionContext,
-) -> Result<Uuid, ActionError> {
+) -> Result<db::model::Disk, ActionError> {
     let osagactx = sagactx.user_data();
     let params = sagactx.saga_params::<Params>()?;
 
-    let volume_id = osagactx
+    let disk = osagactx
         .datastore()
         .project_delete_disk_no_auth(&params.disk_id)
         .await
         .map_err(ActionError::action_failed)?;
-    Ok(volume_id)
+    Ok(disk)
+}
+
+/// Brings the disk back if its volume could not be deleted, so that deleting
+/// the disk can be tried again
+async fn sdd_delete_disk_record_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let disk = sagactx.lookup::<db::model::Disk>("deleted_disk")?;
+    osagactx.datastore().project_undelete_disk_no_auth(&disk).await?;
+    Ok(())
 }
 
 async fn sdd_delete_volume(
     sagactx: NexusActionContext,
 ) -> Result<(), ActionError> {
     let osagactx = sagactx.user_data();
-    let volume_id = sagactx.lookup::<Uuid>("volume_id")?;
+    let volume_id =
+        sagactx.lookup::<db::model::Disk>("deleted_disk")?.volume_id;
     osagactx
         .nexus()
         .volume_delete(volume_id)
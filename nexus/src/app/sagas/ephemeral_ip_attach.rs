@@ -0,0 +1,168 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::ActionRegistry;
+use super::NexusActionContext;
+use super::NexusSaga;
+use super::ACTION_GENERATE_ID;
+use crate::app::sagas::NexusAction;
+use crate::app::MAX_EXTERNAL_IPS_PER_INSTANCE;
+use crate::authn;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::lookup::LookupPath;
+use crate::external_api::views;
+use lazy_static::lazy_static;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::Name;
+use serde::Deserialize;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use steno::ActionError;
+use steno::ActionFunc;
+use steno::Node;
+use uuid::Uuid;
+
+// ephemeral IP attach saga: input parameters
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub project_id: Uuid,
+    pub instance_id: Uuid,
+    pub pool_name: Option<Name>,
+}
+
+// ephemeral IP attach saga: actions
+
+lazy_static! {
+    static ref ALLOCATE_IP: NexusAction = ActionFunc::new_action(
+        "ephemeral-ip-attach.allocate-ip",
+        siea_allocate_ip,
+        siea_allocate_ip_undo,
+    );
+    static ref UPDATE_SLED: NexusAction = ActionFunc::new_action(
+        "ephemeral-ip-attach.update-sled",
+        siea_update_sled,
+        siea_update_sled_undo,
+    );
+}
+
+// ephemeral IP attach saga: definition
+
+#[derive(Debug)]
+pub struct SagaEphemeralIpAttach;
+impl NexusSaga for SagaEphemeralIpAttach {
+    const NAME: &'static str = "ephemeral-ip-attach";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        registry.register(Arc::clone(&*ALLOCATE_IP));
+        registry.register(Arc::clone(&*UPDATE_SLED));
+    }
+
+    fn make_saga_dag(
+        _params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, super::SagaInitError> {
+        builder.append(Node::action(
+            "ip_id",
+            "GenerateIpId",
+            ACTION_GENERATE_ID.as_ref(),
+        ));
+        builder.append(Node::action(
+            "external_ip",
+            "AllocateIp",
+            ALLOCATE_IP.as_ref(),
+        ));
+        builder.append(Node::action(
+            "no_result",
+            "UpdateSled",
+            UPDATE_SLED.as_ref(),
+        ));
+        Ok(builder.build()?)
+    }
+}
+
+// ephemeral IP attach saga: action implementations
+
+/// Allocate an Ephemeral IP for the instance from its project's pools.
+async fn siea_allocate_ip(
+    sagactx: NexusActionContext,
+) -> Result<views::ExternalIp, ActionError> {
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
+    let ip_id = sagactx.lookup::<Uuid>("ip_id")?;
+    let ip = datastore
+        .instance_attach_ephemeral_ip(
+            &opctx,
+            ip_id,
+            params.project_id,
+            params.instance_id,
+            params.pool_name.map(db::model::Name),
+            MAX_EXTERNAL_IPS_PER_INSTANCE,
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+    views::ExternalIp::try_from(ip).map_err(ActionError::action_failed)
+}
+
+async fn siea_allocate_ip_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
+    let ip_id = sagactx.lookup::<Uuid>("ip_id")?;
+    datastore
+        .deallocate_external_ip(&opctx, ip_id)
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+/// Tell the sled running the instance, if any, about its new set of external
+/// IP addresses.
+async fn siea_update_sled(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let params = sagactx.saga_params::<Params>()?;
+    push_external_ips(&sagactx, &params, None)
+        .await
+        .map_err(ActionError::action_failed)
+}
+
+/// Tell the sled to go back to the addresses the instance had before this
+/// saga, i.e., all of them but the one that was allocated.
+async fn siea_update_sled_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let params = sagactx.saga_params::<Params>()?;
+    let ip_id = sagactx.lookup::<Uuid>("ip_id")?;
+    push_external_ips(&sagactx, &params, Some(ip_id)).await?;
+    Ok(())
+}
+
+/// Send the instance's external IP addresses, less `exclude_ip`, to the sled
+/// it's running on
+async fn push_external_ips(
+    sagactx: &NexusActionContext,
+    params: &Params,
+    exclude_ip: Option<Uuid>,
+) -> Result<(), Error> {
+    let osagactx = sagactx.user_data();
+    let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
+    let (.., db_instance) = LookupPath::new(&opctx, osagactx.datastore())
+        .instance_id(params.instance_id)
+        .fetch()
+        .await?;
+    osagactx
+        .nexus()
+        .instance_push_external_ips(&opctx, &db_instance, exclude_ip)
+        .await
+}
@@ -0,0 +1,134 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::ActionRegistry;
+use super::NexusActionContext;
+use super::NexusSaga;
+use crate::app::sagas::NexusAction;
+use crate::authn;
+use crate::context::OpContext;
+use crate::db::lookup::LookupPath;
+use lazy_static::lazy_static;
+use omicron_common::api::external::Error;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::Arc;
+use steno::new_action_noop_undo;
+use steno::ActionError;
+use steno::ActionFunc;
+use steno::Node;
+use uuid::Uuid;
+
+// ephemeral IP detach saga: input parameters
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub instance_id: Uuid,
+    pub ip_id: Uuid,
+}
+
+// ephemeral IP detach saga: actions
+
+lazy_static! {
+    static ref UPDATE_SLED: NexusAction = ActionFunc::new_action(
+        "ephemeral-ip-detach.update-sled",
+        sied_update_sled,
+        sied_update_sled_undo,
+    );
+    static ref DEALLOCATE_IP: NexusAction = new_action_noop_undo(
+        "ephemeral-ip-detach.deallocate-ip",
+        sied_deallocate_ip,
+    );
+}
+
+// ephemeral IP detach saga: definition
+
+#[derive(Debug)]
+pub struct SagaEphemeralIpDetach;
+impl NexusSaga for SagaEphemeralIpDetach {
+    const NAME: &'static str = "ephemeral-ip-detach";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        registry.register(Arc::clone(&*UPDATE_SLED));
+        registry.register(Arc::clone(&*DEALLOCATE_IP));
+    }
+
+    fn make_saga_dag(
+        _params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, super::SagaInitError> {
+        builder.append(Node::action(
+            "no_result0",
+            "UpdateSled",
+            UPDATE_SLED.as_ref(),
+        ));
+        builder.append(Node::action(
+            "no_result1",
+            "DeallocateIp",
+            DEALLOCATE_IP.as_ref(),
+        ));
+        Ok(builder.build()?)
+    }
+}
+
+// ephemeral IP detach saga: action implementations
+
+/// Tell the sled running the instance, if any, to stop using the address
+/// being detached.
+///
+/// The address is removed from the sled before it's released in the database,
+/// so that it can't be allocated to another instance while this one is still
+/// using it.
+async fn sied_update_sled(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let params = sagactx.saga_params::<Params>()?;
+    push_external_ips(&sagactx, &params, Some(params.ip_id))
+        .await
+        .map_err(ActionError::action_failed)
+}
+
+async fn sied_update_sled_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let params = sagactx.saga_params::<Params>()?;
+    push_external_ips(&sagactx, &params, None).await?;
+    Ok(())
+}
+
+/// Send the instance's external IP addresses, less `exclude_ip`, to the sled
+/// it's running on
+async fn push_external_ips(
+    sagactx: &NexusActionContext,
+    params: &Params,
+    exclude_ip: Option<Uuid>,
+) -> Result<(), Error> {
+    let osagactx = sagactx.user_data();
+    let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
+    let (.., db_instance) = LookupPath::new(&opctx, osagactx.datastore())
+        .instance_id(params.instance_id)
+        .fetch()
+        .await?;
+    osagactx
+        .nexus()
+        .instance_push_external_ips(&opctx, &db_instance, exclude_ip)
+        .await
+}
+
+/// Release the address in the database.
+async fn sied_deallocate_ip(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
+    osagactx
+        .datastore()
+        .deallocate_external_ip(&opctx, params.ip_id)
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
@@ -477,24 +477,21 @@ async fn sic_create_custom_network_interface(
         .lookup_for(authz::Action::CreateChild)
         .await
         .map_err(ActionError::action_failed)?;
-    let (.., authz_vpc) = LookupPath::new(&opctx, &datastore)
-        .project_id(saga_params.project_id)
-        .vpc_name(&db::model::Name::from(interface_params.vpc_name.clone()))
-        .lookup_for(authz::Action::Read)
-        .await
-        .map_err(ActionError::action_failed)?;
 
     // TODO-correctness: It seems racy to fetch the subnet and create the
     // interface in separate requests, but outside of a transaction. This
     // should probably either be in a transaction, or the
     // `instance_create_network_interface` function/query needs some JOIN
     // on the `vpc_subnet` table.
-    let (.., authz_subnet, db_subnet) = LookupPath::new(&opctx, &datastore)
-        .vpc_id(authz_vpc.id())
-        .vpc_subnet_name(&db::model::Name::from(
-            interface_params.subnet_name.clone(),
-        ))
-        .fetch()
+    let vpc_name = interface_params.vpc_name.clone().map(db::model::Name);
+    let subnet_name = interface_params.subnet_name.clone().map(db::model::Name);
+    let (authz_vpc, authz_subnet, db_subnet) = datastore
+        .project_lookup_interface_subnet(
+            &opctx,
+            saga_params.project_id,
+            vpc_name.as_ref(),
+            subnet_name.as_ref(),
+        )
         .await
         .map_err(ActionError::action_failed)?;
     let interface = db::model::IncompleteNetworkInterface::new(
@@ -540,13 +537,6 @@ async fn sic_create_default_primary_network_interface(
     let opctx =
         OpContext::for_saga_action(&sagactx, &saga_params.serialized_authn);
 
-    // The literal name "default" is currently used for the VPC and VPC Subnet,
-    // when not specified in the client request.
-    // TODO-completeness: We'd like to select these from Project-level defaults.
-    // See https://github.com/oxidecomputer/omicron/issues/1015.
-    let default_name = Name::try_from("default".to_string()).unwrap();
-    let internal_default_name = db::model::Name::from(default_name.clone());
-
     // The name of the default primary interface.
     let iface_name =
         Name::try_from(DEFAULT_PRIMARY_NIC_NAME.to_string()).unwrap();
@@ -559,8 +549,9 @@ async fn sic_create_default_primary_network_interface(
                 saga_params.create_params.identity.name,
             ),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        // Use the project's default VPC and VPC Subnet.
+        vpc_name: None,
+        subnet_name: None,
         ip: None, // Request an IP address allocation
     };
 
@@ -570,14 +561,15 @@ async fn sic_create_default_primary_network_interface(
         .lookup_for(authz::Action::CreateChild)
         .await
         .map_err(ActionError::action_failed)?;
-    let (.., authz_vpc, authz_subnet, db_subnet) =
-        LookupPath::new(&opctx, &datastore)
-            .project_id(saga_params.project_id)
-            .vpc_name(&internal_default_name)
-            .vpc_subnet_name(&internal_default_name)
-            .fetch()
-            .await
-            .map_err(ActionError::action_failed)?;
+    let (authz_vpc, authz_subnet, db_subnet) = datastore
+        .project_lookup_interface_subnet(
+            &opctx,
+            saga_params.project_id,
+            None,
+            None,
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
 
     let interface = db::model::IncompleteNetworkInterface::new(
         interface_id,
@@ -2,7 +2,6 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::instance_create::allocate_sled_ipv6;
 use super::{NexusActionContext, NexusSaga, ACTION_GENERATE_ID};
 use crate::app::sagas::NexusAction;
 use crate::authn;
@@ -153,9 +152,14 @@ async fn sim_migrate_prep(
 async fn sim_allocate_propolis_ip(
     sagactx: NexusActionContext,
 ) -> Result<Ipv6Addr, ActionError> {
+    let osagactx = sagactx.user_data();
     let params = sagactx.saga_params::<Params>()?;
     let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
-    allocate_sled_ipv6(&opctx, sagactx, "dst_sled_uuid").await
+    osagactx
+        .datastore()
+        .next_ipv6_address(&opctx, params.migrate_params.dst_sled_id)
+        .await
+        .map_err(ActionError::action_failed)
 }
 
 async fn sim_instance_migrate(
@@ -171,12 +175,7 @@ async fn sim_instance_migrate(
     let (instance_id, old_runtime) =
         sagactx.lookup::<(Uuid, InstanceRuntimeState)>("migrate_instance")?;
 
-    // Allocate an IP address the destination sled for the new Propolis server.
-    let propolis_addr = osagactx
-        .datastore()
-        .next_ipv6_address(&opctx, dst_sled_id)
-        .await
-        .map_err(ActionError::action_failed)?;
+    let propolis_addr = sagactx.lookup::<Ipv6Addr>("dst_propolis_ip")?;
 
     let runtime = InstanceRuntimeState {
         sled_id: dst_sled_id,
@@ -21,6 +21,8 @@ use uuid::Uuid;
 
 pub mod disk_create;
 pub mod disk_delete;
+pub mod ephemeral_ip_attach;
+pub mod ephemeral_ip_detach;
 pub mod instance_create;
 pub mod instance_migrate;
 pub mod snapshot_create;
@@ -86,31 +88,65 @@ lazy_static! {
         Arc::new(make_action_registry());
 }
 
+/// Every saga that Nexus runs: its name, and the function that registers its
+/// actions
+///
+/// A saga's actions must be registered for Nexus to run (or recover) it, so
+/// this list is complete.  The test suite relies on that to make sure that
+/// every saga gets tested.
+const SAGAS: &[(&str, fn(&mut ActionRegistry))] = &[
+    (
+        disk_create::SagaDiskCreate::NAME,
+        <disk_create::SagaDiskCreate as NexusSaga>::register_actions,
+    ),
+    (
+        disk_delete::SagaDiskDelete::NAME,
+        <disk_delete::SagaDiskDelete as NexusSaga>::register_actions,
+    ),
+    (
+        ephemeral_ip_attach::SagaEphemeralIpAttach::NAME,
+        <ephemeral_ip_attach::SagaEphemeralIpAttach as NexusSaga>::register_actions,
+    ),
+    (
+        ephemeral_ip_detach::SagaEphemeralIpDetach::NAME,
+        <ephemeral_ip_detach::SagaEphemeralIpDetach as NexusSaga>::register_actions,
+    ),
+    (
+        instance_create::SagaInstanceCreate::NAME,
+        <instance_create::SagaInstanceCreate as NexusSaga>::register_actions,
+    ),
+    (
+        instance_migrate::SagaInstanceMigrate::NAME,
+        <instance_migrate::SagaInstanceMigrate as NexusSaga>::register_actions,
+    ),
+    (
+        snapshot_create::SagaSnapshotCreate::NAME,
+        <snapshot_create::SagaSnapshotCreate as NexusSaga>::register_actions,
+    ),
+    (
+        volume_delete::SagaVolumeDelete::NAME,
+        <volume_delete::SagaVolumeDelete as NexusSaga>::register_actions,
+    ),
+    (
+        volume_remove_rop::SagaVolumeRemoveROP::NAME,
+        <volume_remove_rop::SagaVolumeRemoveROP as NexusSaga>::register_actions,
+    ),
+];
+
 fn make_action_registry() -> ActionRegistry {
     let mut registry = steno::ActionRegistry::new();
     registry.register(Arc::clone(&*ACTION_GENERATE_ID));
-
-    <disk_create::SagaDiskCreate as NexusSaga>::register_actions(&mut registry);
-    <disk_delete::SagaDiskDelete as NexusSaga>::register_actions(&mut registry);
-    <instance_create::SagaInstanceCreate as NexusSaga>::register_actions(
-        &mut registry,
-    );
-    <instance_migrate::SagaInstanceMigrate as NexusSaga>::register_actions(
-        &mut registry,
-    );
-    <snapshot_create::SagaSnapshotCreate as NexusSaga>::register_actions(
-        &mut registry,
-    );
-    <volume_delete::SagaVolumeDelete as NexusSaga>::register_actions(
-        &mut registry,
-    );
-    <volume_remove_rop::SagaVolumeRemoveROP as NexusSaga>::register_actions(
-        &mut registry,
-    );
-
+    for (_, register_actions) in SAGAS {
+        register_actions(&mut registry);
+    }
     registry
 }
 
+/// Returns the names of all of the sagas that Nexus runs
+pub fn saga_names() -> impl Iterator<Item = &'static str> {
+    SAGAS.iter().map(|(name, _)| *name)
+}
+
 pub(super) async fn saga_generate_uuid<UserType: SagaType>(
     _: ActionContext<UserType>,
 ) -> Result<Uuid, ActionError> {
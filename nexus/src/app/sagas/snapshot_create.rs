@@ -82,6 +82,8 @@
 //!
 
 use super::{
+    common_storage::delete_crucible_regions,
+    common_storage::delete_crucible_snapshots,
     common_storage::ensure_all_datasets_and_regions, ActionRegistry,
     NexusActionContext, NexusSaga, SagaInitError, ACTION_GENERATE_ID,
 };
@@ -126,13 +128,15 @@ pub struct Params {
 // snapshot create saga: actions
 
 lazy_static! {
-    static ref REGIONS_ALLOC: NexusAction = new_action_noop_undo(
+    static ref REGIONS_ALLOC: NexusAction = ActionFunc::new_action(
         "snapshot-create.regions-alloc",
         ssc_alloc_regions,
+        ssc_alloc_regions_undo,
     );
-    static ref REGIONS_ENSURE: NexusAction = new_action_noop_undo(
+    static ref REGIONS_ENSURE: NexusAction = ActionFunc::new_action(
         "snapshot-create.regions-ensure",
         ssc_regions_ensure,
+        ssc_regions_ensure_undo,
     );
     static ref CREATE_DESTINATION_VOLUME_RECORD: NexusAction =
         ActionFunc::new_action(
@@ -149,9 +153,10 @@ lazy_static! {
         "snapshot-create.send-snapshot-request",
         ssc_send_snapshot_request,
     );
-    static ref START_RUNNING_SNAPSHOT: NexusAction = new_action_noop_undo(
+    static ref START_RUNNING_SNAPSHOT: NexusAction = ActionFunc::new_action(
         "snapshot-create.start-running-snapshot",
         ssc_start_running_snapshot,
+        ssc_start_running_snapshot_undo,
     );
     static ref CREATE_VOLUME_RECORD: NexusAction = ActionFunc::new_action(
         "snapshot-create.create-volume-record",
@@ -312,6 +317,24 @@ async fn ssc_alloc_regions(
     Ok(datasets_and_regions)
 }
 
+async fn ssc_alloc_regions_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+
+    // If the destination volume record was created, undoing that has already
+    // deleted these regions, in which case this does nothing.
+    let region_ids = sagactx
+        .lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?
+        .into_iter()
+        .map(|(_, region)| region.id())
+        .collect::<Vec<Uuid>>();
+    osagactx.datastore().regions_hard_delete(region_ids).await?;
+    Ok(())
+}
+
 async fn ssc_regions_ensure(
     sagactx: NexusActionContext,
 ) -> Result<String, ActionError> {
@@ -389,6 +412,18 @@ async fn ssc_regions_ensure(
     Ok(volume_data)
 }
 
+async fn ssc_regions_ensure_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    delete_crucible_regions(
+        sagactx.lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?,
+    )
+    .await?;
+    Ok(())
+}
+
 async fn ssc_create_destination_volume_record(
     sagactx: NexusActionContext,
 ) -> Result<db::model::Volume, ActionError> {
@@ -706,6 +741,52 @@ async fn ssc_start_running_snapshot(
     Ok(map)
 }
 
+async fn ssc_start_running_snapshot_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = OpContext::for_saga_action(&sagactx, &params.serialized_authn);
+
+    let snapshot_id = sagactx.lookup::<Uuid>("snapshot_id")?;
+
+    let (.., disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .fetch()
+        .await?;
+    let datasets_and_regions =
+        osagactx.datastore().get_allocated_regions(disk.volume_id).await?;
+
+    for (dataset, region) in datasets_and_regions {
+        // If the snapshot's volume record was created, undoing that has
+        // already removed the running snapshots along with their records.
+        let region_snapshot = osagactx
+            .datastore()
+            .region_snapshot_get(dataset.id(), region.id(), snapshot_id)
+            .await?;
+        let region_snapshot = match region_snapshot {
+            Some(region_snapshot) => region_snapshot,
+            None => continue,
+        };
+
+        info!(
+            log,
+            "deleting running snapshot {} of region {}",
+            snapshot_id,
+            region.id()
+        );
+        delete_crucible_snapshots(vec![(dataset.clone(), region_snapshot)])
+            .await?;
+        osagactx
+            .datastore()
+            .region_snapshot_remove(dataset.id(), region.id(), snapshot_id)
+            .await?;
+    }
+
+    Ok(())
+}
+
 async fn ssc_create_volume_record(
     sagactx: NexusActionContext,
 ) -> Result<db::model::Volume, ActionError> {
@@ -0,0 +1,475 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Provisioning of Silo users and groups by identity providers, through SCIM
+//!
+//! In Silos whose users are provisioned through SCIM, the identity provider
+//! creates, updates, deactivates, and deletes users and groups, and manages
+//! group memberships.  It authenticates with a bearer token that the Silo's
+//! administrators create for it.  Users authenticate through SAML or OpenID
+//! Connect as usual, but they're never created when they log in.
+//!
+//! Users are identified to SCIM clients by their id, and their SCIM "userName"
+//! is their external id (which is what the identity provider identifies them
+//! by when they log in).  Similarly, a group's SCIM "displayName" is its
+//! external id.
+
+use crate::authz;
+use crate::authz::ApiResource;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::identity::Asset;
+use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use crate::db::model::UserProvisionType;
+use crate::external_api::views;
+use chrono::Utc;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
+use ring::digest;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Returns the hash of a SCIM client bearer token, as stored in the database
+fn scim_bearer_token_hash(token: &str) -> String {
+    hex::encode(digest::digest(&digest::SHA256, token.as_bytes()))
+}
+
+impl super::Nexus {
+    // SCIM client bearer tokens
+
+    /// Helper function for looking up a Silo whose users are provisioned
+    /// through SCIM
+    async fn scim_fetch_silo(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+    ) -> LookupResult<(authz::Silo, db::model::Silo)> {
+        let (authz_silo, db_silo) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_name(silo_name)
+            .fetch()
+            .await?;
+        if db_silo.user_provision_type != UserProvisionType::Scim {
+            return Err(Error::invalid_request(
+                "silo's users are not provisioned through SCIM",
+            ));
+        }
+        Ok((authz_silo, db_silo))
+    }
+
+    pub async fn scim_token_list(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+    ) -> ListResultVec<db::model::ScimClientBearerToken> {
+        let (authz_silo, _) = self.scim_fetch_silo(opctx, silo_name).await?;
+        self.db_datastore
+            .scim_client_bearer_token_list(opctx, &authz_silo)
+            .await
+    }
+
+    pub async fn scim_token_create(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+    ) -> CreateResult<views::ScimClientBearerTokenValue> {
+        let (authz_silo, _) = self.scim_fetch_silo(opctx, silo_name).await?;
+        let bearer_token = db::model::generate_token();
+        let token = db::model::ScimClientBearerToken::new(
+            authz_silo.id(),
+            scim_bearer_token_hash(&bearer_token),
+        );
+        let token = self
+            .db_datastore
+            .scim_client_bearer_token_create(opctx, &authz_silo, token)
+            .await?;
+        Ok(views::ScimClientBearerTokenValue {
+            id: token.id,
+            time_created: token.time_created,
+            bearer_token,
+        })
+    }
+
+    pub async fn scim_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo, _) = self.scim_fetch_silo(opctx, silo_name).await?;
+        self.db_datastore
+            .scim_client_bearer_token_delete(opctx, &authz_silo, token_id)
+            .await
+    }
+
+    /// Authenticate a SCIM client of a Silo by its bearer token
+    ///
+    /// Every failure to authenticate produces the same error, so as not to
+    /// reveal whether the Silo exists or is provisioned through SCIM.
+    pub async fn scim_authenticate(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        bearer_token: Option<String>,
+    ) -> LookupResult<authz::Silo> {
+        let unauthenticated = |internal_message: &str| Error::Unauthenticated {
+            internal_message: internal_message.to_string(),
+        };
+
+        let bearer_token =
+            bearer_token.ok_or_else(|| unauthenticated("no bearer token"))?;
+        let (authz_silo, _) = self
+            .scim_fetch_silo(opctx, silo_name)
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } | Error::InvalidRequest { .. } => {
+                    unauthenticated("no such SCIM-provisioned silo")
+                }
+                e => e,
+            })?;
+        self.db_datastore
+            .scim_client_bearer_token_fetch_by_hash(
+                opctx,
+                &authz_silo,
+                scim_bearer_token_hash(&bearer_token),
+            )
+            .await?
+            .ok_or_else(|| unauthenticated("unknown SCIM bearer token"))?;
+        Ok(authz_silo)
+    }
+
+    // SCIM users
+
+    pub async fn scim_user_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        user_name: Option<String>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(i64, Vec<db::model::SiloUser>), Error> {
+        self.db_datastore
+            .silo_users_list_by_offset(
+                opctx, authz_silo, user_name, offset, limit,
+            )
+            .await
+    }
+
+    pub async fn scim_user_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+    ) -> LookupResult<db::model::SiloUser> {
+        let (_, db_silo_user) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Read,
+            )
+            .await?;
+        Ok(db_silo_user)
+    }
+
+    pub async fn scim_user_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        user_name: String,
+        active: bool,
+    ) -> CreateResult<db::model::SiloUser> {
+        opctx.authorize(authz::Action::CreateChild, authz_silo).await?;
+        let mut silo_user = db::model::SiloUser::new(
+            authz_silo.id(),
+            Uuid::new_v4(),
+            user_name,
+        );
+        silo_user.active = active;
+        let (_, db_silo_user) =
+            self.db_datastore.silo_user_create(authz_silo, silo_user).await?;
+        Ok(db_silo_user)
+    }
+
+    pub async fn scim_user_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+        user_name: Option<String>,
+        active: Option<bool>,
+    ) -> UpdateResult<db::model::SiloUser> {
+        let (authz_silo_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Modify,
+            )
+            .await?;
+        self.db_datastore
+            .silo_user_update(
+                opctx,
+                &authz_silo_user,
+                db::model::SiloUserUpdate {
+                    external_id: user_name,
+                    active,
+                    time_modified: Utc::now(),
+                },
+            )
+            .await
+    }
+
+    pub async fn scim_user_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Delete,
+            )
+            .await?;
+        self.db_datastore.silo_user_delete(opctx, &authz_silo_user).await
+    }
+
+    // SCIM groups
+    //
+    // Groups are returned along with the ids of their members.
+
+    async fn scim_group_members(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+    ) -> ListResultVec<Uuid> {
+        Ok(self
+            .db_datastore
+            .silo_group_membership_for_group(opctx, authz_silo_group)
+            .await?
+            .into_iter()
+            .map(|membership| membership.silo_user_id)
+            .collect())
+    }
+
+    /// Check that every member of a group is a user in the group's Silo
+    async fn scim_group_validate_members(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        members: Vec<Uuid>,
+    ) -> LookupResult<Vec<Uuid>> {
+        let members: BTreeSet<Uuid> = members.into_iter().collect();
+        for silo_user_id in &members {
+            self.silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                *silo_user_id,
+                authz::Action::Read,
+            )
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => Error::InvalidValue {
+                    label: String::from("members"),
+                    message: format!("no such user: {}", silo_user_id),
+                },
+                e => e,
+            })?;
+        }
+        Ok(members.into_iter().collect())
+    }
+
+    pub async fn scim_group_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        display_name: Option<String>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(i64, Vec<(db::model::SiloGroup, Vec<Uuid>)>), Error> {
+        let (total, db_silo_groups) = self
+            .db_datastore
+            .silo_groups_list_by_offset(
+                opctx,
+                authz_silo,
+                display_name,
+                offset,
+                limit,
+            )
+            .await?;
+        let mut groups = Vec::with_capacity(db_silo_groups.len());
+        for db_silo_group in db_silo_groups {
+            let authz_silo_group = authz::SiloGroup::new(
+                authz_silo.clone(),
+                db_silo_group.id(),
+                LookupType::ById(db_silo_group.id()),
+            );
+            let members =
+                self.scim_group_members(opctx, &authz_silo_group).await?;
+            groups.push((db_silo_group, members));
+        }
+        Ok((total, groups))
+    }
+
+    pub async fn scim_group_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+    ) -> LookupResult<(db::model::SiloGroup, Vec<Uuid>)> {
+        let (authz_silo_group, db_silo_group) = self
+            .silo_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                authz::Action::Read,
+            )
+            .await?;
+        let members = self.scim_group_members(opctx, &authz_silo_group).await?;
+        Ok((db_silo_group, members))
+    }
+
+    pub async fn scim_group_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        display_name: String,
+        members: Vec<Uuid>,
+    ) -> CreateResult<(db::model::SiloGroup, Vec<Uuid>)> {
+        // Unlike groups created during authentication, groups created
+        // through SCIM must be new.
+        if self
+            .db_datastore
+            .silo_group_optional_lookup(opctx, authz_silo, display_name.clone())
+            .await?
+            .is_some()
+        {
+            return Err(Error::ObjectAlreadyExists {
+                type_name: ResourceType::SiloGroup,
+                object_name: display_name,
+            });
+        }
+
+        let members = self
+            .scim_group_validate_members(opctx, authz_silo, members)
+            .await?;
+        let db_silo_group = self
+            .db_datastore
+            .silo_group_ensure(
+                opctx,
+                authz_silo,
+                db::model::SiloGroup::new(
+                    Uuid::new_v4(),
+                    authz_silo.id(),
+                    display_name,
+                ),
+            )
+            .await?;
+        let (authz_silo_group, _) = self
+            .silo_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                db_silo_group.id(),
+                authz::Action::Modify,
+            )
+            .await?;
+        self.db_datastore
+            .silo_group_membership_replace_for_group(
+                opctx,
+                &authz_silo_group,
+                members.clone(),
+            )
+            .await?;
+        Ok((db_silo_group, members))
+    }
+
+    pub async fn scim_group_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+        display_name: Option<String>,
+        members: Option<Vec<Uuid>>,
+    ) -> UpdateResult<(db::model::SiloGroup, Vec<Uuid>)> {
+        let (authz_silo_group, mut db_silo_group) = self
+            .silo_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                authz::Action::Modify,
+            )
+            .await?;
+
+        if let Some(display_name) = display_name {
+            if display_name != db_silo_group.external_id {
+                db_silo_group = self
+                    .db_datastore
+                    .silo_group_update(
+                        opctx,
+                        &authz_silo_group,
+                        db::model::SiloGroupUpdate {
+                            external_id: display_name,
+                            time_modified: Utc::now(),
+                        },
+                    )
+                    .await?;
+            }
+        }
+
+        let members = match members {
+            Some(members) => {
+                let members = self
+                    .scim_group_validate_members(opctx, authz_silo, members)
+                    .await?;
+                self.db_datastore
+                    .silo_group_membership_replace_for_group(
+                        opctx,
+                        &authz_silo_group,
+                        members.clone(),
+                    )
+                    .await?;
+                members
+            }
+            None => self.scim_group_members(opctx, &authz_silo_group).await?,
+        };
+
+        Ok((db_silo_group, members))
+    }
+
+    pub async fn scim_group_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo_group, _) = self
+            .silo_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                authz::Action::Delete,
+            )
+            .await?;
+
+        // Groups can't be deleted while they have members, but deleting a
+        // group through SCIM removes its members from it.
+        self.db_datastore
+            .silo_group_membership_replace_for_group(
+                opctx,
+                &authz_silo_group,
+                vec![],
+            )
+            .await?;
+        self.db_datastore.silo_group_delete(opctx, &authz_silo_group).await
+    }
+}
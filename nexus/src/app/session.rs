@@ -10,14 +10,19 @@ use crate::authz;
 use crate::context::OpContext;
 use crate::db;
 use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use chrono::{DateTime, Utc};
 use hex;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::UpdateResult;
 use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::net::IpAddr;
 use uuid::Uuid;
 
 fn generate_session_token() -> String {
@@ -33,6 +38,9 @@ fn generate_session_token() -> String {
     hex::encode(random_bytes)
 }
 
+/// Maximum length of a User-Agent stored with a session, in characters
+const USER_AGENT_MAX_LEN: usize = 512;
+
 impl super::Nexus {
     async fn login_allowed(
         &self,
@@ -72,6 +80,8 @@ impl super::Nexus {
         &self,
         opctx: &OpContext,
         user_id: Uuid,
+        user_agent: Option<&str>,
+        client_address: Option<IpAddr>,
     ) -> CreateResult<db::model::ConsoleSession> {
         if !self.login_allowed(opctx, user_id).await? {
             return Err(Error::Unauthenticated {
@@ -79,8 +89,15 @@ impl super::Nexus {
             });
         }
 
-        let session =
-            db::model::ConsoleSession::new(generate_session_token(), user_id);
+        let user_agent = user_agent.map(|user_agent| {
+            user_agent.chars().take(USER_AGENT_MAX_LEN).collect()
+        });
+        let session = db::model::ConsoleSession::new(
+            generate_session_token(),
+            user_id,
+            user_agent,
+            client_address,
+        );
 
         self.db_datastore.session_create(opctx, session).await
     }
@@ -134,6 +151,125 @@ impl super::Nexus {
         self.db_datastore.session_hard_delete(opctx, &authz_session).await
     }
 
+    /// List the unexpired sessions of the user with id `silo_user_id`
+    ///
+    /// Sessions last used at or before `last_used_after` or created at or
+    /// before `created_after` have expired, even if they haven't been
+    /// cleaned up yet.
+    pub async fn sessions_list(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        last_used_after: DateTime<Utc>,
+        created_after: DateTime<Utc>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<db::model::ConsoleSession> {
+        let (.., authz_user) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        self.db_datastore
+            .sessions_list_by_user(
+                opctx,
+                &authz_user,
+                last_used_after,
+                created_after,
+                pagparams,
+            )
+            .await
+    }
+
+    /// Revoke one of the sessions of the user with id `silo_user_id`
+    pub async fn session_delete(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        session_id: Uuid,
+    ) -> DeleteResult {
+        let (.., authz_user) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        self.db_datastore
+            .session_delete_by_id(opctx, &authz_user, session_id)
+            .await
+    }
+
+    /// List the unexpired sessions of a user in a particular Silo
+    pub async fn silo_user_sessions_list(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        silo_user_id: Uuid,
+        last_used_after: DateTime<Utc>,
+        created_after: DateTime<Utc>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<db::model::ConsoleSession> {
+        let authz_user = self
+            .silo_user_lookup_for_sessions(opctx, silo_name, silo_user_id)
+            .await?;
+        self.db_datastore
+            .sessions_list_by_user(
+                opctx,
+                &authz_user,
+                last_used_after,
+                created_after,
+                pagparams,
+            )
+            .await
+    }
+
+    /// Revoke one of the sessions of a user in a particular Silo
+    pub async fn silo_user_session_delete(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        silo_user_id: Uuid,
+        session_id: Uuid,
+    ) -> DeleteResult {
+        let authz_user = self
+            .silo_user_lookup_for_sessions(opctx, silo_name, silo_user_id)
+            .await?;
+        self.db_datastore
+            .session_delete_by_id(opctx, &authz_user, session_id)
+            .await
+    }
+
+    /// Revoke all of the sessions of a user in a particular Silo, logging
+    /// them out everywhere
+    pub async fn silo_user_sessions_delete(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        silo_user_id: Uuid,
+    ) -> DeleteResult {
+        let authz_user = self
+            .silo_user_lookup_for_sessions(opctx, silo_name, silo_user_id)
+            .await?;
+        self.db_datastore.sessions_delete_by_user(opctx, &authz_user).await
+    }
+
+    async fn silo_user_lookup_for_sessions(
+        &self,
+        opctx: &OpContext,
+        silo_name: &Name,
+        silo_user_id: Uuid,
+    ) -> LookupResult<authz::SiloUser> {
+        let (authz_silo,) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_name(silo_name)
+            .lookup_for(authz::Action::Read)
+            .await?;
+        let (authz_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                &authz_silo,
+                silo_user_id,
+                authz::Action::Modify,
+            )
+            .await?;
+        Ok(authz_user)
+    }
+
     pub async fn lookup_silo_for_authn(
         &self,
         opctx: &OpContext,
@@ -2,6 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+#[cfg(feature = "saga-fault-injection")]
+use super::SagaFault;
+#[cfg(feature = "saga-fault-injection")]
+use super::SagaFaultReport;
 use crate::authz;
 use crate::context::OpContext;
 use crate::db::lookup::LookupPath;
@@ -43,6 +47,17 @@ pub trait TestInterfaces {
     ) -> Result<SiloUser, Error>;
 
     fn set_samael_max_issue_delay(&self, max_issue_delay: chrono::Duration);
+
+    /// Arranges for `fault` to be injected into the next saga with a matching
+    /// name that this Nexus executes.  This replaces any fault that has not
+    /// yet been injected.
+    #[cfg(feature = "saga-fault-injection")]
+    fn saga_fault_inject(&self, fault: SagaFault);
+
+    /// Returns a report about the last saga selected for fault injection
+    /// since the last call to `saga_fault_inject()`, if any.
+    #[cfg(feature = "saga-fault-injection")]
+    fn saga_fault_report(&self) -> Option<SagaFaultReport>;
 }
 
 #[async_trait]
@@ -120,4 +135,15 @@ impl TestInterfaces for super::Nexus {
         let mut mid = self.samael_max_issue_delay.lock().unwrap();
         *mid = Some(max_issue_delay);
     }
+
+    #[cfg(feature = "saga-fault-injection")]
+    fn saga_fault_inject(&self, fault: SagaFault) {
+        *self.saga_fault.lock().unwrap() = Some(fault);
+        *self.saga_fault_report.lock().unwrap() = None;
+    }
+
+    #[cfg(feature = "saga-fault-injection")]
+    fn saga_fault_report(&self) -> Option<SagaFaultReport> {
+        self.saga_fault_report.lock().unwrap().take()
+    }
 }
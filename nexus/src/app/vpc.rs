@@ -14,6 +14,8 @@ use crate::db::model::Name;
 use crate::db::model::VpcRouterKind;
 use crate::db::queries::vpc_subnet::SubnetError;
 use crate::external_api::params;
+use crate::external_api::shared;
+use crate::external_api::views;
 use nexus_defaults as defaults;
 use omicron_common::api::external;
 use omicron_common::api::external::CreateResult;
@@ -33,10 +35,13 @@ use omicron_common::api::external::VpcFirewallRuleUpdateParams;
 use sled_agent_client::types::IpNet;
 use sled_agent_client::types::NetworkInterface;
 
+use anyhow::Context;
+use dropshot::PaginationOrder;
 use futures::future::join_all;
 use ipnetwork::IpNetwork;
 use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
+use std::num::NonZeroU32;
 use uuid::Uuid;
 
 impl super::Nexus {
@@ -98,6 +103,15 @@ impl super::Nexus {
             .db_datastore
             .vpc_create_router(&opctx, &authz_vpc, router)
             .await?;
+
+        // The default route sends traffic out of the rack through the VPC's
+        // "outbound" internet gateway, which allocates addresses from the
+        // project's default IP Pool until it's given pools of its own.
+        let gateway =
+            db::model::InternetGateway::new_outbound(Uuid::new_v4(), vpc_id);
+        self.db_datastore
+            .vpc_create_internet_gateway(&opctx, &authz_vpc, gateway)
+            .await?;
         let route = db::model::RouterRoute::new(
             default_route_id,
             system_router_id,
@@ -277,7 +291,8 @@ impl super::Nexus {
             LookupType::ById(db_vpc.system_router_id),
         );
 
-        // Possibly delete the VPC, then the router and firewall.
+        // Possibly delete the VPC, then the router, internet gateways and
+        // firewall.
         //
         // We must delete the VPC first. This will fail if the VPC still
         // contains at least one subnet, since those are independent containers
@@ -293,6 +308,9 @@ impl super::Nexus {
             .project_delete_vpc(opctx, &db_vpc, &authz_vpc)
             .await?;
         self.db_datastore.vpc_delete_router(&opctx, &authz_vpc_router).await?;
+        self.db_datastore
+            .vpc_delete_all_internet_gateways(&opctx, &authz_vpc)
+            .await?;
 
         // Delete all firewall rules after deleting the VPC, to ensure no
         // firewall rules get added between rules deletion and VPC deletion.
@@ -301,6 +319,59 @@ impl super::Nexus {
             .await
     }
 
+    pub async fn vpc_fetch_policy(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+    ) -> LookupResult<shared::Policy<authz::VpcRole>> {
+        let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .lookup_for(authz::Action::ReadPolicy)
+            .await?;
+        let role_assignments = self
+            .db_datastore
+            .role_assignment_fetch_visible(opctx, &authz_vpc)
+            .await?
+            .into_iter()
+            .map(|r| r.try_into().context("parsing database role assignment"))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| Error::internal_error(&format!("{:#}", error)))?;
+        Ok(shared::Policy { role_assignments })
+    }
+
+    pub async fn vpc_update_policy(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        policy: &shared::Policy<authz::VpcRole>,
+    ) -> UpdateResult<shared::Policy<authz::VpcRole>> {
+        let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .lookup_for(authz::Action::ModifyPolicy)
+            .await?;
+
+        let role_assignments = self
+            .db_datastore
+            .role_assignment_replace_visible(
+                opctx,
+                &authz_vpc,
+                &policy.role_assignments,
+            )
+            .await?
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(shared::Policy { role_assignments })
+    }
+
     // Firewall rules
 
     pub async fn vpc_list_firewall_rules(
@@ -350,6 +421,293 @@ impl super::Nexus {
         Ok(rules)
     }
 
+    /// Evaluate a synthetic flow against a VPC's firewall rules, returning
+    /// the rule that decides its fate and the resulting action
+    pub async fn vpc_evaluate_firewall_rules(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        params: &params::VpcFirewallRuleEvaluate,
+    ) -> LookupResult<views::VpcFirewallRuleEvaluation> {
+        let (.., authz_vpc, db_vpc) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .fetch_for(authz::Action::Read)
+                .await?;
+        let source = self
+            .vpc_resolve_flow_host(opctx, &authz_vpc, &db_vpc, &params.source)
+            .await?;
+        let destination = self
+            .vpc_resolve_flow_host(
+                opctx,
+                &authz_vpc,
+                &db_vpc,
+                &params.destination,
+            )
+            .await?;
+        let (target, remote) = match params.direction {
+            external::VpcFirewallRuleDirection::Inbound => {
+                (destination, source)
+            }
+            external::VpcFirewallRuleDirection::Outbound => {
+                (source, destination)
+            }
+        };
+        let target_interface = match target.interface {
+            Some(interface) => interface,
+            None => {
+                return Err(Error::invalid_request(&format!(
+                    "{} is not an interface in VPC \"{}\"",
+                    target.ip,
+                    db_vpc.name(),
+                )))
+            }
+        };
+
+        let rules: Vec<external::VpcFirewallRule> = self
+            .db_datastore
+            .vpc_list_firewall_rules(opctx, &authz_vpc)
+            .await?
+            .into_iter()
+            .map(|rule| rule.into())
+            .collect();
+
+        // Subnet host filters match on the subnet's address ranges, not just
+        // the interfaces in it.
+        let subnets = rules
+            .iter()
+            .flat_map(|rule| rule.filters.hosts.iter().flatten())
+            .filter_map(|host| match host {
+                external::VpcFirewallRuleHostFilter::Subnet(name) => {
+                    Some(Name::from(name.clone()))
+                }
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let subnet_networks: HashMap<external::Name, Vec<IpNetwork>> = self
+            .db_datastore
+            .resolve_vpc_subnets_to_ip_networks(&db_vpc, subnets)
+            .await?
+            .into_iter()
+            .map(|(name, v)| (name.0, v))
+            .collect();
+
+        // As are the host filters naming VPCs peered with this one.
+        let peers =
+            self.db_datastore.vpc_list_active_peers(db_vpc.id()).await?;
+        let mut peer_networks = self
+            .db_datastore
+            .resolve_vpcs_to_ip_networks(
+                &peers.iter().map(|(_, peer)| peer).collect::<Vec<_>>(),
+            )
+            .await?;
+        let peer_networks = peers
+            .iter()
+            .map(|(_, peer)| {
+                (
+                    peer.name().clone(),
+                    peer_networks.remove(&peer.id()).unwrap_or_default(),
+                )
+            })
+            .collect();
+
+        let flow = FirewallFlow {
+            direction: params.direction,
+            vpc: db_vpc.name().clone(),
+            peer_networks,
+            target_ip: target.ip,
+            target: target_interface,
+            remote,
+            protocol: params.protocol,
+            port: params.port,
+        };
+        let rule = evaluate_firewall_rules(&rules, &subnet_networks, &flow);
+        debug!(
+            opctx.log,
+            "evaluated firewall rules";
+            "flow" => ?flow,
+            "rule" => ?rule.map(|rule| rule.identity.name.clone()),
+        );
+        Ok(match rule {
+            Some(rule) => views::VpcFirewallRuleEvaluation {
+                priority: Some(rule.priority),
+                action: rule.action,
+                rule: Some(rule.clone()),
+            },
+            None => views::VpcFirewallRuleEvaluation {
+                rule: None,
+                priority: None,
+                action: default_firewall_action(params.direction),
+            },
+        })
+    }
+
+    /// Resolve one end of a flow to its IP address and, if it has one, its
+    /// interface in the VPC
+    async fn vpc_resolve_flow_host(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        vpc: &db::model::Vpc,
+        host: &params::VpcFirewallFlowHost,
+    ) -> LookupResult<FlowHost> {
+        let (nic, instance, subnet) = match host {
+            params::VpcFirewallFlowHost::Instance(name) => {
+                let (.., authz_instance) =
+                    LookupPath::new(opctx, &self.db_datastore)
+                        .project_id(vpc.project_id)
+                        .instance_name(&Name::from(name.clone()))
+                        .lookup_for(authz::Action::Read)
+                        .await?;
+                self.db_datastore
+                    .vpc_lookup_instance_network_interface(
+                        opctx,
+                        authz_vpc,
+                        &authz_instance,
+                    )
+                    .await?
+                    .ok_or_else(|| {
+                        Error::invalid_request(&format!(
+                            "instance \"{}\" has no interface in VPC \"{}\"",
+                            name,
+                            vpc.name(),
+                        ))
+                    })?
+            }
+            params::VpcFirewallFlowHost::Ip(ip) => {
+                match self
+                    .db_datastore
+                    .vpc_lookup_network_interface_by_ip(opctx, authz_vpc, *ip)
+                    .await?
+                {
+                    Some(found) => found,
+                    None => return Ok(FlowHost { ip: *ip, interface: None }),
+                }
+            }
+        };
+        Ok(FlowHost {
+            ip: nic.ip.ip(),
+            interface: Some(FlowInterface {
+                instance: instance.name().clone(),
+                subnet: subnet.name().clone(),
+                tags: instance.tags.into_iter().map(|tag| tag.0).collect(),
+            }),
+        })
+    }
+
+    /// Re-resolve a VPC's firewall rules and send them to its sleds
+    ///
+    /// This is needed when the set of instances selected by the rules changes
+    /// without the rules themselves being updated, e.g. when an instance's
+    /// tags change.
+    pub(crate) async fn vpc_resend_firewall_rules(
+        &self,
+        opctx: &OpContext,
+        vpc: &db::model::Vpc,
+    ) -> Result<(), Error> {
+        let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .vpc_id(vpc.id())
+            .lookup_for(authz::Action::Read)
+            .await?;
+        let rules = self
+            .db_datastore
+            .vpc_list_firewall_rules(opctx, &authz_vpc)
+            .await?;
+        self.send_sled_agents_firewall_rules(opctx, vpc, &rules).await
+    }
+
+    /// Send a VPC's firewall rules and routes to its sleds after the set of
+    /// instances or network interfaces in it has changed
+    ///
+    /// The change has already been made by the time this is called, so
+    /// failures are logged rather than returned. The periodic reconciliation
+    /// pass will correct any sled that missed the update.
+    pub(crate) async fn vpc_membership_changed(
+        &self,
+        opctx: &OpContext,
+        vpc: &db::model::Vpc,
+    ) {
+        if let Err(error) = self.vpc_resend_firewall_rules(opctx, vpc).await {
+            warn!(
+                self.log,
+                "failed to propagate firewall rules";
+                "vpc_id" => %vpc.id(),
+                "error" => %error,
+            );
+        }
+        if let Err(error) = self.vpc_resend_routes(opctx, vpc).await {
+            warn!(
+                self.log,
+                "failed to propagate routes";
+                "vpc_id" => %vpc.id(),
+                "error" => %error,
+            );
+        }
+    }
+
+    /// Re-send the firewall rules and routes of every VPC to the sleds
+    /// hosting it
+    ///
+    /// This is run periodically in the background to converge sleds on the
+    /// rules resolved from the current state of the database.
+    pub async fn vpc_firewall_rules_reconcile(&self) -> Result<(), Error> {
+        let opctx = self.opctx_for_firewall_reconciler();
+        let limit = NonZeroU32::new(100).unwrap();
+        let mut marker = None;
+        let mut nvpcs = 0;
+        let mut nfailed = 0;
+        loop {
+            let pagparams = DataPageParams {
+                marker: marker.as_ref(),
+                direction: PaginationOrder::Ascending,
+                limit,
+            };
+            let vpcs =
+                self.db_datastore.vpc_list_all(&opctx, &pagparams).await?;
+            for vpc in &vpcs {
+                if let Err(error) =
+                    self.vpc_resend_firewall_rules(&opctx, vpc).await
+                {
+                    warn!(
+                        opctx.log,
+                        "failed to reconcile firewall rules";
+                        "vpc_id" => %vpc.id(),
+                        "error" => %error,
+                    );
+                    nfailed += 1;
+                } else if let Err(error) =
+                    self.vpc_resend_routes(&opctx, vpc).await
+                {
+                    warn!(
+                        opctx.log,
+                        "failed to reconcile routes";
+                        "vpc_id" => %vpc.id(),
+                        "error" => %error,
+                    );
+                    nfailed += 1;
+                }
+            }
+            nvpcs += vpcs.len();
+            match vpcs.last() {
+                Some(vpc) if vpcs.len() == limit.get() as usize => {
+                    marker = Some(vpc.id());
+                }
+                _ => break,
+            }
+        }
+        info!(
+            opctx.log,
+            "reconciled firewall rules";
+            "vpcs" => nvpcs,
+            "failed" => nfailed,
+        );
+        Ok(())
+    }
+
     /// Customize the default firewall rules for a particular VPC
     /// by replacing the name `default` with the VPC's actual name.
     async fn default_firewall_rules_for_vpc(
@@ -463,6 +821,23 @@ impl super::Nexus {
         let mut instances: HashSet<Name> = HashSet::new();
         let mut subnets: HashSet<Name> = HashSet::new();
         let mut vpcs: HashSet<Name> = HashSet::new();
+        let mut tags: HashSet<Name> = HashSet::new();
+
+        // Host filters may also name VPCs peered with this one, which stand
+        // for all of the peer's addresses.
+        let peers = self.db_datastore.vpc_list_active_peers(vpc.id()).await?;
+        let peer_networks = self
+            .db_datastore
+            .resolve_vpcs_to_ip_networks(
+                &peers.iter().map(|(_, peer)| peer).collect::<Vec<_>>(),
+            )
+            .await?;
+        let peer_networks_by_name: HashMap<&external::Name, &Vec<IpNetwork>> =
+            peers
+                .iter()
+                .map(|(_, peer)| (peer.name(), &peer_networks[&peer.id()]))
+                .collect();
+
         for rule in rules {
             for target in &rule.targets {
                 match &target.0 {
@@ -480,6 +855,9 @@ impl super::Nexus {
                         }
                         vpcs.insert(name.clone().into());
                     }
+                    external::VpcFirewallRuleTarget::Tag(name) => {
+                        tags.insert(name.clone().into());
+                    }
                     external::VpcFirewallRuleTarget::Ip(_)
                     | external::VpcFirewallRuleTarget::IpNet(_) => {
                         vpcs.insert(vpc.name().clone().into());
@@ -495,13 +873,16 @@ impl super::Nexus {
                     external::VpcFirewallRuleHostFilter::Subnet(name) => {
                         subnets.insert(name.clone().into());
                     }
+                    // Other VPCs are resolved through the peerings above. A
+                    // VPC that isn't peered with this one resolves to nothing,
+                    // like any other name that isn't found.
                     external::VpcFirewallRuleHostFilter::Vpc(name) => {
-                        if name != vpc.name() {
-                            return Err(Error::invalid_request(
-                                "cross-VPC firewall host filter unsupported",
-                            ));
+                        if name == vpc.name() {
+                            vpcs.insert(name.clone().into());
                         }
-                        vpcs.insert(name.clone().into());
+                    }
+                    external::VpcFirewallRuleHostFilter::Tag(name) => {
+                        tags.insert(name.clone().into());
                     }
                     // We don't need to resolve anything for Ip(Net)s.
                     external::VpcFirewallRuleHostFilter::Ip(_) => (),
@@ -586,6 +967,29 @@ impl super::Nexus {
             }
         }
 
+        // Tags select instances with interfaces in this VPC only.
+        let mut tag_interfaces: NicMap = HashMap::new();
+        if !tags.is_empty() {
+            let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+                .vpc_id(vpc.id())
+                .lookup_for(authz::Action::ListChildren)
+                .await?;
+            for tag in &tags {
+                for iface in self
+                    .db_datastore
+                    .derive_vpc_tagged_network_interface_info(
+                        opctx, &authz_vpc, tag,
+                    )
+                    .await?
+                {
+                    tag_interfaces
+                        .entry(tag.0.clone())
+                        .or_insert_with(Vec::new)
+                        .push(iface);
+                }
+            }
+        }
+
         let subnet_networks: NetMap = self
             .db_datastore
             .resolve_vpc_subnets_to_ip_networks(vpc, subnets)
@@ -600,6 +1004,7 @@ impl super::Nexus {
             "instance_interfaces" => ?instance_interfaces,
             "vpc_interfaces" => ?vpc_interfaces,
             "subnet_interfaces" => ?subnet_interfaces,
+            "tag_interfaces" => ?tag_interfaces,
             "subnet_networks" => ?subnet_networks,
         );
 
@@ -652,6 +1057,13 @@ impl super::Nexus {
                             .iter()
                             .for_each(&mut push_target_nic);
                     }
+                    external::VpcFirewallRuleTarget::Tag(name) => {
+                        tag_interfaces
+                            .get(&name)
+                            .unwrap_or(&no_interfaces)
+                            .iter()
+                            .for_each(&mut push_target_nic);
+                    }
                     external::VpcFirewallRuleTarget::Ip(addr) => {
                         vpc_interfaces
                             .get(vpc.name())
@@ -709,12 +1121,31 @@ impl super::Nexus {
                                     host_addrs.push(IpNet::from(*subnet));
                                 }
                             }
+                            external::VpcFirewallRuleHostFilter::Tag(name) => {
+                                for interface in tag_interfaces
+                                    .get(&name)
+                                    .unwrap_or(&no_interfaces)
+                                {
+                                    host_addrs.push(IpNet::from(interface.ip))
+                                }
+                            }
                             external::VpcFirewallRuleHostFilter::Ip(addr) => {
                                 host_addrs.push(IpNet::from(*addr))
                             }
                             external::VpcFirewallRuleHostFilter::IpNet(net) => {
                                 host_addrs.push(IpNet::from(*net))
                             }
+                            external::VpcFirewallRuleHostFilter::Vpc(name)
+                                if name != vpc.name() =>
+                            {
+                                for network in peer_networks_by_name
+                                    .get(&name)
+                                    .copied()
+                                    .unwrap_or(&no_networks)
+                                {
+                                    host_addrs.push(IpNet::from(*network));
+                                }
+                            }
                             external::VpcFirewallRuleHostFilter::Vpc(name) => {
                                 for interface in vpc_interfaces
                                     .get(&name)
@@ -763,3 +1194,366 @@ impl super::Nexus {
         Ok(sled_agent_rules)
     }
 }
+
+/// The action taken on flows matching no firewall rule: like OPTE, inbound
+/// traffic is dropped and outbound traffic is allowed.
+fn default_firewall_action(
+    direction: external::VpcFirewallRuleDirection,
+) -> external::VpcFirewallRuleAction {
+    match direction {
+        external::VpcFirewallRuleDirection::Inbound => {
+            external::VpcFirewallRuleAction::Deny
+        }
+        external::VpcFirewallRuleDirection::Outbound => {
+            external::VpcFirewallRuleAction::Allow
+        }
+    }
+}
+
+/// An instance's network interface in a VPC, as seen by firewall rules
+#[derive(Clone, Debug)]
+struct FlowInterface {
+    instance: external::Name,
+    subnet: external::Name,
+    tags: Vec<external::Name>,
+}
+
+/// One end of a flow evaluated against a VPC's firewall rules
+#[derive(Clone, Debug)]
+struct FlowHost {
+    ip: IpAddr,
+    /// The interface in the VPC holding `ip`, if any
+    interface: Option<FlowInterface>,
+}
+
+/// A flow resolved for evaluation against a VPC's firewall rules
+#[derive(Clone, Debug)]
+struct FirewallFlow {
+    direction: external::VpcFirewallRuleDirection,
+    /// The name of the VPC whose rules are evaluated
+    vpc: external::Name,
+    /// The address ranges of the VPCs peered with `vpc`, by name
+    peer_networks: HashMap<external::Name, Vec<IpNetwork>>,
+    /// The address of the interface whose rules are evaluated, i.e., the
+    /// destination of inbound flows and the source of outbound ones
+    target_ip: IpAddr,
+    target: FlowInterface,
+    /// The other end of the flow
+    remote: FlowHost,
+    protocol: external::VpcFirewallRuleProtocol,
+    port: Option<external::L4Port>,
+}
+
+fn ip_net_contains(net: &external::IpNet, ip: IpAddr) -> bool {
+    match (net, ip) {
+        (external::IpNet::V4(net), IpAddr::V4(ip)) => net.contains(ip),
+        (external::IpNet::V6(net), IpAddr::V6(ip)) => net.contains(ip),
+        (_, _) => false,
+    }
+}
+
+/// Return whether a firewall rule applies to a flow
+///
+/// This mirrors how rules are resolved in
+/// `resolve_firewall_rules_for_sled_agent` and applied by OPTE: a rule
+/// without targets applies to every interface, and a rule whose targets or
+/// host filters match nothing applies to no traffic.
+fn firewall_rule_matches(
+    rule: &external::VpcFirewallRule,
+    subnet_networks: &HashMap<external::Name, Vec<IpNetwork>>,
+    flow: &FirewallFlow,
+) -> bool {
+    if rule.status != external::VpcFirewallRuleStatus::Enabled
+        || rule.direction != flow.direction
+    {
+        return false;
+    }
+
+    let target_matches = |target: &external::VpcFirewallRuleTarget| match target
+    {
+        external::VpcFirewallRuleTarget::Vpc(name) => *name == flow.vpc,
+        external::VpcFirewallRuleTarget::Subnet(name) => {
+            *name == flow.target.subnet
+        }
+        external::VpcFirewallRuleTarget::Instance(name) => {
+            *name == flow.target.instance
+        }
+        external::VpcFirewallRuleTarget::Tag(name) => {
+            flow.target.tags.contains(name)
+        }
+        external::VpcFirewallRuleTarget::Ip(addr) => *addr == flow.target_ip,
+        external::VpcFirewallRuleTarget::IpNet(net) => {
+            ip_net_contains(net, flow.target_ip)
+        }
+    };
+    if !rule.targets.is_empty() && !rule.targets.iter().any(target_matches) {
+        return false;
+    }
+
+    let remote = &flow.remote;
+    let host_matches = |host: &external::VpcFirewallRuleHostFilter| match host {
+        external::VpcFirewallRuleHostFilter::Vpc(name) if *name == flow.vpc => {
+            remote.interface.is_some()
+        }
+        external::VpcFirewallRuleHostFilter::Vpc(name) => flow
+            .peer_networks
+            .get(name)
+            .map(|networks| {
+                networks.iter().any(|network| network.contains(remote.ip))
+            })
+            .unwrap_or(false),
+        external::VpcFirewallRuleHostFilter::Subnet(name) => subnet_networks
+            .get(name)
+            .map(|networks| {
+                networks.iter().any(|network| network.contains(remote.ip))
+            })
+            .unwrap_or(false),
+        external::VpcFirewallRuleHostFilter::Instance(name) => remote
+            .interface
+            .as_ref()
+            .map(|iface| iface.instance == *name)
+            .unwrap_or(false),
+        external::VpcFirewallRuleHostFilter::Tag(name) => remote
+            .interface
+            .as_ref()
+            .map(|iface| iface.tags.contains(name))
+            .unwrap_or(false),
+        external::VpcFirewallRuleHostFilter::Ip(addr) => *addr == remote.ip,
+        external::VpcFirewallRuleHostFilter::IpNet(net) => {
+            ip_net_contains(net, remote.ip)
+        }
+    };
+    if let Some(hosts) = &rule.filters.hosts {
+        if !hosts.iter().any(host_matches) {
+            return false;
+        }
+    }
+
+    if let Some(protocols) = &rule.filters.protocols {
+        if !protocols.contains(&flow.protocol) {
+            return false;
+        }
+    }
+
+    // An empty list of ports is treated by OPTE as any port.
+    match &rule.filters.ports {
+        Some(ports) if !ports.is_empty() => match flow.port {
+            Some(port) => ports
+                .iter()
+                .any(|range| range.first <= port && port <= range.last),
+            None => false,
+        },
+        _ => true,
+    }
+}
+
+/// Return the rule deciding the fate of a flow, if any
+///
+/// Rules with lower priority values take precedence. Among matching rules of
+/// the same priority, one denying the flow wins.
+fn evaluate_firewall_rules<'a>(
+    rules: &'a [external::VpcFirewallRule],
+    subnet_networks: &HashMap<external::Name, Vec<IpNetwork>>,
+    flow: &FirewallFlow,
+) -> Option<&'a external::VpcFirewallRule> {
+    rules
+        .iter()
+        .filter(|rule| firewall_rule_matches(rule, subnet_networks, flow))
+        .min_by_key(|rule| {
+            (
+                rule.priority.0,
+                rule.action == external::VpcFirewallRuleAction::Allow,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::evaluate_firewall_rules;
+    use super::FirewallFlow;
+    use super::FlowHost;
+    use super::FlowInterface;
+    use chrono::Utc;
+    use omicron_common::api::external::{
+        IdentityMetadata, L4PortRange, Name, VpcFirewallRule,
+        VpcFirewallRuleAction, VpcFirewallRuleDirection, VpcFirewallRuleFilter,
+        VpcFirewallRuleHostFilter, VpcFirewallRulePriority,
+        VpcFirewallRuleProtocol, VpcFirewallRuleStatus, VpcFirewallRuleTarget,
+    };
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn rule(
+        name: &str,
+        targets: Vec<VpcFirewallRuleTarget>,
+        filters: VpcFirewallRuleFilter,
+        action: VpcFirewallRuleAction,
+        priority: u16,
+    ) -> VpcFirewallRule {
+        VpcFirewallRule {
+            identity: IdentityMetadata {
+                id: Uuid::new_v4(),
+                name: name.parse().unwrap(),
+                description: String::new(),
+                time_created: Utc::now(),
+                time_modified: Utc::now(),
+            },
+            status: VpcFirewallRuleStatus::Enabled,
+            direction: VpcFirewallRuleDirection::Inbound,
+            targets,
+            filters,
+            action,
+            priority: VpcFirewallRulePriority(priority),
+            vpc_id: Uuid::new_v4(),
+        }
+    }
+
+    fn no_filters() -> VpcFirewallRuleFilter {
+        VpcFirewallRuleFilter { hosts: None, protocols: None, ports: None }
+    }
+
+    fn flow(port: u16) -> FirewallFlow {
+        let name = |s: &str| s.parse::<Name>().unwrap();
+        FirewallFlow {
+            direction: VpcFirewallRuleDirection::Inbound,
+            vpc: name("vpc"),
+            peer_networks: HashMap::new(),
+            target_ip: "172.30.0.5".parse().unwrap(),
+            target: FlowInterface {
+                instance: name("db"),
+                subnet: name("default"),
+                tags: vec![name("postgres")],
+            },
+            remote: FlowHost {
+                ip: "172.30.0.6".parse().unwrap(),
+                interface: Some(FlowInterface {
+                    instance: name("web"),
+                    subnet: name("default"),
+                    tags: vec![],
+                }),
+            },
+            protocol: VpcFirewallRuleProtocol::Tcp,
+            port: Some(port.try_into().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_firewall_rules_priority() {
+        let rules = vec![
+            rule(
+                "allow-all",
+                vec![],
+                no_filters(),
+                VpcFirewallRuleAction::Allow,
+                100,
+            ),
+            rule(
+                "deny-all",
+                vec![],
+                no_filters(),
+                VpcFirewallRuleAction::Deny,
+                100,
+            ),
+            rule(
+                "allow-postgres",
+                vec![VpcFirewallRuleTarget::Tag("postgres".parse().unwrap())],
+                VpcFirewallRuleFilter {
+                    hosts: Some(vec![VpcFirewallRuleHostFilter::Instance(
+                        "web".parse().unwrap(),
+                    )]),
+                    protocols: Some(vec![VpcFirewallRuleProtocol::Tcp]),
+                    ports: Some(vec!["5432".parse::<L4PortRange>().unwrap()]),
+                },
+                VpcFirewallRuleAction::Allow,
+                10,
+            ),
+        ];
+        let networks = HashMap::new();
+
+        // The more specific, higher-priority rule wins.
+        let matched =
+            evaluate_firewall_rules(&rules, &networks, &flow(5432)).unwrap();
+        assert_eq!(matched.identity.name.as_str(), "allow-postgres");
+
+        // Among rules of equal priority, denying wins.
+        let matched =
+            evaluate_firewall_rules(&rules, &networks, &flow(22)).unwrap();
+        assert_eq!(matched.identity.name.as_str(), "deny-all");
+    }
+
+    #[test]
+    fn test_evaluate_firewall_rules_filters() {
+        let mut rules = vec![rule(
+            "allow-ssh",
+            vec![VpcFirewallRuleTarget::Instance("db".parse().unwrap())],
+            VpcFirewallRuleFilter {
+                hosts: Some(vec![VpcFirewallRuleHostFilter::IpNet(
+                    "10.0.0.0/8".parse().unwrap(),
+                )]),
+                protocols: None,
+                ports: Some(vec!["22".parse().unwrap()]),
+            },
+            VpcFirewallRuleAction::Allow,
+            10,
+        )];
+        let networks = HashMap::new();
+
+        // The source isn't covered by the host filter.
+        assert!(evaluate_firewall_rules(&rules, &networks, &flow(22)).is_none());
+
+        let mut from_private = flow(22);
+        from_private.remote =
+            FlowHost { ip: "10.1.2.3".parse().unwrap(), interface: None };
+        assert!(
+            evaluate_firewall_rules(&rules, &networks, &from_private).is_some()
+        );
+
+        // Outbound flows and disabled rules don't match inbound rules.
+        let mut outbound = from_private.clone();
+        outbound.direction = VpcFirewallRuleDirection::Outbound;
+        assert!(evaluate_firewall_rules(&rules, &networks, &outbound).is_none());
+        rules[0].status = VpcFirewallRuleStatus::Disabled;
+        assert!(
+            evaluate_firewall_rules(&rules, &networks, &from_private).is_none()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_firewall_rules_peer_vpc() {
+        let rules = vec![rule(
+            "allow-peer",
+            vec![],
+            VpcFirewallRuleFilter {
+                hosts: Some(vec![VpcFirewallRuleHostFilter::Vpc(
+                    "peer".parse().unwrap(),
+                )]),
+                protocols: None,
+                ports: None,
+            },
+            VpcFirewallRuleAction::Allow,
+            10,
+        )];
+        let networks = HashMap::new();
+
+        // The peer VPC's name means nothing until the VPCs are peered.
+        let mut from_peer = flow(22);
+        from_peer.remote =
+            FlowHost { ip: "10.1.2.3".parse().unwrap(), interface: None };
+        assert!(
+            evaluate_firewall_rules(&rules, &networks, &from_peer).is_none()
+        );
+
+        // Once they are, it stands for the peer's address ranges.
+        from_peer.peer_networks.insert(
+            "peer".parse().unwrap(),
+            vec!["10.1.0.0/16".parse().unwrap()],
+        );
+        assert!(
+            evaluate_firewall_rules(&rules, &networks, &from_peer).is_some()
+        );
+        from_peer.remote.ip = "10.2.0.1".parse().unwrap();
+        assert!(
+            evaluate_firewall_rules(&rules, &networks, &from_peer).is_none()
+        );
+    }
+}
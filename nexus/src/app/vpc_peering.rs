@@ -0,0 +1,187 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! VPC peerings
+
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::identity::Resource;
+use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use crate::db::model::VpcPeeringState;
+use crate::external_api::params;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use uuid::Uuid;
+
+impl super::Nexus {
+    pub async fn vpc_list_peerings(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        pagparams: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<db::model::VpcPeering> {
+        let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        self.db_datastore.vpc_list_peerings(opctx, &authz_vpc, pagparams).await
+    }
+
+    pub async fn vpc_peering_fetch(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        peering_name: &Name,
+    ) -> LookupResult<db::model::VpcPeering> {
+        let (.., db_peering) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .vpc_peering_name(peering_name)
+            .fetch()
+            .await?;
+        Ok(db_peering)
+    }
+
+    pub async fn vpc_peering_fetch_by_id(
+        &self,
+        opctx: &OpContext,
+        vpc_peering_id: &Uuid,
+    ) -> LookupResult<db::model::VpcPeering> {
+        let (.., db_peering) = LookupPath::new(opctx, &self.db_datastore)
+            .vpc_peering_id(*vpc_peering_id)
+            .fetch()
+            .await?;
+        Ok(db_peering)
+    }
+
+    /// Check a request to peer a VPC with another VPC in the same Silo.
+    ///
+    /// Peering requires the approval of both projects: a peering would only
+    /// become active once a user able to modify the peer VPC peers it with
+    /// this one in turn.  But peerings can't be created yet, so this only does
+    /// the lookups and authorization checks that creating one will do, leaving
+    /// the caller to report that the operation is not implemented.
+    //
+    // TODO-completeness: Create the peering here once sled agents can forward
+    // traffic between peered VPCs, which requires OPTE to encapsulate traffic
+    // for a VNI other than the port's own.  Until then an active peering would
+    // route nothing.
+    pub async fn vpc_create_peering_check(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        params: &params::VpcPeeringCreate,
+    ) -> Result<(), Error> {
+        let (.., db_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(organization_name)
+            .project_name(project_name)
+            .vpc_name(vpc_name)
+            .fetch_for(authz::Action::CreateChild)
+            .await?;
+        // Organizations are looked up within the caller's Silo, so the peer
+        // VPC is necessarily in the same Silo as this one.
+        let (.., peer_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .organization_name(&Name::from(params.peer_organization.clone()))
+            .project_name(&Name::from(params.peer_project.clone()))
+            .vpc_name(&Name::from(params.peer_vpc.clone()))
+            .fetch()
+            .await?;
+        if peer_vpc.id() == db_vpc.id() {
+            return Err(Error::invalid_request(
+                "a VPC cannot peer with itself",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Delete a VPC's side of a peering, which deactivates the peering
+    pub async fn vpc_delete_peering(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        peering_name: &Name,
+    ) -> DeleteResult {
+        let (.., authz_peering, db_peering) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .vpc_peering_name(peering_name)
+                .fetch_for(authz::Action::Delete)
+                .await?;
+        self.db_datastore
+            .vpc_delete_peering(opctx, &authz_peering, &db_peering)
+            .await?;
+        if db_peering.state == VpcPeeringState::Active {
+            self.vpc_peering_changed(&db_peering).await;
+        }
+        Ok(())
+    }
+
+    // Data plane
+
+    /// Send the firewall rules and routes of both VPCs of a peering to their
+    /// sleds after the peering was activated or deactivated
+    ///
+    /// The caller need not be able to read the peer VPC, so this acts as the
+    /// firewall reconciler, which also corrects any sled missing the update.
+    async fn vpc_peering_changed(&self, peering: &db::model::VpcPeering) {
+        let opctx = self.opctx_for_firewall_reconciler();
+        for vpc_id in [peering.vpc_id, peering.peer_vpc_id] {
+            match LookupPath::new(&opctx, &self.db_datastore)
+                .vpc_id(vpc_id)
+                .fetch()
+                .await
+            {
+                Ok((.., vpc)) => {
+                    self.vpc_membership_changed(&opctx, &vpc).await
+                }
+                Err(error) => warn!(
+                    self.log,
+                    "failed to look up peered VPC";
+                    "vpc_id" => %vpc_id,
+                    "error" => %error,
+                ),
+            }
+        }
+    }
+
+    /// Send the firewall rules and routes of the VPCs peered with a VPC to
+    /// their sleds after the VPC's address ranges have changed
+    pub(crate) async fn vpc_peers_changed(&self, vpc_id: Uuid) {
+        let opctx = self.opctx_for_firewall_reconciler();
+        let peers = match self.db_datastore.vpc_list_active_peers(vpc_id).await
+        {
+            Ok(peers) => peers,
+            Err(error) => {
+                warn!(
+                    self.log,
+                    "failed to list VPC peers";
+                    "vpc_id" => %vpc_id,
+                    "error" => %error,
+                );
+                return;
+            }
+        };
+        for (_, peer) in peers {
+            self.vpc_membership_changed(&opctx, &peer).await;
+        }
+    }
+}
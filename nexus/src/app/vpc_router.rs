@@ -7,22 +7,37 @@
 use crate::authz;
 use crate::context::OpContext;
 use crate::db;
+use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::model::Name;
 use crate::db::model::RouterRoute;
 use crate::db::model::VpcRouter;
 use crate::db::model::VpcRouterKind;
 use crate::external_api::params;
+use futures::future::join_all;
+use ipnetwork::IpNetwork;
+use ipnetwork::Ipv4Network;
+use ipnetwork::Ipv6Network;
+use omicron_common::api::external;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::RouteDestination;
+use omicron_common::api::external::RouteTarget;
 use omicron_common::api::external::RouterRouteCreateParams;
 use omicron_common::api::external::RouterRouteKind;
 use omicron_common::api::external::RouterRouteUpdateParams;
 use omicron_common::api::external::UpdateResult;
+use sled_agent_client::types::IpNet;
+use sled_agent_client::types::NetworkInterface;
+use sled_agent_client::types::VpcRouteTarget;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
 use uuid::Uuid;
 
 impl super::Nexus {
@@ -129,9 +144,8 @@ impl super::Nexus {
             .await
     }
 
-    // TODO: When a router is deleted all its routes should be deleted
-    // TODO: When a router is deleted it should be unassociated w/ any subnets it may be associated with
-    //       or trigger an error
+    /// Delete a custom router, along with its routes. Any VPC Subnets
+    /// associated with the router fall back to the VPC's system router.
     pub async fn vpc_delete_router(
         &self,
         opctx: &OpContext,
@@ -140,7 +154,7 @@ impl super::Nexus {
         vpc_name: &Name,
         router_name: &Name,
     ) -> DeleteResult {
-        let (.., authz_router, db_router) =
+        let (.., authz_vpc, authz_router, db_router) =
             LookupPath::new(opctx, &self.db_datastore)
                 .organization_name(organization_name)
                 .project_name(project_name)
@@ -157,7 +171,8 @@ impl super::Nexus {
                 internal_message: "Cannot delete system router".to_string(),
             });
         }
-        self.db_datastore.vpc_delete_router(opctx, &authz_router).await
+        self.db_datastore.vpc_delete_router(opctx, &authz_router).await?;
+        self.vpc_routes_changed(opctx, &authz_vpc).await
     }
 
     // Routes
@@ -173,13 +188,15 @@ impl super::Nexus {
         kind: &RouterRouteKind,
         params: &RouterRouteCreateParams,
     ) -> CreateResult<db::model::RouterRoute> {
-        let (.., authz_router) = LookupPath::new(opctx, &self.db_datastore)
-            .organization_name(organization_name)
-            .project_name(project_name)
-            .vpc_name(vpc_name)
-            .vpc_router_name(router_name)
-            .lookup_for(authz::Action::CreateChild)
-            .await?;
+        let (.., authz_vpc, authz_router) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .vpc_router_name(router_name)
+                .lookup_for(authz::Action::CreateChild)
+                .await?;
+        self.validate_route_target(opctx, &authz_vpc, &params.target).await?;
         let id = Uuid::new_v4();
         let route = db::model::RouterRoute::new(
             id,
@@ -191,6 +208,7 @@ impl super::Nexus {
             .db_datastore
             .router_create_route(&opctx, &authz_router, route)
             .await?;
+        self.vpc_routes_changed(opctx, &authz_vpc).await?;
         Ok(route)
     }
 
@@ -258,7 +276,7 @@ impl super::Nexus {
         route_name: &Name,
         params: &RouterRouteUpdateParams,
     ) -> UpdateResult<RouterRoute> {
-        let (.., authz_route, db_route) =
+        let (.., authz_vpc, _, authz_route, db_route) =
             LookupPath::new(opctx, &self.db_datastore)
                 .organization_name(organization_name)
                 .project_name(project_name)
@@ -267,8 +285,6 @@ impl super::Nexus {
                 .router_route_name(route_name)
                 .fetch()
                 .await?;
-        // TODO: Write a test for this once there's a way to test it (i.e.
-        // subnets automatically register to the system router table)
         match db_route.kind.0 {
             RouterRouteKind::Custom | RouterRouteKind::Default => (),
             _ => {
@@ -281,9 +297,13 @@ impl super::Nexus {
                 })
             }
         }
-        self.db_datastore
+        self.validate_route_target(opctx, &authz_vpc, &params.target).await?;
+        let route = self
+            .db_datastore
             .router_update_route(&opctx, &authz_route, params.clone().into())
-            .await
+            .await?;
+        self.vpc_routes_changed(opctx, &authz_vpc).await?;
+        Ok(route)
     }
 
     pub async fn router_delete_route(
@@ -295,7 +315,7 @@ impl super::Nexus {
         router_name: &Name,
         route_name: &Name,
     ) -> DeleteResult {
-        let (.., authz_route, db_route) =
+        let (.., authz_vpc, _, authz_route, db_route) =
             LookupPath::new(opctx, &self.db_datastore)
                 .organization_name(organization_name)
                 .project_name(project_name)
@@ -313,6 +333,299 @@ impl super::Nexus {
                     .to_string(),
             });
         }
-        self.db_datastore.router_delete_route(opctx, &authz_route).await
+        self.db_datastore.router_delete_route(opctx, &authz_route).await?;
+        self.vpc_routes_changed(opctx, &authz_vpc).await
+    }
+
+    // Data plane
+
+    /// Send the routes of a VPC to its sleds after they have been modified
+    ///
+    /// The modification is already committed by the time this is called.  If
+    /// a sled can't apply it, the error is still returned, so that the change
+    /// isn't reported as having taken effect when it hasn't.  The periodic
+    /// reconciliation pass keeps retrying it.
+    pub(crate) async fn vpc_routes_changed(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+    ) -> Result<(), Error> {
+        let (.., db_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .vpc_id(authz_vpc.id())
+            .fetch()
+            .await?;
+        self.vpc_resend_routes(opctx, &db_vpc).await
+    }
+
+    /// Re-resolve the routes of all of a VPC's routers and send them to its
+    /// sleds
+    pub(crate) async fn vpc_resend_routes(
+        &self,
+        opctx: &OpContext,
+        vpc: &db::model::Vpc,
+    ) -> Result<(), Error> {
+        let routes = self.resolve_routes_for_sled_agent(opctx, vpc).await?;
+        debug!(self.log, "resolved {} routes for sleds", routes.len());
+        let sled_routes_request =
+            sled_agent_client::types::VpcRoutesEnsureBody {
+                routes,
+                vni: vpc.vni.0.into(),
+            };
+
+        let vpc_to_sleds =
+            self.db_datastore.vpc_resolve_to_sleds(vpc.id()).await?;
+        let mut sled_requests = Vec::with_capacity(vpc_to_sleds.len());
+        for sled in &vpc_to_sleds {
+            let sled_id = sled.id();
+            let vpc_id = vpc.id();
+            let sled_routes_request = sled_routes_request.clone();
+            sled_requests.push(async move {
+                self.sled_client(&sled_id)
+                    .await?
+                    .vpc_routes_put(&vpc_id, &sled_routes_request)
+                    .await
+                    .map_err(|e| Error::internal_error(&e.to_string()))
+            });
+        }
+
+        let results = join_all(sled_requests).await;
+        for (sled, result) in vpc_to_sleds.iter().zip(results) {
+            if let Err(e) = result {
+                warn!(self.log, "failed to update routes on sled agent";
+                      "sled_id" => %sled.id(),
+                      "vpc_id" => %vpc.id(),
+                      "error" => %e);
+                return Err(e);
+            }
+        }
+        info!(self.log, "updated routes on {} sleds", vpc_to_sleds.len());
+
+        Ok(())
+    }
+
+    /// Resolve the names in the routes of a VPC's routers to the addresses,
+    /// subnets, and network interfaces they refer to.
+    ///
+    /// Routes of the system router apply to every interface in the VPC, while
+    /// routes of a custom router apply only to the interfaces in the VPC
+    /// Subnets associated with it. Routes whose destination or target cannot
+    /// be resolved, or that apply to no interfaces, are skipped.
+    pub(crate) async fn resolve_routes_for_sled_agent(
+        &self,
+        opctx: &OpContext,
+        vpc: &db::model::Vpc,
+    ) -> Result<Vec<sled_agent_client::types::VpcRoute>, Error> {
+        let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+            .vpc_id(vpc.id())
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        let routes =
+            self.db_datastore.vpc_list_all_routes(opctx, &authz_vpc).await?;
+
+        let mut router_interfaces: HashMap<Uuid, Vec<NetworkInterface>> =
+            HashMap::new();
+        let mut subnets: HashSet<Name> = HashSet::new();
+        let mut instances: HashSet<Name> = HashSet::new();
+        for route in &routes {
+            if !router_interfaces.contains_key(&route.vpc_router_id) {
+                let interfaces = if route.vpc_router_id == vpc.system_router_id
+                {
+                    self.db_datastore
+                        .derive_vpc_network_interface_info(opctx, &authz_vpc)
+                        .await?
+                } else {
+                    self.db_datastore
+                        .derive_router_network_interface_info(
+                            opctx,
+                            &authz_vpc,
+                            route.vpc_router_id,
+                        )
+                        .await?
+                };
+                router_interfaces.insert(route.vpc_router_id, interfaces);
+            }
+            if let RouteDestination::Subnet(name) = &route.destination.0 {
+                subnets.insert(name.clone().into());
+            }
+            match &route.target.0 {
+                RouteTarget::Subnet(name) => {
+                    subnets.insert(name.clone().into());
+                }
+                RouteTarget::Instance(name) => {
+                    instances.insert(name.clone().into());
+                }
+                _ => (),
+            }
+        }
+
+        // Instance targets forward traffic to the address of the instance's
+        // primary interface in this VPC.
+        let mut instance_ips: HashMap<external::Name, IpAddr> = HashMap::new();
+        for instance_name in &instances {
+            if let Ok((.., authz_instance)) =
+                LookupPath::new(opctx, &self.db_datastore)
+                    .project_id(vpc.project_id)
+                    .instance_name(instance_name)
+                    .lookup_for(authz::Action::Read)
+                    .await
+            {
+                if let Some((nic, ..)) = self
+                    .db_datastore
+                    .vpc_lookup_instance_network_interface(
+                        opctx,
+                        &authz_vpc,
+                        &authz_instance,
+                    )
+                    .await?
+                {
+                    instance_ips.insert(instance_name.0.clone(), nic.ip.ip());
+                }
+            }
+        }
+
+        let subnet_networks: HashMap<external::Name, Vec<IpNetwork>> = self
+            .db_datastore
+            .resolve_vpc_subnets_to_ip_networks(vpc, subnets)
+            .await?
+            .into_iter()
+            .map(|(name, v)| (name.0, v))
+            .collect();
+        let no_networks: Vec<IpNetwork> = Vec::new();
+
+        // Routes to peered VPCs are resolved through the peerings: the routes
+        // the system maintains by the peering they're named after, and custom
+        // routes by the peer VPC's name.
+        let peers = self.db_datastore.vpc_list_active_peers(vpc.id()).await?;
+        let peer_networks = self
+            .db_datastore
+            .resolve_vpcs_to_ip_networks(
+                &peers.iter().map(|(_, peer)| peer).collect::<Vec<_>>(),
+            )
+            .await?;
+        let peers_by_route: HashMap<external::Name, &db::model::Vpc> = peers
+            .iter()
+            .map(|(peering, peer)| {
+                (RouterRoute::vpc_peering_route_name(peering.id()), peer)
+            })
+            .collect();
+        let peers_by_name: HashMap<&external::Name, &db::model::Vpc> =
+            peers.iter().map(|(_, peer)| (peer.name(), peer)).collect();
+
+        let gateways: HashSet<external::Name> = self
+            .db_datastore
+            .resolve_vpc_internet_gateways(vpc.id())
+            .await?
+            .into_iter()
+            .map(|gateway| gateway.name().clone())
+            .collect();
+
+        let mut sled_agent_routes = Vec::with_capacity(routes.len());
+        for route in &routes {
+            let interfaces = &router_interfaces[&route.vpc_router_id];
+            if interfaces.is_empty() {
+                continue;
+            }
+            let peer = if route.kind.0 == RouterRouteKind::VpcPeering {
+                peers_by_route.get(route.name()).copied()
+            } else {
+                None
+            };
+
+            let dests: Vec<IpNetwork> = match &route.destination.0 {
+                // The default route of a VPC catches all traffic that no more
+                // specific route matches, whatever its nominal destination.
+                _ if route.kind.0 == RouterRouteKind::Default => vec![
+                    IpNetwork::V4(
+                        Ipv4Network::new(Ipv4Addr::UNSPECIFIED, 0).unwrap(),
+                    ),
+                    IpNetwork::V6(
+                        Ipv6Network::new(Ipv6Addr::UNSPECIFIED, 0).unwrap(),
+                    ),
+                ],
+                _ if route.kind.0 == RouterRouteKind::VpcPeering => {
+                    match peer {
+                        Some(peer) => peer_networks[&peer.id()].clone(),
+                        None => continue,
+                    }
+                }
+                RouteDestination::Ip(ip) => vec![IpNetwork::from(*ip)],
+                RouteDestination::IpNet(net) => vec![IpNetwork::from(*net)],
+                // VPCs have no IPv4 range of their own, only the ranges of
+                // their subnets.
+                RouteDestination::Vpc(name) if name == vpc.name() => {
+                    vec![IpNetwork::V6(vpc.ipv6_prefix.0 .0)]
+                }
+                RouteDestination::Vpc(name) => match peers_by_name.get(name) {
+                    Some(peer) => peer_networks[&peer.id()].clone(),
+                    None => continue,
+                },
+                RouteDestination::Subnet(name) => {
+                    subnet_networks.get(name).unwrap_or(&no_networks).clone()
+                }
+            };
+
+            // Routes refer to gateways by name, and the named gateway may not
+            // exist. The route can't be programmed, but say so rather than
+            // dropping it quietly.
+            if let RouteTarget::InternetGateway(name) = &route.target.0 {
+                if !gateways.contains(name) {
+                    warn!(
+                        self.log,
+                        "skipping route to nonexistent internet gateway";
+                        "vpc_id" => %vpc.id(),
+                        "route" => %route.name(),
+                        "gateway" => %name,
+                    );
+                    continue;
+                }
+            }
+
+            for dest in dests {
+                let target = match &route.target.0 {
+                    RouteTarget::Ip(ip) => VpcRouteTarget::Ip(*ip),
+                    RouteTarget::Instance(name) => match instance_ips.get(name)
+                    {
+                        Some(ip) => VpcRouteTarget::Ip(*ip),
+                        None => continue,
+                    },
+                    // Forward to the subnet's range of the same IP version
+                    // as the destination.
+                    RouteTarget::Subnet(name) => match subnet_networks
+                        .get(name)
+                        .unwrap_or(&no_networks)
+                        .iter()
+                        .find(|net| net.is_ipv4() == dest.is_ipv4())
+                    {
+                        Some(net) => {
+                            VpcRouteTarget::VpcSubnet(IpNet::from(*net))
+                        }
+                        None => continue,
+                    },
+                    RouteTarget::InternetGateway(_) => {
+                        VpcRouteTarget::InternetGateway
+                    }
+                    RouteTarget::Vpc(name) => match peer
+                        .or_else(|| peers_by_name.get(name).copied())
+                    {
+                        Some(peer) => {
+                            VpcRouteTarget::VpcPeering(peer.vni.0.into())
+                        }
+                        None => continue,
+                    },
+                };
+                sled_agent_routes.push(sled_agent_client::types::VpcRoute {
+                    dest: IpNet::from(dest),
+                    target,
+                    interfaces: interfaces.clone(),
+                });
+            }
+        }
+        debug!(
+            self.log,
+            "resolved routes for sled agents";
+            "sled_agent_routes" => ?sled_agent_routes,
+        );
+
+        Ok(sled_agent_routes)
     }
 }
@@ -10,9 +10,11 @@ use crate::db;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::model::Name;
+use crate::db::model::VpcRouterKind;
 use crate::db::model::VpcSubnet;
 use crate::db::queries::vpc_subnet::SubnetError;
 use crate::external_api::params;
+use ipnetwork::IpNetwork;
 use nexus_defaults as defaults;
 use omicron_common::api::external;
 use omicron_common::api::external::CreateResult;
@@ -24,8 +26,6 @@ use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
 impl super::Nexus {
-    // TODO: When a subnet is created it should add a route entry into the VPC's
-    // system router
     pub async fn vpc_create_subnet(
         &self,
         opctx: &OpContext,
@@ -62,6 +62,35 @@ impl super::Nexus {
             )));
         }
 
+        // Traffic is routed between peered VPCs by address, so the new range
+        // can't overlap those of any peer.
+        //
+        // TODO-correctness: This isn't transactional with the insert below,
+        // nor with peerings being established concurrently.
+        let peers =
+            self.db_datastore.vpc_list_active_peers(db_vpc.id()).await?;
+        let peer_networks = self
+            .db_datastore
+            .resolve_vpcs_to_ip_networks(
+                &peers.iter().map(|(_, peer)| peer).collect::<Vec<_>>(),
+            )
+            .await?;
+        let ipv4_block = params.ipv4_block.0;
+        if let Some((_, peer)) = peers.iter().find(|(_, peer)| {
+            peer_networks[&peer.id()].iter().any(|network| match network {
+                IpNetwork::V4(network) => {
+                    network.contains(ipv4_block.network())
+                        || ipv4_block.contains(network.network())
+                }
+                IpNetwork::V6(_) => false,
+            })
+        }) {
+            return Err(external::Error::invalid_request(&format!(
+                "VPC Subnet IPv4 address range overlaps with peered VPC \"{}\"",
+                peer.name(),
+            )));
+        }
+
         // Allocate an ID and insert the record.
         //
         // If the client provided an IPv6 range, we try to insert that or fail
@@ -77,7 +106,7 @@ impl super::Nexus {
         // See <https://github.com/oxidecomputer/omicron/issues/685> for
         // details.
         let subnet_id = Uuid::new_v4();
-        match params.ipv6_block {
+        let subnet = match params.ipv6_block {
             None => {
                 const NUM_RETRIES: usize = 2;
                 let mut retry = 0;
@@ -180,7 +209,13 @@ impl super::Nexus {
                     .await
                     .map_err(SubnetError::into_external)
             }
-        }
+        }?;
+
+        // The new subnet's route in the system router applies to every
+        // interface in the VPC, and its range is routed to from any peers.
+        self.vpc_peers_changed(db_vpc.id()).await;
+        self.vpc_routes_changed(opctx, &authz_vpc).await?;
+        Ok(subnet)
     }
 
     pub async fn vpc_list_subnets(
@@ -239,20 +274,29 @@ impl super::Nexus {
         subnet_name: &Name,
         params: &params::VpcSubnetUpdate,
     ) -> UpdateResult<VpcSubnet> {
-        let (.., authz_subnet) = LookupPath::new(opctx, &self.db_datastore)
-            .organization_name(organization_name)
-            .project_name(project_name)
-            .vpc_name(vpc_name)
-            .vpc_subnet_name(subnet_name)
-            .lookup_for(authz::Action::Modify)
+        let (.., authz_vpc, authz_subnet, db_subnet) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .vpc_subnet_name(subnet_name)
+                .fetch_for(authz::Action::Modify)
+                .await?;
+        let subnet = self
+            .db_datastore
+            .vpc_update_subnet(
+                &opctx,
+                &authz_subnet,
+                &db_subnet,
+                params.clone().into(),
+            )
             .await?;
-        self.db_datastore
-            .vpc_update_subnet(&opctx, &authz_subnet, params.clone().into())
-            .await
+        if subnet.name() != db_subnet.name() {
+            self.vpc_routes_changed(opctx, &authz_vpc).await?;
+        }
+        Ok(subnet)
     }
 
-    // TODO: When a subnet is deleted it should remove its entry from the VPC's
-    // system router.
     pub async fn vpc_delete_subnet(
         &self,
         opctx: &OpContext,
@@ -261,7 +305,7 @@ impl super::Nexus {
         vpc_name: &Name,
         subnet_name: &Name,
     ) -> DeleteResult {
-        let (.., authz_subnet, db_subnet) =
+        let (.., authz_vpc, authz_subnet, db_subnet) =
             LookupPath::new(opctx, &self.db_datastore)
                 .organization_name(organization_name)
                 .project_name(project_name)
@@ -271,7 +315,74 @@ impl super::Nexus {
                 .await?;
         self.db_datastore
             .vpc_delete_subnet(opctx, &db_subnet, &authz_subnet)
-            .await
+            .await?;
+        self.vpc_peers_changed(authz_vpc.id()).await;
+        self.vpc_routes_changed(opctx, &authz_vpc).await
+    }
+
+    /// Associate a VPC Subnet with a custom router, whose routes then apply
+    /// to the network interfaces in the subnet in addition to those of the
+    /// VPC's system router.
+    pub async fn vpc_subnet_attach_router(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        subnet_name: &Name,
+        router_name: &Name,
+    ) -> UpdateResult<VpcSubnet> {
+        let (.., authz_vpc, authz_subnet) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .vpc_subnet_name(subnet_name)
+                .lookup_for(authz::Action::Modify)
+                .await?;
+        let (.., db_router) = LookupPath::new(opctx, &self.db_datastore)
+            .vpc_id(authz_vpc.id())
+            .vpc_router_name(router_name)
+            .fetch()
+            .await?;
+        if db_router.kind == VpcRouterKind::System {
+            return Err(external::Error::invalid_request(
+                "VPC Subnets can only be associated with custom routers",
+            ));
+        }
+        let subnet = self
+            .db_datastore
+            .vpc_subnet_set_custom_router(
+                opctx,
+                &authz_subnet,
+                Some(db_router.id()),
+            )
+            .await?;
+        self.vpc_routes_changed(opctx, &authz_vpc).await?;
+        Ok(subnet)
+    }
+
+    /// Dissociate a VPC Subnet from its custom router, if any
+    pub async fn vpc_subnet_detach_router(
+        &self,
+        opctx: &OpContext,
+        organization_name: &Name,
+        project_name: &Name,
+        vpc_name: &Name,
+        subnet_name: &Name,
+    ) -> DeleteResult {
+        let (.., authz_vpc, authz_subnet) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .organization_name(organization_name)
+                .project_name(project_name)
+                .vpc_name(vpc_name)
+                .vpc_subnet_name(subnet_name)
+                .lookup_for(authz::Action::Modify)
+                .await?;
+        self.db_datastore
+            .vpc_subnet_set_custom_router(opctx, &authz_subnet, None)
+            .await?;
+        self.vpc_routes_changed(opctx, &authz_vpc).await
     }
 
     pub async fn subnet_list_network_interfaces(
@@ -8,6 +8,7 @@ use crate::authz;
 use crate::context::OpContext;
 use crate::db::lookup::LookupPath;
 use crate::db::{model, DataStore};
+use omicron_common::api::external::Error;
 use omicron_common::api::external::LookupResult;
 
 use anyhow::{anyhow, Result};
@@ -21,7 +22,14 @@ use samael::service_provider::ServiceProvider;
 use samael::service_provider::ServiceProviderBuilder;
 
 use dropshot::HttpError;
+use jsonwebtoken::Algorithm;
+use jsonwebtoken::DecodingKey;
+use jsonwebtoken::Validation;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use std::time::Instant;
+use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct SamlIdentityProvider {
@@ -66,6 +74,7 @@ impl TryFrom<model::SamlIdentityProvider> for SamlIdentityProvider {
 
 pub enum IdentityProviderType {
     Saml(SamlIdentityProvider),
+    Oidc(OidcIdentityProvider),
 }
 
 impl IdentityProviderType {
@@ -115,6 +124,21 @@ impl IdentityProviderType {
 
                 Ok((authz_silo, db_silo, saml_identity_provider))
             }
+
+            model::IdentityProviderType::Oidc => {
+                let (.., oidc_identity_provider) =
+                    LookupPath::new(opctx, datastore)
+                        .silo_name(silo_name)
+                        .oidc_identity_provider_name(provider_name)
+                        .fetch()
+                        .await?;
+
+                Ok((
+                    authz_silo,
+                    db_silo,
+                    IdentityProviderType::Oidc(oidc_identity_provider.into()),
+                ))
+            }
         }
     }
 }
@@ -444,3 +468,406 @@ pub struct AuthenticatedSubject {
     pub external_id: String,
     pub groups: Vec<String>,
 }
+
+/// How long to wait for an OpenID provider to respond
+const OIDC_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long an OpenID provider's signing keys are cached
+const OIDC_JWKS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// How often an OpenID provider's signing keys may be fetched again, when an
+/// ID token is signed by a key that isn't cached
+const OIDC_JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+fn oidc_http_client() -> Result<reqwest::Client, Error> {
+    reqwest::ClientBuilder::new()
+        .connect_timeout(OIDC_REQUEST_TIMEOUT)
+        .timeout(OIDC_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| {
+            Error::internal_error(&format!(
+                "failed to build reqwest client: {}",
+                e
+            ))
+        })
+}
+
+/// The parts of an OpenID provider's discovery document that Nexus uses
+///
+/// See "OpenID Connect Discovery 1.0", section 3.
+#[derive(Debug, Deserialize)]
+pub struct OidcDiscoveryDocument {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+impl OidcDiscoveryDocument {
+    /// Fetch the discovery document of the OpenID provider identified by
+    /// `issuer`
+    pub async fn fetch(issuer: &str) -> Result<Self, Error> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let invalid = |message: String| Error::InvalidValue {
+            label: String::from("issuer"),
+            message,
+        };
+
+        let response =
+            oidc_http_client()?.get(&url).send().await.map_err(|e| {
+                invalid(format!("error querying {}: {}", url, e))
+            })?;
+        if !response.status().is_success() {
+            return Err(invalid(format!(
+                "querying {} returned: {}",
+                url,
+                response.status()
+            )));
+        }
+        let document: Self = response.json().await.map_err(|e| {
+            invalid(format!("error parsing discovery document: {}", e))
+        })?;
+
+        // The issuer in the document must exactly match the one it was
+        // fetched from, since it's also the issuer of every ID token.
+        if document.issuer != issuer {
+            return Err(invalid(format!(
+                "discovery document names issuer {}",
+                document.issuer
+            )));
+        }
+
+        Ok(document)
+    }
+}
+
+pub struct OidcIdentityProvider {
+    pub id: Uuid,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: Vec<String>,
+    pub redirect_url: String,
+    pub group_claim_name: Option<String>,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+impl From<model::OidcIdentityProvider> for OidcIdentityProvider {
+    fn from(model: model::OidcIdentityProvider) -> Self {
+        OidcIdentityProvider {
+            id: model.identity.id,
+            issuer: model.issuer,
+            client_id: model.client_id,
+            client_secret: model.client_secret,
+            scopes: model.scopes,
+            redirect_url: model.redirect_url,
+            group_claim_name: model.group_claim_name,
+            authorization_endpoint: model.authorization_endpoint,
+            token_endpoint: model.token_endpoint,
+            jwks_uri: model.jwks_uri,
+        }
+    }
+}
+
+/// Response from an OpenID provider's token endpoint
+#[derive(Deserialize)]
+struct OidcTokenResponse {
+    id_token: String,
+}
+
+/// The claims of an ID token that Nexus uses
+///
+/// Expiry, issuer, and audience are checked when the token is decoded.
+#[derive(Deserialize)]
+struct OidcIdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl OidcIdentityProvider {
+    /// Returns the URL of the provider's authorization endpoint to which the
+    /// user is sent to log in (using the authorization code flow)
+    pub fn sign_in_url(&self, state: &str, nonce: &str) -> Result<String> {
+        let scope = std::iter::once("openid")
+            .chain(
+                self.scopes
+                    .iter()
+                    .map(|s| s.as_str())
+                    .filter(|s| *s != "openid"),
+            )
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let url = reqwest::Url::parse_with_params(
+            &self.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", &self.client_id),
+                ("redirect_uri", &self.redirect_url),
+                ("scope", &scope),
+                ("state", state),
+                ("nonce", nonce),
+            ],
+        )?;
+        Ok(url.to_string())
+    }
+
+    /// Exchange an authorization code for an ID token, validate the token, and
+    /// return the subject it identifies.
+    ///
+    /// `nonce` is the value sent to the provider with the authorization
+    /// request, which the ID token must contain.
+    pub async fn authenticated_subject(
+        &self,
+        code: &str,
+        nonce: &str,
+        jwks_cache: &OidcJwksCache,
+    ) -> Result<AuthenticatedSubject, Error> {
+        let unauthenticated = |internal_message: String| {
+            Error::Unauthenticated { internal_message }
+        };
+
+        let response = oidc_http_client()?
+            .post(&self.token_endpoint)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.redirect_url),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                Error::unavail(&format!("error querying token endpoint: {}", e))
+            })?;
+        if !response.status().is_success() {
+            return Err(unauthenticated(format!(
+                "token endpoint returned: {}",
+                response.status()
+            )));
+        }
+        let token_response: OidcTokenResponse =
+            response.json().await.map_err(|e| {
+                unauthenticated(format!("error parsing token response: {}", e))
+            })?;
+
+        let claims = self
+            .validate_id_token(&token_response.id_token, jwks_cache)
+            .await?;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(unauthenticated(String::from(
+                "ID token nonce does not match",
+            )));
+        }
+
+        // Extract group membership, which may be given either as a list of
+        // group names or as a single comma separated string of them.
+        let mut groups = vec![];
+        if let Some(group_claim_name) = &self.group_claim_name {
+            let values = match claims.other.get(group_claim_name) {
+                Some(serde_json::Value::Array(values)) => values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .collect::<Vec<&str>>(),
+                Some(serde_json::Value::String(value)) => {
+                    value.split(',').collect()
+                }
+                _ => vec![],
+            };
+            for group in values {
+                let group = group.trim();
+                if !group.is_empty() {
+                    groups.push(group.to_string());
+                }
+            }
+        }
+
+        Ok(AuthenticatedSubject { external_id: claims.sub, groups })
+    }
+
+    /// Check an ID token's signature, expiry, issuer, and audience, and
+    /// return its claims
+    async fn validate_id_token(
+        &self,
+        id_token: &str,
+        jwks_cache: &OidcJwksCache,
+    ) -> Result<OidcIdTokenClaims, Error> {
+        let unauthenticated = |internal_message: String| {
+            Error::Unauthenticated { internal_message }
+        };
+
+        let header = jsonwebtoken::decode_header(id_token).map_err(|e| {
+            unauthenticated(format!("error decoding ID token header: {}", e))
+        })?;
+
+        // Only accept RSA signatures, which every OpenID provider supports.
+        // In particular this rejects "none" and HMAC algorithms, the latter of
+        // which would need the client secret as the key.
+        match header.alg {
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => (),
+            alg => {
+                return Err(unauthenticated(format!(
+                    "ID token signature algorithm {:?} is not allowed",
+                    alg
+                )));
+            }
+        }
+
+        let key = jwks_cache.key(self, header.kid.as_deref()).await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        let token = jsonwebtoken::decode::<OidcIdTokenClaims>(
+            id_token,
+            &key,
+            &validation,
+        )
+        .map_err(|e| unauthenticated(format!("invalid ID token: {}", e)))?;
+        Ok(token.claims)
+    }
+}
+
+/// A JSON Web Key, as found in an OpenID provider's key set
+///
+/// Only the parameters of RSA keys are read.
+#[derive(Clone, Debug, Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    key_use: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+struct CachedJwkSet {
+    time_fetched: Instant,
+    keys: Vec<Jwk>,
+}
+
+/// Cache of the keys with which OpenID providers sign ID tokens
+///
+/// A provider's keys are fetched when first needed, and fetched again once
+/// they're old, or when a token is signed by a key that isn't cached (as
+/// happens when the provider rotates its keys).  To keep bogus tokens from
+/// making Nexus hammer the provider, keys are fetched at most once every
+/// [`OIDC_JWKS_MIN_REFRESH_INTERVAL`].
+pub struct OidcJwksCache {
+    key_sets: std::sync::Mutex<BTreeMap<Uuid, CachedJwkSet>>,
+}
+
+impl OidcJwksCache {
+    pub fn new() -> Self {
+        OidcJwksCache { key_sets: std::sync::Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Returns the provider's key with id `kid`, or the provider's only key
+    /// if `kid` isn't given
+    async fn key(
+        &self,
+        provider: &OidcIdentityProvider,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, Error> {
+        let (cached_key, may_refresh) = {
+            let key_sets = self.key_sets.lock().unwrap();
+            match key_sets.get(&provider.id) {
+                Some(key_set) => {
+                    let age = key_set.time_fetched.elapsed();
+                    let key = if age < OIDC_JWKS_MAX_AGE {
+                        Self::find_key(&key_set.keys, kid)
+                    } else {
+                        None
+                    };
+                    (key, age >= OIDC_JWKS_MIN_REFRESH_INTERVAL)
+                }
+                None => (None, true),
+            }
+        };
+
+        let jwk = match (cached_key, may_refresh) {
+            (Some(jwk), _) => jwk,
+            (None, false) => {
+                return Err(Error::Unauthenticated {
+                    internal_message: format!(
+                        "no signing key {:?} for OpenID provider",
+                        kid
+                    ),
+                });
+            }
+            (None, true) => {
+                let keys = Self::fetch(provider).await?;
+                let jwk = Self::find_key(&keys, kid);
+                self.key_sets.lock().unwrap().insert(
+                    provider.id,
+                    CachedJwkSet { time_fetched: Instant::now(), keys },
+                );
+                jwk.ok_or_else(|| Error::Unauthenticated {
+                    internal_message: format!(
+                        "no signing key {:?} for OpenID provider",
+                        kid
+                    ),
+                })?
+            }
+        };
+
+        match (&jwk.n, &jwk.e) {
+            (Some(n), Some(e)) => DecodingKey::from_rsa_components(n, e)
+                .map_err(|e| {
+                    Error::internal_error(&format!(
+                        "invalid OpenID provider signing key: {}",
+                        e
+                    ))
+                }),
+            _ => Err(Error::internal_error(
+                "OpenID provider signing key is missing RSA parameters",
+            )),
+        }
+    }
+
+    fn find_key(keys: &[Jwk], kid: Option<&str>) -> Option<Jwk> {
+        let mut signing_keys = keys.iter().filter(|jwk| {
+            jwk.kty == "RSA" && jwk.key_use.as_deref().unwrap_or("sig") == "sig"
+        });
+        match kid {
+            Some(kid) => signing_keys
+                .find(|jwk| jwk.kid.as_deref() == Some(kid))
+                .cloned(),
+            None => {
+                let jwk = signing_keys.next();
+                match signing_keys.next() {
+                    None => jwk.cloned(),
+                    // Without a key id, there's no telling which key to use.
+                    Some(_) => None,
+                }
+            }
+        }
+    }
+
+    async fn fetch(provider: &OidcIdentityProvider) -> Result<Vec<Jwk>, Error> {
+        let response =
+            oidc_http_client()?.get(&provider.jwks_uri).send().await.map_err(
+                |e| Error::unavail(&format!("error querying jwks_uri: {}", e)),
+            )?;
+        if !response.status().is_success() {
+            return Err(Error::unavail(&format!(
+                "querying jwks_uri returned: {}",
+                response.status()
+            )));
+        }
+        let key_set: JwkSet = response.json().await.map_err(|e| {
+            Error::unavail(&format!("error parsing key set: {}", e))
+        })?;
+        Ok(key_set.keys)
+    }
+}
@@ -74,6 +74,14 @@ pub trait ApiResource:
     fn resource_type(&self) -> ResourceType;
     fn lookup_type(&self) -> &LookupType;
 
+    /// Returns the unique id of this resource, if it's identified by one
+    ///
+    /// Some resources are identified by other kinds of keys (e.g., names or
+    /// secret tokens).  These return `None`.
+    fn resource_uuid(&self) -> Option<Uuid> {
+        None
+    }
+
     /// Returns an error as though this resource were not found, suitable for
     /// use when an actor should not be able to see that this resource exists
     fn not_found(&self) -> Error {
@@ -140,6 +148,10 @@ impl<T: ApiResource + oso::PolarClass + Clone> AuthorizedResource for T {
     fn polar_class(&self) -> oso::Class {
         Self::get_polar_class()
     }
+
+    fn audit_id(&self) -> Option<Uuid> {
+        self.resource_uuid()
+    }
 }
 
 /// Represents the Oxide fleet for authz purposes
@@ -616,6 +628,241 @@ impl AuthorizedResource for SiloUserList {
     }
 }
 
+/// Synthetic resource describing the list of Groups in a Silo
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SiloGroupList(Silo);
+
+impl SiloGroupList {
+    pub fn new(silo: Silo) -> SiloGroupList {
+        SiloGroupList(silo)
+    }
+
+    pub fn silo(&self) -> &Silo {
+        &self.0
+    }
+}
+
+impl oso::PolarClass for SiloGroupList {
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+            .with_equality_check()
+            .add_attribute_getter("silo", |list: &SiloGroupList| list.0.clone())
+    }
+}
+
+impl AuthorizedResource for SiloGroupList {
+    fn load_roles<'a, 'b, 'c, 'd, 'e, 'f>(
+        &'a self,
+        opctx: &'b OpContext,
+        datastore: &'c DataStore,
+        authn: &'d authn::Context,
+        roleset: &'e mut RoleSet,
+    ) -> futures::future::BoxFuture<'f, Result<(), Error>>
+    where
+        'a: 'f,
+        'b: 'f,
+        'c: 'f,
+        'd: 'f,
+        'e: 'f,
+    {
+        // There are no roles on this resource, but we still need to load the
+        // Silo-related roles.
+        self.silo().load_roles(opctx, datastore, authn, roleset)
+    }
+
+    fn on_unauthorized(
+        &self,
+        _: &Authz,
+        error: Error,
+        _: AnyActor,
+        _: Action,
+    ) -> Error {
+        error
+    }
+
+    fn polar_class(&self) -> oso::Class {
+        Self::get_polar_class()
+    }
+}
+
+/// Synthetic resource describing the audit log of requests made to the
+/// external API
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuditLog;
+/// Singleton representing the [`AuditLog`] itself for authz purposes
+pub const AUDIT_LOG: AuditLog = AuditLog;
+
+impl oso::PolarClass for AuditLog {
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+            .with_equality_check()
+            .add_attribute_getter("fleet", |_| FLEET)
+    }
+}
+
+impl AuthorizedResource for AuditLog {
+    fn load_roles<'a, 'b, 'c, 'd, 'e, 'f>(
+        &'a self,
+        opctx: &'b OpContext,
+        datastore: &'c DataStore,
+        authn: &'d authn::Context,
+        roleset: &'e mut RoleSet,
+    ) -> futures::future::BoxFuture<'f, Result<(), Error>>
+    where
+        'a: 'f,
+        'b: 'f,
+        'c: 'f,
+        'd: 'f,
+        'e: 'f,
+    {
+        // There are no roles on the AuditLog, only permissions.  But we still
+        // need to load the Fleet-related roles.
+        load_roles_for_resource(
+            opctx,
+            datastore,
+            authn,
+            ResourceType::Fleet,
+            *FLEET_ID,
+            roleset,
+        )
+        .boxed()
+    }
+
+    fn on_unauthorized(
+        &self,
+        _: &Authz,
+        error: Error,
+        _: AnyActor,
+        _: Action,
+    ) -> Error {
+        error
+    }
+
+    fn polar_class(&self) -> oso::Class {
+        Self::get_polar_class()
+    }
+}
+
+/// Synthetic resource describing the part of the audit log made up of requests
+/// from clients in one Silo
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SiloAuditLog(Silo);
+
+impl SiloAuditLog {
+    pub fn new(silo: Silo) -> SiloAuditLog {
+        SiloAuditLog(silo)
+    }
+
+    pub fn silo(&self) -> &Silo {
+        &self.0
+    }
+}
+
+impl oso::PolarClass for SiloAuditLog {
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+            .with_equality_check()
+            .add_attribute_getter("silo", |log: &SiloAuditLog| log.0.clone())
+    }
+}
+
+impl AuthorizedResource for SiloAuditLog {
+    fn load_roles<'a, 'b, 'c, 'd, 'e, 'f>(
+        &'a self,
+        opctx: &'b OpContext,
+        datastore: &'c DataStore,
+        authn: &'d authn::Context,
+        roleset: &'e mut RoleSet,
+    ) -> futures::future::BoxFuture<'f, Result<(), Error>>
+    where
+        'a: 'f,
+        'b: 'f,
+        'c: 'f,
+        'd: 'f,
+        'e: 'f,
+    {
+        // There are no roles on this resource, but we still need to load the
+        // Silo-related roles.
+        self.silo().load_roles(opctx, datastore, authn, roleset)
+    }
+
+    fn on_unauthorized(
+        &self,
+        _: &Authz,
+        error: Error,
+        _: AnyActor,
+        _: Action,
+    ) -> Error {
+        error
+    }
+
+    fn polar_class(&self) -> oso::Class {
+        Self::get_polar_class()
+    }
+}
+
+/// Synthetic resource describing the quotas of a Silo
+///
+/// Silo administrators can see the quotas of their Silo, but only Fleet
+/// administrators can change them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SiloQuotas(Silo);
+
+impl SiloQuotas {
+    pub fn new(silo: Silo) -> SiloQuotas {
+        SiloQuotas(silo)
+    }
+
+    pub fn silo(&self) -> &Silo {
+        &self.0
+    }
+}
+
+impl oso::PolarClass for SiloQuotas {
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+            .with_equality_check()
+            .add_attribute_getter("silo", |quotas: &SiloQuotas| {
+                quotas.0.clone()
+            })
+    }
+}
+
+impl AuthorizedResource for SiloQuotas {
+    fn load_roles<'a, 'b, 'c, 'd, 'e, 'f>(
+        &'a self,
+        opctx: &'b OpContext,
+        datastore: &'c DataStore,
+        authn: &'d authn::Context,
+        roleset: &'e mut RoleSet,
+    ) -> futures::future::BoxFuture<'f, Result<(), Error>>
+    where
+        'a: 'f,
+        'b: 'f,
+        'c: 'f,
+        'd: 'f,
+        'e: 'f,
+    {
+        // There are no roles on this resource, but we still need to load the
+        // Silo-related roles.
+        self.silo().load_roles(opctx, datastore, authn, roleset)
+    }
+
+    fn on_unauthorized(
+        &self,
+        _: &Authz,
+        error: Error,
+        _: AnyActor,
+        _: Action,
+    ) -> Error {
+        error
+    }
+
+    fn polar_class(&self) -> oso::Class {
+        Self::get_polar_class()
+    }
+}
+
 // Main resource hierarchy: Organizations, Projects, and their resources
 
 authz_resource! {
@@ -731,14 +978,138 @@ impl db::model::DatabaseString for ProjectRole {
     }
 }
 
+/// Synthetic resource describing the quotas of a Project
+///
+/// Anybody who can see the Project can see its quotas, but only
+/// administrators of the Organization containing it can change them.  Project
+/// administrators cannot raise their own quotas.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProjectQuotas(Project);
+
+impl ProjectQuotas {
+    pub fn new(project: Project) -> ProjectQuotas {
+        ProjectQuotas(project)
+    }
+
+    pub fn project(&self) -> &Project {
+        &self.0
+    }
+}
+
+impl oso::PolarClass for ProjectQuotas {
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+            .with_equality_check()
+            .add_attribute_getter("project", |quotas: &ProjectQuotas| {
+                quotas.0.clone()
+            })
+    }
+}
+
+impl AuthorizedResource for ProjectQuotas {
+    fn load_roles<'a, 'b, 'c, 'd, 'e, 'f>(
+        &'a self,
+        opctx: &'b OpContext,
+        datastore: &'c DataStore,
+        authn: &'d authn::Context,
+        roleset: &'e mut RoleSet,
+    ) -> futures::future::BoxFuture<'f, Result<(), Error>>
+    where
+        'a: 'f,
+        'b: 'f,
+        'c: 'f,
+        'd: 'f,
+        'e: 'f,
+    {
+        // There are no roles on this resource, but we still need to load the
+        // Project-related roles.
+        self.project().load_roles(opctx, datastore, authn, roleset)
+    }
+
+    fn on_unauthorized(
+        &self,
+        _: &Authz,
+        error: Error,
+        _: AnyActor,
+        _: Action,
+    ) -> Error {
+        error
+    }
+
+    fn polar_class(&self) -> oso::Class {
+        Self::get_polar_class()
+    }
+}
+
+/// Defines the roles for a resource that can be operated and viewed
+/// independently of its Project, with the usual derives and a
+/// [`db::model::DatabaseString`] impl
+///
+/// `$label` names the resource in errors about unknown role names.
+macro_rules! operator_viewer_role {
+    ($role:ident, $label:literal) => {
+        #[derive(
+            Clone,
+            Copy,
+            Debug,
+            Deserialize,
+            Display,
+            Eq,
+            FromStr,
+            PartialEq,
+            Serialize,
+            JsonSchema,
+        )]
+        #[cfg_attr(test, derive(EnumIter))]
+        #[display(style = "kebab-case")]
+        #[serde(rename_all = "snake_case")]
+        pub enum $role {
+            Operator,
+            Viewer,
+        }
+
+        impl db::model::DatabaseString for $role {
+            type Error = anyhow::Error;
+
+            fn to_database_string(&self) -> &str {
+                match self {
+                    $role::Operator => "operator",
+                    $role::Viewer => "viewer",
+                }
+            }
+
+            fn from_database_string(s: &str) -> Result<Self, Self::Error> {
+                match s {
+                    "operator" => Ok($role::Operator),
+                    "viewer" => Ok($role::Viewer),
+                    _ => Err(anyhow!(
+                        concat!(
+                            "unsupported ",
+                            $label,
+                            " role from database: {:?}"
+                        ),
+                        s
+                    )),
+                }
+            }
+        }
+    };
+}
+
 authz_resource! {
     name = "Disk",
     parent = "Project",
     primary_key = Uuid,
-    roles_allowed = false,
-    polar_snippet = InProject,
+    roles_allowed = true,
+    polar_snippet = Custom,
+}
+
+impl ApiResourceWithRolesType for Disk {
+    type AllowedRoles = DiskRole;
 }
 
+operator_viewer_role!(DiskRole, "Disk");
+
 authz_resource! {
     name = "Image",
     parent = "Project",
@@ -756,13 +1127,27 @@ authz_resource! {
 }
 
 authz_resource! {
-    name = "Instance",
+    name = "FloatingIp",
     parent = "Project",
     primary_key = Uuid,
     roles_allowed = false,
     polar_snippet = InProject,
 }
 
+authz_resource! {
+    name = "Instance",
+    parent = "Project",
+    primary_key = Uuid,
+    roles_allowed = true,
+    polar_snippet = Custom,
+}
+
+impl ApiResourceWithRolesType for Instance {
+    type AllowedRoles = InstanceRole;
+}
+
+operator_viewer_role!(InstanceRole, "Instance");
+
 authz_resource! {
     name = "NetworkInterface",
     parent = "Instance",
@@ -775,10 +1160,16 @@ authz_resource! {
     name = "Vpc",
     parent = "Project",
     primary_key = Uuid,
-    roles_allowed = false,
-    polar_snippet = InProject,
+    roles_allowed = true,
+    polar_snippet = Custom,
+}
+
+impl ApiResourceWithRolesType for Vpc {
+    type AllowedRoles = VpcRole;
 }
 
+operator_viewer_role!(VpcRole, "VPC");
+
 authz_resource! {
     name = "VpcRouter",
     parent = "Vpc",
@@ -803,6 +1194,22 @@ authz_resource! {
     polar_snippet = InProject,
 }
 
+authz_resource! {
+    name = "VpcPeering",
+    parent = "Vpc",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = InProject,
+}
+
+authz_resource! {
+    name = "InternetGateway",
+    parent = "Vpc",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = InProject,
+}
+
 // Miscellaneous resources nested directly below "Fleet"
 
 authz_resource! {
@@ -939,6 +1346,14 @@ authz_resource! {
     polar_snippet = Custom,
 }
 
+authz_resource! {
+    name = "OidcIdentityProvider",
+    parent = "Silo",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = Custom,
+}
+
 authz_resource! {
     name = "SshKey",
     parent = "SiloUser",
@@ -947,6 +1362,14 @@ authz_resource! {
     polar_snippet = Custom,
 }
 
+authz_resource! {
+    name = "ApiToken",
+    parent = "SiloUser",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = Custom,
+}
+
 authz_resource! {
     name = "Sled",
     parent = "Fleet",
@@ -18,6 +18,7 @@ use oso::Oso;
 use oso::OsoError;
 use std::collections::BTreeSet;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// Server-wide authorization context
 pub struct Authz {
@@ -189,6 +190,12 @@ pub trait AuthorizedResource: oso::ToPolar + Send + Sync + 'static {
 
     /// Returns the Polar class that implements this resource
     fn polar_class(&self) -> oso::Class;
+
+    /// Returns the id to record in the audit log when a request acts on this
+    /// resource, if it has one
+    fn audit_id(&self) -> Option<Uuid> {
+        None
+    }
 }
 
 #[cfg(test)]
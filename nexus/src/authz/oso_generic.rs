@@ -111,6 +111,11 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         DeviceAuthRequestList::get_polar_class(),
         SiloIdentityProviderList::get_polar_class(),
         SiloUserList::get_polar_class(),
+        SiloGroupList::get_polar_class(),
+        AuditLog::get_polar_class(),
+        SiloAuditLog::get_polar_class(),
+        SiloQuotas::get_polar_class(),
+        ProjectQuotas::get_polar_class(),
     ];
     for c in classes {
         oso_builder = oso_builder.register_class(c)?;
@@ -122,6 +127,7 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         Project::init(),
         Disk::init(),
         Snapshot::init(),
+        FloatingIp::init(),
         Instance::init(),
         IpPool::init(),
         NetworkInterface::init(),
@@ -129,6 +135,8 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         VpcRouter::init(),
         RouterRoute::init(),
         VpcSubnet::init(),
+        VpcPeering::init(),
+        InternetGateway::init(),
         // Fleet-level resources
         ConsoleSession::init(),
         DeviceAuthRequest::init(),
@@ -136,11 +144,13 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         Rack::init(),
         RoleBuiltin::init(),
         SshKey::init(),
+        ApiToken::init(),
         Silo::init(),
         SiloUser::init(),
         SiloGroup::init(),
         IdentityProvider::init(),
         SamlIdentityProvider::init(),
+        OidcIdentityProvider::init(),
         Sled::init(),
         UpdateAvailableArtifact::init(),
         UserBuiltin::init(),
@@ -195,6 +205,8 @@ pub enum Perm {
     Query, // Only for [`Database`]
     Read,
     Modify,
+    ModifyPolicy,
+    Delete,
     ListChildren,
     CreateChild,
 }
@@ -206,8 +218,8 @@ impl From<&Action> for Perm {
             Action::Read => Perm::Read,
             Action::ReadPolicy => Perm::Read,
             Action::Modify => Perm::Modify,
-            Action::ModifyPolicy => Perm::Modify,
-            Action::Delete => Perm::Modify,
+            Action::ModifyPolicy => Perm::ModifyPolicy,
+            Action::Delete => Perm::Delete,
             Action::ListChildren => Perm::ListChildren,
             Action::CreateChild => Perm::CreateChild,
         }
@@ -222,6 +234,8 @@ impl fmt::Display for Perm {
             Perm::Query => "query",
             Perm::Read => "read",
             Perm::Modify => "modify",
+            Perm::ModifyPolicy => "modify_policy",
+            Perm::Delete => "delete",
             Perm::ListChildren => "list_children",
             Perm::CreateChild => "create_child",
         })
@@ -42,13 +42,13 @@ use uuid::Uuid;
 ///
 /// This test constructs a hierarchy of resources, from the Fleet all the way
 /// down to things like Instances and Disks.  For every resource that supports
-/// roles (Fleet, Silo, Organization, and Project), for every supported role, we
-/// create one user that has that role on one of the resources that supports it
-/// (i.e., one "fleet-admin", one "fleet-viewer", one "silo-admin" for one Silo,
-/// etc.).  Then we exhaustively test `authorize()` for all of these users
-/// attempting every possible action on every resource we created.  This tests
-/// not only whether "silo1-admin" has all privileges on "silo1", but also that
-/// they have no privileges on "silo2" or "fleet".
+/// roles (Fleet, Silo, Organization, Project, Instance, Disk, and Vpc), for
+/// every supported role, we create one user that has that role on one of the
+/// resources that supports it (i.e., one "fleet-admin", one "fleet-viewer", one
+/// "silo-admin" for one Silo, etc.).  Then we exhaustively test `authorize()`
+/// for all of these users attempting every possible action on every resource
+/// we created.  This tests not only whether "silo1-admin" has all privileges
+/// on "silo1", but also that they have no privileges on "silo2" or "fleet".
 ///
 /// When we say we create resources in this test, we just create the `authz`
 /// objects needed to do an authz check.  We're not going through the API and we
@@ -217,7 +217,7 @@ async fn authorize_one_resource(
         let mut out = Cursor::new(&mut buffer);
         write!(out, "resource: {}\n\n", resource.resource_name())?;
 
-        write!(out, "  {:31}", "USER")?;
+        write!(out, "  {:37}", "USER")?;
         for action in authz::Action::iter() {
             write!(out, " {:>2}", action_abbreviation(action))?;
         }
@@ -225,7 +225,7 @@ async fn authorize_one_resource(
 
         for ctx_tuple in user_contexts.iter() {
             let (ref username, ref opctx) = **ctx_tuple;
-            write!(out, "  {:31}", &username)?;
+            write!(out, "  {:37}", &username)?;
             for action in authz::Action::iter() {
                 let result = resource.do_authorize(opctx, action).await;
                 trace!(
@@ -68,6 +68,7 @@ pub async fn make_resources<'a>(
     builder.new_resource(authz::DEVICE_AUTH_REQUEST_LIST.clone());
     builder.new_resource(authz::GLOBAL_IMAGE_LIST.clone());
     builder.new_resource(authz::IP_POOL_LIST.clone());
+    builder.new_resource(authz::AUDIT_LOG.clone());
 
     // Silo/organization/project hierarchy
     make_silo(&mut builder, "silo1", main_silo_id, true).await;
@@ -111,6 +112,9 @@ async fn make_silo(
 
     builder.new_resource(authz::SiloIdentityProviderList::new(silo.clone()));
     builder.new_resource(authz::SiloUserList::new(silo.clone()));
+    builder.new_resource(authz::SiloGroupList::new(silo.clone()));
+    builder.new_resource(authz::SiloAuditLog::new(silo.clone()));
+    builder.new_resource(authz::SiloQuotas::new(silo.clone()));
 
     let norganizations = if first_branch { 2 } else { 1 };
     for i in 0..norganizations {
@@ -186,30 +190,61 @@ async fn make_project(
     );
 
     let disk_name = format!("{}-disk1", project_name);
-    builder.new_resource(authz::Disk::new(
+    let disk = authz::Disk::new(
         project.clone(),
         Uuid::new_v4(),
         LookupType::ByName(disk_name.clone()),
-    ));
-    builder.new_resource(instance.clone());
+    );
+
+    // Roles on Disks, Instances, and Vpcs are supported, too.  As with the
+    // Project, we only need users for one of each.
+    if first_branch {
+        builder.new_resource_with_users(disk).await;
+        builder.new_resource_with_users(instance.clone()).await;
+    } else {
+        builder.new_resource(disk);
+        builder.new_resource(instance.clone());
+    }
     builder.new_resource(authz::NetworkInterface::new(
         instance,
         Uuid::new_v4(),
         LookupType::ByName(format!("{}-nic1", instance_name)),
     ));
-    builder.new_resource(vpc1.clone());
+    if first_branch {
+        builder.new_resource_with_users(vpc1.clone()).await;
+    } else {
+        builder.new_resource(vpc1.clone());
+    }
     // Test a resource nested two levels below Project
     builder.new_resource(authz::VpcSubnet::new(
-        vpc1,
+        vpc1.clone(),
         Uuid::new_v4(),
         LookupType::ByName(format!("{}-subnet1", vpc1_name)),
     ));
+    builder.new_resource(authz::VpcPeering::new(
+        vpc1.clone(),
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-peering1", vpc1_name)),
+    ));
+    builder.new_resource(authz::InternetGateway::new(
+        vpc1,
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-gateway1", vpc1_name)),
+    ));
 
     builder.new_resource(authz::Snapshot::new(
         project.clone(),
         Uuid::new_v4(),
         LookupType::ByName(format!("{}-snapshot1", disk_name)),
     ));
+
+    builder.new_resource(authz::FloatingIp::new(
+        project.clone(),
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-fip1", project_name)),
+    ));
+
+    builder.new_resource(authz::ProjectQuotas::new(project));
 }
 
 /// Returns the set of authz classes exempted from the coverage test
@@ -254,10 +289,12 @@ pub fn exempted_authz_classes() -> BTreeSet<String> {
         authz::DeviceAccessToken::get_polar_class(),
         authz::RoleBuiltin::get_polar_class(),
         authz::SshKey::get_polar_class(),
+        authz::ApiToken::get_polar_class(),
         authz::SiloUser::get_polar_class(),
         authz::SiloGroup::get_polar_class(),
         authz::IdentityProvider::get_polar_class(),
         authz::SamlIdentityProvider::get_polar_class(),
+        authz::OidcIdentityProvider::get_polar_class(),
         authz::UpdateAvailableArtifact::get_polar_class(),
         authz::UserBuiltin::get_polar_class(),
         authz::GlobalImage::get_polar_class(),
@@ -24,6 +24,8 @@ use std::path::{Path, PathBuf};
 pub struct AuthnConfig {
     /// allowed authentication schemes for external HTTP server
     pub schemes_external: Vec<SchemeName>,
+    /// how long a device access token is valid after it's granted
+    pub device_token_ttl_minutes: u32,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -321,6 +323,7 @@ mod test {
             session_absolute_timeout_minutes = 480
             [authn]
             schemes_external = []
+            device_token_ttl_minutes = 1440
             [log]
             mode = "file"
             level = "debug"
@@ -380,7 +383,10 @@ mod test {
                         session_idle_timeout_minutes: 60,
                         session_absolute_timeout_minutes: 480
                     },
-                    authn: AuthnConfig { schemes_external: Vec::new() },
+                    authn: AuthnConfig {
+                        schemes_external: Vec::new(),
+                        device_token_ttl_minutes: 1440,
+                    },
                     log: ConfigLogging::File {
                         level: ConfigLoggingLevel::Debug,
                         if_exists: ConfigLoggingIfExists::Fail,
@@ -408,6 +414,7 @@ mod test {
             session_absolute_timeout_minutes = 480
             [authn]
             schemes_external = [ "spoof", "session_cookie" ]
+            device_token_ttl_minutes = 1440
             [log]
             mode = "file"
             level = "debug"
@@ -450,6 +457,7 @@ mod test {
             session_absolute_timeout_minutes = 480
             [authn]
             schemes_external = ["trust-me"]
+            device_token_ttl_minutes = 1440
             [log]
             mode = "file"
             level = "debug"
@@ -501,6 +509,7 @@ mod test {
             session_absolute_timeout_minutes = 480
             [authn]
             schemes_external = []
+            device_token_ttl_minutes = 1440
             [log]
             mode = "file"
             level = "debug"
@@ -19,6 +19,10 @@ use authn::external::spoof::HttpAuthnSpoof;
 use authn::external::token::HttpAuthnToken;
 use authn::external::HttpAuthnScheme;
 use chrono::{DateTime, Duration, Utc};
+use dropshot::HttpError;
+use dropshot::HttpResponse;
+use futures::Future;
+use http::{Method, StatusCode};
 use internal_dns_client::names::{ServiceName, SRV};
 use omicron_common::address::{Ipv6Subnet, AZ_PREFIX, COCKROACH_PORT};
 use omicron_common::api::external::Error;
@@ -33,6 +37,7 @@ use std::fmt::Debug;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Instant;
 use std::time::SystemTime;
 use uuid::Uuid;
@@ -57,6 +62,9 @@ pub struct ServerContext {
     pub producer_registry: ProducerRegistry,
     /// tunable settings needed for the console at runtime
     pub console_config: ConsoleConfig,
+    /// audit log details for external API requests that are in progress,
+    /// by request id
+    audit_pending: Mutex<BTreeMap<String, Arc<Mutex<AuditDetails>>>>,
 }
 
 pub struct ConsoleConfig {
@@ -199,8 +207,157 @@ impl ServerContext {
                     config.pkg.console.cache_control_max_age_minutes.into(),
                 ),
             },
+            audit_pending: Mutex::new(BTreeMap::new()),
         }))
     }
+
+    /// Runs `handler`, which handles an external API request, recording its
+    /// latency and, if the request may change the state of the system, an
+    /// entry in the audit log
+    pub async fn instrument_external_handler<H, R>(
+        &self,
+        rqctx: &dropshot::RequestContext<Arc<ServerContext>>,
+        handler: H,
+    ) -> Result<R, HttpError>
+    where
+        R: HttpResponse,
+        H: Future<Output = Result<R, HttpError>>,
+    {
+        let handler =
+            self.external_latencies.instrument_dropshot_handler(rqctx, handler);
+        self.audit_external_request(rqctx, handler, |_| {
+            R::response_metadata().success.unwrap()
+        })
+        .await
+    }
+
+    /// Runs `handler`, which handles an external API request whose response
+    /// is built by hand, recording an entry in the audit log if the request
+    /// may change the state of the system
+    pub async fn audit_external_handler<H>(
+        &self,
+        rqctx: &dropshot::RequestContext<Arc<ServerContext>>,
+        handler: H,
+    ) -> Result<http::Response<hyper::Body>, HttpError>
+    where
+        H: Future<Output = Result<http::Response<hyper::Body>, HttpError>>,
+    {
+        self.audit_external_request(rqctx, handler, |response| {
+            response.status()
+        })
+        .await
+    }
+
+    async fn audit_external_request<H, T, F>(
+        &self,
+        rqctx: &dropshot::RequestContext<Arc<ServerContext>>,
+        handler: H,
+        success_status: F,
+    ) -> Result<T, HttpError>
+    where
+        H: Future<Output = Result<T, HttpError>>,
+        F: FnOnce(&T) -> StatusCode,
+    {
+        let (http_method, request_uri) = {
+            let request = rqctx.request.lock().await;
+            (request.method().clone(), request.uri().to_string())
+        };
+        if [Method::GET, Method::HEAD, Method::OPTIONS].contains(&http_method) {
+            return handler.await;
+        }
+
+        let time_started = Utc::now();
+        let details = Arc::new(Mutex::new(AuditDetails::default()));
+        let result = {
+            // If the handler is cancelled (e.g., because the client went
+            // away), the guard makes sure we don't leak the pending entry.
+            let _pending = AuditPendingGuard::new(
+                &self.audit_pending,
+                &rqctx.request_id,
+                Arc::clone(&details),
+            );
+            handler.await
+        };
+
+        let (http_status_code, error_message) = match &result {
+            Ok(response) => (success_status(response), None),
+            Err(error) => {
+                (error.status_code, Some(error.external_message.clone()))
+            }
+        };
+        let details = details.lock().unwrap().clone();
+        let entry = db::model::AuditLogEntry {
+            id: Uuid::new_v4(),
+            time_started,
+            time_completed: Utc::now(),
+            request_id: rqctx.request_id.clone(),
+            actor_id: details.actor_id,
+            actor_silo_id: details.actor_silo_id,
+            http_method: http_method.to_string(),
+            request_uri,
+            resource_ids: details.resource_ids,
+            http_status_code: http_status_code.as_u16().into(),
+            error_message,
+        };
+
+        // TODO-robustness If this fails, the request has already been
+        // completed but there's no record of it.  We could instead fail
+        // requests that can't be recorded (e.g., by recording them before
+        // they're executed and updating the record afterwards), but that would
+        // take down the whole API whenever the audit log is unavailable.
+        if let Err(error) = self.nexus.audit_log_entry_create(entry).await {
+            error!(rqctx.log, "failed to record request in audit log";
+                "error" => %error,
+            );
+        }
+
+        result
+    }
+
+    /// Returns the audit log details for the given external API request, if
+    /// it's one that's being recorded in the audit log
+    pub fn audit_details(
+        &self,
+        request_id: &str,
+    ) -> Option<Arc<Mutex<AuditDetails>>> {
+        self.audit_pending.lock().unwrap().get(request_id).cloned()
+    }
+}
+
+/// Makes an external API request's audit log details available (see
+/// [`ServerContext::audit_details()`]) for as long as it exists
+struct AuditPendingGuard<'a> {
+    audit_pending: &'a Mutex<BTreeMap<String, Arc<Mutex<AuditDetails>>>>,
+    request_id: &'a str,
+}
+
+impl<'a> AuditPendingGuard<'a> {
+    fn new(
+        audit_pending: &'a Mutex<BTreeMap<String, Arc<Mutex<AuditDetails>>>>,
+        request_id: &'a str,
+        details: Arc<Mutex<AuditDetails>>,
+    ) -> AuditPendingGuard<'a> {
+        audit_pending.lock().unwrap().insert(request_id.to_string(), details);
+        AuditPendingGuard { audit_pending, request_id }
+    }
+}
+
+impl<'a> Drop for AuditPendingGuard<'a> {
+    fn drop(&mut self) {
+        self.audit_pending.lock().unwrap().remove(self.request_id);
+    }
+}
+
+/// Details about an external API request that are gathered while it's handled
+/// and recorded in the audit log when it completes
+#[derive(Clone, Debug, Default)]
+pub struct AuditDetails {
+    /// the authenticated actor, if any
+    pub actor_id: Option<Uuid>,
+    /// the Silo of the client making the request, if any
+    pub actor_silo_id: Option<Uuid>,
+    /// resources that the request was authorized to change
+    pub resource_ids: Vec<Uuid>,
 }
 
 /// Provides general facilities scoped to whatever operation Nexus is currently
@@ -229,6 +386,7 @@ pub struct OpContext {
     created_walltime: SystemTime,
     metadata: BTreeMap<String, String>,
     kind: OpKind,
+    audit: Option<Arc<Mutex<AuditDetails>>>,
 }
 
 enum OpKind {
@@ -265,6 +423,13 @@ impl OpContext {
             OpContext::log_and_metadata_for_authn(&rqctx.log, &authn);
         OpContext::load_request_metadata(rqctx, &mut metadata).await;
 
+        let audit = apictx.audit_details(&rqctx.request_id);
+        if let Some(audit) = &audit {
+            let mut details = audit.lock().unwrap();
+            details.actor_id = authn.actor().map(|actor| actor.actor_id());
+            details.actor_silo_id = authn.actor().and_then(|a| a.silo_id());
+        }
+
         Ok(OpContext {
             log,
             authz,
@@ -273,6 +438,7 @@ impl OpContext {
             created_walltime,
             metadata,
             kind: OpKind::ExternalApiRequest,
+            audit,
         })
     }
 
@@ -304,6 +470,7 @@ impl OpContext {
             created_walltime,
             metadata,
             kind: OpKind::InternalApiRequest,
+            audit: None,
         }
     }
 
@@ -380,6 +547,7 @@ impl OpContext {
             created_walltime,
             metadata,
             kind: OpKind::Saga,
+            audit: None,
         }
     }
 
@@ -406,6 +574,7 @@ impl OpContext {
             created_walltime,
             metadata: BTreeMap::new(),
             kind: OpKind::Background,
+            audit: None,
         }
     }
 
@@ -435,6 +604,7 @@ impl OpContext {
             created_walltime,
             metadata: BTreeMap::new(),
             kind: OpKind::Test,
+            audit: None,
         }
     }
 
@@ -464,6 +634,26 @@ impl OpContext {
             "resource" => ?*resource,
             "result" => ?result,
         );
+
+        // Record the resources that this request was allowed to change so that
+        // they show up in the audit log.
+        if let (Ok(()), Some(audit), Some(id)) =
+            (&result, &self.audit, resource.audit_id())
+        {
+            if matches!(
+                action,
+                authz::Action::Modify
+                    | authz::Action::ModifyPolicy
+                    | authz::Action::CreateChild
+                    | authz::Action::Delete
+            ) {
+                let mut details = audit.lock().unwrap();
+                if !details.resource_ids.contains(&id) {
+                    details.resource_ids.push(id);
+                }
+            }
+        }
+
         result
     }
 }
@@ -550,8 +740,14 @@ impl authn::external::token::TokenContext for ServerContext {
         &self,
         token: String,
     ) -> Result<authn::Actor, authn::Reason> {
+        // Device access tokens and API tokens look the same, so try both.
         let opctx = self.nexus.opctx_external_authn();
-        self.nexus.device_access_token_actor(opctx, token).await
+        match self.nexus.device_access_token_actor(opctx, token.clone()).await {
+            Err(authn::Reason::UnknownActor { .. }) => {
+                self.nexus.api_token_actor(opctx, token).await
+            }
+            result => result,
+        }
     }
 }
 
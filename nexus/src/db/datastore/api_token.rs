@@ -0,0 +1,154 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to [`ApiToken`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::identity::Resource;
+use crate::db::model::ApiToken;
+use crate::db::model::Name;
+use crate::db::pagination::paginated;
+use crate::db::update_and_check::UpdateAndCheck;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+impl DataStore {
+    /// List a user's API tokens
+    ///
+    /// Like reading a token, listing a user's tokens requires permission to
+    /// manage the user.
+    pub async fn api_tokens_list(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        page_params: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<ApiToken> {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::api_token::dsl;
+        paginated(dsl::api_token, dsl::name, page_params)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .filter(dsl::time_deleted.is_null())
+            .select(ApiToken::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Create a new API token for a user.
+    pub async fn api_token_create(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        api_token: ApiToken,
+    ) -> CreateResult<ApiToken> {
+        assert_eq!(authz_user.id(), api_token.silo_user_id);
+        opctx.authorize(authz::Action::CreateChild, authz_user).await?;
+        let name = api_token.name().to_string();
+
+        use db::schema::api_token::dsl;
+        diesel::insert_into(dsl::api_token)
+            .values(api_token)
+            .returning(ApiToken::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(ResourceType::ApiToken, &name),
+                )
+            })
+    }
+
+    /// Revoke an existing API token.
+    pub async fn api_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_api_token: &authz::ApiToken,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_api_token).await?;
+
+        use db::schema::api_token::dsl;
+        diesel::update(dsl::api_token)
+            .filter(dsl::id.eq(authz_api_token.id()))
+            .filter(dsl::time_deleted.is_null())
+            .set(dsl::time_deleted.eq(Utc::now()))
+            .check_if_exists::<ApiToken>(authz_api_token.id())
+            .execute_and_check(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_api_token),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Look up an API token by the hash of the token itself.
+    ///
+    /// Like [`DataStore::device_access_token_fetch`], this is used to
+    /// authenticate requests, so it does not fit the usual lookup machinery
+    /// and does not do any authz checks.  Tokens are high-entropy random
+    /// values, and so should not be guessable by an attacker.
+    pub async fn api_token_fetch_by_hash(
+        &self,
+        opctx: &OpContext,
+        token_hash: String,
+    ) -> LookupResult<ApiToken> {
+        use db::schema::api_token::dsl;
+        dsl::api_token
+            .filter(dsl::token_hash.eq(token_hash))
+            .filter(dsl::time_deleted.is_null())
+            .select(ApiToken::as_select())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByLookup(
+                        ResourceType::ApiToken,
+                        LookupType::ByCompositeId("token_hash".to_string()),
+                    ),
+                )
+            })
+    }
+
+    /// Record that an API token was just used to authenticate a request.
+    ///
+    /// As with [`DataStore::api_token_fetch_by_hash`], there are no authz
+    /// checks here.
+    pub async fn api_token_update_last_used(
+        &self,
+        opctx: &OpContext,
+        token_id: Uuid,
+    ) -> UpdateResult<()> {
+        use db::schema::api_token::dsl;
+        diesel::update(dsl::api_token)
+            .filter(dsl::id.eq(token_id))
+            .filter(dsl::time_deleted.is_null())
+            .set(dsl::time_last_used.eq(Utc::now()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to the audit log
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::model::AuditLogEntry;
+use crate::db::pagination::paginated_multicolumn;
+use crate::external_api::params;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::ListResultVec;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Append an entry to the audit log
+    pub async fn audit_log_entry_create(
+        &self,
+        opctx: &OpContext,
+        entry: AuditLogEntry,
+    ) -> CreateResult<()> {
+        opctx.authorize(authz::Action::CreateChild, &authz::AUDIT_LOG).await?;
+
+        use db::schema::audit_log::dsl;
+        diesel::insert_into(dsl::audit_log)
+            .values(entry)
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map(|_| ())
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List entries from the whole audit log, oldest first
+    pub async fn audit_log_list(
+        &self,
+        opctx: &OpContext,
+        filter: &params::AuditLogFilter,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<AuditLogEntry> {
+        opctx.authorize(authz::Action::ListChildren, &authz::AUDIT_LOG).await?;
+        self.audit_log_list_impl(opctx, None, filter, pagparams).await
+    }
+
+    /// List entries from the audit log for requests made by clients in one
+    /// Silo, oldest first
+    pub async fn silo_audit_log_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo_audit_log: &authz::SiloAuditLog,
+        filter: &params::AuditLogFilter,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<AuditLogEntry> {
+        opctx
+            .authorize(authz::Action::ListChildren, authz_silo_audit_log)
+            .await?;
+        let silo_id = authz_silo_audit_log.silo().id();
+        self.audit_log_list_impl(opctx, Some(silo_id), filter, pagparams).await
+    }
+
+    async fn audit_log_list_impl(
+        &self,
+        opctx: &OpContext,
+        silo_id: Option<Uuid>,
+        filter: &params::AuditLogFilter,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<AuditLogEntry> {
+        use db::schema::audit_log::dsl;
+        let mut query = paginated_multicolumn(
+            dsl::audit_log,
+            (dsl::time_completed, dsl::id),
+            pagparams,
+        );
+        if let Some(silo_id) = silo_id {
+            query = query.filter(dsl::actor_silo_id.eq(silo_id));
+        }
+        if let Some(start_time) = filter.start_time {
+            query = query.filter(dsl::time_completed.ge(start_time));
+        }
+        if let Some(end_time) = filter.end_time {
+            query = query.filter(dsl::time_completed.lt(end_time));
+        }
+        if let Some(actor_id) = filter.actor_id {
+            query = query.filter(dsl::actor_id.eq(actor_id));
+        }
+        if let Some(resource_id) = filter.resource_id {
+            query = query.filter(dsl::resource_ids.contains(vec![resource_id]));
+        }
+        query
+            .select(AuditLogEntry::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+}
@@ -9,16 +9,24 @@ use crate::authn;
 use crate::authz;
 use crate::context::OpContext;
 use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
 use crate::db::lookup::LookupPath;
 use crate::db::model::ConsoleSession;
+use crate::db::pagination::paginated;
 use async_bb8_diesel::AsyncRunQueryDsl;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
 
 impl DataStore {
     // TODO-correctness: fix session method errors. the map_errs turn all errors
@@ -140,4 +148,74 @@ impl DataStore {
                 ))
             })
     }
+
+    /// List a user's sessions that were last used after `last_used_after`
+    /// and created after `created_after`, i.e., those that haven't expired
+    ///
+    /// Unlike creating sessions, which is governed by
+    /// [`authz::ConsoleSessionList`], managing a particular user's sessions
+    /// requires permission to manage the user: the user themselves, or an
+    /// administrator of their Silo or the Fleet.
+    pub async fn sessions_list_by_user(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        last_used_after: DateTime<Utc>,
+        created_after: DateTime<Utc>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<ConsoleSession> {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::console_session::dsl;
+        paginated(dsl::console_session, dsl::id, pagparams)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .filter(dsl::time_last_used.gt(last_used_after))
+            .filter(dsl::time_created.gt(created_after))
+            .select(ConsoleSession::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Revoke one of a user's sessions, identified by its id
+    pub async fn session_delete_by_id(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        session_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::console_session::dsl;
+        let deleted = diesel::delete(dsl::console_session)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .filter(dsl::id.eq(session_id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if deleted == 0 {
+            return Err(LookupType::ById(session_id)
+                .into_not_found(ResourceType::ConsoleSession));
+        }
+        Ok(())
+    }
+
+    /// Revoke all of a user's sessions, logging them out everywhere
+    pub async fn sessions_delete_by_user(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::console_session::dsl;
+        diesel::delete(dsl::console_session)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map(|_rows_deleted| ())
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
 }
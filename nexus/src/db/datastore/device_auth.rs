@@ -13,11 +13,16 @@ use crate::db::error::ErrorHandler;
 use crate::db::error::TransactionError;
 use crate::db::model::DeviceAccessToken;
 use crate::db::model::DeviceAuthRequest;
+use crate::db::pagination::paginated;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
@@ -141,4 +146,55 @@ impl DataStore {
                 )
             })
     }
+
+    /// List the unexpired device access tokens granted to a user.
+    ///
+    /// Like API tokens, listing a user's tokens requires permission to
+    /// manage the user.
+    pub async fn device_access_tokens_list(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<DeviceAccessToken> {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::device_access_token::dsl;
+        paginated(dsl::device_access_token, dsl::id, pagparams)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .filter(
+                dsl::time_expires
+                    .is_null()
+                    .or(dsl::time_expires.gt(Utc::now())),
+            )
+            .select(DeviceAccessToken::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Revoke a device access token granted to a user.
+    pub async fn device_access_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::device_access_token::dsl;
+        let deleted = diesel::delete(dsl::device_access_token)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .filter(dsl::id.eq(token_id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if deleted == 0 {
+            return Err(LookupType::ById(token_id)
+                .into_not_found(ResourceType::DeviceAccessToken));
+        }
+        Ok(())
+    }
 }
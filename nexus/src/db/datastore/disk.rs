@@ -15,15 +15,18 @@ use crate::db::collection_detach::DatastoreDetachTarget;
 use crate::db::collection_detach::DetachError;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::model::Disk;
 use crate::db::model::DiskRuntimeState;
 use crate::db::model::Instance;
 use crate::db::model::Name;
+use crate::db::model::ResourceUsage;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
@@ -64,18 +67,30 @@ impl DataStore {
 
         let gen = disk.runtime().gen;
         let name = disk.name().clone();
-        let disk: Disk = diesel::insert_into(dsl::disk)
-            .values(disk)
-            .on_conflict(dsl::id)
-            .do_nothing()
-            .returning(Disk::as_returning())
-            .get_result_async(self.pool())
+        let project_id = disk.project_id;
+        let usage = ResourceUsage::for_disk(&disk);
+        type TxnError = TransactionError<Error>;
+        let disk: Disk = self
+            .pool()
+            .transaction_async(|conn| async move {
+                let disk = diesel::insert_into(dsl::disk)
+                    .values(disk)
+                    .on_conflict(dsl::id)
+                    .do_nothing()
+                    .returning(Disk::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                DataStore::resource_usage_charge(&conn, project_id, usage)
+                    .await?;
+                Ok(disk)
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::Conflict(ResourceType::Disk, name.as_str()),
-                )
+                ),
             })?;
 
         let runtime = disk.runtime();
@@ -445,7 +460,7 @@ impl DataStore {
 
     /// Updates a disk record to indicate it has been deleted.
     ///
-    /// Returns the volume ID of associated with the deleted disk.
+    /// Returns the disk record as it was before it was deleted.
     ///
     /// Does not attempt to modify any resources (e.g. regions) which may
     /// belong to the disk.
@@ -469,7 +484,7 @@ impl DataStore {
     pub async fn project_delete_disk_no_auth(
         &self,
         disk_id: &Uuid,
-    ) -> Result<Uuid, Error> {
+    ) -> Result<Disk, Error> {
         use db::schema::disk::dsl;
         let pool = self.pool();
         let now = Utc::now();
@@ -484,27 +499,55 @@ impl DataStore {
             ok_to_delete_states.iter().map(|s| s.label()).collect();
         let destroyed = api::external::DiskState::Destroyed.label();
 
-        let result = diesel::update(dsl::disk)
-            .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(*disk_id))
-            .filter(dsl::disk_state.eq_any(ok_to_delete_state_labels))
-            .filter(dsl::attach_instance_id.is_null())
-            .set((dsl::disk_state.eq(destroyed), dsl::time_deleted.eq(now)))
-            .check_if_exists::<Disk>(*disk_id)
-            .execute_and_check(pool)
+        type TxnError = TransactionError<Error>;
+        let result = pool
+            .transaction_async(|conn| async move {
+                let result = diesel::update(dsl::disk)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(*disk_id))
+                    .filter(dsl::disk_state.eq_any(ok_to_delete_state_labels))
+                    .filter(dsl::attach_instance_id.is_null())
+                    .set((
+                        dsl::disk_state.eq(destroyed),
+                        dsl::time_deleted.eq(now),
+                    ))
+                    .check_if_exists::<Disk>(*disk_id)
+                    .execute_and_check(&conn)
+                    .await?;
+
+                // Return the disk's space to the quotas it was counted
+                // against and drop its role assignments, unless an earlier
+                // call has already done so.
+                if let UpdateStatus::Updated = result.status {
+                    DataStore::resource_usage_charge(
+                        &conn,
+                        result.found.project_id,
+                        -ResourceUsage::for_disk(&result.found),
+                    )
+                    .await?;
+                    DataStore::role_assignment_delete_all_query(
+                        ResourceType::Disk,
+                        *disk_id,
+                    )
+                    .execute_async(&conn)
+                    .await?;
+                }
+                Ok(result)
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByLookup(
                         ResourceType::Disk,
                         LookupType::ById(*disk_id),
                     ),
-                )
+                ),
             })?;
 
         match result.status {
-            UpdateStatus::Updated => Ok(result.found.volume_id),
+            UpdateStatus::Updated => Ok(result.found),
             UpdateStatus::NotUpdatedButExists => {
                 let disk = result.found;
                 let disk_state = disk.state();
@@ -514,7 +557,7 @@ impl DataStore {
                 {
                     // To maintain idempotency, if the disk has already been
                     // destroyed, don't throw an error.
-                    return Ok(disk.volume_id);
+                    return Ok(disk);
                 } else if !ok_to_delete_states.contains(disk_state.state()) {
                     return Err(Error::InvalidRequest {
                         message: format!(
@@ -538,4 +581,68 @@ impl DataStore {
             }
         }
     }
+
+    /// Undoes [`DataStore::project_delete_disk_no_auth()`], given the disk
+    /// record that it returned
+    ///
+    /// This is used to unwind a saga that deleted the disk but could not
+    /// finish deleting the resources behind it.  The disk gets back the
+    /// runtime state it had before, and its space is counted against its
+    /// Project's quotas again, even if they have since been lowered.
+    ///
+    /// The disk's own role assignments are not restored: they were removed
+    /// along with the disk record, so the undeleted disk is only accessible
+    /// through roles on its Project (and above) until an admin assigns them
+    /// again.
+    // TODO-robustness This fails if another disk has taken the name of this one
+    // in the meantime.
+    pub async fn project_undelete_disk_no_auth(
+        &self,
+        disk: &Disk,
+    ) -> Result<(), Error> {
+        use db::schema::disk::dsl;
+
+        let destroyed = api::external::DiskState::Destroyed.label();
+        // If the saga had to delete the disk more than once, the record we
+        // were given may already be the deleted one.
+        let runtime = if disk.time_deleted().is_some() {
+            disk.runtime().detach()
+        } else {
+            disk.runtime()
+        };
+        let disk_id = disk.id();
+        let project_id = disk.project_id;
+        let usage = ResourceUsage::for_disk(disk);
+
+        type TxnError = TransactionError<Error>;
+        self.pool()
+            .transaction_async(|conn| async move {
+                let undeleted = diesel::update(dsl::disk)
+                    .filter(dsl::id.eq(disk_id))
+                    .filter(dsl::time_deleted.is_not_null())
+                    .filter(dsl::disk_state.eq(destroyed))
+                    .set((
+                        dsl::time_deleted.eq(None::<chrono::DateTime<Utc>>),
+                        runtime,
+                    ))
+                    .execute_async(&conn)
+                    .await?;
+                if undeleted > 0 {
+                    DataStore::resource_usage_restore(&conn, project_id, usage)
+                        .await?;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByLookup(
+                        ResourceType::Disk,
+                        LookupType::ById(disk_id),
+                    ),
+                ),
+            })
+    }
 }
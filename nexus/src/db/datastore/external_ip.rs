@@ -5,31 +5,49 @@
 //! [`DataStore`] methods on [`ExternalIp`]s.
 
 use super::DataStore;
+use crate::authz;
 use crate::context::OpContext;
 use crate::db;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::model::ExternalIp;
+use crate::db::model::FloatingIp;
 use crate::db::model::IncompleteExternalIp;
 use crate::db::model::IpKind;
 use crate::db::model::IpPool;
 use crate::db::model::Name;
+use crate::db::model::ResourceUsage;
+use crate::db::pagination::paginated;
+use crate::db::pool::DbConnection;
 use crate::db::queries::external_ip::NextExternalIp;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use async_bb8_diesel::PoolError;
 use chrono::Utc;
 use diesel::prelude::*;
 use nexus_types::identity::Resource;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
 impl DataStore {
     /// Create an external IP address for source NAT for an instance.
+    ///
+    /// The address is allocated from the IP Pools of the internet gateway
+    /// through which the instance's VPC routes outbound traffic, trying each
+    /// in turn. If that gateway has no pools, the project's default IP Pool
+    /// is used instead.
     pub async fn allocate_instance_snat_ip(
         &self,
         opctx: &OpContext,
@@ -37,13 +55,37 @@ impl DataStore {
         project_id: Uuid,
         instance_id: Uuid,
     ) -> CreateResult<ExternalIp> {
+        let gateway_pool_ids =
+            self.instance_internet_gateway_ip_pools(opctx, instance_id).await?;
+        let mut exhausted = None;
+        for pool_id in gateway_pool_ids {
+            let data = IncompleteExternalIp::for_instance_source_nat(
+                ip_id,
+                project_id,
+                instance_id,
+                Some(pool_id),
+            );
+            // Move on to the next pool if this one has no addresses left.
+            match self
+                .allocate_external_ip(opctx, data, ErrorHandler::Server, None)
+                .await
+            {
+                Err(e @ Error::InvalidRequest { .. }) => exhausted = Some(e),
+                result => return result,
+            }
+        }
+        if let Some(e) = exhausted {
+            return Err(e);
+        }
+
+        let pool_id = self.ip_pool_for_project(opctx, project_id, None).await?;
         let data = IncompleteExternalIp::for_instance_source_nat(
             ip_id,
             project_id,
             instance_id,
-            /* pool_id = */ None,
+            pool_id,
         );
-        self.allocate_external_ip(opctx, data).await
+        self.allocate_external_ip(opctx, data, ErrorHandler::Server, None).await
     }
 
     /// Create an Ephemeral IP address for an instance.
@@ -55,7 +97,89 @@ impl DataStore {
         instance_id: Uuid,
         pool_name: Option<Name>,
     ) -> CreateResult<ExternalIp> {
-        let pool_id = if let Some(ref name) = pool_name {
+        let pool_id =
+            self.ip_pool_for_project(opctx, project_id, pool_name).await?;
+        let data = IncompleteExternalIp::for_ephemeral(
+            ip_id,
+            project_id,
+            instance_id,
+            pool_id,
+        );
+        self.allocate_external_ip(opctx, data, ErrorHandler::Server, None).await
+    }
+
+    /// Create an Ephemeral IP address for an existing instance, which must
+    /// not already have `max_external_ips` Ephemeral and Floating IPs.
+    pub async fn instance_attach_ephemeral_ip(
+        &self,
+        opctx: &OpContext,
+        ip_id: Uuid,
+        project_id: Uuid,
+        instance_id: Uuid,
+        pool_name: Option<Name>,
+        max_external_ips: usize,
+    ) -> CreateResult<ExternalIp> {
+        let pool_id =
+            self.ip_pool_for_project(opctx, project_id, pool_name).await?;
+        let data = IncompleteExternalIp::for_ephemeral(
+            ip_id,
+            project_id,
+            instance_id,
+            pool_id,
+        );
+        self.allocate_external_ip(
+            opctx,
+            data,
+            ErrorHandler::Server,
+            Some(max_external_ips),
+        )
+        .await
+    }
+
+    /// Create a Floating IP address in a project.
+    pub async fn allocate_floating_ip(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        ip_id: Uuid,
+        name: &Name,
+        description: &str,
+        pool_name: Option<Name>,
+    ) -> CreateResult<FloatingIp> {
+        opctx.authorize(authz::Action::CreateChild, authz_project).await?;
+
+        let project_id = authz_project.id();
+        let pool_id =
+            self.ip_pool_for_project(opctx, project_id, pool_name).await?;
+        let data = IncompleteExternalIp::for_floating(
+            ip_id,
+            name,
+            description,
+            project_id,
+            pool_id,
+        );
+        self.allocate_external_ip(
+            opctx,
+            data,
+            ErrorHandler::Conflict(ResourceType::FloatingIp, name.as_str()),
+            None,
+        )
+        .await
+        .and_then(FloatingIp::try_from)
+    }
+
+    /// Returns the id of the IP Pool named `pool_name`, if one is given, which
+    /// must be available to project `project_id`
+    ///
+    /// If no name is given, this returns the project's default IP Pool, if it
+    /// has one.
+    pub async fn ip_pool_for_project(
+        &self,
+        opctx: &OpContext,
+        project_id: Uuid,
+        pool_name: Option<Name>,
+    ) -> LookupResult<Option<Uuid>> {
+        if let Some(ref name) = pool_name {
             // We'd like to add authz checks here, and use the `LookupPath`
             // methods on the project-scoped view of this resource. It's not
             // entirely clear how that'll work in the API, so see RFD 288 and
@@ -65,7 +189,7 @@ impl DataStore {
             // For now, we just ensure that the pool is either unreserved, or
             // reserved for the instance's project.
             use db::schema::ip_pool::dsl;
-            Some(
+            Ok(Some(
                 dsl::ip_pool
                     .filter(dsl::name.eq(name.clone()))
                     .filter(dsl::time_deleted.is_null())
@@ -88,17 +212,13 @@ impl DataStore {
                     })?
                     .identity
                     .id,
-            )
+            ))
         } else {
-            None
-        };
-        let data = IncompleteExternalIp::for_ephemeral(
-            ip_id,
-            project_id,
-            instance_id,
-            pool_id,
-        );
-        self.allocate_external_ip(opctx, data).await
+            Ok(self
+                .project_settings_get(opctx, project_id)
+                .await?
+                .default_ip_pool_id)
+        }
     }
 
     /// Allocates an IP address for internal service usage.
@@ -112,30 +232,166 @@ impl DataStore {
             self.ip_pools_lookup_by_rack_id(opctx, rack_id).await?;
 
         let data = IncompleteExternalIp::for_service(ip_id, pool.id());
-        self.allocate_external_ip(opctx, data).await
+        self.allocate_external_ip(opctx, data, ErrorHandler::Server, None).await
     }
 
+    /// Allocates an external IP address, counting it against the quotas of
+    /// its project
+    ///
+    /// `handler` describes how to report errors from the database other than
+    /// there being no addresses left.  If `max_external_ips` is provided, the
+    /// allocation fails if the address's instance already has that many
+    /// Ephemeral and Floating IPs.
     async fn allocate_external_ip(
         &self,
         opctx: &OpContext,
         data: IncompleteExternalIp,
+        handler: ErrorHandler<'_>,
+        max_external_ips: Option<usize>,
     ) -> CreateResult<ExternalIp> {
-        NextExternalIp::new(data)
-            .get_result_async(self.pool_authorized(opctx).await?)
+        use db::schema::external_ip::dsl;
+        let ip_id = *data.id();
+        let instance_id = *data.instance_id();
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                if let (Some(max), Some(instance_id)) =
+                    (max_external_ips, instance_id)
+                {
+                    DataStore::instance_external_ip_limit_check(
+                        &conn,
+                        instance_id,
+                        ip_id,
+                        max,
+                    )
+                    .await?;
+                }
+                // Allocating an address is idempotent, but it should only be
+                // counted against quotas the first time.
+                let exists = dsl::external_ip
+                    .filter(dsl::id.eq(ip_id))
+                    .select(dsl::id)
+                    .get_result_async::<Uuid>(&conn)
+                    .await
+                    .optional()?
+                    .is_some();
+                let ip = NextExternalIp::new(data)
+                    .get_result_async::<ExternalIp>(&conn)
+                    .await?;
+                if let (false, Some(project_id)) = (exists, ip.project_id) {
+                    DataStore::resource_usage_charge(
+                        &conn,
+                        project_id,
+                        ResourceUsage::for_external_ip(&ip),
+                    )
+                    .await?;
+                }
+                Ok(ip)
+            })
             .await
             .map_err(|e| {
                 use async_bb8_diesel::ConnectionError::Query;
                 use async_bb8_diesel::PoolError::Connection;
                 use diesel::result::Error::NotFound;
                 match e {
-                    Connection(Query(NotFound)) => Error::invalid_request(
-                        "No external IP addresses available",
-                    ),
-                    _ => public_error_from_diesel_pool(e, ErrorHandler::Server),
+                    TxnError::CustomError(e) => e,
+                    TxnError::Pool(Connection(Query(NotFound))) => {
+                        Error::invalid_request(
+                            "No external IP addresses available",
+                        )
+                    }
+                    TxnError::Pool(e) => {
+                        public_error_from_diesel_pool(e, handler)
+                    }
                 }
             })
     }
 
+    /// Fails if instance `instance_id` already has `max_external_ips`
+    /// Ephemeral and Floating IPs, not counting `ip_id`
+    ///
+    /// This must be called from the transaction that gives the instance
+    /// another address.  The instance's row is locked so that concurrent
+    /// attachments can't both see room for one more address.
+    async fn instance_external_ip_limit_check<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        instance_id: Uuid,
+        ip_id: Uuid,
+        max_external_ips: usize,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+    {
+        type TxnError = TransactionError<Error>;
+        let db_err = |e: ConnErr| TxnError::Pool(PoolError::from(e));
+
+        {
+            use db::schema::instance::dsl;
+            let found = dsl::instance
+                .filter(dsl::id.eq(instance_id))
+                .filter(dsl::time_deleted.is_null())
+                .select(dsl::id)
+                .for_update()
+                .get_results_async::<Uuid>(conn)
+                .await
+                .map_err(db_err)?;
+            if found.is_empty() {
+                return Err(TxnError::CustomError(Error::not_found_by_id(
+                    ResourceType::Instance,
+                    &instance_id,
+                )));
+            }
+        }
+
+        use db::schema::external_ip::dsl;
+        let n_external_ips = dsl::external_ip
+            .filter(dsl::instance_id.eq(instance_id))
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::kind.ne(IpKind::SNat))
+            .filter(dsl::id.ne(ip_id))
+            .count()
+            .get_result_async::<i64>(conn)
+            .await
+            .map_err(db_err)?;
+        if n_external_ips >= i64::try_from(max_external_ips).unwrap() {
+            return Err(TxnError::CustomError(Error::invalid_request(
+                &format!(
+                    "An instance may not have more than {} external IP \
+                    addresses",
+                    max_external_ips,
+                ),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns an external IP address that has just been deallocated to the
+    /// quotas of its project, as part of the transaction deallocating it
+    async fn external_ip_release_usage<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        ip: &ExternalIp,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+    {
+        match ip.project_id {
+            Some(project_id) => {
+                DataStore::resource_usage_charge(
+                    conn,
+                    project_id,
+                    -ResourceUsage::for_external_ip(ip),
+                )
+                .await
+            }
+            None => Ok(()),
+        }
+    }
+
     /// Deallocate the external IP address with the provided ID.
     ///
     /// To support idempotency, such as in saga operations, this method returns
@@ -153,18 +409,36 @@ impl DataStore {
     ) -> Result<bool, Error> {
         use db::schema::external_ip::dsl;
         let now = Utc::now();
-        diesel::update(dsl::external_ip)
-            .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(ip_id))
-            .set(dsl::time_deleted.eq(now))
-            .check_if_exists::<ExternalIp>(ip_id)
-            .execute_and_check(self.pool_authorized(opctx).await?)
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let result = diesel::update(dsl::external_ip)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(ip_id))
+                    .set(dsl::time_deleted.eq(now))
+                    .check_if_exists::<ExternalIp>(ip_id)
+                    .execute_and_check(&conn)
+                    .await?;
+                match result.status {
+                    UpdateStatus::Updated => {
+                        DataStore::external_ip_release_usage(
+                            &conn,
+                            &result.found,
+                        )
+                        .await?;
+                        Ok(true)
+                    }
+                    UpdateStatus::NotUpdatedButExists => Ok(false),
+                }
+            })
             .await
-            .map(|r| match r.status {
-                UpdateStatus::Updated => true,
-                UpdateStatus::NotUpdatedButExists => false,
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
             })
-            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
     /// Delete all external IP addresses associated with the provided instance
@@ -173,8 +447,9 @@ impl DataStore {
     /// This method returns the number of records deleted, rather than the usual
     /// `DeleteResult`. That's mostly useful for tests, but could be important
     /// if callers have some invariants they'd like to check.
-    // TODO-correctness: This can't be used for Floating IPs, we'll need a
-    // _detatch_ method for that.
+    ///
+    /// Floating IPs are not deleted.  Use
+    /// [`DataStore::detach_floating_ips_by_instance_id()`] to detach them.
     pub async fn deallocate_external_ip_by_instance_id(
         &self,
         opctx: &OpContext,
@@ -182,14 +457,30 @@ impl DataStore {
     ) -> Result<usize, Error> {
         use db::schema::external_ip::dsl;
         let now = Utc::now();
-        diesel::update(dsl::external_ip)
-            .filter(dsl::time_deleted.is_null())
-            .filter(dsl::instance_id.eq(instance_id))
-            .filter(dsl::kind.ne(IpKind::Floating))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool_authorized(opctx).await?)
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let ips = diesel::update(dsl::external_ip)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::instance_id.eq(instance_id))
+                    .filter(dsl::kind.ne(IpKind::Floating))
+                    .set(dsl::time_deleted.eq(now))
+                    .returning(ExternalIp::as_returning())
+                    .get_results_async(&conn)
+                    .await?;
+                for ip in &ips {
+                    DataStore::external_ip_release_usage(&conn, ip).await?;
+                }
+                Ok(ips.len())
+            })
             .await
-            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
     }
 
     /// Fetch all external IP addresses of any kind for the provided instance
@@ -207,4 +498,228 @@ impl DataStore {
             .await
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
+
+    /// Detach all Floating IPs from the provided instance ID, returning the
+    /// number of addresses detached
+    pub async fn detach_floating_ips_by_instance_id(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> Result<usize, Error> {
+        use db::schema::external_ip::dsl;
+        let now = Utc::now();
+        diesel::update(dsl::external_ip)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::instance_id.eq(instance_id))
+            .filter(dsl::kind.eq(IpKind::Floating))
+            .set((
+                dsl::instance_id.eq(Option::<Uuid>::None),
+                dsl::time_modified.eq(now),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the Floating IPs in a project
+    pub async fn floating_ips_list(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        pagparams: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<FloatingIp> {
+        opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+
+        use db::schema::floating_ip::dsl;
+        paginated(dsl::floating_ip, dsl::name, pagparams)
+            .filter(dsl::project_id.eq(authz_project.id()))
+            .filter(dsl::time_deleted.is_null())
+            .select(FloatingIp::as_select())
+            .get_results_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete a Floating IP, which must not be attached to an instance
+    pub async fn floating_ip_delete(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_fip).await?;
+
+        use db::schema::external_ip::dsl;
+        let now = Utc::now();
+        type TxnError = TransactionError<Error>;
+        let result = self
+            .pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let result = diesel::update(dsl::external_ip)
+                    .filter(dsl::id.eq(authz_fip.id()))
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::instance_id.is_null())
+                    .set(dsl::time_deleted.eq(now))
+                    .check_if_exists::<ExternalIp>(authz_fip.id())
+                    .execute_and_check(&conn)
+                    .await?;
+                if let UpdateStatus::Updated = result.status {
+                    DataStore::external_ip_release_usage(&conn, &result.found)
+                        .await?;
+                }
+                Ok(result)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_fip),
+                ),
+            })?;
+        match result.status {
+            UpdateStatus::Updated => Ok(()),
+            UpdateStatus::NotUpdatedButExists => {
+                if result.found.time_deleted.is_some() {
+                    Err(authz_fip.not_found())
+                } else {
+                    Err(Error::invalid_request(
+                        "Floating IP cannot be deleted while attached to an \
+                        instance",
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Attach a Floating IP to an instance
+    ///
+    /// This succeeds if the Floating IP is already attached to the instance,
+    /// and fails if it's attached to any other instance, or if the instance
+    /// already has `max_external_ips` Ephemeral and Floating IPs.
+    pub async fn floating_ip_attach(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+        authz_instance: &authz::Instance,
+        max_external_ips: usize,
+    ) -> UpdateResult<FloatingIp> {
+        opctx.authorize(authz::Action::Modify, authz_fip).await?;
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        use db::schema::external_ip::dsl;
+        let fip_id = authz_fip.id();
+        let instance_id = authz_instance.id();
+        let now = Utc::now();
+        type TxnError = TransactionError<Error>;
+        let result = self
+            .pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                DataStore::instance_external_ip_limit_check(
+                    &conn,
+                    instance_id,
+                    fip_id,
+                    max_external_ips,
+                )
+                .await?;
+                let result = diesel::update(dsl::external_ip)
+                    .filter(dsl::id.eq(fip_id))
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::kind.eq(IpKind::Floating))
+                    .filter(dsl::instance_id.is_null())
+                    .set((
+                        dsl::instance_id.eq(Some(instance_id)),
+                        dsl::time_modified.eq(now),
+                    ))
+                    .check_if_exists::<ExternalIp>(fip_id)
+                    .execute_and_check(&conn)
+                    .await?;
+                Ok(result)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_fip),
+                ),
+            })?;
+
+        // The row we get back is as it was before the update.
+        let mut fip = result.found;
+        match result.status {
+            UpdateStatus::Updated => {
+                fip.instance_id = Some(instance_id);
+                fip.time_modified = now;
+            }
+            UpdateStatus::NotUpdatedButExists => {
+                if fip.time_deleted.is_some() {
+                    return Err(authz_fip.not_found());
+                }
+                if fip.instance_id != Some(instance_id) {
+                    return Err(Error::invalid_request(
+                        "Floating IP is already attached to an instance",
+                    ));
+                }
+            }
+        }
+        FloatingIp::try_from(fip)
+    }
+
+    /// Detach a Floating IP from the instance it's attached to
+    ///
+    /// This succeeds if the Floating IP is not attached to any instance, and
+    /// fails if it's attached to an instance other than `authz_instance`.
+    pub async fn floating_ip_detach(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+        authz_instance: &authz::Instance,
+    ) -> UpdateResult<FloatingIp> {
+        opctx.authorize(authz::Action::Modify, authz_fip).await?;
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        use db::schema::external_ip::dsl;
+        let instance_id = authz_instance.id();
+        let now = Utc::now();
+        let result = diesel::update(dsl::external_ip)
+            .filter(dsl::id.eq(authz_fip.id()))
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::kind.eq(IpKind::Floating))
+            .filter(dsl::instance_id.eq(instance_id))
+            .set((
+                dsl::instance_id.eq(Option::<Uuid>::None),
+                dsl::time_modified.eq(now),
+            ))
+            .check_if_exists::<ExternalIp>(authz_fip.id())
+            .execute_and_check(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_fip),
+                )
+            })?;
+
+        // The row we get back is as it was before the update.
+        let mut fip = result.found;
+        match result.status {
+            UpdateStatus::Updated => {
+                fip.instance_id = None;
+                fip.time_modified = now;
+            }
+            UpdateStatus::NotUpdatedButExists => {
+                if fip.time_deleted.is_some() {
+                    return Err(authz_fip.not_found());
+                }
+                if fip.instance_id.is_some() {
+                    return Err(Error::invalid_request(
+                        "Floating IP is attached to a different instance",
+                    ));
+                }
+            }
+        }
+        FloatingIp::try_from(fip)
+    }
 }
@@ -13,14 +13,17 @@ use crate::db::collection_detach_many::DatastoreDetachManyTarget;
 use crate::db::collection_detach_many::DetachManyError;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::model::Instance;
 use crate::db::model::InstanceRuntimeState;
 use crate::db::model::Name;
+use crate::db::model::ResourceUsage;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
@@ -33,6 +36,7 @@ use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use omicron_common::bail_unless;
 use uuid::Uuid;
 
@@ -66,21 +70,33 @@ impl DataStore {
 
         let gen = instance.runtime().gen;
         let name = instance.name().clone();
-        let instance: Instance = diesel::insert_into(dsl::instance)
-            .values(instance)
-            .on_conflict(dsl::id)
-            .do_nothing()
-            .returning(Instance::as_returning())
-            .get_result_async(self.pool())
+        let project_id = instance.project_id;
+        let usage = ResourceUsage::for_instance(&instance);
+        type TxnError = TransactionError<Error>;
+        let instance: Instance = self
+            .pool()
+            .transaction_async(|conn| async move {
+                let instance = diesel::insert_into(dsl::instance)
+                    .values(instance)
+                    .on_conflict(dsl::id)
+                    .do_nothing()
+                    .returning(Instance::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                DataStore::resource_usage_charge(&conn, project_id, usage)
+                    .await?;
+                Ok(instance)
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::Conflict(
                         ResourceType::Instance,
                         name.as_str(),
                     ),
-                )
+                ),
             })?;
 
         bail_unless!(
@@ -138,6 +154,31 @@ impl DataStore {
         Ok(db_instance)
     }
 
+    /// Replace the set of tags attached to an Instance
+    pub async fn instance_update_tags(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        tags: Vec<Name>,
+    ) -> UpdateResult<Instance> {
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        use db::schema::instance::dsl;
+        diesel::update(dsl::instance)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_instance.id()))
+            .set((dsl::tags.eq(tags), dsl::time_modified.eq(Utc::now())))
+            .returning(Instance::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_instance),
+                )
+            })
+    }
+
     // TODO-design It's tempting to return the updated state of the Instance
     // here because it's convenient for consumers and by using a RETURNING
     // clause, we could ensure that the "update" and "fetch" are atomic.
@@ -208,47 +249,79 @@ impl DataStore {
         let ok_to_detach_disk_state_labels: Vec<_> =
             ok_to_detach_disk_states.iter().map(|s| s.label()).collect();
 
-        let _instance = Instance::detach_resources(
-            authz_instance.id(),
-            instance::table.into_boxed().filter(
-                instance::dsl::state.eq_any(ok_to_delete_instance_states),
-            ),
-            disk::table.into_boxed().filter(
-                disk::dsl::disk_state.eq_any(ok_to_detach_disk_state_labels),
-            ),
-            diesel::update(instance::dsl::instance).set((
-                instance::dsl::state.eq(destroyed),
-                instance::dsl::time_deleted.eq(Utc::now()),
-            )),
-            diesel::update(disk::dsl::disk).set((
-                disk::dsl::disk_state.eq(detached_label),
-                disk::dsl::attach_instance_id.eq(Option::<Uuid>::None),
-            )),
-        )
-        .detach_and_get_result_async(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| match e {
-            DetachManyError::CollectionNotFound => Error::not_found_by_id(
-                ResourceType::Instance,
-                &authz_instance.id(),
-            ),
-            DetachManyError::NoUpdate { collection } => {
-                let instance_state = collection.runtime_state.state.state();
-                match instance_state {
-                    api::external::InstanceState::Stopped
-                    | api::external::InstanceState::Failed => {
-                        Error::internal_error("cannot delete instance")
-                    }
-                    _ => Error::invalid_request(&format!(
-                        "instance cannot be deleted in state \"{}\"",
-                        instance_state,
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let instance = Instance::detach_resources(
+                    authz_instance.id(),
+                    instance::table.into_boxed().filter(
+                        instance::dsl::state
+                            .eq_any(ok_to_delete_instance_states),
+                    ),
+                    disk::table.into_boxed().filter(
+                        disk::dsl::disk_state
+                            .eq_any(ok_to_detach_disk_state_labels),
+                    ),
+                    diesel::update(instance::dsl::instance).set((
+                        instance::dsl::state.eq(destroyed),
+                        instance::dsl::time_deleted.eq(Utc::now()),
+                    )),
+                    diesel::update(disk::dsl::disk).set((
+                        disk::dsl::disk_state.eq(detached_label),
+                        disk::dsl::attach_instance_id.eq(Option::<Uuid>::None),
                     )),
+                )
+                .detach_and_get_result_async(&conn)
+                .await
+                .map_err(|e| match e {
+                    DetachManyError::CollectionNotFound => {
+                        TxnError::CustomError(Error::not_found_by_id(
+                            ResourceType::Instance,
+                            &authz_instance.id(),
+                        ))
+                    }
+                    DetachManyError::NoUpdate { collection } => {
+                        let instance_state =
+                            collection.runtime_state.state.state();
+                        TxnError::CustomError(match instance_state {
+                            api::external::InstanceState::Stopped
+                            | api::external::InstanceState::Failed => {
+                                Error::internal_error("cannot delete instance")
+                            }
+                            _ => Error::invalid_request(&format!(
+                                "instance cannot be deleted in state \"{}\"",
+                                instance_state,
+                            )),
+                        })
+                    }
+                    DetachManyError::DatabaseError(e) => TxnError::Pool(e),
+                })?;
+
+                // Return the instance's resources to the quotas they were
+                // counted against.
+                DataStore::resource_usage_charge(
+                    &conn,
+                    instance.project_id,
+                    -ResourceUsage::for_instance(&instance),
+                )
+                .await?;
+
+                DataStore::role_assignment_delete_all_query(
+                    ResourceType::Instance,
+                    authz_instance.id(),
+                )
+                .execute_async(&conn)
+                .await?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
                 }
-            }
-            DetachManyError::DatabaseError(e) => {
-                public_error_from_diesel_pool(e, ErrorHandler::Server)
-            }
-        })?;
+            })?;
 
         Ok(())
     }
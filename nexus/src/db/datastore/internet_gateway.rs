@@ -0,0 +1,350 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`InternetGateway`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::diesel_pool_result_optional;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::identity::Resource;
+use crate::db::model::InternetGateway;
+use crate::db::model::InternetGatewayUpdate;
+use crate::db::model::Name;
+use crate::db::model::RouteTarget;
+use crate::db::model::RouterRoute;
+use crate::db::model::RouterRouteKind;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+impl DataStore {
+    pub async fn vpc_list_internet_gateways(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        pagparams: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<InternetGateway> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::internet_gateway::dsl;
+        paginated(dsl::internet_gateway, dsl::name, pagparams)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::vpc_id.eq(authz_vpc.id()))
+            .select(InternetGateway::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    pub async fn vpc_create_internet_gateway(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        gateway: InternetGateway,
+    ) -> CreateResult<InternetGateway> {
+        assert_eq!(authz_vpc.id(), gateway.vpc_id);
+        opctx.authorize(authz::Action::CreateChild, authz_vpc).await?;
+
+        use db::schema::internet_gateway::dsl;
+        let name = gateway.name().clone();
+        diesel::insert_into(dsl::internet_gateway)
+            .values(gateway)
+            .on_conflict(dsl::id)
+            .do_nothing()
+            .returning(InternetGateway::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::InternetGateway,
+                        name.as_str(),
+                    ),
+                )
+            })
+    }
+
+    /// Update an internet gateway.
+    ///
+    /// Routes refer to gateways by name, so a gateway can't be renamed while
+    /// any route in its VPC targets it.
+    pub async fn vpc_update_internet_gateway(
+        &self,
+        opctx: &OpContext,
+        authz_gateway: &authz::InternetGateway,
+        db_gateway: &InternetGateway,
+        updates: InternetGatewayUpdate,
+    ) -> UpdateResult<InternetGateway> {
+        opctx.authorize(authz::Action::Modify, authz_gateway).await?;
+
+        let renamed = match &updates.name {
+            Some(name) => name.0 != *db_gateway.name(),
+            None => false,
+        };
+        if renamed && self.internet_gateway_is_routed(opctx, db_gateway).await?
+        {
+            return Err(Error::invalid_request(
+                "internet gateway cannot be renamed while routes target it",
+            ));
+        }
+
+        use db::schema::internet_gateway::dsl;
+        diesel::update(dsl::internet_gateway)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_gateway.id()))
+            .set(updates)
+            .returning(InternetGateway::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_gateway),
+                )
+            })
+    }
+
+    /// Delete an internet gateway, which no route in its VPC may target
+    pub async fn vpc_delete_internet_gateway(
+        &self,
+        opctx: &OpContext,
+        authz_gateway: &authz::InternetGateway,
+        db_gateway: &InternetGateway,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_gateway).await?;
+
+        if self.internet_gateway_is_routed(opctx, db_gateway).await? {
+            return Err(Error::invalid_request(
+                "internet gateway cannot be deleted while routes target it",
+            ));
+        }
+
+        use db::schema::internet_gateway::dsl;
+        let now = Utc::now();
+        diesel::update(dsl::internet_gateway)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_gateway.id()))
+            .set(dsl::time_deleted.eq(now))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_gateway),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Delete all of a VPC's internet gateways, once the VPC itself has been
+    /// deleted
+    pub async fn vpc_delete_all_internet_gateways(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_vpc).await?;
+
+        use db::schema::internet_gateway::dsl;
+        let now = Utc::now();
+        diesel::update(dsl::internet_gateway)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::vpc_id.eq(authz_vpc.id()))
+            .set(dsl::time_deleted.eq(now))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_vpc),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Create the "outbound" internet gateway for any VPCs that never had
+    /// internet gateways, i.e., VPCs created before Nexus supported them.
+    ///
+    /// The default routes of those VPCs already target "outbound", so without
+    /// the gateway their instances have no route out of the rack. VPCs that
+    /// have ever had a gateway are left alone, so this doesn't resurrect a
+    /// gateway that a user deleted.
+    ///
+    /// Returns the number of gateways created.
+    pub async fn vpc_internet_gateway_backfill(
+        &self,
+        opctx: &OpContext,
+    ) -> Result<usize, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::DATABASE).await?;
+
+        use db::schema::internet_gateway::dsl;
+        use db::schema::vpc;
+
+        // TODO-scalability: Like the VPC Subnet route backfill, this loads
+        // every VPC in the fleet.
+        let vpc_ids = vpc::table
+            .filter(vpc::time_deleted.is_null())
+            .filter(diesel::dsl::not(diesel::dsl::exists(
+                dsl::internet_gateway.filter(dsl::vpc_id.eq(vpc::id)),
+            )))
+            .select(vpc::id)
+            .load_async::<Uuid>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        let mut ncreated = 0;
+        for vpc_id in vpc_ids {
+            // If another Nexus created the gateway since we listed the VPCs,
+            // this conflicts on the gateway name and inserts nothing.
+            ncreated += diesel::insert_into(dsl::internet_gateway)
+                .values(InternetGateway::new_outbound(Uuid::new_v4(), vpc_id))
+                .on_conflict_do_nothing()
+                .execute_async(self.pool_authorized(opctx).await?)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                })?;
+        }
+        Ok(ncreated)
+    }
+
+    /// Returns true if any route in the gateway's VPC targets it
+    async fn internet_gateway_is_routed(
+        &self,
+        opctx: &OpContext,
+        db_gateway: &InternetGateway,
+    ) -> Result<bool, Error> {
+        use db::schema::router_route;
+        use db::schema::vpc_router;
+        let target = RouteTarget(external::RouteTarget::InternetGateway(
+            db_gateway.name().clone(),
+        ));
+        diesel_pool_result_optional(
+            router_route::table
+                .filter(router_route::time_deleted.is_null())
+                .filter(router_route::target.eq(target))
+                .filter(
+                    router_route::vpc_router_id.eq_any(
+                        vpc_router::table
+                            .filter(vpc_router::vpc_id.eq(db_gateway.vpc_id))
+                            .filter(vpc_router::time_deleted.is_null())
+                            .select(vpc_router::id),
+                    ),
+                )
+                .select(router_route::id)
+                .limit(1)
+                .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map(|route| route.is_some())
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the internet gateways of a VPC.
+    ///
+    /// Like [`DataStore::vpc_list_active_peers`], this is used when resolving
+    /// a VPC's routes, whatever the caller's access to the gateways.
+    pub async fn resolve_vpc_internet_gateways(
+        &self,
+        vpc_id: Uuid,
+    ) -> ListResultVec<InternetGateway> {
+        use db::schema::internet_gateway::dsl;
+        dsl::internet_gateway
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::vpc_id.eq(vpc_id))
+            .select(InternetGateway::as_select())
+            .load_async(self.pool())
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Returns the IP Pools from which an instance's source NAT address should
+    /// be allocated, in order of preference.
+    ///
+    /// These are the pools of the internet gateway targeted by the default
+    /// route of the VPC containing the instance's primary network interface.
+    /// This is empty if the instance has no network interfaces, if the
+    /// default route doesn't target an existing gateway, or if the gateway
+    /// has no pools of its own.
+    pub(super) async fn instance_internet_gateway_ip_pools(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> LookupResult<Vec<Uuid>> {
+        use db::schema::internet_gateway::dsl;
+        use db::schema::network_interface;
+        use db::schema::router_route;
+        use db::schema::vpc;
+
+        let vpc_id = diesel_pool_result_optional(
+            network_interface::table
+                .filter(network_interface::instance_id.eq(instance_id))
+                .filter(network_interface::is_primary.eq(true))
+                .filter(network_interface::time_deleted.is_null())
+                .select(network_interface::vpc_id)
+                .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?;
+        let vpc_id = match vpc_id {
+            Some(vpc_id) => vpc_id,
+            None => return Ok(vec![]),
+        };
+
+        let default_route = diesel_pool_result_optional(
+            router_route::table
+                .filter(router_route::time_deleted.is_null())
+                .filter(
+                    router_route::kind.eq(RouterRouteKind(
+                        external::RouterRouteKind::Default,
+                    )),
+                )
+                .filter(
+                    router_route::vpc_router_id.eq_any(
+                        vpc::table
+                            .filter(vpc::id.eq(vpc_id))
+                            .select(vpc::system_router_id),
+                    ),
+                )
+                .select(RouterRoute::as_select())
+                .first_async(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?;
+        let gateway_name = match default_route.map(|route| route.target.0) {
+            Some(external::RouteTarget::InternetGateway(name)) => name,
+            _ => return Ok(vec![]),
+        };
+
+        let ip_pool_ids = diesel_pool_result_optional(
+            dsl::internet_gateway
+                .filter(dsl::time_deleted.is_null())
+                .filter(dsl::vpc_id.eq(vpc_id))
+                .filter(dsl::name.eq(Name(gateway_name)))
+                .select(dsl::ip_pool_ids)
+                .first_async::<Vec<Uuid>>(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?;
+        Ok(ip_pool_ids.unwrap_or_default())
+    }
+}
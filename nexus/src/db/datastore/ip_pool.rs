@@ -13,6 +13,7 @@ use crate::db::collection_insert::DatastoreCollection;
 use crate::db::error::diesel_pool_result_optional;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::model::IpPool;
@@ -23,7 +24,9 @@ use crate::db::pagination::paginated;
 use crate::db::queries::ip_pool::FilterOverlappingIpRanges;
 use crate::external_api::params;
 use crate::external_api::shared::IpRange;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
 use chrono::Utc;
 use diesel::prelude::*;
 use ipnetwork::IpNetwork;
@@ -177,10 +180,34 @@ impl DataStore {
         authz_pool: &authz::IpPool,
         db_pool: &IpPool,
     ) -> DeleteResult {
+        use db::schema::internet_gateway;
         use db::schema::ip_pool::dsl;
         use db::schema::ip_pool_range;
+        use db::schema::project_settings;
         opctx.authorize(authz::Action::Delete, authz_pool).await?;
 
+        // Verify the pool isn't used by any internet gateway
+        let gateway = diesel_pool_result_optional(
+            internet_gateway::dsl::internet_gateway
+                .filter(
+                    internet_gateway::dsl::ip_pool_ids
+                        .contains(vec![authz_pool.id()]),
+                )
+                .filter(internet_gateway::dsl::time_deleted.is_null())
+                .select(internet_gateway::dsl::id)
+                .limit(1)
+                .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?;
+        if gateway.is_some() {
+            return Err(Error::InvalidRequest {
+                message: "IP Pool cannot be deleted while it is used by an \
+                    internet gateway"
+                    .to_string(),
+            });
+        }
+
         // Verify there are no IP ranges still in this pool
         let range = diesel_pool_result_optional(
             ip_pool_range::dsl::ip_pool_range
@@ -203,29 +230,56 @@ impl DataStore {
         // Delete the pool, conditional on the rcgen not having changed. This
         // protects the delete from occuring if clients created a new IP range
         // in between the above check for children and this query.
+        //
+        // Whether the pool is the default of any project is checked in the
+        // same transaction as the delete, since a project could otherwise make
+        // it the default in between.
+        let pool_id = authz_pool.id();
         let now = Utc::now();
-        let updated_rows = diesel::update(dsl::ip_pool)
+        let delete_query = diesel::update(dsl::ip_pool)
             .filter(dsl::rack_id.is_null())
             .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_pool.id()))
+            .filter(dsl::id.eq(pool_id))
             .filter(dsl::rcgen.eq(db_pool.rcgen))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool_authorized(opctx).await?)
+            .set(dsl::time_deleted.eq(now));
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let project = project_settings::dsl::project_settings
+                    .filter(
+                        project_settings::dsl::default_ip_pool_id.eq(pool_id),
+                    )
+                    .select(project_settings::dsl::project_id)
+                    .limit(1)
+                    .first_async::<Uuid>(&conn)
+                    .await
+                    .optional()?;
+                if project.is_some() {
+                    return Err(TxnError::CustomError(Error::InvalidRequest {
+                        message: "IP Pool cannot be deleted while it is the \
+                            default IP Pool of a project"
+                            .to_string(),
+                    }));
+                }
+                let updated_rows = delete_query.execute_async(&conn).await?;
+                if updated_rows == 0 {
+                    return Err(TxnError::CustomError(Error::InvalidRequest {
+                        message: "deletion failed due to concurrent \
+                            modification"
+                            .to_string(),
+                    }));
+                }
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_pool),
-                )
-            })?;
-
-        if updated_rows == 0 {
-            return Err(Error::InvalidRequest {
-                message: "deletion failed due to concurrent modification"
-                    .to_string(),
-            });
-        }
-        Ok(())
+                ),
+            })
     }
 
     pub async fn ip_pool_update(
@@ -40,6 +40,8 @@ use std::net::Ipv6Addr;
 use std::sync::Arc;
 use uuid::Uuid;
 
+mod api_token;
+mod audit_log;
 mod console_session;
 mod dataset;
 mod device_auth;
@@ -48,16 +50,20 @@ mod external_ip;
 mod global_image;
 mod identity_provider;
 mod instance;
+mod internet_gateway;
 mod ip_pool;
 mod network_interface;
 mod organization;
 mod oximeter;
 mod project;
+mod project_settings;
 mod rack;
 mod region;
 mod region_snapshot;
+mod resource_quota;
 mod role;
 mod saga;
+mod scim;
 mod service;
 mod silo;
 mod silo_group;
@@ -68,6 +74,7 @@ mod ssh_key;
 mod update;
 mod volume;
 mod vpc;
+mod vpc_peering;
 mod zpool;
 
 pub use volume::CrucibleResources;
@@ -328,6 +335,9 @@ mod test {
             time_created: Utc::now() - Duration::minutes(5),
             time_last_used: Utc::now() - Duration::minutes(5),
             silo_user_id,
+            id: Uuid::new_v4(),
+            user_agent: None,
+            client_address: None,
         };
 
         let _ = datastore
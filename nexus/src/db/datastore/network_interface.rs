@@ -24,17 +24,22 @@ use crate::db::pagination::paginated;
 use crate::db::queries::network_interface;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
 use chrono::Utc;
 use diesel::prelude::*;
+use ipnetwork::IpNetwork;
 use omicron_common::api::external;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use sled_agent_client::types as sled_client_types;
+use std::net::IpAddr;
+use uuid::Uuid;
 
 /// OPTE requires information that's currently split across the network
 /// interface and VPC subnet tables.
@@ -262,6 +267,36 @@ impl DataStore {
         .await
     }
 
+    /// Return information about all VNICs connected to a VPC whose instance
+    /// carries the given tag, as required for the sled agent to instantiate
+    /// firewall rules via OPTE.
+    pub(crate) async fn derive_vpc_tagged_network_interface_info(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        tag: &Name,
+    ) -> ListResultVec<sled_client_types::NetworkInterface> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::instance;
+        use db::schema::network_interface;
+        self.derive_network_interface_info(
+            opctx,
+            network_interface::table
+                .filter(network_interface::vpc_id.eq(authz_vpc.id()))
+                .filter(
+                    network_interface::instance_id.eq_any(
+                        instance::table
+                            .filter(instance::time_deleted.is_null())
+                            .filter(instance::tags.contains(vec![tag.clone()]))
+                            .select(instance::id),
+                    ),
+                )
+                .into_boxed(),
+        )
+        .await
+    }
+
     /// Return information about all VNICs connected to a VpcSubnet required
     /// for the sled agent to instantiate firewall rules via OPTE.
     pub(crate) async fn derive_subnet_network_interface_info(
@@ -281,6 +316,118 @@ impl DataStore {
         .await
     }
 
+    /// Return information about all VNICs in the VPC Subnets associated with
+    /// a custom VPC router, as required for the sled agent to program the
+    /// router's routes via OPTE.
+    pub(crate) async fn derive_router_network_interface_info(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        router_id: Uuid,
+    ) -> ListResultVec<sled_client_types::NetworkInterface> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::network_interface;
+        use db::schema::vpc_subnet;
+        self.derive_network_interface_info(
+            opctx,
+            network_interface::table
+                .filter(network_interface::vpc_id.eq(authz_vpc.id()))
+                .filter(
+                    network_interface::subnet_id.eq_any(
+                        vpc_subnet::table
+                            .filter(vpc_subnet::time_deleted.is_null())
+                            .filter(vpc_subnet::custom_router_id.eq(router_id))
+                            .select(vpc_subnet::id),
+                    ),
+                )
+                .into_boxed(),
+        )
+        .await
+    }
+
+    /// Return the first network interface in a VPC matched by a partially
+    /// constructed query over the network interface table, along with the
+    /// instance and VPC Subnet it belongs to. Primary interfaces are
+    /// preferred.
+    async fn vpc_lookup_network_interface(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        partial_query: BoxedQuery<db::schema::network_interface::table>,
+    ) -> LookupResult<Option<(NetworkInterface, Instance, VpcSubnet)>> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::instance;
+        use db::schema::network_interface;
+        use db::schema::vpc_subnet;
+        partial_query
+            .filter(network_interface::vpc_id.eq(authz_vpc.id()))
+            .filter(network_interface::time_deleted.is_null())
+            .inner_join(
+                instance::table
+                    .on(network_interface::instance_id.eq(instance::id)),
+            )
+            .inner_join(
+                vpc_subnet::table
+                    .on(network_interface::subnet_id.eq(vpc_subnet::id)),
+            )
+            .filter(instance::time_deleted.is_null())
+            .order_by((
+                network_interface::is_primary.desc(),
+                network_interface::slot,
+            ))
+            .select((
+                NetworkInterface::as_select(),
+                Instance::as_select(),
+                VpcSubnet::as_select(),
+            ))
+            .first_async(self.pool_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Look up the network interface in a VPC holding an IP address, along
+    /// with the instance and VPC Subnet it belongs to.
+    pub async fn vpc_lookup_network_interface_by_ip(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        ip: IpAddr,
+    ) -> LookupResult<Option<(NetworkInterface, Instance, VpcSubnet)>> {
+        use db::schema::network_interface;
+        self.vpc_lookup_network_interface(
+            opctx,
+            authz_vpc,
+            network_interface::table
+                .filter(network_interface::ip.eq(IpNetwork::from(ip)))
+                .into_boxed(),
+        )
+        .await
+    }
+
+    /// Look up an instance's network interface in a VPC, along with the VPC
+    /// Subnet it belongs to. The primary interface is preferred.
+    pub async fn vpc_lookup_instance_network_interface(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        authz_instance: &authz::Instance,
+    ) -> LookupResult<Option<(NetworkInterface, Instance, VpcSubnet)>> {
+        opctx.authorize(authz::Action::ListChildren, authz_instance).await?;
+
+        use db::schema::network_interface;
+        self.vpc_lookup_network_interface(
+            opctx,
+            authz_vpc,
+            network_interface::table
+                .filter(network_interface::instance_id.eq(authz_instance.id()))
+                .into_boxed(),
+        )
+        .await
+    }
+
     /// List network interfaces associated with a given instance.
     pub async fn instance_list_network_interfaces(
         &self,
@@ -77,6 +77,7 @@ impl DataStore {
         opctx.authorize(authz::Action::Delete, authz_project).await?;
 
         use db::schema::project::dsl;
+        use db::schema::project_settings;
 
         let now = Utc::now();
         diesel::update(dsl::project)
@@ -92,6 +93,16 @@ impl DataStore {
                     ErrorHandler::NotFoundByResource(authz_project),
                 )
             })?;
+
+        // Drop the project's settings, so that they no longer prevent the
+        // deletion of the project's default IP Pool.
+        diesel::delete(project_settings::dsl::project_settings)
+            .filter(project_settings::dsl::project_id.eq(authz_project.id()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
         Ok(())
     }
 
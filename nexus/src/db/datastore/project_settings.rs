@@ -0,0 +1,216 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`ProjectSettings`].
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::lookup::LookupPath;
+use crate::db::model::Name;
+use crate::db::model::ProjectSettings;
+use crate::db::model::VpcSubnet;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Fetch the settings of a project
+    pub async fn project_settings_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+    ) -> LookupResult<ProjectSettings> {
+        opctx.authorize(authz::Action::Read, authz_project).await?;
+        self.project_settings_get(opctx, authz_project.id()).await
+    }
+
+    /// Replace the settings of a project
+    ///
+    /// The caller is responsible for checking that the default resources
+    /// named by the settings belong to the project.  They're checked again
+    /// here, in the same transaction as the update, to make sure they haven't
+    /// been deleted since.  Deleting a default resource checks the settings in
+    /// its own transaction, so either the deletion or this update fails.
+    pub async fn project_settings_update(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        settings: ProjectSettings,
+    ) -> UpdateResult<ProjectSettings> {
+        opctx.authorize(authz::Action::Modify, authz_project).await?;
+
+        use db::schema::ip_pool;
+        use db::schema::project_settings::dsl;
+        use db::schema::vpc;
+        use db::schema::vpc_subnet;
+
+        let default_vpc_id = settings.default_vpc_id;
+        let default_vpc_subnet_id = settings.default_vpc_subnet_id;
+        let default_ip_pool_id = settings.default_ip_pool_id;
+        let update_query = diesel::insert_into(dsl::project_settings)
+            .values(settings)
+            .on_conflict(dsl::project_id)
+            .do_update()
+            .set((
+                dsl::time_modified.eq(excluded(dsl::time_modified)),
+                dsl::default_vpc_id.eq(excluded(dsl::default_vpc_id)),
+                dsl::default_vpc_subnet_id
+                    .eq(excluded(dsl::default_vpc_subnet_id)),
+                dsl::default_ip_pool_id.eq(excluded(dsl::default_ip_pool_id)),
+            ))
+            .returning(ProjectSettings::as_returning());
+
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                if let Some(id) = default_vpc_id {
+                    vpc::dsl::vpc
+                        .filter(vpc::dsl::id.eq(id))
+                        .filter(vpc::dsl::time_deleted.is_null())
+                        .select(vpc::dsl::id)
+                        .first_async::<Uuid>(&conn)
+                        .await
+                        .optional()?
+                        .ok_or_else(|| {
+                            TxnError::CustomError(Error::not_found_by_id(
+                                ResourceType::Vpc,
+                                &id,
+                            ))
+                        })?;
+                }
+                if let Some(id) = default_vpc_subnet_id {
+                    vpc_subnet::dsl::vpc_subnet
+                        .filter(vpc_subnet::dsl::id.eq(id))
+                        .filter(vpc_subnet::dsl::time_deleted.is_null())
+                        .select(vpc_subnet::dsl::id)
+                        .first_async::<Uuid>(&conn)
+                        .await
+                        .optional()?
+                        .ok_or_else(|| {
+                            TxnError::CustomError(Error::not_found_by_id(
+                                ResourceType::VpcSubnet,
+                                &id,
+                            ))
+                        })?;
+                }
+                if let Some(id) = default_ip_pool_id {
+                    ip_pool::dsl::ip_pool
+                        .filter(ip_pool::dsl::id.eq(id))
+                        .filter(ip_pool::dsl::time_deleted.is_null())
+                        .select(ip_pool::dsl::id)
+                        .first_async::<Uuid>(&conn)
+                        .await
+                        .optional()?
+                        .ok_or_else(|| {
+                            TxnError::CustomError(Error::not_found_by_id(
+                                ResourceType::IpPool,
+                                &id,
+                            ))
+                        })?;
+                }
+                Ok(update_query.get_result_async(&conn).await?)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// Fetch the settings of a project, without checking that the caller can
+    /// read the project
+    pub(super) async fn project_settings_get(
+        &self,
+        opctx: &OpContext,
+        project_id: Uuid,
+    ) -> LookupResult<ProjectSettings> {
+        use db::schema::project_settings::dsl;
+        let settings = dsl::project_settings
+            .filter(dsl::project_id.eq(project_id))
+            .select(ProjectSettings::as_select())
+            .first_async(self.pool_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(settings.unwrap_or_else(|| ProjectSettings::new(project_id)))
+    }
+
+    /// Look up the VPC Subnet in which to create a network interface in a
+    /// project, falling back to the project's defaults for the VPC or VPC
+    /// Subnet not provided
+    pub async fn project_lookup_interface_subnet(
+        &self,
+        opctx: &OpContext,
+        project_id: Uuid,
+        vpc_name: Option<&Name>,
+        subnet_name: Option<&Name>,
+    ) -> LookupResult<(authz::Vpc, authz::VpcSubnet, VpcSubnet)> {
+        let settings = self.project_settings_get(opctx, project_id).await?;
+        let default_name = Name("default".parse().unwrap());
+
+        let authz_vpc = match (vpc_name, settings.default_vpc_id) {
+            (Some(vpc_name), _) => {
+                let (.., authz_vpc) = LookupPath::new(opctx, self)
+                    .project_id(project_id)
+                    .vpc_name(vpc_name)
+                    .lookup_for(authz::Action::Read)
+                    .await?;
+                authz_vpc
+            }
+            (None, Some(vpc_id)) => {
+                let (.., authz_vpc) = LookupPath::new(opctx, self)
+                    .vpc_id(vpc_id)
+                    .lookup_for(authz::Action::Read)
+                    .await?;
+                authz_vpc
+            }
+            (None, None) => {
+                let (.., authz_vpc) = LookupPath::new(opctx, self)
+                    .project_id(project_id)
+                    .vpc_name(&default_name)
+                    .lookup_for(authz::Action::Read)
+                    .await?;
+                authz_vpc
+            }
+        };
+
+        // The default VPC Subnet only applies to interfaces created in the VPC
+        // containing it.
+        if subnet_name.is_none() {
+            if let Some(subnet_id) = settings.default_vpc_subnet_id {
+                let (.., authz_subnet, db_subnet) =
+                    LookupPath::new(opctx, self)
+                        .vpc_subnet_id(subnet_id)
+                        .fetch()
+                        .await?;
+                if db_subnet.vpc_id == authz_vpc.id() {
+                    return Ok((authz_vpc, authz_subnet, db_subnet));
+                }
+            }
+        }
+        let (.., authz_subnet, db_subnet) = LookupPath::new(opctx, self)
+            .vpc_id(authz_vpc.id())
+            .vpc_subnet_name(subnet_name.unwrap_or(&default_name))
+            .fetch()
+            .await?;
+        Ok((authz_vpc, authz_subnet, db_subnet))
+    }
+}
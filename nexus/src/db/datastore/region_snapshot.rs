@@ -10,9 +10,11 @@ use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
 use crate::db::model::RegionSnapshot;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
 use diesel::prelude::*;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::LookupResult;
 use uuid::Uuid;
 
 impl DataStore {
@@ -31,6 +33,25 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    pub async fn region_snapshot_get(
+        &self,
+        dataset_id: Uuid,
+        region_id: Uuid,
+        snapshot_id: Uuid,
+    ) -> LookupResult<Option<RegionSnapshot>> {
+        use db::schema::region_snapshot::dsl;
+
+        dsl::region_snapshot
+            .filter(dsl::dataset_id.eq(dataset_id))
+            .filter(dsl::region_id.eq(region_id))
+            .filter(dsl::snapshot_id.eq(snapshot_id))
+            .select(RegionSnapshot::as_select())
+            .first_async::<RegionSnapshot>(self.pool())
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     pub async fn region_snapshot_remove(
         &self,
         dataset_id: Uuid,
@@ -0,0 +1,408 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`ResourceQuota`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::model::Disk;
+use crate::db::model::ExternalIp;
+use crate::db::model::Instance;
+use crate::db::model::ResourceQuota;
+use crate::db::model::ResourceUsage;
+use crate::db::pool::DbConnection;
+use crate::external_api::params;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use async_bb8_diesel::PoolError;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::UpdateResult;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Name of the "db_metadata" row recording when resource usage was first
+/// counted (see [`DataStore::resource_usage_backfill()`])
+const RESOURCE_USAGE_BACKFILLED: &str = "resource_usage_backfilled";
+
+impl DataStore {
+    /// Fetch the quotas of a Silo, and the resources allocated within it
+    pub async fn silo_quotas_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo_quotas: &authz::SiloQuotas,
+    ) -> LookupResult<ResourceQuota> {
+        opctx.authorize(authz::Action::Read, authz_silo_quotas).await?;
+        self.resource_quota_get(opctx, authz_silo_quotas.silo().id()).await
+    }
+
+    /// Replace the quotas of a Silo
+    pub async fn silo_quotas_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo_quotas: &authz::SiloQuotas,
+        params: &params::ResourceQuotasUpdate,
+    ) -> UpdateResult<ResourceQuota> {
+        opctx.authorize(authz::Action::Modify, authz_silo_quotas).await?;
+        self.resource_quota_set_limits(
+            opctx,
+            authz_silo_quotas.silo().id(),
+            params,
+        )
+        .await
+    }
+
+    /// Fetch the quotas of a Project, and the resources allocated within it
+    pub async fn project_quotas_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_project_quotas: &authz::ProjectQuotas,
+    ) -> LookupResult<ResourceQuota> {
+        opctx.authorize(authz::Action::Read, authz_project_quotas).await?;
+        self.resource_quota_get(opctx, authz_project_quotas.project().id())
+            .await
+    }
+
+    /// Replace the quotas of a Project
+    pub async fn project_quotas_update(
+        &self,
+        opctx: &OpContext,
+        authz_project_quotas: &authz::ProjectQuotas,
+        params: &params::ResourceQuotasUpdate,
+    ) -> UpdateResult<ResourceQuota> {
+        opctx.authorize(authz::Action::Modify, authz_project_quotas).await?;
+        self.resource_quota_set_limits(
+            opctx,
+            authz_project_quotas.project().id(),
+            params,
+        )
+        .await
+    }
+
+    async fn resource_quota_get(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+    ) -> LookupResult<ResourceQuota> {
+        use db::schema::resource_quota::dsl;
+        let quota = dsl::resource_quota
+            .filter(dsl::id.eq(id))
+            .select(ResourceQuota::as_select())
+            .first_async(self.pool_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(quota.unwrap_or_else(|| ResourceQuota::new(id)))
+    }
+
+    async fn resource_quota_set_limits(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+        params: &params::ResourceQuotasUpdate,
+    ) -> UpdateResult<ResourceQuota> {
+        let mut quota = ResourceQuota::new(id);
+        quota.set_limits(params);
+
+        use db::schema::resource_quota::dsl;
+        diesel::insert_into(dsl::resource_quota)
+            .values(quota)
+            .on_conflict(dsl::id)
+            .do_update()
+            .set((
+                dsl::time_modified.eq(excluded(dsl::time_modified)),
+                dsl::cpus_limit.eq(excluded(dsl::cpus_limit)),
+                dsl::memory_limit.eq(excluded(dsl::memory_limit)),
+                dsl::disk_limit.eq(excluded(dsl::disk_limit)),
+                dsl::snapshots_limit.eq(excluded(dsl::snapshots_limit)),
+                dsl::external_ips_limit.eq(excluded(dsl::external_ips_limit)),
+            ))
+            .returning(ResourceQuota::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Count the resources allocated within every Project and Silo, if that
+    /// hasn't been done before
+    ///
+    /// Resources are counted as they're allocated and freed, but those
+    /// allocated before Nexus counted them never were, and freeing them would
+    /// take the count below zero.  The first time this runs against a
+    /// database, it replaces the counts with the resources that actually
+    /// exist, leaving the limits as they are.  It then records that in
+    /// "db_metadata", so that later calls do nothing: from then on, the counts
+    /// are kept up to date as resources come and go.
+    pub async fn resource_usage_backfill(
+        &self,
+        opctx: &OpContext,
+    ) -> Result<(), Error> {
+        opctx.authorize(authz::Action::Modify, &authz::DATABASE).await?;
+
+        use db::schema::db_metadata;
+        use db::schema::disk;
+        use db::schema::external_ip;
+        use db::schema::instance;
+        use db::schema::organization;
+        use db::schema::project;
+        use db::schema::resource_quota::dsl;
+        use db::schema::snapshot;
+
+        // TODO-scalability: The first time, this loads every instance, disk,
+        // snapshot, and external IP in the fleet, in a single transaction so
+        // that nothing is allocated or freed while they're counted.
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let done = db_metadata::table
+                    .filter(db_metadata::name.eq(RESOURCE_USAGE_BACKFILLED))
+                    .select(db_metadata::value)
+                    .first_async::<String>(&conn)
+                    .await
+                    .optional()?
+                    .is_some();
+                if done {
+                    return Ok(());
+                }
+
+                // Start from zero for every Silo and Project that has had
+                // anything counted against it, in case it has since freed
+                // everything.  Locking these rows, as
+                // `resource_usage_charge()` does, makes allocations and frees
+                // in those Silos and Projects wait until the counts are
+                // replaced, rather than conflicting with this transaction.
+                let mut usage: HashMap<Uuid, ResourceUsage> =
+                    dsl::resource_quota
+                        .select(dsl::id)
+                        .for_update()
+                        .load_async::<Uuid>(&conn)
+                        .await?
+                        .into_iter()
+                        .map(|id| (id, ResourceUsage::default()))
+                        .collect();
+
+                // Projects are charged without regard to whether they've been
+                // deleted, so this doesn't filter deleted ones out either.
+                let silo_ids: HashMap<Uuid, Uuid> = project::table
+                    .inner_join(
+                        organization::table
+                            .on(organization::id.eq(project::organization_id)),
+                    )
+                    .select((project::id, organization::silo_id))
+                    .load_async::<(Uuid, Uuid)>(&conn)
+                    .await?
+                    .into_iter()
+                    .collect();
+
+                let mut allocated: Vec<(Uuid, ResourceUsage)> = Vec::new();
+                allocated.extend(
+                    instance::table
+                        .filter(instance::time_deleted.is_null())
+                        .select(Instance::as_select())
+                        .load_async::<Instance>(&conn)
+                        .await?
+                        .iter()
+                        .map(|i| {
+                            (i.project_id, ResourceUsage::for_instance(i))
+                        }),
+                );
+                allocated.extend(
+                    disk::table
+                        .filter(disk::time_deleted.is_null())
+                        .select(Disk::as_select())
+                        .load_async::<Disk>(&conn)
+                        .await?
+                        .iter()
+                        .map(|d| (d.project_id, ResourceUsage::for_disk(d))),
+                );
+                allocated.extend(
+                    snapshot::table
+                        .filter(snapshot::time_deleted.is_null())
+                        .select(snapshot::project_id)
+                        .load_async::<Uuid>(&conn)
+                        .await?
+                        .into_iter()
+                        .map(|id| (id, ResourceUsage::for_snapshot())),
+                );
+                allocated.extend(
+                    external_ip::table
+                        .filter(external_ip::time_deleted.is_null())
+                        .select(ExternalIp::as_select())
+                        .load_async::<ExternalIp>(&conn)
+                        .await?
+                        .iter()
+                        .filter_map(|ip| {
+                            ip.project_id.map(|id| {
+                                (id, ResourceUsage::for_external_ip(ip))
+                            })
+                        }),
+                );
+
+                for (project_id, allocated) in allocated {
+                    *usage.entry(project_id).or_default() += allocated;
+                    if let Some(silo_id) = silo_ids.get(&project_id) {
+                        *usage.entry(*silo_id).or_default() += allocated;
+                    }
+                }
+
+                for (id, usage) in usage {
+                    let mut quota = ResourceQuota::new(id);
+                    quota.set_usage(&usage);
+                    diesel::insert_into(dsl::resource_quota)
+                        .values(quota)
+                        .on_conflict(dsl::id)
+                        .do_update()
+                        .set((
+                            dsl::time_modified.eq(excluded(dsl::time_modified)),
+                            dsl::cpus_used.eq(excluded(dsl::cpus_used)),
+                            dsl::memory_used.eq(excluded(dsl::memory_used)),
+                            dsl::disk_used.eq(excluded(dsl::disk_used)),
+                            dsl::snapshots_used
+                                .eq(excluded(dsl::snapshots_used)),
+                            dsl::external_ips_used
+                                .eq(excluded(dsl::external_ips_used)),
+                        ))
+                        .execute_async(&conn)
+                        .await?;
+                }
+
+                diesel::insert_into(db_metadata::table)
+                    .values((
+                        db_metadata::name.eq(RESOURCE_USAGE_BACKFILLED),
+                        db_metadata::value.eq(Utc::now().to_rfc3339()),
+                    ))
+                    .execute_async(&conn)
+                    .await?;
+                Ok(())
+            })
+            .await
+            .map_err(|e: TxnError| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// Counts `usage` against the quotas of the Project `project_id` and of
+    /// its Silo, as part of the transaction in which the resources are
+    /// allocated or freed
+    ///
+    /// This fails if allocating the resources would exceed either quota, in
+    /// which case the caller is expected to abandon the transaction.  Freeing
+    /// resources is never refused on account of the limits, but it relies on
+    /// the resources having been counted in the first place (see
+    /// [`DataStore::resource_usage_backfill`]): the database won't let the
+    /// count go below zero.
+    pub(super) async fn resource_usage_charge<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        project_id: Uuid,
+        usage: ResourceUsage,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+    {
+        DataStore::resource_usage_update(conn, project_id, usage, true).await
+    }
+
+    /// Like [`DataStore::resource_usage_charge()`], but for resources being
+    /// put back after they were freed (as when unwinding a saga that freed
+    /// them), which is never refused on account of the limits
+    pub(super) async fn resource_usage_restore<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        project_id: Uuid,
+        usage: ResourceUsage,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+    {
+        DataStore::resource_usage_update(conn, project_id, usage, false).await
+    }
+
+    async fn resource_usage_update<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        project_id: Uuid,
+        usage: ResourceUsage,
+        check_limits: bool,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+    {
+        type TxnError = TransactionError<Error>;
+        let db_err = |e: ConnErr| TxnError::Pool(PoolError::from(e));
+
+        if usage.is_empty() {
+            return Ok(());
+        }
+
+        use db::schema::organization;
+        use db::schema::project;
+        use db::schema::resource_quota::dsl;
+        let silo_id = project::table
+            .inner_join(
+                organization::table
+                    .on(organization::id.eq(project::organization_id)),
+            )
+            .filter(project::id.eq(project_id))
+            .select(organization::silo_id)
+            .get_result_async::<Uuid>(conn)
+            .await
+            .map_err(db_err)?;
+
+        for (scope, id) in [("project", project_id), ("silo", silo_id)] {
+            diesel::insert_into(dsl::resource_quota)
+                .values(ResourceQuota::new(id))
+                .on_conflict(dsl::id)
+                .do_nothing()
+                .execute_async(conn)
+                .await
+                .map_err(db_err)?;
+            let quota = dsl::resource_quota
+                .filter(dsl::id.eq(id))
+                .select(ResourceQuota::as_select())
+                .for_update()
+                .get_result_async(conn)
+                .await
+                .map_err(db_err)?;
+            if check_limits {
+                quota
+                    .check_allocation(scope, &usage)
+                    .map_err(TxnError::CustomError)?;
+            }
+            diesel::update(dsl::resource_quota)
+                .filter(dsl::id.eq(id))
+                .set((
+                    dsl::cpus_used.eq(dsl::cpus_used + usage.cpus),
+                    dsl::memory_used.eq(dsl::memory_used + usage.memory),
+                    dsl::disk_used.eq(dsl::disk_used + usage.disk),
+                    dsl::snapshots_used
+                        .eq(dsl::snapshots_used + usage.snapshots),
+                    dsl::external_ips_used
+                        .eq(dsl::external_ips_used + usage.external_ips),
+                ))
+                .execute_async(conn)
+                .await
+                .map_err(db_err)?;
+        }
+        Ok(())
+    }
+}
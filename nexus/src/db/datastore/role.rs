@@ -331,4 +331,24 @@ impl DataStore {
 
         Ok((delete_old_query, insert_new_query))
     }
+
+    /// Returns a query that removes all of the externally-visible role
+    /// assignments on the resource identified by `resource_type` and
+    /// `resource_id`
+    ///
+    /// This is meant to run in the same transaction that deletes the resource,
+    /// so that the assignments never outlive it.  Deleted resources cannot be
+    /// looked up, so leftover assignments would be inert, but there's no
+    /// reason to keep them around.  Callers are responsible for having
+    /// authorized the deletion of the resource itself.
+    pub fn role_assignment_delete_all_query(
+        resource_type: ResourceType,
+        resource_id: Uuid,
+    ) -> impl RunnableQueryNoReturn {
+        use db::schema::role_assignment::dsl;
+        diesel::delete(dsl::role_assignment)
+            .filter(dsl::resource_id.eq(resource_id))
+            .filter(dsl::resource_type.eq(resource_type.to_string()))
+            .filter(dsl::identity_type.ne(IdentityType::UserBuiltin))
+    }
 }
@@ -5,20 +5,28 @@
 //! [`DataStore`] methods on [`db::saga_types::Saga`]s.
 
 use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
 use crate::db;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::model::Generation;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
 impl DataStore {
@@ -38,25 +46,78 @@ impl DataStore {
         Ok(())
     }
 
+    /// Record an event for a saga owned by SEC `current_sec`
+    ///
+    /// This fails without recording anything if `current_sec` no longer owns
+    /// the saga at generation `current_adopt_generation`, as when an operator
+    /// has abandoned the saga or handed it to another SEC.
     pub async fn saga_create_event(
         &self,
         event: &db::saga_types::SagaNodeEvent,
+        current_sec: db::saga_types::SecId,
+        current_adopt_generation: Generation,
     ) -> Result<(), Error> {
-        use db::schema::saga_node_event::dsl;
+        #[derive(Debug, thiserror::Error)]
+        enum SagaEventError {
+            #[error("saga is not owned by this SEC")]
+            NotOwned,
+        }
+        type TxnError = TransactionError<SagaEventError>;
 
-        // TODO-robustness This INSERT ought to be conditional on this SEC still
-        // owning this saga.
-        diesel::insert_into(dsl::saga_node_event)
-            .values(event.clone())
-            .execute_async(self.pool())
+        let event = event.clone();
+        let saga_id = event.saga_id;
+        self.pool()
+            .transaction_async(|conn| async move {
+                use db::schema::saga::dsl as saga_dsl;
+                use db::schema::saga_node_event::dsl;
+
+                // Lock the saga record so that an operator can't abandon or
+                // re-assign it between this check and the INSERT.
+                let owned = saga_dsl::saga
+                    .filter(saga_dsl::id.eq(saga_id))
+                    .filter(saga_dsl::current_sec.eq(current_sec))
+                    .filter(
+                        saga_dsl::adopt_generation.eq(current_adopt_generation),
+                    )
+                    .select(saga_dsl::id)
+                    .for_update()
+                    .get_result_async::<db::saga_types::SagaId>(&conn)
+                    .await
+                    .optional()?
+                    .is_some();
+                if !owned {
+                    return Err(TxnError::CustomError(
+                        SagaEventError::NotOwned,
+                    ));
+                }
+
+                // When Steno resumes a saga, it may record again the start of
+                // a node that had started but not finished, which is fine.
+                diesel::insert_into(dsl::saga_node_event)
+                    .values(event)
+                    .on_conflict((dsl::saga_id, dsl::node_id, dsl::event_type))
+                    .do_nothing()
+                    .execute_async(&conn)
+                    .await?;
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(SagaEventError::NotOwned) => {
+                    Error::InvalidRequest {
+                        message: format!(
+                            "failed to record event for saga {:?}: \
+                            preconditions not met: expected current_sec = \
+                            {:?}, adopt_generation = {:?}",
+                            saga_id, current_sec, current_adopt_generation,
+                        ),
+                    }
+                }
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::Conflict(ResourceType::SagaDbg, "Saga Event"),
-                )
-            })?;
-        Ok(())
+                ),
+            })
     }
 
     pub async fn saga_update_state(
@@ -153,4 +214,199 @@ impl DataStore {
             .map(|db_event| steno::SagaNodeEvent::try_from(db_event))
             .collect::<Result<_, Error>>()
     }
+
+    /// Fetch the database record for a saga, regardless of which SEC (if
+    /// any) currently owns it
+    pub async fn saga_fetch(
+        &self,
+        opctx: &OpContext,
+        saga_id: steno::SagaId,
+    ) -> LookupResult<db::saga_types::Saga> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+        self.saga_fetch_unauthorized(saga_id).await
+    }
+
+    /// Fetch the database record for a saga without an authz check
+    ///
+    /// This is used by the SEC itself, which does not operate on behalf of any
+    /// particular user.
+    pub async fn saga_fetch_unauthorized(
+        &self,
+        saga_id: steno::SagaId,
+    ) -> LookupResult<db::saga_types::Saga> {
+        use db::schema::saga::dsl;
+
+        let saga_id: db::saga_types::SagaId = saga_id.into();
+        dsl::saga
+            .filter(dsl::id.eq(saga_id))
+            .select(db::saga_types::Saga::as_select())
+            .get_result_async(self.pool())
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByLookup(
+                        ResourceType::SagaDbg,
+                        LookupType::ById(saga_id.0.into()),
+                    ),
+                )
+            })
+    }
+
+    /// List all events recorded for a saga, in the order they were recorded
+    // The number of events is bounded by the number of nodes in the saga DAG
+    // (each node has at most five events), so this is not paginated.
+    pub async fn saga_node_event_list_all(
+        &self,
+        opctx: &OpContext,
+        saga_id: steno::SagaId,
+    ) -> ListResultVec<db::saga_types::SagaNodeEvent> {
+        use db::schema::saga_node_event::dsl;
+
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+        let saga_id: db::saga_types::SagaId = saga_id.into();
+        dsl::saga_node_event
+            .filter(dsl::saga_id.eq(saga_id))
+            .order((dsl::event_time.asc(), dsl::node_id.asc()))
+            .load_async::<db::saga_types::SagaNodeEvent>(
+                self.pool_authorized(opctx).await?,
+            )
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the operator interventions recorded for a saga, oldest first
+    pub async fn saga_intervention_list(
+        &self,
+        opctx: &OpContext,
+        saga_id: steno::SagaId,
+    ) -> ListResultVec<db::saga_types::SagaIntervention> {
+        use db::schema::saga_intervention::dsl;
+
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+        let saga_id: db::saga_types::SagaId = saga_id.into();
+        dsl::saga_intervention
+            .filter(dsl::saga_id.eq(saga_id))
+            .order(dsl::time_created.asc())
+            .select(db::saga_types::SagaIntervention::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Release an unfinished saga from whichever SEC currently owns it so that
+    /// no SEC will resume it, recording `intervention` as the reason
+    pub async fn saga_abandon(
+        &self,
+        opctx: &OpContext,
+        intervention: db::saga_types::SagaIntervention,
+    ) -> UpdateResult<db::saga_types::Saga> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.saga_intervene(opctx, intervention, None).await
+    }
+
+    /// Make `new_sec` the owner of an unfinished saga, recording
+    /// `intervention` as the reason
+    ///
+    /// This only changes the database record.  It's up to the caller to
+    /// actually resume execution of the saga in `new_sec`.
+    pub async fn saga_adopt(
+        &self,
+        opctx: &OpContext,
+        intervention: db::saga_types::SagaIntervention,
+        new_sec: db::saga_types::SecId,
+    ) -> UpdateResult<db::saga_types::Saga> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.saga_intervene(opctx, intervention, Some(new_sec)).await
+    }
+
+    async fn saga_intervene(
+        &self,
+        opctx: &OpContext,
+        intervention: db::saga_types::SagaIntervention,
+        new_sec: Option<db::saga_types::SecId>,
+    ) -> UpdateResult<db::saga_types::Saga> {
+        #[derive(Debug, thiserror::Error)]
+        enum SagaInterveneError {
+            #[error("saga has already finished")]
+            AlreadyDone,
+            #[error("saga is owned by SEC {0}")]
+            OwnedByOtherSec(db::saga_types::SecId),
+        }
+        type TxnError = TransactionError<SagaInterveneError>;
+
+        let saga_id = intervention.saga_id;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::saga::dsl;
+
+                let saga = dsl::saga
+                    .filter(dsl::id.eq(saga_id))
+                    .select(db::saga_types::Saga::as_select())
+                    .get_result_async(&conn)
+                    .await?;
+                if saga.saga_state.0 == steno::SagaCachedState::Done {
+                    return Err(TxnError::CustomError(
+                        SagaInterveneError::AlreadyDone,
+                    ));
+                }
+
+                // Another SEC may still be executing a saga that it owns, so
+                // only an abandoned saga (or one that already belongs to the
+                // adopting SEC) may be adopted.
+                if let (Some(new_sec), Some(current_sec)) =
+                    (new_sec, saga.current_sec)
+                {
+                    if new_sec != current_sec {
+                        return Err(TxnError::CustomError(
+                            SagaInterveneError::OwnedByOtherSec(current_sec),
+                        ));
+                    }
+                }
+
+                // Bumping the generation ensures that whichever SEC owned the
+                // saga before can't record any further progress.
+                let saga = diesel::update(dsl::saga)
+                    .filter(dsl::id.eq(saga_id))
+                    .filter(dsl::adopt_generation.eq(saga.adopt_generation))
+                    .set((
+                        dsl::current_sec.eq(new_sec),
+                        dsl::adopt_generation
+                            .eq(Generation::from(saga.adopt_generation.next())),
+                        dsl::adopt_time.eq(Utc::now()),
+                    ))
+                    .returning(db::saga_types::Saga::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+
+                use db::schema::saga_intervention::dsl as intervention_dsl;
+                diesel::insert_into(intervention_dsl::saga_intervention)
+                    .values(intervention)
+                    .execute_async(&conn)
+                    .await?;
+
+                Ok(saga)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(SagaInterveneError::AlreadyDone) => {
+                    Error::invalid_request("saga has already finished")
+                }
+                TxnError::CustomError(SagaInterveneError::OwnedByOtherSec(
+                    sec_id,
+                )) => Error::invalid_request(&format!(
+                    "saga is owned by SEC {}; it must be abandoned before it \
+                    can be retried elsewhere",
+                    sec_id
+                )),
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByLookup(
+                        ResourceType::SagaDbg,
+                        LookupType::ById(saga_id.0.into()),
+                    ),
+                ),
+            })
+    }
 }
@@ -0,0 +1,109 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to SCIM provisioning of Silos.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::model::ScimClientBearerToken;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use diesel::prelude::*;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use uuid::Uuid;
+
+impl DataStore {
+    // SCIM client bearer tokens are credentials that let an identity provider
+    // manage all of a Silo's users and groups, so only those who may modify
+    // the Silo may see or manage them.
+
+    pub async fn scim_client_bearer_token_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+    ) -> ListResultVec<ScimClientBearerToken> {
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        dsl::scim_client_bearer_token
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .order(dsl::time_created)
+            .select(ScimClientBearerToken::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    pub async fn scim_client_bearer_token_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        token: ScimClientBearerToken,
+    ) -> CreateResult<ScimClientBearerToken> {
+        assert_eq!(token.silo_id, authz_silo.id());
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        diesel::insert_into(dsl::scim_client_bearer_token)
+            .values(token)
+            .returning(ScimClientBearerToken::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    pub async fn scim_client_bearer_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        let deleted = diesel::delete(dsl::scim_client_bearer_token)
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::id.eq(token_id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if deleted == 0 {
+            return Err(LookupType::ById(token_id)
+                .into_not_found(ResourceType::ScimClientBearerToken));
+        }
+        Ok(())
+    }
+
+    /// Look up the Silo's token whose hash is `bearer_token_hash`, if there is
+    /// one
+    pub async fn scim_client_bearer_token_fetch_by_hash(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        bearer_token_hash: String,
+    ) -> LookupResult<Option<ScimClientBearerToken>> {
+        opctx.authorize(authz::Action::Read, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        dsl::scim_client_bearer_token
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::bearer_token_hash.eq(bearer_token_hash))
+            .select(ScimClientBearerToken::as_select())
+            .first_async(self.pool_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+}
@@ -12,8 +12,11 @@ use crate::db::datastore::RunnableQuery;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
 use crate::db::error::TransactionError;
+use crate::db::model::IdentityType;
 use crate::db::model::SiloGroup;
 use crate::db::model::SiloGroupMembership;
+use crate::db::model::SiloGroupUpdate;
+use crate::db::model::SiloUser;
 use crate::db::pagination::paginated;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use async_bb8_diesel::{AsyncConnection, OptionalExtension};
@@ -26,6 +29,8 @@ use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
@@ -181,6 +186,203 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    /// Rename a silo group, i.e., change its external id
+    pub async fn silo_group_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        update: SiloGroupUpdate,
+    ) -> UpdateResult<SiloGroup> {
+        opctx.authorize(authz::Action::Modify, authz_silo_group).await?;
+
+        let external_id = update.external_id.clone();
+        use db::schema::silo_group::dsl;
+        diesel::update(dsl::silo_group)
+            .filter(dsl::id.eq(authz_silo_group.id()))
+            .filter(dsl::time_deleted.is_null())
+            .set(update)
+            .returning(SiloGroup::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SiloGroup,
+                        &external_id,
+                    ),
+                )
+            })
+    }
+
+    pub async fn silo_group_membership_for_group(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+    ) -> ListResultVec<SiloGroupMembership> {
+        opctx.authorize(authz::Action::Read, authz_silo_group).await?;
+
+        use db::schema::silo_group_membership::dsl;
+        dsl::silo_group_membership
+            .filter(dsl::silo_group_id.eq(authz_silo_group.id()))
+            .select(SiloGroupMembership::as_returning())
+            .get_results_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Replace the members of a silo group
+    ///
+    /// As with [`DataStore::silo_group_membership_replace_for_user`], this is
+    /// one transaction that deletes all of the group's memberships and then
+    /// adds back the ones it's supposed to have.
+    pub async fn silo_group_membership_replace_for_group(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        silo_user_ids: Vec<Uuid>,
+    ) -> UpdateResult<()> {
+        opctx.authorize(authz::Action::Modify, authz_silo_group).await?;
+
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::silo_group_membership::dsl;
+
+                // Delete existing memberships for group
+                let silo_group_id = authz_silo_group.id();
+                diesel::delete(dsl::silo_group_membership)
+                    .filter(dsl::silo_group_id.eq(silo_group_id))
+                    .execute_async(&conn)
+                    .await?;
+
+                // Create new memberships for group
+                let silo_group_memberships: Vec<SiloGroupMembership> =
+                    silo_user_ids
+                        .iter()
+                        .map(|user_id| {
+                            SiloGroupMembership::new(silo_group_id, *user_id)
+                        })
+                        .collect();
+
+                diesel::insert_into(dsl::silo_group_membership)
+                    .values(silo_group_memberships)
+                    .execute_async(&conn)
+                    .await?;
+
+                Ok(())
+            })
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Create a group in a Silo
+    ///
+    /// Unlike [`DataStore::silo_group_ensure`], this fails if a group with the
+    /// same external id already exists.
+    pub async fn silo_group_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group_list: &authz::SiloGroupList,
+        silo_group: SiloGroup,
+    ) -> CreateResult<SiloGroup> {
+        assert_eq!(silo_group.silo_id, authz_silo_group_list.silo().id());
+        opctx
+            .authorize(authz::Action::CreateChild, authz_silo_group_list)
+            .await?;
+
+        let external_id = silo_group.external_id.clone();
+        use db::schema::silo_group::dsl;
+        diesel::insert_into(dsl::silo_group)
+            .values(silo_group)
+            .returning(SiloGroup::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SiloGroup,
+                        &external_id,
+                    ),
+                )
+            })
+    }
+
+    /// List the users who are members of a silo group
+    pub async fn silo_group_members_list_by_id(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<SiloUser> {
+        opctx.authorize(authz::Action::Read, authz_silo_group).await?;
+
+        use db::schema::{silo_group_membership as sgm, silo_user as su};
+        paginated(su::dsl::silo_user, su::id, pagparams)
+            .inner_join(sgm::table.on(sgm::silo_user_id.eq(su::id)))
+            .filter(sgm::silo_group_id.eq(authz_silo_group.id()))
+            .filter(su::time_deleted.is_null())
+            .select(SiloUser::as_returning())
+            .get_results_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Add a user to a silo group
+    ///
+    /// This succeeds if the user is already a member of the group.
+    pub async fn silo_group_membership_add(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        authz_silo_user: &authz::SiloUser,
+    ) -> UpdateResult<()> {
+        opctx.authorize(authz::Action::Modify, authz_silo_group).await?;
+
+        use db::schema::silo_group_membership::dsl;
+        diesel::insert_into(dsl::silo_group_membership)
+            .values(SiloGroupMembership::new(
+                authz_silo_group.id(),
+                authz_silo_user.id(),
+            ))
+            .on_conflict((dsl::silo_group_id, dsl::silo_user_id))
+            .do_nothing()
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Remove a user from a silo group
+    ///
+    /// This fails with a 404 if the user is not a member of the group.
+    pub async fn silo_group_membership_remove(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        silo_user_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_silo_group).await?;
+
+        use db::schema::silo_group_membership::dsl;
+        let deleted = diesel::delete(dsl::silo_group_membership)
+            .filter(dsl::silo_group_id.eq(authz_silo_group.id()))
+            .filter(dsl::silo_user_id.eq(silo_user_id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if deleted == 0 {
+            return Err(LookupType::ById(silo_user_id)
+                .into_not_found(ResourceType::SiloUser));
+        }
+        Ok(())
+    }
+
     pub async fn silo_group_delete(
         &self,
         opctx: &OpContext,
@@ -220,6 +422,17 @@ impl DataStore {
                     ));
                 }
 
+                // Delete the group's role assignments, so that they don't
+                // linger in policies
+                {
+                    use db::schema::role_assignment::dsl;
+                    diesel::delete(dsl::role_assignment)
+                        .filter(dsl::identity_type.eq(IdentityType::SiloGroup))
+                        .filter(dsl::identity_id.eq(group_id))
+                        .execute_async(&conn)
+                        .await?;
+                }
+
                 // Delete silo group
                 use db::schema::silo_group::dsl;
                 diesel::update(dsl::silo_group)
@@ -247,17 +460,64 @@ impl DataStore {
             })
     }
 
-    pub async fn silo_groups_list_by_id(
+    /// List the groups of a Silo by offset rather than by marker, optionally
+    /// only those with a given external id
+    ///
+    /// This is how SCIM clients page through groups.  Returns the total number
+    /// of matching groups along with the requested page of them.
+    pub async fn silo_groups_list_by_offset(
         &self,
         opctx: &OpContext,
         authz_silo: &authz::Silo,
+        external_id: Option<String>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(i64, Vec<SiloGroup>), Error> {
+        opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
+
+        use db::schema::silo_group::dsl;
+        let filtered = || {
+            let mut query = dsl::silo_group
+                .filter(dsl::silo_id.eq(authz_silo.id()))
+                .filter(dsl::time_deleted.is_null())
+                .into_boxed();
+            if let Some(external_id) = &external_id {
+                query = query.filter(dsl::external_id.eq(external_id.clone()));
+            }
+            query
+        };
+
+        let pool = self.pool_authorized(opctx).await?;
+        let total =
+            filtered().count().get_result_async::<i64>(pool).await.map_err(
+                |e| public_error_from_diesel_pool(e, ErrorHandler::Server),
+            )?;
+        let groups = filtered()
+            .order(dsl::id)
+            .offset(offset)
+            .limit(limit)
+            .select(SiloGroup::as_select())
+            .load_async::<SiloGroup>(pool)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok((total, groups))
+    }
+
+    pub async fn silo_groups_list_by_id(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group_list: &authz::SiloGroupList,
         pagparams: &DataPageParams<'_, Uuid>,
     ) -> ListResultVec<SiloGroup> {
         use db::schema::silo_group::dsl;
 
-        opctx.authorize(authz::Action::Read, authz_silo).await?;
+        opctx
+            .authorize(authz::Action::ListChildren, authz_silo_group_list)
+            .await?;
         paginated(dsl::silo_group, dsl::id, pagparams)
-            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::silo_id.eq(authz_silo_group_list.silo().id()))
             .filter(dsl::time_deleted.is_null())
             .select(SiloGroup::as_select())
             .load_async::<SiloGroup>(self.pool_authorized(opctx).await?)
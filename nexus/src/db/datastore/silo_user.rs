@@ -15,21 +15,28 @@ use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
 use crate::db::model::Name;
 use crate::db::model::SiloUser;
+use crate::db::model::SiloUserPasswordHash;
+use crate::db::model::SiloUserUpdate;
 use crate::db::model::UserBuiltin;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::external_api::params;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use chrono::DateTime;
 use chrono::Utc;
 use diesel::prelude::*;
+use diesel::upsert::excluded;
 use nexus_types::identity::Asset;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
 impl DataStore {
@@ -123,6 +130,15 @@ impl DataStore {
                         .await?;
                 }
 
+                // Delete the user's password.
+                {
+                    use db::schema::silo_user_password_hash::dsl;
+                    diesel::delete(dsl::silo_user_password_hash)
+                        .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                        .execute_async(&mut conn)
+                        .await?;
+                }
+
                 // Delete ssh keys.
                 {
                     use db::schema::ssh_key::dsl;
@@ -134,6 +150,17 @@ impl DataStore {
                         .await?;
                 }
 
+                // Delete API tokens.
+                {
+                    use db::schema::api_token::dsl;
+                    diesel::update(dsl::api_token)
+                        .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .set(dsl::time_deleted.eq(Utc::now()))
+                        .execute_async(&mut conn)
+                        .await?;
+                }
+
                 Ok(())
             })
             .await
@@ -146,6 +173,76 @@ impl DataStore {
             })
     }
 
+    /// Update a silo user's external id or whether they're active
+    ///
+    /// Deactivating a user also ends their console sessions and revokes their
+    /// device access tokens and API tokens, so that they can't keep using the
+    /// system.
+    pub async fn silo_user_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+        update: SiloUserUpdate,
+    ) -> UpdateResult<SiloUser> {
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        let authz_silo_user_id = authz_silo_user.id();
+        let deactivate = update.active == Some(false);
+        let conflict_name = update.external_id.clone().unwrap_or_default();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let db_silo_user = {
+                    use db::schema::silo_user::dsl;
+                    diesel::update(dsl::silo_user)
+                        .filter(dsl::id.eq(authz_silo_user_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .set(update)
+                        .returning(SiloUser::as_returning())
+                        .get_result_async(&conn)
+                        .await?
+                };
+
+                if deactivate {
+                    {
+                        use db::schema::console_session::dsl;
+                        diesel::delete(dsl::console_session)
+                            .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                            .execute_async(&conn)
+                            .await?;
+                    }
+                    {
+                        use db::schema::device_access_token::dsl;
+                        diesel::delete(dsl::device_access_token)
+                            .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                            .execute_async(&conn)
+                            .await?;
+                    }
+                    {
+                        use db::schema::api_token::dsl;
+                        diesel::update(dsl::api_token)
+                            .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                            .filter(dsl::time_deleted.is_null())
+                            .set(dsl::time_deleted.eq(Utc::now()))
+                            .execute_async(&conn)
+                            .await?;
+                    }
+                }
+
+                Ok(db_silo_user)
+            })
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SiloUser,
+                        &conflict_name,
+                    ),
+                )
+            })
+    }
+
     /// Given an external ID, return
     /// - Ok(Some((authz::SiloUser, SiloUser))) if that external id refers to an
     ///   existing silo user
@@ -202,6 +299,170 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    /// List the users of a Silo by offset rather than by marker, optionally
+    /// only those with a given external id
+    ///
+    /// This is how SCIM clients page through users.  Returns the total number
+    /// of matching users along with the requested page of them.
+    pub async fn silo_users_list_by_offset(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        external_id: Option<String>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(i64, Vec<SiloUser>), Error> {
+        opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
+
+        use db::schema::silo_user::dsl;
+        let filtered = || {
+            let mut query = dsl::silo_user
+                .filter(dsl::silo_id.eq(authz_silo.id()))
+                .filter(dsl::time_deleted.is_null())
+                .into_boxed();
+            if let Some(external_id) = &external_id {
+                query = query.filter(dsl::external_id.eq(external_id.clone()));
+            }
+            query
+        };
+
+        let pool = self.pool_authorized(opctx).await?;
+        let total =
+            filtered().count().get_result_async::<i64>(pool).await.map_err(
+                |e| public_error_from_diesel_pool(e, ErrorHandler::Server),
+            )?;
+        let users = filtered()
+            .order(dsl::id)
+            .offset(offset)
+            .limit(limit)
+            .select(SiloUser::as_select())
+            .load_async::<SiloUser>(pool)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok((total, users))
+    }
+
+    /// Fetch the password hash of a silo user, if they have a password
+    ///
+    /// Password hashes are secret, so they may only be read by those who may
+    /// modify the user.
+    pub async fn silo_user_password_hash_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+    ) -> LookupResult<Option<SiloUserPasswordHash>> {
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        use db::schema::silo_user_password_hash::dsl;
+        dsl::silo_user_password_hash
+            .filter(dsl::silo_user_id.eq(authz_silo_user.id()))
+            .select(SiloUserPasswordHash::as_select())
+            .first_async(self.pool_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Set (or replace) the password hash of a silo user
+    ///
+    /// This also clears any failed login attempts and lockout.
+    pub async fn silo_user_password_hash_set(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+        password_hash: SiloUserPasswordHash,
+    ) -> UpdateResult<()> {
+        assert_eq!(authz_silo_user.id(), password_hash.silo_user_id);
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        use db::schema::silo_user_password_hash::dsl;
+        diesel::insert_into(dsl::silo_user_password_hash)
+            .values(password_hash)
+            .on_conflict(dsl::silo_user_id)
+            .do_update()
+            .set((
+                dsl::hash.eq(excluded(dsl::hash)),
+                dsl::time_modified.eq(excluded(dsl::time_modified)),
+                dsl::failed_attempts.eq(0),
+                dsl::time_locked_until.eq(None::<DateTime<Utc>>),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Record a failed password login by a silo user
+    ///
+    /// Once the user has failed `max_failed_attempts` times in a row, they are
+    /// locked out until `lockout` from now, and the count starts again.
+    pub async fn silo_user_password_login_failed(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+        max_failed_attempts: i64,
+        lockout: chrono::Duration,
+    ) -> UpdateResult<()> {
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        use db::schema::silo_user_password_hash::dsl;
+        let failed_attempts = diesel::update(dsl::silo_user_password_hash)
+            .filter(dsl::silo_user_id.eq(authz_silo_user.id()))
+            .set(dsl::failed_attempts.eq(dsl::failed_attempts + 1))
+            .returning(dsl::failed_attempts)
+            .get_result_async::<i64>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_silo_user),
+                )
+            })?;
+        if failed_attempts < max_failed_attempts {
+            return Ok(());
+        }
+
+        diesel::update(dsl::silo_user_password_hash)
+            .filter(dsl::silo_user_id.eq(authz_silo_user.id()))
+            .filter(dsl::failed_attempts.ge(max_failed_attempts))
+            .set((
+                dsl::failed_attempts.eq(0),
+                dsl::time_locked_until.eq(Some(Utc::now() + lockout)),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Record a successful password login by a silo user, clearing their
+    /// failed login attempts
+    pub async fn silo_user_password_login_succeeded(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+    ) -> UpdateResult<()> {
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        use db::schema::silo_user_password_hash::dsl;
+        diesel::update(dsl::silo_user_password_hash)
+            .filter(dsl::silo_user_id.eq(authz_silo_user.id()))
+            .filter(dsl::failed_attempts.ne(0))
+            .set(dsl::failed_attempts.eq(0))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
     pub async fn users_builtin_list_by_name(
         &self,
         opctx: &OpContext,
@@ -11,13 +11,17 @@ use crate::db;
 use crate::db::datastore::RunnableQuery;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::lookup::LookupPath;
 use crate::db::model::Generation;
 use crate::db::model::Name;
+use crate::db::model::ResourceUsage;
 use crate::db::model::Snapshot;
 use crate::db::model::SnapshotState;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
+use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
@@ -54,14 +58,35 @@ impl DataStore {
         snapshot: Snapshot,
     ) -> CreateResult<Snapshot> {
         let snapshot_id = snapshot.id();
+        let project_id = snapshot.project_id;
         let gen = snapshot.gen;
 
-        DataStore::snapshot_ensure_query(opctx, authz_silo, snapshot)
+        let query =
+            DataStore::snapshot_ensure_query(opctx, authz_silo, snapshot)
+                .await?;
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
             .await?
-            .execute_async(self.pool_authorized(opctx).await?)
+            .transaction_async(|conn| async move {
+                // Only count the snapshot against the project's quotas the
+                // first time it's created.
+                let inserted = query.execute_async(&conn).await?;
+                if inserted > 0 {
+                    DataStore::resource_usage_charge(
+                        &conn,
+                        project_id,
+                        ResourceUsage::for_snapshot(),
+                    )
+                    .await?;
+                }
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
             })?;
 
         let (.., snapshot) = LookupPath::new(&opctx, self)
@@ -149,16 +174,34 @@ impl DataStore {
 
         use db::schema::snapshot::dsl;
 
-        diesel::update(dsl::snapshot)
-            .filter(dsl::time_deleted.is_null())
-            .filter(dsl::gen.eq(gen))
-            .filter(dsl::id.eq(snapshot_id))
-            .set(dsl::time_deleted.eq(now))
-            .check_if_exists::<Snapshot>(snapshot_id)
-            .execute_async(self.pool_authorized(&opctx).await?)
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(&opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let result = diesel::update(dsl::snapshot)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::gen.eq(gen))
+                    .filter(dsl::id.eq(snapshot_id))
+                    .set(dsl::time_deleted.eq(now))
+                    .check_if_exists::<Snapshot>(snapshot_id)
+                    .execute_and_check(&conn)
+                    .await?;
+                if let UpdateStatus::Updated = result.status {
+                    DataStore::resource_usage_charge(
+                        &conn,
+                        result.found.project_id,
+                        -ResourceUsage::for_snapshot(),
+                    )
+                    .await?;
+                }
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
             })?;
 
         Ok(snapshot_id)
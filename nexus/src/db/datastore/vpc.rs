@@ -19,6 +19,7 @@ use crate::db::model::IncompleteVpc;
 use crate::db::model::Name;
 use crate::db::model::NetworkInterface;
 use crate::db::model::RouterRoute;
+use crate::db::model::RouterRouteKind;
 use crate::db::model::RouterRouteUpdate;
 use crate::db::model::Sled;
 use crate::db::model::Vni;
@@ -36,9 +37,11 @@ use crate::db::queries::vpc_subnet::FilterConflictingVpcSubnetRangesQuery;
 use crate::db::queries::vpc_subnet::SubnetError;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
 use chrono::Utc;
 use diesel::prelude::*;
 use ipnetwork::IpNetwork;
+use omicron_common::api::external;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
@@ -49,6 +52,7 @@ use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use std::collections::BTreeMap;
+use std::collections::HashSet;
 use uuid::Uuid;
 
 impl DataStore {
@@ -140,7 +144,9 @@ impl DataStore {
     ) -> DeleteResult {
         opctx.authorize(authz::Action::Delete, authz_vpc).await?;
 
+        use db::schema::project_settings;
         use db::schema::vpc::dsl;
+        use db::schema::vpc_peering;
         use db::schema::vpc_subnet;
 
         // Note that we don't ensure the firewall rules are empty here, because
@@ -177,30 +183,86 @@ impl DataStore {
             });
         }
 
+        // Peerings must be deleted explicitly too, so that the routes to this
+        // VPC are removed from the peer's system router.
+        if diesel_pool_result_optional(
+            vpc_peering::dsl::vpc_peering
+                .filter(vpc_peering::dsl::vpc_id.eq(authz_vpc.id()))
+                .filter(vpc_peering::dsl::time_deleted.is_null())
+                .select(vpc_peering::dsl::id)
+                .limit(1)
+                .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?
+        .is_some()
+        {
+            return Err(Error::InvalidRequest {
+                message: String::from(
+                    "VPC cannot be deleted while VPC peerings exist",
+                ),
+            });
+        }
+
         // Delete the VPC, conditional on the subnet_gen not having changed.
+        //
+        // The project's default VPC must be changed before it can be deleted.
+        // That's checked in the same transaction as the deletion, which
+        // conflicts with a concurrent settings update making this VPC the
+        // default (see `project_settings_update()`).
+        let vpc_id = authz_vpc.id();
         let now = Utc::now();
-        let updated_rows = diesel::update(dsl::vpc)
+        let delete_query = diesel::update(dsl::vpc)
             .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_vpc.id()))
+            .filter(dsl::id.eq(vpc_id))
             .filter(dsl::subnet_gen.eq(db_vpc.subnet_gen))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool_authorized(opctx).await?)
+            .set(dsl::time_deleted.eq(now));
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let default_for_project =
+                    project_settings::dsl::project_settings
+                        .filter(
+                            project_settings::dsl::default_vpc_id.eq(vpc_id),
+                        )
+                        .select(project_settings::dsl::project_id)
+                        .limit(1)
+                        .first_async::<Uuid>(&conn)
+                        .await
+                        .optional()?;
+                if default_for_project.is_some() {
+                    return Err(TxnError::CustomError(Error::InvalidRequest {
+                        message: String::from(
+                            "VPC cannot be deleted while it is the project's \
+                            default VPC",
+                        ),
+                    }));
+                }
+                let updated_rows = delete_query.execute_async(&conn).await?;
+                if updated_rows == 0 {
+                    return Err(TxnError::CustomError(Error::InvalidRequest {
+                        message: String::from(
+                            "deletion failed to to concurrent modification",
+                        ),
+                    }));
+                }
+                DataStore::role_assignment_delete_all_query(
+                    ResourceType::Vpc,
+                    vpc_id,
+                )
+                .execute_async(&conn)
+                .await?;
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_vpc),
-                )
-            })?;
-        if updated_rows == 0 {
-            Err(Error::InvalidRequest {
-                message: String::from(
-                    "deletion failed to to concurrent modification",
                 ),
             })
-        } else {
-            Ok(())
-        }
     }
 
     pub async fn vpc_list_firewall_rules(
@@ -349,6 +411,56 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    /// Return the VPC that an Instance's network interfaces are in, if the
+    /// Instance has any interfaces.
+    pub async fn instance_resolve_to_vpc(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+    ) -> LookupResult<Option<Vpc>> {
+        opctx.authorize(authz::Action::Read, authz_instance).await?;
+
+        // All of an Instance's interfaces are in the same VPC (see the check
+        // in `project_create_instance`), so any one of them will do.
+        use db::schema::{network_interface, vpc};
+        vpc::table
+            .filter(vpc::time_deleted.is_null())
+            .filter(
+                vpc::id.eq_any(
+                    network_interface::table
+                        .filter(
+                            network_interface::instance_id
+                                .eq(authz_instance.id()),
+                        )
+                        .filter(network_interface::time_deleted.is_null())
+                        .select(network_interface::vpc_id),
+                ),
+            )
+            .select(Vpc::as_select())
+            .first_async(self.pool_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List all VPCs in the fleet, e.g., to reconcile their firewall rules
+    /// with the sleds hosting them.
+    pub async fn vpc_list_all(
+        &self,
+        opctx: &OpContext,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<Vpc> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        use db::schema::vpc::dsl;
+        paginated(dsl::vpc, dsl::id, pagparams)
+            .filter(dsl::time_deleted.is_null())
+            .select(Vpc::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     pub async fn vpc_list_subnets(
         &self,
         opctx: &OpContext,
@@ -367,7 +479,8 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
-    /// Insert a VPC Subnet, checking for unique IP address ranges.
+    /// Insert a VPC Subnet, checking for unique IP address ranges, along with
+    /// the route directing traffic to it in the VPC's system router.
     pub async fn vpc_create_subnet(
         &self,
         opctx: &OpContext,
@@ -380,9 +493,74 @@ impl DataStore {
             .map_err(SubnetError::External)?;
         assert_eq!(authz_vpc.id(), subnet.vpc_id);
 
-        self.vpc_create_subnet_raw(subnet).await
+        use db::schema::router_route;
+        use db::schema::vpc;
+        use db::schema::vpc_subnet;
+
+        let vpc_id = authz_vpc.id();
+        let insert_subnet_query = diesel::insert_into(vpc_subnet::table)
+            .values(FilterConflictingVpcSubnetRangesQuery::new(subnet.clone()));
+
+        type TxnError = TransactionError<SubnetError>;
+        self.pool_authorized(opctx)
+            .await
+            .map_err(SubnetError::External)?
+            .transaction_async(|conn| async move {
+                let system_router_id = vpc::table
+                    .filter(vpc::id.eq(vpc_id))
+                    .filter(vpc::time_deleted.is_null())
+                    .select(vpc::system_router_id)
+                    .get_result_async::<Uuid>(&conn)
+                    .await?;
+                let subnet = insert_subnet_query
+                    .returning(VpcSubnet::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                let route = RouterRoute::for_vpc_subnet(
+                    Uuid::new_v4(),
+                    system_router_id,
+                    subnet.id(),
+                    subnet.name().clone(),
+                );
+                let route_name = route.name().clone();
+                VpcRouter::insert_resource(
+                    system_router_id,
+                    diesel::insert_into(router_route::table).values(route),
+                )
+                .insert_and_get_result_async(&conn)
+                .await
+                .map_err(|e| {
+                    let error = match e {
+                        AsyncInsertError::CollectionNotFound => {
+                            Error::ObjectNotFound {
+                                type_name: ResourceType::VpcRouter,
+                                lookup_type: LookupType::ById(system_router_id),
+                            }
+                        }
+                        AsyncInsertError::DatabaseError(e) => {
+                            public_error_from_diesel_pool(
+                                e,
+                                ErrorHandler::Conflict(
+                                    ResourceType::RouterRoute,
+                                    route_name.as_str(),
+                                ),
+                            )
+                        }
+                    };
+                    TxnError::CustomError(SubnetError::External(error))
+                })?;
+                Ok(subnet)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => SubnetError::from_pool(e, &subnet),
+            })
     }
 
+    /// Insert a VPC Subnet without a route in its VPC's system router, so the
+    /// subnet queries can be tested without creating a VPC.
+    #[cfg(test)]
     pub(crate) async fn vpc_create_subnet_raw(
         &self,
         subnet: VpcSubnet,
@@ -406,6 +584,8 @@ impl DataStore {
         opctx.authorize(authz::Action::Delete, authz_subnet).await?;
 
         use db::schema::network_interface;
+        use db::schema::project_settings;
+        use db::schema::router_route;
         use db::schema::vpc_subnet::dsl;
 
         // Verify there are no child network interfaces in this VPC Subnet
@@ -429,45 +609,248 @@ impl DataStore {
             });
         }
 
-        // Delete the subnet, conditional on the rcgen not having changed.
+        // Delete the subnet, conditional on the rcgen not having changed,
+        // along with its route in the VPC's system router.  As with VPCs, the
+        // check that this isn't the project's default VPC Subnet is part of
+        // the same transaction.
+        let subnet_id = authz_subnet.id();
         let now = Utc::now();
-        let updated_rows = diesel::update(dsl::vpc_subnet)
+        let delete_subnet_query = diesel::update(dsl::vpc_subnet)
             .filter(dsl::time_deleted.is_null())
             .filter(dsl::id.eq(authz_subnet.id()))
             .filter(dsl::rcgen.eq(db_subnet.rcgen))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool_authorized(opctx).await?)
+            .set(dsl::time_deleted.eq(now));
+        let delete_route_query = self
+            .vpc_subnet_route_query(db_subnet)
+            .set(router_route::time_deleted.eq(now));
+
+        #[derive(Debug)]
+        enum SubnetDeleteError {
+            ProjectDefault,
+            ConcurrentModification,
+        }
+        type TxnError = TransactionError<SubnetDeleteError>;
+
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let default_for_project =
+                    project_settings::dsl::project_settings
+                        .filter(
+                            project_settings::dsl::default_vpc_subnet_id
+                                .eq(subnet_id),
+                        )
+                        .select(project_settings::dsl::project_id)
+                        .limit(1)
+                        .first_async::<Uuid>(&conn)
+                        .await
+                        .optional()?;
+                if default_for_project.is_some() {
+                    return Err(TxnError::CustomError(
+                        SubnetDeleteError::ProjectDefault,
+                    ));
+                }
+                let updated_rows =
+                    delete_subnet_query.execute_async(&conn).await?;
+                if updated_rows == 0 {
+                    return Err(TxnError::CustomError(
+                        SubnetDeleteError::ConcurrentModification,
+                    ));
+                }
+                delete_route_query.execute_async(&conn).await?;
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(SubnetDeleteError::ProjectDefault) => {
+                    Error::InvalidRequest {
+                        message: String::from(
+                            "VPC Subnet cannot be deleted while it is the \
+                            project's default VPC Subnet",
+                        ),
+                    }
+                }
+                TxnError::CustomError(
+                    SubnetDeleteError::ConcurrentModification,
+                ) => Error::InvalidRequest {
+                    message: String::from(
+                        "deletion failed to to concurrent modification",
+                    ),
+                },
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_subnet),
-                )
-            })?;
-        if updated_rows == 0 {
-            return Err(Error::InvalidRequest {
-                message: String::from(
-                    "deletion failed to to concurrent modification",
                 ),
-            });
-        } else {
-            Ok(())
-        }
+            })
     }
 
+    /// Update a VPC Subnet. If it is renamed, its route in the VPC's system
+    /// router follows it.
     pub async fn vpc_update_subnet(
         &self,
         opctx: &OpContext,
         authz_subnet: &authz::VpcSubnet,
+        db_subnet: &VpcSubnet,
         updates: VpcSubnetUpdate,
     ) -> UpdateResult<VpcSubnet> {
         opctx.authorize(authz::Action::Modify, authz_subnet).await?;
 
         use db::schema::vpc_subnet::dsl;
-        diesel::update(dsl::vpc_subnet)
+        let rename_route_query = updates.name.as_ref().map(|name| {
+            self.vpc_subnet_route_query(db_subnet)
+                .set(RouterRouteUpdate::for_vpc_subnet(name.0.clone()))
+        });
+        let update_subnet_query = diesel::update(dsl::vpc_subnet)
             .filter(dsl::time_deleted.is_null())
             .filter(dsl::id.eq(authz_subnet.id()))
             .set(updates)
+            .returning(VpcSubnet::as_returning());
+
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let subnet =
+                    update_subnet_query.get_result_async(&conn).await?;
+                if let Some(query) = rename_route_query {
+                    query.execute_async(&conn).await?;
+                }
+                Ok(subnet)
+            })
+            .await
+            .map_err(|e: TransactionError<Error>| match e {
+                TransactionError::CustomError(e) => e,
+                TransactionError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_subnet),
+                ),
+            })
+    }
+
+    /// Build an update of the live route for a VPC Subnet in its VPC's system
+    /// router (see [`RouterRoute::for_vpc_subnet`]).
+    fn vpc_subnet_route_query(
+        &self,
+        subnet: &VpcSubnet,
+    ) -> diesel::query_builder::BoxedUpdateStatement<
+        'static,
+        diesel::pg::Pg,
+        db::schema::router_route::table,
+    > {
+        use db::schema::{router_route, vpc};
+        diesel::update(router_route::table)
+            .filter(router_route::time_deleted.is_null())
+            .filter(
+                router_route::kind
+                    .eq(RouterRouteKind(external::RouterRouteKind::VpcSubnet)),
+            )
+            .filter(
+                router_route::name
+                    .eq(Name(RouterRoute::vpc_subnet_route_name(subnet.id()))),
+            )
+            .filter(
+                router_route::vpc_router_id.eq_any(
+                    vpc::table
+                        .filter(vpc::id.eq(subnet.vpc_id))
+                        .select(vpc::system_router_id),
+                ),
+            )
+            .into_boxed()
+    }
+
+    /// Create the system router routes for any VPC Subnets that lack them,
+    /// e.g., subnets created before Nexus maintained such routes.
+    ///
+    /// Returns the number of routes created.
+    pub async fn vpc_subnet_routes_backfill(
+        &self,
+        opctx: &OpContext,
+    ) -> Result<usize, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::DATABASE).await?;
+
+        use db::schema::{router_route, vpc, vpc_subnet};
+
+        // TODO-scalability: This loads every subnet and subnet route in the
+        // fleet. That's fine for the one-time upgrade it's meant for, but it
+        // should be paginated if it's ever run routinely.
+        let subnets = vpc_subnet::table
+            .inner_join(vpc::table.on(vpc::id.eq(vpc_subnet::vpc_id)))
+            .filter(vpc_subnet::time_deleted.is_null())
+            .filter(vpc::time_deleted.is_null())
+            .select((vpc::system_router_id, vpc_subnet::id, vpc_subnet::name))
+            .load_async::<(Uuid, Uuid, Name)>(
+                self.pool_authorized(opctx).await?,
+            )
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        let existing: HashSet<(Uuid, Name)> = router_route::table
+            .filter(router_route::time_deleted.is_null())
+            .filter(
+                router_route::kind
+                    .eq(RouterRouteKind(external::RouterRouteKind::VpcSubnet)),
+            )
+            .select((router_route::vpc_router_id, router_route::name))
+            .load_async::<(Uuid, Name)>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?
+            .into_iter()
+            .collect();
+
+        let mut ncreated = 0;
+        for (system_router_id, subnet_id, subnet_name) in subnets {
+            let route_name =
+                Name(RouterRoute::vpc_subnet_route_name(subnet_id));
+            if existing.contains(&(system_router_id, route_name)) {
+                continue;
+            }
+            let route = RouterRoute::for_vpc_subnet(
+                Uuid::new_v4(),
+                system_router_id,
+                subnet_id,
+                subnet_name.0,
+            );
+            match VpcRouter::insert_resource(
+                system_router_id,
+                diesel::insert_into(router_route::table).values(route),
+            )
+            .insert_and_get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            {
+                Ok(_) => ncreated += 1,
+                // The VPC was deleted since we listed its subnets.
+                Err(AsyncInsertError::CollectionNotFound) => (),
+                Err(AsyncInsertError::DatabaseError(e)) => {
+                    return Err(public_error_from_diesel_pool(
+                        e,
+                        ErrorHandler::Server,
+                    ));
+                }
+            }
+        }
+        Ok(ncreated)
+    }
+
+    /// Associate a VPC Subnet with a custom router, or dissociate it from any
+    /// custom router if `router_id` is `None`
+    pub async fn vpc_subnet_set_custom_router(
+        &self,
+        opctx: &OpContext,
+        authz_subnet: &authz::VpcSubnet,
+        router_id: Option<Uuid>,
+    ) -> UpdateResult<VpcSubnet> {
+        opctx.authorize(authz::Action::Modify, authz_subnet).await?;
+
+        use db::schema::vpc_subnet::dsl;
+        diesel::update(dsl::vpc_subnet)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_subnet.id()))
+            .set((
+                dsl::custom_router_id.eq(router_id),
+                dsl::time_modified.eq(Utc::now()),
+            ))
             .returning(VpcSubnet::as_returning())
             .get_result_async(self.pool_authorized(opctx).await?)
             .await
@@ -555,6 +938,8 @@ impl DataStore {
         ))
     }
 
+    /// Delete a VPC router along with all of its routes, detaching it from
+    /// any VPC Subnets it is associated with
     pub async fn vpc_delete_router(
         &self,
         opctx: &OpContext,
@@ -562,21 +947,42 @@ impl DataStore {
     ) -> DeleteResult {
         opctx.authorize(authz::Action::Delete, authz_router).await?;
 
-        use db::schema::vpc_router::dsl;
+        use db::schema::router_route;
+        use db::schema::vpc_router;
+        use db::schema::vpc_subnet;
+        let router_id = authz_router.id();
         let now = Utc::now();
-        diesel::update(dsl::vpc_router)
-            .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_router.id()))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool())
+        let delete_router_query = diesel::update(vpc_router::table)
+            .filter(vpc_router::time_deleted.is_null())
+            .filter(vpc_router::id.eq(router_id))
+            .set(vpc_router::time_deleted.eq(now));
+        let delete_routes_query = diesel::update(router_route::table)
+            .filter(router_route::time_deleted.is_null())
+            .filter(router_route::vpc_router_id.eq(router_id))
+            .set(router_route::time_deleted.eq(now));
+        let detach_subnets_query = diesel::update(vpc_subnet::table)
+            .filter(vpc_subnet::custom_router_id.eq(router_id))
+            .set((
+                vpc_subnet::custom_router_id.eq(None::<Uuid>),
+                vpc_subnet::time_modified.eq(now),
+            ));
+
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                delete_router_query.execute_async(&conn).await?;
+                delete_routes_query.execute_async(&conn).await?;
+                detach_subnets_query.execute_async(&conn).await?;
+                Ok(())
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e: TransactionError<Error>| match e {
+                TransactionError::CustomError(e) => e,
+                TransactionError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_router),
-                )
-            })?;
-        Ok(())
+                ),
+            })
     }
 
     pub async fn vpc_update_router(
@@ -707,6 +1113,31 @@ impl DataStore {
             })
     }
 
+    /// List the routes of every router in a VPC, e.g., to resolve them for
+    /// the sleds hosting the VPC.
+    pub async fn vpc_list_all_routes(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+    ) -> ListResultVec<RouterRoute> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::{router_route, vpc_router};
+        router_route::table
+            .inner_join(
+                vpc_router::table
+                    .on(vpc_router::id.eq(router_route::vpc_router_id)),
+            )
+            .filter(vpc_router::vpc_id.eq(authz_vpc.id()))
+            .filter(vpc_router::time_deleted.is_null())
+            .filter(router_route::time_deleted.is_null())
+            .order_by((router_route::vpc_router_id, router_route::name))
+            .select(RouterRoute::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     /// Identify all subnets in use by each VpcSubnet
     pub async fn resolve_vpc_subnets_to_ip_networks<
         T: IntoIterator<Item = Name>,
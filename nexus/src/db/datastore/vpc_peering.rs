@@ -0,0 +1,342 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`VpcPeering`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::collection_insert::AsyncInsertError;
+use crate::db::collection_insert::DatastoreCollection;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::identity::Resource;
+use crate::db::model::Ipv4Net;
+use crate::db::model::Name;
+use crate::db::model::RouterRoute;
+use crate::db::model::RouterRouteKind;
+use crate::db::model::Vpc;
+use crate::db::model::VpcPeering;
+use crate::db::model::VpcPeeringState;
+use crate::db::model::VpcRouter;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use chrono::Utc;
+use diesel::prelude::*;
+use ipnetwork::IpNetwork;
+use omicron_common::api::external;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::ResourceType;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+impl DataStore {
+    pub async fn vpc_list_peerings(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        pagparams: &DataPageParams<'_, Name>,
+    ) -> ListResultVec<VpcPeering> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::vpc_peering::dsl;
+        paginated(dsl::vpc_peering, dsl::name, pagparams)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::vpc_id.eq(authz_vpc.id()))
+            .select(VpcPeering::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Create one VPC's side of a peering with `peer_vpc`.
+    ///
+    /// If the peer VPC has already peered with this one, both sides of the
+    /// peering become active, and routes to each VPC are installed in the
+    /// system router of the other. Otherwise, the peering is pending until the
+    /// peer VPC reciprocates.
+    pub async fn vpc_create_peering(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        vpc: &Vpc,
+        peer_vpc: &Vpc,
+        peering: VpcPeering,
+    ) -> CreateResult<VpcPeering> {
+        opctx.authorize(authz::Action::CreateChild, authz_vpc).await?;
+        assert_eq!(authz_vpc.id(), vpc.id());
+        assert_eq!(peering.vpc_id, vpc.id());
+        assert_eq!(peering.peer_vpc_id, peer_vpc.id());
+
+        use db::schema::router_route;
+        use db::schema::vpc_peering::dsl;
+        use db::schema::vpc_subnet;
+
+        let vpc = vpc.clone();
+        let peer_vpc = peer_vpc.clone();
+        let name = peering.name().clone();
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                // Traffic can only be routed between the VPCs if none of their
+                // addresses overlap.
+                let vpc_prefix = vpc.ipv6_prefix.0 .0;
+                let peer_prefix = peer_vpc.ipv6_prefix.0 .0;
+                if vpc_prefix.contains(peer_prefix.network())
+                    || peer_prefix.contains(vpc_prefix.network())
+                {
+                    return Err(TxnError::CustomError(Error::invalid_request(
+                        "cannot peer VPCs with overlapping IPv6 prefixes",
+                    )));
+                }
+                let ipv4_blocks = |vpc_id: Uuid| {
+                    vpc_subnet::table
+                        .filter(vpc_subnet::vpc_id.eq(vpc_id))
+                        .filter(vpc_subnet::time_deleted.is_null())
+                        .select(vpc_subnet::ipv4_block)
+                };
+                let vpc_blocks =
+                    ipv4_blocks(vpc.id()).load_async::<Ipv4Net>(&conn).await?;
+                let peer_blocks = ipv4_blocks(peer_vpc.id())
+                    .load_async::<Ipv4Net>(&conn)
+                    .await?;
+                for block in &vpc_blocks {
+                    if peer_blocks.iter().any(|peer| peer.overlaps(block)) {
+                        return Err(TxnError::CustomError(
+                            Error::invalid_request(&format!(
+                                "cannot peer VPCs with overlapping IPv4 \
+                                subnets (\"{}\")",
+                                block.0 .0
+                            )),
+                        ));
+                    }
+                }
+
+                // Check whether the peer VPC is waiting for this side of the
+                // peering.
+                let reciprocal = dsl::vpc_peering
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::vpc_id.eq(peer_vpc.id()))
+                    .filter(dsl::peer_vpc_id.eq(vpc.id()))
+                    .select(VpcPeering::as_select())
+                    .get_result_async(&conn)
+                    .await
+                    .optional()?;
+
+                let mut peering = peering;
+                if let Some(reciprocal) = reciprocal {
+                    peering.state = VpcPeeringState::Active;
+                    diesel::update(dsl::vpc_peering)
+                        .filter(dsl::id.eq(reciprocal.id()))
+                        .set((
+                            dsl::state.eq(VpcPeeringState::Active),
+                            dsl::time_modified.eq(Utc::now()),
+                        ))
+                        .execute_async(&conn)
+                        .await?;
+                    let routes = [
+                        RouterRoute::for_vpc_peering(
+                            Uuid::new_v4(),
+                            vpc.system_router_id,
+                            peering.id(),
+                            peer_vpc.name().clone(),
+                        ),
+                        RouterRoute::for_vpc_peering(
+                            Uuid::new_v4(),
+                            peer_vpc.system_router_id,
+                            reciprocal.id(),
+                            vpc.name().clone(),
+                        ),
+                    ];
+                    for route in routes {
+                        let router_id = route.vpc_router_id;
+                        VpcRouter::insert_resource(
+                            router_id,
+                            diesel::insert_into(router_route::table)
+                                .values(route),
+                        )
+                        .insert_and_get_result_async(&conn)
+                        .await
+                        .map_err(|e| {
+                            TxnError::CustomError(match e {
+                                AsyncInsertError::CollectionNotFound => {
+                                    Error::not_found_by_id(
+                                        ResourceType::VpcRouter,
+                                        &router_id,
+                                    )
+                                }
+                                AsyncInsertError::DatabaseError(e) => {
+                                    public_error_from_diesel_pool(
+                                        e,
+                                        ErrorHandler::Server,
+                                    )
+                                }
+                            })
+                        })?;
+                    }
+                }
+
+                let peering = diesel::insert_into(dsl::vpc_peering)
+                    .values(peering)
+                    .returning(VpcPeering::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                Ok(peering)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::VpcPeering,
+                        name.as_str(),
+                    ),
+                ),
+            })
+    }
+
+    /// Delete one VPC's side of a peering.
+    ///
+    /// If the peering was active, the routes between the VPCs are removed and
+    /// the peer VPC's side of the peering reverts to pending.
+    pub async fn vpc_delete_peering(
+        &self,
+        opctx: &OpContext,
+        authz_peering: &authz::VpcPeering,
+        db_peering: &VpcPeering,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_peering).await?;
+
+        use db::schema::router_route;
+        use db::schema::vpc;
+        use db::schema::vpc_peering::dsl;
+
+        let db_peering = db_peering.clone();
+        let now = Utc::now();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                diesel::update(dsl::vpc_peering)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(db_peering.id()))
+                    .set(dsl::time_deleted.eq(now))
+                    .execute_async(&conn)
+                    .await?;
+
+                let reciprocal = diesel::update(dsl::vpc_peering)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::vpc_id.eq(db_peering.peer_vpc_id))
+                    .filter(dsl::peer_vpc_id.eq(db_peering.vpc_id))
+                    .set((
+                        dsl::state.eq(VpcPeeringState::Pending),
+                        dsl::time_modified.eq(now),
+                    ))
+                    .returning(VpcPeering::as_returning())
+                    .get_result_async(&conn)
+                    .await
+                    .optional()?;
+
+                // Remove the route to the peer VPC from each VPC's system
+                // router. Each route is named after the peering on its side.
+                let mut route_sides =
+                    vec![(db_peering.vpc_id, db_peering.id())];
+                if let Some(reciprocal) = reciprocal {
+                    route_sides.push((reciprocal.vpc_id, reciprocal.id()));
+                }
+                for (vpc_id, peering_id) in route_sides {
+                    let route_name =
+                        Name(RouterRoute::vpc_peering_route_name(peering_id));
+                    diesel::update(router_route::table)
+                        .filter(router_route::time_deleted.is_null())
+                        .filter(router_route::kind.eq(RouterRouteKind(
+                            external::RouterRouteKind::VpcPeering,
+                        )))
+                        .filter(router_route::name.eq(route_name))
+                        .filter(
+                            router_route::vpc_router_id.eq_any(
+                                vpc::table
+                                    .filter(vpc::id.eq(vpc_id))
+                                    .select(vpc::system_router_id),
+                            ),
+                        )
+                        .set(router_route::time_deleted.eq(now))
+                        .execute_async(&conn)
+                        .await?;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e: TransactionError<Error>| match e {
+                TransactionError::CustomError(e) => e,
+                TransactionError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_peering),
+                ),
+            })
+    }
+
+    /// List the peerings of a VPC that are active, along with the VPCs they
+    /// peer with.
+    ///
+    /// Like [`DataStore::resolve_vpc_subnets_to_ip_networks`], this is used
+    /// when resolving a VPC's routes and firewall rules, which refer to the
+    /// peer VPCs whatever the caller's access to them.
+    pub async fn vpc_list_active_peers(
+        &self,
+        vpc_id: Uuid,
+    ) -> ListResultVec<(VpcPeering, Vpc)> {
+        use db::schema::vpc as peer_vpc;
+        use db::schema::vpc_peering::dsl;
+        dsl::vpc_peering
+            .inner_join(peer_vpc::table.on(peer_vpc::id.eq(dsl::peer_vpc_id)))
+            .filter(dsl::vpc_id.eq(vpc_id))
+            .filter(dsl::state.eq(VpcPeeringState::Active))
+            .filter(dsl::time_deleted.is_null())
+            .filter(peer_vpc::time_deleted.is_null())
+            .select((VpcPeering::as_select(), Vpc::as_select()))
+            .load_async(self.pool())
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Identify the address ranges of each VPC: its IPv6 prefix and the IPv4
+    /// blocks of its subnets
+    pub async fn resolve_vpcs_to_ip_networks(
+        &self,
+        vpcs: &[&Vpc],
+    ) -> Result<BTreeMap<Uuid, Vec<IpNetwork>>, Error> {
+        use db::schema::vpc_subnet;
+        let vpc_ids: Vec<Uuid> = vpcs.iter().map(|vpc| vpc.id()).collect();
+        let ipv4_blocks = vpc_subnet::table
+            .filter(vpc_subnet::vpc_id.eq_any(vpc_ids))
+            .filter(vpc_subnet::time_deleted.is_null())
+            .select((vpc_subnet::vpc_id, vpc_subnet::ipv4_block))
+            .get_results_async::<(Uuid, Ipv4Net)>(self.pool())
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        let mut result: BTreeMap<Uuid, Vec<IpNetwork>> = vpcs
+            .iter()
+            .map(|vpc| (vpc.id(), vec![IpNetwork::V6(vpc.ipv6_prefix.0 .0)]))
+            .collect();
+        for (vpc_id, block) in ipv4_blocks {
+            if let Some(networks) = result.get_mut(&vpc_id) {
+                networks.push(IpNetwork::V4(block.0 .0));
+            }
+        }
+        Ok(result)
+    }
+}
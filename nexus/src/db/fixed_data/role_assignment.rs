@@ -37,6 +37,18 @@ lazy_static! {
                 role_builtin::FLEET_VIEWER.role_name,
             ),
 
+            // The "internal-read" user also gets the "network-reconciler" role
+            // on the sole Fleet, which lets it read the VPCs (and the
+            // instances and subnets in them) whose firewall rules it
+            // periodically sends to sleds.
+            RoleAssignment::new(
+                IdentityType::UserBuiltin,
+                user_builtin::USER_INTERNAL_READ.id,
+                role_builtin::FLEET_NETWORK_RECONCILER.resource_type,
+                *FLEET_ID,
+                role_builtin::FLEET_NETWORK_RECONCILER.role_name,
+            ),
+
             // The "external-authenticator" user gets the "authenticator" role
             // on the sole fleet.  This grants them the ability to create
             // sessions.
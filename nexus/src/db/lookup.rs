@@ -251,6 +251,11 @@ impl<'a> LookupPath<'a> {
         Snapshot::PrimaryKey(Root { lookup_root: self }, id)
     }
 
+    /// Select a resource of type FloatingIp, identified by its id
+    pub fn floating_ip_id(self, id: Uuid) -> FloatingIp<'a> {
+        FloatingIp::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     /// Select a resource of type NetworkInterface, identified by its id
     pub fn network_interface_id(self, id: Uuid) -> NetworkInterface<'a> {
         NetworkInterface::PrimaryKey(Root { lookup_root: self }, id)
@@ -266,6 +271,16 @@ impl<'a> LookupPath<'a> {
         VpcSubnet::PrimaryKey(Root { lookup_root: self }, id)
     }
 
+    /// Select a resource of type VpcPeering, identified by its id
+    pub fn vpc_peering_id(self, id: Uuid) -> VpcPeering<'a> {
+        VpcPeering::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
+    /// Select a resource of type InternetGateway, identified by its id
+    pub fn internet_gateway_id(self, id: Uuid) -> InternetGateway<'a> {
+        InternetGateway::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     /// Select a resource of type VpcRouter, identified by its id
     pub fn vpc_router_id(self, id: Uuid) -> VpcRouter<'a> {
         VpcRouter::PrimaryKey(Root { lookup_root: self }, id)
@@ -442,7 +457,12 @@ impl<'a> Root<'a> {
 lookup_resource! {
     name = "Silo",
     ancestors = [],
-    children = [ "Organization", "IdentityProvider", "SamlIdentityProvider"],
+    children = [
+        "Organization",
+        "IdentityProvider",
+        "SamlIdentityProvider",
+        "OidcIdentityProvider"
+    ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -451,7 +471,7 @@ lookup_resource! {
 lookup_resource! {
     name = "SiloUser",
     ancestors = [ "Silo" ],
-    children = [ "SshKey" ],
+    children = [ "SshKey", "ApiToken" ],
     lookup_by_name = false,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ],
@@ -500,6 +520,18 @@ lookup_resource! {
     visible_outside_silo = true
 }
 
+lookup_resource! {
+    name = "OidcIdentityProvider",
+    ancestors = [ "Silo" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [
+        { column_name = "id", rust_type = Uuid },
+    ],
+    visible_outside_silo = true
+}
+
 lookup_resource! {
     name = "SshKey",
     ancestors = [ "Silo", "SiloUser" ],
@@ -509,6 +541,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "ApiToken",
+    ancestors = [ "Silo", "SiloUser" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "Organization",
     ancestors = [ "Silo" ],
@@ -521,7 +562,7 @@ lookup_resource! {
 lookup_resource! {
     name = "Project",
     ancestors = [ "Silo", "Organization" ],
-    children = [ "Disk", "Instance", "Vpc", "Snapshot" ],
+    children = [ "Disk", "Instance", "Vpc", "Snapshot", "FloatingIp" ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -554,6 +595,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "FloatingIp",
+    ancestors = [ "Silo", "Organization", "Project" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "Instance",
     ancestors = [ "Silo", "Organization", "Project" ],
@@ -575,7 +625,7 @@ lookup_resource! {
 lookup_resource! {
     name = "Vpc",
     ancestors = [ "Silo", "Organization", "Project" ],
-    children = [ "VpcRouter", "VpcSubnet" ],
+    children = [ "VpcRouter", "VpcSubnet", "VpcPeering", "InternetGateway" ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -608,6 +658,24 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "VpcPeering",
+    ancestors = [ "Silo", "Organization", "Project", "Vpc" ],
+    children = [ ],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
+lookup_resource! {
+    name = "InternetGateway",
+    ancestors = [ "Silo", "Organization", "Project", "Vpc" ],
+    children = [ ],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 // Miscellaneous resources nested directly below "Fleet"
 
 lookup_resource! {
@@ -40,6 +40,8 @@ pub use nexus_db_model::schema;
 pub use config::Config;
 pub use datastore::DataStore;
 pub use pool::Pool;
+pub(crate) use saga_recovery::load_saga_log;
+pub(crate) use saga_recovery::recover_saga;
 pub use saga_recovery::{recover, RecoveryTask};
 pub use saga_types::SecId;
 pub use sec_store::CockroachDbSecStore;
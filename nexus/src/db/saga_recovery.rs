@@ -214,12 +214,14 @@ async fn list_unfinished_sagas(
 /// Recovers an individual saga
 ///
 /// This function loads the saga log and uses `sec_client` to resume execution.
+/// Besides recovery at startup, this is used when an operator asks this Nexus
+/// to retry a saga.
 ///
 /// This function returns a future that completes when the resumed saga
 /// has completed. The saga executor will attempt to execute the saga
 /// regardless of this future - it is for notification purposes only,
 /// and does not need to be polled.
-async fn recover_saga<'a, T>(
+pub(crate) async fn recover_saga<'a, T>(
     opctx: &'a OpContext,
     uctx: Arc<T::ExecContextType>,
     datastore: &'a db::DataStore,
@@ -277,7 +279,7 @@ where
 }
 
 /// Queries the database to load the full log for the specified saga
-async fn load_saga_log(
+pub(crate) async fn load_saga_log(
     datastore: &db::DataStore,
     saga: &db::saga_types::Saga,
 ) -> Result<Vec<steno::SagaNodeEvent>, Error> {
@@ -7,9 +7,12 @@
 use crate::db::{self, model::Generation};
 use anyhow::Context;
 use async_trait::async_trait;
+use omicron_common::api::external::Error;
 use slog::Logger;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
+use std::sync::Mutex;
 use steno::SagaId;
 
 /// Implementation of [`steno::SecStore`] backed by the Omicron CockroachDB
@@ -18,6 +21,12 @@ pub struct CockroachDbSecStore {
     sec_id: db::SecId,
     datastore: Arc<db::DataStore>,
     log: Logger,
+    /// adoption generation of each unfinished saga that this SEC is executing
+    ///
+    /// Every write to the saga log is conditional on this SEC still owning the
+    /// saga at this generation.  Sagas that this SEC recovered (or that an
+    /// operator asked it to retry) are added the first time they write.
+    generations: Mutex<BTreeMap<SagaId, Generation>>,
 }
 
 impl fmt::Debug for CockroachDbSecStore {
@@ -32,7 +41,70 @@ impl CockroachDbSecStore {
         datastore: Arc<db::DataStore>,
         log: Logger,
     ) -> Self {
-        CockroachDbSecStore { sec_id, datastore, log }
+        CockroachDbSecStore {
+            sec_id,
+            datastore,
+            log,
+            generations: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the generation at which this SEC owns saga `id`, or `None` if
+    /// it no longer owns the saga
+    async fn saga_generation(&self, id: SagaId) -> Option<Generation> {
+        if let Some(generation) = self.generations.lock().unwrap().get(&id) {
+            return Some(*generation);
+        }
+
+        // TODO-robustness This should be wrapped with a retry loop rather than
+        // unwrapping the result.
+        let saga = self
+            .datastore
+            .saga_fetch_unauthorized(id)
+            .await
+            .expect("failed to fetch saga");
+        if saga.current_sec != Some(self.sec_id) {
+            return None;
+        }
+        self.generations.lock().unwrap().insert(id, saga.adopt_generation);
+        Some(saga.adopt_generation)
+    }
+
+    /// Handles a failed write to the log of saga `id`
+    ///
+    /// An operator may have abandoned this saga (or handed it to another SEC)
+    /// while we were still executing it.  We have no way to stop the
+    /// execution, but we must not clobber the state recorded by whoever owns
+    /// it now, so we just stop recording its progress.  Any other failure is
+    /// fatal.
+    async fn saga_write_failed(
+        &self,
+        id: SagaId,
+        generation: Generation,
+        error: Error,
+        what: &str,
+    ) {
+        // TODO-robustness This should be wrapped with a retry loop rather than
+        // unwrapping the result.
+        let saga = self
+            .datastore
+            .saga_fetch_unauthorized(id)
+            .await
+            .expect("failed to fetch saga after failed write");
+        if saga.current_sec != Some(self.sec_id)
+            || saga.adopt_generation != generation
+        {
+            warn!(&self.log, "not recording progress of saga we no longer own";
+                "saga_id" => id.to_string(),
+                "write" => what,
+                "current_sec" => ?saga.current_sec,
+                "adopt_generation" => ?saga.adopt_generation,
+            );
+            self.generations.lock().unwrap().remove(&id);
+            return;
+        }
+
+        panic!("failed to record {} for saga {}: {:#}", what, id, error);
     }
 }
 
@@ -46,10 +118,16 @@ impl steno::SecStore for CockroachDbSecStore {
             "saga_id" => create_params.id.to_string(),
             "saga_name" => create_params.name.to_string(),
         );
+        let saga = db::saga_types::Saga::new(self.sec_id, create_params);
         self.datastore
-            .saga_create(&db::saga_types::Saga::new(self.sec_id, create_params))
+            .saga_create(&saga)
             .await
-            .context("creating saga record")
+            .context("creating saga record")?;
+        self.generations
+            .lock()
+            .unwrap()
+            .insert(saga.id.into(), saga.adopt_generation);
+        Ok(())
     }
 
     async fn record_event(&self, event: steno::SagaNodeEvent) {
@@ -58,27 +136,55 @@ impl steno::SecStore for CockroachDbSecStore {
             "node_id" => ?event.node_id,
             "event_type" => ?event.event_type,
         );
+        let saga_id = event.saga_id;
+        let generation = match self.saga_generation(saga_id).await {
+            Some(generation) => generation,
+            None => {
+                warn!(&self.log, "not recording event for saga we do not own";
+                    "saga_id" => saga_id.to_string(),
+                );
+                return;
+            }
+        };
         let our_event = db::saga_types::SagaNodeEvent::new(event, self.sec_id);
-
-        // TODO-robustness This should be wrapped with a retry loop rather than
-        // unwrapping the result.
-        self.datastore.saga_create_event(&our_event).await.unwrap();
+        if let Err(error) = self
+            .datastore
+            .saga_create_event(&our_event, self.sec_id, generation)
+            .await
+        {
+            self.saga_write_failed(saga_id, generation, error, "event").await;
+        }
     }
 
     async fn saga_update(&self, id: SagaId, update: steno::SagaCachedState) {
-        // TODO-robustness We should track the current generation of the saga
-        // and use it.  We'll know this either from when it was created or when
-        // it was recovered.
         info!(&self.log, "updating state";
             "saga_id" => id.to_string(),
             "new_state" => update.to_string()
         );
+        let generation = match self.saga_generation(id).await {
+            Some(generation) => generation,
+            None => {
+                warn!(&self.log, "not updating state of saga we do not own";
+                    "saga_id" => id.to_string(),
+                    "new_state" => update.to_string(),
+                );
+                return;
+            }
+        };
 
-        // TODO-robustness This should be wrapped with a retry loop rather than
-        // unwrapping the result.
-        self.datastore
-            .saga_update_state(id, update, self.sec_id, Generation::new())
-            .await
-            .unwrap();
+        let result = self
+            .datastore
+            .saga_update_state(id, update, self.sec_id, generation)
+            .await;
+        match result {
+            Ok(()) => {
+                if update == steno::SagaCachedState::Done {
+                    self.generations.lock().unwrap().remove(&id);
+                }
+            }
+            Err(error) => {
+                self.saga_write_failed(id, generation, error, "state").await;
+            }
+        }
     }
 }
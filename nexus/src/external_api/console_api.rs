@@ -7,6 +7,7 @@
 //! This was originally conceived as a separate dropshot server from the
 //! external API, but in order to avoid CORS issues for now, we are serving
 //! these routes directly from the external API.
+use super::params;
 use super::views;
 use crate::authn::{
     silos::IdentityProviderType, USER_TEST_PRIVILEGED, USER_TEST_UNPRIVILEGED,
@@ -38,12 +39,67 @@ use omicron_common::api::external::http_pagination::{
     data_page_params_for, PaginatedById, ScanById, ScanParams,
 };
 use omicron_common::api::external::Error;
+use omicron_common::api::external::ResourceType;
+use rand::RngCore;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_urlencoded;
+use std::net::{IpAddr, SocketAddr};
 use std::{collections::HashSet, ffi::OsString, path::PathBuf, sync::Arc};
 use uuid::Uuid;
 
+/// Returns the User-Agent the client sent with this request, if any
+async fn request_user_agent(
+    rqctx: &RequestContext<Arc<ServerContext>>,
+) -> Option<String> {
+    let request = rqctx.request.lock().await;
+    request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .map(String::from)
+}
+
+/// Returns the IP address of the client that sent this request, if known
+//
+// TODO: Dropshot doesn't give handlers the client's address, except as the
+// "remote_addr" key of the request's logger, so that's where we find it.  Use
+// the request itself once dropshot exposes the address there.
+fn request_client_address(
+    rqctx: &RequestContext<Arc<ServerContext>>,
+) -> Option<IpAddr> {
+    struct RemoteAddr(Option<IpAddr>);
+    impl slog::Serializer for RemoteAddr {
+        fn emit_arguments(
+            &mut self,
+            key: slog::Key,
+            val: &std::fmt::Arguments,
+        ) -> slog::Result {
+            if key == "remote_addr" {
+                self.0 = val
+                    .to_string()
+                    .parse::<SocketAddr>()
+                    .ok()
+                    .map(|addr| addr.ip());
+            }
+            Ok(())
+        }
+    }
+
+    let mut remote_addr = RemoteAddr(None);
+    let record_static = record_static!(slog::Level::Info, "");
+    let _ = slog::KV::serialize(
+        rqctx.log.list(),
+        &slog::Record::new(
+            &record_static,
+            &format_args!(""),
+            slog::BorrowedKV(&()),
+        ),
+        &mut remote_addr,
+    );
+    remote_addr.0
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct SpoofLoginBody {
     pub username: String,
@@ -85,7 +141,16 @@ pub async fn login_spoof(
         // Once we have real SAML login, maybe we can cons up a real OpContext
         // for this user and use their own privileges to create the session.
         let authn_opctx = nexus.opctx_external_authn();
-        let session = nexus.session_create(&authn_opctx, user_id).await?;
+        let user_agent = request_user_agent(&rqctx).await;
+        let client_address = request_client_address(&rqctx);
+        let session = nexus
+            .session_create(
+                &authn_opctx,
+                user_id,
+                user_agent.as_deref(),
+                client_address,
+            )
+            .await?;
 
         let mut response =
             HttpResponseHeaders::new_unnamed(HttpResponseUpdatedNoContent());
@@ -107,7 +172,7 @@ pub async fn login_spoof(
         };
         Ok(response)
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Silos have one or more identity providers, and an unauthenticated user will
@@ -317,10 +382,15 @@ pub async fn login_saml_begin(
 
                 http_response_found(sign_in_url)
             }
+            IdentityProviderType::Oidc(_) => Err(Error::not_found_by_name(
+                ResourceType::SamlIdentityProvider,
+                &path_params.provider_name.0,
+            )
+            .into()),
         }
     };
 
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Authenticate a user
@@ -364,6 +434,13 @@ pub async fn login_saml(
                         nexus.samael_max_issue_delay(),
                     )?
                 }
+                IdentityProviderType::Oidc(_) => {
+                    return Err(Error::not_found_by_name(
+                        ResourceType::SamlIdentityProvider,
+                        &path_params.provider_name.0,
+                    )
+                    .into());
+                }
             };
 
         let relay_state: Option<RelayState> =
@@ -395,7 +472,16 @@ pub async fn login_saml(
         let user = user.unwrap();
 
         // always create a new console session if the user is POSTing here.
-        let session = nexus.session_create(&opctx, user.id()).await?;
+        let user_agent = request_user_agent(&rqctx).await;
+        let client_address = request_client_address(&rqctx);
+        let session = nexus
+            .session_create(
+                &opctx,
+                user.id(),
+                user_agent.as_deref(),
+                client_address,
+            )
+            .await?;
 
         let next_url = if let Some(relay_state) = &relay_state {
             if let Some(referer) = &relay_state.referer {
@@ -437,7 +523,420 @@ pub async fn login_saml(
         }
         Ok(response_with_headers)
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// OpenID Connect login flow
+// -------------------------
+//
+// Nexus is the relying party, and uses the authorization code flow: a GET of
+// /login/{silo_name}/oidc/{provider_name} redirects the user to the OpenID
+// provider's authorization endpoint, with random "state" and "nonce" values
+// that are also stored in a short-lived cookie.  Once the user authenticates,
+// the provider redirects them back to
+//
+//   GET /login/{silo_name}/oidc/{provider_name}/callback?code=...&state=...
+//
+// where Nexus checks the state against the cookie (so that the login must have
+// been started by the same browser), exchanges the code for an ID token at the
+// provider's token endpoint, and validates the token's signature against the
+// provider's keys and its nonce against the cookie.
+
+/// Name of the cookie that holds the state of an OpenID Connect login
+const OIDC_LOGIN_COOKIE_NAME: &str = "oidc_login";
+
+/// How long a user has to complete an OpenID Connect login
+const OIDC_LOGIN_MAX_AGE: chrono::Duration = chrono::Duration::minutes(10);
+
+/// State of an OpenID Connect login, kept in a cookie between the redirect to
+/// the provider and the callback
+#[derive(Deserialize, Serialize)]
+struct OidcLoginState {
+    state: String,
+    nonce: String,
+    referer: Option<String>,
+}
+
+impl OidcLoginState {
+    fn cookie_header_value(
+        &self,
+        path: &str,
+        max_age: chrono::Duration,
+    ) -> Result<http::HeaderValue, HttpError> {
+        let encoded =
+            base64::encode(serde_json::to_string(&self).map_err(|e| {
+                HttpError::for_internal_error(format!(
+                    "encoding OIDC login state: {}",
+                    e
+                ))
+            })?);
+        http::HeaderValue::from_str(&format!(
+            "{}={}; Path={}; HttpOnly; SameSite=Lax; Max-Age={}",
+            OIDC_LOGIN_COOKIE_NAME,
+            encoded,
+            path,
+            max_age.num_seconds()
+        ))
+        .map_err(|error| {
+            HttpError::for_internal_error(format!(
+                "unsupported cookie value: {:#}",
+                error
+            ))
+        })
+    }
+
+    fn from_cookies(cookies: &Cookies) -> Result<Self, anyhow::Error> {
+        let cookie = cookies
+            .get(OIDC_LOGIN_COOKIE_NAME)
+            .ok_or_else(|| anyhow::anyhow!("no OIDC login in progress"))?;
+        serde_json::from_str(
+            &String::from_utf8(
+                base64::decode(cookie.value())
+                    .context("base64 decoding OIDC login state")?,
+            )
+            .context("creating OIDC login state string")?,
+        )
+        .context("json from OIDC login state string")
+    }
+}
+
+/// Returns a random value for the "state" or "nonce" of an OpenID Connect login
+fn oidc_login_random() -> String {
+    // 20 bytes = 160 bits of entropy, as for session tokens
+    let mut random_bytes: [u8; 20] = [0; 20];
+    rand::rngs::OsRng.fill_bytes(&mut random_bytes);
+    hex::encode(random_bytes)
+}
+
+fn oidc_login_cookie_path(path_params: &LoginToProviderPathParam) -> String {
+    format!(
+        "/login/{}/oidc/{}",
+        path_params.silo_name, path_params.provider_name
+    )
+}
+
+/// Prompt user login with an OpenID Connect identity provider
+///
+/// Redirect the user to their identity provider.
+#[endpoint {
+   method = GET,
+   path = "/login/{silo_name}/oidc/{provider_name}",
+   tags = ["login"],
+}]
+pub async fn login_oidc_begin(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<LoginToProviderPathParam>,
+) -> Result<HttpResponseFound, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path_params = path_params.into_inner();
+        let request = &rqctx.request.lock().await;
+
+        // Use opctx_external_authn because this request will be
+        // unauthenticated.
+        let opctx = nexus.opctx_external_authn();
+
+        let (.., identity_provider) = IdentityProviderType::lookup(
+            &nexus.datastore(),
+            &opctx,
+            &path_params.silo_name,
+            &path_params.provider_name,
+        )
+        .await?;
+
+        let oidc_identity_provider = match identity_provider {
+            IdentityProviderType::Oidc(oidc_identity_provider) => {
+                oidc_identity_provider
+            }
+            IdentityProviderType::Saml(_) => {
+                return Err(Error::not_found_by_name(
+                    ResourceType::OidcIdentityProvider,
+                    &path_params.provider_name.0,
+                )
+                .into());
+            }
+        };
+
+        let referer = match request.headers().get(hyper::header::REFERER) {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .map_err(|e| {
+                        HttpError::for_bad_request(
+                            None,
+                            format!("referer header to_str failed! {}", e),
+                        )
+                    })?
+                    .to_string(),
+            ),
+            None => None,
+        };
+        let login_state = OidcLoginState {
+            state: oidc_login_random(),
+            nonce: oidc_login_random(),
+            referer,
+        };
+
+        let sign_in_url = oidc_identity_provider
+            .sign_in_url(&login_state.state, &login_state.nonce)
+            .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+
+        let mut response = http_response_found(sign_in_url)?;
+        response.headers_mut().append(
+            header::SET_COOKIE,
+            login_state.cookie_header_value(
+                &oidc_login_cookie_path(&path_params),
+                OIDC_LOGIN_MAX_AGE,
+            )?,
+        );
+        Ok(response)
+    };
+
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Query parameters with which an OpenID provider redirects the user back to
+/// Nexus
+#[derive(Deserialize, JsonSchema)]
+pub struct OidcCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Authenticate a user with an OpenID Connect identity provider
+///
+/// This is where the identity provider redirects the user after they
+/// authenticate.  Use the authorization code to set the user's session cookie.
+#[endpoint {
+   method = GET,
+   path = "/login/{silo_name}/oidc/{provider_name}/callback",
+   tags = ["login"],
+}]
+pub async fn login_oidc(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<LoginToProviderPathParam>,
+    query_params: Query<OidcCallbackParams>,
+    cookies: Cookies,
+) -> Result<HttpResponseSeeOther, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path_params = path_params.into_inner();
+        let query_params = query_params.into_inner();
+
+        // Use opctx_external_authn because this request will be
+        // unauthenticated.
+        let opctx = nexus.opctx_external_authn();
+
+        let (authz_silo, db_silo, identity_provider) =
+            IdentityProviderType::lookup(
+                &nexus.datastore(),
+                &opctx,
+                &path_params.silo_name,
+                &path_params.provider_name,
+            )
+            .await?;
+
+        let oidc_identity_provider = match identity_provider {
+            IdentityProviderType::Oidc(oidc_identity_provider) => {
+                oidc_identity_provider
+            }
+            IdentityProviderType::Saml(_) => {
+                return Err(Error::not_found_by_name(
+                    ResourceType::OidcIdentityProvider,
+                    &path_params.provider_name.0,
+                )
+                .into());
+            }
+        };
+
+        let login_state =
+            OidcLoginState::from_cookies(&cookies).map_err(|e| {
+                HttpError::for_bad_request(None, format!("{:#}", e))
+            })?;
+        if query_params.state.as_ref() != Some(&login_state.state) {
+            return Err(HttpError::for_bad_request(
+                None,
+                String::from("OIDC login state does not match"),
+            ));
+        }
+
+        if let Some(error) = &query_params.error {
+            return Err(Error::Unauthenticated {
+                internal_message: format!(
+                    "OpenID provider returned error {}: {}",
+                    error,
+                    query_params.error_description.as_deref().unwrap_or("")
+                ),
+            }
+            .into());
+        }
+        let code = query_params.code.as_ref().ok_or_else(|| {
+            HttpError::for_bad_request(
+                None,
+                String::from("missing authorization code"),
+            )
+        })?;
+
+        let authenticated_subject = oidc_identity_provider
+            .authenticated_subject(
+                code,
+                &login_state.nonce,
+                nexus.oidc_jwks_cache(),
+            )
+            .await?;
+
+        let user = nexus
+            .silo_user_from_authenticated_subject(
+                &opctx,
+                &authz_silo,
+                &db_silo,
+                &authenticated_subject,
+            )
+            .await?
+            .ok_or_else(|| Error::Unauthenticated {
+                internal_message: String::from(
+                    "no matching user found or credentials were not valid",
+                ),
+            })?;
+
+        // always create a new console session when a login completes.
+        let user_agent = request_user_agent(&rqctx).await;
+        let client_address = request_client_address(&rqctx);
+        let session = nexus
+            .session_create(
+                &opctx,
+                user.id(),
+                user_agent.as_deref(),
+                client_address,
+            )
+            .await?;
+
+        debug!(
+            &apictx.log,
+            "successful login to silo {} using provider {}: authenticated \
+            subject {} = user id {}",
+            path_params.silo_name,
+            path_params.provider_name,
+            authenticated_subject.external_id,
+            user.id(),
+        );
+
+        let next_url = login_state.referer.unwrap_or_else(|| "/".to_string());
+        let mut response_with_headers = http_response_see_other(next_url)?;
+        {
+            let headers = response_with_headers.headers_mut();
+            headers.append(
+                header::SET_COOKIE,
+                http::HeaderValue::from_str(&session_cookie_header_value(
+                    &session.token,
+                    apictx.session_idle_timeout(),
+                ))
+                .map_err(|error| {
+                    HttpError::for_internal_error(format!(
+                        "unsupported cookie value: {:#}",
+                        error
+                    ))
+                })?,
+            );
+            // The login is complete, so its state is no longer needed.
+            headers.append(
+                header::SET_COOKIE,
+                OidcLoginState {
+                    state: String::new(),
+                    nonce: String::new(),
+                    referer: None,
+                }
+                .cookie_header_value(
+                    &oidc_login_cookie_path(&path_params),
+                    chrono::Duration::zero(),
+                )?,
+            );
+        }
+        Ok(response_with_headers)
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LoginPathParam {
+    pub silo_name: crate::db::model::Name,
+}
+
+/// Authenticate a user via username and password
+///
+/// This is only available in Silos whose users are managed through the API
+/// (see the "local" identity provider), and only for users who have been
+/// given a password.  Repeated failures lock the user out for a while.
+#[endpoint {
+   method = POST,
+   path = "/login/{silo_name}/local",
+   tags = ["login"],
+}]
+pub async fn login_local(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<LoginPathParam>,
+    credentials: TypedBody<params::UsernamePasswordCredentials>,
+) -> Result<HttpResponseHeaders<HttpResponseUpdatedNoContent>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path_params = path_params.into_inner();
+
+        // Use opctx_external_authn because this request will be
+        // unauthenticated.
+        let opctx = nexus.opctx_external_authn();
+
+        let user = nexus
+            .local_idp_login(
+                &opctx,
+                &path_params.silo_name,
+                credentials.into_inner(),
+            )
+            .await?;
+        let user_agent = request_user_agent(&rqctx).await;
+        let client_address = request_client_address(&rqctx);
+        let session = nexus
+            .session_create(
+                &opctx,
+                user.id(),
+                user_agent.as_deref(),
+                client_address,
+            )
+            .await?;
+
+        debug!(
+            &apictx.log,
+            "successful login to silo {} using local identity provider: \
+            user id {}",
+            path_params.silo_name,
+            user.id(),
+        );
+
+        let mut response =
+            HttpResponseHeaders::new_unnamed(HttpResponseUpdatedNoContent());
+        {
+            let headers = response.headers_mut();
+            headers.append(
+                header::SET_COOKIE,
+                http::HeaderValue::from_str(&session_cookie_header_value(
+                    &session.token,
+                    apictx.session_idle_timeout(),
+                ))
+                .map_err(|error| {
+                    HttpError::for_internal_error(format!(
+                        "unsupported cookie value: {:#}",
+                        error
+                    ))
+                })?,
+            );
+        }
+        Ok(response)
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Log user out of web console by deleting session in both server and browser
@@ -495,7 +994,7 @@ pub async fn logout(
         Ok(response)
     };
 
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -584,7 +1083,7 @@ pub async fn login_begin(
         let login_url = get_login_url(redirect_url);
         http_response_found(login_url)
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Fetch the user associated with the current session
@@ -606,7 +1105,7 @@ pub async fn session_me(
         let user = nexus.silo_user_fetch_self(&opctx).await?;
         Ok(HttpResponseOk(user.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Fetch the silo groups the current user belongs to
@@ -642,7 +1141,7 @@ pub async fn session_me_groups(
             &|_, group: &views::Group| group.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 pub async fn console_index_or_login_redirect(
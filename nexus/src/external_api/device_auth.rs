@@ -115,9 +115,7 @@ pub async fn device_auth_request(
             &model.into_response(rqctx.server.tls, host),
         )
     };
-    // TODO: instrumentation doesn't work because we use `Response<Body>`
-    //apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-    handler.await
+    apictx.audit_external_handler(&rqctx, handler).await
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -185,7 +183,7 @@ pub async fn device_auth_confirm(
             .await?;
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -268,7 +266,5 @@ pub async fn device_access_token(
             ),
         }
     };
-    // TODO: instrumentation doesn't work because we use `Response<Body>`
-    //apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-    handler.await
+    apictx.audit_external_handler(&rqctx, handler).await
 }
@@ -7,19 +7,21 @@
 use super::views::IpPool;
 use super::views::IpPoolRange;
 use super::{
-    console_api, device_auth, params, views,
+    console_api, device_auth, params, scim, views,
     views::{
         GlobalImage, Group, IdentityProvider, Image, Organization, Project,
         Rack, Role, Silo, Sled, Snapshot, SshKey, User, UserBuiltin, Vpc,
         VpcRouter, VpcSubnet,
     },
 };
+use crate::authn::external::session_cookie::SessionStore;
 use crate::authz;
 use crate::context::OpContext;
 use crate::db;
 use crate::db::model::Name;
 use crate::external_api::shared;
 use crate::ServerContext;
+use chrono::{DateTime, Utc};
 use dropshot::ApiDescription;
 use dropshot::EmptyScanParams;
 use dropshot::HttpError;
@@ -105,6 +107,10 @@ pub fn external_api() -> NexusApiDescription {
         api.register(project_update)?;
         api.register(project_policy_view)?;
         api.register(project_policy_update)?;
+        api.register(project_settings_view)?;
+        api.register(project_settings_update)?;
+        api.register(project_quotas_view)?;
+        api.register(project_quotas_update)?;
 
         // Customer-Accessible IP Pools API
         api.register(ip_pool_list)?;
@@ -132,6 +138,8 @@ pub fn external_api() -> NexusApiDescription {
         api.register(disk_view)?;
         api.register(disk_view_by_id)?;
         api.register(disk_delete)?;
+        api.register(disk_policy_view)?;
+        api.register(disk_policy_update)?;
         api.register(disk_metrics_list)?;
 
         api.register(instance_list)?;
@@ -139,7 +147,10 @@ pub fn external_api() -> NexusApiDescription {
         api.register(instance_view)?;
         api.register(instance_view_by_id)?;
         api.register(instance_delete)?;
+        api.register(instance_policy_view)?;
+        api.register(instance_policy_update)?;
         api.register(instance_migrate)?;
+        api.register(instance_tags_update)?;
         api.register(instance_reboot)?;
         api.register(instance_start)?;
         api.register(instance_stop)?;
@@ -163,12 +174,22 @@ pub fn external_api() -> NexusApiDescription {
         api.register(snapshot_view_by_id)?;
         api.register(snapshot_delete)?;
 
+        api.register(floating_ip_list)?;
+        api.register(floating_ip_create)?;
+        api.register(floating_ip_view)?;
+        api.register(floating_ip_view_by_id)?;
+        api.register(floating_ip_delete)?;
+        api.register(floating_ip_attach)?;
+        api.register(floating_ip_detach)?;
+
         api.register(vpc_list)?;
         api.register(vpc_create)?;
         api.register(vpc_view)?;
         api.register(vpc_view_by_id)?;
         api.register(vpc_update)?;
         api.register(vpc_delete)?;
+        api.register(vpc_policy_view)?;
+        api.register(vpc_policy_update)?;
 
         api.register(vpc_subnet_list)?;
         api.register(vpc_subnet_view)?;
@@ -177,6 +198,8 @@ pub fn external_api() -> NexusApiDescription {
         api.register(vpc_subnet_delete)?;
         api.register(vpc_subnet_update)?;
         api.register(vpc_subnet_list_network_interfaces)?;
+        api.register(vpc_subnet_router_attach)?;
+        api.register(vpc_subnet_router_detach)?;
 
         api.register(instance_network_interface_create)?;
         api.register(instance_network_interface_list)?;
@@ -186,6 +209,8 @@ pub fn external_api() -> NexusApiDescription {
         api.register(instance_network_interface_delete)?;
 
         api.register(instance_external_ip_list)?;
+        api.register(instance_ephemeral_ip_attach)?;
+        api.register(instance_ephemeral_ip_detach)?;
 
         api.register(vpc_router_list)?;
         api.register(vpc_router_view)?;
@@ -203,6 +228,20 @@ pub fn external_api() -> NexusApiDescription {
 
         api.register(vpc_firewall_rules_view)?;
         api.register(vpc_firewall_rules_update)?;
+        api.register(vpc_firewall_rules_evaluate)?;
+
+        api.register(vpc_peering_list)?;
+        api.register(vpc_peering_view)?;
+        api.register(vpc_peering_view_by_id)?;
+        api.register(vpc_peering_create)?;
+        api.register(vpc_peering_delete)?;
+
+        api.register(internet_gateway_list)?;
+        api.register(internet_gateway_view)?;
+        api.register(internet_gateway_view_by_id)?;
+        api.register(internet_gateway_create)?;
+        api.register(internet_gateway_update)?;
+        api.register(internet_gateway_delete)?;
 
         api.register(rack_list)?;
         api.register(rack_view)?;
@@ -211,6 +250,9 @@ pub fn external_api() -> NexusApiDescription {
 
         api.register(saga_list)?;
         api.register(saga_view)?;
+        api.register(saga_log_view)?;
+        api.register(saga_abandon)?;
+        api.register(saga_retry)?;
 
         api.register(system_user_list)?;
         api.register(system_user_view)?;
@@ -225,6 +267,15 @@ pub fn external_api() -> NexusApiDescription {
         api.register(session_sshkey_create)?;
         api.register(session_sshkey_delete)?;
 
+        api.register(session_api_token_list)?;
+        api.register(session_api_token_view)?;
+        api.register(session_api_token_create)?;
+        api.register(session_api_token_delete)?;
+        api.register(session_device_token_list)?;
+        api.register(session_device_token_delete)?;
+        api.register(session_console_session_list)?;
+        api.register(session_console_session_delete)?;
+
         // Fleet-wide API operations
         api.register(silo_list)?;
         api.register(silo_create)?;
@@ -234,12 +285,21 @@ pub fn external_api() -> NexusApiDescription {
         api.register(silo_identity_provider_list)?;
         api.register(silo_policy_view)?;
         api.register(silo_policy_update)?;
+        api.register(silo_quotas_view)?;
+        api.register(silo_quotas_update)?;
 
         api.register(saml_identity_provider_create)?;
         api.register(saml_identity_provider_view)?;
+        api.register(oidc_identity_provider_create)?;
+        api.register(oidc_identity_provider_view)?;
 
         api.register(local_idp_user_create)?;
         api.register(local_idp_user_delete)?;
+        api.register(local_idp_user_set_password)?;
+
+        api.register(scim_token_list)?;
+        api.register(scim_token_create)?;
+        api.register(scim_token_delete)?;
 
         api.register(system_image_list)?;
         api.register(system_image_create)?;
@@ -252,6 +312,18 @@ pub fn external_api() -> NexusApiDescription {
         api.register(user_list)?;
         api.register(silo_users_list)?;
         api.register(silo_user_view)?;
+        api.register(silo_user_sessions_list)?;
+        api.register(silo_user_sessions_delete)?;
+        api.register(silo_user_session_delete)?;
+        api.register(system_audit_log_list)?;
+        api.register(silo_audit_log_list)?;
+        api.register(silo_groups_list)?;
+        api.register(silo_group_create)?;
+        api.register(silo_group_view)?;
+        api.register(silo_group_delete)?;
+        api.register(silo_group_members_list)?;
+        api.register(silo_group_member_add)?;
+        api.register(silo_group_member_remove)?;
         api.register(group_list)?;
 
         // Console API operations
@@ -260,6 +332,9 @@ pub fn external_api() -> NexusApiDescription {
         api.register(console_api::login_spoof)?;
         api.register(console_api::login_saml_begin)?;
         api.register(console_api::login_saml)?;
+        api.register(console_api::login_oidc_begin)?;
+        api.register(console_api::login_oidc)?;
+        api.register(console_api::login_local)?;
         api.register(console_api::logout)?;
 
         api.register(console_api::session_me)?;
@@ -276,6 +351,19 @@ pub fn external_api() -> NexusApiDescription {
         api.register(device_auth::device_auth_confirm)?;
         api.register(device_auth::device_access_token)?;
 
+        api.register(scim::scim_user_list)?;
+        api.register(scim::scim_user_create)?;
+        api.register(scim::scim_user_view)?;
+        api.register(scim::scim_user_replace)?;
+        api.register(scim::scim_user_patch)?;
+        api.register(scim::scim_user_delete)?;
+        api.register(scim::scim_group_list)?;
+        api.register(scim::scim_group_create)?;
+        api.register(scim::scim_group_view)?;
+        api.register(scim::scim_group_replace)?;
+        api.register(scim::scim_group_patch)?;
+        api.register(scim::scim_group_delete)?;
+
         Ok(())
     }
 
@@ -347,7 +435,7 @@ async fn system_policy_view(
         let policy = nexus.fleet_fetch_policy(&opctx).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for `/by-id/` endpoints
@@ -378,7 +466,7 @@ async fn system_policy_update(
         let policy = nexus.fleet_update_policy(&opctx, &new_policy).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Fetch the current silo's IAM policy
@@ -403,7 +491,7 @@ pub async fn policy_view(
         let policy = nexus.silo_fetch_policy(&opctx, lookup).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Update the current silo's IAM policy
@@ -434,7 +522,7 @@ async fn policy_update(
             nexus.silo_update_policy(&opctx, lookup, &new_policy).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// List silos
@@ -479,7 +567,7 @@ async fn silo_list(
             &marker_for_name_or_id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Create a silo
@@ -500,7 +588,7 @@ async fn silo_create(
             nexus.silo_create(&opctx, new_silo_params.into_inner()).await?;
         Ok(HttpResponseCreated(silo.try_into()?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for Silo requests
@@ -531,7 +619,7 @@ async fn silo_view(
         let silo = nexus.silo_fetch(&opctx, &silo_name).await?;
         Ok(HttpResponseOk(silo.try_into()?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Fetch a silo by id
@@ -553,7 +641,7 @@ async fn silo_view_by_id(
         let silo = nexus.silo_fetch_by_id(&opctx, id).await?;
         Ok(HttpResponseOk(silo.try_into()?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Delete a silo
@@ -577,7 +665,7 @@ async fn silo_delete(
         nexus.silo_delete(&opctx, &silo_name).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Fetch a silo's IAM policy
@@ -601,7 +689,7 @@ async fn silo_policy_view(
         let policy = nexus.silo_fetch_policy(&opctx, lookup).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Update a silo's IAM policy
@@ -631,7 +719,64 @@ async fn silo_policy_update(
             nexus.silo_update_policy(&opctx, lookup, &new_policy).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// Silo-specific quotas
+
+/// Fetch a silo's quotas
+///
+/// The response includes the resources allocated within the silo that count
+/// against its quotas.
+#[endpoint {
+    method = GET,
+    path = "/system/silos/{silo_name}/quotas",
+    tags = ["system"],
+}]
+async fn silo_quotas_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+) -> Result<HttpResponseOk<views::ResourceQuotas>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let silo_name = &path.silo_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let quotas = nexus.silo_fetch_quotas(&opctx, silo_name).await?;
+        Ok(HttpResponseOk(quotas.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update a silo's quotas
+///
+/// The quotas limit the resources that can be allocated across all projects
+/// in the silo.
+#[endpoint {
+    method = PUT,
+    path = "/system/silos/{silo_name}/quotas",
+    tags = ["system"],
+}]
+async fn silo_quotas_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+    new_quotas: TypedBody<params::ResourceQuotasUpdate>,
+) -> Result<HttpResponseOk<views::ResourceQuotas>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let new_quotas = new_quotas.into_inner();
+    let silo_name = &path.silo_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let quotas =
+            nexus.silo_update_quotas(&opctx, silo_name, &new_quotas).await?;
+        Ok(HttpResponseOk(quotas.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Silo-specific user endpoints
@@ -666,7 +811,7 @@ async fn silo_users_list(
             &|_, user: &User| user.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for Silo User requests
@@ -701,651 +846,2052 @@ async fn silo_user_view(
             .await?;
         Ok(HttpResponseOk(user.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Silo identity providers
-
-/// List a silo's IDPs
+/// List a user's console sessions
+///
+/// List the unexpired web console sessions of a user in a specific Silo.
 #[endpoint {
     method = GET,
-    path = "/system/silos/{silo_name}/identity-providers",
+    path = "/system/silos/{silo_name}/users/id/{user_id}/sessions",
     tags = ["system"],
 }]
-async fn silo_identity_provider_list(
+async fn silo_user_sessions_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<SiloPathParam>,
-    query_params: Query<PaginatedByName>,
-) -> Result<HttpResponseOk<ResultsPage<IdentityProvider>>, HttpError> {
+    path_params: Path<UserPathParam>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<views::ConsoleSession>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let path = path_params.into_inner();
-    let silo_name = &path.silo_name;
+    let path_params = path_params.into_inner();
+    let query = query_params.into_inner();
+    let pagparams = data_page_params_for(&rqctx, &query)?;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let query = query_params.into_inner();
-        let pagination_params = data_page_params_for(&rqctx, &query)?
-            .map_name(|n| Name::ref_cast(n));
-        let identity_providers = nexus
-            .identity_provider_list(&opctx, &silo_name, &pagination_params)
+        let (last_used_after, created_after) = session_expiry_cutoffs(apictx);
+        let sessions = nexus
+            .silo_user_sessions_list(
+                &opctx,
+                &path_params.silo_name,
+                path_params.user_id,
+                last_used_after,
+                created_after,
+                &pagparams,
+            )
             .await?
             .into_iter()
-            .map(|x| x.into())
+            .map(views::ConsoleSession::from)
             .collect();
-        Ok(HttpResponseOk(ScanByName::results_page(
+        Ok(HttpResponseOk(ScanById::results_page(
             &query,
-            identity_providers,
-            &marker_for_name,
+            sessions,
+            &|_, session: &views::ConsoleSession| session.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Silo SAML identity providers
-
-/// Create a SAML IDP
+/// Log a user out everywhere
+///
+/// Revoke all of the web console sessions of a user in a specific Silo.
 #[endpoint {
-    method = POST,
-    path = "/system/silos/{silo_name}/identity-providers/saml",
+    method = DELETE,
+    path = "/system/silos/{silo_name}/users/id/{user_id}/sessions",
     tags = ["system"],
 }]
-async fn saml_identity_provider_create(
+async fn silo_user_sessions_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<SiloPathParam>,
-    new_provider: TypedBody<params::SamlIdentityProviderCreate>,
-) -> Result<HttpResponseCreated<views::SamlIdentityProvider>, HttpError> {
+    path_params: Path<UserPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-
+    let path_params = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let provider = nexus
-            .saml_identity_provider_create(
+        nexus
+            .silo_user_sessions_delete(
                 &opctx,
-                &path_params.into_inner().silo_name,
-                new_provider.into_inner(),
+                &path_params.silo_name,
+                path_params.user_id,
             )
             .await?;
-        Ok(HttpResponseCreated(provider.into()))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Silo SAML identity provider requests
+/// Path parameters for requests about a Silo User's console sessions
 #[derive(Deserialize, JsonSchema)]
-struct SiloSamlPathParam {
+struct UserSessionPathParam {
     /// The silo's unique name.
     silo_name: Name,
-    /// The SAML identity provider's name
-    provider_name: Name,
+    /// The user's internal id
+    user_id: Uuid,
+    /// The session's id
+    session_id: Uuid,
 }
 
-/// Fetch a SAML IDP
+/// Revoke a user's console session
 #[endpoint {
-    method = GET,
-    path = "/system/silos/{silo_name}/identity-providers/saml/{provider_name}",
+    method = DELETE,
+    path = "/system/silos/{silo_name}/users/id/{user_id}/sessions/{session_id}",
     tags = ["system"],
 }]
-async fn saml_identity_provider_view(
+async fn silo_user_session_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<SiloSamlPathParam>,
-) -> Result<HttpResponseOk<views::SamlIdentityProvider>, HttpError> {
+    path_params: Path<UserSessionPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-
     let path_params = path_params.into_inner();
-
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let provider = nexus
-            .saml_identity_provider_fetch(
+        nexus
+            .silo_user_session_delete(
                 &opctx,
                 &path_params.silo_name,
-                &path_params.provider_name,
+                path_params.user_id,
+                path_params.session_id,
             )
             .await?;
-
-        Ok(HttpResponseOk(provider.into()))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// TODO: no DELETE for identity providers?
+/// Returns the times at or before which a console session must have been last
+/// used or created, respectively, to have expired
+fn session_expiry_cutoffs(
+    apictx: &ServerContext,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let now = Utc::now();
+    (
+        now - apictx.session_idle_timeout(),
+        now - apictx.session_absolute_timeout(),
+    )
+}
 
-// "Local" Identity Provider
+// Audit log
 
-/// Create a user
+/// Page selector for the audit log
 ///
-/// Users can only be created in Silos with `provision_type` == `Fixed`.
-/// Otherwise, Silo users are just-in-time (JIT) provisioned when a user first
-/// logs in using an external Identity Provider.
+/// Entries are ordered by the time the request completed, with the entry's id
+/// breaking ties.  The filter is carried from page to page.
+#[derive(Deserialize, JsonSchema, Serialize)]
+struct AuditLogPage {
+    #[serde(flatten)]
+    filter: params::AuditLogFilter,
+    last_seen_time: DateTime<Utc>,
+    last_seen_id: Uuid,
+}
+
+type AuditLogPaginationParams =
+    PaginationParams<params::AuditLogFilter, AuditLogPage>;
+
+/// Returns the filter and the pagination marker for a page of the audit log
+fn audit_log_filter_and_marker(
+    query: &AuditLogPaginationParams,
+) -> (params::AuditLogFilter, Option<(DateTime<Utc>, Uuid)>) {
+    match &query.page {
+        WhichPage::First(filter) => (filter.clone(), None),
+        WhichPage::Next(page) => (
+            page.filter.clone(),
+            Some((page.last_seen_time, page.last_seen_id)),
+        ),
+    }
+}
+
+/// Builds a page of results from the audit log
+fn audit_log_results_page(
+    filter: &params::AuditLogFilter,
+    entries: Vec<db::model::AuditLogEntry>,
+) -> Result<ResultsPage<views::AuditLogEntry>, HttpError> {
+    let entries = entries.into_iter().map(views::AuditLogEntry::from).collect();
+    ResultsPage::new(entries, filter, |entry: &views::AuditLogEntry, filter| {
+        AuditLogPage {
+            filter: filter.clone(),
+            last_seen_time: entry.time_completed,
+            last_seen_id: entry.id,
+        }
+    })
+}
+
+/// List audit log entries
+///
+/// List the requests made to the external API that changed (or tried to
+/// change) the state of the system, oldest first.
 #[endpoint {
-    method = POST,
-    path = "/system/silos/{silo_name}/identity-providers/local/users",
+    method = GET,
+    path = "/system/audit-log",
     tags = ["system"],
 }]
-async fn local_idp_user_create(
+async fn system_audit_log_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<SiloPathParam>,
-    new_user_params: TypedBody<params::UserCreate>,
-) -> Result<HttpResponseCreated<User>, HttpError> {
+    query_params: Query<AuditLogPaginationParams>,
+) -> Result<HttpResponseOk<ResultsPage<views::AuditLogEntry>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let silo_name = path_params.into_inner().silo_name;
+    let query = query_params.into_inner();
+    let (filter, marker) = audit_log_filter_and_marker(&query);
+    let pagparams = DataPageParams {
+        limit: rqctx.page_limit(&query)?,
+        direction: PaginationOrder::Ascending,
+        marker: marker.as_ref(),
+    };
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let user = nexus
-            .local_idp_create_user(
-                &opctx,
-                &silo_name,
-                new_user_params.into_inner(),
-            )
-            .await?;
-        Ok(HttpResponseCreated(user.into()))
+        let entries = nexus.audit_log_list(&opctx, &filter, &pagparams).await?;
+        Ok(HttpResponseOk(audit_log_results_page(&filter, entries)?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
+/// List audit log entries for a Silo
+///
+/// List the requests made to the external API by users (and SCIM clients) of a
+/// specific Silo that changed (or tried to change) the state of the system,
+/// oldest first.
 #[endpoint {
-    method = DELETE,
-    path = "/system/silos/{silo_name}/identity-providers/local/users/{user_id}",
+    method = GET,
+    path = "/system/silos/{silo_name}/audit-log",
     tags = ["system"],
 }]
-async fn local_idp_user_delete(
+async fn silo_audit_log_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<UserPathParam>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    path_params: Path<SiloPathParam>,
+    query_params: Query<AuditLogPaginationParams>,
+) -> Result<HttpResponseOk<ResultsPage<views::AuditLogEntry>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let path_params = path_params.into_inner();
+    let silo_name = path_params.into_inner().silo_name;
+    let query = query_params.into_inner();
+    let (filter, marker) = audit_log_filter_and_marker(&query);
+    let pagparams = DataPageParams {
+        limit: rqctx.page_limit(&query)?,
+        direction: PaginationOrder::Ascending,
+        marker: marker.as_ref(),
+    };
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus
-            .local_idp_delete_user(
-                &opctx,
-                &path_params.silo_name,
-                path_params.user_id,
-            )
+        let entries = nexus
+            .silo_audit_log_list(&opctx, &silo_name, &filter, &pagparams)
             .await?;
-        Ok(HttpResponseDeleted())
+        Ok(HttpResponseOk(audit_log_results_page(&filter, entries)?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List organizations
+// Silo-specific group endpoints
+
+/// List groups in a specific Silo
 #[endpoint {
     method = GET,
-    path = "/organizations",
-    tags = ["organizations"],
+    path = "/system/silos/{silo_name}/groups",
+    tags = ["system"],
 }]
-async fn organization_list(
+async fn silo_groups_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    query_params: Query<PaginatedByNameOrId>,
-) -> Result<HttpResponseOk<ResultsPage<Organization>>, HttpError> {
+    path_params: Path<SiloPathParam>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<Group>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
+    let silo_name = path_params.into_inner().silo_name;
+    let query = query_params.into_inner();
+    let pagparams = data_page_params_for(&rqctx, &query)?;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let query = query_params.into_inner();
-        let params = ScanByNameOrId::from_query(&query)?;
-        let field = pagination_field_for_scan_params(params);
-
-        let organizations = match field {
-            PagField::Id => {
-                let page_selector = data_page_params_nameid_id(&rqctx, &query)?;
-                nexus.organizations_list_by_id(&opctx, &page_selector).await?
-            }
-
-            PagField::Name => {
-                let page_selector =
-                    data_page_params_nameid_name(&rqctx, &query)?
-                        .map_name(|n| Name::ref_cast(n));
-                nexus.organizations_list_by_name(&opctx, &page_selector).await?
-            }
-        }
-        .into_iter()
-        .map(|p| p.into())
-        .collect();
-        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+        let groups = nexus
+            .silo_list_groups(&opctx, &silo_name, &pagparams)
+            .await?
+            .into_iter()
+            .map(|i| i.into())
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
             &query,
-            organizations,
-            &marker_for_name_or_id,
+            groups,
+            &|_, group: &Group| group.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create an organization
+/// Create a group
+///
+/// Groups can only be created in Silos with `identity_mode` ==
+/// `local_only`.  Otherwise, groups are managed by the Silo's identity
+/// provider.
 #[endpoint {
     method = POST,
-    path = "/organizations",
-    tags = ["organizations"],
+    path = "/system/silos/{silo_name}/groups",
+    tags = ["system"],
 }]
-async fn organization_create(
+async fn silo_group_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    new_organization: TypedBody<params::OrganizationCreate>,
-) -> Result<HttpResponseCreated<Organization>, HttpError> {
+    path_params: Path<SiloPathParam>,
+    new_group_params: TypedBody<params::GroupCreate>,
+) -> Result<HttpResponseCreated<Group>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
+    let silo_name = path_params.into_inner().silo_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let organization = nexus
-            .organization_create(&opctx, &new_organization.into_inner())
+        let group = nexus
+            .silo_group_create(
+                &opctx,
+                &silo_name,
+                new_group_params.into_inner(),
+            )
             .await?;
-        Ok(HttpResponseCreated(organization.into()))
+        Ok(HttpResponseCreated(group.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Organization requests
+/// Path parameters for Silo Group requests
 #[derive(Deserialize, JsonSchema)]
-struct OrganizationPathParam {
-    /// The organization's unique name.
-    organization_name: Name,
+struct GroupPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The group's internal id
+    group_id: Uuid,
 }
 
-/// Fetch an organization
+/// Fetch a group
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}",
-    tags = ["organizations"],
+    path = "/system/silos/{silo_name}/groups/{group_id}",
+    tags = ["system"],
 }]
-async fn organization_view(
+async fn silo_group_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<OrganizationPathParam>,
-) -> Result<HttpResponseOk<Organization>, HttpError> {
+    path_params: Path<GroupPathParam>,
+) -> Result<HttpResponseOk<Group>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
+    let path_params = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let organization =
-            nexus.organization_fetch(&opctx, &organization_name).await?;
-        Ok(HttpResponseOk(organization.into()))
+        let group = nexus
+            .silo_group_fetch(
+                &opctx,
+                &path_params.silo_name,
+                path_params.group_id,
+            )
+            .await?;
+        Ok(HttpResponseOk(group.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Delete a group
+///
+/// Only groups without members can be deleted.  Any roles granted to the
+/// group are revoked.
+#[endpoint {
+    method = DELETE,
+    path = "/system/silos/{silo_name}/groups/{group_id}",
+    tags = ["system"],
+}]
+async fn silo_group_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<GroupPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus
+            .silo_group_delete(
+                &opctx,
+                &path_params.silo_name,
+                path_params.group_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// List a group's members
+#[endpoint {
+    method = GET,
+    path = "/system/silos/{silo_name}/groups/{group_id}/members",
+    tags = ["system"],
+}]
+async fn silo_group_members_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<GroupPathParam>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<User>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let query = query_params.into_inner();
+    let pagparams = data_page_params_for(&rqctx, &query)?;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let users = nexus
+            .silo_group_list_members(
+                &opctx,
+                &path_params.silo_name,
+                path_params.group_id,
+                &pagparams,
+            )
+            .await?
+            .into_iter()
+            .map(|i| i.into())
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            users,
+            &|_, user: &User| user.id,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Add a user to a group
+#[endpoint {
+    method = POST,
+    path = "/system/silos/{silo_name}/groups/{group_id}/members",
+    tags = ["system"],
+}]
+async fn silo_group_member_add(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<GroupPathParam>,
+    member_params: TypedBody<params::GroupMemberAdd>,
+) -> Result<HttpResponseCreated<User>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let user = nexus
+            .silo_group_add_member(
+                &opctx,
+                &path_params.silo_name,
+                path_params.group_id,
+                member_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(user.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Silo Group membership requests
+#[derive(Deserialize, JsonSchema)]
+struct GroupMemberPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The group's internal id
+    group_id: Uuid,
+    /// The member's internal user id
+    user_id: Uuid,
+}
+
+/// Remove a user from a group
+#[endpoint {
+    method = DELETE,
+    path = "/system/silos/{silo_name}/groups/{group_id}/members/{user_id}",
+    tags = ["system"],
+}]
+async fn silo_group_member_remove(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<GroupMemberPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus
+            .silo_group_remove_member(
+                &opctx,
+                &path_params.silo_name,
+                path_params.group_id,
+                path_params.user_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// Silo identity providers
+
+/// List a silo's IDPs
+#[endpoint {
+    method = GET,
+    path = "/system/silos/{silo_name}/identity-providers",
+    tags = ["system"],
+}]
+async fn silo_identity_provider_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+    query_params: Query<PaginatedByName>,
+) -> Result<HttpResponseOk<ResultsPage<IdentityProvider>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let silo_name = &path.silo_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let query = query_params.into_inner();
+        let pagination_params = data_page_params_for(&rqctx, &query)?
+            .map_name(|n| Name::ref_cast(n));
+        let identity_providers = nexus
+            .identity_provider_list(&opctx, &silo_name, &pagination_params)
+            .await?
+            .into_iter()
+            .map(|x| x.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByName::results_page(
+            &query,
+            identity_providers,
+            &marker_for_name,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// Silo SAML identity providers
+
+/// Create a SAML IDP
+#[endpoint {
+    method = POST,
+    path = "/system/silos/{silo_name}/identity-providers/saml",
+    tags = ["system"],
+}]
+async fn saml_identity_provider_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+    new_provider: TypedBody<params::SamlIdentityProviderCreate>,
+) -> Result<HttpResponseCreated<views::SamlIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let provider = nexus
+            .saml_identity_provider_create(
+                &opctx,
+                &path_params.into_inner().silo_name,
+                new_provider.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(provider.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Silo SAML identity provider requests
+#[derive(Deserialize, JsonSchema)]
+struct SiloSamlPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The SAML identity provider's name
+    provider_name: Name,
+}
+
+/// Fetch a SAML IDP
+#[endpoint {
+    method = GET,
+    path = "/system/silos/{silo_name}/identity-providers/saml/{provider_name}",
+    tags = ["system"],
+}]
+async fn saml_identity_provider_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloSamlPathParam>,
+) -> Result<HttpResponseOk<views::SamlIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+
+    let path_params = path_params.into_inner();
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let provider = nexus
+            .saml_identity_provider_fetch(
+                &opctx,
+                &path_params.silo_name,
+                &path_params.provider_name,
+            )
+            .await?;
+
+        Ok(HttpResponseOk(provider.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// Silo OpenID Connect identity providers
+
+/// Create an OpenID Connect IDP
+///
+/// The provider's discovery document is fetched from its issuer when the
+/// provider is created.
+#[endpoint {
+    method = POST,
+    path = "/system/silos/{silo_name}/identity-providers/oidc",
+    tags = ["system"],
+}]
+async fn oidc_identity_provider_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+    new_provider: TypedBody<params::OidcIdentityProviderCreate>,
+) -> Result<HttpResponseCreated<views::OidcIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let provider = nexus
+            .oidc_identity_provider_create(
+                &opctx,
+                &path_params.into_inner().silo_name,
+                new_provider.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(provider.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Silo OpenID Connect identity provider requests
+#[derive(Deserialize, JsonSchema)]
+struct SiloOidcPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The OpenID Connect identity provider's name
+    provider_name: Name,
+}
+
+/// Fetch an OpenID Connect IDP
+#[endpoint {
+    method = GET,
+    path = "/system/silos/{silo_name}/identity-providers/oidc/{provider_name}",
+    tags = ["system"],
+}]
+async fn oidc_identity_provider_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloOidcPathParam>,
+) -> Result<HttpResponseOk<views::OidcIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+
+    let path_params = path_params.into_inner();
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let provider = nexus
+            .oidc_identity_provider_fetch(
+                &opctx,
+                &path_params.silo_name,
+                &path_params.provider_name,
+            )
+            .await?;
+
+        Ok(HttpResponseOk(provider.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// TODO: no DELETE for identity providers?
+
+// "Local" Identity Provider
+
+/// Create a user
+///
+/// Users can only be created in Silos with `provision_type` == `Fixed`.
+/// Otherwise, Silo users are just-in-time (JIT) provisioned when a user first
+/// logs in using an external Identity Provider.
+#[endpoint {
+    method = POST,
+    path = "/system/silos/{silo_name}/identity-providers/local/users",
+    tags = ["system"],
+}]
+async fn local_idp_user_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+    new_user_params: TypedBody<params::UserCreate>,
+) -> Result<HttpResponseCreated<User>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let silo_name = path_params.into_inner().silo_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let user = nexus
+            .local_idp_create_user(
+                &opctx,
+                &silo_name,
+                new_user_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(user.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+#[endpoint {
+    method = DELETE,
+    path = "/system/silos/{silo_name}/identity-providers/local/users/{user_id}",
+    tags = ["system"],
+}]
+async fn local_idp_user_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<UserPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus
+            .local_idp_delete_user(
+                &opctx,
+                &path_params.silo_name,
+                path_params.user_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Set or reset a user's password
+///
+/// Passwords can only be set for users of Silos with `provision_type` ==
+/// `Fixed`.  Setting a password also unlocks a user who has been locked out by
+/// repeated failed logins.
+#[endpoint {
+    method = POST,
+    path = "/system/silos/{silo_name}/identity-providers/local/users/{user_id}/set-password",
+    tags = ["system"],
+}]
+async fn local_idp_user_set_password(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<UserPathParam>,
+    update: TypedBody<params::UserPassword>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus
+            .local_idp_user_set_password(
+                &opctx,
+                &path_params.silo_name,
+                path_params.user_id,
+                update.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// List a silo's SCIM client bearer tokens
+///
+/// The tokens' values are only revealed when they're created.
+#[endpoint {
+    method = GET,
+    path = "/system/silos/{silo_name}/scim/tokens",
+    tags = ["system"],
+}]
+async fn scim_token_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+) -> Result<HttpResponseOk<Vec<views::ScimClientBearerToken>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let silo_name = path_params.into_inner().silo_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let tokens = nexus
+            .scim_token_list(&opctx, &silo_name)
+            .await?
+            .into_iter()
+            .map(|t| t.into())
+            .collect();
+        Ok(HttpResponseOk(tokens))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Create a SCIM client bearer token
+///
+/// Silos whose users are provisioned through SCIM (`provision_type` ==
+/// `Scim`) are managed by an identity provider that authenticates with one of
+/// these tokens.
+#[endpoint {
+    method = POST,
+    path = "/system/silos/{silo_name}/scim/tokens",
+    tags = ["system"],
+}]
+async fn scim_token_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SiloPathParam>,
+) -> Result<HttpResponseCreated<views::ScimClientBearerTokenValue>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let silo_name = path_params.into_inner().silo_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let token = nexus.scim_token_create(&opctx, &silo_name).await?;
+        Ok(HttpResponseCreated(token))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for SCIM client bearer token requests
+#[derive(Deserialize, JsonSchema)]
+struct ScimTokenPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The token's id
+    token_id: Uuid,
+}
+
+/// Delete a SCIM client bearer token
+#[endpoint {
+    method = DELETE,
+    path = "/system/silos/{silo_name}/scim/tokens/{token_id}",
+    tags = ["system"],
+}]
+async fn scim_token_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimTokenPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus
+            .scim_token_delete(
+                &opctx,
+                &path_params.silo_name,
+                path_params.token_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// List organizations
+#[endpoint {
+    method = GET,
+    path = "/organizations",
+    tags = ["organizations"],
+}]
+async fn organization_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<Organization>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let query = query_params.into_inner();
+        let params = ScanByNameOrId::from_query(&query)?;
+        let field = pagination_field_for_scan_params(params);
+
+        let organizations = match field {
+            PagField::Id => {
+                let page_selector = data_page_params_nameid_id(&rqctx, &query)?;
+                nexus.organizations_list_by_id(&opctx, &page_selector).await?
+            }
+
+            PagField::Name => {
+                let page_selector =
+                    data_page_params_nameid_name(&rqctx, &query)?
+                        .map_name(|n| Name::ref_cast(n));
+                nexus.organizations_list_by_name(&opctx, &page_selector).await?
+            }
+        }
+        .into_iter()
+        .map(|p| p.into())
+        .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            organizations,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Create an organization
+#[endpoint {
+    method = POST,
+    path = "/organizations",
+    tags = ["organizations"],
+}]
+async fn organization_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    new_organization: TypedBody<params::OrganizationCreate>,
+) -> Result<HttpResponseCreated<Organization>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let organization = nexus
+            .organization_create(&opctx, &new_organization.into_inner())
+            .await?;
+        Ok(HttpResponseCreated(organization.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Organization requests
+#[derive(Deserialize, JsonSchema)]
+struct OrganizationPathParam {
+    /// The organization's unique name.
+    organization_name: Name,
+}
+
+/// Fetch an organization
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}",
+    tags = ["organizations"],
+}]
+async fn organization_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<OrganizationPathParam>,
+) -> Result<HttpResponseOk<Organization>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let organization =
+            nexus.organization_fetch(&opctx, &organization_name).await?;
+        Ok(HttpResponseOk(organization.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Fetch an organization by id
 #[endpoint {
     method = GET,
-    path = "/by-id/organizations/{id}",
-    tags = ["organizations"],
+    path = "/by-id/organizations/{id}",
+    tags = ["organizations"],
+}]
+async fn organization_view_by_id(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<Organization>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let id = &path.id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let organization = nexus.organization_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(organization.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Delete an organization
+#[endpoint {
+    method = DELETE,
+    path = "/organizations/{organization_name}",
+    tags = ["organizations"],
+}]
+async fn organization_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<OrganizationPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let params = path_params.into_inner();
+    let organization_name = &params.organization_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus.organization_delete(&opctx, &organization_name).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update an organization
+// TODO-correctness: Is it valid for PUT to accept application/json that's a
+// subset of what the resource actually represents?  If not, is that a problem?
+// (HTTP may require that this be idempotent.)  If so, can we get around that
+// having this be a slightly different content-type (e.g.,
+// "application/json-patch")?  We should see what other APIs do.
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}",
+    tags = ["organizations"],
+}]
+async fn organization_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<OrganizationPathParam>,
+    updated_organization: TypedBody<params::OrganizationUpdate>,
+) -> Result<HttpResponseOk<Organization>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let new_organization = nexus
+            .organization_update(
+                &opctx,
+                &organization_name,
+                &updated_organization.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(new_organization.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch an organization's IAM policy
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/policy",
+    tags = ["organizations"],
+}]
+async fn organization_policy_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<OrganizationPathParam>,
+) -> Result<HttpResponseOk<shared::Policy<authz::OrganizationRole>>, HttpError>
+{
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let policy =
+            nexus.organization_fetch_policy(&opctx, organization_name).await?;
+        Ok(HttpResponseOk(policy))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update an organization's IAM policy
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/policy",
+    tags = ["organizations"],
+}]
+async fn organization_policy_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<OrganizationPathParam>,
+    new_policy: TypedBody<shared::Policy<authz::OrganizationRole>>,
+) -> Result<HttpResponseOk<shared::Policy<authz::OrganizationRole>>, HttpError>
+{
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let new_policy = new_policy.into_inner();
+    let organization_name = &path.organization_name;
+
+    let handler = async {
+        let nasgns = new_policy.role_assignments.len();
+        // This should have been validated during parsing.
+        bail_unless!(nasgns <= shared::MAX_ROLE_ASSIGNMENTS_PER_RESOURCE);
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let policy = nexus
+            .organization_update_policy(&opctx, organization_name, &new_policy)
+            .await?;
+        Ok(HttpResponseOk(policy))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// List projects
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects",
+    tags = ["projects"],
+}]
+async fn project_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedByNameOrId>,
+    path_params: Path<OrganizationPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<Project>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let params = ScanByNameOrId::from_query(&query)?;
+        let field = pagination_field_for_scan_params(params);
+        let projects = match field {
+            PagField::Id => {
+                let page_selector = data_page_params_nameid_id(&rqctx, &query)?;
+                nexus
+                    .projects_list_by_id(
+                        &opctx,
+                        &organization_name,
+                        &page_selector,
+                    )
+                    .await?
+            }
+
+            PagField::Name => {
+                let page_selector =
+                    data_page_params_nameid_name(&rqctx, &query)?
+                        .map_name(|n| Name::ref_cast(n));
+                nexus
+                    .projects_list_by_name(
+                        &opctx,
+                        &organization_name,
+                        &page_selector,
+                    )
+                    .await?
+            }
+        }
+        .into_iter()
+        .map(|p| p.into())
+        .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            projects,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Create a project
+#[endpoint {
+    method = POST,
+    path = "/organizations/{organization_name}/projects",
+    tags = ["projects"],
+}]
+async fn project_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<OrganizationPathParam>,
+    new_project: TypedBody<params::ProjectCreate>,
+) -> Result<HttpResponseCreated<Project>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let params = path_params.into_inner();
+    let organization_name = &params.organization_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let project = nexus
+            .project_create(
+                &opctx,
+                &organization_name,
+                &new_project.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(project.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Project requests
+#[derive(Deserialize, JsonSchema)]
+struct ProjectPathParam {
+    /// The organization's unique name.
+    organization_name: Name,
+    /// The project's unique name within the organization.
+    project_name: Name,
+}
+
+/// Fetch a project
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}",
+    tags = ["projects"],
+}]
+async fn project_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<Project>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let project = nexus
+            .project_fetch(&opctx, &organization_name, &project_name)
+            .await?;
+        Ok(HttpResponseOk(project.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch a project by id
+#[endpoint {
+    method = GET,
+    path = "/by-id/projects/{id}",
+    tags = ["projects"],
+}]
+async fn project_view_by_id(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<Project>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let id = &path.id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let project = nexus.project_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(project.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Delete a project
+#[endpoint {
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}",
+    tags = ["projects"],
+}]
+async fn project_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let params = path_params.into_inner();
+    let organization_name = &params.organization_name;
+    let project_name = &params.project_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus.project_delete(&opctx, &organization_name, &project_name).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update a project
+// TODO-correctness: Is it valid for PUT to accept application/json that's a
+// subset of what the resource actually represents?  If not, is that a problem?
+// (HTTP may require that this be idempotent.)  If so, can we get around that
+// having this be a slightly different content-type (e.g.,
+// "application/json-patch")?  We should see what other APIs do.
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}",
+    tags = ["projects"],
+}]
+async fn project_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+    updated_project: TypedBody<params::ProjectUpdate>,
+) -> Result<HttpResponseOk<Project>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let newproject = nexus
+            .project_update(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &updated_project.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(newproject.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch a project's IAM policy
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/policy",
+    tags = ["projects"],
+}]
+async fn project_policy_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<shared::Policy<authz::ProjectRole>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let policy = nexus
+            .project_fetch_policy(&opctx, organization_name, project_name)
+            .await?;
+        Ok(HttpResponseOk(policy))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update a project's IAM policy
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/policy",
+    tags = ["projects"],
+}]
+async fn project_policy_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+    new_policy: TypedBody<shared::Policy<authz::ProjectRole>>,
+) -> Result<HttpResponseOk<shared::Policy<authz::ProjectRole>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let new_policy = new_policy.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+
+    let handler = async {
+        let nasgns = new_policy.role_assignments.len();
+        // This should have been validated during parsing.
+        bail_unless!(nasgns <= shared::MAX_ROLE_ASSIGNMENTS_PER_RESOURCE);
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let policy = nexus
+            .project_update_policy(
+                &opctx,
+                organization_name,
+                project_name,
+                &new_policy,
+            )
+            .await?;
+        Ok(HttpResponseOk(policy))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch a project's settings
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/settings",
+    tags = ["projects"],
+}]
+async fn project_settings_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<views::ProjectSettings>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let settings = nexus
+            .project_fetch_settings(&opctx, organization_name, project_name)
+            .await?;
+        Ok(HttpResponseOk(settings.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update a project's settings
+///
+/// The settings name the VPC, VPC Subnet and IP Pool used by default when
+/// creating instances and network interfaces in the project.
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/settings",
+    tags = ["projects"],
+}]
+async fn project_settings_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+    new_settings: TypedBody<params::ProjectSettingsUpdate>,
+) -> Result<HttpResponseOk<views::ProjectSettings>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let new_settings = new_settings.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let settings = nexus
+            .project_update_settings(
+                &opctx,
+                organization_name,
+                project_name,
+                &new_settings,
+            )
+            .await?;
+        Ok(HttpResponseOk(settings.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch a project's quotas
+///
+/// The response includes the resources allocated within the project that
+/// count against its quotas.
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/quotas",
+    tags = ["projects"],
+}]
+async fn project_quotas_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<views::ResourceQuotas>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let quotas = nexus
+            .project_fetch_quotas(&opctx, organization_name, project_name)
+            .await?;
+        Ok(HttpResponseOk(quotas.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update a project's quotas
+///
+/// Resources allocated within the project are also counted against the
+/// quotas of its silo.
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/quotas",
+    tags = ["projects"],
+}]
+async fn project_quotas_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+    new_quotas: TypedBody<params::ResourceQuotasUpdate>,
+) -> Result<HttpResponseOk<views::ResourceQuotas>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let new_quotas = new_quotas.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let quotas = nexus
+            .project_update_quotas(
+                &opctx,
+                organization_name,
+                project_name,
+                &new_quotas,
+            )
+            .await?;
+        Ok(HttpResponseOk(quotas.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// IP Pools
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IpPoolPathParam {
+    pub pool_name: Name,
+}
+
+/// List IP pools
+#[endpoint {
+    method = GET,
+    path = "/system/ip-pools",
+    tags = ["system"],
+}]
+async fn ip_pool_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<IpPool>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let params = ScanByNameOrId::from_query(&query)?;
+        let field = pagination_field_for_scan_params(params);
+        let pools = match field {
+            PagField::Id => {
+                let page_selector = data_page_params_nameid_id(&rqctx, &query)?;
+                nexus.ip_pools_list_by_id(&opctx, &page_selector).await?
+            }
+            PagField::Name => {
+                let page_selector =
+                    data_page_params_nameid_name(&rqctx, &query)?
+                        .map_name(|n| Name::ref_cast(n));
+                nexus.ip_pools_list_by_name(&opctx, &page_selector).await?
+            }
+        }
+        .into_iter()
+        .map(IpPool::from)
+        .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            pools,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Create an IP pool
+#[endpoint {
+    method = POST,
+    path = "/system/ip-pools",
+    tags = ["system"],
+}]
+async fn ip_pool_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    pool_params: TypedBody<params::IpPoolCreate>,
+) -> Result<HttpResponseCreated<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let pool_params = pool_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let pool = nexus.ip_pool_create(&opctx, &pool_params).await?;
+        Ok(HttpResponseCreated(IpPool::from(pool)))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch an IP pool
+#[endpoint {
+    method = GET,
+    path = "/system/ip-pools/{pool_name}",
+    tags = ["system"],
 }]
-async fn organization_view_by_id(
+async fn ip_pool_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<IpPoolPathParam>,
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let pool_name = &path.pool_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let pool = nexus.ip_pool_fetch(&opctx, pool_name).await?;
+        Ok(HttpResponseOk(IpPool::from(pool)))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch an IP pool by id
+#[endpoint {
+    method = GET,
+    path = "/system/by-id/ip-pools/{id}",
+    tags = ["system"],
+}]
+async fn ip_pool_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Organization>, HttpError> {
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let organization = nexus.organization_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(organization.into()))
+        let pool = nexus.ip_pool_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(IpPool::from(pool)))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete an organization
+/// Delete an IP Pool
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}",
-    tags = ["organizations"],
+    path = "/system/ip-pools/{pool_name}",
+    tags = ["system"],
 }]
-async fn organization_delete(
+async fn ip_pool_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<OrganizationPathParam>,
+    path_params: Path<IpPoolPathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let params = path_params.into_inner();
-    let organization_name = &params.organization_name;
+    let path = path_params.into_inner();
+    let pool_name = &path.pool_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus.organization_delete(&opctx, &organization_name).await?;
+        nexus.ip_pool_delete(&opctx, pool_name).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update an organization
-// TODO-correctness: Is it valid for PUT to accept application/json that's a
-// subset of what the resource actually represents?  If not, is that a problem?
-// (HTTP may require that this be idempotent.)  If so, can we get around that
-// having this be a slightly different content-type (e.g.,
-// "application/json-patch")?  We should see what other APIs do.
+/// Update an IP Pool
 #[endpoint {
     method = PUT,
-    path = "/organizations/{organization_name}",
-    tags = ["organizations"],
+    path = "/system/ip-pools/{pool_name}",
+    tags = ["system"],
+}]
+async fn ip_pool_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<IpPoolPathParam>,
+    updates: TypedBody<params::IpPoolUpdate>,
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let pool_name = &path.pool_name;
+    let updates = updates.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let pool = nexus.ip_pool_update(&opctx, pool_name, &updates).await?;
+        Ok(HttpResponseOk(pool.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch an IP pool used for Oxide services.
+#[endpoint {
+    method = GET,
+    path = "/system/ip-pools-service/{rack_id}",
+    tags = ["system"],
+}]
+async fn ip_pool_service_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<IpPoolServicePathParam>,
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let rack_id = path.rack_id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let pool = nexus.ip_pool_service_fetch(&opctx, rack_id).await?;
+        Ok(HttpResponseOk(IpPool::from(pool)))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+type IpPoolRangePaginationParams = PaginationParams<EmptyScanParams, IpNetwork>;
+
+/// List ranges for an IP pool
+///
+/// Ranges are ordered by their first address.
+#[endpoint {
+    method = GET,
+    path = "/system/ip-pools/{pool_name}/ranges",
+    tags = ["system"],
+}]
+async fn ip_pool_range_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<IpPoolPathParam>,
+    query_params: Query<IpPoolRangePaginationParams>,
+) -> Result<HttpResponseOk<ResultsPage<IpPoolRange>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let path = path_params.into_inner();
+    let pool_name = &path.pool_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let marker = match query.page {
+            WhichPage::First(_) => None,
+            WhichPage::Next(ref addr) => Some(addr),
+        };
+        let pag_params = DataPageParams {
+            limit: rqctx.page_limit(&query)?,
+            direction: PaginationOrder::Ascending,
+            marker,
+        };
+        let ranges = nexus
+            .ip_pool_list_ranges(&opctx, pool_name, &pag_params)
+            .await?
+            .into_iter()
+            .map(|range| range.into())
+            .collect();
+        Ok(HttpResponseOk(ResultsPage::new(
+            ranges,
+            &EmptyScanParams {},
+            |range: &IpPoolRange, _| {
+                IpNetwork::from(range.range.first_address())
+            },
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Add a range to an IP pool
+#[endpoint {
+    method = POST,
+    path = "/system/ip-pools/{pool_name}/ranges/add",
+    tags = ["system"],
+}]
+async fn ip_pool_range_add(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<IpPoolPathParam>,
+    range_params: TypedBody<shared::IpRange>,
+) -> Result<HttpResponseCreated<IpPoolRange>, HttpError> {
+    let apictx = &rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let pool_name = &path.pool_name;
+    let range = range_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let out = nexus.ip_pool_add_range(&opctx, pool_name, &range).await?;
+        Ok(HttpResponseCreated(out.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Remove a range from an IP pool
+#[endpoint {
+    method = POST,
+    path = "/system/ip-pools/{pool_name}/ranges/remove",
+    tags = ["system"],
+}]
+async fn ip_pool_range_remove(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<IpPoolPathParam>,
+    range_params: TypedBody<shared::IpRange>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = &rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let pool_name = &path.pool_name;
+    let range = range_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus.ip_pool_delete_range(&opctx, pool_name, &range).await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IpPoolServicePathParam {
+    pub rack_id: Uuid,
+}
+
+/// List ranges for an IP pool used for Oxide services.
+///
+/// Ranges are ordered by their first address.
+#[endpoint {
+    method = GET,
+    path = "/system/ip-pools-service/{rack_id}/ranges",
+    tags = ["system"],
 }]
-async fn organization_update(
+async fn ip_pool_service_range_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<OrganizationPathParam>,
-    updated_organization: TypedBody<params::OrganizationUpdate>,
-) -> Result<HttpResponseOk<Organization>, HttpError> {
+    path_params: Path<IpPoolServicePathParam>,
+    query_params: Query<IpPoolRangePaginationParams>,
+) -> Result<HttpResponseOk<ResultsPage<IpPoolRange>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
+    let rack_id = path.rack_id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let new_organization = nexus
-            .organization_update(
-                &opctx,
-                &organization_name,
-                &updated_organization.into_inner(),
-            )
-            .await?;
-        Ok(HttpResponseOk(new_organization.into()))
+        let marker = match query.page {
+            WhichPage::First(_) => None,
+            WhichPage::Next(ref addr) => Some(addr),
+        };
+        let pag_params = DataPageParams {
+            limit: rqctx.page_limit(&query)?,
+            direction: PaginationOrder::Ascending,
+            marker,
+        };
+        let ranges = nexus
+            .ip_pool_service_list_ranges(&opctx, rack_id, &pag_params)
+            .await?
+            .into_iter()
+            .map(|range| range.into())
+            .collect();
+        Ok(HttpResponseOk(ResultsPage::new(
+            ranges,
+            &EmptyScanParams {},
+            |range: &IpPoolRange, _| {
+                IpNetwork::from(range.range.first_address())
+            },
+        )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an organization's IAM policy
+/// Add a range to an IP pool used for Oxide services.
 #[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/policy",
-    tags = ["organizations"],
+    method = POST,
+    path = "/system/ip-pools-service/{rack_id}/ranges/add",
+    tags = ["system"],
 }]
-async fn organization_policy_view(
+async fn ip_pool_service_range_add(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<OrganizationPathParam>,
-) -> Result<HttpResponseOk<shared::Policy<authz::OrganizationRole>>, HttpError>
-{
-    let apictx = rqctx.context();
+    path_params: Path<IpPoolServicePathParam>,
+    range_params: TypedBody<shared::IpRange>,
+) -> Result<HttpResponseCreated<IpPoolRange>, HttpError> {
+    let apictx = &rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-
+    let rack_id = path.rack_id;
+    let range = range_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let policy =
-            nexus.organization_fetch_policy(&opctx, organization_name).await?;
-        Ok(HttpResponseOk(policy))
+        let out =
+            nexus.ip_pool_service_add_range(&opctx, rack_id, &range).await?;
+        Ok(HttpResponseCreated(out.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update an organization's IAM policy
+/// Remove a range from an IP pool used for Oxide services.
 #[endpoint {
-    method = PUT,
-    path = "/organizations/{organization_name}/policy",
-    tags = ["organizations"],
+    method = POST,
+    path = "/system/ip-pools-service/{rack_id}/ranges/remove",
+    tags = ["system"],
 }]
-async fn organization_policy_update(
+async fn ip_pool_service_range_remove(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<OrganizationPathParam>,
-    new_policy: TypedBody<shared::Policy<authz::OrganizationRole>>,
-) -> Result<HttpResponseOk<shared::Policy<authz::OrganizationRole>>, HttpError>
-{
-    let apictx = rqctx.context();
+    path_params: Path<IpPoolServicePathParam>,
+    range_params: TypedBody<shared::IpRange>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = &rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let new_policy = new_policy.into_inner();
-    let organization_name = &path.organization_name;
-
+    let rack_id = path.rack_id;
+    let range = range_params.into_inner();
     let handler = async {
-        let nasgns = new_policy.role_assignments.len();
-        // This should have been validated during parsing.
-        bail_unless!(nasgns <= shared::MAX_ROLE_ASSIGNMENTS_PER_RESOURCE);
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let policy = nexus
-            .organization_update_policy(&opctx, organization_name, &new_policy)
-            .await?;
-        Ok(HttpResponseOk(policy))
+        nexus.ip_pool_service_delete_range(&opctx, rack_id, &range).await?;
+        Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List projects
+// Disks
+
+/// List disks
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects",
-    tags = ["projects"],
+    path = "/organizations/{organization_name}/projects/{project_name}/disks",
+    tags = ["disks"]
 }]
-async fn project_list(
+async fn disk_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    query_params: Query<PaginatedByNameOrId>,
-    path_params: Path<OrganizationPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Project>>, HttpError> {
+    query_params: Query<PaginatedByName>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<Disk>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
-
+    let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let params = ScanByNameOrId::from_query(&query)?;
-        let field = pagination_field_for_scan_params(params);
-        let projects = match field {
-            PagField::Id => {
-                let page_selector = data_page_params_nameid_id(&rqctx, &query)?;
-                nexus
-                    .projects_list_by_id(
-                        &opctx,
-                        &organization_name,
-                        &page_selector,
-                    )
-                    .await?
-            }
-
-            PagField::Name => {
-                let page_selector =
-                    data_page_params_nameid_name(&rqctx, &query)?
-                        .map_name(|n| Name::ref_cast(n));
-                nexus
-                    .projects_list_by_name(
-                        &opctx,
-                        &organization_name,
-                        &page_selector,
-                    )
-                    .await?
-            }
-        }
-        .into_iter()
-        .map(|p| p.into())
-        .collect();
-        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+        let disks = nexus
+            .project_list_disks(
+                &opctx,
+                organization_name,
+                project_name,
+                &data_page_params_for(&rqctx, &query)?
+                    .map_name(|n| Name::ref_cast(n)),
+            )
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            projects,
-            &marker_for_name_or_id,
+            disks,
+            &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a project
+/// Create a disk
+// TODO-correctness See note about instance create.  This should be async.
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects",
-    tags = ["projects"],
+    path = "/organizations/{organization_name}/projects/{project_name}/disks",
+    tags = ["disks"]
 }]
-async fn project_create(
+async fn disk_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<OrganizationPathParam>,
-    new_project: TypedBody<params::ProjectCreate>,
-) -> Result<HttpResponseCreated<Project>, HttpError> {
+    path_params: Path<ProjectPathParam>,
+    new_disk: TypedBody<params::DiskCreate>,
+) -> Result<HttpResponseCreated<Disk>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let params = path_params.into_inner();
-    let organization_name = &params.organization_name;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let new_disk_params = &new_disk.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let project = nexus
-            .project_create(
+        let disk = nexus
+            .project_create_disk(
                 &opctx,
                 &organization_name,
-                &new_project.into_inner(),
+                &project_name,
+                &new_disk_params,
             )
             .await?;
-        Ok(HttpResponseCreated(project.into()))
+        Ok(HttpResponseCreated(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Project requests
+/// Path parameters for Disk requests
 #[derive(Deserialize, JsonSchema)]
-struct ProjectPathParam {
-    /// The organization's unique name.
+struct DiskPathParam {
     organization_name: Name,
-    /// The project's unique name within the organization.
     project_name: Name,
+    disk_name: Name,
 }
 
-/// Fetch a project
+/// Fetch a disk
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}",
-    tags = ["projects"],
+    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}",
+    tags = ["disks"],
 }]
-async fn project_view(
+async fn disk_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<Project>, HttpError> {
+    path_params: Path<DiskPathParam>,
+) -> Result<HttpResponseOk<Disk>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
+    let disk_name = &path.disk_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let project = nexus
-            .project_fetch(&opctx, &organization_name, &project_name)
+        let disk = nexus
+            .disk_fetch(&opctx, &organization_name, &project_name, &disk_name)
             .await?;
-        Ok(HttpResponseOk(project.into()))
+        Ok(HttpResponseOk(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a project by id
+/// Fetch a disk by id
 #[endpoint {
     method = GET,
-    path = "/by-id/projects/{id}",
-    tags = ["projects"],
+    path = "/by-id/disks/{id}",
+    tags = ["disks"],
 }]
-async fn project_view_by_id(
+async fn disk_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Project>, HttpError> {
-    let apictx = rqctx.context();
-    let nexus = &apictx.nexus;
-    let path = path_params.into_inner();
-    let id = &path.id;
-    let handler = async {
-        let opctx = OpContext::for_external_api(&rqctx).await?;
-        let project = nexus.project_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(project.into()))
-    };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Delete a project
-#[endpoint {
-    method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}",
-    tags = ["projects"],
-}]
-async fn project_delete(
-    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseDeleted, HttpError> {
+) -> Result<HttpResponseOk<Disk>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let params = path_params.into_inner();
-    let organization_name = &params.organization_name;
-    let project_name = &params.project_name;
+    let path = path_params.into_inner();
+    let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus.project_delete(&opctx, &organization_name, &project_name).await?;
-        Ok(HttpResponseDeleted())
+        let disk = nexus.disk_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update a project
-// TODO-correctness: Is it valid for PUT to accept application/json that's a
-// subset of what the resource actually represents?  If not, is that a problem?
-// (HTTP may require that this be idempotent.)  If so, can we get around that
-// having this be a slightly different content-type (e.g.,
-// "application/json-patch")?  We should see what other APIs do.
+/// Delete a disk
 #[endpoint {
-    method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}",
-    tags = ["projects"],
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}",
+    tags = ["disks"],
 }]
-async fn project_update(
+async fn disk_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-    updated_project: TypedBody<params::ProjectUpdate>,
-) -> Result<HttpResponseOk<Project>, HttpError> {
+    path_params: Path<DiskPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
+    let disk_name = &path.disk_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let newproject = nexus
-            .project_update(
+        nexus
+            .project_delete_disk(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &updated_project.into_inner(),
+                &disk_name,
             )
             .await?;
-        Ok(HttpResponseOk(newproject.into()))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a project's IAM policy
+/// Fetch a disk's IAM policy
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/policy",
-    tags = ["projects"],
+    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}/policy",
+    tags = ["disks"],
 }]
-async fn project_policy_view(
+async fn disk_policy_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<shared::Policy<authz::ProjectRole>>, HttpError> {
+    path_params: Path<DiskPathParam>,
+) -> Result<HttpResponseOk<shared::Policy<authz::DiskRole>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
+    let disk_name = &path.disk_name;
 
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
         let policy = nexus
-            .project_fetch_policy(&opctx, organization_name, project_name)
+            .disk_fetch_policy(
+                &opctx,
+                organization_name,
+                project_name,
+                disk_name,
+            )
             .await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update a project's IAM policy
+/// Update a disk's IAM policy
 #[endpoint {
     method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/policy",
-    tags = ["projects"],
+    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}/policy",
+    tags = ["disks"],
 }]
-async fn project_policy_update(
+async fn disk_policy_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-    new_policy: TypedBody<shared::Policy<authz::ProjectRole>>,
-) -> Result<HttpResponseOk<shared::Policy<authz::ProjectRole>>, HttpError> {
+    path_params: Path<DiskPathParam>,
+    new_policy: TypedBody<shared::Policy<authz::DiskRole>>,
+) -> Result<HttpResponseOk<shared::Policy<authz::DiskRole>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let new_policy = new_policy.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
+    let disk_name = &path.disk_name;
 
     let handler = async {
         let nasgns = new_policy.role_assignments.len();
@@ -1353,421 +2899,680 @@ async fn project_policy_update(
         bail_unless!(nasgns <= shared::MAX_ROLE_ASSIGNMENTS_PER_RESOURCE);
         let opctx = OpContext::for_external_api(&rqctx).await?;
         let policy = nexus
-            .project_update_policy(
+            .disk_update_policy(
                 &opctx,
                 organization_name,
                 project_name,
+                disk_name,
                 &new_policy,
             )
             .await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// IP Pools
+#[derive(Display, Deserialize, JsonSchema)]
+#[display(style = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DiskMetricName {
+    Activated,
+    Flush,
+    Read,
+    ReadBytes,
+    Write,
+    WriteBytes,
+}
 
-#[derive(Deserialize, JsonSchema)]
-pub struct IpPoolPathParam {
-    pub pool_name: Name,
+/// Fetch disk metrics
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}/metrics/{metric_name}",
+    tags = ["disks"],
+}]
+async fn disk_metrics_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<MetricsPathParam<DiskPathParam, DiskMetricName>>,
+    query_params: Query<
+        PaginationParams<params::ResourceMetrics, params::ResourceMetrics>,
+    >,
+) -> Result<HttpResponseOk<ResultsPage<oximeter_db::Measurement>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+
+    let path = path_params.into_inner();
+    let organization_name = &path.inner.organization_name;
+    let project_name = &path.inner.project_name;
+    let disk_name = &path.inner.disk_name;
+    let metric_name = path.metric_name;
+
+    let query = query_params.into_inner();
+    let limit = rqctx.page_limit(&query)?;
+
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+
+        // This ensures the user is authorized on Action::Read for this disk
+        let disk = nexus
+            .disk_fetch(&opctx, organization_name, project_name, disk_name)
+            .await?;
+        let upstairs_uuid = disk.id();
+        let result = nexus
+            .select_timeseries(
+                &format!("crucible_upstairs:{}", metric_name),
+                &[&format!("upstairs_uuid=={}", upstairs_uuid)],
+                query,
+                limit,
+            )
+            .await?;
+
+        Ok(HttpResponseOk(result))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List IP pools
+// Instances
+
+/// List instances
 #[endpoint {
     method = GET,
-    path = "/system/ip-pools",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances",
+    tags = ["instances"],
 }]
-async fn ip_pool_list(
+async fn instance_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    query_params: Query<PaginatedByNameOrId>,
-) -> Result<HttpResponseOk<ResultsPage<IpPool>>, HttpError> {
+    query_params: Query<PaginatedByName>,
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<Instance>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let params = ScanByNameOrId::from_query(&query)?;
-        let field = pagination_field_for_scan_params(params);
-        let pools = match field {
-            PagField::Id => {
-                let page_selector = data_page_params_nameid_id(&rqctx, &query)?;
-                nexus.ip_pools_list_by_id(&opctx, &page_selector).await?
-            }
-            PagField::Name => {
-                let page_selector =
-                    data_page_params_nameid_name(&rqctx, &query)?
-                        .map_name(|n| Name::ref_cast(n));
-                nexus.ip_pools_list_by_name(&opctx, &page_selector).await?
-            }
-        }
-        .into_iter()
-        .map(IpPool::from)
-        .collect();
-        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+        let instances = nexus
+            .project_list_instances(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &data_page_params_for(&rqctx, &query)?
+                    .map_name(|n| Name::ref_cast(n)),
+            )
+            .await?
+            .into_iter()
+            .map(|i| i.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            pools,
-            &marker_for_name_or_id,
+            instances,
+            &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Create an instance
+// TODO-correctness This is supposed to be async.  Is that right?  We can create
+// the instance immediately -- it's just not booted yet.  Maybe the boot
+// operation is what's a separate operation_id.  What about the response code
+// (201 Created vs 202 Accepted)?  Is that orthogonal?  Things can return a
+// useful response, including an operation id, with either response code.  Maybe
+// a "reboot" operation would return a 202 Accepted because there's no actual
+// resource created?
+#[endpoint {
+    method = POST,
+     path = "/organizations/{organization_name}/projects/{project_name}/instances",
+    tags = ["instances"],
+}]
+async fn instance_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ProjectPathParam>,
+    new_instance: TypedBody<params::InstanceCreate>,
+) -> Result<HttpResponseCreated<Instance>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let new_instance_params = &new_instance.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let instance = nexus
+            .project_create_instance(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &new_instance_params,
+            )
+            .await?;
+        Ok(HttpResponseCreated(instance.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Instance requests
+#[derive(Deserialize, JsonSchema)]
+struct InstancePathParam {
+    organization_name: Name,
+    project_name: Name,
+    instance_name: Name,
+}
+
+/// Fetch an instance
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
+    tags = ["instances"],
+}]
+async fn instance_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let instance = nexus
+            .instance_fetch(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+            )
+            .await?;
+        Ok(HttpResponseOk(instance.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch an instance by id
+#[endpoint {
+    method = GET,
+    path = "/by-id/instances/{id}",
+    tags = ["instances"],
+}]
+async fn instance_view_by_id(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let id = &path.id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let instance = nexus.instance_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(instance.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create an IP pool
+/// Delete an instance
 #[endpoint {
-    method = POST,
-    path = "/system/ip-pools",
-    tags = ["system"],
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
+    tags = ["instances"],
 }]
-async fn ip_pool_create(
+async fn instance_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    pool_params: TypedBody<params::IpPoolCreate>,
-) -> Result<HttpResponseCreated<views::IpPool>, HttpError> {
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let pool_params = pool_params.into_inner();
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let pool = nexus.ip_pool_create(&opctx, &pool_params).await?;
-        Ok(HttpResponseCreated(IpPool::from(pool)))
+        nexus
+            .project_destroy_instance(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an IP pool
+/// Fetch an instance's IAM policy
 #[endpoint {
     method = GET,
-    path = "/system/ip-pools/{pool_name}",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/policy",
+    tags = ["instances"],
 }]
-async fn ip_pool_view(
+async fn instance_policy_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolPathParam>,
-) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseOk<shared::Policy<authz::InstanceRole>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let pool_name = &path.pool_name;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
+
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let pool = nexus.ip_pool_fetch(&opctx, pool_name).await?;
-        Ok(HttpResponseOk(IpPool::from(pool)))
+        let policy = nexus
+            .instance_fetch_policy(
+                &opctx,
+                organization_name,
+                project_name,
+                instance_name,
+            )
+            .await?;
+        Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an IP pool by id
+/// Update an instance's IAM policy
 #[endpoint {
-    method = GET,
-    path = "/system/by-id/ip-pools/{id}",
-    tags = ["system"],
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/policy",
+    tags = ["instances"],
 }]
-async fn ip_pool_view_by_id(
+async fn instance_policy_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    path_params: Path<InstancePathParam>,
+    new_policy: TypedBody<shared::Policy<authz::InstanceRole>>,
+) -> Result<HttpResponseOk<shared::Policy<authz::InstanceRole>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let id = &path.id;
+    let new_policy = new_policy.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
+
     let handler = async {
+        let nasgns = new_policy.role_assignments.len();
+        // This should have been validated during parsing.
+        bail_unless!(nasgns <= shared::MAX_ROLE_ASSIGNMENTS_PER_RESOURCE);
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let pool = nexus.ip_pool_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(IpPool::from(pool)))
+        let policy = nexus
+            .instance_update_policy(
+                &opctx,
+                organization_name,
+                project_name,
+                instance_name,
+                &new_policy,
+            )
+            .await?;
+        Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete an IP Pool
+// TODO should this be in the public API?
+/// Migrate an instance
 #[endpoint {
-    method = DELETE,
-    path = "/system/ip-pools/{pool_name}",
-    tags = ["system"],
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/migrate",
+    tags = ["instances"],
 }]
-async fn ip_pool_delete(
+async fn instance_migrate(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolPathParam>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    path_params: Path<InstancePathParam>,
+    migrate_params: TypedBody<params::InstanceMigrate>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let pool_name = &path.pool_name;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
+    let migrate_instance_params = migrate_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus.ip_pool_delete(&opctx, pool_name).await?;
-        Ok(HttpResponseDeleted())
+        let instance = nexus
+            .project_instance_migrate(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+                migrate_instance_params,
+            )
+            .await?;
+        Ok(HttpResponseOk(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update an IP Pool
+/// Replace the tags attached to an instance
+///
+/// Firewall rules targeting or filtering on tags are re-applied to reflect
+/// the instance's new tags.
 #[endpoint {
     method = PUT,
-    path = "/system/ip-pools/{pool_name}",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/tags",
+    tags = ["instances"],
 }]
-async fn ip_pool_update(
+async fn instance_tags_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolPathParam>,
-    updates: TypedBody<params::IpPoolUpdate>,
-) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    path_params: Path<InstancePathParam>,
+    tags_params: TypedBody<params::InstanceTagsUpdate>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let pool_name = &path.pool_name;
-    let updates = updates.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let pool = nexus.ip_pool_update(&opctx, pool_name, &updates).await?;
-        Ok(HttpResponseOk(pool.into()))
+        let instance = nexus
+            .instance_update_tags(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+                &tags_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an IP pool used for Oxide services.
+/// Reboot an instance
 #[endpoint {
-    method = GET,
-    path = "/system/ip-pools-service/{rack_id}",
-    tags = ["system"],
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/reboot",
+    tags = ["instances"],
 }]
-async fn ip_pool_service_view(
+async fn instance_reboot(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolServicePathParam>,
-) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseAccepted<Instance>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let rack_id = path.rack_id;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let pool = nexus.ip_pool_service_fetch(&opctx, rack_id).await?;
-        Ok(HttpResponseOk(IpPool::from(pool)))
+        let instance = nexus
+            .instance_reboot(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+            )
+            .await?;
+        Ok(HttpResponseAccepted(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-type IpPoolRangePaginationParams = PaginationParams<EmptyScanParams, IpNetwork>;
-
-/// List ranges for an IP pool
-///
-/// Ranges are ordered by their first address.
+/// Boot an instance
 #[endpoint {
-    method = GET,
-    path = "/system/ip-pools/{pool_name}/ranges",
-    tags = ["system"],
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/start",
+    tags = ["instances"],
 }]
-async fn ip_pool_range_list(
+async fn instance_start(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolPathParam>,
-    query_params: Query<IpPoolRangePaginationParams>,
-) -> Result<HttpResponseOk<ResultsPage<IpPoolRange>>, HttpError> {
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseAccepted<Instance>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let query = query_params.into_inner();
     let path = path_params.into_inner();
-    let pool_name = &path.pool_name;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let marker = match query.page {
-            WhichPage::First(_) => None,
-            WhichPage::Next(ref addr) => Some(addr),
-        };
-        let pag_params = DataPageParams {
-            limit: rqctx.page_limit(&query)?,
-            direction: PaginationOrder::Ascending,
-            marker,
-        };
-        let ranges = nexus
-            .ip_pool_list_ranges(&opctx, pool_name, &pag_params)
-            .await?
-            .into_iter()
-            .map(|range| range.into())
-            .collect();
-        Ok(HttpResponseOk(ResultsPage::new(
-            ranges,
-            &EmptyScanParams {},
-            |range: &IpPoolRange, _| {
-                IpNetwork::from(range.range.first_address())
-            },
-        )?))
+        let instance = nexus
+            .instance_start(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+            )
+            .await?;
+        Ok(HttpResponseAccepted(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Add a range to an IP pool
+/// Halt an instance
 #[endpoint {
     method = POST,
-    path = "/system/ip-pools/{pool_name}/ranges/add",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/stop",
+    tags = ["instances"],
 }]
-async fn ip_pool_range_add(
-    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolPathParam>,
-    range_params: TypedBody<shared::IpRange>,
-) -> Result<HttpResponseCreated<IpPoolRange>, HttpError> {
-    let apictx = &rqctx.context();
+async fn instance_stop(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseAccepted<Instance>, HttpError> {
+    let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let pool_name = &path.pool_name;
-    let range = range_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let out = nexus.ip_pool_add_range(&opctx, pool_name, &range).await?;
-        Ok(HttpResponseCreated(out.into()))
+        let instance = nexus
+            .instance_stop(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+            )
+            .await?;
+        Ok(HttpResponseAccepted(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Remove a range from an IP pool
+/// Fetch an instance's serial console
 #[endpoint {
-    method = POST,
-    path = "/system/ip-pools/{pool_name}/ranges/remove",
-    tags = ["system"],
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console",
+    tags = ["instances"],
 }]
-async fn ip_pool_range_remove(
+async fn instance_serial_console(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolPathParam>,
-    range_params: TypedBody<shared::IpRange>,
-) -> Result<HttpResponseUpdatedNoContent, HttpError> {
-    let apictx = &rqctx.context();
+    path_params: Path<InstancePathParam>,
+    query_params: Query<params::InstanceSerialConsoleRequest>,
+) -> Result<HttpResponseOk<params::InstanceSerialConsoleData>, HttpError> {
+    let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let pool_name = &path.pool_name;
-    let range = range_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus.ip_pool_delete_range(&opctx, pool_name, &range).await?;
-        Ok(HttpResponseUpdatedNoContent())
+        let data = nexus
+            .instance_serial_console_data(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+                &query_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(data))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-#[derive(Deserialize, JsonSchema)]
-pub struct IpPoolServicePathParam {
-    pub rack_id: Uuid,
+/// Connect to an instance's serial console
+#[channel {
+    protocol = WEBSOCKETS,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console/stream",
+    tags = ["instances"],
+}]
+async fn instance_serial_console_stream(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    conn: WebsocketConnection,
+    path_params: Path<InstancePathParam>,
+) -> WebsocketChannelResult {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
+    let opctx = OpContext::for_external_api(&rqctx).await?;
+    nexus
+        .instance_serial_console_stream(
+            &opctx,
+            conn,
+            organization_name,
+            project_name,
+            instance_name,
+        )
+        .await?;
+    Ok(())
 }
 
-/// List ranges for an IP pool used for Oxide services.
-///
-/// Ranges are ordered by their first address.
+/// List an instance's disks
+// TODO-scalability needs to be paginated
 #[endpoint {
     method = GET,
-    path = "/system/ip-pools-service/{rack_id}/ranges",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks",
+    tags = ["instances"],
 }]
-async fn ip_pool_service_range_list(
+async fn instance_disk_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolServicePathParam>,
-    query_params: Query<IpPoolRangePaginationParams>,
-) -> Result<HttpResponseOk<ResultsPage<IpPoolRange>>, HttpError> {
+    query_params: Query<PaginatedByName>,
+    path_params: Path<InstancePathParam>,
+) -> Result<HttpResponseOk<ResultsPage<Disk>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
-    let rack_id = path.rack_id;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let marker = match query.page {
-            WhichPage::First(_) => None,
-            WhichPage::Next(ref addr) => Some(addr),
-        };
-        let pag_params = DataPageParams {
-            limit: rqctx.page_limit(&query)?,
-            direction: PaginationOrder::Ascending,
-            marker,
-        };
-        let ranges = nexus
-            .ip_pool_service_list_ranges(&opctx, rack_id, &pag_params)
+        let disks = nexus
+            .instance_list_disks(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+                &data_page_params_for(&rqctx, &query)?
+                    .map_name(|n| Name::ref_cast(n)),
+            )
             .await?
             .into_iter()
-            .map(|range| range.into())
+            .map(|d| d.into())
             .collect();
-        Ok(HttpResponseOk(ResultsPage::new(
-            ranges,
-            &EmptyScanParams {},
-            |range: &IpPoolRange, _| {
-                IpNetwork::from(range.range.first_address())
-            },
+        Ok(HttpResponseOk(ScanByName::results_page(
+            &query,
+            disks,
+            &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Add a range to an IP pool used for Oxide services.
+/// Attach a disk to an instance
 #[endpoint {
     method = POST,
-    path = "/system/ip-pools-service/{rack_id}/ranges/add",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks/attach",
+    tags = ["instances"],
 }]
-async fn ip_pool_service_range_add(
+async fn instance_disk_attach(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolServicePathParam>,
-    range_params: TypedBody<shared::IpRange>,
-) -> Result<HttpResponseCreated<IpPoolRange>, HttpError> {
-    let apictx = &rqctx.context();
+    path_params: Path<InstancePathParam>,
+    disk_to_attach: TypedBody<params::DiskIdentifier>,
+) -> Result<HttpResponseAccepted<Disk>, HttpError> {
+    let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let rack_id = path.rack_id;
-    let range = range_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let out =
-            nexus.ip_pool_service_add_range(&opctx, rack_id, &range).await?;
-        Ok(HttpResponseCreated(out.into()))
+        let disk = nexus
+            .instance_attach_disk(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+                &disk_to_attach.into_inner().name.into(),
+            )
+            .await?;
+        Ok(HttpResponseAccepted(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Remove a range from an IP pool used for Oxide services.
+/// Detach a disk from an instance
 #[endpoint {
     method = POST,
-    path = "/system/ip-pools-service/{rack_id}/ranges/remove",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks/detach",
+    tags = ["instances"],
 }]
-async fn ip_pool_service_range_remove(
+async fn instance_disk_detach(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<IpPoolServicePathParam>,
-    range_params: TypedBody<shared::IpRange>,
-) -> Result<HttpResponseUpdatedNoContent, HttpError> {
-    let apictx = &rqctx.context();
+    path_params: Path<InstancePathParam>,
+    disk_to_detach: TypedBody<params::DiskIdentifier>,
+) -> Result<HttpResponseAccepted<Disk>, HttpError> {
+    let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let rack_id = path.rack_id;
-    let range = range_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus.ip_pool_service_delete_range(&opctx, rack_id, &range).await?;
-        Ok(HttpResponseUpdatedNoContent())
+        let disk = nexus
+            .instance_detach_disk(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &instance_name,
+                &disk_to_detach.into_inner().name.into(),
+            )
+            .await?;
+        Ok(HttpResponseAccepted(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Disks
+// Images
 
-/// List disks
+/// List system-wide images
+///
+/// Returns a list of all the system-wide images. System-wide images are returned sorted
+/// by creation date, with the most recent images appearing first.
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/disks",
-    tags = ["disks"]
+    path = "/system/images",
+    tags = ["system"],
 }]
-async fn disk_list(
+async fn system_image_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
-    path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Disk>>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<GlobalImage>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
-    let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disks = nexus
-            .project_list_disks(
+        let images = nexus
+            .global_images_list(
                 &opctx,
-                organization_name,
-                project_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
@@ -1777,205 +3582,129 @@ async fn disk_list(
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            disks,
+            images,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a disk
-// TODO-correctness See note about instance create.  This should be async.
+/// Create a system-wide image
+///
+/// Create a new system-wide image. This image can then be used by any user in any silo as a
+/// base for instances.
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/disks",
-    tags = ["disks"]
+    path = "/system/images",
+    tags = ["system"]
 }]
-async fn disk_create(
+async fn system_image_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-    new_disk: TypedBody<params::DiskCreate>,
-) -> Result<HttpResponseCreated<Disk>, HttpError> {
-    let apictx = rqctx.context();
-    let nexus = &apictx.nexus;
-    let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let new_disk_params = &new_disk.into_inner();
+    new_image: TypedBody<params::GlobalImageCreate>,
+) -> Result<HttpResponseCreated<GlobalImage>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let new_image_params = new_image.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disk = nexus
-            .project_create_disk(
-                &opctx,
-                &organization_name,
-                &project_name,
-                &new_disk_params,
-            )
-            .await?;
-        Ok(HttpResponseCreated(disk.into()))
+        let image = nexus.global_image_create(&opctx, new_image_params).await?;
+        Ok(HttpResponseCreated(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Disk requests
+/// Path parameters for Image requests
 #[derive(Deserialize, JsonSchema)]
-struct DiskPathParam {
-    organization_name: Name,
-    project_name: Name,
-    disk_name: Name,
+struct GlobalImagePathParam {
+    image_name: Name,
 }
 
-/// Fetch a disk
+/// Fetch a system-wide image
+///
+/// Returns the details of a specific system-wide image.
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}",
-    tags = ["disks"],
+    path = "/system/images/{image_name}",
+    tags = ["system"],
 }]
-async fn disk_view(
+async fn system_image_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<DiskPathParam>,
-) -> Result<HttpResponseOk<Disk>, HttpError> {
+    path_params: Path<GlobalImagePathParam>,
+) -> Result<HttpResponseOk<GlobalImage>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let disk_name = &path.disk_name;
+    let image_name = &path.image_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disk = nexus
-            .disk_fetch(&opctx, &organization_name, &project_name, &disk_name)
-            .await?;
-        Ok(HttpResponseOk(disk.into()))
+        let image = nexus.global_image_fetch(&opctx, &image_name).await?;
+        Ok(HttpResponseOk(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a disk by id
+/// Fetch a system-wide image by id
 #[endpoint {
     method = GET,
-    path = "/by-id/disks/{id}",
-    tags = ["disks"],
+    path = "/system/by-id/images/{id}",
+    tags = ["system"],
 }]
-async fn disk_view_by_id(
+async fn system_image_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Disk>, HttpError> {
+) -> Result<HttpResponseOk<GlobalImage>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disk = nexus.disk_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(disk.into()))
+        let image = nexus.global_image_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a disk
+/// Delete a system-wide image
+///
+/// Permanently delete a system-wide image. This operation cannot be undone. Any
+/// instances using the system-wide image will continue to run, however new instances
+/// can not be created with this image.
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}",
-    tags = ["disks"],
+    path = "/system/images/{image_name}",
+    tags = ["system"],
 }]
-async fn disk_delete(
+async fn system_image_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<DiskPathParam>,
+    path_params: Path<GlobalImagePathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let disk_name = &path.disk_name;
+    let image_name = &path.image_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus
-            .project_delete_disk(
-                &opctx,
-                &organization_name,
-                &project_name,
-                &disk_name,
-            )
-            .await?;
+        nexus.global_image_delete(&opctx, &image_name).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-#[derive(Display, Deserialize, JsonSchema)]
-#[display(style = "snake_case")]
-#[serde(rename_all = "snake_case")]
-pub enum DiskMetricName {
-    Activated,
-    Flush,
-    Read,
-    ReadBytes,
-    Write,
-    WriteBytes,
-}
-
-/// Fetch disk metrics
-#[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/disks/{disk_name}/metrics/{metric_name}",
-    tags = ["disks"],
-}]
-async fn disk_metrics_list(
-    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<MetricsPathParam<DiskPathParam, DiskMetricName>>,
-    query_params: Query<
-        PaginationParams<params::ResourceMetrics, params::ResourceMetrics>,
-    >,
-) -> Result<HttpResponseOk<ResultsPage<oximeter_db::Measurement>>, HttpError> {
-    let apictx = rqctx.context();
-    let nexus = &apictx.nexus;
-
-    let path = path_params.into_inner();
-    let organization_name = &path.inner.organization_name;
-    let project_name = &path.inner.project_name;
-    let disk_name = &path.inner.disk_name;
-    let metric_name = path.metric_name;
-
-    let query = query_params.into_inner();
-    let limit = rqctx.page_limit(&query)?;
-
-    let handler = async {
-        let opctx = OpContext::for_external_api(&rqctx).await?;
-
-        // This ensures the user is authorized on Action::Read for this disk
-        let disk = nexus
-            .disk_fetch(&opctx, organization_name, project_name, disk_name)
-            .await?;
-        let upstairs_uuid = disk.id();
-        let result = nexus
-            .select_timeseries(
-                &format!("crucible_upstairs:{}", metric_name),
-                &[&format!("upstairs_uuid=={}", upstairs_uuid)],
-                query,
-                limit,
-            )
-            .await?;
-
-        Ok(HttpResponseOk(result))
-    };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Instances
-
-/// List instances
+/// List images
+///
+/// List images in a project. The images are returned sorted by creation date,
+/// with the most recent images appearing first.
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances",
-    tags = ["instances"],
+    path = "/organizations/{organization_name}/projects/{project_name}/images",
+    tags = ["images"],
 }]
-async fn instance_list(
+async fn image_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
     path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Instance>>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<Image>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
@@ -1984,203 +3713,216 @@ async fn instance_list(
     let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instances = nexus
-            .project_list_instances(
+        let images = nexus
+            .project_list_images(
                 &opctx,
-                &organization_name,
-                &project_name,
+                organization_name,
+                project_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
             .await?
             .into_iter()
-            .map(|i| i.into())
+            .map(|d| d.into())
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            instances,
+            images,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create an instance
-// TODO-correctness This is supposed to be async.  Is that right?  We can create
-// the instance immediately -- it's just not booted yet.  Maybe the boot
-// operation is what's a separate operation_id.  What about the response code
-// (201 Created vs 202 Accepted)?  Is that orthogonal?  Things can return a
-// useful response, including an operation id, with either response code.  Maybe
-// a "reboot" operation would return a 202 Accepted because there's no actual
-// resource created?
+/// Create an image
+///
+/// Create a new image in a project.
 #[endpoint {
     method = POST,
-     path = "/organizations/{organization_name}/projects/{project_name}/instances",
-    tags = ["instances"],
+    path = "/organizations/{organization_name}/projects/{project_name}/images",
+    tags = ["images"]
 }]
-async fn instance_create(
+async fn image_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ProjectPathParam>,
-    new_instance: TypedBody<params::InstanceCreate>,
-) -> Result<HttpResponseCreated<Instance>, HttpError> {
+    new_image: TypedBody<params::ImageCreate>,
+) -> Result<HttpResponseCreated<Image>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let new_instance_params = &new_instance.into_inner();
+    let new_image_params = &new_image.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus
-            .project_create_instance(
+        let image = nexus
+            .project_create_image(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &new_instance_params,
+                &new_image_params,
             )
             .await?;
-        Ok(HttpResponseCreated(instance.into()))
+        Ok(HttpResponseCreated(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Instance requests
+/// Path parameters for Image requests
 #[derive(Deserialize, JsonSchema)]
-struct InstancePathParam {
+struct ImagePathParam {
     organization_name: Name,
     project_name: Name,
-    instance_name: Name,
+    image_name: Name,
 }
 
-/// Fetch an instance
+/// Fetch an image
+///
+/// Fetch the details for a specific image in a project.
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
-    tags = ["instances"],
+    path = "/organizations/{organization_name}/projects/{project_name}/images/{image_name}",
+    tags = ["images"],
 }]
-async fn instance_view(
+async fn image_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseOk<Instance>, HttpError> {
+    path_params: Path<ImagePathParam>,
+) -> Result<HttpResponseOk<Image>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
+    let image_name = &path.image_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus
-            .instance_fetch(
+        let image = nexus
+            .project_image_fetch(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &instance_name,
+                &image_name,
             )
             .await?;
-        Ok(HttpResponseOk(instance.into()))
+        Ok(HttpResponseOk(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an instance by id
+/// Fetch an image by id
 #[endpoint {
     method = GET,
-    path = "/by-id/instances/{id}",
-    tags = ["instances"],
+    path = "/by-id/images/{id}",
+    tags = ["images"],
 }]
-async fn instance_view_by_id(
+async fn image_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Instance>, HttpError> {
+) -> Result<HttpResponseOk<Image>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus.instance_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(instance.into()))
+        let image = nexus.project_image_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete an instance
+/// Delete an image
+///
+/// Permanently delete an image from a project. This operation cannot be undone.
+/// Any instances in the project using the image will continue to run, however
+/// new instances can not be created with this image.
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}",
-    tags = ["instances"],
+    path = "/organizations/{organization_name}/projects/{project_name}/images/{image_name}",
+    tags = ["images"],
 }]
-async fn instance_delete(
+async fn image_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
+    path_params: Path<ImagePathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
+    let image_name = &path.image_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
         nexus
-            .project_destroy_instance(
+            .project_delete_image(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &instance_name,
+                &image_name,
             )
             .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// TODO should this be in the public API?
-/// Migrate an instance
+/*
+ * VPCs
+ */
+
+/// List network interfaces
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/migrate",
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces",
     tags = ["instances"],
 }]
-async fn instance_migrate(
+async fn instance_network_interface_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedByName>,
     path_params: Path<InstancePathParam>,
-    migrate_params: TypedBody<params::InstanceMigrate>,
-) -> Result<HttpResponseOk<Instance>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<NetworkInterface>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
     let instance_name = &path.instance_name;
-    let migrate_instance_params = migrate_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus
-            .project_instance_migrate(
+        let interfaces = nexus
+            .instance_list_network_interfaces(
                 &opctx,
                 &organization_name,
                 &project_name,
                 &instance_name,
-                migrate_instance_params,
+                &data_page_params_for(&rqctx, &query)?
+                    .map_name(|n| Name::ref_cast(n)),
             )
-            .await?;
-        Ok(HttpResponseOk(instance.into()))
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByName::results_page(
+            &query,
+            interfaces,
+            &marker_for_name,
+        )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Reboot an instance
+/// Create a network interface
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/reboot",
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces",
     tags = ["instances"],
 }]
-async fn instance_reboot(
+async fn instance_network_interface_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseAccepted<Instance>, HttpError> {
+    interface_params: TypedBody<params::NetworkInterfaceCreate>,
+) -> Result<HttpResponseCreated<NetworkInterface>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
@@ -2189,198 +3931,205 @@ async fn instance_reboot(
     let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus
-            .instance_reboot(
+        let iface = nexus
+            .instance_create_network_interface(
                 &opctx,
                 &organization_name,
                 &project_name,
                 &instance_name,
+                &interface_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseAccepted(instance.into()))
+        Ok(HttpResponseCreated(iface.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Boot an instance
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct NetworkInterfacePathParam {
+    pub organization_name: Name,
+    pub project_name: Name,
+    pub instance_name: Name,
+    pub interface_name: Name,
+}
+
+/// Delete a network interface
+///
+/// Note that the primary interface for an instance cannot be deleted if there
+/// are any secondary interfaces. A new primary interface must be designated
+/// first. The primary interface can be deleted if there are no secondary
+/// interfaces.
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/start",
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces/{interface_name}",
     tags = ["instances"],
 }]
-async fn instance_start(
+async fn instance_network_interface_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseAccepted<Instance>, HttpError> {
+    path_params: Path<NetworkInterfacePathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
     let instance_name = &path.instance_name;
+    let interface_name = &path.interface_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus
-            .instance_start(
+        nexus
+            .instance_delete_network_interface(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
+                organization_name,
+                project_name,
+                instance_name,
+                interface_name,
             )
             .await?;
-        Ok(HttpResponseAccepted(instance.into()))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Halt an instance
+/// Fetch a network interface
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/stop",
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces/{interface_name}",
     tags = ["instances"],
 }]
-async fn instance_stop(
+async fn instance_network_interface_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseAccepted<Instance>, HttpError> {
+    path_params: Path<NetworkInterfacePathParam>,
+) -> Result<HttpResponseOk<NetworkInterface>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
     let instance_name = &path.instance_name;
+    let interface_name = &path.interface_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let instance = nexus
-            .instance_stop(
+        let interface = nexus
+            .network_interface_fetch(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
+                organization_name,
+                project_name,
+                instance_name,
+                interface_name,
             )
             .await?;
-        Ok(HttpResponseAccepted(instance.into()))
+        Ok(HttpResponseOk(NetworkInterface::from(interface)))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an instance's serial console
+/// Fetch a network interface by id
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console",
+    path = "/by-id/network-interfaces/{id}",
     tags = ["instances"],
 }]
-async fn instance_serial_console(
+async fn instance_network_interface_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
-    query_params: Query<params::InstanceSerialConsoleRequest>,
-) -> Result<HttpResponseOk<params::InstanceSerialConsoleData>, HttpError> {
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<NetworkInterface>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
+    let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let data = nexus
-            .instance_serial_console_data(
-                &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
-                &query_params.into_inner(),
-            )
-            .await?;
-        Ok(HttpResponseOk(data))
+        let network_interface =
+            nexus.network_interface_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(network_interface.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Connect to an instance's serial console
-#[channel {
-    protocol = WEBSOCKETS,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/serial-console/stream",
+/// Update a network interface
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces/{interface_name}",
     tags = ["instances"],
 }]
-async fn instance_serial_console_stream(
+async fn instance_network_interface_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    conn: WebsocketConnection,
-    path_params: Path<InstancePathParam>,
-) -> WebsocketChannelResult {
+    path_params: Path<NetworkInterfacePathParam>,
+    updated_iface: TypedBody<params::NetworkInterfaceUpdate>,
+) -> Result<HttpResponseOk<NetworkInterface>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
     let instance_name = &path.instance_name;
-    let opctx = OpContext::for_external_api(&rqctx).await?;
-    nexus
-        .instance_serial_console_stream(
-            &opctx,
-            conn,
-            organization_name,
-            project_name,
-            instance_name,
-        )
-        .await?;
-    Ok(())
+    let interface_name = &path.interface_name;
+    let updated_iface = updated_iface.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let interface = nexus
+            .network_interface_update(
+                &opctx,
+                organization_name,
+                project_name,
+                instance_name,
+                interface_name,
+                updated_iface,
+            )
+            .await?;
+        Ok(HttpResponseOk(NetworkInterface::from(interface)))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List an instance's disks
-// TODO-scalability needs to be paginated
+// External IP addresses for instances
+
+/// List external IP addresses
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks",
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/external-ips",
     tags = ["instances"],
 }]
-async fn instance_disk_list(
+async fn instance_external_ip_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    query_params: Query<PaginatedByName>,
     path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Disk>>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<views::ExternalIp>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let query = query_params.into_inner();
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
     let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disks = nexus
-            .instance_list_disks(
+        let ips = nexus
+            .instance_list_external_ips(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
-                &data_page_params_for(&rqctx, &query)?
-                    .map_name(|n| Name::ref_cast(n)),
+                organization_name,
+                project_name,
+                instance_name,
             )
-            .await?
-            .into_iter()
-            .map(|d| d.into())
-            .collect();
-        Ok(HttpResponseOk(ScanByName::results_page(
-            &query,
-            disks,
-            &marker_for_name,
-        )?))
+            .await?;
+        Ok(HttpResponseOk(ResultsPage { items: ips, next_page: None }))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Attach a disk to an instance
+/// Allocate an ephemeral IP address for an instance
+///
+/// The instance must be running or stopped, and must not already have an
+/// ephemeral IP address.
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks/attach",
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/external-ips/ephemeral",
     tags = ["instances"],
 }]
-async fn instance_disk_attach(
+async fn instance_ephemeral_ip_attach(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<InstancePathParam>,
-    disk_to_attach: TypedBody<params::DiskIdentifier>,
-) -> Result<HttpResponseAccepted<Disk>, HttpError> {
+    ip_params: TypedBody<params::EphemeralIpCreate>,
+) -> Result<HttpResponseAccepted<views::ExternalIp>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
@@ -2389,31 +4138,30 @@ async fn instance_disk_attach(
     let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disk = nexus
-            .instance_attach_disk(
+        let ip = nexus
+            .instance_attach_ephemeral_ip(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
-                &disk_to_attach.into_inner().name.into(),
+                organization_name,
+                project_name,
+                instance_name,
+                &ip_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseAccepted(disk.into()))
+        Ok(HttpResponseAccepted(ip))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Detach a disk from an instance
+/// Release the ephemeral IP address of an instance
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/disks/detach",
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/external-ips/ephemeral",
     tags = ["instances"],
 }]
-async fn instance_disk_detach(
+async fn instance_ephemeral_ip_detach(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<InstancePathParam>,
-    disk_to_detach: TypedBody<params::DiskIdentifier>,
-) -> Result<HttpResponseAccepted<Disk>, HttpError> {
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
@@ -2422,43 +4170,45 @@ async fn instance_disk_detach(
     let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let disk = nexus
-            .instance_detach_disk(
+        nexus
+            .instance_detach_ephemeral_ip(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
-                &disk_to_detach.into_inner().name.into(),
+                organization_name,
+                project_name,
+                instance_name,
             )
             .await?;
-        Ok(HttpResponseAccepted(disk.into()))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Images
+// Snapshots
 
-/// List system-wide images
-///
-/// Returns a list of all the system-wide images. System-wide images are returned sorted
-/// by creation date, with the most recent images appearing first.
+/// List snapshots
 #[endpoint {
     method = GET,
-    path = "/system/images",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/snapshots",
+    tags = ["snapshots"],
 }]
-async fn system_image_list(
+async fn snapshot_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
-) -> Result<HttpResponseOk<ResultsPage<GlobalImage>>, HttpError> {
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<Snapshot>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let images = nexus
-            .global_images_list(
+        let snapshots = nexus
+            .project_list_snapshots(
                 &opctx,
+                organization_name,
+                project_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
@@ -2468,129 +4218,152 @@ async fn system_image_list(
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            images,
+            snapshots,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a system-wide image
+/// Create a snapshot
 ///
-/// Create a new system-wide image. This image can then be used by any user in any silo as a
-/// base for instances.
+/// Creates a point-in-time snapshot from a disk.
 #[endpoint {
     method = POST,
-    path = "/system/images",
-    tags = ["system"]
+    path = "/organizations/{organization_name}/projects/{project_name}/snapshots",
+    tags = ["snapshots"],
 }]
-async fn system_image_create(
+async fn snapshot_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    new_image: TypedBody<params::GlobalImageCreate>,
-) -> Result<HttpResponseCreated<GlobalImage>, HttpError> {
+    path_params: Path<ProjectPathParam>,
+    new_snapshot: TypedBody<params::SnapshotCreate>,
+) -> Result<HttpResponseCreated<Snapshot>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let new_image_params = new_image.into_inner();
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let new_snapshot_params = &new_snapshot.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let image = nexus.global_image_create(&opctx, new_image_params).await?;
-        Ok(HttpResponseCreated(image.into()))
+        let snapshot = nexus
+            .project_create_snapshot(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &new_snapshot_params,
+            )
+            .await?;
+        Ok(HttpResponseCreated(snapshot.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Image requests
+/// Path parameters for Snapshot requests
 #[derive(Deserialize, JsonSchema)]
-struct GlobalImagePathParam {
-    image_name: Name,
+struct SnapshotPathParam {
+    organization_name: Name,
+    project_name: Name,
+    snapshot_name: Name,
 }
 
-/// Fetch a system-wide image
-///
-/// Returns the details of a specific system-wide image.
+/// Fetch a snapshot
 #[endpoint {
     method = GET,
-    path = "/system/images/{image_name}",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/snapshots/{snapshot_name}",
+    tags = ["snapshots"],
 }]
-async fn system_image_view(
+async fn snapshot_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<GlobalImagePathParam>,
-) -> Result<HttpResponseOk<GlobalImage>, HttpError> {
+    path_params: Path<SnapshotPathParam>,
+) -> Result<HttpResponseOk<Snapshot>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let image_name = &path.image_name;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let snapshot_name = &path.snapshot_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let image = nexus.global_image_fetch(&opctx, &image_name).await?;
-        Ok(HttpResponseOk(image.into()))
+        let snapshot = nexus
+            .snapshot_fetch(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &snapshot_name,
+            )
+            .await?;
+        Ok(HttpResponseOk(snapshot.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a system-wide image by id
+/// Fetch a snapshot by id
 #[endpoint {
     method = GET,
-    path = "/system/by-id/images/{id}",
-    tags = ["system"],
+    path = "/by-id/snapshots/{id}",
+    tags = ["snapshots"],
 }]
-async fn system_image_view_by_id(
+async fn snapshot_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<GlobalImage>, HttpError> {
+) -> Result<HttpResponseOk<Snapshot>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let image = nexus.global_image_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(image.into()))
+        let snapshot = nexus.snapshot_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(snapshot.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a system-wide image
-///
-/// Permanently delete a system-wide image. This operation cannot be undone. Any
-/// instances using the system-wide image will continue to run, however new instances
-/// can not be created with this image.
+/// Delete a snapshot
 #[endpoint {
     method = DELETE,
-    path = "/system/images/{image_name}",
-    tags = ["system"],
+    path = "/organizations/{organization_name}/projects/{project_name}/snapshots/{snapshot_name}",
+    tags = ["snapshots"],
 }]
-async fn system_image_delete(
+async fn snapshot_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<GlobalImagePathParam>,
+    path_params: Path<SnapshotPathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let image_name = &path.image_name;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let snapshot_name = &path.snapshot_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus.global_image_delete(&opctx, &image_name).await?;
+        nexus
+            .project_delete_snapshot(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &snapshot_name,
+            )
+            .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List images
-///
-/// List images in a project. The images are returned sorted by creation date,
-/// with the most recent images appearing first.
+// Floating IPs
+
+/// List floating IPs
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/images",
-    tags = ["images"],
+    path = "/organizations/{organization_name}/projects/{project_name}/floating-ips",
+    tags = ["floating-ips"],
 }]
-async fn image_list(
+async fn floating_ip_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
     path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Image>>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<views::FloatingIp>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
@@ -2599,8 +4372,8 @@ async fn image_list(
     let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let images = nexus
-            .project_list_images(
+        let fips = nexus
+            .floating_ips_list(
                 &opctx,
                 organization_name,
                 project_name,
@@ -2609,778 +4382,882 @@ async fn image_list(
             )
             .await?
             .into_iter()
-            .map(|d| d.into())
+            .map(|ip| ip.into())
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            images,
+            fips,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create an image
+/// Create a floating IP
 ///
-/// Create a new image in a project.
+/// Allocates an external IP address from an IP Pool, which can later be
+/// attached to any instance in the project.
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/images",
-    tags = ["images"]
+    path = "/organizations/{organization_name}/projects/{project_name}/floating-ips",
+    tags = ["floating-ips"],
 }]
-async fn image_create(
+async fn floating_ip_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ProjectPathParam>,
-    new_image: TypedBody<params::ImageCreate>,
-) -> Result<HttpResponseCreated<Image>, HttpError> {
+    new_fip: TypedBody<params::FloatingIpCreate>,
+) -> Result<HttpResponseCreated<views::FloatingIp>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let new_image_params = &new_image.into_inner();
+    let new_fip_params = &new_fip.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let image = nexus
-            .project_create_image(
+        let fip = nexus
+            .floating_ip_create(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &new_image_params,
+                &new_fip_params,
             )
             .await?;
-        Ok(HttpResponseCreated(image.into()))
+        Ok(HttpResponseCreated(fip.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Image requests
+/// Path parameters for Floating IP requests
 #[derive(Deserialize, JsonSchema)]
-struct ImagePathParam {
+struct FloatingIpPathParam {
     organization_name: Name,
     project_name: Name,
-    image_name: Name,
+    floating_ip_name: Name,
 }
 
-/// Fetch an image
-///
-/// Fetch the details for a specific image in a project.
+/// Fetch a floating IP
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/images/{image_name}",
-    tags = ["images"],
+    path = "/organizations/{organization_name}/projects/{project_name}/floating-ips/{floating_ip_name}",
+    tags = ["floating-ips"],
 }]
-async fn image_view(
+async fn floating_ip_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ImagePathParam>,
-) -> Result<HttpResponseOk<Image>, HttpError> {
+    path_params: Path<FloatingIpPathParam>,
+) -> Result<HttpResponseOk<views::FloatingIp>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let image_name = &path.image_name;
+    let floating_ip_name = &path.floating_ip_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let image = nexus
-            .project_image_fetch(
+        let fip = nexus
+            .floating_ip_fetch(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &image_name,
+                &floating_ip_name,
             )
             .await?;
-        Ok(HttpResponseOk(image.into()))
+        Ok(HttpResponseOk(fip.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch an image by id
+/// Fetch a floating IP by id
 #[endpoint {
     method = GET,
-    path = "/by-id/images/{id}",
-    tags = ["images"],
+    path = "/by-id/floating-ips/{id}",
+    tags = ["floating-ips"],
 }]
-async fn image_view_by_id(
+async fn floating_ip_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Image>, HttpError> {
+) -> Result<HttpResponseOk<views::FloatingIp>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let image = nexus.project_image_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(image.into()))
+        let fip = nexus.floating_ip_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(fip.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete an image
+/// Delete a floating IP
 ///
-/// Permanently delete an image from a project. This operation cannot be undone.
-/// Any instances in the project using the image will continue to run, however
-/// new instances can not be created with this image.
+/// The floating IP must not be attached to an instance.
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/images/{image_name}",
-    tags = ["images"],
+    path = "/organizations/{organization_name}/projects/{project_name}/floating-ips/{floating_ip_name}",
+    tags = ["floating-ips"],
 }]
-async fn image_delete(
+async fn floating_ip_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ImagePathParam>,
+    path_params: Path<FloatingIpPathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let image_name = &path.image_name;
+    let floating_ip_name = &path.floating_ip_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
         nexus
-            .project_delete_image(
+            .floating_ip_delete(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &image_name,
+                &floating_ip_name,
             )
             .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/*
- * VPCs
- */
+/// Attach a floating IP to an instance
+///
+/// The instance must be in the same project, and must be running or stopped.
+#[endpoint {
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/floating-ips/{floating_ip_name}/attach",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_attach(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<FloatingIpPathParam>,
+    attach_params: TypedBody<params::FloatingIpAttach>,
+) -> Result<HttpResponseAccepted<views::FloatingIp>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let floating_ip_name = &path.floating_ip_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let fip = nexus
+            .floating_ip_attach(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &floating_ip_name,
+                &attach_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseAccepted(fip.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
 
-/// List network interfaces
+/// Detach a floating IP from an instance
+#[endpoint {
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/floating-ips/{floating_ip_name}/detach",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_detach(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<FloatingIpPathParam>,
+) -> Result<HttpResponseAccepted<views::FloatingIp>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let floating_ip_name = &path.floating_ip_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let fip = nexus
+            .floating_ip_detach(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &floating_ip_name,
+            )
+            .await?;
+        Ok(HttpResponseAccepted(fip.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// VPCs
+
+/// List VPCs
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces",
-    tags = ["instances"],
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs",
+    tags = ["vpcs"],
 }]
-async fn instance_network_interface_list(
+async fn vpc_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
-    path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseOk<ResultsPage<NetworkInterface>>, HttpError> {
+    path_params: Path<ProjectPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<Vpc>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let interfaces = nexus
-            .instance_list_network_interfaces(
+        let vpcs = nexus
+            .project_list_vpcs(
                 &opctx,
                 &organization_name,
                 &project_name,
-                &instance_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
             .await?
             .into_iter()
-            .map(|d| d.into())
+            .map(|p| p.into())
             .collect();
+
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            interfaces,
+            vpcs,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a network interface
+/// Path parameters for VPC requests
+#[derive(Deserialize, JsonSchema)]
+struct VpcPathParam {
+    organization_name: Name,
+    project_name: Name,
+    vpc_name: Name,
+}
+
+/// Fetch a VPC
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces",
-    tags = ["instances"],
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}",
+    tags = ["vpcs"],
 }]
-async fn instance_network_interface_create(
+async fn vpc_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
-    interface_params: TypedBody<params::NetworkInterfaceCreate>,
-) -> Result<HttpResponseCreated<NetworkInterface>, HttpError> {
+    path_params: Path<VpcPathParam>,
+) -> Result<HttpResponseOk<Vpc>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
+    let vpc_name = &path.vpc_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let iface = nexus
-            .instance_create_network_interface(
-                &opctx,
-                &organization_name,
-                &project_name,
-                &instance_name,
-                &interface_params.into_inner(),
-            )
+        let vpc = nexus
+            .vpc_fetch(&opctx, &organization_name, &project_name, &vpc_name)
             .await?;
-        Ok(HttpResponseCreated(iface.into()))
+        Ok(HttpResponseOk(vpc.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
-pub struct NetworkInterfacePathParam {
-    pub organization_name: Name,
-    pub project_name: Name,
-    pub instance_name: Name,
-    pub interface_name: Name,
+/// Fetch a VPC
+#[endpoint {
+    method = GET,
+    path = "/by-id/vpcs/{id}",
+    tags = ["vpcs"],
+}]
+async fn vpc_view_by_id(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<Vpc>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let id = &path.id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let vpc = nexus.vpc_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(vpc.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a network interface
-///
-/// Note that the primary interface for an instance cannot be deleted if there
-/// are any secondary interfaces. A new primary interface must be designated
-/// first. The primary interface can be deleted if there are no secondary
-/// interfaces.
+/// Create a VPC
 #[endpoint {
-    method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces/{interface_name}",
-    tags = ["instances"],
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs",
+    tags = ["vpcs"],
 }]
-async fn instance_network_interface_delete(
+async fn vpc_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<NetworkInterfacePathParam>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    path_params: Path<ProjectPathParam>,
+    new_vpc: TypedBody<params::VpcCreate>,
+) -> Result<HttpResponseCreated<Vpc>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
-    let interface_name = &path.interface_name;
+    let new_vpc_params = &new_vpc.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus
-            .instance_delete_network_interface(
+        let vpc = nexus
+            .project_create_vpc(
                 &opctx,
-                organization_name,
-                project_name,
-                instance_name,
-                interface_name,
+                &organization_name,
+                &project_name,
+                &new_vpc_params,
             )
             .await?;
-        Ok(HttpResponseDeleted())
+        Ok(HttpResponseCreated(vpc.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a network interface
+/// Update a VPC
 #[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces/{interface_name}",
-    tags = ["instances"],
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}",
+    tags = ["vpcs"],
 }]
-async fn instance_network_interface_view(
+async fn vpc_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<NetworkInterfacePathParam>,
-) -> Result<HttpResponseOk<NetworkInterface>, HttpError> {
+    path_params: Path<VpcPathParam>,
+    updated_vpc: TypedBody<params::VpcUpdate>,
+) -> Result<HttpResponseOk<Vpc>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
-    let interface_name = &path.interface_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let interface = nexus
-            .network_interface_fetch(
+        let newvpc = nexus
+            .project_update_vpc(
                 &opctx,
-                organization_name,
-                project_name,
-                instance_name,
-                interface_name,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &updated_vpc.into_inner(),
             )
             .await?;
-        Ok(HttpResponseOk(NetworkInterface::from(interface)))
+        Ok(HttpResponseOk(newvpc.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a network interface by id
+/// Delete a VPC
 #[endpoint {
-    method = GET,
-    path = "/by-id/network-interfaces/{id}",
-    tags = ["instances"],
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}",
+    tags = ["vpcs"],
 }]
-async fn instance_network_interface_view_by_id(
+async fn vpc_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<NetworkInterface>, HttpError> {
+    path_params: Path<VpcPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let id = &path.id;
+    let organization_name = &path.organization_name;
+    let project_name = &path.project_name;
+    let vpc_name = &path.vpc_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let network_interface =
-            nexus.network_interface_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(network_interface.into()))
+        nexus
+            .project_delete_vpc(
+                &opctx,
+                &organization_name,
+                &project_name,
+                &vpc_name,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update a network interface
+/// Fetch a VPC's IAM policy
 #[endpoint {
-    method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/network-interfaces/{interface_name}",
-    tags = ["instances"],
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/policy",
+    tags = ["vpcs"],
 }]
-async fn instance_network_interface_update(
+async fn vpc_policy_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<NetworkInterfacePathParam>,
-    updated_iface: TypedBody<params::NetworkInterfaceUpdate>,
-) -> Result<HttpResponseOk<NetworkInterface>, HttpError> {
+    path_params: Path<VpcPathParam>,
+) -> Result<HttpResponseOk<shared::Policy<authz::VpcRole>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
-    let interface_name = &path.interface_name;
-    let updated_iface = updated_iface.into_inner();
+    let vpc_name = &path.vpc_name;
+
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let interface = nexus
-            .network_interface_update(
-                &opctx,
-                organization_name,
-                project_name,
-                instance_name,
-                interface_name,
-                updated_iface,
-            )
+        let policy = nexus
+            .vpc_fetch_policy(&opctx, organization_name, project_name, vpc_name)
             .await?;
-        Ok(HttpResponseOk(NetworkInterface::from(interface)))
+        Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// External IP addresses for instances
-
-/// List external IP addresses
+/// Update a VPC's IAM policy
 #[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/instances/{instance_name}/external-ips",
-    tags = ["instances"],
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/policy",
+    tags = ["vpcs"],
 }]
-async fn instance_external_ip_list(
+async fn vpc_policy_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<InstancePathParam>,
-) -> Result<HttpResponseOk<ResultsPage<views::ExternalIp>>, HttpError> {
+    path_params: Path<VpcPathParam>,
+    new_policy: TypedBody<shared::Policy<authz::VpcRole>>,
+) -> Result<HttpResponseOk<shared::Policy<authz::VpcRole>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
+    let new_policy = new_policy.into_inner();
     let organization_name = &path.organization_name;
     let project_name = &path.project_name;
-    let instance_name = &path.instance_name;
+    let vpc_name = &path.vpc_name;
+
     let handler = async {
+        let nasgns = new_policy.role_assignments.len();
+        // This should have been validated during parsing.
+        bail_unless!(nasgns <= shared::MAX_ROLE_ASSIGNMENTS_PER_RESOURCE);
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let ips = nexus
-            .instance_list_external_ips(
+        let policy = nexus
+            .vpc_update_policy(
                 &opctx,
                 organization_name,
                 project_name,
-                instance_name,
+                vpc_name,
+                &new_policy,
             )
             .await?;
-        Ok(HttpResponseOk(ResultsPage { items: ips, next_page: None }))
+        Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Snapshots
-
-/// List snapshots
+/// List subnets
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/snapshots",
-    tags = ["snapshots"],
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets",
+    tags = ["vpcs"],
 }]
-async fn snapshot_list(
+async fn vpc_subnet_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
-    path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Snapshot>>, HttpError> {
+    path_params: Path<VpcPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<VpcSubnet>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let snapshots = nexus
-            .project_list_snapshots(
+        let vpcs = nexus
+            .vpc_list_subnets(
                 &opctx,
-                organization_name,
-                project_name,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
             .await?
             .into_iter()
-            .map(|d| d.into())
+            .map(|vpc| vpc.into())
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            snapshots,
+            vpcs,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a snapshot
-///
-/// Creates a point-in-time snapshot from a disk.
+/// Path parameters for VPC Subnet requests
+#[derive(Deserialize, JsonSchema)]
+struct VpcSubnetPathParam {
+    organization_name: Name,
+    project_name: Name,
+    vpc_name: Name,
+    subnet_name: Name,
+}
+
+/// Fetch a subnet
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/snapshots",
-    tags = ["snapshots"],
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}",
+    tags = ["vpcs"],
 }]
-async fn snapshot_create(
+async fn vpc_subnet_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-    new_snapshot: TypedBody<params::SnapshotCreate>,
-) -> Result<HttpResponseCreated<Snapshot>, HttpError> {
+    path_params: Path<VpcSubnetPathParam>,
+) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let new_snapshot_params = &new_snapshot.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let snapshot = nexus
-            .project_create_snapshot(
+        let subnet = nexus
+            .vpc_subnet_fetch(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &new_snapshot_params,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.subnet_name,
             )
             .await?;
-        Ok(HttpResponseCreated(snapshot.into()))
+        Ok(HttpResponseOk(subnet.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Path parameters for Snapshot requests
-#[derive(Deserialize, JsonSchema)]
-struct SnapshotPathParam {
-    organization_name: Name,
-    project_name: Name,
-    snapshot_name: Name,
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a snapshot
+/// Fetch a subnet by id
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/snapshots/{snapshot_name}",
-    tags = ["snapshots"],
+    path = "/by-id/vpc-subnets/{id}",
+    tags = ["vpcs"],
 }]
-async fn snapshot_view(
+async fn vpc_subnet_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<SnapshotPathParam>,
-) -> Result<HttpResponseOk<Snapshot>, HttpError> {
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let snapshot_name = &path.snapshot_name;
+    let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let snapshot = nexus
-            .snapshot_fetch(
-                &opctx,
-                &organization_name,
-                &project_name,
-                &snapshot_name,
-            )
-            .await?;
-        Ok(HttpResponseOk(snapshot.into()))
+        let subnet = nexus.vpc_subnet_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(subnet.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a snapshot by id
+/// Create a subnet
 #[endpoint {
-    method = GET,
-    path = "/by-id/snapshots/{id}",
-    tags = ["snapshots"],
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets",
+    tags = ["vpcs"],
 }]
-async fn snapshot_view_by_id(
+async fn vpc_subnet_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Snapshot>, HttpError> {
+    path_params: Path<VpcPathParam>,
+    create_params: TypedBody<params::VpcSubnetCreate>,
+) -> Result<HttpResponseCreated<VpcSubnet>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let snapshot = nexus.snapshot_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(snapshot.into()))
+        let subnet = nexus
+            .vpc_create_subnet(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &create_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(subnet.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a snapshot
+/// Delete a subnet
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/snapshots/{snapshot_name}",
-    tags = ["snapshots"],
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}",
+    tags = ["vpcs"],
 }]
-async fn snapshot_delete(
+async fn vpc_subnet_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<SnapshotPathParam>,
+    path_params: Path<VpcSubnetPathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let snapshot_name = &path.snapshot_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
         nexus
-            .project_delete_snapshot(
+            .vpc_delete_subnet(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &snapshot_name,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.subnet_name,
             )
             .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// VPCs
+/// Update a subnet
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}",
+    tags = ["vpcs"],
+}]
+async fn vpc_subnet_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<VpcSubnetPathParam>,
+    subnet_params: TypedBody<params::VpcSubnetUpdate>,
+) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let subnet = nexus
+            .vpc_update_subnet(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.subnet_name,
+                &subnet_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(subnet.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
 
-/// List VPCs
+/// List network interfaces
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}/network-interfaces",
     tags = ["vpcs"],
 }]
-async fn vpc_list(
+async fn vpc_subnet_list_network_interfaces(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
-    path_params: Path<ProjectPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<Vpc>>, HttpError> {
+    path_params: Path<VpcSubnetPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<NetworkInterface>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let vpcs = nexus
-            .project_list_vpcs(
+        let interfaces = nexus
+            .subnet_list_network_interfaces(
                 &opctx,
-                &organization_name,
-                &project_name,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.subnet_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
             .await?
             .into_iter()
-            .map(|p| p.into())
+            .map(|interfaces| interfaces.into())
             .collect();
-
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            vpcs,
+            interfaces,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Path parameters for VPC requests
-#[derive(Deserialize, JsonSchema)]
-struct VpcPathParam {
-    organization_name: Name,
-    project_name: Name,
-    vpc_name: Name,
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a VPC
+/// Associate a subnet with a custom router
 #[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}",
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}/router",
     tags = ["vpcs"],
 }]
-async fn vpc_view(
+async fn vpc_subnet_router_attach(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcPathParam>,
-) -> Result<HttpResponseOk<Vpc>, HttpError> {
+    path_params: Path<VpcSubnetPathParam>,
+    attach_params: TypedBody<params::VpcSubnetRouterAttach>,
+) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let vpc_name = &path.vpc_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let vpc = nexus
-            .vpc_fetch(&opctx, &organization_name, &project_name, &vpc_name)
+        let subnet = nexus
+            .vpc_subnet_attach_router(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.subnet_name,
+                &attach_params.into_inner().router.into(),
+            )
             .await?;
-        Ok(HttpResponseOk(vpc.into()))
+        Ok(HttpResponseOk(subnet.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a VPC
+/// Dissociate a subnet from its custom router
 #[endpoint {
-    method = GET,
-    path = "/by-id/vpcs/{id}",
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}/router",
     tags = ["vpcs"],
 }]
-async fn vpc_view_by_id(
+async fn vpc_subnet_router_detach(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<Vpc>, HttpError> {
+    path_params: Path<VpcSubnetPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let vpc = nexus.vpc_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(vpc.into()))
+        nexus
+            .vpc_subnet_detach_router(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.subnet_name,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a VPC
+// VPC Firewalls
+
+// TODO Is the number of firewall rules bounded?
+/// List firewall rules
 #[endpoint {
-    method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs",
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
     tags = ["vpcs"],
 }]
-async fn vpc_create(
+async fn vpc_firewall_rules_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<ProjectPathParam>,
-    new_vpc: TypedBody<params::VpcCreate>,
-) -> Result<HttpResponseCreated<Vpc>, HttpError> {
+    path_params: Path<VpcPathParam>,
+) -> Result<HttpResponseOk<VpcFirewallRules>, HttpError> {
+    // TODO: Check If-Match and fail if the ETag doesn't match anymore.
+    // Without this check, if firewall rules change while someone is listing
+    // the rules, they will see a mix of the old and new rules.
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let new_vpc_params = &new_vpc.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let vpc = nexus
-            .project_create_vpc(
+        let rules = nexus
+            .vpc_list_firewall_rules(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &new_vpc_params,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
             )
             .await?;
-        Ok(HttpResponseCreated(vpc.into()))
+        Ok(HttpResponseOk(VpcFirewallRules {
+            rules: rules.into_iter().map(|rule| rule.into()).collect(),
+        }))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update a VPC
+/// Replace firewall rules
 #[endpoint {
     method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
     tags = ["vpcs"],
 }]
-async fn vpc_update(
+async fn vpc_firewall_rules_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<VpcPathParam>,
-    updated_vpc: TypedBody<params::VpcUpdate>,
-) -> Result<HttpResponseOk<Vpc>, HttpError> {
+    router_params: TypedBody<VpcFirewallRuleUpdateParams>,
+) -> Result<HttpResponseOk<VpcFirewallRules>, HttpError> {
+    // TODO: Check If-Match and fail if the ETag doesn't match anymore.
+    // TODO: limit size of the ruleset because the GET endpoint is not paginated
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let newvpc = nexus
-            .project_update_vpc(
+        let rules = nexus
+            .vpc_update_firewall_rules(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &updated_vpc.into_inner(),
+                &router_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseOk(newvpc.into()))
+        Ok(HttpResponseOk(VpcFirewallRules {
+            rules: rules.into_iter().map(|rule| rule.into()).collect(),
+        }))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a VPC
+/// Evaluate a flow against firewall rules
+///
+/// Returns the rule deciding whether the described flow is allowed, along
+/// with its priority and the resulting action.
 #[endpoint {
-    method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}",
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/evaluate",
     tags = ["vpcs"],
 }]
-async fn vpc_delete(
+async fn vpc_firewall_rules_evaluate(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<VpcPathParam>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    flow_params: TypedBody<params::VpcFirewallRuleEvaluate>,
+) -> Result<HttpResponseOk<views::VpcFirewallRuleEvaluation>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
-    let organization_name = &path.organization_name;
-    let project_name = &path.project_name;
-    let vpc_name = &path.vpc_name;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus
-            .project_delete_vpc(
+        let evaluation = nexus
+            .vpc_evaluate_firewall_rules(
                 &opctx,
-                &organization_name,
-                &project_name,
-                &vpc_name,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &flow_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseDeleted())
+        Ok(HttpResponseOk(evaluation))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List subnets
+// VPC Routers
+
+/// List routers
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers",
     tags = ["vpcs"],
 }]
-async fn vpc_subnet_list(
+async fn vpc_router_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
     path_params: Path<VpcPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<VpcSubnet>>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<VpcRouter>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let vpcs = nexus
-            .vpc_list_subnets(
+        let routers = nexus
+            .vpc_list_routers(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
@@ -3390,116 +5267,117 @@ async fn vpc_subnet_list(
             )
             .await?
             .into_iter()
-            .map(|vpc| vpc.into())
+            .map(|s| s.into())
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            vpcs,
+            routers,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for VPC Subnet requests
+/// Path parameters for VPC Router requests
 #[derive(Deserialize, JsonSchema)]
-struct VpcSubnetPathParam {
+struct VpcRouterPathParam {
     organization_name: Name,
     project_name: Name,
     vpc_name: Name,
-    subnet_name: Name,
+    router_name: Name,
 }
 
-/// Fetch a subnet
+/// Get a router
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_subnet_view(
+async fn vpc_router_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcSubnetPathParam>,
-) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
+    path_params: Path<VpcRouterPathParam>,
+) -> Result<HttpResponseOk<VpcRouter>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let subnet = nexus
-            .vpc_subnet_fetch(
+        let vpc_router = nexus
+            .vpc_router_fetch(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.subnet_name,
+                &path.router_name,
             )
             .await?;
-        Ok(HttpResponseOk(subnet.into()))
+        Ok(HttpResponseOk(vpc_router.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a subnet by id
+/// Get a router by id
 #[endpoint {
     method = GET,
-    path = "/by-id/vpc-subnets/{id}",
+    path = "/by-id/vpc-routers/{id}",
     tags = ["vpcs"],
 }]
-async fn vpc_subnet_view_by_id(
+async fn vpc_router_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
+) -> Result<HttpResponseOk<VpcRouter>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let subnet = nexus.vpc_subnet_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(subnet.into()))
+        let router = nexus.vpc_router_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(router.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a subnet
+/// Create a router
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers",
     tags = ["vpcs"],
 }]
-async fn vpc_subnet_create(
+async fn vpc_router_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<VpcPathParam>,
-    create_params: TypedBody<params::VpcSubnetCreate>,
-) -> Result<HttpResponseCreated<VpcSubnet>, HttpError> {
+    create_params: TypedBody<params::VpcRouterCreate>,
+) -> Result<HttpResponseCreated<VpcRouter>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let subnet = nexus
-            .vpc_create_subnet(
+        let router = nexus
+            .vpc_create_router(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
+                &db::model::VpcRouterKind::Custom,
                 &create_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseCreated(subnet.into()))
+        Ok(HttpResponseCreated(router.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a subnet
+/// Delete a router
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_subnet_delete(
+async fn vpc_router_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcSubnetPathParam>,
+    path_params: Path<VpcRouterPathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
@@ -3507,181 +5385,271 @@ async fn vpc_subnet_delete(
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
         nexus
-            .vpc_delete_subnet(
+            .vpc_delete_router(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.router_name,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Update a router
+#[endpoint {
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}",
+    tags = ["vpcs"],
+}]
+async fn vpc_router_update(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<VpcRouterPathParam>,
+    router_params: TypedBody<params::VpcRouterUpdate>,
+) -> Result<HttpResponseOk<VpcRouter>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let router = nexus
+            .vpc_update_router(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.router_name,
+                &router_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(router.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// Vpc Router Routes
+
+/// List routes
+///
+/// List the routes associated with a router in a particular VPC.
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes",
+    tags = ["vpcs"],
+}]
+async fn vpc_router_route_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedByName>,
+    path_params: Path<VpcRouterPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<RouterRoute>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let path = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let routes = nexus
+            .router_list_routes(
+                &opctx,
+                &path.organization_name,
+                &path.project_name,
+                &path.vpc_name,
+                &path.router_name,
+                &data_page_params_for(&rqctx, &query)?
+                    .map_name(|n| Name::ref_cast(n)),
+            )
+            .await?
+            .into_iter()
+            .map(|route| route.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByName::results_page(
+            &query,
+            routes,
+            &marker_for_name,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for Router Route requests
+#[derive(Deserialize, JsonSchema)]
+struct RouterRoutePathParam {
+    organization_name: Name,
+    project_name: Name,
+    vpc_name: Name,
+    router_name: Name,
+    route_name: Name,
+}
+
+/// Fetch a route
+#[endpoint {
+    method = GET,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes/{route_name}",
+    tags = ["vpcs"],
+}]
+async fn vpc_router_route_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<RouterRoutePathParam>,
+) -> Result<HttpResponseOk<RouterRoute>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let route = nexus
+            .route_fetch(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.subnet_name,
+                &path.router_name,
+                &path.route_name,
             )
             .await?;
-        Ok(HttpResponseDeleted())
+        Ok(HttpResponseOk(route.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update a subnet
+/// Fetch a route by id
 #[endpoint {
-    method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}",
-    tags = ["vpcs"],
+    method = GET,
+    path = "/by-id/vpc-router-routes/{id}",
+    tags = ["vpcs"]
 }]
-async fn vpc_subnet_update(
+async fn vpc_router_route_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcSubnetPathParam>,
-    subnet_params: TypedBody<params::VpcSubnetUpdate>,
-) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
+    path_params: Path<ByIdPathParams>,
+) -> Result<HttpResponseOk<RouterRoute>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
+    let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let subnet = nexus
-            .vpc_update_subnet(
-                &opctx,
-                &path.organization_name,
-                &path.project_name,
-                &path.vpc_name,
-                &path.subnet_name,
-                &subnet_params.into_inner(),
-            )
-            .await?;
-        Ok(HttpResponseOk(subnet.into()))
+        let route = nexus.route_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(route.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// List network interfaces
+/// Create a router
 #[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/subnets/{subnet_name}/network-interfaces",
+    method = POST,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes",
     tags = ["vpcs"],
 }]
-async fn vpc_subnet_list_network_interfaces(
+async fn vpc_router_route_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    query_params: Query<PaginatedByName>,
-    path_params: Path<VpcSubnetPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<NetworkInterface>>, HttpError> {
+    path_params: Path<VpcRouterPathParam>,
+    create_params: TypedBody<RouterRouteCreateParams>,
+) -> Result<HttpResponseCreated<RouterRoute>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
-    let query = query_params.into_inner();
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let interfaces = nexus
-            .subnet_list_network_interfaces(
+        let route = nexus
+            .router_create_route(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.subnet_name,
-                &data_page_params_for(&rqctx, &query)?
-                    .map_name(|n| Name::ref_cast(n)),
+                &path.router_name,
+                &RouterRouteKind::Custom,
+                &create_params.into_inner(),
             )
-            .await?
-            .into_iter()
-            .map(|interfaces| interfaces.into())
-            .collect();
-        Ok(HttpResponseOk(ScanByName::results_page(
-            &query,
-            interfaces,
-            &marker_for_name,
-        )?))
+            .await?;
+        Ok(HttpResponseCreated(route.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// VPC Firewalls
-
-// TODO Is the number of firewall rules bounded?
-/// List firewall rules
+/// Delete a route
 #[endpoint {
-    method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes/{route_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_firewall_rules_view(
+async fn vpc_router_route_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcPathParam>,
-) -> Result<HttpResponseOk<VpcFirewallRules>, HttpError> {
-    // TODO: Check If-Match and fail if the ETag doesn't match anymore.
-    // Without this check, if firewall rules change while someone is listing
-    // the rules, they will see a mix of the old and new rules.
+    path_params: Path<RouterRoutePathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let rules = nexus
-            .vpc_list_firewall_rules(
+        nexus
+            .router_delete_route(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
+                &path.router_name,
+                &path.route_name,
             )
             .await?;
-        Ok(HttpResponseOk(VpcFirewallRules {
-            rules: rules.into_iter().map(|rule| rule.into()).collect(),
-        }))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Replace firewall rules
+/// Update a route
 #[endpoint {
     method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/firewall/rules",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes/{route_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_firewall_rules_update(
+async fn vpc_router_route_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcPathParam>,
-    router_params: TypedBody<VpcFirewallRuleUpdateParams>,
-) -> Result<HttpResponseOk<VpcFirewallRules>, HttpError> {
-    // TODO: Check If-Match and fail if the ETag doesn't match anymore.
-    // TODO: limit size of the ruleset because the GET endpoint is not paginated
+    path_params: Path<RouterRoutePathParam>,
+    router_params: TypedBody<RouterRouteUpdateParams>,
+) -> Result<HttpResponseOk<RouterRoute>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let rules = nexus
-            .vpc_update_firewall_rules(
+        let router_route = nexus
+            .router_update_route(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
+                &path.router_name,
+                &path.route_name,
                 &router_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseOk(VpcFirewallRules {
-            rules: rules.into_iter().map(|rule| rule.into()).collect(),
-        }))
+        Ok(HttpResponseOk(router_route.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// VPC Routers
+// VPC Peerings
 
-/// List routers
+/// List peerings
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/peerings",
     tags = ["vpcs"],
 }]
-async fn vpc_router_list(
+async fn vpc_peering_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
     path_params: Path<VpcPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<VpcRouter>>, HttpError> {
+) -> Result<HttpResponseOk<ResultsPage<views::VpcPeering>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let routers = nexus
-            .vpc_list_routers(
+        let peerings = nexus
+            .vpc_list_peerings(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
@@ -3691,117 +5659,132 @@ async fn vpc_router_list(
             )
             .await?
             .into_iter()
-            .map(|s| s.into())
+            .map(|p| p.into())
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            routers,
+            peerings,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for VPC Router requests
+/// Path parameters for VPC Peering requests
 #[derive(Deserialize, JsonSchema)]
-struct VpcRouterPathParam {
+struct VpcPeeringPathParam {
     organization_name: Name,
     project_name: Name,
     vpc_name: Name,
-    router_name: Name,
+    peering_name: Name,
 }
 
-/// Get a router
+/// Get a peering
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/peerings/{peering_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_router_view(
+async fn vpc_peering_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcRouterPathParam>,
-) -> Result<HttpResponseOk<VpcRouter>, HttpError> {
+    path_params: Path<VpcPeeringPathParam>,
+) -> Result<HttpResponseOk<views::VpcPeering>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let vpc_router = nexus
-            .vpc_router_fetch(
+        let peering = nexus
+            .vpc_peering_fetch(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
+                &path.peering_name,
             )
             .await?;
-        Ok(HttpResponseOk(vpc_router.into()))
+        Ok(HttpResponseOk(peering.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Get a router by id
+/// Get a peering by id
 #[endpoint {
     method = GET,
-    path = "/by-id/vpc-routers/{id}",
+    path = "/by-id/vpc-peerings/{id}",
     tags = ["vpcs"],
 }]
-async fn vpc_router_view_by_id(
+async fn vpc_peering_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<VpcRouter>, HttpError> {
+) -> Result<HttpResponseOk<views::VpcPeering>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let router = nexus.vpc_router_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(router.into()))
+        let peering = nexus.vpc_peering_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(peering.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a router
+/// Peer a VPC with another VPC in the same silo
+///
+/// Peering is not implemented yet: once the request has been checked, this
+/// fails with 501 Not Implemented.
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/peerings",
     tags = ["vpcs"],
 }]
-async fn vpc_router_create(
+async fn vpc_peering_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<VpcPathParam>,
-    create_params: TypedBody<params::VpcRouterCreate>,
-) -> Result<HttpResponseCreated<VpcRouter>, HttpError> {
+    create_params: TypedBody<params::VpcPeeringCreate>,
+) -> Result<HttpResponseCreated<views::VpcPeering>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let router = nexus
-            .vpc_create_router(
+        nexus
+            .vpc_create_peering_check(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &db::model::VpcRouterKind::Custom,
                 &create_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseCreated(router.into()))
-    };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Delete a router
+        // Unlike the stubs built with `Nexus::unimplemented_todo()`, this
+        // endpoint is available to everyone who could create a peering, so
+        // tell them plainly that it doesn't work yet rather than reporting a
+        // server error.
+        Err(HttpError {
+            status_code: http::StatusCode::NOT_IMPLEMENTED,
+            error_code: None,
+            external_message: String::from(
+                "VPC peering is not implemented yet",
+            ),
+            internal_message: String::from(
+                "sleds cannot forward traffic between peered VPCs",
+            ),
+        })
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Delete a peering
 #[endpoint {
     method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/peerings/{peering_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_router_delete(
+async fn vpc_peering_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcRouterPathParam>,
+    path_params: Path<VpcPeeringPathParam>,
 ) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
@@ -3809,248 +5792,210 @@ async fn vpc_router_delete(
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
         nexus
-            .vpc_delete_router(
+            .vpc_delete_peering(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
+                &path.peering_name,
             )
             .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Update a router
-#[endpoint {
-    method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}",
-    tags = ["vpcs"],
-}]
-async fn vpc_router_update(
-    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcRouterPathParam>,
-    router_params: TypedBody<params::VpcRouterUpdate>,
-) -> Result<HttpResponseOk<VpcRouter>, HttpError> {
-    let apictx = rqctx.context();
-    let nexus = &apictx.nexus;
-    let path = path_params.into_inner();
-    let handler = async {
-        let opctx = OpContext::for_external_api(&rqctx).await?;
-        let router = nexus
-            .vpc_update_router(
-                &opctx,
-                &path.organization_name,
-                &path.project_name,
-                &path.vpc_name,
-                &path.router_name,
-                &router_params.into_inner(),
-            )
-            .await?;
-        Ok(HttpResponseOk(router.into()))
-    };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-// Vpc Router Routes
+// Internet Gateways
 
-/// List routes
-///
-/// List the routes associated with a router in a particular VPC.
+/// List internet gateways
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/internet-gateways",
     tags = ["vpcs"],
 }]
-async fn vpc_router_route_list(
+async fn internet_gateway_list(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     query_params: Query<PaginatedByName>,
-    path_params: Path<VpcRouterPathParam>,
-) -> Result<HttpResponseOk<ResultsPage<RouterRoute>>, HttpError> {
+    path_params: Path<VpcPathParam>,
+) -> Result<HttpResponseOk<ResultsPage<views::InternetGateway>>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let query = query_params.into_inner();
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let routes = nexus
-            .router_list_routes(
+        let gateways = nexus
+            .vpc_list_internet_gateways(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
                 &data_page_params_for(&rqctx, &query)?
                     .map_name(|n| Name::ref_cast(n)),
             )
             .await?
             .into_iter()
-            .map(|route| route.into())
+            .map(|g| g.into())
             .collect();
         Ok(HttpResponseOk(ScanByName::results_page(
             &query,
-            routes,
+            gateways,
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Path parameters for Router Route requests
+/// Path parameters for Internet Gateway requests
 #[derive(Deserialize, JsonSchema)]
-struct RouterRoutePathParam {
+struct InternetGatewayPathParam {
     organization_name: Name,
     project_name: Name,
     vpc_name: Name,
-    router_name: Name,
-    route_name: Name,
+    gateway_name: Name,
 }
 
-/// Fetch a route
+/// Get an internet gateway
 #[endpoint {
     method = GET,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes/{route_name}",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/internet-gateways/{gateway_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_router_route_view(
+async fn internet_gateway_view(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<RouterRoutePathParam>,
-) -> Result<HttpResponseOk<RouterRoute>, HttpError> {
+    path_params: Path<InternetGatewayPathParam>,
+) -> Result<HttpResponseOk<views::InternetGateway>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let route = nexus
-            .route_fetch(
+        let gateway = nexus
+            .internet_gateway_fetch(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
-                &path.route_name,
+                &path.gateway_name,
             )
             .await?;
-        Ok(HttpResponseOk(route.into()))
+        Ok(HttpResponseOk(gateway.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Fetch a route by id
+/// Get an internet gateway by id
 #[endpoint {
     method = GET,
-    path = "/by-id/vpc-router-routes/{id}",
-    tags = ["vpcs"]
+    path = "/by-id/internet-gateways/{id}",
+    tags = ["vpcs"],
 }]
-async fn vpc_router_route_view_by_id(
+async fn internet_gateway_view_by_id(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
     path_params: Path<ByIdPathParams>,
-) -> Result<HttpResponseOk<RouterRoute>, HttpError> {
+) -> Result<HttpResponseOk<views::InternetGateway>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let id = &path.id;
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let route = nexus.route_fetch_by_id(&opctx, id).await?;
-        Ok(HttpResponseOk(route.into()))
+        let gateway = nexus.internet_gateway_fetch_by_id(&opctx, id).await?;
+        Ok(HttpResponseOk(gateway.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Create a router
+/// Create an internet gateway
 #[endpoint {
     method = POST,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes",
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/internet-gateways",
     tags = ["vpcs"],
 }]
-async fn vpc_router_route_create(
+async fn internet_gateway_create(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<VpcRouterPathParam>,
-    create_params: TypedBody<RouterRouteCreateParams>,
-) -> Result<HttpResponseCreated<RouterRoute>, HttpError> {
+    path_params: Path<VpcPathParam>,
+    create_params: TypedBody<params::InternetGatewayCreate>,
+) -> Result<HttpResponseCreated<views::InternetGateway>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let route = nexus
-            .router_create_route(
+        let gateway = nexus
+            .vpc_create_internet_gateway(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
-                &RouterRouteKind::Custom,
                 &create_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseCreated(route.into()))
+        Ok(HttpResponseCreated(gateway.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Delete a route
+/// Update an internet gateway
 #[endpoint {
-    method = DELETE,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes/{route_name}",
+    method = PUT,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/internet-gateways/{gateway_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_router_route_delete(
+async fn internet_gateway_update(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<RouterRoutePathParam>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    path_params: Path<InternetGatewayPathParam>,
+    update_params: TypedBody<params::InternetGatewayUpdate>,
+) -> Result<HttpResponseOk<views::InternetGateway>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        nexus
-            .router_delete_route(
+        let gateway = nexus
+            .vpc_update_internet_gateway(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
-                &path.route_name,
+                &path.gateway_name,
+                &update_params.into_inner(),
             )
             .await?;
-        Ok(HttpResponseDeleted())
+        Ok(HttpResponseOk(gateway.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
-/// Update a route
+/// Delete an internet gateway
+///
+/// A gateway can't be deleted while any route in its VPC targets it.
 #[endpoint {
-    method = PUT,
-    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/routers/{router_name}/routes/{route_name}",
+    method = DELETE,
+    path = "/organizations/{organization_name}/projects/{project_name}/vpcs/{vpc_name}/internet-gateways/{gateway_name}",
     tags = ["vpcs"],
 }]
-async fn vpc_router_route_update(
+async fn internet_gateway_delete(
     rqctx: Arc<RequestContext<Arc<ServerContext>>>,
-    path_params: Path<RouterRoutePathParam>,
-    router_params: TypedBody<RouterRouteUpdateParams>,
-) -> Result<HttpResponseOk<RouterRoute>, HttpError> {
+    path_params: Path<InternetGatewayPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let handler = async {
         let opctx = OpContext::for_external_api(&rqctx).await?;
-        let router_route = nexus
-            .router_update_route(
+        nexus
+            .vpc_delete_internet_gateway(
                 &opctx,
                 &path.organization_name,
                 &path.project_name,
                 &path.vpc_name,
-                &path.router_name,
-                &path.route_name,
-                &router_params.into_inner(),
+                &path.gateway_name,
             )
             .await?;
-        Ok(HttpResponseOk(router_route.into()))
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Racks
@@ -4082,7 +6027,7 @@ async fn rack_list(
             &|_, rack: &Rack| rack.identity.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for Rack requests
@@ -4110,7 +6055,7 @@ async fn rack_view(
         let rack_info = nexus.rack_lookup(&opctx, &path.rack_id).await?;
         Ok(HttpResponseOk(rack_info.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Sleds
@@ -4142,7 +6087,7 @@ async fn sled_list(
             &|_, sled: &Sled| sled.identity.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for Sled requests
@@ -4170,7 +6115,7 @@ async fn sled_view(
         let sled_info = nexus.sled_lookup(&opctx, &path.sled_id).await?;
         Ok(HttpResponseOk(sled_info.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Updates
@@ -4191,7 +6136,7 @@ async fn updates_refresh(
         nexus.updates_refresh_metadata(&opctx).await?;
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Sagas
@@ -4220,7 +6165,7 @@ async fn saga_list(
             &|_, saga: &Saga| saga.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for Saga requests
@@ -4247,7 +6192,84 @@ async fn saga_view(
         let saga = nexus.saga_get(&opctx, path.saga_id).await?;
         Ok(HttpResponseOk(saga))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Fetch a saga's log
+///
+/// The log includes every event recorded for the saga's nodes, along with any
+/// operator interventions.
+#[endpoint {
+    method = GET,
+    path = "/system/sagas/{saga_id}/log",
+    tags = ["system"],
+}]
+async fn saga_log_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SagaPathParam>,
+) -> Result<HttpResponseOk<views::SagaLog>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let log = nexus.saga_log_view(&opctx, path.saga_id).await?;
+        Ok(HttpResponseOk(log))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Abandon a saga
+///
+/// An abandoned saga will not be resumed by any Nexus.  This does not stop a
+/// saga that is currently executing, but its further progress is not recorded.
+#[endpoint {
+    method = POST,
+    path = "/system/sagas/{saga_id}/abandon",
+    tags = ["system"],
+}]
+async fn saga_abandon(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SagaPathParam>,
+    intervene_params: TypedBody<params::SagaIntervene>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let params = intervene_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus.saga_abandon(&opctx, path.saga_id, &params).await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Retry a saga
+///
+/// The saga is adopted by the Nexus serving this request and resumed from its
+/// log.  The saga must not be done, and must not be currently executing in
+/// this Nexus.
+#[endpoint {
+    method = POST,
+    path = "/system/sagas/{saga_id}/retry",
+    tags = ["system"],
+}]
+async fn saga_retry(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SagaPathParam>,
+    intervene_params: TypedBody<params::SagaIntervene>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let params = intervene_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        nexus.saga_retry(&opctx, path.saga_id, &params).await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Silo users
@@ -4280,7 +6302,7 @@ async fn user_list(
             &|_, user: &User| user.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Silo groups
@@ -4313,7 +6335,7 @@ async fn group_list(
             &|_, group: &Group| group.id,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Built-in (system) users
@@ -4347,7 +6369,7 @@ async fn system_user_list(
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for global (system) user requests
@@ -4376,7 +6398,7 @@ async fn system_user_view(
         let user = nexus.user_builtin_fetch(&opctx, &user_name).await?;
         Ok(HttpResponseOk(user.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// List timeseries schema
@@ -4399,7 +6421,7 @@ async fn timeseries_schema_get(
         let list = nexus.timeseries_schema_list(&opctx, &query, limit).await?;
         Ok(HttpResponseOk(list))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Built-in roles
@@ -4455,7 +6477,7 @@ async fn role_list(
             |role: &Role, _| RolePage { last_seen: role.name.to_string() },
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for global (system) role requests
@@ -4484,7 +6506,7 @@ async fn role_view(
         let role = nexus.role_builtin_fetch(&opctx, &role_name).await?;
         Ok(HttpResponseOk(role.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 // Per-user SSH public keys
@@ -4524,7 +6546,7 @@ async fn session_sshkey_list(
             &marker_for_name,
         )?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Create an SSH public key
@@ -4552,7 +6574,7 @@ async fn session_sshkey_create(
             .await?;
         Ok(HttpResponseCreated(ssh_key.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for SSH key requests by name
@@ -4587,7 +6609,7 @@ async fn session_sshkey_view(
             nexus.ssh_key_fetch(&opctx, actor.actor_id(), ssh_key_name).await?;
         Ok(HttpResponseOk(ssh_key.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Delete an SSH public key
@@ -4615,7 +6637,294 @@ async fn session_sshkey_delete(
         nexus.ssh_key_delete(&opctx, actor.actor_id(), ssh_key_name).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+// Per-user API tokens
+
+/// List API tokens
+///
+/// Lists API tokens for the currently authenticated user.
+#[endpoint {
+    method = GET,
+    path = "/session/me/api-tokens",
+    tags = ["session"],
+}]
+async fn session_api_token_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedByName>,
+) -> Result<HttpResponseOk<ResultsPage<views::ApiToken>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("listing current user's api tokens")?;
+        let page_params =
+            data_page_params_for(&rqctx, &query)?.map_name(Name::ref_cast);
+        let api_tokens = nexus
+            .api_tokens_list(&opctx, actor.actor_id(), &page_params)
+            .await?
+            .into_iter()
+            .map(views::ApiToken::from)
+            .collect::<Vec<views::ApiToken>>();
+        Ok(HttpResponseOk(ScanByName::results_page(
+            &query,
+            api_tokens,
+            &marker_for_name,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Create an API token
+///
+/// Create an API token for the currently authenticated user.  The token
+/// itself is only revealed in the response to this request.
+#[endpoint {
+    method = POST,
+    path = "/session/me/api-tokens",
+    tags = ["session"],
+}]
+async fn session_api_token_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    new_token: TypedBody<params::ApiTokenCreate>,
+) -> Result<HttpResponseCreated<views::ApiTokenValue>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("creating api token for current user")?;
+        let api_token = nexus
+            .api_token_create(&opctx, actor.actor_id(), new_token.into_inner())
+            .await?;
+        Ok(HttpResponseCreated(api_token))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for API token requests by name
+#[derive(Deserialize, JsonSchema)]
+struct ApiTokenPathParams {
+    api_token_name: Name,
+}
+
+/// Fetch an API token
+///
+/// Fetch an API token belonging to the currently authenticated user.
+#[endpoint {
+    method = GET,
+    path = "/session/me/api-tokens/{api_token_name}",
+    tags = ["session"],
+}]
+async fn session_api_token_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ApiTokenPathParams>,
+) -> Result<HttpResponseOk<views::ApiToken>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let api_token_name = &path.api_token_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("fetching one of current user's api tokens")?;
+        let api_token = nexus
+            .api_token_fetch(&opctx, actor.actor_id(), api_token_name)
+            .await?;
+        Ok(HttpResponseOk(api_token.into()))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Delete an API token
+///
+/// Revoke an API token belonging to the currently authenticated user.
+#[endpoint {
+    method = DELETE,
+    path = "/session/me/api-tokens/{api_token_name}",
+    tags = ["session"],
+}]
+async fn session_api_token_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ApiTokenPathParams>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let api_token_name = &path.api_token_name;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("deleting one of current user's api tokens")?;
+        nexus
+            .api_token_delete(&opctx, actor.actor_id(), api_token_name)
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// List device access tokens
+///
+/// List the unexpired device access tokens granted to the currently
+/// authenticated user, e.g. by logging in with the CLI.
+#[endpoint {
+    method = GET,
+    path = "/session/me/tokens",
+    tags = ["session"],
+}]
+async fn session_device_token_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<views::DeviceAccessToken>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let pagparams = data_page_params_for(&rqctx, &query)?;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("listing current user's device tokens")?;
+        let tokens = nexus
+            .device_access_tokens_list(&opctx, actor.actor_id(), &pagparams)
+            .await?
+            .into_iter()
+            .map(views::DeviceAccessToken::from)
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            tokens,
+            &|_, token: &views::DeviceAccessToken| token.id,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for device access token requests
+#[derive(Deserialize, JsonSchema)]
+struct DeviceTokenPathParams {
+    token_id: Uuid,
+}
+
+/// Revoke a device access token
+///
+/// Revoke a device access token granted to the currently authenticated user.
+#[endpoint {
+    method = DELETE,
+    path = "/session/me/tokens/{token_id}",
+    tags = ["session"],
+}]
+async fn session_device_token_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<DeviceTokenPathParams>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let token_id = path_params.into_inner().token_id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("deleting one of current user's device tokens")?;
+        nexus
+            .device_access_token_delete(&opctx, actor.actor_id(), token_id)
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// List console sessions
+///
+/// List the unexpired web console sessions of the currently authenticated
+/// user.
+#[endpoint {
+    method = GET,
+    path = "/session/me/sessions",
+    tags = ["session"],
+}]
+async fn session_console_session_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<views::ConsoleSession>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let pagparams = data_page_params_for(&rqctx, &query)?;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("listing current user's sessions")?;
+        let (last_used_after, created_after) = session_expiry_cutoffs(apictx);
+        let sessions = nexus
+            .sessions_list(
+                &opctx,
+                actor.actor_id(),
+                last_used_after,
+                created_after,
+                &pagparams,
+            )
+            .await?
+            .into_iter()
+            .map(views::ConsoleSession::from)
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            sessions,
+            &|_, session: &views::ConsoleSession| session.id,
+        )?))
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
+}
+
+/// Path parameters for console session requests
+#[derive(Deserialize, JsonSchema)]
+struct SessionPathParams {
+    session_id: Uuid,
+}
+
+/// Revoke a console session
+///
+/// Revoke one of the web console sessions of the currently authenticated user,
+/// logging out whoever is using it.
+#[endpoint {
+    method = DELETE,
+    path = "/session/me/sessions/{session_id}",
+    tags = ["session"],
+}]
+async fn session_console_session_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<SessionPathParams>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let session_id = path_params.into_inner().session_id;
+    let handler = async {
+        let opctx = OpContext::for_external_api(&rqctx).await?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("deleting one of current user's sessions")?;
+        nexus.session_delete(&opctx, actor.actor_id(), session_id).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_external_handler(&rqctx, handler).await
 }
 
 /// Path parameters for metrics requests where `/metrics/{metric_name}` is
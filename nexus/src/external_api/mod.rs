@@ -5,6 +5,7 @@
 pub mod console_api;
 pub mod device_auth;
 pub mod http_entrypoints;
+pub mod scim;
 
 pub use nexus_types::external_api::params;
 pub use nexus_types::external_api::shared;
@@ -0,0 +1,904 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Entrypoints for SCIM 2.0 (RFC 7643, RFC 7644) provisioning of Silo users
+//! and groups
+//!
+//! These are endpoints used by a Silo's identity provider, not by its users.
+//! The identity provider authenticates with a bearer token created through the
+//! system API.  SCIM has its own request and response bodies (including for
+//! errors) and content type, so these endpoints build their own responses.
+
+use crate::context::OpContext;
+use crate::db;
+use crate::db::identity::Asset;
+use crate::db::model::Name;
+use crate::ServerContext;
+use dropshot::{endpoint, HttpError, Path, Query, RequestContext, UntypedBody};
+use http::{header, Response, StatusCode};
+use hyper::Body;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+const SCIM_SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const SCIM_SCHEMA_GROUP: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const SCIM_SCHEMA_LIST_RESPONSE: &str =
+    "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const SCIM_SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+/// Default number of resources returned by a list request
+const SCIM_LIST_DEFAULT_COUNT: u32 = 100;
+/// Maximum number of resources returned by a list request
+const SCIM_LIST_MAX_COUNT: u32 = 1000;
+
+fn build_scim_response<T>(
+    status: StatusCode,
+    body: &T,
+) -> Result<Response<Body>, HttpError>
+where
+    T: ?Sized + Serialize,
+{
+    let body = serde_json::to_string(body)
+        .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+    Ok(Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, SCIM_CONTENT_TYPE)
+        .body(body.into())?)
+}
+
+/// SCIM clients expect errors in SCIM's format (RFC 7644 §3.12), not
+/// Dropshot's.
+fn scim_result(
+    result: Result<Response<Body>, HttpError>,
+) -> Result<Response<Body>, HttpError> {
+    let error = match result {
+        Ok(response) => return Ok(response),
+        Err(error) => error,
+    };
+    let scim_type = match error.status_code {
+        StatusCode::CONFLICT => Some("uniqueness"),
+        StatusCode::BAD_REQUEST => Some("invalidValue"),
+        _ => None,
+    };
+    let mut response = build_scim_response(
+        error.status_code,
+        &ScimError {
+            schemas: vec![SCIM_SCHEMA_ERROR],
+            status: error.status_code.as_u16().to_string(),
+            scim_type,
+            detail: error.external_message,
+        },
+    )?;
+    if error.status_code == StatusCode::UNAUTHORIZED {
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            header::HeaderValue::from_static("Bearer"),
+        );
+    }
+    Ok(response)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimError {
+    schemas: Vec<&'static str>,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scim_type: Option<&'static str>,
+    detail: String,
+}
+
+/// Authenticate the SCIM client making this request
+///
+/// Returns the authorization context for the rest of the request, which is
+/// that of the external authenticator: the bearer token grants its holder
+/// control over the Silo's users and groups, and nothing else.
+async fn scim_authenticate<'a>(
+    rqctx: &'a RequestContext<Arc<ServerContext>>,
+    silo_name: &Name,
+) -> Result<(&'a OpContext, crate::authz::Silo), HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let bearer_token = {
+        let request = rqctx.request.lock().await;
+        request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string())
+    };
+    let opctx = nexus.opctx_external_authn();
+    let authz_silo =
+        nexus.scim_authenticate(opctx, silo_name, bearer_token).await?;
+
+    // SCIM clients aren't users, so there's no actor to record in the audit
+    // log.  Record the Silo that they're provisioning instead.
+    if let Some(audit) = rqctx.context().audit_details(&rqctx.request_id) {
+        let mut details = audit.lock().unwrap();
+        details.actor_silo_id = Some(authz_silo.id());
+        details.resource_ids.push(authz_silo.id());
+    }
+    Ok((opctx, authz_silo))
+}
+
+/// Parse a request body
+///
+/// SCIM clients send `application/scim+json`, which Dropshot doesn't accept
+/// for typed bodies.
+fn scim_parse_body<T: DeserializeOwned>(
+    body: UntypedBody,
+) -> Result<T, HttpError> {
+    serde_json::from_slice(body.as_bytes()).map_err(|e| {
+        HttpError::for_bad_request(
+            None,
+            format!("unable to parse request body: {}", e),
+        )
+    })
+}
+
+// Resources
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimMeta {
+    resource_type: &'static str,
+    created: chrono::DateTime<chrono::Utc>,
+    last_modified: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimUser {
+    schemas: Vec<&'static str>,
+    id: Uuid,
+    user_name: String,
+    active: bool,
+    meta: ScimMeta,
+}
+
+impl From<db::model::SiloUser> for ScimUser {
+    fn from(user: db::model::SiloUser) -> Self {
+        Self {
+            schemas: vec![SCIM_SCHEMA_USER],
+            id: user.id(),
+            meta: ScimMeta {
+                resource_type: "User",
+                created: user.time_created(),
+                last_modified: user.time_modified(),
+            },
+            user_name: user.external_id,
+            active: user.active,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct ScimMember {
+    value: Uuid,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimGroup {
+    schemas: Vec<&'static str>,
+    id: Uuid,
+    display_name: String,
+    members: Vec<ScimMember>,
+    meta: ScimMeta,
+}
+
+impl From<(db::model::SiloGroup, Vec<Uuid>)> for ScimGroup {
+    fn from((group, members): (db::model::SiloGroup, Vec<Uuid>)) -> Self {
+        Self {
+            schemas: vec![SCIM_SCHEMA_GROUP],
+            id: group.id(),
+            meta: ScimMeta {
+                resource_type: "Group",
+                created: group.time_created(),
+                last_modified: group.time_modified(),
+            },
+            display_name: group.external_id,
+            members: members
+                .into_iter()
+                .map(|value| ScimMember { value })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimListResponse<T> {
+    schemas: Vec<&'static str>,
+    total_results: i64,
+    start_index: u32,
+    items_per_page: usize,
+    #[serde(rename = "Resources")]
+    resources: Vec<T>,
+}
+
+// Requests
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimUserRequest {
+    user_name: String,
+    #[serde(default = "scim_default_active")]
+    active: bool,
+}
+
+fn scim_default_active() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimGroupRequest {
+    display_name: String,
+    #[serde(default)]
+    members: Vec<ScimMember>,
+}
+
+#[derive(Deserialize)]
+struct ScimPatchRequest {
+    #[serde(rename = "Operations")]
+    operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Deserialize)]
+struct ScimPatchOperation {
+    op: String,
+    path: Option<String>,
+    value: Option<serde_json::Value>,
+}
+
+/// Parsed SCIM PATCH operation
+///
+/// Some identity providers capitalize the operation names, so they're matched
+/// without regard to case.
+enum ScimPatchOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+impl ScimPatchOperation {
+    fn op(&self) -> Result<ScimPatchOp, HttpError> {
+        match self.op.to_ascii_lowercase().as_str() {
+            "add" => Ok(ScimPatchOp::Add),
+            "remove" => Ok(ScimPatchOp::Remove),
+            "replace" => Ok(ScimPatchOp::Replace),
+            _ => Err(scim_bad_request(format!(
+                "unsupported PATCH operation: {:?}",
+                self.op
+            ))),
+        }
+    }
+
+    /// Returns the operation's path, if any, in lower case, since SCIM
+    /// attribute names aren't case-sensitive
+    fn path(&self) -> Option<String> {
+        self.path.as_ref().map(|path| path.to_ascii_lowercase())
+    }
+
+    fn value_required(&self) -> Result<&serde_json::Value, HttpError> {
+        self.value.as_ref().ok_or_else(|| {
+            scim_bad_request(format!(
+                "PATCH operation {:?} requires a value",
+                self.op
+            ))
+        })
+    }
+}
+
+fn scim_bad_request(message: String) -> HttpError {
+    HttpError::for_bad_request(None, message)
+}
+
+fn scim_value_string(value: &serde_json::Value) -> Result<String, HttpError> {
+    value
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| scim_bad_request(format!("expected string: {}", value)))
+}
+
+/// Some identity providers send booleans as strings
+fn scim_value_bool(value: &serde_json::Value) -> Result<bool, HttpError> {
+    match value {
+        serde_json::Value::Bool(b) => Ok(*b),
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("true") => {
+            Ok(true)
+        }
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("false") => {
+            Ok(false)
+        }
+        _ => Err(scim_bad_request(format!("expected boolean: {}", value))),
+    }
+}
+
+fn scim_value_members(
+    value: &serde_json::Value,
+) -> Result<Vec<Uuid>, HttpError> {
+    let members: Vec<ScimMember> = serde_json::from_value(value.clone())
+        .map_err(|e| scim_bad_request(format!("invalid members: {}", e)))?;
+    Ok(members.into_iter().map(|m| m.value).collect())
+}
+
+/// Query parameters for SCIM list requests (RFC 7644 §3.4.2)
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimListQuery {
+    /// Filter, which must be of the form `userName eq "..."` for users or
+    /// `displayName eq "..."` for groups
+    filter: Option<String>,
+    /// 1-based index of the first result to return
+    #[serde(rename = "startIndex")]
+    start_index: Option<u32>,
+    /// Maximum number of results to return
+    count: Option<u32>,
+}
+
+impl ScimListQuery {
+    /// Returns the value of the filter on `attribute`, if any
+    ///
+    /// Only equality filters on the attribute that identifies the resource are
+    /// supported, since those are what identity providers use to find out
+    /// whether a resource already exists.
+    fn filter_value(
+        &self,
+        attribute: &str,
+    ) -> Result<Option<String>, HttpError> {
+        let filter = match &self.filter {
+            Some(filter) => filter.trim(),
+            None => return Ok(None),
+        };
+        let invalid_filter =
+            || scim_bad_request(format!("unsupported filter: {:?}", filter));
+        let mut parts = filter.splitn(3, ' ');
+        let (name, op, value) = match (parts.next(), parts.next(), parts.next())
+        {
+            (Some(name), Some(op), Some(value)) => (name, op, value.trim()),
+            _ => return Err(invalid_filter()),
+        };
+        if !name.eq_ignore_ascii_case(attribute)
+            || !op.eq_ignore_ascii_case("eq")
+        {
+            return Err(invalid_filter());
+        }
+        let value: String =
+            serde_json::from_str(value).map_err(|_| invalid_filter())?;
+        Ok(Some(value))
+    }
+
+    /// Returns the 1-based start index, the offset, and the limit to list with
+    fn page(&self) -> (u32, i64, i64) {
+        let start_index = self.start_index.unwrap_or(1).max(1);
+        let count = self
+            .count
+            .unwrap_or(SCIM_LIST_DEFAULT_COUNT)
+            .min(SCIM_LIST_MAX_COUNT);
+        (start_index, i64::from(start_index - 1), i64::from(count))
+    }
+}
+
+/// Path parameters for SCIM requests
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimSiloPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+}
+
+/// Path parameters for SCIM user requests
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimUserPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The user's internal id
+    user_id: Uuid,
+}
+
+/// Path parameters for SCIM group requests
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimGroupPathParam {
+    /// The silo's unique name.
+    silo_name: Name,
+    /// The group's internal id
+    group_id: Uuid,
+}
+
+// Users
+
+/// List users through SCIM
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/{silo_name}/Users",
+    tags = ["scim"],
+}]
+pub async fn scim_user_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimSiloPathParam>,
+    query_params: Query<ScimListQuery>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let query = query_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let user_name = query.filter_value("userName")?;
+        let (start_index, offset, limit) = query.page();
+        let (total_results, users) = nexus
+            .scim_user_list(opctx, &authz_silo, user_name, offset, limit)
+            .await?;
+        let resources: Vec<ScimUser> =
+            users.into_iter().map(|u| u.into()).collect();
+        build_scim_response(
+            StatusCode::OK,
+            &ScimListResponse {
+                schemas: vec![SCIM_SCHEMA_LIST_RESPONSE],
+                total_results,
+                start_index,
+                items_per_page: resources.len(),
+                resources,
+            },
+        )
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Create a user through SCIM
+#[endpoint {
+    method = POST,
+    path = "/scim/v2/{silo_name}/Users",
+    tags = ["scim"],
+}]
+pub async fn scim_user_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimSiloPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let request: ScimUserRequest = scim_parse_body(body)?;
+        let user = nexus
+            .scim_user_create(
+                opctx,
+                &authz_silo,
+                request.user_name,
+                request.active,
+            )
+            .await?;
+        build_scim_response(StatusCode::CREATED, &ScimUser::from(user))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Fetch a user through SCIM
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/{silo_name}/Users/{user_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_user_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimUserPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let user = nexus
+            .scim_user_fetch(opctx, &authz_silo, path_params.user_id)
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimUser::from(user))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Replace a user through SCIM
+///
+/// Deactivating a user (setting `active` to false) prevents them from logging
+/// in and ends their existing sessions.
+#[endpoint {
+    method = PUT,
+    path = "/scim/v2/{silo_name}/Users/{user_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_user_replace(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimUserPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let request: ScimUserRequest = scim_parse_body(body)?;
+        let user = nexus
+            .scim_user_update(
+                opctx,
+                &authz_silo,
+                path_params.user_id,
+                Some(request.user_name),
+                Some(request.active),
+            )
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimUser::from(user))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Update a user through SCIM
+///
+/// Only `userName` and `active` may be modified.
+#[endpoint {
+    method = PATCH,
+    path = "/scim/v2/{silo_name}/Users/{user_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_user_patch(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimUserPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let request: ScimPatchRequest = scim_parse_body(body)?;
+
+        let mut user_name = None;
+        let mut active = None;
+        for operation in &request.operations {
+            if let ScimPatchOp::Remove = operation.op()? {
+                return Err(scim_bad_request(String::from(
+                    "user attributes cannot be removed",
+                )));
+            }
+            let value = operation.value_required()?;
+            match operation.path().as_deref() {
+                Some("username") => user_name = Some(scim_value_string(value)?),
+                Some("active") => active = Some(scim_value_bool(value)?),
+                Some(path) => {
+                    return Err(scim_bad_request(format!(
+                        "unsupported PATCH path: {:?}",
+                        path
+                    )))
+                }
+                // Without a path, the value contains the attributes to set.
+                None => {
+                    let attributes = value.as_object().ok_or_else(|| {
+                        scim_bad_request(format!("expected object: {}", value))
+                    })?;
+                    for (name, value) in attributes {
+                        match name.to_ascii_lowercase().as_str() {
+                            "username" => {
+                                user_name = Some(scim_value_string(value)?)
+                            }
+                            "active" => active = Some(scim_value_bool(value)?),
+                            _ => {
+                                return Err(scim_bad_request(format!(
+                                    "unsupported attribute: {:?}",
+                                    name
+                                )))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let user = nexus
+            .scim_user_update(
+                opctx,
+                &authz_silo,
+                path_params.user_id,
+                user_name,
+                active,
+            )
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimUser::from(user))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Delete a user through SCIM
+#[endpoint {
+    method = DELETE,
+    path = "/scim/v2/{silo_name}/Users/{user_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_user_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimUserPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        nexus.scim_user_delete(opctx, &authz_silo, path_params.user_id).await?;
+        Ok(Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())?)
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+// Groups
+
+/// List groups through SCIM
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/{silo_name}/Groups",
+    tags = ["scim"],
+}]
+pub async fn scim_group_list(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimSiloPathParam>,
+    query_params: Query<ScimListQuery>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let query = query_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let display_name = query.filter_value("displayName")?;
+        let (start_index, offset, limit) = query.page();
+        let (total_results, groups) = nexus
+            .scim_group_list(opctx, &authz_silo, display_name, offset, limit)
+            .await?;
+        let resources: Vec<ScimGroup> =
+            groups.into_iter().map(|g| g.into()).collect();
+        build_scim_response(
+            StatusCode::OK,
+            &ScimListResponse {
+                schemas: vec![SCIM_SCHEMA_LIST_RESPONSE],
+                total_results,
+                start_index,
+                items_per_page: resources.len(),
+                resources,
+            },
+        )
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Create a group through SCIM
+#[endpoint {
+    method = POST,
+    path = "/scim/v2/{silo_name}/Groups",
+    tags = ["scim"],
+}]
+pub async fn scim_group_create(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimSiloPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let request: ScimGroupRequest = scim_parse_body(body)?;
+        let group = nexus
+            .scim_group_create(
+                opctx,
+                &authz_silo,
+                request.display_name,
+                request.members.into_iter().map(|m| m.value).collect(),
+            )
+            .await?;
+        build_scim_response(StatusCode::CREATED, &ScimGroup::from(group))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Fetch a group through SCIM
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/{silo_name}/Groups/{group_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_group_view(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimGroupPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let group = nexus
+            .scim_group_fetch(opctx, &authz_silo, path_params.group_id)
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimGroup::from(group))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Replace a group through SCIM
+#[endpoint {
+    method = PUT,
+    path = "/scim/v2/{silo_name}/Groups/{group_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_group_replace(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimGroupPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let request: ScimGroupRequest = scim_parse_body(body)?;
+        let group = nexus
+            .scim_group_update(
+                opctx,
+                &authz_silo,
+                path_params.group_id,
+                Some(request.display_name),
+                Some(request.members.into_iter().map(|m| m.value).collect()),
+            )
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimGroup::from(group))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Update a group through SCIM
+///
+/// Only `displayName` and `members` may be modified.  Members may be added,
+/// removed (including with a path like `members[value eq "..."]`), or
+/// replaced.
+#[endpoint {
+    method = PATCH,
+    path = "/scim/v2/{silo_name}/Groups/{group_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_group_patch(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimGroupPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        let request: ScimPatchRequest = scim_parse_body(body)?;
+
+        // Apply the operations to the group as it is now, then store the
+        // result.
+        let (group, mut members) = nexus
+            .scim_group_fetch(opctx, &authz_silo, path_params.group_id)
+            .await?;
+        let mut display_name = group.external_id;
+        for operation in &request.operations {
+            let op = operation.op()?;
+            let path = operation.path();
+            match (op, path.as_deref()) {
+                (ScimPatchOp::Add, Some("members")) => {
+                    for member in
+                        scim_value_members(operation.value_required()?)?
+                    {
+                        if !members.contains(&member) {
+                            members.push(member);
+                        }
+                    }
+                }
+                (ScimPatchOp::Remove, Some("members")) => {
+                    match &operation.value {
+                        Some(value) => {
+                            let removed = scim_value_members(value)?;
+                            members.retain(|m| !removed.contains(m));
+                        }
+                        None => members.clear(),
+                    }
+                }
+                (ScimPatchOp::Remove, Some(path))
+                    if path.starts_with("members[") && path.ends_with(']') =>
+                {
+                    let filter = ScimListQuery {
+                        filter: Some(
+                            path["members[".len()..path.len() - 1].to_string(),
+                        ),
+                        start_index: None,
+                        count: None,
+                    };
+                    let removed = filter
+                        .filter_value("value")?
+                        .and_then(|value| value.parse::<Uuid>().ok());
+                    members.retain(|m| Some(*m) != removed);
+                }
+                (ScimPatchOp::Replace, Some("members")) => {
+                    members = scim_value_members(operation.value_required()?)?;
+                }
+                (
+                    ScimPatchOp::Add | ScimPatchOp::Replace,
+                    Some("displayname"),
+                ) => {
+                    display_name =
+                        scim_value_string(operation.value_required()?)?;
+                }
+                // Without a path, the value contains the attributes to set.
+                (ScimPatchOp::Add | ScimPatchOp::Replace, None) => {
+                    let value = operation.value_required()?;
+                    let attributes = value.as_object().ok_or_else(|| {
+                        scim_bad_request(format!("expected object: {}", value))
+                    })?;
+                    for (name, value) in attributes {
+                        match name.to_ascii_lowercase().as_str() {
+                            "displayname" => {
+                                display_name = scim_value_string(value)?
+                            }
+                            "members" => members = scim_value_members(value)?,
+                            _ => {
+                                return Err(scim_bad_request(format!(
+                                    "unsupported attribute: {:?}",
+                                    name
+                                )))
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return Err(scim_bad_request(format!(
+                        "unsupported PATCH operation {:?} on path {:?}",
+                        operation.op, operation.path
+                    )))
+                }
+            }
+        }
+
+        let group = nexus
+            .scim_group_update(
+                opctx,
+                &authz_silo,
+                path_params.group_id,
+                Some(display_name),
+                Some(members),
+            )
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimGroup::from(group))
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
+
+/// Delete a group through SCIM
+#[endpoint {
+    method = DELETE,
+    path = "/scim/v2/{silo_name}/Groups/{group_id}",
+    tags = ["scim"],
+}]
+pub async fn scim_group_delete(
+    rqctx: Arc<RequestContext<Arc<ServerContext>>>,
+    path_params: Path<ScimGroupPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().nexus;
+    let path_params = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) =
+            scim_authenticate(&rqctx, &path_params.silo_name).await?;
+        nexus
+            .scim_group_delete(opctx, &authz_silo, path_params.group_id)
+            .await?;
+        Ok(Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())?)
+    };
+    scim_result(rqctx.context().audit_external_handler(&rqctx, handler).await)
+}
@@ -304,8 +304,66 @@ impl Populator for PopulateRack {
     }
 }
 
+/// Populates the system router routes of VPC Subnets created before Nexus
+/// maintained them
+#[derive(Debug)]
+struct PopulateVpcSubnetRoutes;
+impl Populator for PopulateVpcSubnetRoutes {
+    fn populate<'a, 'b>(
+        &self,
+        opctx: &'a OpContext,
+        datastore: &'a DataStore,
+        _args: &'a PopulateArgs,
+    ) -> BoxFuture<'b, Result<(), Error>>
+    where
+        'a: 'b,
+    {
+        async { datastore.vpc_subnet_routes_backfill(opctx).await.map(|_| ()) }
+            .boxed()
+    }
+}
+
+/// Populates the "outbound" internet gateway of VPCs created before Nexus
+/// supported internet gateways
+#[derive(Debug)]
+struct PopulateVpcInternetGateways;
+impl Populator for PopulateVpcInternetGateways {
+    fn populate<'a, 'b>(
+        &self,
+        opctx: &'a OpContext,
+        datastore: &'a DataStore,
+        _args: &'a PopulateArgs,
+    ) -> BoxFuture<'b, Result<(), Error>>
+    where
+        'a: 'b,
+    {
+        async {
+            datastore.vpc_internet_gateway_backfill(opctx).await.map(|_| ())
+        }
+        .boxed()
+    }
+}
+
+/// Populates the resources counted against the quotas of each Silo and
+/// Project, which weren't counted before Nexus supported quotas
+#[derive(Debug)]
+struct PopulateResourceUsage;
+impl Populator for PopulateResourceUsage {
+    fn populate<'a, 'b>(
+        &self,
+        opctx: &'a OpContext,
+        datastore: &'a DataStore,
+        _args: &'a PopulateArgs,
+    ) -> BoxFuture<'b, Result<(), Error>>
+    where
+        'a: 'b,
+    {
+        async { datastore.resource_usage_backfill(opctx).await }.boxed()
+    }
+}
+
 lazy_static! {
-    static ref ALL_POPULATORS: [&'static dyn Populator; 7] = [
+    static ref ALL_POPULATORS: [&'static dyn Populator; 10] = [
         &PopulateBuiltinUsers,
         &PopulateBuiltinRoles,
         &PopulateBuiltinRoleAssignments,
@@ -313,6 +371,9 @@ lazy_static! {
         &PopulateSiloUsers,
         &PopulateSiloUserRoleAssignments,
         &PopulateRack,
+        &PopulateVpcSubnetRoutes,
+        &PopulateVpcInternetGateways,
+        &PopulateResourceUsage,
     ];
 }
 
@@ -0,0 +1,121 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Developer tool for running Nexus against several simulated sleds
+
+use anyhow::Context;
+use clap::Parser;
+use dropshot::test_util::LogContext;
+use nexus_test_utils::rack::{SimRack, SimSledConfig};
+use omicron_common::api::external::ByteCount;
+use omicron_common::cmd::fatal;
+use omicron_common::cmd::CmdError;
+use omicron_sled_agent::sim::SimMode;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Run CockroachDB, ClickHouse, Nexus, and Oximeter along with some number of
+/// simulated sleds
+#[derive(Debug, Parser)]
+#[clap(version)]
+struct Args {
+    /// Nexus configuration file.  The database addresses in it are replaced
+    /// with those of the databases started by this tool.
+    #[clap(long, default_value = "nexus/tests/config.test.toml", action)]
+    nexus_config: PathBuf,
+
+    /// Number of simulated sleds
+    #[clap(long, default_value = "4", action)]
+    sleds: usize,
+
+    /// Hardware threads available to instances on each sled
+    #[clap(long, default_value = "128", action)]
+    hardware_threads: u32,
+
+    /// Memory available to instances on each sled, in GiB
+    #[clap(long, default_value = "1024", action)]
+    physical_ram_gib: u32,
+
+    /// Number of zpools on each sled
+    #[clap(long, default_value = "10", action)]
+    zpools: usize,
+
+    /// Size of each zpool, in GiB
+    #[clap(long, default_value = "1024", action)]
+    zpool_size_gib: u32,
+
+    /// Complete simulated instance and disk transitions only when asked to
+    /// (rather than automatically, after a short delay)
+    #[clap(long, action)]
+    explicit: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(error) = do_run().await {
+        fatal(CmdError::Failure(format!("{:#}", error)));
+    }
+}
+
+async fn do_run() -> Result<(), anyhow::Error> {
+    let args = Args::parse();
+
+    let mut config = omicron_nexus::Config::from_file(&args.nexus_config)
+        .with_context(|| format!("loading {}", args.nexus_config.display()))?;
+    config.deployment.id = Uuid::new_v4();
+    let logctx = LogContext::new("omicron-sim-rack", &config.pkg.log);
+
+    let sled = SimSledConfig {
+        hardware_threads: args.hardware_threads,
+        physical_ram: ByteCount::from_gibibytes_u32(args.physical_ram_gib),
+        zpools: vec![
+            ByteCount::from_gibibytes_u32(args.zpool_size_gib);
+            args.zpools
+        ],
+    };
+    let sim_mode =
+        if args.explicit { SimMode::Explicit } else { SimMode::Auto };
+
+    println!("omicron-sim-rack: starting {} simulated sleds", args.sleds);
+    let rack = SimRack::start(
+        &logctx.log,
+        &mut config,
+        sim_mode,
+        &vec![sled; args.sleds],
+    )
+    .await
+    .map_err(anyhow::Error::msg)?;
+
+    println!(
+        "omicron-sim-rack: CockroachDB listening at: {}",
+        rack.database.listen_url()
+    );
+    println!(
+        "omicron-sim-rack: ClickHouse listening on port {}",
+        rack.clickhouse.port()
+    );
+    for server in &rack.server.http_servers_external {
+        println!(
+            "omicron-sim-rack: Nexus external API: {}",
+            server.local_addr()
+        );
+    }
+    println!(
+        "omicron-sim-rack: Nexus internal API: {}",
+        rack.server.http_server_internal.local_addr()
+    );
+    for sled in &rack.sleds {
+        println!(
+            "omicron-sim-rack: sled {}: {}",
+            sled.id,
+            sled.server.http_server.local_addr()
+        );
+    }
+
+    tokio::signal::ctrl_c().await.context("waiting for SIGINT")?;
+    eprintln!("omicron-sim-rack: caught signal, shutting down");
+    rack.teardown().await.map_err(anyhow::Error::msg)?;
+    logctx.cleanup_successful();
+    Ok(())
+}
@@ -18,20 +18,27 @@ use oximeter_collector::Oximeter;
 use oximeter_producer::Server as ProducerServer;
 use slog::o;
 use slog::Logger;
-use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::path::Path;
 use std::time::Duration;
 use uuid::Uuid;
 
 pub mod db;
 pub mod http_testing;
+pub mod rack;
 pub mod resource_helpers;
+pub mod sagas;
 
 pub const SLED_AGENT_UUID: &str = "b6d65341-167c-41df-9b5c-41cded99c229";
 pub const RACK_UUID: &str = "c19a698f-c6f9-4a17-ae30-20d711b8f7dc";
 pub const OXIMETER_UUID: &str = "39e6175b-4df2-4730-b11d-cbc1e60a2e78";
 pub const PRODUCER_UUID: &str = "a6458b7d-87c3-4483-be96-854d814c20de";
 
+/// Hardware threads available to instances on the simulated sled
+pub const SLED_AGENT_HARDWARE_THREADS: u32 = 64;
+/// Memory available to instances on the simulated sled, in GiB
+pub const SLED_AGENT_PHYSICAL_RAM_GIB: u32 = 256;
+
 pub struct ControlPlaneTestContext {
     pub external_client: ClientTestContext,
     pub internal_client: ClientTestContext,
@@ -94,31 +101,8 @@ pub async fn test_setup_with_config(
     let logctx = LogContext::new(test_name, &config.pkg.log);
     let log = &logctx.log;
 
-    // Start up CockroachDB.
-    let database = db::test_setup_database(log).await;
-
-    // Start ClickHouse database server.
-    let clickhouse = dev::clickhouse::ClickHouseInstance::new(0).await.unwrap();
-
-    // Store actual address/port information for the databases after they start.
-    config.deployment.database =
-        nexus_config::Database::FromUrl { url: database.pg_config().clone() };
-    config
-        .pkg
-        .timeseries_db
-        .address
-        .as_mut()
-        .expect("Tests expect to set a port of Clickhouse")
-        .set_port(clickhouse.port());
-
-    let server =
-        omicron_nexus::Server::start(&config, &logctx.log).await.unwrap();
-    server
-        .apictx
-        .nexus
-        .wait_for_populate()
-        .await
-        .expect("Nexus never loaded users");
+    let (database, clickhouse, server) =
+        start_nexus_and_databases(log, config).await;
 
     let testctx_external = ClientTestContext::new(
         server.http_servers_external[0].local_addr(),
@@ -176,29 +160,60 @@ pub async fn test_setup_with_config(
     }
 }
 
+/// Starts CockroachDB, ClickHouse, and then Nexus, configured by `config`
+/// (which is updated with the databases' addresses)
+pub async fn start_nexus_and_databases(
+    log: &Logger,
+    config: &mut omicron_nexus::Config,
+) -> (
+    dev::db::CockroachInstance,
+    dev::clickhouse::ClickHouseInstance,
+    omicron_nexus::Server,
+) {
+    // Start up CockroachDB.
+    let database = db::test_setup_database(log).await;
+
+    // Start ClickHouse database server.
+    let clickhouse = dev::clickhouse::ClickHouseInstance::new(0).await.unwrap();
+
+    // Store actual address/port information for the databases after they start.
+    config.deployment.database =
+        nexus_config::Database::FromUrl { url: database.pg_config().clone() };
+    config
+        .pkg
+        .timeseries_db
+        .address
+        .as_mut()
+        .expect("Tests expect to set a port of Clickhouse")
+        .set_port(clickhouse.port());
+
+    let server = omicron_nexus::Server::start(&config, log).await.unwrap();
+    server
+        .apictx
+        .nexus
+        .wait_for_populate()
+        .await
+        .expect("Nexus never loaded users");
+
+    (database, clickhouse, server)
+}
+
+/// Starts a simulated sled agent like the one used by most tests
+///
+/// See [`rack::start_sim_sled()`] for sleds with other capacities.
 pub async fn start_sled_agent(
     log: Logger,
     nexus_address: SocketAddr,
     id: Uuid,
 ) -> Result<sim::Server, String> {
-    let config = sim::Config {
-        id,
-        sim_mode: sim::SimMode::Explicit,
+    rack::start_sim_sled(
+        log,
         nexus_address,
-        dropshot: ConfigDropshot {
-            bind_address: SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 0),
-            request_body_max_bytes: 1024 * 1024,
-            ..Default::default()
-        },
-        // TODO-cleanup this is unused
-        log: ConfigLogging::StderrTerminal { level: ConfigLoggingLevel::Debug },
-        storage: sim::ConfigStorage {
-            zpools: vec![],
-            ip: IpAddr::from(Ipv6Addr::LOCALHOST),
-        },
-    };
-
-    sim::Server::start(&config, &log).await
+        id,
+        sim::SimMode::Explicit,
+        &rack::SimSledConfig::default(),
+    )
+    .await
 }
 
 pub async fn start_oximeter(
@@ -0,0 +1,171 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Facilities for running a simulated rack: one Nexus (with its CockroachDB
+//! and ClickHouse) managing any number of simulated sleds
+//!
+//! This is useful for exercising behavior that depends on there being more
+//! than one sled, like instance placement and region allocation, without any
+//! real hardware.
+
+use crate::start_nexus_and_databases;
+use crate::start_oximeter;
+use crate::OXIMETER_UUID;
+use crate::SLED_AGENT_HARDWARE_THREADS;
+use crate::SLED_AGENT_PHYSICAL_RAM_GIB;
+use dropshot::ConfigDropshot;
+use dropshot::ConfigLogging;
+use dropshot::ConfigLoggingLevel;
+use omicron_common::api::external::ByteCount;
+use omicron_sled_agent::sim;
+use omicron_test_utils::dev;
+use oximeter_collector::Oximeter;
+use slog::o;
+use slog::Logger;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use uuid::Uuid;
+
+/// Describes the capacity of one simulated sled
+#[derive(Clone, Debug)]
+pub struct SimSledConfig {
+    /// hardware threads available to instances
+    pub hardware_threads: u32,
+    /// memory available to instances
+    pub physical_ram: ByteCount,
+    /// sizes of the sled's zpools, each of which gets one Crucible dataset
+    pub zpools: Vec<ByteCount>,
+}
+
+impl Default for SimSledConfig {
+    /// Returns the configuration of the sled used by most tests, which has no
+    /// zpools (tests add them as needed with
+    /// [`DiskTest`](crate::resource_helpers::DiskTest))
+    fn default() -> Self {
+        SimSledConfig {
+            hardware_threads: SLED_AGENT_HARDWARE_THREADS,
+            physical_ram: ByteCount::from_gibibytes_u32(
+                SLED_AGENT_PHYSICAL_RAM_GIB,
+            ),
+            zpools: vec![],
+        }
+    }
+}
+
+/// Starts a simulated sled agent with id `id` and capacity `sled`, which
+/// registers itself with the Nexus whose internal API is at `nexus_address`
+pub async fn start_sim_sled(
+    log: Logger,
+    nexus_address: SocketAddr,
+    id: Uuid,
+    sim_mode: sim::SimMode,
+    sled: &SimSledConfig,
+) -> Result<sim::Server, String> {
+    let config = sim::Config {
+        id,
+        sim_mode,
+        nexus_address,
+        dropshot: ConfigDropshot {
+            bind_address: SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 0),
+            request_body_max_bytes: 1024 * 1024,
+            ..Default::default()
+        },
+        // TODO-cleanup this is unused
+        log: ConfigLogging::StderrTerminal { level: ConfigLoggingLevel::Debug },
+        storage: sim::ConfigStorage {
+            zpools: sled
+                .zpools
+                .iter()
+                .map(|size| sim::ConfigZpool { size: size.to_bytes() })
+                .collect(),
+            ip: IpAddr::from(Ipv6Addr::LOCALHOST),
+        },
+        hardware: sim::ConfigHardware {
+            hardware_threads: sled.hardware_threads,
+            physical_ram: sled.physical_ram.to_bytes(),
+        },
+        faults: sim::ConfigFaults::default(),
+    };
+
+    sim::Server::start(&config, &log).await
+}
+
+/// A running simulated sled agent
+pub struct SimSled {
+    pub id: Uuid,
+    pub server: sim::Server,
+}
+
+/// Starts one simulated sled agent for each of `sleds`, each with a new id,
+/// all registering themselves with the Nexus at `nexus_address`
+pub async fn start_sim_sleds(
+    log: &Logger,
+    nexus_address: SocketAddr,
+    sim_mode: sim::SimMode,
+    sleds: &[SimSledConfig],
+) -> Result<Vec<SimSled>, String> {
+    let mut started = Vec::with_capacity(sleds.len());
+    for sled in sleds {
+        let id = Uuid::new_v4();
+        let log = log.new(o!(
+            "component" => "omicron_sled_agent::sim::Server",
+            "sled_id" => id.to_string(),
+        ));
+        let server =
+            start_sim_sled(log, nexus_address, id, sim_mode, sled).await?;
+        started.push(SimSled { id, server });
+    }
+    Ok(started)
+}
+
+/// A running simulated rack
+pub struct SimRack {
+    pub server: omicron_nexus::Server,
+    pub database: dev::db::CockroachInstance,
+    pub clickhouse: dev::clickhouse::ClickHouseInstance,
+    pub oximeter: Oximeter,
+    pub sleds: Vec<SimSled>,
+}
+
+impl SimRack {
+    /// Starts CockroachDB, ClickHouse, Nexus (configured by `config`),
+    /// Oximeter, and a simulated sled agent for each of `sleds`
+    pub async fn start(
+        log: &Logger,
+        config: &mut omicron_nexus::Config,
+        sim_mode: sim::SimMode,
+        sleds: &[SimSledConfig],
+    ) -> Result<SimRack, String> {
+        let (database, clickhouse, server) =
+            start_nexus_and_databases(log, config).await;
+        let nexus_address = server.http_server_internal.local_addr();
+
+        let sleds =
+            start_sim_sleds(log, nexus_address, sim_mode, sleds).await?;
+
+        let oximeter = start_oximeter(
+            log.new(o!("component" => "oximeter")),
+            nexus_address,
+            clickhouse.port(),
+            Uuid::parse_str(OXIMETER_UUID).unwrap(),
+        )
+        .await?;
+
+        Ok(SimRack { server, database, clickhouse, oximeter, sleds })
+    }
+
+    /// Shuts down everything and cleans up the databases' storage
+    pub async fn teardown(mut self) -> Result<(), String> {
+        for server in self.server.http_servers_external {
+            server.close().await?;
+        }
+        self.server.http_server_internal.close().await?;
+        for sled in self.sleds {
+            sled.server.http_server.close().await?;
+        }
+        self.oximeter.close().await.map_err(|e| e.to_string())?;
+        self.database.cleanup().await.map_err(|e| e.to_string())?;
+        self.clickhouse.cleanup().await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
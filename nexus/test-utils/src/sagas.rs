@@ -0,0 +1,384 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Facilities for testing how sagas behave when their actions fail or are
+//! repeated
+//!
+//! The main entry point is [`test_saga_faults()`], which runs an operation
+//! that executes a saga over and over again, injecting a fault at a different
+//! node of the saga each time, and checks that nothing is left behind.
+
+use crate::http_testing::AuthnMode;
+use crate::http_testing::NexusRequest;
+use crate::resource_helpers::DiskTest;
+use crate::ControlPlaneTestContext;
+use omicron_nexus::app::SagaFault;
+use omicron_nexus::app::SagaFaultKind;
+use omicron_nexus::app::SagaFaultReport;
+use omicron_nexus::crucible_agent_client::types::State as RegionState;
+use omicron_nexus::external_api::views::SagaLog;
+use omicron_nexus::external_api::views::SagaLogState;
+use omicron_nexus::external_api::views::SagaNodeEventType;
+use omicron_nexus::TestInterfaces;
+use slog::info;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::future::Future;
+use uuid::Uuid;
+
+/// Database tables whose contents are expected to change while sagas are run
+/// (or while the test drives them through the API), even when nothing leaks
+const UNTRACKED_TABLES: &[&str] = &[
+    "audit_log",
+    "metric_producer",
+    "oximeter",
+    "resource_quota",
+    "saga",
+    "saga_intervention",
+    "saga_node_event",
+];
+
+/// The resources allocated by sagas, used to detect leaks
+#[derive(Debug, Eq, PartialEq)]
+pub struct ResourceSnapshot {
+    /// number of rows in each database table, not counting soft-deleted rows
+    pub db_rows: BTreeMap<String, i64>,
+    /// space used on each dataset, according to the database
+    pub dataset_size_used: BTreeMap<String, i64>,
+    /// resources counted against each Silo's or Project's quotas, omitting
+    /// those with nothing counted (whose rows a saga may leave behind)
+    pub resource_usage: BTreeMap<String, Vec<i64>>,
+    /// ids of Crucible regions that have not been destroyed
+    pub crucible_regions: BTreeSet<String>,
+    /// ids of instances on the simulated sled that have not been destroyed
+    pub sled_instances: BTreeSet<Uuid>,
+    /// ids of disks on the simulated sled that have not been destroyed
+    pub sled_disks: BTreeSet<Uuid>,
+}
+
+impl ResourceSnapshot {
+    pub async fn new(
+        cptestctx: &ControlPlaneTestContext,
+        disk_test: &DiskTest,
+    ) -> ResourceSnapshot {
+        let client = cptestctx
+            .database
+            .connect()
+            .await
+            .expect("failed to connect to database");
+
+        let tables: Vec<String> = client
+            .query(
+                "SELECT table_name FROM omicron.information_schema.tables \
+                WHERE table_schema = 'public' AND table_type = 'BASE TABLE'",
+                &[],
+            )
+            .await
+            .expect("failed to list tables")
+            .iter()
+            .map(|row| row.get(0))
+            .filter(|table: &String| !UNTRACKED_TABLES.contains(&&**table))
+            .collect();
+        let soft_deleted: BTreeSet<String> = client
+            .query(
+                "SELECT table_name FROM omicron.information_schema.columns \
+                WHERE table_schema = 'public' AND \
+                column_name = 'time_deleted'",
+                &[],
+            )
+            .await
+            .expect("failed to list soft-deleted tables")
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        let mut db_rows = BTreeMap::new();
+        for table in tables {
+            let filter = if soft_deleted.contains(&table) {
+                " WHERE time_deleted IS NULL"
+            } else {
+                ""
+            };
+            let query = format!(
+                "SELECT COUNT(*) FROM omicron.public.{}{}",
+                table, filter
+            );
+            let count: i64 = client
+                .query_one(query.as_str(), &[])
+                .await
+                .unwrap_or_else(|e| panic!("failed to count {}: {}", table, e))
+                .get(0);
+            db_rows.insert(table, count);
+        }
+
+        let dataset_size_used = client
+            .query(
+                "SELECT id::STRING, size_used FROM omicron.public.dataset \
+                WHERE size_used IS NOT NULL",
+                &[],
+            )
+            .await
+            .expect("failed to list datasets")
+            .iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect();
+        let resource_usage = client
+            .query(
+                "SELECT id::STRING, cpus_used, memory_used, disk_used, \
+                snapshots_used, external_ips_used \
+                FROM omicron.public.resource_quota \
+                WHERE cpus_used != 0 OR memory_used != 0 OR disk_used != 0 \
+                OR snapshots_used != 0 OR external_ips_used != 0",
+                &[],
+            )
+            .await
+            .expect("failed to list resource usage")
+            .iter()
+            .map(|row| (row.get(0), (1..6).map(|i| row.get(i)).collect()))
+            .collect();
+        client.cleanup().await.expect("failed to clean up connection");
+
+        let mut crucible_regions = BTreeSet::new();
+        for zpool in &disk_test.zpools {
+            for dataset in &zpool.datasets {
+                let crucible = disk_test
+                    .sled_agent
+                    .get_crucible_dataset(zpool.id, dataset.id)
+                    .await;
+                crucible_regions.extend(
+                    crucible
+                        .list()
+                        .await
+                        .into_iter()
+                        .filter(|r| r.state != RegionState::Destroyed)
+                        .map(|r| r.id.0),
+                );
+            }
+        }
+
+        let sled_agent = &cptestctx.sled_agent.sled_agent;
+        ResourceSnapshot {
+            db_rows,
+            dataset_size_used,
+            resource_usage,
+            crucible_regions,
+            sled_instances: sled_agent
+                .instance_ids()
+                .await
+                .into_iter()
+                .collect(),
+            sled_disks: sled_agent.disk_ids().await.into_iter().collect(),
+        }
+    }
+}
+
+/// What [`test_saga_faults()`] expects of a saga into which an error has been
+/// injected
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SagaUnwind {
+    /// Unwinding the saga releases everything it allocated, leaving no trace
+    /// of it.  This is what's expected of any saga that creates something.
+    Complete,
+    /// Unwinding the saga puts back whatever it had released, so that `op`
+    /// can be retried.  This is what's expected of a saga that deletes
+    /// something, which `op` has to create first.  Resources are compared
+    /// after running `cleanup`, which must then release everything.
+    Cleanup,
+}
+
+/// Exercises saga `saga_name` with faults injected at each of its action
+/// nodes
+///
+/// `op` is an operation (usually an API request) that executes the saga once
+/// and returns whether it succeeded.  `cleanup` undoes a successful `op` (for
+/// example, by deleting whatever `op` created) so that `op` can be run again.
+///
+/// This runs `op`:
+///
+/// * once without any fault, to learn the shape of the saga
+/// * once for each action node, with an error injected at that node.  Each
+///   of these must fail and every node that had completed must have been
+///   undone, and no resources may have leaked (see [`SagaUnwind`] for when
+///   this is checked).
+/// * once for each action node, with that node's action run twice.  Each of
+///   these must succeed, and `cleanup` must then release everything, as it
+///   would if the action had run only once.
+///
+/// Resources are compared using [`ResourceSnapshot`], so `disk_test` must
+/// describe all of the simulated storage in use.
+pub async fn test_saga_faults<Op, OpFut, Cleanup, CleanupFut>(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+    saga_name: &str,
+    unwind: SagaUnwind,
+    op: Op,
+    cleanup: Cleanup,
+) where
+    Op: Fn() -> OpFut,
+    OpFut: Future<Output = Result<(), anyhow::Error>>,
+    Cleanup: Fn() -> CleanupFut,
+    CleanupFut: Future<Output = ()>,
+{
+    let log = &cptestctx.logctx.log;
+    let before = ResourceSnapshot::new(cptestctx, disk_test).await;
+
+    // Run the saga without any fault (by asking for one at a node that can't
+    // exist) to find its action nodes.
+    let report = run_with_fault(
+        cptestctx,
+        saga_name,
+        u32::MAX,
+        SagaFaultKind::Error,
+        &op,
+    )
+    .await;
+    assert!(report.injected_at.is_none());
+    assert!(
+        !report.action_nodes.is_empty(),
+        "found no action nodes in saga {:?}",
+        saga_name
+    );
+    cleanup().await;
+    assert_eq!(
+        before,
+        ResourceSnapshot::new(cptestctx, disk_test).await,
+        "resources leaked by successful saga {:?} followed by cleanup",
+        saga_name,
+    );
+
+    for node_index in &report.action_nodes {
+        let node_index = *node_index;
+        let report = run_with_fault(
+            cptestctx,
+            saga_name,
+            node_index,
+            SagaFaultKind::Error,
+            &op,
+        )
+        .await;
+        let node_name = report.injected_at.expect("fault was not injected");
+        info!(log, "injected error into saga";
+            "saga_name" => saga_name,
+            "node_name" => &node_name,
+        );
+
+        let saga_log: SagaLog = NexusRequest::object_get(
+            &cptestctx.external_client,
+            &format!("/system/sagas/{}/log", report.saga_id),
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to fetch saga log")
+        .parsed_body()
+        .unwrap();
+        assert_eq!(saga_log.state, SagaLogState::Done);
+        let events_for = |event_type: SagaNodeEventType| {
+            saga_log
+                .events
+                .iter()
+                .filter(|e| e.event_type == event_type)
+                .map(|e| e.node_id)
+                .collect::<BTreeSet<_>>()
+        };
+        let succeeded = events_for(SagaNodeEventType::Succeeded);
+        let undone = events_for(SagaNodeEventType::UndoFinished);
+        assert!(!succeeded.contains(&node_index));
+        assert_eq!(
+            succeeded, undone,
+            "saga {:?} with error at {:?}: not every completed node was undone",
+            saga_name, node_name,
+        );
+
+        match unwind {
+            SagaUnwind::Complete => assert_eq!(
+                before,
+                ResourceSnapshot::new(cptestctx, disk_test).await,
+                "resources leaked by saga {:?} with error at {:?}",
+                saga_name,
+                node_name,
+            ),
+            SagaUnwind::Cleanup => {
+                cleanup().await;
+                assert_eq!(
+                    before,
+                    ResourceSnapshot::new(cptestctx, disk_test).await,
+                    "resources leaked by saga {:?} with error at {:?} \
+                    followed by cleanup",
+                    saga_name,
+                    node_name,
+                );
+            }
+        }
+    }
+
+    for node_index in &report.action_nodes {
+        let report = run_with_fault(
+            cptestctx,
+            saga_name,
+            *node_index,
+            SagaFaultKind::Repeat,
+            &op,
+        )
+        .await;
+        let node_name = report.injected_at.expect("fault was not injected");
+        info!(log, "repeated action in saga";
+            "saga_name" => saga_name,
+            "node_name" => &node_name,
+        );
+
+        cleanup().await;
+        assert_eq!(
+            before,
+            ResourceSnapshot::new(cptestctx, disk_test).await,
+            "resources leaked by saga {:?} with repeated action {:?}",
+            saga_name,
+            node_name,
+        );
+    }
+}
+
+/// Runs `op` with a fault of kind `kind` injected at node `node_index` of
+/// saga `saga_name`, returning the report about the saga that ran
+///
+/// For `node_index`es that are not action nodes, this injects nothing and
+/// `op` must succeed.  Otherwise, `op` must fail if an error was injected and
+/// succeed if an action was repeated.
+async fn run_with_fault<Op, OpFut>(
+    cptestctx: &ControlPlaneTestContext,
+    saga_name: &str,
+    node_index: u32,
+    kind: SagaFaultKind,
+    op: &Op,
+) -> SagaFaultReport
+where
+    Op: Fn() -> OpFut,
+    OpFut: Future<Output = Result<(), anyhow::Error>>,
+{
+    let nexus = &cptestctx.server.apictx.nexus;
+    nexus.saga_fault_inject(SagaFault {
+        saga_name: String::from(saga_name),
+        node_index,
+        kind,
+    });
+
+    let result = op().await;
+    let report = nexus.saga_fault_report().unwrap_or_else(|| {
+        panic!("operation did not run saga {:?}", saga_name)
+    });
+    if kind == SagaFaultKind::Error && report.injected_at.is_some() {
+        assert!(
+            result.is_err(),
+            "saga {:?} succeeded despite error injected at node {}",
+            saga_name,
+            node_index
+        );
+    } else if let Err(error) = result {
+        panic!(
+            "saga {:?} failed with fault {:?} at node {}: {:#}",
+            saga_name, kind, node_index, error
+        );
+    }
+    report
+}
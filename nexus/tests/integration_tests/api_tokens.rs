@@ -0,0 +1,145 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for user-managed API tokens
+
+use chrono::{Duration, Utc};
+use dropshot::test_util::ClientTestContext;
+use dropshot::ResultsPage;
+use http::{header, method::Method, StatusCode};
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::authn::USER_TEST_UNPRIVILEGED;
+use omicron_nexus::external_api::params::ApiTokenCreate;
+use omicron_nexus::external_api::views::{ApiToken, ApiTokenValue, User};
+
+const TOKENS_URL: &str = "/session/me/api-tokens";
+
+fn token_params(name: &str) -> ApiTokenCreate {
+    ApiTokenCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: format!("token for {}", name),
+        },
+        time_expires: None,
+    }
+}
+
+async fn list_tokens(client: &ClientTestContext) -> Vec<ApiToken> {
+    NexusRequest::object_get(client, TOKENS_URL)
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .expect("failed to list API tokens")
+        .parsed_body::<ResultsPage<ApiToken>>()
+        .unwrap()
+        .items
+}
+
+/// Fetch the current user, authenticating with `access_token`
+async fn session_me(
+    client: &ClientTestContext,
+    access_token: &str,
+    expected_status: StatusCode,
+) -> Option<User> {
+    let response = RequestBuilder::new(client, Method::GET, "/session/me")
+        .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+        .expect_status(Some(expected_status))
+        .execute()
+        .await
+        .expect("failed to fetch current user");
+    (expected_status == StatusCode::OK).then(|| response.parsed_body().unwrap())
+}
+
+// Note: as with SSH keys, we use UnprivilegedUser in this test because all
+// the endpoints here _can_ be accessed by that user.
+#[nexus_test]
+async fn test_api_tokens(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    assert!(list_tokens(client).await.is_empty());
+
+    let created: ApiTokenValue =
+        NexusRequest::objects_post(client, TOKENS_URL, &token_params("ci"))
+            .authn_as(AuthnMode::UnprivilegedUser)
+            .execute()
+            .await
+            .expect("failed to create API token")
+            .parsed_body()
+            .unwrap();
+    assert_eq!(created.token.identity.name.as_str(), "ci");
+    assert_eq!(created.token.silo_user_id, USER_TEST_UNPRIVILEGED.id());
+    assert_eq!(created.token.time_expires, None);
+    assert_eq!(created.token.time_last_used, None);
+    assert!(created.access_token.starts_with("oxide-token-"));
+
+    // Token names are unique per user.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        TOKENS_URL,
+        &token_params("ci"),
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Tokens can't expire in the past.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        TOKENS_URL,
+        &ApiTokenCreate {
+            time_expires: Some(Utc::now() - Duration::hours(1)),
+            ..token_params("expired")
+        },
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // The token authenticates its user, and using it is recorded.
+    let user = session_me(client, &created.access_token, StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(user.id, USER_TEST_UNPRIVILEGED.id());
+    let tokens = list_tokens(client).await;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].identity.id, created.token.identity.id);
+    assert!(tokens[0].time_last_used.is_some());
+
+    // Bogus tokens don't authenticate anybody.
+    session_me(
+        client,
+        "oxide-token-0000000000000000000000000000000000000000",
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+
+    // Revoked tokens no longer authenticate.
+    let token_url = format!("{}/ci", TOKENS_URL);
+    NexusRequest::object_delete(client, &token_url)
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete API token");
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &token_url,
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    assert!(list_tokens(client).await.is_empty());
+    session_me(client, &created.access_token, StatusCode::UNAUTHORIZED).await;
+}
@@ -0,0 +1,199 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for the audit log of external API requests
+
+use chrono::{SecondsFormat, Utc};
+use dropshot::test_util::ClientTestContext;
+use dropshot::ResultsPage;
+use http::{method::Method, StatusCode};
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::create_organization;
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::authn::USER_TEST_PRIVILEGED;
+use omicron_nexus::db::fixed_data::silo::DEFAULT_SILO;
+use omicron_nexus::db::identity::Resource;
+use omicron_nexus::external_api::device_auth::{
+    DeviceAccessTokenRequest, DeviceAuthRequest,
+};
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::AuditLogEntry;
+use uuid::Uuid;
+
+async fn audit_log_list(
+    client: &ClientTestContext,
+    url: &str,
+) -> Vec<AuditLogEntry> {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to list audit log")
+        .parsed_body::<ResultsPage<AuditLogEntry>>()
+        .unwrap()
+        .items
+}
+
+#[nexus_test]
+async fn test_audit_log(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let time_before = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+
+    // Make a few requests: a successful create, a failed one, a read, and a
+    // delete.
+    let org = create_organization(client, "audited-org").await;
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        "/organizations",
+        &params::OrganizationCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "audited-org".parse().unwrap(),
+                description: String::from("a duplicate"),
+            },
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("unexpectedly created duplicate organization");
+    NexusRequest::object_get(client, "/organizations/audited-org")
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to fetch organization");
+    NexusRequest::object_delete(client, "/organizations/audited-org")
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete organization");
+
+    // Only the requests that changed (or tried to change) something are
+    // recorded, in order.
+    let entries = audit_log_list(
+        client,
+        &format!(
+            "/system/audit-log?start_time={}&actor_id={}",
+            time_before,
+            USER_TEST_PRIVILEGED.id()
+        ),
+    )
+    .await;
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            (e.http_method.as_str(), e.request_uri.as_str(), e.http_status_code)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("POST", "/organizations", 201),
+            ("POST", "/organizations", 400),
+            ("DELETE", "/organizations/audited-org", 204),
+        ]
+    );
+    for entry in &entries {
+        assert_eq!(entry.actor_id, Some(USER_TEST_PRIVILEGED.id()));
+        assert_eq!(entry.actor_silo_id, Some(DEFAULT_SILO.id()));
+        assert!(entry.time_started <= entry.time_completed);
+    }
+
+    // Creating an Organization is authorized against its Silo, and deleting one
+    // is authorized against the Organization itself.
+    assert_eq!(entries[0].resource_ids, vec![DEFAULT_SILO.id()]);
+    assert_eq!(entries[0].error_message, None);
+    assert!(entries[1].error_message.is_some());
+    assert_eq!(entries[2].resource_ids, vec![org.identity.id]);
+
+    // Filter by resource.
+    let entries = audit_log_list(
+        client,
+        &format!(
+            "/system/audit-log?start_time={}&resource_id={}",
+            time_before, org.identity.id
+        ),
+    )
+    .await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].http_method, "DELETE");
+
+    // The Silo's audit log includes requests made by its users.
+    let entries = audit_log_list(
+        client,
+        &format!(
+            "/system/silos/{}/audit-log?start_time={}",
+            DEFAULT_SILO.identity().name,
+            time_before
+        ),
+    )
+    .await;
+    assert!(entries
+        .iter()
+        .any(|e| e.request_uri == "/organizations/audited-org"));
+
+    // Nothing has happened since now.
+    let time_after = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+    let entries = audit_log_list(
+        client,
+        &format!("/system/audit-log?start_time={}", time_after),
+    )
+    .await;
+    assert!(entries.is_empty());
+}
+
+#[nexus_test]
+async fn test_audit_log_device_auth(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let time_before = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+
+    // The device authorization endpoints build their responses by hand, and
+    // their requests are unauthenticated, but they're recorded all the same.
+    let client_id = Uuid::new_v4();
+    RequestBuilder::new(client, Method::POST, "/device/auth")
+        .allow_non_dropshot_errors()
+        .body_urlencoded(Some(&DeviceAuthRequest { client_id }))
+        .expect_status(Some(StatusCode::OK))
+        .execute()
+        .await
+        .expect("failed to start device authorization flow");
+    RequestBuilder::new(client, Method::POST, "/device/token")
+        .allow_non_dropshot_errors()
+        .body_urlencoded(Some(&DeviceAccessTokenRequest {
+            grant_type: "urn:ietf:params:oauth:grant-type:device_code".into(),
+            device_code: "not-a-device-code".into(),
+            client_id,
+        }))
+        .expect_status(Some(StatusCode::BAD_REQUEST))
+        .execute()
+        .await
+        .expect("unexpectedly granted a device access token");
+
+    let entries = audit_log_list(
+        client,
+        &format!("/system/audit-log?start_time={}", time_before),
+    )
+    .await;
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            (
+                e.http_method.as_str(),
+                e.request_uri.as_str(),
+                e.http_status_code,
+                e.actor_id,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("POST", "/device/auth", 200, None),
+            ("POST", "/device/token", 400, None),
+        ]
+    );
+}
@@ -20,15 +20,17 @@ use omicron_nexus::external_api::{
     params,
     views::{Project, Sled},
 };
+use omicron_sled_agent::sim::SimMode;
 use serde::Serialize;
 use uuid::Uuid;
 
 use nexus_test_utils::http_testing::AuthnMode;
 use nexus_test_utils::http_testing::NexusRequest;
 use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::rack::start_sim_sleds;
+use nexus_test_utils::rack::SimSledConfig;
 use nexus_test_utils::resource_helpers::create_organization;
 use nexus_test_utils::resource_helpers::create_project;
-use nexus_test_utils::start_sled_agent;
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
 
@@ -592,14 +594,14 @@ async fn test_sleds_list(cptestctx: &ControlPlaneTestContext) {
 
     // Now start a few more sled agents.
     let nsleds = 3;
-    let mut sas = Vec::with_capacity(nsleds);
-    for _ in 0..nsleds {
-        let sa_id = Uuid::new_v4();
-        let log =
-            cptestctx.logctx.log.new(o!( "sled_id" => sa_id.to_string() ));
-        let addr = cptestctx.server.http_server_internal.local_addr();
-        sas.push(start_sled_agent(log, addr, sa_id).await.unwrap());
-    }
+    let sas = start_sim_sleds(
+        &cptestctx.logctx.log,
+        cptestctx.server.http_server_internal.local_addr(),
+        SimMode::Explicit,
+        &vec![SimSledConfig::default(); nsleds],
+    )
+    .await
+    .unwrap();
 
     // List sleds again.
     let sleds_found = sleds_list(&client, &sleds_url).await;
@@ -610,10 +612,13 @@ async fn test_sleds_list(cptestctx: &ControlPlaneTestContext) {
     let mut sledids_found_sorted = sledids_found.clone();
     sledids_found_sorted.sort();
     assert_eq!(sledids_found, sledids_found_sorted);
+    for sa in &sas {
+        assert!(sledids_found.contains(&sa.id));
+    }
 
     // Tear down the agents.
     for sa in sas {
-        sa.http_server.close().await.unwrap();
+        sa.server.http_server.close().await.unwrap();
     }
 }
 
@@ -22,8 +22,9 @@ use omicron_nexus::authz::SiloRole;
 use omicron_nexus::db::fixed_data::silo::DEFAULT_SILO;
 use omicron_nexus::db::identity::{Asset, Resource};
 use omicron_nexus::external_api::console_api::SpoofLoginBody;
-use omicron_nexus::external_api::params::OrganizationCreate;
+use omicron_nexus::external_api::params::{self, OrganizationCreate};
 use omicron_nexus::external_api::{shared, views};
+use std::str::FromStr;
 
 #[nexus_test]
 async fn test_sessions(cptestctx: &ControlPlaneTestContext) {
@@ -428,6 +429,167 @@ async fn test_login_redirect(cptestctx: &ControlPlaneTestContext) {
     expect_redirect(testctx, "/login?state=", "/spoof_login").await;
 }
 
+#[nexus_test]
+async fn test_login_local(cptestctx: &ControlPlaneTestContext) {
+    let testctx = &cptestctx.external_client;
+
+    let silo_name = DEFAULT_SILO.identity().name.clone();
+    let users_url =
+        format!("/system/silos/{}/identity-providers/local/users", silo_name);
+    let login_url = format!("/login/{}/local", silo_name);
+    let user: views::User = NexusRequest::objects_post(
+        testctx,
+        &users_url,
+        &params::UserCreate {
+            external_id: params::UserId::from_str("alice").unwrap(),
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to create user")
+    .parsed_body()
+    .unwrap();
+    let set_password_url = format!("{}/{}/set-password", users_url, user.id);
+
+    // Users can't log in until they've been given a password.
+    local_login(testctx, &login_url, "alice", "hunter2hunter2", false).await;
+    set_password(testctx, &set_password_url, "hunter2hunter2").await;
+
+    // Logging in gives the user a session.
+    let session_token =
+        local_login(testctx, &login_url, "alice", "hunter2hunter2", true)
+            .await
+            .unwrap();
+    let me = RequestBuilder::new(&testctx, Method::GET, "/session/me")
+        .header(header::COOKIE, &session_token)
+        .expect_status(Some(StatusCode::OK))
+        .execute()
+        .await
+        .expect("failed to get current user")
+        .parsed_body::<views::User>()
+        .unwrap();
+    assert_eq!(me.id, user.id);
+
+    // Unknown users and Silos are refused just like wrong passwords.
+    local_login(testctx, &login_url, "bob", "hunter2hunter2", false).await;
+    local_login(
+        testctx,
+        "/login/nonexistent/local",
+        "alice",
+        "hunter2hunter2",
+        false,
+    )
+    .await;
+
+    // After five wrong passwords in a row, even the right one is refused...
+    for _ in 0..5 {
+        local_login(testctx, &login_url, "alice", "wrong-password", false)
+            .await;
+    }
+    local_login(testctx, &login_url, "alice", "hunter2hunter2", false).await;
+
+    // ... until the password is reset.
+    set_password(testctx, &set_password_url, "correct-horse").await;
+    local_login(testctx, &login_url, "alice", "hunter2hunter2", false).await;
+    local_login(testctx, &login_url, "alice", "correct-horse", true).await;
+}
+
+#[nexus_test]
+async fn test_session_management(cptestctx: &ControlPlaneTestContext) {
+    let testctx = &cptestctx.external_client;
+
+    let session_token = log_in_and_extract_token(&testctx).await;
+    let login = RequestBuilder::new(&testctx, Method::POST, "/login")
+        .header(header::USER_AGENT, "test-browser/1.0")
+        .body(Some(&SpoofLoginBody { username: "unprivileged".to_string() }))
+        .expect_status(Some(StatusCode::NO_CONTENT))
+        .execute()
+        .await
+        .expect("failed to log in");
+    let session_cookie = get_header_value(login, header::SET_COOKIE);
+    let (other_session_token, _) = session_cookie.split_once("; ").unwrap();
+
+    // Users can see their own sessions, but not the session tokens.
+    let sessions = list_sessions(testctx, &session_token).await;
+    assert_eq!(sessions.len(), 2);
+    let other_session = sessions
+        .iter()
+        .find(|session| {
+            session.user_agent.as_deref() == Some("test-browser/1.0")
+        })
+        .expect("session should record the User-Agent");
+
+    // The test server only listens on localhost, so that's where the client
+    // logged in from.
+    for session in &sessions {
+        let client_address =
+            session.client_address.expect("session should record the address");
+        assert!(client_address.is_loopback(), "{}", client_address);
+    }
+
+    // Users can revoke their own sessions.
+    RequestBuilder::new(
+        &testctx,
+        Method::DELETE,
+        &format!("/session/me/sessions/{}", other_session.id),
+    )
+    .header(header::COOKIE, &session_token)
+    .expect_status(Some(StatusCode::NO_CONTENT))
+    .execute()
+    .await
+    .expect("failed to revoke session");
+    assert_eq!(list_sessions(testctx, &session_token).await.len(), 1);
+    RequestBuilder::new(&testctx, Method::GET, "/session/me")
+        .header(header::COOKIE, other_session_token)
+        .expect_status(Some(StatusCode::UNAUTHORIZED))
+        .execute()
+        .await
+        .expect("failed to 401 on revoked session");
+
+    // Administrators can see a user's sessions and log them out everywhere.
+    let user_sessions_url = format!(
+        "/system/silos/{}/users/id/{}/sessions",
+        DEFAULT_SILO.identity().name,
+        USER_TEST_UNPRIVILEGED.id()
+    );
+    let user_sessions = NexusRequest::object_get(testctx, &user_sessions_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to list user's sessions")
+        .parsed_body::<ResultsPage<views::ConsoleSession>>()
+        .unwrap()
+        .items;
+    assert_eq!(user_sessions.len(), 1);
+    NexusRequest::object_delete(testctx, &user_sessions_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to log user out everywhere");
+    RequestBuilder::new(&testctx, Method::GET, "/session/me")
+        .header(header::COOKIE, &session_token)
+        .expect_status(Some(StatusCode::UNAUTHORIZED))
+        .execute()
+        .await
+        .expect("failed to 401 after logging out everywhere");
+}
+
+async fn list_sessions(
+    testctx: &ClientTestContext,
+    session_token: &str,
+) -> Vec<views::ConsoleSession> {
+    RequestBuilder::new(&testctx, Method::GET, "/session/me/sessions")
+        .header(header::COOKIE, session_token)
+        .expect_status(Some(StatusCode::OK))
+        .execute()
+        .await
+        .expect("failed to list sessions")
+        .parsed_body::<ResultsPage<views::ConsoleSession>>()
+        .unwrap()
+        .items
+}
+
 fn get_header_value(resp: TestResponse, header_name: HeaderName) -> String {
     resp.headers.get(header_name).unwrap().to_str().unwrap().to_string()
 }
@@ -457,3 +619,56 @@ async fn expect_redirect(testctx: &ClientTestContext, from: &str, to: &str) {
         .await
         .expect("did not find expected redirect");
 }
+
+/// Log in to a Silo's local identity provider, returning the session cookie
+/// if the login is expected to succeed
+async fn local_login(
+    testctx: &ClientTestContext,
+    login_url: &str,
+    username: &str,
+    password: &str,
+    expect_success: bool,
+) -> Option<String> {
+    let credentials = params::UsernamePasswordCredentials {
+        username: params::UserId::from_str(username).unwrap(),
+        password: params::Password::from_str(password).unwrap(),
+    };
+    let status = if expect_success {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::UNAUTHORIZED
+    };
+    let login = RequestBuilder::new(&testctx, Method::POST, login_url)
+        .body(Some(&credentials))
+        .expect_status(Some(status))
+        .execute()
+        .await
+        .expect("unexpected login result");
+    if !expect_success {
+        return None;
+    }
+
+    let session_cookie = get_header_value(login, header::SET_COOKIE);
+    let (session_token, _) = session_cookie.split_once("; ").unwrap();
+    assert!(session_token.starts_with("session="));
+    Some(session_token.to_string())
+}
+
+async fn set_password(
+    testctx: &ClientTestContext,
+    set_password_url: &str,
+    password: &str,
+) {
+    let update = params::UserPassword {
+        password: params::Password::from_str(password).unwrap(),
+    };
+    NexusRequest::new(
+        RequestBuilder::new(testctx, Method::POST, set_password_url)
+            .body(Some(&update))
+            .expect_status(Some(StatusCode::NO_CONTENT)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to set password");
+}
@@ -2,6 +2,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use dropshot::test_util::ClientTestContext;
+use dropshot::ResultsPage;
 use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
@@ -9,9 +11,11 @@ use omicron_nexus::external_api::device_auth::{
     DeviceAccessTokenRequest, DeviceAuthRequest, DeviceAuthVerify,
 };
 use omicron_nexus::external_api::views::{
-    DeviceAccessTokenGrant, DeviceAccessTokenType, DeviceAuthResponse,
+    DeviceAccessToken, DeviceAccessTokenGrant, DeviceAccessTokenType,
+    DeviceAuthResponse,
 };
 
+use chrono::{Duration, Utc};
 use http::{header, method::Method, StatusCode};
 use serde::Deserialize;
 use uuid::Uuid;
@@ -152,4 +156,65 @@ async fn test_device_auth_flow(cptestctx: &ControlPlaneTestContext) {
     assert_eq!(token.token_type, DeviceAccessTokenType::Bearer);
     assert_eq!(token.access_token.len(), 52);
     assert!(token.access_token.starts_with("oxide-token-"));
+
+    // The user can see the token they were granted, but not the token itself.
+    // It expires after the TTL in the test config.
+    let tokens = list_device_tokens(testctx).await;
+    assert_eq!(tokens.len(), 1);
+    let time_expires = tokens[0].time_expires.expect("token should expire");
+    assert!(time_expires > Utc::now() + Duration::minutes(1430));
+    assert!(time_expires <= Utc::now() + Duration::minutes(1440));
+
+    // Other users can't see it.
+    let other_tokens: ResultsPage<DeviceAccessToken> =
+        NexusRequest::object_get(testctx, "/session/me/tokens")
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .expect("failed to list device tokens")
+            .parsed_body()
+            .expect("failed to deserialize device tokens");
+    assert!(other_tokens.items.is_empty());
+
+    // Revoke the token.
+    let token_url = format!("/session/me/tokens/{}", tokens[0].id);
+    NexusRequest::expect_failure(
+        testctx,
+        StatusCode::NOT_FOUND,
+        Method::DELETE,
+        &token_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to 404 on another user's token");
+    NexusRequest::object_delete(testctx, &token_url)
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .expect("failed to revoke token");
+    assert!(list_device_tokens(testctx).await.is_empty());
+    NexusRequest::expect_failure(
+        testctx,
+        StatusCode::NOT_FOUND,
+        Method::DELETE,
+        &token_url,
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .expect("failed to 404 on revoked token");
+}
+
+async fn list_device_tokens(
+    testctx: &ClientTestContext,
+) -> Vec<DeviceAccessToken> {
+    NexusRequest::object_get(testctx, "/session/me/tokens")
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .expect("failed to list device tokens")
+        .parsed_body::<ResultsPage<DeviceAccessToken>>()
+        .expect("failed to deserialize device tokens")
+        .items
 }
@@ -35,6 +35,9 @@ use omicron_common::api::external::Name;
 use omicron_common::backoff;
 use omicron_nexus::TestInterfaces as _;
 use omicron_nexus::{external_api::params, Nexus};
+use omicron_sled_agent::sim::{
+    ResourceUsage, SimFault, SimFaultRule, SimFaultTarget,
+};
 use oximeter::types::Datum;
 use oximeter::types::Measurement;
 use sled_agent_client::TestInterfaces as _;
@@ -644,6 +647,57 @@ async fn test_disk_region_creation_failure(
     let _ = create_disk(client, ORG_NAME, PROJECT_NAME, DISK_NAME).await;
 }
 
+// Tests that a fault injected into the simulated Crucible Agent causes disk
+// allocation to fail, and that the fault only lasts as long as requested.
+#[nexus_test]
+async fn test_disk_region_creation_injected_fault(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+
+    // Fail the first region creation request, whichever region it's for.
+    cptestctx
+        .sled_agent
+        .sled_agent
+        .fault_add(
+            SimFaultTarget::Regions,
+            SimFaultRule {
+                id: None,
+                count: Some(1),
+                fault: SimFault::Error { status: 500 },
+            },
+        )
+        .await
+        .unwrap();
+
+    let disks_url = get_disks_url();
+    let new_disk = params::DiskCreate {
+        identity: IdentityMetadataCreateParams {
+            name: DISK_NAME.parse().unwrap(),
+            description: String::from("sells rainsticks"),
+        },
+        disk_source: params::DiskSource::Blank {
+            block_size: params::BlockSize::try_from(512).unwrap(),
+        },
+        size: ByteCount::from_gibibytes_u32(1),
+    };
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &disks_url)
+            .body(Some(&new_disk))
+            .expect_status(Some(StatusCode::INTERNAL_SERVER_ERROR)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    assert_eq!(disks_list(&client, &disks_url).await.len(), 0);
+
+    // The rule has been used up, so trying again succeeds.
+    create_disk(client, ORG_NAME, PROJECT_NAME, DISK_NAME).await;
+}
+
 // Tests that invalid block sizes are rejected
 #[nexus_test]
 async fn test_disk_invalid_block_size_rejected(
@@ -963,6 +1017,7 @@ async fn test_disk_size_accounting(cptestctx: &ControlPlaneTestContext) {
             );
         }
     }
+    assert_sled_zpool_usage(&test, ByteCount::from_gibibytes_u32(7)).await;
 
     // Ask for a 4 gibibyte disk, this should fail because there isn't space
     // available.
@@ -1024,6 +1079,7 @@ async fn test_disk_size_accounting(cptestctx: &ControlPlaneTestContext) {
             );
         }
     }
+    assert_sled_zpool_usage(&test, ByteCount::from(0)).await;
 
     // Ask for a 10 gibibyte disk.
     let disk_size = ByteCount::from_gibibytes_u32(10);
@@ -1060,6 +1116,7 @@ async fn test_disk_size_accounting(cptestctx: &ControlPlaneTestContext) {
             );
         }
     }
+    assert_sled_zpool_usage(&test, ByteCount::from_gibibytes_u32(10)).await;
 }
 
 // Test creating two disks across six zpools
@@ -1270,6 +1327,26 @@ async fn test_disk_metrics_paginated(cptestctx: &ControlPlaneTestContext) {
     }
 }
 
+/// Checks that the simulated sled agent reports `used` bytes in use in each
+/// of the test's zpools
+async fn assert_sled_zpool_usage(test: &DiskTest, used: ByteCount) {
+    let usage = test.sled_agent.usage().await;
+    for zpool in &test.zpools {
+        let zpool_usage = usage
+            .zpools
+            .iter()
+            .find(|z| z.id == zpool.id)
+            .expect("sled agent did not report usage for zpool");
+        assert_eq!(
+            zpool_usage.size,
+            ResourceUsage {
+                total: zpool.size.to_bytes(),
+                used: used.to_bytes()
+            }
+        );
+    }
+}
+
 async fn disk_get(client: &ClientTestContext, disk_url: &str) -> Disk {
     NexusRequest::object_get(client, disk_url)
         .authn_as(AuthnMode::PrivilegedUser)
@@ -19,11 +19,14 @@ use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::IdentityMetadataUpdateParams;
 use omicron_common::api::external::InstanceCpuCount;
 use omicron_common::api::external::Ipv4Net;
+use omicron_common::api::external::L4Port;
 use omicron_common::api::external::Name;
 use omicron_common::api::external::RouteDestination;
 use omicron_common::api::external::RouteTarget;
 use omicron_common::api::external::RouterRouteCreateParams;
 use omicron_common::api::external::RouterRouteUpdateParams;
+use omicron_common::api::external::VpcFirewallRuleDirection;
+use omicron_common::api::external::VpcFirewallRuleProtocol;
 use omicron_common::api::external::VpcFirewallRuleUpdateParams;
 use omicron_nexus::authn;
 use omicron_nexus::authz;
@@ -52,6 +55,8 @@ lazy_static! {
         format!("/system/silos/{}", *DEMO_SILO_NAME);
     pub static ref DEMO_SILO_POLICY_URL: String =
         format!("/system/silos/{}/policy", *DEMO_SILO_NAME);
+    pub static ref DEMO_SILO_QUOTAS_URL: String =
+        format!("/system/silos/{}/quotas", *DEMO_SILO_NAME);
     pub static ref DEMO_SILO_CREATE: params::SiloCreate =
         params::SiloCreate {
             identity: IdentityMetadataCreateParams {
@@ -62,6 +67,36 @@ lazy_static! {
             identity_mode: shared::SiloIdentityMode::SamlJit,
             admin_group_name: None,
         };
+    // Silo used for testing OpenID Connect identity providers
+    pub static ref DEMO_OIDC_SILO_NAME: Name =
+        "demo-oidc-silo".parse().unwrap();
+    pub static ref DEMO_OIDC_SILO_CREATE: params::SiloCreate =
+        params::SiloCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_OIDC_SILO_NAME.clone(),
+                description: String::from(""),
+            },
+            discoverable: true,
+            identity_mode: shared::SiloIdentityMode::OidcJit,
+            admin_group_name: None,
+        };
+    // Silo used for testing SCIM client bearer tokens
+    pub static ref DEMO_SCIM_SILO_NAME: Name =
+        "demo-scim-silo".parse().unwrap();
+    pub static ref DEMO_SCIM_SILO_CREATE: params::SiloCreate =
+        params::SiloCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_SCIM_SILO_NAME.clone(),
+                description: String::from(""),
+            },
+            discoverable: true,
+            identity_mode: shared::SiloIdentityMode::SamlScim,
+            admin_group_name: None,
+        };
+    pub static ref DEMO_SCIM_TOKENS_URL: String =
+        format!("/system/silos/{}/scim/tokens", *DEMO_SCIM_SILO_NAME);
+    pub static ref DEMO_SCIM_TOKEN_ID_URL: String =
+        format!("/system/silos/{}/scim/tokens/{{id}}", *DEMO_SCIM_SILO_NAME);
     // Use the default Silo for testing the local IdP
     pub static ref DEMO_SILO_USERS_CREATE_URL: String = format!(
         "/system/silos/{}/identity-providers/local/users",
@@ -79,6 +114,40 @@ lazy_static! {
         "/system/silos/{}/identity-providers/local/users/{{id}}",
         DEFAULT_SILO.identity().name,
     );
+    pub static ref DEMO_SILO_USER_ID_SET_PASSWORD_URL: String = format!(
+        "/system/silos/{}/identity-providers/local/users/{{id}}/set-password",
+        DEFAULT_SILO.identity().name,
+    );
+    pub static ref DEMO_SILO_USER_ID_SESSIONS_URL: String = format!(
+        "/system/silos/{}/users/id/{{id}}/sessions",
+        DEFAULT_SILO.identity().name,
+    );
+    pub static ref DEMO_SILO_USER_ID_SESSION_URL: String = format!(
+        "{}/{}",
+        *DEMO_SILO_USER_ID_SESSIONS_URL,
+        *DEMO_SESSION_ID,
+    );
+    pub static ref DEMO_SILO_AUDIT_LOG_URL: String = format!(
+        "/system/silos/{}/audit-log",
+        DEFAULT_SILO.identity().name,
+    );
+    pub static ref DEMO_SILO_GROUPS_URL: String = format!(
+        "/system/silos/{}/groups",
+        DEFAULT_SILO.identity().name,
+    );
+    pub static ref DEMO_SILO_GROUP_ID_URL: String = format!(
+        "/system/silos/{}/groups/{{id}}",
+        DEFAULT_SILO.identity().name,
+    );
+    pub static ref DEMO_SILO_GROUP_ID_MEMBERS_URL: String = format!(
+        "/system/silos/{}/groups/{{id}}/members",
+        DEFAULT_SILO.identity().name,
+    );
+    pub static ref DEMO_SILO_GROUP_ID_MEMBER_URL: String = format!(
+        "/system/silos/{}/groups/{{id}}/members/{}",
+        DEFAULT_SILO.identity().name,
+        *DEMO_GROUP_MEMBER_ID,
+    );
 
     // Organization used for testing
     pub static ref DEMO_ORG_NAME: Name = "demo-org".parse().unwrap();
@@ -102,10 +171,16 @@ lazy_static! {
         format!("{}/{}", *DEMO_ORG_PROJECTS_URL, *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_POLICY_URL: String =
         format!("{}/policy", *DEMO_PROJECT_URL);
+    pub static ref DEMO_PROJECT_SETTINGS_URL: String =
+        format!("{}/settings", *DEMO_PROJECT_URL);
+    pub static ref DEMO_PROJECT_QUOTAS_URL: String =
+        format!("{}/quotas", *DEMO_PROJECT_URL);
     pub static ref DEMO_PROJECT_URL_DISKS: String =
         format!("{}/disks", *DEMO_PROJECT_URL);
     pub static ref DEMO_PROJECT_URL_IMAGES: String =
         format!("{}/images", *DEMO_PROJECT_URL);
+    pub static ref DEMO_PROJECT_URL_FIPS: String =
+        format!("{}/floating-ips", *DEMO_PROJECT_URL);
     pub static ref DEMO_PROJECT_URL_INSTANCES: String =
         format!("{}/instances", *DEMO_PROJECT_URL);
     pub static ref DEMO_PROJECT_URL_SNAPSHOTS: String =
@@ -124,8 +199,12 @@ lazy_static! {
     pub static ref DEMO_VPC_NAME: Name = "demo-vpc".parse().unwrap();
     pub static ref DEMO_VPC_URL: String =
         format!("{}/{}", *DEMO_PROJECT_URL_VPCS, *DEMO_VPC_NAME);
+    pub static ref DEMO_VPC_POLICY_URL: String =
+        format!("{}/policy", *DEMO_VPC_URL);
     pub static ref DEMO_VPC_URL_FIREWALL_RULES: String =
         format!("{}/firewall/rules", *DEMO_VPC_URL);
+    pub static ref DEMO_VPC_URL_FIREWALL_EVALUATE: String =
+        format!("{}/firewall/evaluate", *DEMO_VPC_URL);
     pub static ref DEMO_VPC_URL_ROUTERS: String =
         format!("{}/routers", *DEMO_VPC_URL);
     pub static ref DEMO_VPC_URL_SUBNETS: String =
@@ -147,6 +226,8 @@ lazy_static! {
         format!("{}/{}", *DEMO_VPC_URL_SUBNETS, *DEMO_VPC_SUBNET_NAME);
     pub static ref DEMO_VPC_SUBNET_INTERFACES_URL: String =
         format!("{}/network-interfaces", *DEMO_VPC_SUBNET_URL);
+    pub static ref DEMO_VPC_SUBNET_ROUTER_URL: String =
+        format!("{}/router", *DEMO_VPC_SUBNET_URL);
     pub static ref DEMO_VPC_SUBNET_CREATE: params::VpcSubnetCreate =
         params::VpcSubnetCreate {
             identity: IdentityMetadataCreateParams {
@@ -172,6 +253,43 @@ lazy_static! {
             },
         };
 
+    // VPC Peering used for testing.  Peerings can't be created yet, so this
+    // one never exists.
+    pub static ref DEMO_VPC_URL_PEERINGS: String =
+        format!("{}/peerings", *DEMO_VPC_URL);
+    pub static ref DEMO_VPC_PEERING_NAME: Name =
+        "demo-vpc-peering".parse().unwrap();
+    pub static ref DEMO_VPC_PEERING_URL: String =
+        format!("{}/{}", *DEMO_VPC_URL_PEERINGS, *DEMO_VPC_PEERING_NAME);
+    pub static ref DEMO_VPC_PEERING_CREATE: params::VpcPeeringCreate =
+        params::VpcPeeringCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_VPC_PEERING_NAME.clone(),
+                description: String::from(""),
+            },
+            peer_organization: DEMO_ORG_NAME.clone(),
+            peer_project: DEMO_PROJECT_NAME.clone(),
+            peer_vpc: DEMO_VPC_NAME.clone(),
+        };
+
+    // Internet Gateway used for testing
+    pub static ref DEMO_VPC_URL_INTERNET_GATEWAYS: String =
+        format!("{}/internet-gateways", *DEMO_VPC_URL);
+    pub static ref DEMO_INTERNET_GATEWAY_NAME: Name =
+        "demo-internet-gateway".parse().unwrap();
+    pub static ref DEMO_INTERNET_GATEWAY_URL: String = format!(
+        "{}/{}",
+        *DEMO_VPC_URL_INTERNET_GATEWAYS, *DEMO_INTERNET_GATEWAY_NAME
+    );
+    pub static ref DEMO_INTERNET_GATEWAY_CREATE: params::InternetGatewayCreate =
+        params::InternetGatewayCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_INTERNET_GATEWAY_NAME.clone(),
+                description: String::from(""),
+            },
+            ip_pools: vec![],
+        };
+
     // Router Route used for testing
     pub static ref DEMO_ROUTER_ROUTE_NAME: Name =
         "demo-router-route".parse().unwrap();
@@ -191,6 +309,8 @@ lazy_static! {
     pub static ref DEMO_DISK_NAME: Name = "demo-disk".parse().unwrap();
     pub static ref DEMO_DISK_URL: String =
         format!("{}/{}", *DEMO_PROJECT_URL_DISKS, *DEMO_DISK_NAME);
+    pub static ref DEMO_DISK_POLICY_URL: String =
+        format!("{}/policy", *DEMO_DISK_URL);
     pub static ref DEMO_DISK_CREATE: params::DiskCreate =
         params::DiskCreate {
             identity: IdentityMetadataCreateParams {
@@ -221,6 +341,8 @@ lazy_static! {
     pub static ref DEMO_INSTANCE_NAME: Name = "demo-instance".parse().unwrap();
     pub static ref DEMO_INSTANCE_URL: String =
         format!("{}/{}", *DEMO_PROJECT_URL_INSTANCES, *DEMO_INSTANCE_NAME);
+    pub static ref DEMO_INSTANCE_POLICY_URL: String =
+        format!("{}/policy", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_START_URL: String =
         format!("{}/start", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_STOP_URL: String =
@@ -229,6 +351,8 @@ lazy_static! {
         format!("{}/reboot", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_MIGRATE_URL: String =
         format!("{}/migrate", *DEMO_INSTANCE_URL);
+    pub static ref DEMO_INSTANCE_TAGS_URL: String =
+        format!("{}/tags", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_DISKS_URL: String =
         format!("{}/disks", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_DISKS_ATTACH_URL: String =
@@ -239,6 +363,10 @@ lazy_static! {
         format!("{}/network-interfaces", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_EXTERNAL_IPS_URL: String =
         format!("{}/external-ips", *DEMO_INSTANCE_URL);
+    pub static ref DEMO_INSTANCE_EPHEMERAL_IP_URL: String =
+        format!("{}/external-ips/ephemeral", *DEMO_INSTANCE_URL);
+    pub static ref DEMO_EPHEMERAL_IP_CREATE: params::EphemeralIpCreate =
+        params::EphemeralIpCreate { pool_name: None };
     pub static ref DEMO_INSTANCE_SERIAL_URL: String =
         format!("{}/serial-console", *DEMO_INSTANCE_URL);
     pub static ref DEMO_INSTANCE_SERIAL_STREAM_URL: String =
@@ -273,8 +401,8 @@ lazy_static! {
                 name: DEMO_INSTANCE_NIC_NAME.clone(),
                 description: String::from(""),
             },
-            vpc_name: DEMO_VPC_NAME.clone(),
-            subnet_name: DEMO_VPC_SUBNET_NAME.clone(),
+            vpc_name: Some(DEMO_VPC_NAME.clone()),
+            subnet_name: Some(DEMO_VPC_SUBNET_NAME.clone()),
             ip: None,
         };
     pub static ref DEMO_INSTANCE_NIC_PUT: params::NetworkInterfaceUpdate = {
@@ -368,6 +496,27 @@ lazy_static! {
             disk: DEMO_DISK_NAME.clone(),
         };
 
+    // Floating IPs
+    pub static ref DEMO_FLOATING_IP_NAME: Name = "demo-fip".parse().unwrap();
+    pub static ref DEMO_FLOATING_IP_URL: String =
+        format!("{}/{}", *DEMO_PROJECT_URL_FIPS, *DEMO_FLOATING_IP_NAME);
+    pub static ref DEMO_FLOATING_IP_ATTACH_URL: String =
+        format!("{}/attach", *DEMO_FLOATING_IP_URL);
+    pub static ref DEMO_FLOATING_IP_DETACH_URL: String =
+        format!("{}/detach", *DEMO_FLOATING_IP_URL);
+    pub static ref DEMO_FLOATING_IP_CREATE: params::FloatingIpCreate =
+        params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_FLOATING_IP_NAME.clone(),
+                description: String::from("a new floating IP"),
+            },
+            pool_name: None,
+        };
+    pub static ref DEMO_FLOATING_IP_ATTACH: params::FloatingIpAttach =
+        params::FloatingIpAttach {
+            instance: DEMO_INSTANCE_NAME.clone(),
+        };
+
     // SSH keys
     pub static ref DEMO_SSHKEYS_URL: &'static str = "/session/me/sshkeys";
     pub static ref DEMO_SSHKEY_NAME: Name = "aaaaa-ssh-key".parse().unwrap();
@@ -382,6 +531,36 @@ lazy_static! {
 
     pub static ref DEMO_SPECIFIC_SSHKEY_URL: String =
         format!("{}/{}", *DEMO_SSHKEYS_URL, *DEMO_SSHKEY_NAME);
+
+    // API tokens
+    pub static ref DEMO_API_TOKENS_URL: &'static str = "/session/me/api-tokens";
+    pub static ref DEMO_API_TOKEN_NAME: Name =
+        "aaaaa-api-token".parse().unwrap();
+    pub static ref DEMO_API_TOKEN_CREATE: params::ApiTokenCreate =
+        params::ApiTokenCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_API_TOKEN_NAME.clone(),
+                description: "a demo token".to_string(),
+            },
+            time_expires: None,
+        };
+    pub static ref DEMO_SPECIFIC_API_TOKEN_URL: String =
+        format!("{}/{}", *DEMO_API_TOKENS_URL, *DEMO_API_TOKEN_NAME);
+
+    // Console sessions used for testing
+    pub static ref DEMO_SESSIONS_URL: &'static str = "/session/me/sessions";
+    pub static ref DEMO_SESSION_ID: &'static str =
+        "5d0d8b1c-6b1f-4c07-9bfa-6f4c1e2a3b4d";
+    pub static ref DEMO_SPECIFIC_SESSION_URL: String =
+        format!("{}/{}", *DEMO_SESSIONS_URL, *DEMO_SESSION_ID);
+
+    // Device access token used for testing
+    pub static ref DEMO_DEVICE_TOKENS_URL: &'static str = "/session/me/tokens";
+    pub static ref DEMO_SPECIFIC_DEVICE_TOKEN_URL: String = format!(
+        "{}/{}",
+        *DEMO_DEVICE_TOKENS_URL,
+        "3b8eb2bd-7b2b-4cf3-b0ac-4a3d0e7bd8f6"
+    );
 }
 
 lazy_static! {
@@ -412,10 +591,49 @@ lazy_static! {
             group_attribute_name: None,
         };
 
+    pub static ref OIDC_IDENTITY_PROVIDERS_URL: String = format!("/system/silos/{}/identity-providers/oidc", *DEMO_OIDC_SILO_NAME);
+
+    pub static ref DEMO_OIDC_IDENTITY_PROVIDER_NAME: Name = "demo-oidc-provider".parse().unwrap();
+    pub static ref SPECIFIC_OIDC_IDENTITY_PROVIDER_URL: String = format!("{}/{}", *OIDC_IDENTITY_PROVIDERS_URL, *DEMO_OIDC_IDENTITY_PROVIDER_NAME);
+
+    pub static ref OIDC_IDENTITY_PROVIDER: params::OidcIdentityProviderCreate =
+        params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_OIDC_IDENTITY_PROVIDER_NAME.clone(),
+                description: "a demo provider".to_string(),
+            },
+
+            issuer: HTTP_SERVER.url("/oidc").to_string(),
+            client_id: "client_id".to_string(),
+            client_secret: "client_secret".to_string(),
+            scopes: vec![],
+            redirect_url: "http://redirect".to_string(),
+
+            group_claim_name: None,
+        };
+
     // Users
     pub static ref DEMO_USER_CREATE: params::UserCreate = params::UserCreate {
         external_id: params::UserId::from_str("dummy-user").unwrap(),
     };
+    pub static ref DEMO_USER_PASSWORD: params::UserPassword =
+        params::UserPassword {
+            password: params::Password::from_str("dummy-password").unwrap(),
+        };
+    pub static ref DEMO_GROUP_CREATE: params::GroupCreate =
+        params::GroupCreate {
+            display_name: "dummy-group".parse().unwrap(),
+        };
+    pub static ref DEMO_GROUP_MEMBER_ID: uuid::Uuid =
+        "f9db4d11-2a19-4a0e-8dc5-63bba6db5c0f".parse().unwrap();
+    pub static ref DEMO_GROUP_MEMBER_ADD: params::GroupMemberAdd =
+        params::GroupMemberAdd { user_id: *DEMO_GROUP_MEMBER_ID };
+
+    // Sagas
+    pub static ref DEMO_SAGA_INTERVENE: params::SagaIntervene =
+        params::SagaIntervene {
+            reason: String::from("stuck in testing"),
+        };
 }
 
 /// Describes an API endpoint to be verified by the "unauthorized" test
@@ -725,6 +943,19 @@ lazy_static! {
                 ),
             ],
         },
+        VerifyEndpoint {
+            url: &*DEMO_SILO_QUOTAS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &params::ResourceQuotasUpdate::default()
+                    ).unwrap()
+                ),
+            ],
+        },
         VerifyEndpoint {
             url: "/policy",
             visibility: Visibility::Public,
@@ -788,6 +1019,92 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_SILO_USER_ID_SESSIONS_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_USER_ID_SESSION_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_USER_ID_SET_PASSWORD_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::ReadOnly,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(
+                        &*DEMO_USER_PASSWORD
+                    ).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_AUDIT_LOG_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_GROUPS_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::ReadOnly,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(
+                        &*DEMO_GROUP_CREATE
+                    ).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_GROUP_ID_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::ReadOnly,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_GROUP_ID_MEMBERS_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::ReadOnly,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(
+                        &*DEMO_GROUP_MEMBER_ADD
+                    ).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_SILO_GROUP_ID_MEMBER_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::ReadOnly,
+            allowed_methods: vec![
+                AllowedMethod::Delete,
+            ],
+        },
+
         VerifyEndpoint {
             url: "/groups",
             visibility: Visibility::Public,
@@ -919,6 +1236,36 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_PROJECT_SETTINGS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(&params::ProjectSettingsUpdate {
+                        default_vpc: None,
+                        default_vpc_subnet: None,
+                        default_ip_pool: None,
+                    }).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_PROJECT_QUOTAS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &params::ResourceQuotasUpdate::default()
+                    ).unwrap()
+                ),
+            ],
+        },
+
         /* VPCs */
         VerifyEndpoint {
             url: &*DEMO_PROJECT_URL_VPCS,
@@ -960,6 +1307,22 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_VPC_POLICY_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &shared::Policy::<authz::VpcRole> {
+                            role_assignments: vec![]
+                        }
+                    ).unwrap()
+                ),
+            ],
+        },
+
         /* Firewall rules */
         VerifyEndpoint {
             url: &*DEMO_VPC_URL_FIREWALL_RULES,
@@ -975,6 +1338,27 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_VPC_URL_FIREWALL_EVALUATE,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(params::VpcFirewallRuleEvaluate {
+                        source: params::VpcFirewallFlowHost::Ip(
+                            "10.0.0.1".parse().unwrap()
+                        ),
+                        destination: params::VpcFirewallFlowHost::Ip(
+                            "172.30.0.5".parse().unwrap()
+                        ),
+                        protocol: VpcFirewallRuleProtocol::Tcp,
+                        port: Some(L4Port::try_from(5432).unwrap()),
+                        direction: VpcFirewallRuleDirection::Inbound,
+                    }).unwrap()
+                ),
+            ],
+        },
+
         /* VPC Subnets */
         VerifyEndpoint {
             url: &*DEMO_VPC_URL_SUBNETS,
@@ -1024,6 +1408,20 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_VPC_SUBNET_ROUTER_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&params::VpcSubnetRouterAttach {
+                        router: DEMO_VPC_ROUTER_NAME.clone(),
+                    }).unwrap()
+                ),
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* VPC Routers */
 
         VerifyEndpoint {
@@ -1065,6 +1463,81 @@ lazy_static! {
             ],
         },
 
+        /* VPC Peerings */
+
+        VerifyEndpoint {
+            url: &*DEMO_VPC_URL_PEERINGS,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_VPC_PEERING_CREATE).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: "/by-id/vpc-peerings/{id}",
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::GetNonexistent,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_VPC_PEERING_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::GetNonexistent,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        /* Internet Gateways */
+
+        VerifyEndpoint {
+            url: &*DEMO_VPC_URL_INTERNET_GATEWAYS,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_INTERNET_GATEWAY_CREATE).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: "/by-id/internet-gateways/{id}",
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_INTERNET_GATEWAY_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(&params::InternetGatewayUpdate {
+                        identity: IdentityMetadataUpdateParams {
+                            name: None,
+                            description: Some("different".to_string())
+                        },
+                        ip_pools: None,
+                    }).unwrap()
+                ),
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* Router Routes */
 
         VerifyEndpoint {
@@ -1143,6 +1616,22 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_DISK_POLICY_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &shared::Policy::<authz::DiskRole> {
+                            role_assignments: vec![]
+                        }
+                    ).unwrap()
+                ),
+            ],
+        },
+
         VerifyEndpoint {
             url: &*DEMO_DISK_METRICS_URL,
             visibility: Visibility::Protected,
@@ -1251,6 +1740,59 @@ lazy_static! {
             ]
         },
 
+        /* Floating IPs */
+
+        VerifyEndpoint {
+            url: &*DEMO_PROJECT_URL_FIPS,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_FLOATING_IP_CREATE).unwrap(),
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: "/by-id/floating-ips/{id}",
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_FLOATING_IP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_FLOATING_IP_ATTACH_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_FLOATING_IP_ATTACH).unwrap(),
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &*DEMO_FLOATING_IP_DETACH_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::Value::Null),
+            ],
+        },
+
         /* Instances */
         VerifyEndpoint {
             url: &*DEMO_PROJECT_URL_INSTANCES,
@@ -1283,6 +1825,22 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_INSTANCE_POLICY_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &shared::Policy::<authz::InstanceRole> {
+                            role_assignments: vec![]
+                        }
+                    ).unwrap()
+                ),
+            ],
+        },
+
         VerifyEndpoint {
             url: &*DEMO_INSTANCE_START_URL,
             visibility: Visibility::Protected,
@@ -1319,6 +1877,18 @@ lazy_static! {
                 ).unwrap()),
             ],
         },
+        VerifyEndpoint {
+            url: &*DEMO_INSTANCE_TAGS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Put(serde_json::to_value(
+                    params::InstanceTagsUpdate {
+                        tags: vec!["web".parse().unwrap()],
+                    }
+                ).unwrap()),
+            ],
+        },
         VerifyEndpoint {
             url: &*DEMO_INSTANCE_SERIAL_URL,
             visibility: Visibility::Protected,
@@ -1379,6 +1949,18 @@ lazy_static! {
             allowed_methods: vec![AllowedMethod::Get],
         },
 
+        VerifyEndpoint {
+            url: &*DEMO_INSTANCE_EPHEMERAL_IP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_EPHEMERAL_IP_CREATE).unwrap(),
+                ),
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* IAM */
 
         VerifyEndpoint {
@@ -1453,6 +2035,40 @@ lazy_static! {
             allowed_methods: vec![AllowedMethod::GetNonexistent],
         },
 
+        VerifyEndpoint {
+            url: "/system/sagas/48a1b8c8-fc1c-6fea-9de9-fdeb8dda7823/log",
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::GetNonexistent],
+        },
+
+        VerifyEndpoint {
+            url: "/system/sagas/48a1b8c8-fc1c-6fea-9de9-fdeb8dda7823/abandon",
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Post(
+                serde_json::to_value(&*DEMO_SAGA_INTERVENE).unwrap()
+            )],
+        },
+
+        VerifyEndpoint {
+            url: "/system/sagas/48a1b8c8-fc1c-6fea-9de9-fdeb8dda7823/retry",
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Post(
+                serde_json::to_value(&*DEMO_SAGA_INTERVENE).unwrap()
+            )],
+        },
+
+        /* Audit log */
+
+        VerifyEndpoint {
+            url: "/system/audit-log",
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
         /* Timeseries schema */
 
         VerifyEndpoint {
@@ -1547,6 +2163,42 @@ lazy_static! {
             unprivileged_access: UnprivilegedAccess::None,
             allowed_methods: vec![AllowedMethod::Get],
         },
+        VerifyEndpoint {
+            url: &*OIDC_IDENTITY_PROVIDERS_URL,
+            // As with SAML identity providers, these live in a non-default
+            // Silo that unprivileged users can't see.
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Post(
+                serde_json::to_value(&*OIDC_IDENTITY_PROVIDER).unwrap(),
+            )],
+        },
+        VerifyEndpoint {
+            url: &*SPECIFIC_OIDC_IDENTITY_PROVIDER_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
+        /* SCIM client bearer tokens */
+
+        VerifyEndpoint {
+            url: &*DEMO_SCIM_TOKENS_URL,
+            // As with identity providers, these live in a non-default Silo
+            // that unprivileged users can't see.
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(serde_json::Value::Null),
+            ],
+        },
+        VerifyEndpoint {
+            url: &*DEMO_SCIM_TOKEN_ID_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Delete],
+        },
 
         /* Misc */
 
@@ -1589,5 +2241,58 @@ lazy_static! {
                 AllowedMethod::Delete,
             ],
         },
+
+        /* API tokens */
+
+        VerifyEndpoint {
+            url: &*DEMO_API_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_API_TOKEN_CREATE).unwrap(),
+                ),
+            ],
+        },
+        VerifyEndpoint {
+            url: &*DEMO_SPECIFIC_API_TOKEN_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        /* Console sessions */
+
+        VerifyEndpoint {
+            url: &*DEMO_SESSIONS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+        VerifyEndpoint {
+            url: &*DEMO_SPECIFIC_SESSION_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![AllowedMethod::Delete],
+        },
+
+        /* Device access tokens */
+
+        VerifyEndpoint {
+            url: &*DEMO_DEVICE_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+        VerifyEndpoint {
+            url: &*DEMO_SPECIFIC_DEVICE_TOKEN_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![AllowedMethod::Delete],
+        },
     ];
 }
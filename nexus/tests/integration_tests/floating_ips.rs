@@ -0,0 +1,414 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests Floating IPs and attaching them to instances
+
+use crate::integration_tests::instances::instance_simulate;
+use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::http_testing::TestResponse;
+use nexus_test_utils::resource_helpers::create_instance;
+use nexus_test_utils::resource_helpers::create_ip_pool;
+use nexus_test_utils::resource_helpers::create_organization;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::InstanceCpuCount;
+use omicron_common::api::external::Name;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared::IpKind;
+use omicron_nexus::external_api::shared::IpRange;
+use omicron_nexus::external_api::shared::Ipv4Range;
+use omicron_nexus::external_api::views::ExternalIp;
+use omicron_nexus::external_api::views::FloatingIp;
+use omicron_sled_agent::sim::{SimFault, SimFaultRule, SimFaultTarget};
+
+static POOL_NAME: &str = "p0";
+static ORGANIZATION_NAME: &str = "test-org";
+static PROJECT_NAME: &str = "springfield-squidport";
+static FIP_NAME: &str = "fip0";
+static INSTANCE_NAME: &str = "inst0";
+
+fn get_fips_url() -> String {
+    format!(
+        "/organizations/{}/projects/{}/floating-ips",
+        ORGANIZATION_NAME, PROJECT_NAME
+    )
+}
+
+fn get_fip_url(name: &str) -> String {
+    format!("{}/{}", get_fips_url(), name)
+}
+
+async fn create_org_and_project(client: &ClientTestContext) {
+    create_organization(&client, ORGANIZATION_NAME).await;
+    create_project(client, ORGANIZATION_NAME, PROJECT_NAME).await;
+}
+
+async fn create_floating_ip(
+    client: &ClientTestContext,
+    name: &str,
+) -> FloatingIp {
+    object_create(
+        client,
+        &get_fips_url(),
+        &params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: name.parse().unwrap(),
+                description: String::from("a floating IP"),
+            },
+            pool_name: None,
+        },
+    )
+    .await
+}
+
+async fn floating_ip_get(client: &ClientTestContext, url: &str) -> FloatingIp {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+/// Attaches or detaches a Floating IP, expecting `status`.
+async fn floating_ip_post(
+    client: &ClientTestContext,
+    url: &str,
+    body: Option<&params::FloatingIpAttach>,
+    status: StatusCode,
+) -> TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, url)
+            .body(body)
+            .expect_status(Some(status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
+/// Creates an instance and simulates it booting.
+async fn create_running_instance(
+    cptestctx: &ControlPlaneTestContext,
+    name: &str,
+) -> Instance {
+    let instance = create_instance(
+        &cptestctx.external_client,
+        ORGANIZATION_NAME,
+        PROJECT_NAME,
+        name,
+    )
+    .await;
+    instance_simulate(&cptestctx.server.apictx.nexus, &instance.identity.id)
+        .await;
+    instance
+}
+
+#[nexus_test]
+async fn test_floating_ip_create_list_delete(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let range = IpRange::V4(
+        Ipv4Range::new(
+            std::net::Ipv4Addr::new(10, 0, 0, 1),
+            std::net::Ipv4Addr::new(10, 0, 0, 5),
+        )
+        .unwrap(),
+    );
+    create_ip_pool(&client, POOL_NAME, Some(range), None).await;
+    create_org_and_project(client).await;
+
+    // There are no Floating IPs to start with.
+    let fips =
+        objects_list_page_authz::<FloatingIp>(client, &get_fips_url()).await;
+    assert!(fips.items.is_empty());
+
+    let fip = create_floating_ip(client, FIP_NAME).await;
+    assert_eq!(fip.identity.name, FIP_NAME);
+    assert!(fip.ip >= range.first_address() && fip.ip <= range.last_address());
+    assert_eq!(fip.instance_id, None);
+
+    // It can be fetched by name or id, and shows up in the list.
+    let fetched = floating_ip_get(client, &get_fip_url(FIP_NAME)).await;
+    assert_eq!(fetched.identity.id, fip.identity.id);
+    assert_eq!(fetched.ip, fip.ip);
+    let fetched = floating_ip_get(
+        client,
+        &format!("/by-id/floating-ips/{}", fip.identity.id),
+    )
+    .await;
+    assert_eq!(fetched.identity.name, FIP_NAME);
+    let fips =
+        objects_list_page_authz::<FloatingIp>(client, &get_fips_url()).await;
+    assert_eq!(fips.items.len(), 1);
+    assert_eq!(fips.items[0].identity.id, fip.identity.id);
+
+    // Names are unique within the project.
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &get_fips_url(),
+        &params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: FIP_NAME.parse().unwrap(),
+                description: String::from("a duplicate floating IP"),
+            },
+            pool_name: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        format!("already exists: floating-ip \"{}\"", FIP_NAME)
+    );
+
+    // Deleting the Floating IP releases its address back to the pool.
+    NexusRequest::object_delete(client, &get_fip_url(FIP_NAME))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &get_fip_url(FIP_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let fips =
+        objects_list_page_authz::<FloatingIp>(client, &get_fips_url()).await;
+    assert!(fips.items.is_empty());
+    let fip = create_floating_ip(client, FIP_NAME).await;
+    assert!(fip.ip >= range.first_address() && fip.ip <= range.last_address());
+}
+
+#[nexus_test]
+async fn test_floating_ip_attach_detach(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+    create_ip_pool(&client, POOL_NAME, None, None).await;
+    create_org_and_project(client).await;
+
+    let fip = create_floating_ip(client, FIP_NAME).await;
+    let instance = create_running_instance(cptestctx, INSTANCE_NAME).await;
+    let instance_id = instance.identity.id;
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![])
+    );
+
+    // Attach the Floating IP, and check that the sled hosting the instance
+    // was told about it.
+    let attach_url = format!("{}/attach", get_fip_url(FIP_NAME));
+    let attach =
+        params::FloatingIpAttach { instance: INSTANCE_NAME.parse().unwrap() };
+    let attached: FloatingIp = floating_ip_post(
+        client,
+        &attach_url,
+        Some(&attach),
+        StatusCode::ACCEPTED,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(attached.instance_id, Some(instance_id));
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![fip.ip])
+    );
+    let ips = objects_list_page_authz::<ExternalIp>(
+        client,
+        &format!(
+            "/organizations/{}/projects/{}/instances/{}/external-ips",
+            ORGANIZATION_NAME, PROJECT_NAME, INSTANCE_NAME
+        ),
+    )
+    .await;
+    assert_eq!(ips.items.len(), 1);
+    assert_eq!(ips.items[0].kind, IpKind::Floating);
+    assert_eq!(ips.items[0].ip, fip.ip);
+
+    // Attaching it again to the same instance is a no-op.
+    floating_ip_post(client, &attach_url, Some(&attach), StatusCode::ACCEPTED)
+        .await;
+
+    // An attached Floating IP cannot be deleted.
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &get_fip_url(FIP_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Detach it, after which the sled no longer has the address and the
+    // Floating IP can be deleted.
+    let detach_url = format!("{}/detach", get_fip_url(FIP_NAME));
+    let detached: FloatingIp =
+        floating_ip_post(client, &detach_url, None, StatusCode::ACCEPTED)
+            .await
+            .parsed_body()
+            .unwrap();
+    assert_eq!(detached.instance_id, None);
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![])
+    );
+    NexusRequest::object_delete(client, &get_fip_url(FIP_NAME))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+}
+
+#[nexus_test]
+async fn test_floating_ip_attach_limits(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_ip_pool(&client, POOL_NAME, None, None).await;
+    create_org_and_project(client).await;
+    create_floating_ip(client, FIP_NAME).await;
+
+    // An instance which already has an Ephemeral IP can't take a Floating IP.
+    let instance: Instance = object_create(
+        client,
+        &format!(
+            "/organizations/{}/projects/{}/instances",
+            ORGANIZATION_NAME, PROJECT_NAME
+        ),
+        &params::InstanceCreate {
+            identity: IdentityMetadataCreateParams {
+                name: INSTANCE_NAME.parse().unwrap(),
+                description: String::from("instance with an ephemeral IP"),
+            },
+            ncpus: InstanceCpuCount::try_from(2).unwrap(),
+            memory: ByteCount::from_gibibytes_u32(1),
+            hostname: String::from("inst"),
+            user_data: vec![],
+            network_interfaces:
+                params::InstanceNetworkInterfaceAttachment::Default,
+            external_ips: vec![params::ExternalIpCreate::Ephemeral {
+                pool_name: None,
+            }],
+            disks: vec![],
+            start: true,
+        },
+    )
+    .await;
+    instance_simulate(&cptestctx.server.apictx.nexus, &instance.identity.id)
+        .await;
+    let attach_url = format!("{}/attach", get_fip_url(FIP_NAME));
+    let attach =
+        params::FloatingIpAttach { instance: INSTANCE_NAME.parse().unwrap() };
+    let error: HttpErrorResponseBody = floating_ip_post(
+        client,
+        &attach_url,
+        Some(&attach),
+        StatusCode::BAD_REQUEST,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "An instance may not have more than 1 external IP addresses"
+    );
+
+    // Instances in other projects can't be named at all.
+    create_project(client, ORGANIZATION_NAME, "other-project").await;
+    create_instance(client, ORGANIZATION_NAME, "other-project", "other-inst")
+        .await;
+    let attach = params::FloatingIpAttach {
+        instance: Name::try_from(String::from("other-inst")).unwrap(),
+    };
+    floating_ip_post(client, &attach_url, Some(&attach), StatusCode::NOT_FOUND)
+        .await;
+
+    let fip = floating_ip_get(client, &get_fip_url(FIP_NAME)).await;
+    assert_eq!(fip.instance_id, None);
+}
+
+#[nexus_test]
+async fn test_floating_ip_attach_sled_failure(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+    create_ip_pool(&client, POOL_NAME, None, None).await;
+    create_org_and_project(client).await;
+    create_floating_ip(client, FIP_NAME).await;
+    let instance = create_running_instance(cptestctx, INSTANCE_NAME).await;
+    let instance_id = instance.identity.id;
+
+    // If the sled can't apply the new address, the attachment is undone.
+    sled_agent
+        .fault_add(
+            SimFaultTarget::Instances,
+            SimFaultRule {
+                id: Some(instance_id),
+                count: Some(1),
+                fault: SimFault::Error { status: 503 },
+            },
+        )
+        .await
+        .unwrap();
+    let attach_url = format!("{}/attach", get_fip_url(FIP_NAME));
+    let attach =
+        params::FloatingIpAttach { instance: INSTANCE_NAME.parse().unwrap() };
+    floating_ip_post(
+        client,
+        &attach_url,
+        Some(&attach),
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+    .await;
+    let fip = floating_ip_get(client, &get_fip_url(FIP_NAME)).await;
+    assert_eq!(fip.instance_id, None);
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![])
+    );
+
+    // Once the fault has cleared, the attach goes through.
+    let attached: FloatingIp = floating_ip_post(
+        client,
+        &attach_url,
+        Some(&attach),
+        StatusCode::ACCEPTED,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(attached.instance_id, Some(instance_id));
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![attached.ip])
+    );
+}
@@ -31,6 +31,7 @@ use omicron_nexus::external_api::shared::Ipv4Range;
 use omicron_nexus::external_api::views;
 use omicron_nexus::TestInterfaces as _;
 use omicron_nexus::{external_api::params, Nexus};
+use omicron_sled_agent::sim::{SimFault, SimFaultRule, SimFaultTarget};
 use sled_agent_client::TestInterfaces as _;
 use std::convert::TryFrom;
 use std::sync::Arc;
@@ -44,6 +45,7 @@ use nexus_test_utils::resource_helpers::{
     create_instance, create_organization, create_project,
 };
 use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils::SLED_AGENT_HARDWARE_THREADS;
 use nexus_test_utils_macros::nexus_test;
 
 static POOL_NAME: &str = "p0";
@@ -653,8 +655,8 @@ async fn test_instance_create_saga_removes_instance_database_record(
             name: Name::try_from(String::from("if0")).unwrap(),
             description: String::from("first custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(default_name.clone()),
         ip: Some(requested_address),
     };
     let interface_params =
@@ -720,8 +722,8 @@ async fn test_instance_create_saga_removes_instance_database_record(
             name: Name::try_from(String::from("if0")).unwrap(),
             description: String::from("first custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(default_name.clone()),
         ip: Some(requested_address),
     };
     let interface_params =
@@ -742,6 +744,155 @@ async fn test_instance_create_saga_removes_instance_database_record(
     assert_eq!(instance.identity.name, instance_params.identity.name);
 }
 
+// Tests that an instance-create saga unwinds when the sled agent fails to
+// start the instance, using a fault injected into the simulated sled agent.
+#[nexus_test]
+async fn test_instance_create_unwinds_on_injected_sled_fault(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    create_org_and_project(client).await;
+
+    cptestctx
+        .sled_agent
+        .sled_agent
+        .fault_add(
+            SimFaultTarget::Instances,
+            SimFaultRule {
+                id: None,
+                count: Some(1),
+                fault: SimFault::Error { status: 503 },
+            },
+        )
+        .await
+        .unwrap();
+
+    let instance_params = params::InstanceCreate {
+        identity: IdentityMetadataCreateParams {
+            name: Name::try_from(String::from("unlucky")).unwrap(),
+            description: String::from("fails to boot the first time"),
+        },
+        ncpus: InstanceCpuCount::try_from(2).unwrap(),
+        memory: ByteCount::from_gibibytes_u32(4),
+        hostname: String::from("unlucky"),
+        user_data: vec![],
+        network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
+        external_ips: vec![],
+        disks: vec![],
+        start: true,
+    };
+    let url_instances = get_instances_url();
+    NexusRequest::objects_post(client, &url_instances, &instance_params)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect_err("instance creation should have failed");
+    assert!(instances_list(&client, &url_instances).await.is_empty());
+    assert!(cptestctx.sled_agent.sled_agent.instance_ids().await.is_empty());
+
+    // The rule only applied to one request, so the same instance can now be
+    // created.
+    let response =
+        NexusRequest::objects_post(client, &url_instances, &instance_params)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .expect("failed to create instance after fault was used up");
+    let instance = response.parsed_body::<Instance>().unwrap();
+    assert_eq!(instance.identity.name, instance_params.identity.name);
+}
+
+// Tests that the simulated sled refuses to run more instances than it has
+// room for, and that stopping an instance frees up its resources.
+#[nexus_test]
+async fn test_instances_limited_by_sled_capacity(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx.nexus;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+    create_org_and_project(client).await;
+    let url_instances = get_instances_url();
+
+    let half_the_sled = InstanceCpuCount(
+        u16::try_from(SLED_AGENT_HARDWARE_THREADS / 2).unwrap(),
+    );
+    let instance_params =
+        |name: &str, ncpus: InstanceCpuCount| params::InstanceCreate {
+            identity: IdentityMetadataCreateParams {
+                name: name.parse().unwrap(),
+                description: String::from("takes up space"),
+            },
+            ncpus,
+            memory: ByteCount::from_gibibytes_u32(4),
+            hostname: String::from(name),
+            user_data: vec![],
+            network_interfaces:
+                params::InstanceNetworkInterfaceAttachment::Default,
+            external_ips: vec![],
+            disks: vec![],
+            start: true,
+        };
+
+    let usage = sled_agent.usage().await;
+    assert_eq!(usage.hardware_threads.used, 0);
+    assert_eq!(usage.physical_ram.used, 0);
+
+    let instance: Instance = NexusRequest::objects_post(
+        client,
+        &url_instances,
+        &instance_params("big", half_the_sled),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to create instance")
+    .parsed_body()
+    .unwrap();
+    let usage = sled_agent.usage().await;
+    assert_eq!(
+        usage.hardware_threads.used,
+        u64::from(SLED_AGENT_HARDWARE_THREADS / 2)
+    );
+    assert_eq!(usage.physical_ram.used, 4 << 30);
+
+    // There's no room for another instance this big.
+    let bigger = InstanceCpuCount(half_the_sled.0 + 1);
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &url_instances)
+            .body(Some(&instance_params("bigger", bigger)))
+            .expect_status(Some(StatusCode::INTERNAL_SERVER_ERROR)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    assert_eq!(instances_list(&client, &url_instances).await.len(), 1);
+    assert_eq!(
+        sled_agent.usage().await.hardware_threads.used,
+        u64::from(SLED_AGENT_HARDWARE_THREADS / 2)
+    );
+
+    // Once the first instance is stopped, there is.
+    let instance_url = format!("{}/big", url_instances);
+    instance_simulate(nexus, &instance.identity.id).await;
+    instance_post(client, &instance_url, InstanceOp::Stop).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let usage = sled_agent.usage().await;
+    assert_eq!(usage.hardware_threads.used, 0);
+    assert_eq!(usage.physical_ram.used, 0);
+
+    NexusRequest::objects_post(
+        client,
+        &url_instances,
+        &instance_params("bigger", bigger),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to create instance after freeing up space");
+}
+
 // Basic test requesting an interface with a specific IP address.
 #[nexus_test]
 async fn test_instance_with_single_explicit_ip_address(
@@ -766,8 +917,8 @@ async fn test_instance_with_single_explicit_ip_address(
             name: Name::try_from(String::from("if0")).unwrap(),
             description: String::from("first custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(default_name.clone()),
         ip: Some(requested_address),
     };
     let interface_params =
@@ -876,8 +1027,8 @@ async fn test_instance_with_new_custom_network_interfaces(
             name: Name::try_from(String::from("if0")).unwrap(),
             description: String::from("first custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(default_name.clone()),
         ip: None,
     };
     let if1_params = params::NetworkInterfaceCreate {
@@ -885,8 +1036,8 @@ async fn test_instance_with_new_custom_network_interfaces(
             name: Name::try_from(String::from("if1")).unwrap(),
             description: String::from("second custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: non_default_subnet_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(non_default_subnet_name.clone()),
         ip: None,
     };
     let interface_params =
@@ -1062,8 +1213,8 @@ async fn test_instance_create_delete_network_interface(
                 name: "if0".parse().unwrap(),
                 description: String::from("a new nic"),
             },
-            vpc_name: "default".parse().unwrap(),
-            subnet_name: "default".parse().unwrap(),
+            vpc_name: Some("default".parse().unwrap()),
+            subnet_name: Some("default".parse().unwrap()),
             ip: Some("172.30.0.10".parse().unwrap()),
         },
         params::NetworkInterfaceCreate {
@@ -1071,8 +1222,8 @@ async fn test_instance_create_delete_network_interface(
                 name: "if1".parse().unwrap(),
                 description: String::from("a new nic"),
             },
-            vpc_name: "default".parse().unwrap(),
-            subnet_name: secondary_subnet.identity.name.clone(),
+            vpc_name: Some("default".parse().unwrap()),
+            subnet_name: Some(secondary_subnet.identity.name.clone()),
             ip: Some("172.31.0.11".parse().unwrap()),
         },
     ];
@@ -1299,8 +1450,8 @@ async fn test_instance_update_network_interfaces(
                 name: "if0".parse().unwrap(),
                 description: String::from("a new nic"),
             },
-            vpc_name: "default".parse().unwrap(),
-            subnet_name: "default".parse().unwrap(),
+            vpc_name: Some("default".parse().unwrap()),
+            subnet_name: Some("default".parse().unwrap()),
             ip: Some("172.30.0.10".parse().unwrap()),
         },
         params::NetworkInterfaceCreate {
@@ -1308,8 +1459,8 @@ async fn test_instance_update_network_interfaces(
                 name: "if1".parse().unwrap(),
                 description: String::from("a new nic"),
             },
-            vpc_name: "default".parse().unwrap(),
-            subnet_name: secondary_subnet.identity.name.clone(),
+            vpc_name: Some("default".parse().unwrap()),
+            subnet_name: Some(secondary_subnet.identity.name.clone()),
             ip: Some("172.31.0.11".parse().unwrap()),
         },
     ];
@@ -1650,8 +1801,8 @@ async fn test_instance_with_multiple_nics_unwinds_completely(
             name: Name::try_from(String::from("if0")).unwrap(),
             description: String::from("first custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(default_name.clone()),
         ip: Some("172.30.0.6".parse().unwrap()),
     };
     let if1_params = params::NetworkInterfaceCreate {
@@ -1659,8 +1810,8 @@ async fn test_instance_with_multiple_nics_unwinds_completely(
             name: Name::try_from(String::from("if1")).unwrap(),
             description: String::from("second custom interface"),
         },
-        vpc_name: default_name.clone(),
-        subnet_name: default_name.clone(),
+        vpc_name: Some(default_name.clone()),
+        subnet_name: Some(default_name.clone()),
         ip: Some("172.30.0.7".parse().unwrap()),
     };
     let interface_params =
@@ -1847,12 +1998,13 @@ async fn test_instance_fails_to_boot_with_disk(
             name: Name::try_from(String::from("nfs")).unwrap(),
             description: String::from("probably serving data"),
         },
-        // there's a specific line in the simulated sled agent that will return
-        // a 500 if you try to allocate an instance with more than 16 CPUs. a
-        // 500 error is required to exercise the undo nodes of the instance
-        // create saga (where provision fails, instead of just responding with a
-        // bad request).
-        ncpus: InstanceCpuCount::try_from(32).unwrap(),
+        // the simulated sled agent returns a 500 if you try to start an
+        // instance with more CPUs than the sled has. a 500 error is required
+        // to exercise the undo nodes of the instance create saga (where
+        // provision fails, instead of just responding with a bad request).
+        ncpus: InstanceCpuCount(
+            u16::try_from(SLED_AGENT_HARDWARE_THREADS + 1).unwrap(),
+        ),
         memory: ByteCount::from_gibibytes_u32(4),
         hostname: String::from("nfs"),
         user_data: vec![],
@@ -2689,6 +2841,153 @@ async fn test_instance_ephemeral_ip_from_correct_project(
     );
 }
 
+#[nexus_test]
+async fn test_instance_ephemeral_ip_attach_detach(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let apictx = &cptestctx.server.apictx;
+    let nexus = &apictx.nexus;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+
+    create_ip_pool(&client, POOL_NAME, None, None).await;
+    create_organization(&client, ORGANIZATION_NAME).await;
+    create_project(client, ORGANIZATION_NAME, PROJECT_NAME).await;
+    let instance =
+        create_instance(client, ORGANIZATION_NAME, PROJECT_NAME, "eph").await;
+    let instance_id = instance.identity.id;
+    instance_simulate(nexus, &instance_id).await;
+    let ips_url = format!("{}/eph/external-ips", get_instances_url());
+    let ephemeral_url = format!("{}/ephemeral", ips_url);
+    let ip_params = params::EphemeralIpCreate { pool_name: None };
+
+    // If the sled can't be told about the new address, it's released again.
+    sled_agent
+        .fault_add(
+            SimFaultTarget::Instances,
+            SimFaultRule {
+                id: Some(instance_id),
+                count: Some(1),
+                fault: SimFault::Error { status: 503 },
+            },
+        )
+        .await
+        .unwrap();
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::SERVICE_UNAVAILABLE,
+        Method::POST,
+        &ephemeral_url,
+        &ip_params,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let ips = objects_list_page_authz::<views::ExternalIp>(client, &ips_url)
+        .await
+        .items;
+    assert!(ips.is_empty());
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![])
+    );
+
+    // Attach an Ephemeral IP to the running instance.
+    let ip: views::ExternalIp = NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &ephemeral_url)
+            .body(Some(&ip_params))
+            .expect_status(Some(StatusCode::ACCEPTED)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(ip.kind, IpKind::Ephemeral);
+    let ips = objects_list_page_authz::<views::ExternalIp>(client, &ips_url)
+        .await
+        .items;
+    assert_eq!(ips.len(), 1);
+    assert_eq!(ips[0].ip, ip.ip);
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![ip.ip])
+    );
+
+    // The instance can't have a second one.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &ephemeral_url,
+        &ip_params,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // If the sled can't stop using the address, it's kept.
+    sled_agent
+        .fault_add(
+            SimFaultTarget::Instances,
+            SimFaultRule {
+                id: Some(instance_id),
+                count: Some(1),
+                fault: SimFault::Error { status: 503 },
+            },
+        )
+        .await
+        .unwrap();
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::SERVICE_UNAVAILABLE,
+        Method::DELETE,
+        &ephemeral_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let ips = objects_list_page_authz::<views::ExternalIp>(client, &ips_url)
+        .await
+        .items;
+    assert_eq!(ips.len(), 1);
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![ip.ip])
+    );
+
+    // Detach it for real.
+    NexusRequest::object_delete(client, &ephemeral_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    let ips = objects_list_page_authz::<views::ExternalIp>(client, &ips_url)
+        .await
+        .items;
+    assert!(ips.is_empty());
+    assert_eq!(
+        sled_agent.instance_external_ips(instance_id).await,
+        Some(vec![])
+    );
+
+    // There's nothing left to detach.
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &ephemeral_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
 async fn instance_get(
     client: &ClientTestContext,
     instance_url: &str,
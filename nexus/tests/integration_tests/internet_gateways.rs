@@ -0,0 +1,303 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::http_testing::TestResponse;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::{
+    create_instance, create_ip_pool, create_organization, create_project,
+};
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::IdentityMetadataUpdateParams;
+use omicron_common::api::external::RouteDestination;
+use omicron_common::api::external::RouteTarget;
+use omicron_common::api::external::RouterRoute;
+use omicron_common::api::external::RouterRouteUpdateParams;
+use omicron_nexus::context::OpContext;
+use omicron_nexus::db::model::IpKind;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared::IpRange;
+use omicron_nexus::external_api::views::InternetGateway;
+use omicron_nexus::TestInterfaces as _;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use uuid::Uuid;
+
+#[nexus_test]
+async fn test_internet_gateways(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let organization_name = "test-org";
+    let project_name = "springfield-squidport";
+    create_organization(&client, organization_name).await;
+    create_project(&client, organization_name, project_name).await;
+    let vpc_url = format!(
+        "/organizations/{}/projects/{}/vpcs/default",
+        organization_name, project_name
+    );
+    let gateways_url = format!("{}/internet-gateways", vpc_url);
+    let outbound_url = format!("{}/outbound", gateways_url);
+    let gateway_url = format!("{}/gateway1", gateways_url);
+    let default_route_url =
+        format!("{}/routers/system/routes/default", vpc_url);
+
+    create_ip_pool(&client, "p0", None, None).await;
+    let (pool1, _) =
+        create_ip_pool(&client, "p1", Some(ip_range(10, 1)), None).await;
+    let (pool2, _) =
+        create_ip_pool(&client, "p2", Some(ip_range(10, 2)), None).await;
+
+    // Every VPC has an "outbound" gateway, targeted by its default route,
+    // which has no IP Pools of its own.
+    let outbound = gateway_get(client, &outbound_url).await;
+    assert!(outbound.ip_pool_ids.is_empty());
+    let route: RouterRoute =
+        NexusRequest::object_get(client, &default_route_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert_eq!(
+        route.target,
+        RouteTarget::InternetGateway("outbound".parse().unwrap())
+    );
+
+    // Instances are given SNAT addresses from the gateway's pools, in order.
+    let outbound =
+        gateway_update(client, &outbound_url, &["p2", "p1"], StatusCode::OK)
+            .await
+            .parsed_body::<InternetGateway>()
+            .unwrap();
+    assert_eq!(
+        outbound.ip_pool_ids,
+        vec![pool2.identity.id, pool1.identity.id]
+    );
+    let instance =
+        create_instance(&client, organization_name, project_name, "inst0")
+            .await;
+    let ip = instance_snat_ip(cptestctx, instance.identity.id).await;
+    assert_in_range(ip, &ip_range(10, 2));
+
+    // The pools of a gateway must exist, and can only be given once.
+    gateway_update(client, &outbound_url, &["nope"], StatusCode::NOT_FOUND)
+        .await;
+    gateway_update(
+        client,
+        &outbound_url,
+        &["p1", "p1"],
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+
+    // Create a second gateway and send the VPC's default route through it.
+    let gateway: InternetGateway = NexusRequest::objects_post(
+        client,
+        &gateways_url,
+        &params::InternetGatewayCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "gateway1".parse().unwrap(),
+                description: String::from("a gateway"),
+            },
+            ip_pools: vec!["p1".parse().unwrap()],
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(gateway.ip_pool_ids, vec![pool1.identity.id]);
+    let gateways =
+        objects_list_page_authz::<InternetGateway>(client, &gateways_url)
+            .await
+            .items;
+    assert_eq!(gateways.len(), 2);
+
+    // Routes can only target gateways that exist.
+    route_update(client, &default_route_url, "nope", StatusCode::BAD_REQUEST)
+        .await;
+    route_update(client, &default_route_url, "gateway1", StatusCode::OK).await;
+    let instance =
+        create_instance(&client, organization_name, project_name, "inst1")
+            .await;
+    let ip = instance_snat_ip(cptestctx, instance.identity.id).await;
+    assert_in_range(ip, &ip_range(10, 1));
+
+    // A gateway can't be renamed or deleted while routes target it, and its
+    // pools can't be deleted while it uses them.
+    let error = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::PUT,
+        &gateway_url,
+        &params::InternetGatewayUpdate {
+            identity: IdentityMetadataUpdateParams {
+                name: Some("gateway2".parse().unwrap()),
+                description: None,
+            },
+            ip_pools: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert!(error.message.contains("routes target it"), "{}", error.message);
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &gateway_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let error = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        "/system/ip-pools/p2",
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert!(error.message.contains("internet gateway"), "{}", error.message);
+
+    // The "outbound" gateway is no longer targeted, so it can be deleted.
+    NexusRequest::object_delete(client, &outbound_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &outbound_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+/// Returns the range of 256 addresses starting at `a.b.0.0`
+fn ip_range(a: u8, b: u8) -> IpRange {
+    IpRange::try_from((Ipv4Addr::new(a, b, 0, 0), Ipv4Addr::new(a, b, 0, 255)))
+        .unwrap()
+}
+
+fn assert_in_range(ip: IpAddr, range: &IpRange) {
+    assert!(
+        range.first_address() <= ip && ip <= range.last_address(),
+        "{} is outside of {:?}",
+        ip,
+        range
+    );
+}
+
+async fn gateway_get(
+    client: &ClientTestContext,
+    gateway_url: &str,
+) -> InternetGateway {
+    NexusRequest::object_get(client, gateway_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+/// Replace the IP Pools of an internet gateway
+async fn gateway_update(
+    client: &ClientTestContext,
+    gateway_url: &str,
+    ip_pools: &[&str],
+    status: StatusCode,
+) -> TestResponse {
+    let update = params::InternetGatewayUpdate {
+        identity: IdentityMetadataUpdateParams {
+            name: None,
+            description: None,
+        },
+        ip_pools: Some(
+            ip_pools.iter().map(|name| name.parse().unwrap()).collect(),
+        ),
+    };
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::PUT, gateway_url)
+            .body(Some(&update))
+            .expect_status(Some(status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
+/// Point a VPC's default route at the internet gateway `gateway_name`
+async fn route_update(
+    client: &ClientTestContext,
+    route_url: &str,
+    gateway_name: &str,
+    status: StatusCode,
+) {
+    let update = RouterRouteUpdateParams {
+        identity: IdentityMetadataUpdateParams {
+            name: None,
+            description: None,
+        },
+        target: RouteTarget::InternetGateway(gateway_name.parse().unwrap()),
+        destination: RouteDestination::Vpc("default".parse().unwrap()),
+    };
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::PUT, route_url)
+            .body(Some(&update))
+            .expect_status(Some(status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+/// Returns the SNAT address allocated to an instance
+async fn instance_snat_ip(
+    cptestctx: &ControlPlaneTestContext,
+    instance_id: Uuid,
+) -> IpAddr {
+    let nexus = &cptestctx.server.apictx.nexus;
+    let opctx = OpContext::for_tests(
+        cptestctx.logctx.log.new(o!()),
+        nexus.datastore().clone(),
+    );
+    nexus
+        .datastore()
+        .instance_lookup_external_ips(&opctx, instance_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|ip| ip.kind == IpKind::SNat)
+        .expect("instance has no SNAT address")
+        .ip
+        .ip()
+}
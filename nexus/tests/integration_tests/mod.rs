@@ -3,6 +3,8 @@
 //! See the driver in the parent directory for how and why this is structured
 //! the way it is.
 
+mod api_tokens;
+mod audit_log;
 mod authn_http;
 mod authz;
 mod basic;
@@ -11,17 +13,23 @@ mod console_api;
 mod datasets;
 mod device_auth;
 mod disks;
+mod floating_ips;
 mod images;
 mod instances;
+mod internet_gateways;
 mod ip_pools;
+mod oidc;
 mod organizations;
 mod oximeter;
 mod projects;
+mod quotas;
 mod rack;
 mod role_assignments;
 mod roles_builtin;
 mod router_routes;
+mod sagas;
 mod saml;
+mod scim;
 mod silos;
 mod snapshots;
 mod ssh_keys;
@@ -33,6 +41,7 @@ mod updates;
 mod users_builtin;
 mod volume_management;
 mod vpc_firewall;
+mod vpc_peerings;
 mod vpc_routers;
 mod vpc_subnets;
 mod vpcs;
@@ -0,0 +1,463 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for OpenID Connect identity providers and the login flow through them
+
+use dropshot::test_util::ClientTestContext;
+use dropshot::ResultsPage;
+use http::method::Method;
+use http::StatusCode;
+use httptest::{matchers::*, responders::*, Expectation, Server};
+use nexus_test_utils::http_testing::{NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::{create_silo, object_create};
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::external_api::views;
+use omicron_nexus::external_api::{params, shared};
+
+const SILO_NAME: &str = "oidc-silo";
+const PROVIDER_NAME: &str = "oidc-provider";
+const CLIENT_ID: &str = "oxide-rack";
+const KEY_ID: &str = "key1";
+
+/// A fake OpenID provider, which signs ID tokens with its own RSA key
+struct FakeOpenIdProvider {
+    server: Server,
+    issuer: String,
+    encoding_key: jsonwebtoken::EncodingKey,
+}
+
+impl FakeOpenIdProvider {
+    fn new() -> FakeOpenIdProvider {
+        let server = Server::run();
+        let issuer =
+            server.url("/").to_string().trim_end_matches('/').to_string();
+
+        server.expect(
+            Expectation::matching(request::method_path(
+                "GET",
+                "/.well-known/openid-configuration",
+            ))
+            .respond_with(json_encoded(serde_json::json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{}/authorize", issuer),
+                "token_endpoint": format!("{}/token", issuer),
+                "jwks_uri": format!("{}/jwks", issuer),
+            }))),
+        );
+
+        let rsa = openssl::rsa::Rsa::generate(2048).unwrap();
+        let encode = |bytes: Vec<u8>| {
+            base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+        };
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/jwks"))
+                .times(..)
+                .respond_with(json_encoded(serde_json::json!({
+                    "keys": [{
+                        "kty": "RSA",
+                        "kid": KEY_ID,
+                        "use": "sig",
+                        "n": encode(rsa.n().to_vec()),
+                        "e": encode(rsa.e().to_vec()),
+                    }]
+                }))),
+        );
+        let encoding_key = jsonwebtoken::EncodingKey::from_rsa_pem(
+            &rsa.private_key_to_pem().unwrap(),
+        )
+        .unwrap();
+
+        FakeOpenIdProvider { server, issuer, encoding_key }
+    }
+
+    /// Make the token endpoint exchange `code` for an ID token with the given
+    /// claims (in addition to issuer, audience, and expiry)
+    fn expect_code(&self, code: &str, claims: serde_json::Value) {
+        let mut claims = claims;
+        let claims_map = claims.as_object_mut().unwrap();
+        claims_map.insert("iss".to_string(), self.issuer.clone().into());
+        claims_map.insert("aud".to_string(), CLIENT_ID.into());
+        claims_map.insert(
+            "exp".to_string(),
+            (chrono::Utc::now() + chrono::Duration::minutes(5))
+                .timestamp()
+                .into(),
+        );
+
+        let mut header =
+            jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+        header.kid = Some(KEY_ID.to_string());
+        let id_token =
+            jsonwebtoken::encode(&header, &claims, &self.encoding_key).unwrap();
+
+        self.server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/token"),
+                request::body(url_decoded(contains((
+                    "code",
+                    code.to_string()
+                )))),
+            ])
+            .respond_with(json_encoded(serde_json::json!({
+                "access_token": "unused",
+                "token_type": "Bearer",
+                "id_token": id_token,
+            }))),
+        );
+    }
+}
+
+/// The state of a login that has been started, as the browser sees it
+struct LoginStarted {
+    state: String,
+    nonce: String,
+    cookie: String,
+}
+
+async fn create_provider(
+    client: &ClientTestContext,
+    provider: &FakeOpenIdProvider,
+) -> views::OidcIdentityProvider {
+    create_silo(&client, SILO_NAME, true, shared::SiloIdentityMode::OidcJit)
+        .await;
+    object_create(
+        client,
+        &format!("/system/silos/{}/identity-providers/oidc", SILO_NAME),
+        &params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a demo provider".to_string(),
+            },
+            issuer: provider.issuer.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "client-secret".to_string(),
+            scopes: vec!["email".to_string()],
+            redirect_url: format!(
+                "https://customer.site/login/{}/oidc/{}/callback",
+                SILO_NAME, PROVIDER_NAME
+            ),
+            group_claim_name: Some("groups".to_string()),
+        },
+    )
+    .await
+}
+
+async fn login_begin(client: &ClientTestContext, issuer: &str) -> LoginStarted {
+    let result = NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::GET,
+            &format!("/login/{}/oidc/{}", SILO_NAME, PROVIDER_NAME),
+        )
+        .expect_status(Some(StatusCode::FOUND)),
+    )
+    .execute()
+    .await
+    .expect("expected redirect to the OpenID provider");
+
+    let location = reqwest::Url::parse(
+        result.headers[http::header::LOCATION].to_str().unwrap(),
+    )
+    .unwrap();
+    assert!(location.as_str().starts_with(&format!("{}/authorize?", issuer)));
+    let query = |name: &str| {
+        location
+            .query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+            .unwrap_or_else(|| panic!("no {} in {}", name, location))
+    };
+    assert_eq!(query("client_id"), CLIENT_ID);
+    assert_eq!(query("response_type"), "code");
+    assert_eq!(query("scope"), "openid email");
+
+    let cookie = result.headers[http::header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    LoginStarted { state: query("state"), nonce: query("nonce"), cookie }
+}
+
+async fn login_callback(
+    client: &ClientTestContext,
+    login: &LoginStarted,
+    code: &str,
+    state: &str,
+    status: StatusCode,
+) -> http::HeaderMap {
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::GET,
+            &format!(
+                "/login/{}/oidc/{}/callback?code={}&state={}",
+                SILO_NAME, PROVIDER_NAME, code, state
+            ),
+        )
+        .header(http::header::COOKIE, login.cookie.clone())
+        .expect_status(Some(status)),
+    )
+    .execute()
+    .await
+    .expect("unexpected callback response")
+    .headers
+}
+
+#[nexus_test]
+async fn test_create_an_oidc_idp(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let provider = FakeOpenIdProvider::new();
+
+    let created = create_provider(client, &provider).await;
+    assert_eq!(created.issuer, provider.issuer);
+    assert_eq!(created.scopes, vec!["email".to_string()]);
+
+    let fetched: views::OidcIdentityProvider = NexusRequest::object_get(
+        client,
+        &format!(
+            "/system/silos/{}/identity-providers/oidc/{}",
+            SILO_NAME, PROVIDER_NAME
+        ),
+    )
+    .authn_as(nexus_test_utils::http_testing::AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(fetched.identity.id, created.identity.id);
+    assert_eq!(fetched.client_id, CLIENT_ID);
+
+    // OpenID Connect providers can't be created in a SAML silo.
+    create_silo(&client, "saml-silo", true, shared::SiloIdentityMode::SamlJit)
+        .await;
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            "/system/silos/saml-silo/identity-providers/oidc",
+        )
+        .body(Some(&params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a demo provider".to_string(),
+            },
+            issuer: provider.issuer.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "client-secret".to_string(),
+            scopes: vec![],
+            redirect_url: "https://customer.site/callback".to_string(),
+            group_claim_name: None,
+        }))
+        .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(nexus_test_utils::http_testing::AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+#[nexus_test]
+async fn test_oidc_idp_bad_issuer(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/.well-known/openid-configuration",
+        ))
+        .respond_with(status_code(404)),
+    );
+
+    create_silo(&client, SILO_NAME, true, shared::SiloIdentityMode::OidcJit)
+        .await;
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &format!("/system/silos/{}/identity-providers/oidc", SILO_NAME),
+        )
+        .body(Some(&params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a demo provider".to_string(),
+            },
+            issuer: server.url("/").to_string(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "client-secret".to_string(),
+            scopes: vec![],
+            redirect_url: "https://customer.site/callback".to_string(),
+            group_claim_name: None,
+        }))
+        .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(nexus_test_utils::http_testing::AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+// Test the whole login flow, and asking whoami afterwards
+#[nexus_test]
+async fn test_oidc_login(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let provider = FakeOpenIdProvider::new();
+    create_provider(client, &provider).await;
+
+    let login = login_begin(client, &provider.issuer).await;
+    provider.expect_code(
+        "good-code",
+        serde_json::json!({
+            "sub": "some@customer.com",
+            "nonce": login.nonce,
+            "groups": ["SRE", "Admins"],
+        }),
+    );
+
+    // The state must be the one the login was started with.
+    login_callback(
+        client,
+        &login,
+        "good-code",
+        "bogus-state",
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+
+    let headers = login_callback(
+        client,
+        &login,
+        "good-code",
+        &login.state,
+        StatusCode::SEE_OTHER,
+    )
+    .await;
+    assert_eq!(headers[http::header::LOCATION].to_str().unwrap(), "/");
+    let session_cookie = headers
+        .get_all(http::header::SET_COOKIE)
+        .iter()
+        .map(|value| value.to_str().unwrap().to_string())
+        .find(|value| value.starts_with("session="))
+        .expect("no session cookie");
+
+    let session_me: views::User = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/session/me")
+            .header(http::header::COOKIE, session_cookie.clone())
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute()
+    .await
+    .expect("expected success")
+    .parsed_body()
+    .unwrap();
+    assert_eq!(session_me.display_name, "some@customer.com");
+
+    let groups: ResultsPage<views::Group> = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/session/me/groups")
+            .header(http::header::COOKIE, session_cookie)
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute()
+    .await
+    .expect("expected success")
+    .parsed_body()
+    .unwrap();
+    let mut group_names: Vec<&str> =
+        groups.items.iter().map(|g| g.display_name.as_str()).collect();
+    group_names.sort_unstable();
+    assert_eq!(group_names, vec!["Admins", "SRE"]);
+}
+
+// Test that ID tokens are rejected unless they're for this login and signed by
+// the provider
+#[nexus_test]
+async fn test_oidc_login_bad_token(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let provider = FakeOpenIdProvider::new();
+    create_provider(client, &provider).await;
+
+    let login = login_begin(client, &provider.issuer).await;
+
+    // A token for some other login
+    provider.expect_code(
+        "wrong-nonce",
+        serde_json::json!({ "sub": "some@customer.com", "nonce": "bogus" }),
+    );
+    login_callback(
+        client,
+        &login,
+        "wrong-nonce",
+        &login.state,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+
+    // A token signed by somebody else
+    let impostor_key = jsonwebtoken::EncodingKey::from_rsa_pem(
+        &openssl::rsa::Rsa::generate(2048)
+            .unwrap()
+            .private_key_to_pem()
+            .unwrap(),
+    )
+    .unwrap();
+    let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+    header.kid = Some(KEY_ID.to_string());
+    let id_token = jsonwebtoken::encode(
+        &header,
+        &serde_json::json!({
+            "sub": "some@customer.com",
+            "nonce": login.nonce,
+            "iss": provider.issuer,
+            "aud": CLIENT_ID,
+            "exp": (chrono::Utc::now() + chrono::Duration::minutes(5))
+                .timestamp(),
+        }),
+        &impostor_key,
+    )
+    .unwrap();
+    provider.server.expect(
+        Expectation::matching(all_of![
+            request::method_path("POST", "/token"),
+            request::body(url_decoded(contains((
+                "code",
+                "wrong-key".to_string()
+            )))),
+        ])
+        .respond_with(json_encoded(serde_json::json!({
+            "access_token": "unused",
+            "token_type": "Bearer",
+            "id_token": id_token,
+        }))),
+    );
+    login_callback(
+        client,
+        &login,
+        "wrong-key",
+        &login.state,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+
+    // The provider reported an error
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::GET,
+            &format!(
+                "/login/{}/oidc/{}/callback?error=access_denied&state={}",
+                SILO_NAME, PROVIDER_NAME, login.state
+            ),
+        )
+        .header(http::header::COOKIE, login.cookie.clone())
+        .expect_status(Some(StatusCode::UNAUTHORIZED)),
+    )
+    .execute()
+    .await
+    .unwrap();
+}
@@ -2,11 +2,29 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
 use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils::resource_helpers::project_get;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Ipv4Net;
+use omicron_common::api::external::NetworkInterface;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared::IpRange;
+use omicron_nexus::external_api::views::FloatingIp;
 use omicron_nexus::external_api::views::Project;
+use omicron_nexus::external_api::views::ProjectSettings;
+use omicron_nexus::external_api::views::VpcSubnet;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
 
-use nexus_test_utils::resource_helpers::{create_organization, create_project};
+use nexus_test_utils::resource_helpers::{
+    create_instance, create_ip_pool, create_organization, create_project,
+    create_vpc,
+};
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
 
@@ -70,3 +88,175 @@ async fn test_projects(cptestctx: &ControlPlaneTestContext) {
     assert_eq!(projects.len(), 1);
     assert_eq!(projects[0].identity.name, p1_name);
 }
+
+#[nexus_test]
+async fn test_project_settings(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let org_name = "test-org";
+    let project_name = "springfield-squidport";
+    create_organization(&client, &org_name).await;
+    create_project(&client, &org_name, &project_name).await;
+    let project_url =
+        format!("/organizations/{}/projects/{}", org_name, project_name);
+    let settings_url = format!("{}/settings", project_url);
+
+    // Projects start without settings, and use the resources named "default".
+    let settings: ProjectSettings =
+        NexusRequest::object_get(client, &settings_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert_eq!(settings.default_vpc_id, None);
+    assert_eq!(settings.default_vpc_subnet_id, None);
+    assert_eq!(settings.default_ip_pool_id, None);
+
+    create_ip_pool(&client, "p0", None, None).await;
+    let (pool, _) = create_ip_pool(
+        &client,
+        "p1",
+        Some(
+            IpRange::try_from((
+                Ipv4Addr::new(10, 1, 0, 0),
+                Ipv4Addr::new(10, 1, 0, 255),
+            ))
+            .unwrap(),
+        ),
+        None,
+    )
+    .await;
+    let vpc = create_vpc(&client, org_name, project_name, "vpc2").await;
+    let subnet: VpcSubnet = NexusRequest::objects_post(
+        client,
+        &format!("{}/vpcs/vpc2/subnets", project_url),
+        &params::VpcSubnetCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "subnet2".parse().unwrap(),
+                description: String::from("a subnet"),
+            },
+            ipv4_block: Ipv4Net("10.5.0.0/24".parse().unwrap()),
+            ipv6_block: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+
+    // The default VPC Subnet must be in the default VPC.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::PUT,
+        &settings_url,
+        &params::ProjectSettingsUpdate {
+            default_vpc: None,
+            default_vpc_subnet: Some("subnet2".parse().unwrap()),
+            default_ip_pool: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let settings: ProjectSettings = NexusRequest::object_put(
+        client,
+        &settings_url,
+        Some(&params::ProjectSettingsUpdate {
+            default_vpc: Some("vpc2".parse().unwrap()),
+            default_vpc_subnet: Some("subnet2".parse().unwrap()),
+            default_ip_pool: Some("p1".parse().unwrap()),
+        }),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(settings.default_vpc_id, Some(vpc.identity.id));
+    assert_eq!(settings.default_vpc_subnet_id, Some(subnet.identity.id));
+    assert_eq!(settings.default_ip_pool_id, Some(pool.identity.id));
+
+    // Instances get their default interface in the default VPC Subnet.
+    create_instance(&client, org_name, project_name, "inst0").await;
+    let interfaces = objects_list_page_authz::<NetworkInterface>(
+        client,
+        &format!("{}/instances/inst0/network-interfaces", project_url),
+    )
+    .await
+    .items;
+    assert_eq!(interfaces.len(), 1);
+    assert_eq!(interfaces[0].vpc_id, vpc.identity.id);
+    assert_eq!(interfaces[0].subnet_id, subnet.identity.id);
+
+    // External IPs come from the default IP Pool.
+    let floating_ip: FloatingIp = NexusRequest::objects_post(
+        client,
+        &format!("{}/floating-ips", project_url),
+        &params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "fip0".parse().unwrap(),
+                description: String::from("a floating IP"),
+            },
+            pool_name: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    match floating_ip.ip {
+        IpAddr::V4(ip) => assert_eq!(ip.octets()[..3], [10, 1, 0]),
+        IpAddr::V6(ip) => panic!("unexpected IPv6 address {}", ip),
+    }
+
+    // The default resources can't be deleted.
+    for url in [
+        format!("{}/vpcs/vpc2/subnets/subnet2", project_url),
+        format!("{}/vpcs/vpc2", project_url),
+        String::from("/system/ip-pools/p1"),
+    ] {
+        let error: HttpErrorResponseBody = NexusRequest::expect_failure(
+            client,
+            StatusCode::BAD_REQUEST,
+            Method::DELETE,
+            &url,
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap();
+        assert!(error.message.contains("default"), "{}", error.message);
+    }
+
+    // Resetting the settings falls back to the resources named "default".
+    let settings: ProjectSettings = NexusRequest::object_put(
+        client,
+        &settings_url,
+        Some(&params::ProjectSettingsUpdate {
+            default_vpc: None,
+            default_vpc_subnet: None,
+            default_ip_pool: None,
+        }),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(settings.default_vpc_id, None);
+    assert_eq!(settings.default_vpc_subnet_id, None);
+    assert_eq!(settings.default_ip_pool_id, None);
+}
@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for Silo and Project resource quotas
+
+use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::{method::Method, StatusCode};
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest};
+use nexus_test_utils::resource_helpers::create_disk;
+use nexus_test_utils::resource_helpers::create_organization;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::DiskTest;
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::authn;
+use omicron_nexus::authz;
+use omicron_nexus::context::OpContext;
+use omicron_nexus::db::fixed_data::silo::DEFAULT_SILO;
+use omicron_nexus::db::identity::Resource;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::ResourceQuotas;
+use std::sync::Arc;
+
+const ORG_NAME: &str = "quota-org";
+const PROJECT_NAME: &str = "quota-project";
+const PROJECT_QUOTAS_URL: &str =
+    "/organizations/quota-org/projects/quota-project/quotas";
+const DISKS_URL: &str = "/organizations/quota-org/projects/quota-project/disks";
+
+async fn quotas_get(client: &ClientTestContext, url: &str) -> ResourceQuotas {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to fetch quotas")
+        .parsed_body()
+        .unwrap()
+}
+
+async fn quotas_put(
+    client: &ClientTestContext,
+    url: &str,
+    params: &params::ResourceQuotasUpdate,
+) -> ResourceQuotas {
+    NexusRequest::object_put(client, url, Some(params))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to update quotas")
+        .parsed_body()
+        .unwrap()
+}
+
+#[nexus_test]
+async fn test_project_disk_quota(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    create_organization(client, ORG_NAME).await;
+    create_project(client, ORG_NAME, PROJECT_NAME).await;
+
+    // A new project has no limits, and nothing allocated.
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.limits.disk, None);
+    assert_eq!(quotas.usage.disk.to_bytes(), 0);
+
+    let quotas = quotas_put(
+        client,
+        PROJECT_QUOTAS_URL,
+        &params::ResourceQuotasUpdate {
+            disk: Some(ByteCount::from_gibibytes_u32(1)),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(quotas.limits.disk, Some(ByteCount::from_gibibytes_u32(1)));
+    assert_eq!(quotas.limits.cpus, None);
+
+    // A disk which fits within the quota is counted against it.
+    create_disk(client, ORG_NAME, PROJECT_NAME, "disk1").await;
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk, ByteCount::from_gibibytes_u32(1));
+
+    // A second disk does not fit.
+    let error = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        DISKS_URL,
+        &params::DiskCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "disk2".parse().unwrap(),
+                description: String::from("one too many"),
+            },
+            disk_source: params::DiskSource::Blank {
+                block_size: params::BlockSize::try_from(512).unwrap(),
+            },
+            size: ByteCount::from_gibibytes_u32(1),
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("unexpectedly created disk beyond the quota")
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert!(
+        error.message.starts_with("project quota exceeded"),
+        "unexpected error: {}",
+        error.message
+    );
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk, ByteCount::from_gibibytes_u32(1));
+
+    // Deleting the disk frees its space.
+    NexusRequest::object_delete(client, &format!("{}/disk1", DISKS_URL))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete disk");
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk.to_bytes(), 0);
+
+    // Now the second disk fits.
+    create_disk(client, ORG_NAME, PROJECT_NAME, "disk2").await;
+}
+
+#[nexus_test]
+async fn test_silo_quotas(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    let silo_quotas_url =
+        format!("/system/silos/{}/quotas", DEFAULT_SILO.name());
+    create_organization(client, ORG_NAME).await;
+    create_project(client, ORG_NAME, PROJECT_NAME).await;
+
+    // Allocations within a project count against its Silo, too.
+    let before = quotas_get(client, &silo_quotas_url).await;
+    create_disk(client, ORG_NAME, PROJECT_NAME, "disk1").await;
+    let after = quotas_get(client, &silo_quotas_url).await;
+    assert_eq!(
+        after.usage.disk.to_bytes() - before.usage.disk.to_bytes(),
+        ByteCount::from_gibibytes_u32(1).to_bytes()
+    );
+
+    // A Silo limit applies even when the project has none.
+    quotas_put(
+        client,
+        &silo_quotas_url,
+        &params::ResourceQuotasUpdate {
+            disk: Some(after.usage.disk),
+            ..Default::default()
+        },
+    )
+    .await;
+    let error = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        DISKS_URL,
+        &params::DiskCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "disk2".parse().unwrap(),
+                description: String::from("one too many"),
+            },
+            disk_source: params::DiskSource::Blank {
+                block_size: params::BlockSize::try_from(512).unwrap(),
+            },
+            size: ByteCount::from_gibibytes_u32(1),
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("unexpectedly created disk beyond the quota")
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert!(
+        error.message.starts_with("silo quota exceeded"),
+        "unexpected error: {}",
+        error.message
+    );
+
+    // Clearing the limit allows the allocation again.
+    quotas_put(
+        client,
+        &silo_quotas_url,
+        &params::ResourceQuotasUpdate::default(),
+    )
+    .await;
+    create_disk(client, ORG_NAME, PROJECT_NAME, "disk2").await;
+}
+
+#[nexus_test]
+async fn test_usage_backfill(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    let silo_quotas_url =
+        format!("/system/silos/{}/quotas", DEFAULT_SILO.name());
+    create_organization(client, ORG_NAME).await;
+    create_project(client, ORG_NAME, PROJECT_NAME).await;
+    create_disk(client, ORG_NAME, PROJECT_NAME, "disk1").await;
+
+    // Forget that the disk was ever counted, and that resources were
+    // counted at all, as though the disk had been created before Nexus
+    // counted resources.
+    let db = cptestctx
+        .database
+        .connect()
+        .await
+        .expect("failed to connect to database");
+    db.execute("DELETE FROM omicron.public.resource_quota", &[])
+        .await
+        .expect("failed to clear resource usage");
+    db.execute(
+        "DELETE FROM omicron.public.db_metadata \
+        WHERE name = 'resource_usage_backfilled'",
+        &[],
+    )
+    .await
+    .expect("failed to forget resource usage backfill");
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk.to_bytes(), 0);
+
+    // Counting resources (as Nexus does when it starts) finds the disk again.
+    let nexus = &cptestctx.server.apictx.nexus;
+    let log = cptestctx.logctx.log.new(o!());
+    let opctx = OpContext::for_background(
+        log.clone(),
+        Arc::new(authz::Authz::new(&log)),
+        authn::Context::internal_db_init(),
+        nexus.datastore().clone(),
+    );
+    nexus.datastore().resource_usage_backfill(&opctx).await.unwrap();
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk, ByteCount::from_gibibytes_u32(1));
+    let quotas = quotas_get(client, &silo_quotas_url).await;
+    assert_eq!(quotas.usage.disk, ByteCount::from_gibibytes_u32(1));
+
+    // So it can be deleted, and its space freed.
+    NexusRequest::object_delete(client, &format!("{}/disk1", DISKS_URL))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete disk");
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk.to_bytes(), 0);
+    let quotas = quotas_get(client, &silo_quotas_url).await;
+    assert_eq!(quotas.usage.disk.to_bytes(), 0);
+
+    // Resources are only counted that way once.  From then on, the counts
+    // are kept up to date as resources come and go, so they aren't recounted
+    // even if they've been lost.
+    create_disk(client, ORG_NAME, PROJECT_NAME, "disk2").await;
+    db.execute("DELETE FROM omicron.public.resource_quota", &[])
+        .await
+        .expect("failed to clear resource usage");
+    db.cleanup().await.expect("failed to clean up connection");
+    nexus.datastore().resource_usage_backfill(&opctx).await.unwrap();
+    let quotas = quotas_get(client, PROJECT_QUOTAS_URL).await;
+    assert_eq!(quotas.usage.disk.to_bytes(), 0);
+}
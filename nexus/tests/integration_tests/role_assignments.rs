@@ -12,10 +12,16 @@ use http::Method;
 use http::StatusCode;
 use nexus_test_utils::http_testing::AuthnMode;
 use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::resource_helpers::create_disk;
+use nexus_test_utils::resource_helpers::create_instance;
 use nexus_test_utils::resource_helpers::create_organization;
 use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::DiskTest;
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::Disk;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::Name;
 use omicron_common::api::external::ObjectIdentity;
 use omicron_nexus::authn::USER_TEST_UNPRIVILEGED;
 use omicron_nexus::authz;
@@ -23,6 +29,7 @@ use omicron_nexus::db::fixed_data;
 use omicron_nexus::db::identity::Asset;
 use omicron_nexus::db::identity::Resource;
 use omicron_nexus::db::model::DatabaseString;
+use omicron_nexus::external_api::params;
 use omicron_nexus::external_api::shared;
 use omicron_nexus::external_api::views;
 
@@ -40,6 +47,8 @@ use omicron_nexus::external_api::views;
 /// - verifies privileged conditions (usually: previously-unprivileged user now
 ///   has access)
 /// - revokes the admin role on the resource using the newly-privileged user
+///   (or, if that role does not allow changing the policy, verifies that the
+///   newly-privileged user cannot, and revokes it using the privileged user)
 /// - verifies the initial conditions again
 ///
 /// All together, this verifies basic policy CRUD, plus that the corresponding
@@ -63,6 +72,10 @@ trait RoleAssignmentTest {
     /// Whether this resource is always visible to unprivileged users
     const VISIBLE_TO_UNPRIVILEGED: bool;
 
+    /// Whether a user granted `ROLE` on this resource can change its policy
+    /// (and so revoke their own access)
+    const ROLE_CAN_MODIFY_POLICY: bool = true;
+
     /// Returns the URL of the policy to be checked and updated by the test
     fn policy_url(&self) -> String;
 
@@ -398,12 +411,313 @@ async fn test_role_assignments_project(cptestctx: &ControlPlaneTestContext) {
     run_test(client, test_case).await;
 }
 
+#[nexus_test]
+async fn test_role_assignments_instance(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let org_name = "test-org";
+    let project_name = "test-project";
+    create_organization(client, org_name).await;
+    create_project(client, org_name, project_name).await;
+    let instance_name = "test-instance";
+    create_instance(client, org_name, project_name, instance_name).await;
+    let project_url =
+        format!("/organizations/{}/projects/{}", org_name, project_name);
+    let instance_url = format!("{}/instances/{}", project_url, instance_name);
+
+    struct InstanceRoleAssignmentTest {
+        project_url: String,
+        instance_name: String,
+        instance_url: String,
+        policy_url: String,
+    }
+    let test_case = InstanceRoleAssignmentTest {
+        project_url: project_url.clone(),
+        instance_name: String::from(instance_name),
+        instance_url: instance_url.clone(),
+        policy_url: format!("{}/policy", instance_url),
+    };
+    impl RoleAssignmentTest for InstanceRoleAssignmentTest {
+        type RoleType = authz::InstanceRole;
+        const ROLE: Self::RoleType = authz::InstanceRole::Operator;
+        const VISIBLE_TO_UNPRIVILEGED: bool = false;
+        const ROLE_CAN_MODIFY_POLICY: bool = false;
+        fn policy_url(&self) -> String {
+            self.policy_url.clone()
+        }
+
+        fn verify_initial<'a, 'b, 'c, 'd>(
+            &'a self,
+            client: &'b ClientTestContext,
+            current_policy: &'c shared::Policy<Self::RoleType>,
+        ) -> BoxFuture<'d, ()>
+        where
+            'a: 'd,
+            'b: 'd,
+            'c: 'd,
+        {
+            resource_initial_conditions(
+                client,
+                &self.instance_url,
+                current_policy,
+            )
+            .boxed()
+        }
+
+        fn verify_privileged<'a, 'b, 'c>(
+            &'a self,
+            client: &'b ClientTestContext,
+        ) -> BoxFuture<'c, ()>
+        where
+            'a: 'c,
+            'b: 'c,
+        {
+            async move {
+                resource_privileged_conditions::<Instance>(
+                    client,
+                    &self.instance_url,
+                    &self.instance_name,
+                )
+                .await;
+
+                // Operators can also modify the Instance, and see what's in it.
+                let instance: Instance = NexusRequest::object_put(
+                    client,
+                    &format!("{}/tags", self.instance_url),
+                    Some(&params::InstanceTagsUpdate {
+                        tags: vec!["shared".parse().unwrap()],
+                    }),
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap()
+                .parsed_body()
+                .unwrap();
+                assert_eq!(
+                    instance.tags,
+                    vec!["shared".parse::<Name>().unwrap()]
+                );
+                NexusRequest::object_get(
+                    client,
+                    &format!("{}/network-interfaces", self.instance_url),
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap();
+
+                // But they can't delete it.
+                NexusRequest::expect_failure(
+                    client,
+                    StatusCode::FORBIDDEN,
+                    Method::DELETE,
+                    &self.instance_url,
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap();
+
+                // The role grants nothing on the Project, though.
+                NexusRequest::expect_failure(
+                    client,
+                    StatusCode::NOT_FOUND,
+                    Method::GET,
+                    &self.project_url,
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap();
+            }
+            .boxed()
+        }
+    }
+
+    run_test(client, test_case).await;
+}
+
+#[nexus_test]
+async fn test_role_assignments_disk(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let org_name = "test-org";
+    let project_name = "test-project";
+    DiskTest::new(&cptestctx).await;
+    create_organization(client, org_name).await;
+    create_project(client, org_name, project_name).await;
+    let disk_name = "test-disk";
+    create_disk(client, org_name, project_name, disk_name).await;
+    let project_url =
+        format!("/organizations/{}/projects/{}", org_name, project_name);
+    let disk_url = format!("{}/disks/{}", project_url, disk_name);
+
+    struct DiskRoleAssignmentTest {
+        project_url: String,
+        disk_name: String,
+        disk_url: String,
+        policy_url: String,
+    }
+    let test_case = DiskRoleAssignmentTest {
+        project_url: project_url.clone(),
+        disk_name: String::from(disk_name),
+        disk_url: disk_url.clone(),
+        policy_url: format!("{}/policy", disk_url),
+    };
+    impl RoleAssignmentTest for DiskRoleAssignmentTest {
+        type RoleType = authz::DiskRole;
+        const ROLE: Self::RoleType = authz::DiskRole::Viewer;
+        const VISIBLE_TO_UNPRIVILEGED: bool = false;
+        const ROLE_CAN_MODIFY_POLICY: bool = false;
+        fn policy_url(&self) -> String {
+            self.policy_url.clone()
+        }
+
+        fn verify_initial<'a, 'b, 'c, 'd>(
+            &'a self,
+            client: &'b ClientTestContext,
+            current_policy: &'c shared::Policy<Self::RoleType>,
+        ) -> BoxFuture<'d, ()>
+        where
+            'a: 'd,
+            'b: 'd,
+            'c: 'd,
+        {
+            resource_initial_conditions(client, &self.disk_url, current_policy)
+                .boxed()
+        }
+
+        fn verify_privileged<'a, 'b, 'c>(
+            &'a self,
+            client: &'b ClientTestContext,
+        ) -> BoxFuture<'c, ()>
+        where
+            'a: 'c,
+            'b: 'c,
+        {
+            async move {
+                resource_privileged_conditions::<Disk>(
+                    client,
+                    &self.disk_url,
+                    &self.disk_name,
+                )
+                .await;
+
+                // The role grants nothing on the Project, though.
+                NexusRequest::expect_failure(
+                    client,
+                    StatusCode::NOT_FOUND,
+                    Method::GET,
+                    &self.project_url,
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap();
+            }
+            .boxed()
+        }
+    }
+
+    run_test(client, test_case).await;
+}
+
+#[nexus_test]
+async fn test_role_assignments_vpc(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let org_name = "test-org";
+    let project_name = "test-project";
+    create_organization(client, org_name).await;
+    create_project(client, org_name, project_name).await;
+    // Use the VPC created along with the Project.
+    let vpc_name = "default";
+    let project_url =
+        format!("/organizations/{}/projects/{}", org_name, project_name);
+    let vpc_url = format!("{}/vpcs/{}", project_url, vpc_name);
+
+    struct VpcRoleAssignmentTest {
+        project_url: String,
+        vpc_name: String,
+        vpc_url: String,
+        policy_url: String,
+    }
+    let test_case = VpcRoleAssignmentTest {
+        project_url: project_url.clone(),
+        vpc_name: String::from(vpc_name),
+        vpc_url: vpc_url.clone(),
+        policy_url: format!("{}/policy", vpc_url),
+    };
+    impl RoleAssignmentTest for VpcRoleAssignmentTest {
+        type RoleType = authz::VpcRole;
+        const ROLE: Self::RoleType = authz::VpcRole::Viewer;
+        const VISIBLE_TO_UNPRIVILEGED: bool = false;
+        const ROLE_CAN_MODIFY_POLICY: bool = false;
+        fn policy_url(&self) -> String {
+            self.policy_url.clone()
+        }
+
+        fn verify_initial<'a, 'b, 'c, 'd>(
+            &'a self,
+            client: &'b ClientTestContext,
+            current_policy: &'c shared::Policy<Self::RoleType>,
+        ) -> BoxFuture<'d, ()>
+        where
+            'a: 'd,
+            'b: 'd,
+            'c: 'd,
+        {
+            resource_initial_conditions(client, &self.vpc_url, current_policy)
+                .boxed()
+        }
+
+        fn verify_privileged<'a, 'b, 'c>(
+            &'a self,
+            client: &'b ClientTestContext,
+        ) -> BoxFuture<'c, ()>
+        where
+            'a: 'c,
+            'b: 'c,
+        {
+            async move {
+                resource_privileged_conditions::<views::Vpc>(
+                    client,
+                    &self.vpc_url,
+                    &self.vpc_name,
+                )
+                .await;
+
+                // Viewers can see the resources inside the VPC, too.
+                NexusRequest::object_get(
+                    client,
+                    &format!("{}/subnets/default", self.vpc_url),
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap();
+
+                // The role grants nothing on the Project, though.
+                NexusRequest::expect_failure(
+                    client,
+                    StatusCode::NOT_FOUND,
+                    Method::GET,
+                    &self.project_url,
+                )
+                .authn_as(AuthnMode::UnprivilegedUser)
+                .execute()
+                .await
+                .unwrap();
+            }
+            .boxed()
+        }
+    }
+
+    run_test(client, test_case).await;
+}
+
 /// Helper function for verifying the initial (unprivileged) conditions for most
 /// resources
 ///
-/// This is used for the Organization and Project tests today.  If we add
-/// support for assigning roles on other kinds of resources, we'd likely use
-/// this for those, too.  (It's Fleet and Silo that are special cases.)
+/// This is used for all but the Fleet and Silo tests, which are special cases.
 fn resource_initial_conditions<'a, 'b, 'c, 'd, T>(
     client: &'a ClientTestContext,
     resource_url: &'b str,
@@ -438,9 +752,7 @@ where
 
 /// Helper function for verifying the privileged conditions for most resources
 ///
-/// This is used for the Organization and Project tests today.  If we add
-/// support for assigning roles on other kinds of resources, we'd likely use
-/// this for those, too.  (It's Fleet and Silo that are special cases.)
+/// This is used for all but the Fleet and Silo tests, which are special cases.
 fn resource_privileged_conditions<'a, 'b, 'c, 'd, V>(
     client: &'a ClientTestContext,
     resource_url: &'b str,
@@ -557,10 +869,27 @@ async fn run_test<T: RoleAssignmentTest>(
     test_case.verify_privileged(client).await;
 
     // The way we've defined things, the unprivileged user ought to be able to
-    // revoke their own access.
+    // revoke their own access, unless the role they were granted doesn't allow
+    // changing the policy at all.
+    let revoker = if T::ROLE_CAN_MODIFY_POLICY {
+        AuthnMode::UnprivilegedUser
+    } else {
+        NexusRequest::expect_failure_with_body(
+            client,
+            StatusCode::FORBIDDEN,
+            Method::PUT,
+            &policy_url,
+            &initial_policy,
+        )
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+        AuthnMode::PrivilegedUser
+    };
     let updated_policy: shared::Policy<T::RoleType> =
         NexusRequest::object_put(client, &policy_url, Some(&initial_policy))
-            .authn_as(AuthnMode::UnprivilegedUser)
+            .authn_as(revoker)
             .execute()
             .await
             .unwrap()
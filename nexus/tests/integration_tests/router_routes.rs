@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use dropshot::test_util::ClientTestContext;
 use dropshot::Method;
 use http::StatusCode;
 use nexus_test_utils::http_testing::AuthnMode;
@@ -11,10 +12,12 @@ use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
 use omicron_common::api::external::{
-    IdentityMetadataCreateParams, IdentityMetadataUpdateParams,
+    IdentityMetadataCreateParams, IdentityMetadataUpdateParams, Ipv4Net, Name,
     RouteDestination, RouteTarget, RouterRoute, RouterRouteCreateParams,
     RouterRouteKind, RouterRouteUpdateParams,
 };
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::VpcSubnet;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 
@@ -59,12 +62,14 @@ async fn test_router_routes(cptestctx: &ControlPlaneTestContext) {
     .await
     .items;
 
-    // The system should start with a single, pre-configured route
-    assert_eq!(system_router_routes.len(), 1);
+    // The system router should start with the pre-configured default route,
+    // plus a route for the VPC's default subnet
+    assert_eq!(system_router_routes.len(), 2);
 
-    // That route should be the default route
+    // The first route should be the default route
     let default_route = &system_router_routes[0];
     assert_eq!(default_route.kind, RouterRouteKind::Default);
+    assert_eq!(system_router_routes[1].kind, RouterRouteKind::VpcSubnet);
 
     // It errors if you try to delete the default route
     let error: dropshot::HttpErrorResponseBody = NexusRequest::expect_failure(
@@ -196,3 +201,155 @@ async fn test_router_routes(cptestctx: &ControlPlaneTestContext) {
     .await
     .unwrap();
 }
+
+#[nexus_test]
+async fn test_router_routes_vpc_subnets(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let organization_name = "test-org";
+    let project_name = "springfield-squidport";
+    let vpc_name = "vpc1";
+    let vpc_url = format!(
+        "/organizations/{}/projects/{}/vpcs/{}",
+        organization_name, project_name, vpc_name
+    );
+    let system_routes_url = format!("{}/routers/system/routes", vpc_url);
+    let subnets_url = format!("{}/subnets", vpc_url);
+
+    create_organization(&client, organization_name).await;
+    let _ = create_project(&client, organization_name, project_name).await;
+    create_vpc(&client, organization_name, project_name, vpc_name).await;
+
+    // The default subnet gets a route to itself in the system router
+    let routes = vpc_subnet_routes(client, &system_routes_url).await;
+    assert_eq!(routes.len(), 1);
+    let default_subnet_name: Name = "default".parse().unwrap();
+    assert_eq!(
+        routes[0].destination,
+        RouteDestination::Subnet(default_subnet_name.clone())
+    );
+    assert_eq!(routes[0].target, RouteTarget::Subnet(default_subnet_name));
+
+    // Creating a subnet adds a route for it
+    let subnet: VpcSubnet = NexusRequest::objects_post(
+        client,
+        &subnets_url,
+        &params::VpcSubnetCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "subnet1".parse().unwrap(),
+                description: "a subnet".to_string(),
+            },
+            ipv4_block: Ipv4Net("192.168.42.0/24".parse().unwrap()),
+            ipv6_block: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    let routes = vpc_subnet_routes(client, &system_routes_url).await;
+    assert_eq!(routes.len(), 2);
+    let route = routes
+        .iter()
+        .find(|route| {
+            route.destination
+                == RouteDestination::Subnet(subnet.identity.name.clone())
+        })
+        .expect("missing route for new subnet");
+    let route_url =
+        format!("{}/{}", system_routes_url, route.identity.name.as_str());
+
+    // The route can't be modified or deleted through the API
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::METHOD_NOT_ALLOWED,
+        Method::PUT,
+        &route_url,
+        &RouterRouteUpdateParams {
+            identity: IdentityMetadataUpdateParams {
+                name: None,
+                description: None,
+            },
+            target: RouteTarget::Ip(IpAddr::from(Ipv4Addr::new(
+                192, 168, 1, 1,
+            ))),
+            destination: RouteDestination::Subnet("subnet1".parse().unwrap()),
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::METHOD_NOT_ALLOWED,
+        Method::DELETE,
+        &route_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Renaming the subnet updates its route
+    NexusRequest::object_put(
+        client,
+        &format!("{}/subnet1", subnets_url),
+        Some(&params::VpcSubnetUpdate {
+            identity: IdentityMetadataUpdateParams {
+                name: Some("subnet2".parse().unwrap()),
+                description: None,
+            },
+        }),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let renamed: RouterRoute = NexusRequest::object_get(client, &route_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap();
+    assert_eq!(renamed.identity.id, route.identity.id);
+    let new_subnet_name: Name = "subnet2".parse().unwrap();
+    assert_eq!(
+        renamed.destination,
+        RouteDestination::Subnet(new_subnet_name.clone())
+    );
+    assert_eq!(renamed.target, RouteTarget::Subnet(new_subnet_name));
+
+    // Deleting the subnet removes its route
+    NexusRequest::object_delete(client, &format!("{}/subnet2", subnets_url))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(vpc_subnet_routes(client, &system_routes_url).await.len(), 1);
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &route_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+async fn vpc_subnet_routes(
+    client: &ClientTestContext,
+    routes_url: &str,
+) -> Vec<RouterRoute> {
+    objects_list_page_authz::<RouterRoute>(client, routes_url)
+        .await
+        .items
+        .into_iter()
+        .filter(|route| route.kind == RouterRouteKind::VpcSubnet)
+        .collect()
+}
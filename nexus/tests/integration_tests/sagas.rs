@@ -0,0 +1,657 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for saga inspection, operator interventions, and saga behavior in the
+//! face of faults
+
+use crate::integration_tests::instances::instance_post;
+use crate::integration_tests::instances::instance_simulate;
+use crate::integration_tests::instances::InstanceOp;
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::resource_helpers::create_disk;
+use nexus_test_utils::resource_helpers::create_instance;
+use nexus_test_utils::resource_helpers::create_ip_pool;
+use nexus_test_utils::resource_helpers::create_organization;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::DiskTest;
+use nexus_test_utils::sagas::test_saga_faults;
+use nexus_test_utils::sagas::SagaUnwind;
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::InstanceCpuCount;
+use omicron_nexus::app::saga_names;
+use omicron_nexus::db::model::saga_types::SagaNodeEvent;
+use omicron_nexus::db::SecId;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::SagaLog;
+use omicron_nexus::external_api::views::SagaLogState;
+use omicron_nexus::external_api::views::SagaNodeEventType;
+use omicron_test_utils::dev::poll::wait_for_condition;
+use omicron_test_utils::dev::poll::CondCheckError;
+use std::convert::Infallible;
+use std::time::Duration;
+use uuid::Uuid;
+
+const ORG_NAME: &str = "test-org";
+const PROJECT_NAME: &str = "saga-project";
+const DISK_NAME: &str = "saga-disk";
+const INSTANCE_NAME: &str = "saga-instance";
+const SNAPSHOT_NAME: &str = "saga-snapshot";
+
+#[nexus_test]
+async fn test_saga_log(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    create_organization(&client, ORG_NAME).await;
+    create_project(client, ORG_NAME, PROJECT_NAME).await;
+    create_disk(client, ORG_NAME, PROJECT_NAME, DISK_NAME).await;
+
+    // Find the saga that created the disk.  The saga list doesn't say what
+    // kind of saga each one is, but the log does.
+    let sagas: Vec<serde_json::Value> =
+        NexusRequest::iter_collection_authn(client, "/system/sagas", "", None)
+            .await
+            .expect("failed to list sagas")
+            .all_items;
+    let mut disk_create_log = None;
+    for saga in sagas {
+        let saga_id: Uuid = saga["id"].as_str().unwrap().parse().unwrap();
+        let log = saga_log(client, saga_id).await;
+        if log.name == "disk-create" {
+            disk_create_log = Some(log);
+            break;
+        }
+    }
+    let log = disk_create_log.expect("found no disk-create saga");
+
+    // The saga finished successfully, so every node that started must have
+    // succeeded, and nothing was undone.
+    assert_eq!(log.state, SagaLogState::Done);
+    assert!(log.current_sec.is_some());
+    assert!(log.interventions.is_empty());
+    assert!(!log.events.is_empty());
+    let started = log
+        .events
+        .iter()
+        .filter(|e| e.event_type == SagaNodeEventType::Started)
+        .count();
+    let succeeded = log
+        .events
+        .iter()
+        .filter(|e| e.event_type == SagaNodeEventType::Succeeded)
+        .count();
+    assert_eq!(started, succeeded);
+    assert!(log.events.iter().all(|e| !matches!(
+        e.event_type,
+        SagaNodeEventType::Failed
+            | SagaNodeEventType::UndoStarted
+            | SagaNodeEventType::UndoFinished
+    )));
+    assert!(log.events.iter().all(|e| e.node_name.is_some()));
+    assert!(log.events.windows(2).all(|w| w[0].time <= w[1].time));
+
+    // It's not possible to abandon or retry a saga that's already done.
+    for action in ["abandon", "retry"] {
+        let url = format!("/system/sagas/{}/{}", log.id, action);
+        let error = NexusRequest::expect_failure_with_body(
+            client,
+            StatusCode::BAD_REQUEST,
+            Method::POST,
+            &url,
+            &params::SagaIntervene { reason: String::from("testing") },
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body::<HttpErrorResponseBody>()
+        .unwrap();
+        assert_eq!(error.message, "saga has already finished");
+    }
+
+    // Failed interventions are not recorded.
+    assert!(saga_log(client, log.id).await.interventions.is_empty());
+
+    // We get a 404 for sagas that don't exist.
+    let url = format!("/system/sagas/{}/log", Uuid::new_v4());
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+/// Runs [`test_saga_faults()`] against every saga that Nexus runs
+///
+/// The list of sagas comes from Nexus itself, so there must be a case here for
+/// each one.  Each saga gets its own Project to work in.
+#[nexus_test]
+async fn test_saga_faults_all(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let disk_test = DiskTest::new(&cptestctx).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    create_organization(&client, ORG_NAME).await;
+
+    for saga_name in saga_names() {
+        let project_name = format!("{}-project", saga_name);
+        create_project(client, ORG_NAME, &project_name).await;
+        let project_url =
+            format!("/organizations/{}/projects/{}", ORG_NAME, project_name);
+        let project_url = project_url.as_str();
+        match saga_name {
+            "disk-create" => {
+                disk_create_faults(cptestctx, &disk_test, project_url).await
+            }
+            "disk-delete" | "volume-delete" => {
+                disk_delete_faults(
+                    cptestctx,
+                    &disk_test,
+                    saga_name,
+                    &project_name,
+                )
+                .await
+            }
+            "ephemeral-ip-attach" | "ephemeral-ip-detach" => {
+                ephemeral_ip_faults(
+                    cptestctx,
+                    &disk_test,
+                    saga_name,
+                    &project_name,
+                )
+                .await
+            }
+            "instance-create" => {
+                instance_create_faults(cptestctx, &disk_test, project_url).await
+            }
+            // Migration can't be checked this way yet.  Besides having no undo
+            // actions, the saga never stops the instance on the sled that it
+            // leaves ("CleanupSource" does nothing), so even a migration that
+            // succeeds leaves that copy behind, and an instance that failed
+            // partway through stays "migrating" for good.
+            "instance-migrate" => (),
+            "snapshot-create" => {
+                snapshot_create_faults(cptestctx, &disk_test, &project_name)
+                    .await
+            }
+            "volume-remove-read-only-parent" => {
+                volume_remove_rop_faults(cptestctx, &disk_test).await
+            }
+            _ => panic!("no fault-injection test for saga {:?}", saga_name),
+        }
+    }
+}
+
+fn disk_create_params(name: &str) -> params::DiskCreate {
+    params::DiskCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: String::from("sells rainsticks"),
+        },
+        disk_source: params::DiskSource::Blank {
+            block_size: params::BlockSize::try_from(512).unwrap(),
+        },
+        size: ByteCount::from_gibibytes_u32(1),
+    }
+}
+
+async fn disk_create_faults(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+    project_url: &str,
+) {
+    let client = &cptestctx.external_client;
+    let disks_url = format!("{}/disks", project_url);
+    let disk_url = format!("{}/{}", disks_url, DISK_NAME);
+    let new_disk = disk_create_params(DISK_NAME);
+
+    let disks_url = &disks_url;
+    let disk_url = &disk_url;
+    let new_disk = &new_disk;
+    test_saga_faults(
+        cptestctx,
+        disk_test,
+        "disk-create",
+        SagaUnwind::Complete,
+        move || async move {
+            NexusRequest::objects_post(client, disks_url, new_disk)
+                .authn_as(AuthnMode::PrivilegedUser)
+                .execute()
+                .await
+                .map(|_| ())
+        },
+        move || async move {
+            NexusRequest::object_delete(client, disk_url)
+                .authn_as(AuthnMode::PrivilegedUser)
+                .execute()
+                .await
+                .expect("failed to delete disk");
+        },
+    )
+    .await;
+}
+
+/// Tests the sagas that run when a disk is deleted: "disk-delete" and the
+/// "volume-delete" saga that it runs in turn
+///
+/// A deleted disk can't be brought back, so each attempt creates the disk
+/// first.  When deleting it fails, the disk is left as it was, and deleting
+/// it again must release everything.
+async fn disk_delete_faults(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+    saga_name: &str,
+    project_name: &str,
+) {
+    let client = &cptestctx.external_client;
+    let disk_url = format!(
+        "/organizations/{}/projects/{}/disks/{}",
+        ORG_NAME, project_name, DISK_NAME
+    );
+    let disk_url = &disk_url;
+    let delete = move || async move {
+        NexusRequest::object_delete(client, disk_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .map(|_| ())
+    };
+
+    test_saga_faults(
+        cptestctx,
+        disk_test,
+        saga_name,
+        SagaUnwind::Cleanup,
+        move || async move {
+            create_disk(client, ORG_NAME, project_name, DISK_NAME).await;
+            delete().await
+        },
+        // If the disk is already gone, there's nothing to do.
+        move || async move {
+            let _ = delete().await;
+        },
+    )
+    .await;
+}
+
+async fn ephemeral_ip_faults(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+    saga_name: &str,
+    project_name: &str,
+) {
+    let client = &cptestctx.external_client;
+    let instance =
+        create_instance(client, ORG_NAME, project_name, INSTANCE_NAME).await;
+    instance_simulate(&cptestctx.server.apictx.nexus, &instance.identity.id)
+        .await;
+
+    let ephemeral_url = format!(
+        "/organizations/{}/projects/{}/instances/{}/external-ips/ephemeral",
+        ORG_NAME, project_name, INSTANCE_NAME
+    );
+    let ephemeral_url = &ephemeral_url;
+    let ip_params = &params::EphemeralIpCreate { pool_name: None };
+    let attach = move || async move {
+        NexusRequest::new(
+            RequestBuilder::new(client, Method::POST, ephemeral_url)
+                .body(Some(ip_params))
+                .expect_status(Some(StatusCode::ACCEPTED)),
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .map(|_| ())
+    };
+    let detach = move || async move {
+        NexusRequest::object_delete(client, ephemeral_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .map(|_| ())
+    };
+
+    if saga_name == "ephemeral-ip-attach" {
+        test_saga_faults(
+            cptestctx,
+            disk_test,
+            saga_name,
+            SagaUnwind::Complete,
+            attach,
+            move || async move {
+                detach().await.expect("failed to detach ephemeral IP")
+            },
+        )
+        .await;
+    } else {
+        attach().await.expect("failed to attach ephemeral IP");
+        test_saga_faults(
+            cptestctx,
+            disk_test,
+            saga_name,
+            SagaUnwind::Complete,
+            detach,
+            move || async move {
+                attach().await.expect("failed to attach ephemeral IP")
+            },
+        )
+        .await;
+    }
+}
+
+async fn instance_create_faults(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+    project_url: &str,
+) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx.nexus;
+    let instances_url = format!("{}/instances", project_url);
+    let instance_url = format!("{}/{}", instances_url, INSTANCE_NAME);
+    let new_instance = params::InstanceCreate {
+        identity: IdentityMetadataCreateParams {
+            name: INSTANCE_NAME.parse().unwrap(),
+            description: String::from("instance with everything"),
+        },
+        ncpus: InstanceCpuCount(2),
+        memory: ByteCount::from_gibibytes_u32(1),
+        hostname: String::from("the_host"),
+        user_data: vec![],
+        network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
+        external_ips: vec![params::ExternalIpCreate::Ephemeral {
+            pool_name: None,
+        }],
+        disks: vec![params::InstanceDiskAttachment::Create(
+            disk_create_params(DISK_NAME),
+        )],
+        start: true,
+    };
+
+    let instances_url = &instances_url;
+    let instance_url = &instance_url;
+    let new_instance = &new_instance;
+    test_saga_faults(
+        cptestctx,
+        disk_test,
+        "instance-create",
+        SagaUnwind::Complete,
+        move || async move {
+            NexusRequest::objects_post(client, instances_url, new_instance)
+                .authn_as(AuthnMode::PrivilegedUser)
+                .execute()
+                .await
+                .map(|_| ())
+        },
+        move || async move {
+            let instance: Instance =
+                NexusRequest::object_get(client, instance_url)
+                    .authn_as(AuthnMode::PrivilegedUser)
+                    .execute()
+                    .await
+                    .expect("failed to fetch instance")
+                    .parsed_body()
+                    .unwrap();
+            let instance_id = instance.identity.id;
+            instance_simulate(nexus, &instance_id).await;
+            instance_post(client, instance_url, InstanceOp::Stop).await;
+            instance_simulate(nexus, &instance_id).await;
+            NexusRequest::object_delete(client, instance_url)
+                .authn_as(AuthnMode::PrivilegedUser)
+                .execute()
+                .await
+                .expect("failed to delete instance");
+            // The disk created along with the instance outlives it.
+            NexusRequest::object_delete(
+                client,
+                &format!("{}/disks/{}", project_url, DISK_NAME),
+            )
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .expect("failed to delete disk");
+        },
+    )
+    .await;
+}
+
+async fn snapshot_create_faults(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+    project_name: &str,
+) {
+    let client = &cptestctx.external_client;
+    create_disk(client, ORG_NAME, project_name, DISK_NAME).await;
+
+    let snapshots_url = format!(
+        "/organizations/{}/projects/{}/snapshots",
+        ORG_NAME, project_name
+    );
+    let snapshot_url = format!("{}/{}", snapshots_url, SNAPSHOT_NAME);
+    let new_snapshot = params::SnapshotCreate {
+        identity: IdentityMetadataCreateParams {
+            name: SNAPSHOT_NAME.parse().unwrap(),
+            description: String::from("snapshot of the disk"),
+        },
+        disk: DISK_NAME.parse().unwrap(),
+    };
+
+    let snapshots_url = &snapshots_url;
+    let snapshot_url = &snapshot_url;
+    let new_snapshot = &new_snapshot;
+    test_saga_faults(
+        cptestctx,
+        disk_test,
+        "snapshot-create",
+        SagaUnwind::Complete,
+        move || async move {
+            NexusRequest::objects_post(client, snapshots_url, new_snapshot)
+                .authn_as(AuthnMode::PrivilegedUser)
+                .execute()
+                .await
+                .map(|_| ())
+        },
+        move || async move {
+            NexusRequest::object_delete(client, snapshot_url)
+                .authn_as(AuthnMode::PrivilegedUser)
+                .execute()
+                .await
+                .expect("failed to delete snapshot");
+        },
+    )
+    .await;
+}
+
+/// Tests "volume-remove-read-only-parent"
+///
+/// This is requested through the internal API, and works on any volume
+/// (even one that doesn't exist), so we make up a new one each time.
+async fn volume_remove_rop_faults(
+    cptestctx: &ControlPlaneTestContext,
+    disk_test: &DiskTest,
+) {
+    let internal_client = &cptestctx.internal_client;
+    test_saga_faults(
+        cptestctx,
+        disk_test,
+        "volume-remove-read-only-parent",
+        SagaUnwind::Complete,
+        move || async move {
+            let url =
+                format!("/volume/{}/remove-read-only-parent", Uuid::new_v4());
+            RequestBuilder::new(internal_client, Method::POST, &url)
+                .expect_status(Some(StatusCode::NO_CONTENT))
+                .execute()
+                .await
+                .map(|_| ())
+        },
+        move || async move {},
+    )
+    .await;
+}
+
+#[nexus_test]
+async fn test_saga_abandon_retry(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let datastore = cptestctx.server.apictx.nexus.datastore();
+    DiskTest::new(&cptestctx).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    create_organization(&client, ORG_NAME).await;
+    create_project(client, ORG_NAME, PROJECT_NAME).await;
+    create_disk(client, ORG_NAME, PROJECT_NAME, DISK_NAME).await;
+    let disk_url = format!(
+        "/organizations/{}/projects/{}/disks/{}",
+        ORG_NAME, PROJECT_NAME, DISK_NAME
+    );
+    NexusRequest::object_delete(client, &disk_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete disk");
+
+    // Make up an unfinished saga, owned by some other Nexus, from the
+    // disk-delete saga that just ran.  When it runs again, it will fail
+    // because the disk is already gone, which is fine: all we care about here
+    // is who runs it.
+    let sagas: Vec<serde_json::Value> =
+        NexusRequest::iter_collection_authn(client, "/system/sagas", "", None)
+            .await
+            .expect("failed to list sagas")
+            .all_items;
+    let mut disk_delete_id = None;
+    for saga in sagas {
+        let saga_id: Uuid = saga["id"].as_str().unwrap().parse().unwrap();
+        if saga_log(client, saga_id).await.name == "disk-delete" {
+            disk_delete_id = Some(saga_id);
+            break;
+        }
+    }
+    let disk_delete_id = disk_delete_id.expect("found no disk-delete saga");
+    let mut saga = datastore
+        .saga_fetch_unauthorized(steno::SagaId(disk_delete_id))
+        .await
+        .unwrap();
+    let saga_id = Uuid::new_v4();
+    let other_sec = SecId(Uuid::new_v4());
+    saga.id = steno::SagaId(saga_id).into();
+    saga.saga_state = steno::SagaCachedState::Running.into();
+    saga.current_sec = Some(other_sec);
+    datastore.saga_create(&saga).await.unwrap();
+    let old_generation = saga.adopt_generation;
+
+    // The saga can't be retried while another Nexus owns it.
+    let intervene = params::SagaIntervene { reason: String::from("testing") };
+    let retry_url = format!("/system/sagas/{}/retry", saga_id);
+    let error = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &retry_url,
+        &intervene,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert!(
+        error
+            .message
+            .starts_with(&format!("saga is owned by SEC {}", other_sec)),
+        "unexpected error: {}",
+        error.message
+    );
+    assert!(saga_log(client, saga_id).await.interventions.is_empty());
+
+    // Once abandoned, nobody owns it, and its old owner can no longer record
+    // any progress.
+    NexusRequest::objects_post(
+        client,
+        &format!("/system/sagas/{}/abandon", saga_id),
+        &intervene,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to abandon saga");
+    let log = saga_log(client, saga_id).await;
+    assert_eq!(log.state, SagaLogState::Running);
+    assert_eq!(log.current_sec, None);
+    assert_eq!(log.interventions.len(), 1);
+    let event = SagaNodeEvent::new(
+        steno::SagaNodeEvent {
+            saga_id: steno::SagaId(saga_id),
+            node_id: steno::SagaNodeId::from(0),
+            event_type: steno::SagaNodeEventType::Started,
+        },
+        other_sec,
+    );
+    datastore
+        .saga_create_event(&event, other_sec, old_generation)
+        .await
+        .expect_err("recorded event for abandoned saga");
+    datastore
+        .saga_update_state(
+            steno::SagaId(saga_id),
+            steno::SagaCachedState::Done,
+            other_sec,
+            old_generation,
+        )
+        .await
+        .expect_err("updated state of abandoned saga");
+    assert!(saga_log(client, saga_id).await.events.is_empty());
+
+    // Now this Nexus can adopt it and run it to completion.
+    NexusRequest::objects_post(client, &retry_url, &intervene)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to retry saga");
+    let log = wait_for_condition(
+        || async {
+            let log = saga_log(client, saga_id).await;
+            if log.state == SagaLogState::Done {
+                Ok(log)
+            } else {
+                Err(CondCheckError::<Infallible>::NotYet)
+            }
+        },
+        &Duration::from_millis(50),
+        &Duration::from_secs(30),
+    )
+    .await
+    .expect("retried saga did not finish");
+    let my_sec = saga_log(client, disk_delete_id).await.current_sec;
+    assert!(my_sec.is_some());
+    assert_eq!(log.current_sec, my_sec);
+    assert!(!log.events.is_empty());
+    assert_eq!(log.interventions.len(), 2);
+}
+
+async fn saga_log(
+    client: &dropshot::test_util::ClientTestContext,
+    saga_id: Uuid,
+) -> SagaLog {
+    let url = format!("/system/sagas/{}/log", saga_id);
+    NexusRequest::object_get(client, &url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to fetch saga log")
+        .parsed_body()
+        .unwrap()
+}
@@ -0,0 +1,400 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for SCIM provisioning of Silo users and groups
+
+use dropshot::test_util::ClientTestContext;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::{
+    AuthnMode, NexusRequest, RequestBuilder, TestResponse,
+};
+use nexus_test_utils::resource_helpers::create_silo;
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_nexus::external_api::{shared, views};
+use serde_json::json;
+
+const SILO_NAME: &str = "scim-silo";
+
+async fn create_token(
+    client: &ClientTestContext,
+    silo_name: &str,
+) -> views::ScimClientBearerTokenValue {
+    NexusRequest::objects_post(
+        client,
+        &format!("/system/silos/{}/scim/tokens", silo_name),
+        &serde_json::Value::Null,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to create SCIM token")
+    .parsed_body()
+    .unwrap()
+}
+
+async fn list_tokens(
+    client: &ClientTestContext,
+    silo_name: &str,
+) -> Vec<views::ScimClientBearerToken> {
+    NexusRequest::object_get(
+        client,
+        &format!("/system/silos/{}/scim/tokens", silo_name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to list SCIM tokens")
+    .parsed_body()
+    .unwrap()
+}
+
+/// Create a Silo provisioned through SCIM, returning a token for it
+async fn create_scim_silo(client: &ClientTestContext) -> String {
+    create_silo(client, SILO_NAME, true, shared::SiloIdentityMode::SamlScim)
+        .await;
+    create_token(client, SILO_NAME).await.bearer_token
+}
+
+/// Make a SCIM request, as an identity provider would
+async fn scim_request(
+    client: &ClientTestContext,
+    method: Method,
+    path: &str,
+    token: &str,
+    body: Option<serde_json::Value>,
+    expected_status: StatusCode,
+) -> TestResponse {
+    RequestBuilder::new(
+        client,
+        method,
+        &format!("/scim/v2/{}{}", SILO_NAME, path),
+    )
+    .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+    .header(http::header::CONTENT_TYPE, "application/scim+json")
+    .raw_body(body.map(|body| body.to_string()))
+    .expect_status(Some(expected_status))
+    .allow_non_dropshot_errors()
+    .execute()
+    .await
+    .unwrap()
+}
+
+async fn scim_create_user(
+    client: &ClientTestContext,
+    token: &str,
+    user_name: &str,
+) -> serde_json::Value {
+    scim_request(
+        client,
+        Method::POST,
+        "/Users",
+        token,
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": user_name,
+            "active": true,
+        })),
+        StatusCode::CREATED,
+    )
+    .await
+    .parsed_body()
+    .unwrap()
+}
+
+#[nexus_test]
+async fn test_scim_tokens(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let token = create_scim_silo(client).await;
+
+    let tokens = list_tokens(client, SILO_NAME).await;
+    assert_eq!(tokens.len(), 1);
+
+    scim_request(client, Method::GET, "/Users", &token, None, StatusCode::OK)
+        .await;
+    let error: serde_json::Value = scim_request(
+        client,
+        Method::GET,
+        "/Users",
+        "bogus-token",
+        None,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error["schemas"],
+        json!(["urn:ietf:params:scim:api:messages:2.0:Error"])
+    );
+    assert_eq!(error["status"], "401");
+
+    // Tokens are only for Silos provisioned through SCIM.
+    create_silo(client, "jit-silo", true, shared::SiloIdentityMode::SamlJit)
+        .await;
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            "/system/silos/jit-silo/scim/tokens",
+        )
+        .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Deleted tokens no longer authenticate.
+    NexusRequest::object_delete(
+        client,
+        &format!("/system/silos/{}/scim/tokens/{}", SILO_NAME, tokens[0].id),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    assert!(list_tokens(client, SILO_NAME).await.is_empty());
+    scim_request(
+        client,
+        Method::GET,
+        "/Users",
+        &token,
+        None,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+}
+
+#[nexus_test]
+async fn test_scim_users(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let token = create_scim_silo(client).await;
+
+    let user = scim_create_user(client, &token, "alice@example.com").await;
+    assert_eq!(user["userName"], "alice@example.com");
+    assert_eq!(user["active"], true);
+    let user_path = format!("/Users/{}", user["id"].as_str().unwrap());
+
+    // User names are unique within the Silo.
+    scim_request(
+        client,
+        Method::POST,
+        "/Users",
+        &token,
+        Some(json!({ "userName": "alice@example.com" })),
+        StatusCode::CONFLICT,
+    )
+    .await;
+    scim_create_user(client, &token, "bob@example.com").await;
+
+    // Identity providers look up users by their user name.
+    let list: serde_json::Value = scim_request(
+        client,
+        Method::GET,
+        "/Users?filter=userName%20eq%20%22alice%40example.com%22",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], user["id"]);
+
+    let list: serde_json::Value = scim_request(
+        client,
+        Method::GET,
+        "/Users?startIndex=2&count=1",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(list["totalResults"], 2);
+    assert_eq!(list["startIndex"], 2);
+    assert_eq!(list["itemsPerPage"], 1);
+
+    // Deactivate the user the way some identity providers do.
+    let patched: serde_json::Value = scim_request(
+        client,
+        Method::PATCH,
+        &user_path,
+        &token,
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                { "op": "Replace", "path": "active", "value": "False" },
+            ],
+        })),
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(patched["active"], false);
+    assert_eq!(patched["userName"], "alice@example.com");
+
+    let replaced: serde_json::Value = scim_request(
+        client,
+        Method::PUT,
+        &user_path,
+        &token,
+        Some(json!({ "userName": "alice@example.org", "active": true })),
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(replaced["active"], true);
+    assert_eq!(replaced["userName"], "alice@example.org");
+
+    scim_request(
+        client,
+        Method::DELETE,
+        &user_path,
+        &token,
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    scim_request(
+        client,
+        Method::GET,
+        &user_path,
+        &token,
+        None,
+        StatusCode::NOT_FOUND,
+    )
+    .await;
+}
+
+#[nexus_test]
+async fn test_scim_groups(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let token = create_scim_silo(client).await;
+
+    let alice = scim_create_user(client, &token, "alice").await["id"].clone();
+    let bob = scim_create_user(client, &token, "bob").await["id"].clone();
+
+    let group: serde_json::Value = scim_request(
+        client,
+        Method::POST,
+        "/Groups",
+        &token,
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+            "displayName": "SRE",
+            "members": [{ "value": alice }],
+        })),
+        StatusCode::CREATED,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(group["displayName"], "SRE");
+    assert_eq!(group["members"], json!([{ "value": alice }]));
+    let group_path = format!("/Groups/{}", group["id"].as_str().unwrap());
+
+    scim_request(
+        client,
+        Method::POST,
+        "/Groups",
+        &token,
+        Some(json!({ "displayName": "SRE" })),
+        StatusCode::CONFLICT,
+    )
+    .await;
+
+    // Members must be users of the Silo.
+    scim_request(
+        client,
+        Method::POST,
+        "/Groups",
+        &token,
+        Some(json!({
+            "displayName": "Admins",
+            "members": [{ "value": "00000000-0000-0000-0000-000000000000" }],
+        })),
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+
+    let patch = |operations: serde_json::Value| {
+        json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": operations,
+        })
+    };
+    let patched: serde_json::Value = scim_request(
+        client,
+        Method::PATCH,
+        &group_path,
+        &token,
+        Some(patch(json!([
+            { "op": "add", "path": "members", "value": [{ "value": bob }] },
+            { "op": "replace", "path": "displayName", "value": "SREs" },
+        ]))),
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(patched["displayName"], "SREs");
+    assert_eq!(patched["members"].as_array().unwrap().len(), 2);
+
+    let patched: serde_json::Value = scim_request(
+        client,
+        Method::PATCH,
+        &group_path,
+        &token,
+        Some(patch(json!([{
+            "op": "remove",
+            "path": format!("members[value eq \"{}\"]", alice.as_str().unwrap()),
+        }]))),
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(patched["members"], json!([{ "value": bob }]));
+
+    let list: serde_json::Value = scim_request(
+        client,
+        Method::GET,
+        "/Groups?filter=displayName%20eq%20%22SREs%22",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["members"], json!([{ "value": bob }]));
+
+    // Deleting a group removes its members from it.
+    scim_request(
+        client,
+        Method::DELETE,
+        &group_path,
+        &token,
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    scim_request(
+        client,
+        Method::GET,
+        &group_path,
+        &token,
+        None,
+        StatusCode::NOT_FOUND,
+    )
+    .await;
+}
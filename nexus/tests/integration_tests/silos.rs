@@ -2033,3 +2033,193 @@ async fn run_user_tests(
     println!("last_users: {:?}", last_users);
     assert_eq!(last_users, existing_users);
 }
+
+/// Tests managing groups and their members through the API in a LocalOnly
+/// Silo, and granting roles to those groups
+#[nexus_test]
+async fn test_silo_groups_api(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx.nexus;
+
+    let silo = create_silo(
+        &client,
+        "local-groups",
+        true,
+        shared::SiloIdentityMode::LocalOnly,
+    )
+    .await;
+    let silo_user_id = Uuid::new_v4();
+    nexus
+        .silo_user_create(silo.identity.id, silo_user_id, "alice".into())
+        .await
+        .unwrap();
+
+    let groups_url = "/system/silos/local-groups/groups";
+    let group: views::Group = object_create(
+        client,
+        groups_url,
+        &params::GroupCreate { display_name: "sre".parse().unwrap() },
+    )
+    .await;
+    assert_eq!(group.display_name, "sre");
+    assert_eq!(group.silo_id, silo.identity.id);
+
+    // Group names are unique within the Silo.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        groups_url,
+        &params::GroupCreate { display_name: "sre".parse().unwrap() },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let groups =
+        objects_list_page_authz::<views::Group>(client, groups_url).await.items;
+    assert_eq!(groups, vec![group.clone()]);
+
+    let group_url = format!("{}/{}", groups_url, group.id);
+    let members_url = format!("{}/members", group_url);
+    let member: views::User = object_create(
+        client,
+        &members_url,
+        &params::GroupMemberAdd { user_id: silo_user_id },
+    )
+    .await;
+    assert_eq!(member.id, silo_user_id);
+    let members =
+        objects_list_page_authz::<views::User>(client, &members_url).await;
+    assert_eq!(members.items, vec![member]);
+
+    // The user can't create Organizations until the group is granted a role
+    // that allows it.
+    let org_params = params::OrganizationCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "groups-org".parse().unwrap(),
+            description: String::new(),
+        },
+    };
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::FORBIDDEN,
+        Method::POST,
+        "/organizations",
+        &org_params,
+    )
+    .authn_as(AuthnMode::SiloUser(silo_user_id))
+    .execute()
+    .await
+    .unwrap();
+
+    let policy_url = "/system/silos/local-groups/policy";
+    let policy: shared::Policy<SiloRole> =
+        NexusRequest::object_get(client, policy_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    let role_assignments = policy
+        .role_assignments
+        .into_iter()
+        .chain(std::iter::once(shared::RoleAssignment {
+            identity_type: shared::IdentityType::SiloGroup,
+            identity_id: group.id,
+            role_name: SiloRole::Collaborator,
+        }))
+        .collect();
+    NexusRequest::object_put(
+        client,
+        policy_url,
+        Some(&shared::Policy { role_assignments }),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    NexusRequest::objects_post(client, "/organizations", &org_params)
+        .authn_as(AuthnMode::SiloUser(silo_user_id))
+        .execute()
+        .await
+        .expect("failed to create Organization as group member");
+
+    // Groups with members can't be deleted.
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &group_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    NexusRequest::object_delete(
+        client,
+        &format!("{}/{}", members_url, silo_user_id),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    assert!(objects_list_page_authz::<views::User>(client, &members_url)
+        .await
+        .items
+        .is_empty());
+
+    // Deleting the group revokes its roles.
+    NexusRequest::object_delete(client, &group_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &group_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let policy: shared::Policy<SiloRole> =
+        NexusRequest::object_get(client, policy_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert!(policy
+        .role_assignments
+        .iter()
+        .all(|assignment| assignment.identity_id != group.id));
+
+    // Groups in other Silos are managed by the identity provider.
+    create_silo(&client, "jit", true, shared::SiloIdentityMode::SamlJit).await;
+    let error: dropshot::HttpErrorResponseBody =
+        NexusRequest::expect_failure_with_body(
+            client,
+            StatusCode::BAD_REQUEST,
+            Method::POST,
+            "/system/silos/jit/groups",
+            &params::GroupCreate { display_name: "sre".parse().unwrap() },
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap();
+    assert_eq!(
+        error.message,
+        "silo's groups are managed by its identity provider"
+    );
+}
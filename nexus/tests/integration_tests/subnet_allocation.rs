@@ -42,8 +42,8 @@ async fn create_instance_expect_failure(
                     name: name.parse().unwrap(),
                     description: String::from("description"),
                 },
-                vpc_name: "default".parse().unwrap(),
-                subnet_name: subnet_name.parse().unwrap(),
+                vpc_name: Some("default".parse().unwrap()),
+                subnet_name: Some(subnet_name.parse().unwrap()),
                 ip: None,
             },
         ]);
@@ -126,8 +126,8 @@ async fn test_subnet_allocation(cptestctx: &ControlPlaneTestContext) {
                 name: "eth0".parse().unwrap(),
                 description: String::from("some iface"),
             },
-            vpc_name: "default".parse().unwrap(),
-            subnet_name: "small".parse().unwrap(),
+            vpc_name: Some("default".parse().unwrap()),
+            subnet_name: Some("small".parse().unwrap()),
             ip: None,
         },
     ]);
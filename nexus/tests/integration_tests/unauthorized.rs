@@ -177,6 +177,21 @@ lazy_static! {
                 .respond_with(status_code(200).body(SAML_IDP_DESCRIPTOR)),
         );
 
+        let oidc_issuer = server.url("/oidc").to_string();
+        server.expect(
+            Expectation::matching(request::method_path(
+                "GET",
+                "/oidc/.well-known/openid-configuration",
+            ))
+            .times(1..)
+            .respond_with(json_encoded(serde_json::json!({
+                "issuer": oidc_issuer,
+                "authorization_endpoint": format!("{}/authorize", oidc_issuer),
+                "token_endpoint": format!("{}/token", oidc_issuer),
+                "jwks_uri": format!("{}/jwks", oidc_issuer),
+            }))),
+        );
+
         server
     };
 
@@ -195,6 +210,19 @@ lazy_static! {
             id_routes: vec![
                 &*DEMO_SILO_USER_ID_GET_URL,
                 &*DEMO_SILO_USER_ID_DELETE_URL,
+                &*DEMO_SILO_USER_ID_SET_PASSWORD_URL,
+                &*DEMO_SILO_USER_ID_SESSIONS_URL,
+                &*DEMO_SILO_USER_ID_SESSION_URL,
+            ],
+        },
+        // Create a local Group
+        SetupReq::Post {
+            url: &*DEMO_SILO_GROUPS_URL,
+            body: serde_json::to_value(&*DEMO_GROUP_CREATE).unwrap(),
+            id_routes: vec![
+                &*DEMO_SILO_GROUP_ID_URL,
+                &*DEMO_SILO_GROUP_ID_MEMBERS_URL,
+                &*DEMO_SILO_GROUP_ID_MEMBER_URL,
             ],
         },
         // Create an IP pool
@@ -245,6 +273,12 @@ lazy_static! {
             body: serde_json::to_value(&*DEMO_ROUTER_ROUTE_CREATE).unwrap(),
             id_routes: vec!["/by-id/vpc-router-routes/{id}"],
         },
+        // Create an Internet Gateway in the VPC
+        SetupReq::Post {
+            url: &*DEMO_VPC_URL_INTERNET_GATEWAYS,
+            body: serde_json::to_value(&*DEMO_INTERNET_GATEWAY_CREATE).unwrap(),
+            id_routes: vec!["/by-id/internet-gateways/{id}"],
+        },
         // Create a Disk in the Project
         SetupReq::Post {
             url: &*DEMO_PROJECT_URL_DISKS,
@@ -268,6 +302,12 @@ lazy_static! {
             body: serde_json::to_value(&*DEMO_SNAPSHOT_CREATE).unwrap(),
             id_routes: vec!["/by-id/snapshots/{id}"],
         },
+        // Create a Floating IP in the Project
+        SetupReq::Post {
+            url: &*DEMO_PROJECT_URL_FIPS,
+            body: serde_json::to_value(&*DEMO_FLOATING_IP_CREATE).unwrap(),
+            id_routes: vec!["/by-id/floating-ips/{id}"],
+        },
         // Create a GlobalImage
         SetupReq::Post {
             url: "/system/images",
@@ -280,12 +320,41 @@ lazy_static! {
             body: serde_json::to_value(&*SAML_IDENTITY_PROVIDER).unwrap(),
             id_routes: vec![],
         },
+        // Create a Silo for OpenID Connect, and an identity provider in it
+        SetupReq::Post {
+            url: "/system/silos",
+            body: serde_json::to_value(&*DEMO_OIDC_SILO_CREATE).unwrap(),
+            id_routes: vec![],
+        },
+        SetupReq::Post {
+            url: &*OIDC_IDENTITY_PROVIDERS_URL,
+            body: serde_json::to_value(&*OIDC_IDENTITY_PROVIDER).unwrap(),
+            id_routes: vec![],
+        },
+        // Create a Silo provisioned through SCIM, and a SCIM client bearer
+        // token for it
+        SetupReq::Post {
+            url: "/system/silos",
+            body: serde_json::to_value(&*DEMO_SCIM_SILO_CREATE).unwrap(),
+            id_routes: vec![],
+        },
+        SetupReq::Post {
+            url: &*DEMO_SCIM_TOKENS_URL,
+            body: serde_json::Value::Null,
+            id_routes: vec![&*DEMO_SCIM_TOKEN_ID_URL],
+        },
         // Create a SSH key
         SetupReq::Post {
             url: &*DEMO_SSHKEYS_URL,
             body: serde_json::to_value(&*DEMO_SSHKEY_CREATE).unwrap(),
             id_routes: vec![],
         },
+        // Create an API token
+        SetupReq::Post {
+            url: &*DEMO_API_TOKENS_URL,
+            body: serde_json::to_value(&*DEMO_API_TOKEN_CREATE).unwrap(),
+            id_routes: vec![],
+        },
     ];
 }
 
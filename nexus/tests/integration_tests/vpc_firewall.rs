@@ -2,22 +2,29 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use crate::integration_tests::instances::instance_post;
+use crate::integration_tests::instances::instance_simulate;
+use crate::integration_tests::instances::InstanceOp;
 use http::method::Method;
 use http::StatusCode;
+use ipnetwork::IpNetwork;
 use nexus_test_utils::http_testing::{AuthnMode, NexusRequest};
 use nexus_test_utils::resource_helpers::{
-    create_organization, create_project, create_vpc,
+    create_instance, create_ip_pool, create_organization, create_project,
+    create_vpc,
 };
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
 use omicron_common::api::external::{
-    IdentityMetadata, L4Port, L4PortRange, VpcFirewallRule,
+    IdentityMetadata, Instance, IpNet, L4Port, L4PortRange, VpcFirewallRule,
     VpcFirewallRuleAction, VpcFirewallRuleDirection, VpcFirewallRuleFilter,
     VpcFirewallRuleHostFilter, VpcFirewallRulePriority,
     VpcFirewallRuleProtocol, VpcFirewallRuleStatus, VpcFirewallRuleTarget,
     VpcFirewallRuleUpdate, VpcFirewallRuleUpdateParams, VpcFirewallRules,
 };
+use omicron_nexus::external_api::params;
 use omicron_nexus::external_api::views::Vpc;
+use omicron_nexus::external_api::views::VpcFirewallRuleEvaluation;
 use std::convert::TryFrom;
 use uuid::Uuid;
 
@@ -163,6 +170,320 @@ async fn test_vpc_firewall(cptestctx: &ControlPlaneTestContext) {
     .unwrap();
 }
 
+#[nexus_test]
+async fn test_vpc_firewall_tags(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx.nexus;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+
+    let org_name = "test-org";
+    let project_name = "springfield-squidport";
+    create_organization(&client, &org_name).await;
+    create_project(&client, &org_name, &project_name).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    let instance =
+        create_instance(&client, &org_name, &project_name, "web0").await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    assert!(instance.tags.is_empty());
+
+    let default_vpc_url = format!(
+        "/organizations/{}/projects/{}/vpcs/default",
+        org_name, project_name
+    );
+    let default_vpc: Vpc = NexusRequest::object_get(client, &default_vpc_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap();
+
+    // Allow HTTP to instances tagged "web" from instances tagged "lb".
+    let update_params = VpcFirewallRuleUpdateParams {
+        rules: vec![VpcFirewallRuleUpdate {
+            name: "allow-http".parse().unwrap(),
+            action: VpcFirewallRuleAction::Allow,
+            description: "allow http from load balancers".to_string(),
+            status: VpcFirewallRuleStatus::Enabled,
+            targets: vec![VpcFirewallRuleTarget::Tag("web".parse().unwrap())],
+            filters: VpcFirewallRuleFilter {
+                hosts: Some(vec![VpcFirewallRuleHostFilter::Tag(
+                    "lb".parse().unwrap(),
+                )]),
+                ports: Some(vec![L4PortRange {
+                    first: L4Port::try_from(80).unwrap(),
+                    last: L4Port::try_from(80).unwrap(),
+                }]),
+                protocols: Some(vec![VpcFirewallRuleProtocol::Tcp]),
+            },
+            direction: VpcFirewallRuleDirection::Inbound,
+            priority: VpcFirewallRulePriority(100),
+        }],
+    };
+    let rules = NexusRequest::object_put(
+        client,
+        &format!("{}/firewall/rules", default_vpc_url),
+        Some(&update_params),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<VpcFirewallRules>()
+    .unwrap()
+    .rules;
+    assert_eq!(rules[0].targets, update_params.rules[0].targets);
+
+    // No instance carries either tag, so the rule doesn't resolve to anything.
+    let sled_rules =
+        sled_agent.vpc_firewall_rules(default_vpc.identity.id).await.unwrap();
+    assert!(sled_rules.is_empty());
+
+    // Tagging the instance as both a target and a source makes the rule
+    // resolve. Duplicate tags are collapsed.
+    let tags_url = format!(
+        "/organizations/{}/projects/{}/instances/web0/tags",
+        org_name, project_name
+    );
+    let instance =
+        update_instance_tags(client, &tags_url, &["web", "lb", "web"]).await;
+    assert_eq!(
+        instance.tags,
+        vec!["lb".parse().unwrap(), "web".parse().unwrap()]
+    );
+    let sled_rules =
+        sled_agent.vpc_firewall_rules(default_vpc.identity.id).await.unwrap();
+    assert_eq!(sled_rules.len(), 1);
+    assert_eq!(sled_rules[0].targets.len(), 1);
+    let nic_ip = sled_rules[0].targets[0].ip;
+    assert_eq!(
+        sled_rules[0].filter_hosts.as_ref().unwrap(),
+        &vec![IpNet::from(IpNetwork::from(nic_ip))]
+    );
+
+    // Once the source tag is removed, the rule no longer resolves.
+    let instance = update_instance_tags(client, &tags_url, &["web"]).await;
+    assert_eq!(instance.tags, vec!["web".parse().unwrap()]);
+    let sled_rules =
+        sled_agent.vpc_firewall_rules(default_vpc.identity.id).await.unwrap();
+    assert!(sled_rules.is_empty());
+}
+
+#[nexus_test]
+async fn test_vpc_firewall_membership(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx.nexus;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+
+    let org_name = "test-org";
+    let project_name = "springfield-squidport";
+    create_organization(&client, &org_name).await;
+    create_project(&client, &org_name, &project_name).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    let default_vpc: Vpc = NexusRequest::object_get(
+        client,
+        &format!(
+            "/organizations/{}/projects/{}/vpcs/default",
+            org_name, project_name
+        ),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    let vpc_id = default_vpc.identity.id;
+
+    // Creating an instance pushes the default rules, which target every
+    // instance in the VPC, to its sled.
+    let instance =
+        create_instance(&client, &org_name, &project_name, "inst0").await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let sled_rules = sled_agent.vpc_firewall_rules(vpc_id).await.unwrap();
+    assert!(!sled_rules.is_empty());
+    assert!(sled_rules.iter().all(|rule| rule.targets.len() == 1));
+
+    // A second instance is added to the targets of those rules.
+    let instance1 =
+        create_instance(&client, &org_name, &project_name, "inst1").await;
+    instance_simulate(nexus, &instance1.identity.id).await;
+    let sled_rules = sled_agent.vpc_firewall_rules(vpc_id).await.unwrap();
+    assert!(sled_rules.iter().all(|rule| rule.targets.len() == 2));
+
+    // Deleting it removes it again.
+    let instance1_url = format!(
+        "/organizations/{}/projects/{}/instances/inst1",
+        org_name, project_name
+    );
+    instance_post(client, &instance1_url, InstanceOp::Stop).await;
+    instance_simulate(nexus, &instance1.identity.id).await;
+    NexusRequest::object_delete(client, &instance1_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    let sled_rules = sled_agent.vpc_firewall_rules(vpc_id).await.unwrap();
+    assert!(sled_rules.iter().all(|rule| rule.targets.len() == 1));
+
+    // If a sled loses its rules, the reconciliation pass restores them.
+    let expected_len = sled_rules.len();
+    sled_agent.vpc_firewall_rules_ensure(vpc_id, vec![]).await;
+    nexus.vpc_firewall_rules_reconcile().await.unwrap();
+    let sled_rules = sled_agent.vpc_firewall_rules(vpc_id).await.unwrap();
+    assert_eq!(sled_rules.len(), expected_len);
+    assert!(sled_rules.iter().all(|rule| rule.targets.len() == 1));
+}
+
+#[nexus_test]
+async fn test_vpc_firewall_evaluate(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let org_name = "test-org";
+    let project_name = "springfield-squidport";
+    create_organization(&client, &org_name).await;
+    create_project(&client, &org_name, &project_name).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    create_instance(&client, &org_name, &project_name, "web").await;
+    create_instance(&client, &org_name, &project_name, "db").await;
+    let evaluate_url = format!(
+        "/organizations/{}/projects/{}/vpcs/default/firewall/evaluate",
+        org_name, project_name
+    );
+    let web = params::VpcFirewallFlowHost::Instance("web".parse().unwrap());
+    let db = params::VpcFirewallFlowHost::Instance("db".parse().unwrap());
+    let outside =
+        params::VpcFirewallFlowHost::Ip("203.0.113.5".parse().unwrap());
+    let flow = |source: &params::VpcFirewallFlowHost,
+                destination: &params::VpcFirewallFlowHost,
+                port: u16| params::VpcFirewallRuleEvaluate {
+        source: source.clone(),
+        destination: destination.clone(),
+        protocol: VpcFirewallRuleProtocol::Tcp,
+        port: Some(L4Port::try_from(port).unwrap()),
+        direction: VpcFirewallRuleDirection::Inbound,
+    };
+
+    // Traffic within the VPC is allowed by the default rules.
+    let evaluation =
+        evaluate_flow(client, &evaluate_url, &flow(&web, &db, 5432)).await;
+    assert_eq!(evaluation.action, VpcFirewallRuleAction::Allow);
+    assert_eq!(
+        evaluation.rule.unwrap().identity.name.as_str(),
+        "allow-internal-inbound"
+    );
+    assert_eq!(evaluation.priority, Some(VpcFirewallRulePriority(65534)));
+
+    // So is SSH from outside it, but nothing else.
+    let evaluation =
+        evaluate_flow(client, &evaluate_url, &flow(&outside, &db, 22)).await;
+    assert_eq!(evaluation.action, VpcFirewallRuleAction::Allow);
+    assert_eq!(evaluation.rule.unwrap().identity.name.as_str(), "allow-ssh");
+    let evaluation =
+        evaluate_flow(client, &evaluate_url, &flow(&outside, &db, 5432)).await;
+    assert_eq!(evaluation.action, VpcFirewallRuleAction::Deny);
+    assert!(evaluation.rule.is_none());
+    assert!(evaluation.priority.is_none());
+
+    // A higher-priority rule denying the flow takes precedence.
+    let update_params = VpcFirewallRuleUpdateParams {
+        rules: vec![VpcFirewallRuleUpdate {
+            name: "deny-db-from-web".parse().unwrap(),
+            action: VpcFirewallRuleAction::Deny,
+            description: "keep web away from the database".to_string(),
+            status: VpcFirewallRuleStatus::Enabled,
+            targets: vec![VpcFirewallRuleTarget::Instance(
+                "db".parse().unwrap(),
+            )],
+            filters: VpcFirewallRuleFilter {
+                hosts: Some(vec![VpcFirewallRuleHostFilter::Instance(
+                    "web".parse().unwrap(),
+                )]),
+                ports: Some(vec![L4PortRange {
+                    first: L4Port::try_from(5432).unwrap(),
+                    last: L4Port::try_from(5432).unwrap(),
+                }]),
+                protocols: Some(vec![VpcFirewallRuleProtocol::Tcp]),
+            },
+            direction: VpcFirewallRuleDirection::Inbound,
+            priority: VpcFirewallRulePriority(100),
+        }],
+    };
+    NexusRequest::object_put(
+        client,
+        &format!(
+            "/organizations/{}/projects/{}/vpcs/default/firewall/rules",
+            org_name, project_name
+        ),
+        Some(&update_params),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let evaluation =
+        evaluate_flow(client, &evaluate_url, &flow(&web, &db, 5432)).await;
+    assert_eq!(evaluation.action, VpcFirewallRuleAction::Deny);
+    assert_eq!(
+        evaluation.rule.unwrap().identity.name.as_str(),
+        "deny-db-from-web"
+    );
+
+    // No rule applies to outbound traffic, which is allowed by default.
+    let mut outbound = flow(&db, &outside, 443);
+    outbound.direction = VpcFirewallRuleDirection::Outbound;
+    let evaluation = evaluate_flow(client, &evaluate_url, &outbound).await;
+    assert_eq!(evaluation.action, VpcFirewallRuleAction::Allow);
+    assert!(evaluation.rule.is_none());
+
+    // The end of the flow whose rules are evaluated must be in the VPC.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &evaluate_url,
+        &flow(&db, &outside, 443),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+async fn evaluate_flow(
+    client: &dropshot::test_util::ClientTestContext,
+    url: &str,
+    flow: &params::VpcFirewallRuleEvaluate,
+) -> VpcFirewallRuleEvaluation {
+    NexusRequest::objects_post(client, url, flow)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+async fn update_instance_tags(
+    client: &dropshot::test_util::ClientTestContext,
+    url: &str,
+    tags: &[&str],
+) -> Instance {
+    NexusRequest::object_put(
+        client,
+        url,
+        Some(&params::InstanceTagsUpdate {
+            tags: tags.iter().map(|t| t.parse().unwrap()).collect(),
+        }),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap()
+}
+
 async fn get_rules(
     client: &dropshot::test_util::ClientTestContext,
     url: &str,
@@ -0,0 +1,103 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::{create_organization, create_project};
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::VpcPeering;
+
+#[nexus_test]
+async fn test_vpc_peerings(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let organization_name = "test-org";
+    let project1_name = "springfield-squidport";
+    let project2_name = "shelbyville-squidport";
+    create_organization(&client, organization_name).await;
+    create_project(&client, organization_name, project1_name).await;
+    create_project(&client, organization_name, project2_name).await;
+    let vpc1_url = format!(
+        "/organizations/{}/projects/{}/vpcs/default",
+        organization_name, project1_name
+    );
+    let peerings_url = format!("{}/peerings", vpc1_url);
+
+    // A VPC can't peer with itself.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &peerings_url,
+        &peering_create("peering1", organization_name, project1_name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // The peer VPC must exist.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::POST,
+        &peerings_url,
+        &peering_create("peering1", organization_name, "no-such-project"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Otherwise, the request is valid, but peering isn't implemented yet.
+    let error = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::NOT_IMPLEMENTED,
+        Method::POST,
+        &peerings_url,
+        &peering_create("peering1", organization_name, project2_name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<dropshot::HttpErrorResponseBody>()
+    .unwrap();
+    assert_eq!(error.message, "VPC peering is not implemented yet");
+    let peerings =
+        objects_list_page_authz::<VpcPeering>(client, &peerings_url).await;
+    assert!(peerings.items.is_empty());
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &format!("{}/peering1", peerings_url),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+fn peering_create(
+    name: &str,
+    organization_name: &str,
+    project_name: &str,
+) -> params::VpcPeeringCreate {
+    params::VpcPeeringCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: String::from("a peering"),
+        },
+        peer_organization: organization_name.parse().unwrap(),
+        peer_project: project_name.parse().unwrap(),
+        peer_vpc: "default".parse().unwrap(),
+    }
+}
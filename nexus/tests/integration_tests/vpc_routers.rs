@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use crate::integration_tests::instances::instance_simulate;
 use http::method::Method;
 use http::StatusCode;
 use nexus_test_utils::http_testing::AuthnMode;
@@ -11,15 +12,26 @@ use nexus_test_utils::identity_eq;
 use nexus_test_utils::resource_helpers::create_router;
 use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils::resource_helpers::{
-    create_organization, create_project, create_vpc,
+    create_instance, create_ip_pool, create_organization, create_project,
+    create_vpc,
 };
 use nexus_test_utils::ControlPlaneTestContext;
 use nexus_test_utils_macros::nexus_test;
 use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::IdentityMetadataUpdateParams;
+use omicron_common::api::external::IpNet;
+use omicron_common::api::external::RouteDestination;
+use omicron_common::api::external::RouteTarget;
+use omicron_common::api::external::RouterRoute;
+use omicron_common::api::external::RouterRouteCreateParams;
 use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::Vpc;
 use omicron_nexus::external_api::views::VpcRouter;
 use omicron_nexus::external_api::views::VpcRouterKind;
+use omicron_nexus::external_api::views::VpcSubnet;
+use omicron_sled_agent::params::VpcRouteTarget;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
 
 #[nexus_test]
 async fn test_vpc_routers(cptestctx: &ControlPlaneTestContext) {
@@ -270,6 +282,155 @@ async fn test_vpc_routers(cptestctx: &ControlPlaneTestContext) {
     assert_eq!(router_same_name.vpc_id, vpc2.identity.id);
 }
 
+#[nexus_test]
+async fn test_vpc_routes_data_plane(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx.nexus;
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+
+    let organization_name = "test-org";
+    let project_name = "springfield-squidport";
+    create_organization(&client, organization_name).await;
+    create_project(&client, organization_name, project_name).await;
+    create_ip_pool(&client, "p0", None, None).await;
+    let vpc_url = format!(
+        "/organizations/{}/projects/{}/vpcs/default",
+        organization_name, project_name
+    );
+    let vpc: Vpc = NexusRequest::object_get(client, &vpc_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap();
+    let vpc_id = vpc.identity.id;
+
+    // The routes of the system router (the default route and the route for
+    // the default subnet) apply to every instance in the VPC, and are sent to
+    // the sled when an instance is created.
+    let instance =
+        create_instance(&client, organization_name, project_name, "inst0")
+            .await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let sled_routes = sled_agent.vpc_routes(vpc_id).await.unwrap();
+    assert!(sled_routes.iter().all(|route| route.interfaces.len() == 1));
+    assert!(sled_routes
+        .iter()
+        .any(|route| route.target == VpcRouteTarget::InternetGateway));
+    assert!(sled_routes.iter().any(|route| {
+        matches!(
+            route.target,
+            VpcRouteTarget::VpcSubnet(ref net) if *net == route.dest
+        )
+    }));
+    let ndefault = sled_routes.len();
+
+    // Routes of a custom router apply to nothing until the router is
+    // associated with a subnet.
+    let router_name = "router1";
+    create_router(
+        &client,
+        organization_name,
+        project_name,
+        "default",
+        router_name,
+    )
+    .await;
+    let dest: IpNet = "192.168.0.0/16".parse().unwrap();
+    let target = IpAddr::from(Ipv4Addr::new(172, 30, 0, 5));
+    let _: RouterRoute = NexusRequest::objects_post(
+        client,
+        &format!("{}/routers/{}/routes", vpc_url, router_name),
+        &RouterRouteCreateParams {
+            identity: IdentityMetadataCreateParams {
+                name: "route1".parse().unwrap(),
+                description: String::from("a custom route"),
+            },
+            target: RouteTarget::Ip(target),
+            destination: RouteDestination::IpNet(dest),
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    let sled_routes = sled_agent.vpc_routes(vpc_id).await.unwrap();
+    assert_eq!(sled_routes.len(), ndefault);
+
+    // Associating the router with the instance's subnet programs its routes
+    // for the instance's interface.
+    let subnet_router_url = format!("{}/subnets/default/router", vpc_url);
+    let subnet: VpcSubnet = NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &subnet_router_url)
+            .body(Some(&params::VpcSubnetRouterAttach {
+                router: router_name.parse().unwrap(),
+            }))
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    let router: VpcRouter = NexusRequest::object_get(
+        client,
+        &format!("{}/routers/{}", vpc_url, router_name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(subnet.custom_router_id, Some(router.identity.id));
+    let sled_routes = sled_agent.vpc_routes(vpc_id).await.unwrap();
+    assert_eq!(sled_routes.len(), ndefault + 1);
+    let route = sled_routes.iter().find(|route| route.dest == dest).unwrap();
+    assert_eq!(route.target, VpcRouteTarget::Ip(target));
+    assert_eq!(route.interfaces.len(), 1);
+
+    // The system router cannot be associated with a subnet.
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &subnet_router_url)
+            .body(Some(&params::VpcSubnetRouterAttach {
+                router: "system".parse().unwrap(),
+            }))
+            .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Deleting the router removes its routes from the sled and dissociates
+    // it from the subnet.
+    NexusRequest::object_delete(
+        client,
+        &format!("{}/routers/{}", vpc_url, router_name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let sled_routes = sled_agent.vpc_routes(vpc_id).await.unwrap();
+    assert_eq!(sled_routes.len(), ndefault);
+    let subnet: VpcSubnet = NexusRequest::object_get(
+        client,
+        &format!("{}/subnets/default", vpc_url),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(subnet.custom_router_id, None);
+}
+
 fn routers_eq(sn1: &VpcRouter, sn2: &VpcRouter) {
     identity_eq(&sn1.identity, &sn2.identity);
     assert_eq!(sn1.vpc_id, sn2.vpc_id);
@@ -8,7 +8,8 @@ use crate::external_api::shared;
 use chrono::{DateTime, Utc};
 use omicron_common::api::external::{
     ByteCount, IdentityMetadataCreateParams, IdentityMetadataUpdateParams,
-    InstanceCpuCount, Ipv4Net, Ipv6Net, Name,
+    InstanceCpuCount, Ipv4Net, Ipv6Net, L4Port, Name, VpcFirewallRuleDirection,
+    VpcFirewallRuleProtocol,
 };
 use schemars::JsonSchema;
 use serde::{
@@ -89,6 +90,115 @@ impl JsonSchema for UserId {
     }
 }
 
+/// A password used to authenticate a user in a Silo's local identity provider
+///
+/// Passwords may be between 8 and 512 bytes long.  This type deliberately
+/// doesn't implement `Debug`, so that passwords don't end up in logs.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(try_from = "String")]
+pub struct Password(String);
+
+impl Password {
+    pub const MIN_LENGTH: usize = 8;
+    pub const MAX_LENGTH: usize = 512;
+}
+
+impl AsRef<str> for Password {
+    fn as_ref(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl FromStr for Password {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Password::try_from(String::from(value))
+    }
+}
+
+/// Used to impl `Deserialize`
+impl TryFrom<String> for Password {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.len() < Password::MIN_LENGTH {
+            return Err(format!(
+                "passwords must be at least {} bytes long",
+                Password::MIN_LENGTH
+            ));
+        }
+        if value.len() > Password::MAX_LENGTH {
+            return Err(format!(
+                "passwords may be at most {} bytes long",
+                Password::MAX_LENGTH
+            ));
+        }
+        Ok(Password(value))
+    }
+}
+
+impl JsonSchema for Password {
+    fn schema_name() -> String {
+        "Password".to_string()
+    }
+
+    fn json_schema(
+        _: &mut schemars::gen::SchemaGenerator,
+    ) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            metadata: Some(Box::new(schemars::schema::Metadata {
+                title: Some(
+                    "A password used to authenticate a user".to_string(),
+                ),
+                description: Some(format!(
+                    "Passwords may be between {} and {} bytes long.",
+                    Password::MIN_LENGTH,
+                    Password::MAX_LENGTH
+                )),
+                ..Default::default()
+            })),
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            format: Some("password".to_string()),
+            string: Some(Box::new(schemars::schema::StringValidation {
+                max_length: Some(Password::MAX_LENGTH as u32),
+                min_length: Some(Password::MIN_LENGTH as u32),
+                pattern: None,
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Parameters for setting the password of a user in a Silo's local identity
+/// provider
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
+pub struct UserPassword {
+    pub password: Password,
+}
+
+/// Credentials for logging in to a Silo's local identity provider
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
+pub struct UsernamePasswordCredentials {
+    pub username: UserId,
+    pub password: Password,
+}
+
+// Silo groups
+
+/// Create-time parameters for a [`Group`](crate::external_api::views::Group)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct GroupCreate {
+    /// name that identifies the group within its Silo
+    pub display_name: Name,
+}
+
+/// Parameters for adding a user to a [`Group`](crate::external_api::views::Group)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct GroupMemberAdd {
+    /// the id of the user to add to the group
+    pub user_id: Uuid,
+}
+
 // Silo identity providers
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -241,6 +351,36 @@ pub struct SamlIdentityProviderCreate {
     pub group_attribute_name: Option<String>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OidcIdentityProviderCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// the issuer identifier of the OpenID provider, from which its discovery
+    /// document is fetched (at `/.well-known/openid-configuration`)
+    pub issuer: String,
+
+    /// the client id registered with the OpenID provider
+    pub client_id: String,
+
+    /// the client secret registered with the OpenID provider
+    pub client_secret: String,
+
+    /// scopes to request in addition to "openid"
+    #[serde(default)]
+    pub scopes: Vec<String>,
+
+    /// service provider endpoint to which the OpenID provider redirects the
+    /// user after authentication.  This should be the
+    /// `/login/{silo_name}/oidc/{provider_name}/callback` endpoint.
+    pub redirect_url: String,
+
+    /// If set, this claim of the ID token will be considered to denote a
+    /// user's group membership, where the claim value should be a list of
+    /// group names or a comma-separated string of group names.
+    pub group_claim_name: Option<String>,
+}
+
 /// sign some junk data and validate it with the key pair
 fn sign_junk_data(key_pair: &DerEncodedKeyPair) -> Result<(), anyhow::Error> {
     let private_key = {
@@ -334,6 +474,50 @@ pub struct ProjectUpdate {
     pub identity: IdentityMetadataUpdateParams,
 }
 
+/// Updateable settings of a [`Project`](crate::external_api::views::Project)
+///
+/// Each setting not provided is reset, so that the resource named "default"
+/// is used instead.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ProjectSettingsUpdate {
+    /// The VPC in which network interfaces are created by default
+    #[serde(default)]
+    pub default_vpc: Option<Name>,
+    /// The VPC Subnet in which network interfaces are created by default,
+    /// which must be in the default VPC
+    #[serde(default)]
+    pub default_vpc_subnet: Option<Name>,
+    /// The IP Pool from which external IP addresses are allocated by default
+    #[serde(default)]
+    pub default_ip_pool: Option<Name>,
+}
+
+// QUOTAS
+
+/// Updateable quotas of a Silo or Project
+///
+/// Each limit not provided is removed, so that the resource is unlimited.  A
+/// limit may be set below the amount of the resource already allocated, in
+/// which case no more of it can be allocated until enough has been freed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct ResourceQuotasUpdate {
+    /// Number of vCPUs of instances
+    #[serde(default)]
+    pub cpus: Option<u32>,
+    /// Memory of instances
+    #[serde(default)]
+    pub memory: Option<ByteCount>,
+    /// Total size of disks
+    #[serde(default)]
+    pub disk: Option<ByteCount>,
+    /// Number of snapshots
+    #[serde(default)]
+    pub snapshots: Option<u32>,
+    /// Number of Ephemeral and Floating IPs
+    #[serde(default)]
+    pub external_ips: Option<u32>,
+}
+
 // NETWORK INTERFACES
 
 /// Create-time parameters for a
@@ -342,10 +526,14 @@ pub struct ProjectUpdate {
 pub struct NetworkInterfaceCreate {
     #[serde(flatten)]
     pub identity: IdentityMetadataCreateParams,
-    /// The VPC in which to create the interface.
-    pub vpc_name: Name,
-    /// The VPC Subnet in which to create the interface.
-    pub subnet_name: Name,
+    /// The VPC in which to create the interface. If not provided, the
+    /// project's default VPC is used.
+    #[serde(default)]
+    pub vpc_name: Option<Name>,
+    /// The VPC Subnet in which to create the interface. If not provided, the
+    /// project's default VPC Subnet is used.
+    #[serde(default)]
+    pub subnet_name: Option<Name>,
     /// The IP address for the interface. One will be auto-assigned if not provided.
     pub ip: Option<IpAddr>,
 }
@@ -471,12 +659,45 @@ pub struct InstanceDiskAttach {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ExternalIpCreate {
     /// An IP address providing both inbound and outbound access. The address is
-    /// automatically-assigned from the provided IP Pool, or all available pools
-    /// if not specified.
+    /// automatically-assigned from the provided IP Pool, or the project's
+    /// default IP Pool if not specified.
     Ephemeral { pool_name: Option<Name> },
     // TODO: Add floating IPs: https://github.com/oxidecomputer/omicron/issues/1334
 }
 
+/// Parameters for allocating an Ephemeral IP address for an existing instance
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct EphemeralIpCreate {
+    /// The IP Pool from which to allocate the address.  If not specified, the
+    /// address is allocated from the default pool of the instance's project.
+    #[serde(default)]
+    pub pool_name: Option<Name>,
+}
+
+// FLOATING IPS
+
+/// Create-time parameters for a
+/// [`FloatingIp`](crate::external_api::views::FloatingIp)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FloatingIpCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// The IP Pool from which to allocate the address.  If not specified, the
+    /// address is allocated from the project's default pool.
+    #[serde(default)]
+    pub pool_name: Option<Name>,
+}
+
+/// Parameters for attaching a
+/// [`FloatingIp`](crate::external_api::views::FloatingIp) to an instance
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FloatingIpAttach {
+    /// Name of the instance, which must be in the same project as the
+    /// Floating IP
+    pub instance: Name,
+}
+
 /// Create-time parameters for an [`Instance`](omicron_common::api::external::Instance)
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InstanceCreate {
@@ -593,6 +814,14 @@ pub struct InstanceMigrate {
     pub dst_sled_id: Uuid,
 }
 
+/// Updated set of tags for an [`Instance`](omicron_common::api::external::Instance)
+///
+/// The provided tags replace any tags currently attached to the instance.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct InstanceTagsUpdate {
+    pub tags: Vec<Name>,
+}
+
 /// Forwarded to a sled agent to request the contents of an Instance's serial console.
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
 pub struct InstanceSerialConsoleRequest {
@@ -675,6 +904,39 @@ pub struct VpcSubnetUpdate {
     pub identity: IdentityMetadataUpdateParams,
 }
 
+/// Parameters for associating a [`VpcSubnet`](crate::external_api::views::VpcSubnet)
+/// with a custom VPC router
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcSubnetRouterAttach {
+    /// The name of a custom router in the subnet's VPC
+    pub router: Name,
+}
+
+/// One end of a synthetic flow evaluated against a VPC's firewall rules
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum VpcFirewallFlowHost {
+    /// The primary interface in the VPC of the named instance
+    Instance(Name),
+    /// An IP address, which may belong to an interface in the VPC
+    Ip(IpAddr),
+}
+
+/// A synthetic flow to evaluate against a VPC's firewall rules
+///
+/// For inbound flows the rules of the destination's interface are evaluated,
+/// and for outbound flows those of the source's, so that end of the flow must
+/// be an interface in the VPC.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcFirewallRuleEvaluate {
+    pub source: VpcFirewallFlowHost,
+    pub destination: VpcFirewallFlowHost,
+    pub protocol: VpcFirewallRuleProtocol,
+    /// The destination port, if the protocol has one
+    pub port: Option<L4Port>,
+    pub direction: VpcFirewallRuleDirection,
+}
+
 // VPC ROUTERS
 
 /// Create-time parameters for a [`VpcRouter`](crate::external_api::views::VpcRouter)
@@ -691,6 +953,45 @@ pub struct VpcRouterUpdate {
     pub identity: IdentityMetadataUpdateParams,
 }
 
+// VPC PEERINGS
+
+/// Create-time parameters for a [`VpcPeering`](crate::external_api::views::VpcPeering)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcPeeringCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+    /// The organization containing the peer VPC
+    pub peer_organization: Name,
+    /// The project containing the peer VPC
+    pub peer_project: Name,
+    /// The VPC to peer with, which must be in the same silo
+    pub peer_vpc: Name,
+}
+
+// INTERNET GATEWAYS
+
+/// Create-time parameters for an [`InternetGateway`](crate::external_api::views::InternetGateway)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct InternetGatewayCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+    /// The IP Pools from which instances in the VPC are given source NAT
+    /// addresses, in order of preference. If empty, the project's default IP
+    /// Pool is used.
+    #[serde(default)]
+    pub ip_pools: Vec<Name>,
+}
+
+/// Updateable properties of an [`InternetGateway`](crate::external_api::views::InternetGateway)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct InternetGatewayUpdate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataUpdateParams,
+    /// If present, replaces the IP Pools of the gateway
+    #[serde(default)]
+    pub ip_pools: Option<Vec<Name>>,
+}
+
 // DISKS
 
 pub const MIN_DISK_SIZE_BYTES: u32 = 1 << 30; // 1 GiB
@@ -893,6 +1194,30 @@ pub struct SshKeyCreate {
     pub public_key: String,
 }
 
+// API TOKENS
+//
+// Like SSH keys, API tokens are managed under `/session/me`.
+
+/// Create-time parameters for an [`ApiToken`](crate::external_api::views::ApiToken)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ApiTokenCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// When the token stops being accepted.  Tokens without an expiration
+    /// time are accepted until they're deleted.
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
+// SAGAS
+
+/// Parameters for an operator intervention on a saga (abandon or retry)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SagaIntervene {
+    /// Why the saga is being intervened upon, recorded with the intervention
+    pub reason: String,
+}
+
 // METRICS
 
 /// Query parameters common to resource metrics endpoints.
@@ -903,3 +1228,20 @@ pub struct ResourceMetrics {
     /// An exclusive end time of metrics.
     pub end_time: DateTime<Utc>,
 }
+
+// AUDIT LOG
+
+/// Query parameters for filtering the audit log
+///
+/// All filters are optional.  Entries are returned oldest first.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AuditLogFilter {
+    /// If present, only include requests that completed at or after this time
+    pub start_time: Option<DateTime<Utc>>,
+    /// If present, only include requests that completed before this time
+    pub end_time: Option<DateTime<Utc>>,
+    /// If present, only include requests made by this user
+    pub actor_id: Option<Uuid>,
+    /// If present, only include requests that acted on this resource
+    pub resource_id: Option<Uuid>,
+}
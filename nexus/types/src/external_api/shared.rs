@@ -101,6 +101,22 @@ pub enum SiloIdentityMode {
     /// groups).
     SamlJit,
 
+    /// Users are authenticated with OpenID Connect using an external
+    /// authentication provider.  As with `SamlJit`, users and groups are
+    /// provisioned during successful authentication.
+    OidcJit,
+
+    /// Users are authenticated with SAML using an external authentication
+    /// provider.  Users and groups are provisioned by the identity provider
+    /// through the Silo's SCIM API, not during authentication.
+    SamlScim,
+
+    /// Users are authenticated with OpenID Connect using an external
+    /// authentication provider.  Users and groups are provisioned by the
+    /// identity provider through the Silo's SCIM API, not during
+    /// authentication.
+    OidcScim,
+
     /// The system is the source of truth about users.  There is no linkage to
     /// an external authentication provider or identity provider.
     // NOTE: authentication for these users is not supported yet at all.  It
@@ -113,6 +129,9 @@ impl SiloIdentityMode {
         match self {
             SiloIdentityMode::LocalOnly => AuthenticationMode::Local,
             SiloIdentityMode::SamlJit => AuthenticationMode::Saml,
+            SiloIdentityMode::OidcJit => AuthenticationMode::Oidc,
+            SiloIdentityMode::SamlScim => AuthenticationMode::Saml,
+            SiloIdentityMode::OidcScim => AuthenticationMode::Oidc,
         }
     }
 
@@ -120,6 +139,9 @@ impl SiloIdentityMode {
         match self {
             SiloIdentityMode::LocalOnly => UserProvisionType::ApiOnly,
             SiloIdentityMode::SamlJit => UserProvisionType::Jit,
+            SiloIdentityMode::OidcJit => UserProvisionType::Jit,
+            SiloIdentityMode::SamlScim => UserProvisionType::Scim,
+            SiloIdentityMode::OidcScim => UserProvisionType::Scim,
         }
     }
 }
@@ -131,6 +153,10 @@ pub enum AuthenticationMode {
     /// Authentication is via SAML using an external authentication provider
     Saml,
 
+    /// Authentication is via OpenID Connect using an external authentication
+    /// provider
+    Oidc,
+
     /// Authentication is local to the Oxide system
     Local,
 }
@@ -147,6 +173,11 @@ pub enum UserProvisionType {
     /// Users and groups are created or updated during authentication using
     /// information provided by the authentication provider
     Jit,
+
+    /// Users and groups are created, updated, and deactivated by the identity
+    /// provider through the Silo's SCIM 2.0 API.  They are not created when a
+    /// user logs in.
+    Scim,
 }
 
 /// An IP Range is a contiguous range of IP addresses, usually within an IP
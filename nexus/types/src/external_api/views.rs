@@ -11,7 +11,8 @@ use chrono::DateTime;
 use chrono::Utc;
 use omicron_common::api::external::{
     ByteCount, Digest, IdentityMetadata, Ipv4Net, Ipv6Net, Name,
-    ObjectIdentity, RoleName,
+    ObjectIdentity, RoleName, VpcFirewallRule, VpcFirewallRuleAction,
+    VpcFirewallRulePriority,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -69,6 +70,9 @@ pub struct Silo {
 pub enum IdentityProviderType {
     /// SAML identity provider
     Saml,
+
+    /// OpenID Connect identity provider
+    Oidc,
 }
 
 /// Client view of an [`IdentityProvider`]
@@ -105,6 +109,28 @@ pub struct SamlIdentityProvider {
     pub public_cert: Option<String>,
 }
 
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OidcIdentityProvider {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// the issuer identifier of the OpenID provider
+    pub issuer: String,
+
+    /// the client id registered with the OpenID provider
+    pub client_id: String,
+
+    /// scopes requested in addition to "openid"
+    pub scopes: Vec<String>,
+
+    /// service provider endpoint to which the OpenID provider redirects the
+    /// user after authentication
+    pub redirect_url: String,
+
+    /// if set, this claim of the ID token denotes a user's group membership
+    pub group_claim_name: Option<String>,
+}
+
 // ORGANIZATIONS
 
 /// Client view of an [`Organization`]
@@ -127,6 +153,66 @@ pub struct Project {
     pub organization_id: Uuid,
 }
 
+/// Client view of the settings of a [`Project`]
+///
+/// Settings which are unset fall back to the VPC and VPC Subnet named
+/// "default", and to any IP Pool available to the project.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ProjectSettings {
+    pub project_id: Uuid,
+    /// The VPC in which network interfaces are created by default
+    pub default_vpc_id: Option<Uuid>,
+    /// The VPC Subnet in which network interfaces are created by default
+    pub default_vpc_subnet_id: Option<Uuid>,
+    /// The IP Pool from which external IP addresses are allocated by default
+    pub default_ip_pool_id: Option<Uuid>,
+}
+
+// QUOTAS
+
+/// Client view of the quotas of a Silo or Project, along with the resources
+/// allocated within it that count against them
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ResourceQuotas {
+    /// The Silo or Project to which these quotas apply
+    pub id: Uuid,
+    /// The most of each resource that may be allocated.  A resource without
+    /// a limit is unlimited.
+    pub limits: QuotaLimits,
+    /// The resources currently allocated
+    pub usage: QuotaUsage,
+}
+
+/// Limits on the resources allocated within a Silo or Project
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct QuotaLimits {
+    /// Number of vCPUs of instances
+    pub cpus: Option<u64>,
+    /// Memory of instances
+    pub memory: Option<ByteCount>,
+    /// Total size of disks
+    pub disk: Option<ByteCount>,
+    /// Number of snapshots
+    pub snapshots: Option<u64>,
+    /// Number of Ephemeral and Floating IPs
+    pub external_ips: Option<u64>,
+}
+
+/// Resources allocated within a Silo or Project
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct QuotaUsage {
+    /// Number of vCPUs of instances
+    pub cpus: u64,
+    /// Memory of instances
+    pub memory: ByteCount,
+    /// Total size of disks
+    pub disk: ByteCount,
+    /// Number of snapshots
+    pub snapshots: u64,
+    /// Number of Ephemeral and Floating IPs
+    pub external_ips: u64,
+}
+
 // IMAGES
 
 /// Client view of global Images
@@ -242,6 +328,9 @@ pub struct VpcSubnet {
 
     /// The IPv6 subnet CIDR block.
     pub ipv6_block: Ipv6Net,
+
+    /// The custom VPC router associated with this subnet, if any.
+    pub custom_router_id: Option<Uuid>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
@@ -265,6 +354,69 @@ pub struct VpcRouter {
     pub vpc_id: Uuid,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VpcPeeringState {
+    /// The peer VPC has not yet peered with this VPC.
+    Pending,
+    /// Both VPCs have peered with each other, and each VPC's routes and
+    /// firewall rules take the other into account.
+    Active,
+}
+
+/// A VPC peering connects a VPC to another VPC in the same silo.
+///
+/// Each VPC has its own side of the peering, which is only active once the
+/// peer VPC has peered with it in turn.
+///
+/// Peerings cannot be created yet, because sleds do not forward traffic
+/// between VPCs.
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcPeering {
+    /// common identifying metadata
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// The VPC to which the peering belongs.
+    pub vpc_id: Uuid,
+
+    /// The VPC peered with.
+    pub peer_vpc_id: Uuid,
+
+    pub state: VpcPeeringState,
+}
+
+/// An internet gateway connects a VPC to destinations outside the rack.
+///
+/// Routes direct traffic to a gateway by name. Instances whose VPC's default
+/// route targets the gateway are given source NAT addresses from its IP Pools.
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct InternetGateway {
+    /// common identifying metadata
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// The VPC to which the gateway belongs.
+    pub vpc_id: Uuid,
+
+    /// The IP Pools from which source NAT addresses are allocated, in order
+    /// of preference.
+    pub ip_pool_ids: Vec<Uuid>,
+}
+
+/// The outcome of evaluating a synthetic flow against a VPC's firewall rules
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcFirewallRuleEvaluation {
+    /// The highest-priority enabled rule matching the flow, if any
+    pub rule: Option<VpcFirewallRule>,
+    /// The priority of the matching rule, if any
+    pub priority: Option<VpcFirewallRulePriority>,
+    /// Whether the flow is allowed or dropped
+    ///
+    /// Flows matching no rule are dropped if inbound and allowed if outbound.
+    pub action: VpcFirewallRuleAction,
+}
+
 // IP POOLS
 
 #[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -290,6 +442,19 @@ pub struct ExternalIp {
     pub kind: IpKind,
 }
 
+/// Client view of a Floating IP
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FloatingIp {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+    /// the IP address held by this resource
+    pub ip: IpAddr,
+    /// the project this resource exists within
+    pub project_id: Uuid,
+    /// the instance this Floating IP is attached to, if any
+    pub instance_id: Option<Uuid>,
+}
+
 // RACKS
 
 /// Client view of an [`Rack`]
@@ -371,6 +536,138 @@ pub struct SshKey {
     pub public_key: String,
 }
 
+// API TOKENS
+
+/// Client view of an [`ApiToken`]
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ApiToken {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// The user to whom this token belongs
+    pub silo_user_id: Uuid,
+
+    /// When the token stops being accepted, if ever
+    pub time_expires: Option<DateTime<Utc>>,
+
+    /// When the token was last used to authenticate a request, if ever
+    pub time_last_used: Option<DateTime<Utc>>,
+}
+
+/// A newly-created [`ApiToken`], including the token itself
+///
+/// The token is only revealed when it's created.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ApiTokenValue {
+    #[serde(flatten)]
+    pub token: ApiToken,
+
+    /// Bearer token with which to authenticate requests, e.g.,
+    /// `"Authorization: Bearer oxide-token-..."`
+    pub access_token: String,
+}
+
+// CONSOLE SESSIONS
+
+/// Client view of a web console session, not including its token
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ConsoleSession {
+    /// Identifies the session, e.g. to revoke it
+    pub id: Uuid,
+
+    /// When the user logged in
+    pub time_created: DateTime<Utc>,
+
+    /// When the session was last used to authenticate a request
+    pub time_last_used: DateTime<Utc>,
+
+    /// The User-Agent of the client that logged in, if it sent one
+    pub user_agent: Option<String>,
+
+    /// The IP address of the client that logged in, if known
+    pub client_address: Option<IpAddr>,
+}
+
+// SAGAS
+
+/// Execution log of a saga, as recorded by its saga execution coordinator
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SagaLog {
+    pub id: Uuid,
+    /// The kind of saga, e.g., `instance-create`
+    pub name: String,
+    pub time_created: DateTime<Utc>,
+    /// The persistent state of the saga
+    pub state: SagaLogState,
+    /// The Nexus instance responsible for executing the saga, if any.  A saga
+    /// that is not done and has no coordinator has been abandoned.
+    pub current_sec: Option<Uuid>,
+    /// When the current coordinator took ownership of the saga
+    pub adopt_time: DateTime<Utc>,
+    /// Events recorded for each node of the saga, in the order they happened
+    pub events: Vec<SagaNodeEvent>,
+    /// Operator interventions on this saga, oldest first
+    pub interventions: Vec<SagaIntervention>,
+}
+
+/// Persistent state of a saga
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaLogState {
+    Running,
+    Unwinding,
+    Done,
+}
+
+/// A single event in the log of a saga
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SagaNodeEvent {
+    pub node_id: u32,
+    /// Name of the node in the saga DAG, if it has one
+    pub node_name: Option<String>,
+    pub event_type: SagaNodeEventType,
+    /// Output of a successful action, or the error from a failed one
+    pub data: Option<serde_json::Value>,
+    pub time: DateTime<Utc>,
+    /// The Nexus instance that recorded this event
+    pub sec_id: Uuid,
+}
+
+/// Kinds of events recorded for saga nodes
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaNodeEventType {
+    Started,
+    Succeeded,
+    Failed,
+    UndoStarted,
+    UndoFinished,
+}
+
+/// Record of an operator intervening in the execution of a saga
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SagaIntervention {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub saga_id: Uuid,
+    pub kind: SagaInterventionKind,
+    /// The user who requested the intervention
+    pub actor_id: Uuid,
+    /// For a retry, the Nexus instance that adopted the saga
+    pub new_sec: Option<Uuid>,
+    pub reason: String,
+}
+
+/// Kinds of operator interventions on a saga
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaInterventionKind {
+    /// The saga was released by its coordinator and will not be resumed
+    Abandon,
+    /// The saga was adopted by a Nexus instance and resumed from its log
+    Retry,
+}
+
 // OAUTH 2.0 DEVICE AUTHORIZATION REQUESTS & TOKENS
 
 /// Response to an initial device authorization request.
@@ -413,3 +710,65 @@ pub struct DeviceAccessTokenGrant {
 pub enum DeviceAccessTokenType {
     Bearer,
 }
+
+/// Client view of a device access token, not including the token itself
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DeviceAccessToken {
+    /// Identifies the token, e.g. to revoke it
+    pub id: Uuid,
+
+    /// The client to which the token was granted
+    pub client_id: Uuid,
+
+    pub time_created: DateTime<Utc>,
+
+    /// When the token stops being accepted, if ever
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
+// SCIM
+
+/// A bearer token with which an identity provider authenticates to a Silo's
+/// SCIM API
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimClientBearerToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+}
+
+/// A newly created SCIM client bearer token, including the token itself
+///
+/// The token is only revealed when it's created.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimClientBearerTokenValue {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub bearer_token: String,
+}
+
+// AUDIT LOG
+
+/// Record of a request to the external API that changed (or tried to change)
+/// the state of the system
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    /// When the request was received
+    pub time_started: DateTime<Utc>,
+    /// When the response was sent
+    pub time_completed: DateTime<Utc>,
+    /// The request id, as reported to the client in the `x-request-id` header
+    pub request_id: String,
+    /// The user that made the request, if it was authenticated as one
+    pub actor_id: Option<Uuid>,
+    /// The Silo of the client that made the request, if any
+    pub actor_silo_id: Option<Uuid>,
+    pub http_method: String,
+    pub request_uri: String,
+    /// Resources that the request was authorized to modify, delete, or create
+    /// things in
+    pub resource_ids: Vec<Uuid>,
+    pub http_status_code: u16,
+    /// For failed requests, the error message returned to the client
+    pub error_message: Option<String>,
+}
@@ -13,7 +13,8 @@ use dropshot::ConfigLoggingLevel;
 use omicron_common::cmd::fatal;
 use omicron_common::cmd::CmdError;
 use omicron_sled_agent::sim::{
-    run_server, Config, ConfigStorage, ConfigZpool, SimMode,
+    run_server, Config, ConfigFaults, ConfigHardware, ConfigStorage,
+    ConfigZpool, SimMode,
 };
 use std::net::SocketAddr;
 use std::net::SocketAddrV6;
@@ -46,6 +47,22 @@ struct Args {
 
     #[clap(name = "NEXUS_IP:PORT", action)]
     nexus_addr: SocketAddr,
+
+    #[clap(
+        long = "hardware-threads",
+        default_value = "128",
+        help = "Number of hardware threads available to instances",
+        action
+    )]
+    hardware_threads: u32,
+
+    #[clap(
+        long = "physical-ram-gib",
+        default_value = "1024",
+        help = "Memory available to instances, in GiB",
+        action
+    )]
+    physical_ram_gib: u64,
 }
 
 #[tokio::main]
@@ -73,6 +90,11 @@ async fn do_run() -> Result<(), CmdError> {
             zpools: vec![ConfigZpool { size: 1 << 40 }; 10],
             ip: (*args.sled_agent_addr.ip()).into(),
         },
+        hardware: ConfigHardware {
+            hardware_threads: args.hardware_threads,
+            physical_ram: args.physical_ram_gib << 30,
+        },
+        faults: ConfigFaults::default(),
     };
 
     run_server(&config).await.map_err(CmdError::Failure)
@@ -6,8 +6,9 @@
 
 use crate::params::{
     DatasetEnsureBody, DiskEnsureBody, InstanceEnsureBody,
-    InstanceSerialConsoleData, InstanceSerialConsoleRequest, ServiceEnsureBody,
-    VpcFirewallRulesEnsureBody,
+    InstanceExternalIpBody, InstanceSerialConsoleData,
+    InstanceSerialConsoleRequest, ServiceEnsureBody,
+    VpcFirewallRulesEnsureBody, VpcRoutesEnsureBody,
 };
 use crate::serial::ByteOffset;
 use dropshot::{
@@ -35,12 +36,14 @@ pub fn api() -> SledApiDescription {
         api.register(services_put)?;
         api.register(filesystem_put)?;
         api.register(instance_put)?;
+        api.register(instance_put_external_ips)?;
         api.register(disk_put)?;
         api.register(update_artifact)?;
         api.register(instance_serial_get)?;
         api.register(instance_issue_disk_snapshot_request)?;
         api.register(issue_disk_snapshot_request)?;
         api.register(vpc_firewall_rules_put)?;
+        api.register(vpc_routes_put)?;
 
         Ok(())
     }
@@ -116,6 +119,24 @@ async fn instance_put(
     ))
 }
 
+#[endpoint {
+    method = PUT,
+    path = "/instances/{instance_id}/external-ips",
+}]
+async fn instance_put_external_ips(
+    rqctx: Arc<RequestContext<SledAgent>>,
+    path_params: Path<InstancePathParam>,
+    body: TypedBody<InstanceExternalIpBody>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let instance_id = path_params.into_inner().instance_id;
+    let body_args = body.into_inner();
+    sa.instance_external_ips_ensure(instance_id, body_args.external_ips)
+        .await
+        .map_err(Error::from)?;
+    Ok(HttpResponseUpdatedNoContent())
+}
+
 /// Path parameters for Disk requests (sled agent API)
 #[derive(Deserialize, JsonSchema)]
 struct DiskPathParam {
@@ -318,3 +339,23 @@ async fn vpc_firewall_rules_put(
 
     Ok(HttpResponseUpdatedNoContent())
 }
+
+#[endpoint {
+    method = PUT,
+    path = "/vpc/{vpc_id}/routes",
+}]
+async fn vpc_routes_put(
+    rqctx: Arc<RequestContext<SledAgent>>,
+    path_params: Path<VpcPathParam>,
+    body: TypedBody<VpcRoutesEnsureBody>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let vpc_id = path_params.into_inner().vpc_id;
+    let body_args = body.into_inner();
+
+    sa.routes_ensure(vpc_id, body_args.vni, &body_args.routes[..])
+        .await
+        .map_err(Error::from)?;
+
+    Ok(HttpResponseUpdatedNoContent())
+}
@@ -452,6 +452,10 @@ mockall::mock! {
             disk_id: Uuid,
             snapshot_name: Uuid,
         ) -> Result<(), Error>;
+        pub async fn external_ips_ensure(
+            &self,
+            external_ips: Vec<IpAddr>,
+        ) -> Result<(), Error>;
     }
     impl Clone for Instance {
         fn clone(&self) -> Self;
@@ -861,6 +865,23 @@ impl Instance {
             Err(Error::InstanceNotRunning(inner.properties.id))
         }
     }
+
+    /// Replaces the external IP addresses provided to this instance
+    ///
+    /// If the instance is running, its OPTE ports are updated too.
+    pub async fn external_ips_ensure(
+        &self,
+        external_ips: Vec<IpAddr>,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock().await;
+        if inner.running_state.is_some() {
+            inner
+                .port_manager
+                .external_ips_ensure(inner.properties.id, &external_ips)?;
+        }
+        inner.external_ips = external_ips;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -10,13 +10,15 @@ use crate::nexus::LazyNexusClient;
 use crate::opte::PortManager;
 use crate::params::{
     InstanceHardware, InstanceMigrateParams, InstanceRuntimeStateRequested,
-    InstanceSerialConsoleData, VpcFirewallRule,
+    InstanceSerialConsoleData, VpcFirewallRule, VpcRoute,
 };
 use crate::serial::ByteOffset;
 use macaddr::MacAddr6;
+use omicron_common::api::external;
 use omicron_common::api::internal::nexus::InstanceRuntimeState;
 use slog::Logger;
 use std::collections::BTreeMap;
+use std::net::IpAddr;
 use std::net::Ipv6Addr;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
@@ -213,6 +215,22 @@ impl InstanceManager {
             .map_err(Error::from)
     }
 
+    pub async fn instance_external_ips_ensure(
+        &self,
+        instance_id: Uuid,
+        external_ips: Vec<IpAddr>,
+    ) -> Result<(), Error> {
+        let instance = {
+            let instances = self.inner.instances.lock().unwrap();
+            let (_, instance) = instances
+                .get(&instance_id)
+                .ok_or(Error::NoSuchInstance(instance_id))?;
+            instance.clone()
+        };
+
+        instance.external_ips_ensure(external_ips).await.map_err(Error::from)
+    }
+
     pub async fn firewall_rules_ensure(
         &self,
         rules: &[VpcFirewallRule],
@@ -225,6 +243,21 @@ impl InstanceManager {
         self.inner.port_manager.firewall_rules_ensure(rules)?;
         Ok(())
     }
+
+    pub async fn routes_ensure(
+        &self,
+        vni: external::Vni,
+        routes: &[VpcRoute],
+    ) -> Result<(), Error> {
+        info!(
+            &self.inner.log,
+            "Ensuring VPC routes";
+            "vni" => ?vni,
+            "routes" => ?&routes,
+        );
+        self.inner.port_manager.routes_ensure(vni, routes)?;
+        Ok(())
+    }
 }
 
 /// Represents membership of an instance in the [`InstanceManager`].
@@ -28,6 +28,9 @@ pub enum Error {
     #[error("Failed to wrap OPTE port in a VNIC: {0}")]
     CreateVnic(#[from] dladm::CreateVnicError),
 
+    #[error("Failed to delete the VNIC over an OPTE port: {0}")]
+    DeleteVnic(#[from] dladm::DeleteVnicError),
+
     #[error("Failed to get VNICs for xde underlay devices: {0}")]
     GetVnic(#[from] underlay::Error),
 
@@ -51,6 +54,9 @@ pub enum Error {
 
     #[error(transparent)]
     ResetLinkpropError(#[from] crate::illumos::dladm::ResetLinkpropError),
+
+    #[error("OPTE cannot carry out VPC route: {0}")]
+    UnsupportedRoute(String),
 }
 
 /// Delete all xde devices on the system.
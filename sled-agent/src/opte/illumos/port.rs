@@ -14,6 +14,7 @@ use macaddr::MacAddr6;
 use std::net::IpAddr;
 use std::net::Ipv6Addr;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 #[derive(Debug)]
 struct PortInner {
@@ -36,7 +37,10 @@ struct PortInner {
     _source_nat: Option<SourceNatConfig>,
     // The external IP addresses provided to this port, to allow _inbound_
     // network connectivity.
-    external_ips: Option<Vec<IpAddr>>,
+    //
+    // These change when the port manager rebuilds the port, see
+    // `PortManager::external_ips_ensure()`.
+    external_ips: Mutex<Option<Vec<IpAddr>>>,
     // Information about the virtual gateway, aka OPTE
     _gateway: Gateway,
     // Information about Boundary Services, for forwarding traffic between sleds
@@ -125,7 +129,7 @@ impl Port {
                 vni: vni,
                 _underlay_ip: underlay_ip,
                 _source_nat: source_nat,
-                external_ips,
+                external_ips: Mutex::new(external_ips),
                 _gateway: gateway,
                 _boundary_services: boundary_services,
                 vnic,
@@ -133,8 +137,12 @@ impl Port {
         }
     }
 
-    pub fn external_ips(&self) -> &Option<Vec<IpAddr>> {
-        &self.inner.external_ips
+    pub fn external_ips(&self) -> Option<Vec<IpAddr>> {
+        self.inner.external_ips.lock().unwrap().clone()
+    }
+
+    pub(super) fn set_external_ips(&self, external_ips: Option<Vec<IpAddr>>) {
+        *self.inner.external_ips.lock().unwrap() = external_ips;
     }
 
     pub fn mac(&self) -> &MacAddr6 {
@@ -9,6 +9,7 @@ use crate::illumos::dladm::PhysicalLink;
 use crate::illumos::dladm::VnicSource;
 use crate::opte::default_boundary_services;
 use crate::opte::opte_firewall_rules;
+use crate::opte::BoundaryServices;
 use crate::opte::Error;
 use crate::opte::Gateway;
 use crate::opte::Port;
@@ -16,8 +17,13 @@ use crate::opte::Vni;
 use crate::params::NetworkInterface;
 use crate::params::SourceNatConfig;
 use crate::params::VpcFirewallRule;
+use crate::params::VpcRoute;
+use crate::params::VpcRouteTarget;
 use ipnetwork::IpNetwork;
 use macaddr::MacAddr6;
+use omicron_common::api::external;
+use omicron_common::api::external::IpNet;
+use omicron_common::api::external::Ipv4Net;
 use opte_ioctl::OpteHdl;
 use oxide_vpc::api::AddRouterEntryReq;
 use oxide_vpc::api::IpCfg;
@@ -75,9 +81,198 @@ struct PortManagerInner {
 
     // Map of all ports, keyed on the instance Uuid and the port name.
     ports: Mutex<BTreeMap<(Uuid, String), Port>>,
+
+    // The router entries programmed into each port, keyed on the port name.
+    //
+    // OPTE can't report these back to us, so they're tracked here in order to
+    // reconcile them with the routes the control plane provides.
+    router_entries: Mutex<BTreeMap<String, PortRouterEntries>>,
+
+    // The configuration of the xde device underlying each port, keyed on the
+    // port name, for rebuilding the port when its external IPs change.
+    xde_configs: Mutex<BTreeMap<String, XdeConfig>>,
+}
+
+/// Everything needed to recreate the xde device underlying an OPTE port, and
+/// the VNIC on top of it
+#[derive(Debug, Clone)]
+struct XdeConfig {
+    ipv4_cfg: Ipv4Cfg,
+    private_mac: MacAddr,
+    gateway_mac: MacAddr,
+    vni: Vni,
+    boundary_services: BoundaryServices,
+    // The firewall rules most recently set on the port.
+    firewall_rules: Vec<VpcFirewallRule>,
+    // The guest's MAC address, which the VNIC over the xde device must use.
+    nic_mac: external::MacAddr,
+}
+
+/// The router entries of a single OPTE port
+#[derive(Debug)]
+struct PortRouterEntries {
+    // Entries the port was created with, which always remain in place.
+    defaults: Vec<RouterEntry>,
+    // Every entry programmed into the port, including the defaults.
+    programmed: Vec<RouterEntry>,
+}
+
+/// A router entry programmed into an OPTE port, as described by the control
+/// plane
+#[derive(Debug, Clone, PartialEq)]
+struct RouterEntry {
+    dest: IpNet,
+    target: VpcRouteTarget,
+}
+
+impl RouterEntry {
+    /// Returns the entries every port is created with: one sending traffic for
+    /// the port's own VPC Subnet to that subnet, and a default route to the
+    /// internet gateway.
+    fn defaults_for_subnet(subnet: ipnetwork::Ipv4Network) -> Vec<Self> {
+        let subnet = IpNet::V4(Ipv4Net(subnet));
+        let any =
+            ipnetwork::Ipv4Network::new(std::net::Ipv4Addr::UNSPECIFIED, 0)
+                .unwrap();
+        vec![
+            RouterEntry {
+                dest: subnet,
+                target: VpcRouteTarget::VpcSubnet(subnet),
+            },
+            RouterEntry {
+                dest: IpNet::V4(Ipv4Net(any)),
+                target: VpcRouteTarget::InternetGateway,
+            },
+        ]
+    }
+
+    /// Converts this entry into a request to add it to OPTE port `port_name`
+    ///
+    /// Ports only carry IPv4 traffic for now (see `create_port()`), so an entry
+    /// with an IPv6 destination can't match anything sent through the port,
+    /// and there's no request to make for it.  Entries that could match the
+    /// port's traffic but that OPTE can't carry out are an error.
+    fn to_opte(
+        &self,
+        port_name: &str,
+    ) -> Result<Option<AddRouterEntryReq>, Error> {
+        let dest = match self.dest {
+            IpNet::V4(net) => Ipv4Cidr::new(
+                net.ip().into(),
+                Ipv4PrefixLen::new(net.prefix()).unwrap(),
+            ),
+            IpNet::V6(_) => return Ok(None),
+        };
+        let target = match self.target {
+            VpcRouteTarget::Ip(IpAddr::V4(ip)) => {
+                RouterTarget::Ip(oxide_vpc::api::Ipv4Addr::from(ip).into())
+            }
+            VpcRouteTarget::VpcSubnet(IpNet::V4(net)) => {
+                RouterTarget::VpcSubnet(IpCidr::Ip4(Ipv4Cidr::new(
+                    net.ip().into(),
+                    Ipv4PrefixLen::new(net.prefix()).unwrap(),
+                )))
+            }
+            VpcRouteTarget::InternetGateway => RouterTarget::InternetGateway,
+            VpcRouteTarget::Ip(IpAddr::V6(_))
+            | VpcRouteTarget::VpcSubnet(IpNet::V6(_)) => {
+                return Err(Error::UnsupportedRoute(format!(
+                    "IPv4 destination {} has an IPv6 target {:?}",
+                    self.dest, self.target,
+                )));
+            }
+            // TODO-completeness: OPTE can't yet encapsulate traffic for a VNI
+            // other than the port's own.
+            VpcRouteTarget::VpcPeering(vni) => {
+                return Err(Error::UnsupportedRoute(format!(
+                    "destination {} targets peered VPC with VNI {}, and \
+                    traffic can't yet be sent to another VPC",
+                    self.dest,
+                    u32::from(vni),
+                )));
+            }
+        };
+        Ok(Some(AddRouterEntryReq {
+            port_name: port_name.to_string(),
+            dest: dest.into(),
+            target,
+        }))
+    }
 }
 
 impl PortManagerInner {
+    fn vpc_cfg(&self, config: &XdeConfig) -> VpcCfg {
+        // TODO-completeness: Add support for IPv6.
+        VpcCfg {
+            ip_cfg: IpCfg::Ipv4(config.ipv4_cfg.clone()),
+            private_mac: config.private_mac,
+            gateway_mac: config.gateway_mac,
+            vni: config.vni,
+            phys_ip: self.underlay_ip.into(),
+            boundary_services: config.boundary_services,
+            // TODO-remove: Part of the external IP hack.
+            //
+            // NOTE: This value of this flag is irrelevant, since the driver
+            // always overwrites it. The field itself is used in the `oxide-vpc`
+            // code though, to determine how to set up the ARP layer, which is
+            // why it's still here.
+            proxy_arp_enable: true,
+            phys_gw_mac: Some(MacAddr::from(self.gateway_mac.into_array())),
+        }
+    }
+
+    /// Tear down the xde device underlying port `port_name` and the VNIC on
+    /// top of it, and create them again as described by `config`
+    ///
+    /// The port keeps its name, MAC address and VNIC name.  Its firewall rules
+    /// and `router_entries` are programmed into the new device before the VNIC
+    /// is recreated, so no guest traffic passes through the port until it's
+    /// fully configured.
+    fn rebuild_port(
+        &self,
+        hdl: &OpteHdl,
+        port_name: &str,
+        port: &Port,
+        config: &XdeConfig,
+        router_entries: &[RouterEntry],
+    ) -> Result<(), Error> {
+        // The VNIC has to go first, since an xde device can't be deleted while
+        // there's a VNIC on top of it.  See `PortInner::drop()`.
+        Dladm::delete_vnic(port.vnic_name())?;
+        hdl.delete_xde(port_name)?;
+        hdl.create_xde(
+            port_name,
+            self.vpc_cfg(config),
+            /* passthru = */ false,
+        )?;
+        hdl.set_fw_rules(&SetFwRulesReq {
+            port_name: port_name.to_string(),
+            rules: opte_firewall_rules(
+                &config.firewall_rules,
+                &config.vni,
+                port.mac(),
+            ),
+        })?;
+        for entry in router_entries {
+            if let Some(req) = entry.to_opte(port_name)? {
+                hdl.add_router_entry(&req)?;
+            }
+        }
+        Dladm::create_vnic(
+            &PhysicalLink(port_name.to_string()),
+            port.vnic_name(),
+            Some(config.nic_mac),
+            None,
+        )?;
+        debug!(
+            self.log,
+            "Rebuilt xde device for guest port";
+            "port_name" => port_name,
+            "config" => ?config,
+        );
+        Ok(())
+    }
+
     fn next_port_name(&self) -> String {
         format!(
             "{}{}",
@@ -163,6 +358,8 @@ impl PortManager {
             gateway_mac,
             underlay_ip,
             ports: Mutex::new(BTreeMap::new()),
+            router_entries: Mutex::new(BTreeMap::new()),
+            xde_configs: Mutex::new(BTreeMap::new()),
         });
 
         Self { inner }
@@ -269,32 +466,26 @@ impl PortManager {
         let port_name = self.inner.next_port_name();
         let hdl = OpteHdl::open(OpteHdl::DLD_CTL)?;
 
-        // TODO-completeness: Add support for IPv6.
-        let vpc_cfg = VpcCfg {
-            ip_cfg: IpCfg::Ipv4(Ipv4Cfg {
+        let xde_config = XdeConfig {
+            ipv4_cfg: Ipv4Cfg {
                 vpc_subnet: opte_subnet,
                 private_ip,
                 gateway_ip,
                 snat,
                 external_ips: external_ip,
-            }),
+            },
             private_mac: MacAddr::from(mac.into_array()),
             gateway_mac: MacAddr::from(gateway.mac.into_array()),
             vni,
-            phys_ip: self.inner.underlay_ip.into(),
             boundary_services,
-            // TODO-remove: Part of the external IP hack.
-            //
-            // NOTE: This value of this flag is irrelevant, since the driver
-            // always overwrites it. The field itself is used in the `oxide-vpc`
-            // code though, to determine how to set up the ARP layer, which is
-            // why it's still here.
-            proxy_arp_enable: true,
-            phys_gw_mac: Some(MacAddr::from(
-                self.inner.gateway_mac.into_array(),
-            )),
+            firewall_rules: firewall_rules.to_vec(),
+            nic_mac: nic.mac,
         };
-        hdl.create_xde(&port_name, vpc_cfg, /* passthru = */ false)?;
+        hdl.create_xde(
+            &port_name,
+            self.inner.vpc_cfg(&xde_config),
+            /* passthru = */ false,
+        )?;
         debug!(
             self.inner.log,
             "Created xde device for guest port";
@@ -397,65 +588,53 @@ impl PortManager {
         self.inner.update_secondary_macs(&mut ports)?;
         drop(ports);
 
-        // Add a router entry for this interface's subnet, directing traffic to the
-        // VPC subnet.
-        match subnet.network() {
-            IpAddr::V4(ip) => {
-                let prefix =
-                    Ipv4PrefixLen::new(subnet.prefix()).map_err(|e| {
-                        opte_ioctl::Error::InvalidArgument(format!(
-                            "Invalid IPv4 subnet prefix: {}",
-                            e
-                        ))
-                    })?;
-                let cidr =
-                    Ipv4Cidr::new(oxide_vpc::api::Ipv4Addr::from(ip), prefix);
-                let route = AddRouterEntryReq {
-                    port_name: port_name.clone(),
-                    dest: cidr.into(),
-                    target: RouterTarget::VpcSubnet(IpCidr::Ip4(cidr)),
-                };
-                hdl.add_router_entry(&route)?;
-                debug!(
-                    self.inner.log,
-                    "Added IPv4 VPC Subnet router entry for OPTE port";
-                    "port_name" => &port_name,
-                    "entry" => ?route,
-                );
-            }
-            IpAddr::V6(_) => {
+        // Add router entries for this interface's subnet, directing traffic to
+        // the VPC subnet, and for everything else, directing it to the
+        // internet gateway.
+        //
+        // TODO-remove
+        //
+        // See https://github.com/oxidecomputer/omicron/issues/1336
+        //
+        // The default route is part of the workaround allowing reply traffic
+        // from the guest back out.  Normally, OPTE would drop such traffic at
+        // the router layer, as it has no route for that external IP address.
+        // The same route is among the default routing rules in the VPC System
+        // Router, so it will also arrive from the control plane through
+        // `routes_ensure()`.
+        let subnet = match subnet {
+            IpNetwork::V4(net) => net,
+            IpNetwork::V6(_) => {
                 return Err(opte_ioctl::Error::InvalidArgument(String::from(
                     "IPv6 not yet supported",
                 ))
                 .into());
             }
+        };
+        let entries = RouterEntry::defaults_for_subnet(subnet);
+        for entry in &entries {
+            if let Some(req) = entry.to_opte(&port_name)? {
+                hdl.add_router_entry(&req)?;
+                debug!(
+                    self.inner.log,
+                    "Added default router entry for OPTE port";
+                    "port_name" => &port_name,
+                    "entry" => ?req,
+                );
+            }
         }
-
-        // TODO-remove
-        //
-        // See https://github.com/oxidecomputer/omicron/issues/1336
-        //
-        // This is another part of the workaround, allowing reply traffic from
-        // the guest back out. Normally, OPTE would drop such traffic at the
-        // router layer, as it has no route for that external IP address. This
-        // allows such traffic through.
-        //
-        // Note that this exact rule will eventually be included, since it's one
-        // of the default routing rules in the VPC System Router. However, that
-        // will likely be communicated in a different way, or could be modified,
-        // and this specific call should be removed in favor of sending the
-        // routing rules the control plane provides.
-        //
-        // This rule sends all traffic that has no better match to the gateway.
-        let prefix = Ipv4PrefixLen::new(0).unwrap();
-        let dest =
-            Ipv4Cidr::new(std::net::Ipv4Addr::UNSPECIFIED.into(), prefix);
-        let target = RouterTarget::InternetGateway;
-        hdl.add_router_entry(&AddRouterEntryReq {
-            port_name,
-            dest: dest.into(),
-            target,
-        })?;
+        self.inner.router_entries.lock().unwrap().insert(
+            port_name.clone(),
+            PortRouterEntries {
+                defaults: entries.clone(),
+                programmed: entries,
+            },
+        );
+        self.inner
+            .xde_configs
+            .lock()
+            .unwrap()
+            .insert(port_name.clone(), xde_config);
 
         info!(
             self.inner.log,
@@ -465,12 +644,116 @@ impl PortManager {
         Ok((port, ticket))
     }
 
+    /// Ensure that the OPTE ports of instance `instance_id` provide the
+    /// external IP addresses `external_ips`
+    ///
+    /// The external IPs of an xde device are fixed when it's created, so a
+    /// port whose address changes is rebuilt with the new address, keeping
+    /// its firewall rules and router entries.  If that fails, the port is
+    /// rebuilt as it was.
+    pub fn external_ips_ensure(
+        &self,
+        instance_id: Uuid,
+        external_ips: &[IpAddr],
+    ) -> Result<(), Error> {
+        // OPTE only supports one external IP per port, so that's all that's
+        // provided to it.  See `create_port()`.
+        let external_ip = match external_ips.first() {
+            Some(IpAddr::V4(ip)) => Some((*ip).into()),
+            Some(IpAddr::V6(_)) => {
+                return Err(opte_ioctl::Error::InvalidArgument(String::from(
+                    "IPv6 is not yet supported for external addresses",
+                ))
+                .into());
+            }
+            None => None,
+        };
+
+        let hdl = OpteHdl::open(OpteHdl::DLD_CTL)?;
+        let mut ports = self.inner.ports.lock().unwrap();
+        let router_entries = self.inner.router_entries.lock().unwrap();
+        let mut xde_configs = self.inner.xde_configs.lock().unwrap();
+        let mut rebuilt = false;
+        for ((id, port_name), port) in ports.iter() {
+            if *id != instance_id {
+                continue;
+            }
+            // Only the primary interface of an instance has external IPs.
+            let current = match port.external_ips() {
+                Some(ips) => ips,
+                None => continue,
+            };
+            let config = match xde_configs.get_mut(port_name) {
+                Some(config) => config,
+                None => continue,
+            };
+            if current.first() != external_ips.first() {
+                let entries = router_entries
+                    .get(port_name)
+                    .map(|entries| entries.programmed.as_slice())
+                    .unwrap_or_default();
+                let mut new_config = config.clone();
+                new_config.ipv4_cfg.external_ips = external_ip;
+                if let Err(e) = self.inner.rebuild_port(
+                    &hdl,
+                    port_name,
+                    port,
+                    &new_config,
+                    entries,
+                ) {
+                    warn!(
+                        self.inner.log,
+                        "Failed to rebuild OPTE port with new external IPs";
+                        "port_name" => port_name,
+                        "current" => ?current,
+                        "requested" => ?external_ips,
+                        "err" => ?e,
+                    );
+                    if let Err(e) = self
+                        .inner
+                        .rebuild_port(&hdl, port_name, port, config, entries)
+                    {
+                        warn!(
+                            self.inner.log,
+                            "Failed to restore OPTE port after failing to \
+                            change its external IPs";
+                            "port_name" => port_name,
+                            "err" => ?e,
+                        );
+                    }
+                    return Err(e);
+                }
+                *config = new_config;
+                rebuilt = true;
+                info!(
+                    self.inner.log,
+                    "Changed external IPs of OPTE port";
+                    "port_name" => port_name,
+                    "previous" => ?current,
+                    "external_ips" => ?external_ips,
+                );
+            }
+            port.set_external_ips(Some(external_ips.to_vec()));
+        }
+        drop(xde_configs);
+        drop(router_entries);
+        if rebuilt {
+            self.inner.update_secondary_macs(&mut ports)?;
+        }
+        Ok(())
+    }
+
     pub fn firewall_rules_ensure(
         &self,
         rules: &[VpcFirewallRule],
     ) -> Result<(), Error> {
         let hdl = OpteHdl::open(OpteHdl::DLD_CTL)?;
-        for ((_, port_name), port) in self.inner.ports.lock().unwrap().iter() {
+        let ports = self.inner.ports.lock().unwrap();
+        let mut xde_configs = self.inner.xde_configs.lock().unwrap();
+        for ((_, port_name), port) in ports.iter() {
+            if let Some(config) = xde_configs.get_mut(port_name) {
+                config.firewall_rules = rules.to_vec();
+            }
             let rules = opte_firewall_rules(rules, port.vni(), port.mac());
             let port_name = port_name.clone();
             info!(
@@ -483,6 +766,141 @@ impl PortManager {
         }
         Ok(())
     }
+
+    /// Reconcile the router entries of each port in the VPC with VNI `vni`
+    /// with the VPC routes that apply to it.
+    ///
+    /// A route applies to a port if that port's (VNI, MAC) appears among the
+    /// route's interfaces.  New entries are added to the port in place.  OPTE
+    /// can't remove router entries, so a port with entries for routes that no
+    /// longer apply is rebuilt with only the entries that do, as for changes
+    /// to its external IPs.
+    ///
+    /// Routes that OPTE can't carry out are reported with
+    /// [`Error::UnsupportedRoute`], once every other route is in place.
+    pub fn routes_ensure(
+        &self,
+        vni: external::Vni,
+        routes: &[VpcRoute],
+    ) -> Result<(), Error> {
+        let hdl = OpteHdl::open(OpteHdl::DLD_CTL)?;
+        let ports = self.inner.ports.lock().unwrap();
+        let mut router_entries = self.inner.router_entries.lock().unwrap();
+        let xde_configs = self.inner.xde_configs.lock().unwrap();
+        let mut unsupported = None;
+        for ((_, port_name), port) in ports.iter() {
+            if u32::from(*port.vni()) != u32::from(vni) {
+                continue;
+            }
+            let (entries, config) = match (
+                router_entries.get_mut(port_name),
+                xde_configs.get(port_name),
+            ) {
+                (Some(entries), Some(config)) => (entries, config),
+                _ => continue,
+            };
+            let mut desired = entries.defaults.clone();
+            for route in routes.iter().filter(|route| {
+                route.interfaces.iter().any(|nic| {
+                    // (VNI, MAC) is a unique identifier for the NIC.
+                    u32::from(nic.vni) == u32::from(*port.vni())
+                        && nic.mac.0 == *port.mac()
+                })
+            }) {
+                let entry = RouterEntry {
+                    dest: route.dest,
+                    target: route.target.clone(),
+                };
+                match entry.to_opte(port_name) {
+                    Ok(Some(_)) => {
+                        if !desired.contains(&entry) {
+                            desired.push(entry);
+                        }
+                    }
+                    Ok(None) => {
+                        debug!(
+                            self.inner.log,
+                            "Skipping IPv6 VPC route for IPv4-only OPTE port";
+                            "port_name" => &port_name,
+                            "route" => ?route,
+                        );
+                    }
+                    Err(e) => {
+                        warn!(
+                            self.inner.log,
+                            "Cannot program VPC route into OPTE port";
+                            "port_name" => &port_name,
+                            "route" => ?route,
+                            "err" => ?e,
+                        );
+                        unsupported.get_or_insert(e);
+                    }
+                }
+            }
+
+            let stale = entries
+                .programmed
+                .iter()
+                .filter(|entry| !desired.contains(entry))
+                .collect::<Vec<_>>();
+            if stale.is_empty() {
+                for entry in &desired {
+                    if entries.programmed.contains(entry) {
+                        continue;
+                    }
+                    // Only supported entries made it into `desired`.
+                    let req = entry.to_opte(port_name)?.unwrap();
+                    hdl.add_router_entry(&req)?;
+                    entries.programmed.push(entry.clone());
+                    debug!(
+                        self.inner.log,
+                        "Added VPC router entry for OPTE port";
+                        "port_name" => &port_name,
+                        "entry" => ?req,
+                    );
+                }
+                continue;
+            }
+
+            info!(
+                self.inner.log,
+                "Rebuilding OPTE port to remove stale router entries";
+                "port_name" => &port_name,
+                "stale" => ?stale,
+            );
+            if let Err(e) =
+                self.inner.rebuild_port(&hdl, port_name, port, config, &desired)
+            {
+                warn!(
+                    self.inner.log,
+                    "Failed to rebuild OPTE port with new router entries";
+                    "port_name" => &port_name,
+                    "err" => ?e,
+                );
+                if let Err(e) = self.inner.rebuild_port(
+                    &hdl,
+                    port_name,
+                    port,
+                    config,
+                    &entries.programmed,
+                ) {
+                    warn!(
+                        self.inner.log,
+                        "Failed to restore OPTE port after failing to \
+                        change its router entries";
+                        "port_name" => &port_name,
+                        "err" => ?e,
+                    );
+                }
+                return Err(e);
+            }
+            entries.programmed = desired;
+        }
+        match unsupported {
+            None => Ok(()),
+            Some(e) => Err(e),
+        }
+    }
 }
 
 pub struct PortTicket {
@@ -517,6 +935,8 @@ impl PortTicket {
         if let Some(manager) = self.manager.take() {
             let mut ports = manager.ports.lock().unwrap();
             ports.remove(&(self.id, self.port_name.clone()));
+            manager.router_entries.lock().unwrap().remove(&self.port_name);
+            manager.xde_configs.lock().unwrap().remove(&self.port_name);
             debug!(
                 manager.log,
                 "Removing OPTE port from manager";
@@ -13,8 +13,10 @@ use crate::opte::Vni;
 use crate::params::NetworkInterface;
 use crate::params::SourceNatConfig;
 use crate::params::VpcFirewallRule;
+use crate::params::VpcRoute;
 use ipnetwork::IpNetwork;
 use macaddr::MacAddr6;
+use omicron_common::api::external;
 use slog::debug;
 use slog::info;
 use slog::Logger;
@@ -184,6 +186,19 @@ impl PortManager {
         Ok((port, ticket))
     }
 
+    pub fn external_ips_ensure(
+        &self,
+        instance_id: Uuid,
+        external_ips: &[IpAddr],
+    ) -> Result<(), Error> {
+        info!(
+            self.inner.log,
+            "Ignoring {} external IPs", external_ips.len();
+            "instance_id" => %instance_id,
+        );
+        Ok(())
+    }
+
     pub fn firewall_rules_ensure(
         &self,
         rules: &[VpcFirewallRule],
@@ -191,6 +206,19 @@ impl PortManager {
         info!(self.inner.log, "Ignoring {} firewall rules", rules.len());
         Ok(())
     }
+
+    pub fn routes_ensure(
+        &self,
+        vni: external::Vni,
+        routes: &[VpcRoute],
+    ) -> Result<(), Error> {
+        info!(
+            self.inner.log,
+            "Ignoring {} VPC routes", routes.len();
+            "vni" => ?vni,
+        );
+        Ok(())
+    }
 }
 
 pub struct PortTicket {
@@ -59,6 +59,41 @@ pub struct VpcFirewallRule {
     pub priority: external::VpcFirewallRulePriority,
 }
 
+/// Update the routes of a VPC
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct VpcRoutesEnsureBody {
+    /// The VNI of the VPC, identifying the network interfaces whose routes are
+    /// replaced
+    pub vni: external::Vni,
+    /// Every route of the VPC, replacing any previously provided
+    pub routes: Vec<VpcRoute>,
+}
+
+/// VPC route after object name resolution has been performed by Nexus
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct VpcRoute {
+    /// The destination network of traffic matching this route
+    pub dest: external::IpNet,
+    /// Where matching traffic is sent
+    pub target: VpcRouteTarget,
+    /// The network interfaces whose outbound traffic this route applies to
+    pub interfaces: Vec<NetworkInterface>,
+}
+
+/// The resolved target of a VPC route
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum VpcRouteTarget {
+    /// Forward traffic to a particular IP address in the VPC
+    Ip(IpAddr),
+    /// Forward traffic to the VPC Subnet with this address range
+    VpcSubnet(external::IpNet),
+    /// Forward traffic to the internet gateway
+    InternetGateway,
+    /// Forward traffic to the peered VPC with this VNI
+    VpcPeering(external::Vni),
+}
+
 /// Used to request a Disk state change
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase", tag = "state", content = "instance")]
@@ -107,6 +142,15 @@ pub struct InstanceHardware {
     pub cloud_init_bytes: Option<String>,
 }
 
+/// Sent to a sled agent to change the external IP addresses of an Instance
+/// after it has been created
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct InstanceExternalIpBody {
+    /// Zero or more external IP addresses (either floating or ephemeral),
+    /// provided to an instance to allow inbound connectivity.
+    pub external_ips: Vec<IpAddr>,
+}
+
 /// Sent to a sled agent to establish the runtime state of an Instance
 #[derive(Serialize, Deserialize, JsonSchema)]
 pub struct InstanceEnsureBody {
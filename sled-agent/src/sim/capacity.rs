@@ -0,0 +1,169 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Simulated compute capacity and resource usage reporting
+//!
+//! The simulated sled has a fixed number of hardware threads and a fixed
+//! amount of physical memory, described by its [`ConfigHardware`].  Instances
+//! reserve their vCPUs and memory while they're supposed to be running, and
+//! requests that would oversubscribe the sled fail.
+
+use super::config::ConfigHardware;
+use omicron_common::api::external::Error;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Amount of some resource that is in use, out of the total available
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    pub total: u64,
+    pub used: u64,
+}
+
+/// Space used by Crucible regions in a zpool
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct ZpoolUsage {
+    pub id: Uuid,
+    /// bytes in the zpool
+    pub size: ResourceUsage,
+}
+
+/// Resource usage of a simulated sled
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct SledUsage {
+    /// hardware threads reserved by instances
+    pub hardware_threads: ResourceUsage,
+    /// bytes of physical memory reserved by instances
+    pub physical_ram: ResourceUsage,
+    /// space used in each zpool
+    pub zpools: Vec<ZpoolUsage>,
+}
+
+/// Hardware reserved by one instance
+#[derive(Clone, Copy, Debug)]
+struct Reservation {
+    hardware_threads: u64,
+    physical_ram: u64,
+}
+
+/// Tracks the hardware threads and memory reserved by instances
+#[derive(Debug)]
+pub struct SimHardware {
+    config: ConfigHardware,
+    reservations: BTreeMap<Uuid, Reservation>,
+}
+
+impl SimHardware {
+    pub fn new(config: ConfigHardware) -> SimHardware {
+        SimHardware { config, reservations: BTreeMap::new() }
+    }
+
+    /// Reserves `hardware_threads` and `physical_ram` bytes for instance `id`
+    ///
+    /// Returns whether a new reservation was made.  If the instance already
+    /// has a reservation, it's left alone.  This fails if the sled doesn't
+    /// have enough of either resource left.
+    pub fn reserve(
+        &mut self,
+        id: Uuid,
+        hardware_threads: u64,
+        physical_ram: u64,
+    ) -> Result<bool, Error> {
+        if self.reservations.contains_key(&id) {
+            return Ok(false);
+        }
+
+        let threads = self.hardware_threads();
+        if hardware_threads > threads.total - threads.used {
+            return Err(Error::internal_error(&format!(
+                "could not allocate an instance: {} hardware threads \
+                requested, but only {} of {} are available",
+                hardware_threads,
+                threads.total - threads.used,
+                threads.total,
+            )));
+        }
+        let ram = self.physical_ram();
+        if physical_ram > ram.total - ram.used {
+            return Err(Error::internal_error(&format!(
+                "could not allocate an instance: {} bytes of memory \
+                requested, but only {} of {} are available",
+                physical_ram,
+                ram.total - ram.used,
+                ram.total,
+            )));
+        }
+
+        self.reservations
+            .insert(id, Reservation { hardware_threads, physical_ram });
+        Ok(true)
+    }
+
+    /// Releases whatever instance `id` had reserved
+    pub fn release(&mut self, id: &Uuid) {
+        self.reservations.remove(id);
+    }
+
+    pub fn hardware_threads(&self) -> ResourceUsage {
+        ResourceUsage {
+            total: u64::from(self.config.hardware_threads),
+            used: self.reservations.values().map(|r| r.hardware_threads).sum(),
+        }
+    }
+
+    pub fn physical_ram(&self) -> ResourceUsage {
+        ResourceUsage {
+            total: self.config.physical_ram,
+            used: self.reservations.values().map(|r| r.physical_ram).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConfigHardware;
+    use super::ResourceUsage;
+    use super::SimHardware;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_sim_hardware_reservations() {
+        let mut hardware = SimHardware::new(ConfigHardware {
+            hardware_threads: 8,
+            physical_ram: 16 << 30,
+        });
+        let id1 = Uuid::new_v4();
+        let id2 = Uuid::new_v4();
+        let id3 = Uuid::new_v4();
+
+        assert!(hardware.reserve(id1, 4, 8 << 30).unwrap());
+        // Reserving again for the same instance is a no-op.
+        assert!(!hardware.reserve(id1, 4, 8 << 30).unwrap());
+        assert_eq!(
+            hardware.hardware_threads(),
+            ResourceUsage { total: 8, used: 4 }
+        );
+        assert_eq!(
+            hardware.physical_ram(),
+            ResourceUsage { total: 16 << 30, used: 8 << 30 }
+        );
+
+        // Neither resource may be oversubscribed.
+        assert!(hardware.reserve(id2, 5, 1 << 30).is_err());
+        assert!(hardware.reserve(id2, 1, 9 << 30).is_err());
+        assert!(hardware.reserve(id2, 4, 8 << 30).unwrap());
+        assert!(hardware.reserve(id3, 1, 1 << 30).is_err());
+
+        // Releasing a reservation makes its resources available again.
+        hardware.release(&id1);
+        assert!(hardware.reserve(id3, 1, 1 << 30).unwrap());
+        assert_eq!(
+            hardware.hardware_threads(),
+            ResourceUsage { total: 8, used: 5 }
+        );
+    }
+}
@@ -5,6 +5,8 @@
 //! Simulated sled agent object collection
 
 use super::config::SimMode;
+use super::faults::SimFaultRule;
+use super::faults::SimFaults;
 
 use crate::nexus::NexusClient;
 use futures::channel::mpsc::Receiver;
@@ -12,7 +14,6 @@ use futures::channel::mpsc::Sender;
 use futures::lock::Mutex;
 use futures::stream::StreamExt;
 use omicron_common::api::external::Error;
-use omicron_common::api::external::ResourceType;
 use slog::Logger;
 use std::collections::BTreeMap;
 use std::sync::Arc;
@@ -174,20 +175,25 @@ pub struct SimCollection<S: Simulatable> {
     sim_mode: SimMode,
     /// list of objects being simulated
     objects: Mutex<BTreeMap<Uuid, SimObject<S>>>,
+    /// rules for injecting faults into requests for objects
+    faults: Mutex<SimFaults>,
 }
 
 impl<S: Simulatable + 'static> SimCollection<S> {
-    /// Returns a new collection of simulated objects.
+    /// Returns a new collection of simulated objects that injects `faults`
+    /// into requests.
     pub fn new(
         nexus_client: Arc<NexusClient>,
         log: Logger,
         sim_mode: SimMode,
+        faults: SimFaults,
     ) -> SimCollection<S> {
         SimCollection {
             nexus_client,
             log,
             sim_mode,
             objects: Mutex::new(BTreeMap::new()),
+            faults: Mutex::new(faults),
         }
     }
 
@@ -287,6 +293,10 @@ impl<S: Simulatable + 'static> SimCollection<S> {
         current: S::CurrentState,
         target: S::RequestedState,
     ) -> Result<S::CurrentState, Error> {
+        // Faults are injected before we take the lock so that delaying one
+        // request doesn't hold up requests for other objects.
+        self.sim_fault_inject(id).await?;
+
         let mut objects = self.objects.lock().await;
         let maybe_current_object = objects.remove(id);
         let (mut object, is_new) = {
@@ -324,14 +334,51 @@ impl<S: Simulatable + 'static> SimCollection<S> {
         objects.contains_key(id)
     }
 
+    /// Adds a rule for injecting faults into requests for objects in this
+    /// collection
+    pub async fn sim_fault_add(&self, rule: SimFaultRule) -> Result<(), Error> {
+        info!(self.log, "adding fault rule"; "rule" => ?rule);
+        self.faults.lock().await.add(rule)
+    }
+
+    /// Applies the fault (if any) that the fault injection rules call for in a
+    /// request for object `id`
+    ///
+    /// `sim_ensure()` does this itself.  This is for other requests about
+    /// objects in the collection.
+    pub async fn sim_fault_inject(&self, id: &Uuid) -> Result<(), Error> {
+        let fault = self.faults.lock().await.take(id);
+        if let Some(fault) = fault {
+            info!(self.log, "injecting fault"; "id" => %id, "fault" => ?fault);
+            fault.inject(S::resource_type(), id).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes all fault injection rules for this collection
+    pub async fn sim_faults_clear(&self) {
+        self.faults.lock().await.clear();
+    }
+
+    /// Returns the ids of objects that have not come to rest in a destroyed
+    /// state
+    pub async fn sim_live_ids(&self) -> Vec<Uuid> {
+        let objects = self.objects.lock().await;
+        objects
+            .iter()
+            .filter(|(_, object)| !object.object.ready_to_destroy())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
     pub async fn sim_get_current_state(
         self: &Arc<Self>,
         id: &Uuid,
     ) -> Result<S::CurrentState, Error> {
         let objects = self.objects.lock().await;
-        let instance = objects.get(id).ok_or_else(|| {
-            Error::not_found_by_id(ResourceType::Instance, id)
-        })?;
+        let instance = objects
+            .get(id)
+            .ok_or_else(|| Error::not_found_by_id(S::resource_type(), id))?;
         Ok(instance.object.current().clone())
     }
 }
@@ -4,6 +4,7 @@
 
 //! Interfaces for working with sled agent configuration
 
+use super::faults::SimFaultRule;
 use dropshot::ConfigDropshot;
 use dropshot::ConfigLogging;
 use serde::Deserialize;
@@ -41,6 +42,30 @@ pub struct ConfigStorage {
     pub ip: IpAddr,
 }
 
+/// Configuration describing the simulated sled's compute hardware
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ConfigHardware {
+    /// number of hardware threads available for instances' vCPUs
+    pub hardware_threads: u32,
+    /// bytes of physical memory available for instances
+    pub physical_ram: u64,
+}
+
+/// Faults to inject into requests for simulated objects from the moment the
+/// sled agent starts.  More can be added at runtime.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ConfigFaults {
+    /// rules for requests to ensure the state of an instance
+    #[serde(default)]
+    pub instances: Vec<SimFaultRule>,
+    /// rules for requests to ensure the state of a disk
+    #[serde(default)]
+    pub disks: Vec<SimFaultRule>,
+    /// rules for requests to create or delete Crucible regions
+    #[serde(default)]
+    pub regions: Vec<SimFaultRule>,
+}
+
 /// Configuration for a sled agent
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Config {
@@ -56,4 +81,9 @@ pub struct Config {
     pub log: ConfigLogging,
     /// configuration for the sled agent's storage
     pub storage: ConfigStorage,
+    /// the sled's compute capacity
+    pub hardware: ConfigHardware,
+    /// faults to inject into requests for simulated objects
+    #[serde(default)]
+    pub faults: ConfigFaults,
 }
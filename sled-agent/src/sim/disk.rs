@@ -14,6 +14,7 @@ use dropshot::ConfigLoggingLevel;
 use omicron_common::api::external::DiskState;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::Generation;
+use omicron_common::api::external::ResourceType;
 use omicron_common::api::internal::nexus::DiskRuntimeState;
 use omicron_common::api::internal::nexus::ProducerEndpoint;
 use oximeter_producer::Server as ProducerServer;
@@ -259,6 +260,10 @@ impl Simulatable for SimDisk {
         DiskState::Destroyed == self.current().disk_state
     }
 
+    fn resource_type() -> ResourceType {
+        ResourceType::Disk
+    }
+
     async fn notify(
         nexus_client: &Arc<NexusClient>,
         id: &Uuid,
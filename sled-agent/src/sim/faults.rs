@@ -0,0 +1,226 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Scriptable fault injection for simulated objects
+//!
+//! Each collection of simulated objects (instances, disks, and Crucible
+//! regions) has a list of [`SimFaultRule`]s.  Before handling a request for an
+//! object, the collection finds the first rule that matches the object and
+//! applies its fault, which may fail the request or delay it.  This allows
+//! Nexus tests to exercise error paths deterministically.
+
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Which kind of simulated object a fault rule applies to
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimFaultTarget {
+    /// requests to ensure the state of an instance
+    Instances,
+    /// requests to ensure the state of a disk
+    Disks,
+    /// requests to create or delete Crucible regions
+    Regions,
+}
+
+/// A fault to inject into a request for a simulated object
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimFault {
+    /// Fail the request with the given HTTP status code.  Supported codes are
+    /// 400, 403, 404, 500, and 503.
+    Error { status: u16 },
+    /// Wait for the given number of milliseconds, then handle the request
+    /// normally
+    Delay { milliseconds: u64 },
+}
+
+/// Describes which requests a fault is injected into
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct SimFaultRule {
+    /// object whose requests are affected (all objects, if unspecified)
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    /// number of requests affected before the rule is removed (every
+    /// request, if unspecified)
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// fault to inject
+    pub fault: SimFault,
+}
+
+impl SimFaultRule {
+    fn validate(&self) -> Result<(), Error> {
+        if self.count == Some(0) {
+            return Err(Error::invalid_request(
+                "fault rule count must be positive",
+            ));
+        }
+        if let SimFault::Error { status } = self.fault {
+            if !matches!(status, 400 | 403 | 404 | 500 | 503) {
+                return Err(Error::invalid_request(&format!(
+                    "unsupported status code for simulated error: {}",
+                    status
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The fault rules for one collection of simulated objects
+#[derive(Debug, Default)]
+pub struct SimFaults {
+    rules: Vec<SimFaultRule>,
+}
+
+impl SimFaults {
+    /// Returns a set of rules containing each of `rules`, in order
+    pub fn with_rules(rules: &[SimFaultRule]) -> Result<SimFaults, Error> {
+        let mut faults = SimFaults::default();
+        for rule in rules {
+            faults.add(rule.clone())?;
+        }
+        Ok(faults)
+    }
+
+    /// Adds a rule.  Rules are matched in the order in which they were added.
+    pub fn add(&mut self, rule: SimFaultRule) -> Result<(), Error> {
+        rule.validate()?;
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// Removes all rules
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    /// Returns the fault to inject into a request for object `id`, if any,
+    /// counting the request against the rule that matched
+    pub fn take(&mut self, id: &Uuid) -> Option<SimFault> {
+        let index = self
+            .rules
+            .iter()
+            .position(|rule| rule.id.map_or(true, |rule_id| rule_id == *id))?;
+        let rule = &mut self.rules[index];
+        let fault = rule.fault.clone();
+        if let Some(count) = &mut rule.count {
+            *count -= 1;
+            if *count == 0 {
+                self.rules.remove(index);
+            }
+        }
+        Some(fault)
+    }
+}
+
+impl SimFault {
+    /// Applies this fault to a request for object `id` of type
+    /// `resource_type`, either by waiting or by returning an error
+    pub async fn inject(
+        &self,
+        resource_type: ResourceType,
+        id: &Uuid,
+    ) -> Result<(), Error> {
+        match self {
+            SimFault::Delay { milliseconds } => {
+                tokio::time::sleep(Duration::from_millis(*milliseconds)).await;
+                Ok(())
+            }
+            SimFault::Error { status: 400 } => Err(Error::invalid_request(
+                "simulated failure (injected fault)",
+            )),
+            SimFault::Error { status: 403 } => Err(Error::Forbidden),
+            SimFault::Error { status: 404 } => Err(Error::ObjectNotFound {
+                type_name: resource_type,
+                lookup_type: LookupType::ById(*id),
+            }),
+            SimFault::Error { status: 503 } => {
+                Err(Error::unavail("simulated failure (injected fault)"))
+            }
+            SimFault::Error { .. } => {
+                Err(Error::internal_error("simulated failure (injected fault)"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SimFault;
+    use super::SimFaultRule;
+    use super::SimFaults;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_sim_faults_take() {
+        let id1 = Uuid::new_v4();
+        let id2 = Uuid::new_v4();
+        let mut faults = SimFaults::default();
+        assert_eq!(faults.take(&id1), None);
+
+        // Rules for specific objects only match those objects, and they go
+        // away once they've been used up.
+        faults
+            .add(SimFaultRule {
+                id: Some(id1),
+                count: Some(2),
+                fault: SimFault::Error { status: 503 },
+            })
+            .unwrap();
+        assert_eq!(faults.take(&id2), None);
+        assert_eq!(faults.take(&id1), Some(SimFault::Error { status: 503 }));
+        assert_eq!(faults.take(&id1), Some(SimFault::Error { status: 503 }));
+        assert_eq!(faults.take(&id1), None);
+
+        // Rules without a count last until they're cleared, and rules are
+        // matched in order.
+        faults
+            .add(SimFaultRule {
+                id: None,
+                count: None,
+                fault: SimFault::Delay { milliseconds: 1 },
+            })
+            .unwrap();
+        faults
+            .add(SimFaultRule {
+                id: Some(id1),
+                count: None,
+                fault: SimFault::Error { status: 500 },
+            })
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                faults.take(&id1),
+                Some(SimFault::Delay { milliseconds: 1 })
+            );
+        }
+        faults.clear();
+        assert_eq!(faults.take(&id2), None);
+
+        // Invalid rules are rejected.
+        assert!(faults
+            .add(SimFaultRule {
+                id: None,
+                count: Some(0),
+                fault: SimFault::Error { status: 500 },
+            })
+            .is_err());
+        assert!(faults
+            .add(SimFaultRule {
+                id: None,
+                count: None,
+                fault: SimFault::Error { status: 418 },
+            })
+            .is_err());
+    }
+}
@@ -5,13 +5,15 @@
 //! HTTP entrypoint functions for the sled agent's exposed API
 
 use crate::params::{
-    DiskEnsureBody, InstanceEnsureBody, InstanceSerialConsoleData,
-    InstanceSerialConsoleRequest, VpcFirewallRulesEnsureBody,
+    DiskEnsureBody, InstanceEnsureBody, InstanceExternalIpBody,
+    InstanceSerialConsoleData, InstanceSerialConsoleRequest,
+    VpcFirewallRulesEnsureBody, VpcRoutesEnsureBody,
 };
 use crate::serial::ByteOffset;
 use dropshot::endpoint;
 use dropshot::ApiDescription;
 use dropshot::HttpError;
+use dropshot::HttpResponseDeleted;
 use dropshot::HttpResponseOk;
 use dropshot::HttpResponseUpdatedNoContent;
 use dropshot::Path;
@@ -26,6 +28,8 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
+use super::capacity::SledUsage;
+use super::faults::{SimFaultRule, SimFaultTarget};
 use super::sled_agent::SledAgent;
 
 use crucible_client_types::VolumeConstructionRequest;
@@ -36,6 +40,7 @@ type SledApiDescription = ApiDescription<Arc<SledAgent>>;
 pub fn api() -> SledApiDescription {
     fn register_endpoints(api: &mut SledApiDescription) -> Result<(), String> {
         api.register(instance_put)?;
+        api.register(instance_put_external_ips)?;
         api.register(instance_poke_post)?;
         api.register(disk_put)?;
         api.register(disk_poke_post)?;
@@ -44,6 +49,10 @@ pub fn api() -> SledApiDescription {
         api.register(instance_issue_disk_snapshot_request)?;
         api.register(issue_disk_snapshot_request)?;
         api.register(vpc_firewall_rules_put)?;
+        api.register(vpc_routes_put)?;
+        api.register(usage_get)?;
+        api.register(faults_post)?;
+        api.register(faults_delete)?;
 
         Ok(())
     }
@@ -79,6 +88,23 @@ async fn instance_put(
     ))
 }
 
+#[endpoint {
+    method = PUT,
+    path = "/instances/{instance_id}/external-ips",
+}]
+async fn instance_put_external_ips(
+    rqctx: Arc<RequestContext<Arc<SledAgent>>>,
+    path_params: Path<InstancePathParam>,
+    body: TypedBody<InstanceExternalIpBody>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let instance_id = path_params.into_inner().instance_id;
+    let body_args = body.into_inner();
+    sa.instance_external_ips_ensure(instance_id, body_args.external_ips)
+        .await?;
+    Ok(HttpResponseUpdatedNoContent())
+}
+
 #[endpoint {
     method = POST,
     path = "/instances/{instance_id}/poke",
@@ -301,9 +327,75 @@ async fn vpc_firewall_rules_put(
     path_params: Path<VpcPathParam>,
     body: TypedBody<VpcFirewallRulesEnsureBody>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
-    let _sa = rqctx.context();
-    let _vpc_id = path_params.into_inner().vpc_id;
-    let _body_args = body.into_inner();
+    let sa = rqctx.context();
+    let vpc_id = path_params.into_inner().vpc_id;
+    let body_args = body.into_inner();
+    sa.vpc_firewall_rules_ensure(vpc_id, body_args.rules).await;
+    Ok(HttpResponseUpdatedNoContent())
+}
 
+#[endpoint {
+    method = PUT,
+    path = "/vpc/{vpc_id}/routes",
+}]
+async fn vpc_routes_put(
+    rqctx: Arc<RequestContext<Arc<SledAgent>>>,
+    path_params: Path<VpcPathParam>,
+    body: TypedBody<VpcRoutesEnsureBody>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let vpc_id = path_params.into_inner().vpc_id;
+    let body_args = body.into_inner();
+    sa.vpc_routes_ensure(vpc_id, body_args.routes).await;
     Ok(HttpResponseUpdatedNoContent())
 }
+
+/// Reports the resources used by instances and Crucible regions on the sled
+/// (simulated sled agent only)
+#[endpoint {
+    method = GET,
+    path = "/usage",
+}]
+async fn usage_get(
+    rqctx: Arc<RequestContext<Arc<SledAgent>>>,
+) -> Result<HttpResponseOk<SledUsage>, HttpError> {
+    let sa = rqctx.context();
+    Ok(HttpResponseOk(sa.usage().await))
+}
+
+/// Path parameters for fault injection requests (simulated sled agent only)
+#[derive(Deserialize, JsonSchema)]
+struct FaultsPathParam {
+    target: SimFaultTarget,
+}
+
+/// Adds a rule for injecting faults into requests for simulated objects
+#[endpoint {
+    method = POST,
+    path = "/faults/{target}",
+}]
+async fn faults_post(
+    rqctx: Arc<RequestContext<Arc<SledAgent>>>,
+    path_params: Path<FaultsPathParam>,
+    body: TypedBody<SimFaultRule>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let target = path_params.into_inner().target;
+    sa.fault_add(target, body.into_inner()).await?;
+    Ok(HttpResponseUpdatedNoContent())
+}
+
+/// Removes all rules for injecting faults into requests for simulated objects
+#[endpoint {
+    method = DELETE,
+    path = "/faults/{target}",
+}]
+async fn faults_delete(
+    rqctx: Arc<RequestContext<Arc<SledAgent>>>,
+    path_params: Path<FaultsPathParam>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let sa = rqctx.context();
+    let target = path_params.into_inner().target;
+    sa.faults_clear(target).await;
+    Ok(HttpResponseDeleted())
+}
@@ -76,6 +76,7 @@ async fn region_create(
     let params = body.into_inner();
     let crucible = rc.context();
 
+    crucible.fault_inject(&params.id).await?;
     Ok(HttpResponseOk(crucible.create(params).await))
 }
 
@@ -109,6 +110,7 @@ async fn region_delete(
     let id = path.into_inner().id;
     let crucible = rc.context();
 
+    crucible.fault_inject(&id).await?;
     match crucible
         .delete(id)
         .await
@@ -13,6 +13,7 @@ use nexus_client;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::Generation;
 use omicron_common::api::external::InstanceState;
+use omicron_common::api::external::ResourceType;
 use omicron_common::api::internal::nexus::InstanceRuntimeState;
 use propolis_client::api::InstanceState as PropolisInstanceState;
 use std::sync::Arc;
@@ -95,6 +96,10 @@ impl Simulatable for SimInstance {
         self.current().run_state == InstanceState::Destroyed
     }
 
+    fn resource_type() -> ResourceType {
+        ResourceType::Instance
+    }
+
     async fn notify(
         nexus_client: &Arc<NexusClient>,
         id: &Uuid,
@@ -4,9 +4,11 @@
 
 //! Simulated sled agent implementation
 
+mod capacity;
 mod collection;
 mod config;
 mod disk;
+mod faults;
 mod http_entrypoints;
 mod http_entrypoints_storage;
 mod instance;
@@ -15,6 +17,10 @@ mod simulatable;
 mod sled_agent;
 mod storage;
 
-pub use config::{Config, ConfigStorage, ConfigZpool, SimMode};
+pub use capacity::{ResourceUsage, SledUsage, ZpoolUsage};
+pub use config::{
+    Config, ConfigFaults, ConfigHardware, ConfigStorage, ConfigZpool, SimMode,
+};
+pub use faults::{SimFault, SimFaultRule, SimFaultTarget};
 pub use server::{run_server, Server};
 pub use sled_agent::SledAgent;
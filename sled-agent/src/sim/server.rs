@@ -43,12 +43,15 @@ impl Server {
             "component" => "SledAgent",
             "server" => config.id.clone().to_string()
         ));
-        let sled_agent = Arc::new(SledAgent::new_simulated_with_id(
-            &config,
-            sa_log,
-            config.nexus_address,
-            Arc::clone(&nexus_client),
-        ));
+        let sled_agent = Arc::new(
+            SledAgent::new_simulated_with_id(
+                &config,
+                sa_log,
+                config.nexus_address,
+                Arc::clone(&nexus_client),
+            )
+            .map_err(|error| format!("initializing sled agent: {}", error))?,
+        );
 
         let sa = Arc::clone(&sled_agent);
         let dropshot_log = log.new(o!("component" => "dropshot"));
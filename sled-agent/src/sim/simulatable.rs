@@ -6,6 +6,7 @@ use crate::nexus::NexusClient;
 use async_trait::async_trait;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::Generation;
+use omicron_common::api::external::ResourceType;
 use std::fmt;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -109,6 +110,9 @@ pub trait Simulatable: fmt::Debug + Send + Sync {
     /// the object has been destroyed.
     fn ready_to_destroy(&self) -> bool;
 
+    /// Returns the type of resource being simulated (for error messages).
+    fn resource_type() -> ResourceType;
+
     /// Notifies Nexus (via `nexus_client`) about a new state (`current`) for
     /// the object identified by `id`.
     async fn notify(
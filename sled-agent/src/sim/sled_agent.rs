@@ -7,7 +7,8 @@
 use crate::nexus::NexusClient;
 use crate::params::{
     DiskStateRequested, InstanceHardware, InstanceRuntimeStateRequested,
-    InstanceSerialConsoleData,
+    InstanceSerialConsoleData, InstanceStateRequested, VpcFirewallRule,
+    VpcRoute,
 };
 use crate::serial::ByteOffset;
 use futures::lock::Mutex;
@@ -15,6 +16,7 @@ use omicron_common::api::external::{Error, InstanceState, ResourceType};
 use omicron_common::api::internal::nexus::DiskRuntimeState;
 use omicron_common::api::internal::nexus::InstanceRuntimeState;
 use slog::Logger;
+use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -24,9 +26,11 @@ use std::str::FromStr;
 
 use crucible_client_types::VolumeConstructionRequest;
 
+use super::capacity::{SimHardware, SledUsage};
 use super::collection::SimCollection;
 use super::config::Config;
 use super::disk::SimDisk;
+use super::faults::{SimFaultRule, SimFaultTarget, SimFaults};
 use super::instance::SimInstance;
 use super::storage::{CrucibleData, Storage};
 
@@ -40,9 +44,19 @@ use super::storage::{CrucibleData, Storage};
 pub struct SledAgent {
     /// collection of simulated instances, indexed by instance uuid
     instances: Arc<SimCollection<SimInstance>>,
+    /// external IP addresses most recently provided for each instance
+    instance_external_ips: Mutex<HashMap<Uuid, Vec<IpAddr>>>,
+    /// resolved firewall rules most recently provided for each VPC
+    vpc_firewall_rules: Mutex<HashMap<Uuid, Vec<VpcFirewallRule>>>,
+    /// resolved routes most recently provided for each VPC
+    vpc_routes: Mutex<HashMap<Uuid, Vec<VpcRoute>>>,
     /// collection of simulated disks, indexed by disk uuid
     disks: Arc<SimCollection<SimDisk>>,
     storage: Mutex<Storage>,
+    /// hardware threads and memory reserved by instances
+    hardware: Mutex<SimHardware>,
+    /// rules for injecting faults into requests to the sled's Crucible Agents
+    region_faults: Arc<Mutex<SimFaults>>,
     nexus_address: SocketAddr,
     pub nexus_client: Arc<NexusClient>,
     disk_id_to_region_ids: Mutex<HashMap<String, Vec<Uuid>>>,
@@ -90,12 +104,14 @@ impl SledAgent {
     // TODO-cleanup should this instantiate the NexusClient it needs?
     // Should it take a Config object instead of separate id, sim_mode, etc?
     /// Constructs a simulated SledAgent with the given uuid.
+    ///
+    /// Fails only if the configured fault injection rules are invalid.
     pub fn new_simulated_with_id(
         config: &Config,
         log: Logger,
         nexus_address: SocketAddr,
         nexus_client: Arc<NexusClient>,
-    ) -> SledAgent {
+    ) -> Result<SledAgent, Error> {
         let id = config.id;
         let sim_mode = config.sim_mode;
         info!(&log, "created simulated sled agent"; "sim_mode" => ?sim_mode);
@@ -104,27 +120,39 @@ impl SledAgent {
         let disk_log = log.new(o!("kind" => "disks"));
         let storage_log = log.new(o!("kind" => "storage"));
 
-        SledAgent {
+        let region_faults = Arc::new(Mutex::new(SimFaults::with_rules(
+            &config.faults.regions,
+        )?));
+
+        Ok(SledAgent {
             instances: Arc::new(SimCollection::new(
                 Arc::clone(&nexus_client),
                 instance_log,
                 sim_mode,
+                SimFaults::with_rules(&config.faults.instances)?,
             )),
+            instance_external_ips: Mutex::new(HashMap::new()),
+            vpc_firewall_rules: Mutex::new(HashMap::new()),
+            vpc_routes: Mutex::new(HashMap::new()),
             disks: Arc::new(SimCollection::new(
                 Arc::clone(&nexus_client),
                 disk_log,
                 sim_mode,
+                SimFaults::with_rules(&config.faults.disks)?,
             )),
             storage: Mutex::new(Storage::new(
                 id,
                 Arc::clone(&nexus_client),
                 config.storage.ip,
+                Arc::clone(&region_faults),
                 storage_log,
             )),
+            hardware: Mutex::new(SimHardware::new(config.hardware.clone())),
+            region_faults,
             nexus_address,
             nexus_client,
             disk_id_to_region_ids: Mutex::new(HashMap::new()),
-        }
+        })
     }
 
     /// Map disk id to regions for later lookup
@@ -194,15 +222,50 @@ impl SledAgent {
         initial_hardware: InstanceHardware,
         target: InstanceRuntimeStateRequested,
     ) -> Result<InstanceRuntimeState, Error> {
-        // respond with a fake 500 level failure if asked to ensure an instance
-        // with more than 16 CPUs.
-        let ncpus: i64 = (&initial_hardware.runtime.ncpus).into();
-        if ncpus > 16 {
-            return Err(Error::internal_error(
-                &"could not allocate an instance: ran out of CPUs!",
-            ));
+        // An instance that's supposed to be running needs its vCPUs and
+        // memory.  Reserve them before doing anything else, and give them
+        // back if we fail.
+        let run_state = target.run_state;
+        let reserved = match run_state {
+            InstanceStateRequested::Running
+            | InstanceStateRequested::Reboot
+            | InstanceStateRequested::Migrating => {
+                let runtime = &initial_hardware.runtime;
+                self.hardware.lock().await.reserve(
+                    instance_id,
+                    u64::from(runtime.ncpus.0),
+                    runtime.memory.to_bytes(),
+                )?
+            }
+            InstanceStateRequested::Stopped
+            | InstanceStateRequested::Destroyed => false,
         };
 
+        let result = self
+            .instance_ensure_reserved(instance_id, initial_hardware, target)
+            .await;
+        match (&result, run_state) {
+            (Err(_), _) if reserved => {
+                self.hardware.lock().await.release(&instance_id)
+            }
+            (
+                Ok(_),
+                InstanceStateRequested::Stopped
+                | InstanceStateRequested::Destroyed,
+            ) => self.hardware.lock().await.release(&instance_id),
+            _ => (),
+        }
+        result
+    }
+
+    /// Body of `instance_ensure()`, run once the instance's hardware has been
+    /// reserved (if needed)
+    async fn instance_ensure_reserved(
+        self: &Arc<Self>,
+        instance_id: Uuid,
+        initial_hardware: InstanceHardware,
+        target: InstanceRuntimeStateRequested,
+    ) -> Result<InstanceRuntimeState, Error> {
         for disk in &initial_hardware.disks {
             let initial_state = DiskRuntimeState {
                 disk_state: omicron_common::api::external::DiskState::Attached(
@@ -227,6 +290,10 @@ impl SledAgent {
             .instances
             .sim_ensure(&instance_id, initial_hardware.runtime, target)
             .await?;
+        self.instance_external_ips
+            .lock()
+            .await
+            .insert(instance_id, initial_hardware.external_ips);
 
         for disk_request in &initial_hardware.disks {
             self.map_disk_ids_to_region_ids(
@@ -238,6 +305,64 @@ impl SledAgent {
         Ok(instance_run_time_state)
     }
 
+    /// Replaces the external IP addresses of an instance on this sled
+    pub async fn instance_external_ips_ensure(
+        &self,
+        instance_id: Uuid,
+        external_ips: Vec<IpAddr>,
+    ) -> Result<(), Error> {
+        self.instances.sim_fault_inject(&instance_id).await?;
+        if !self.instances.sim_contains(&instance_id).await {
+            return Err(Error::not_found_by_id(
+                ResourceType::Instance,
+                &instance_id,
+            ));
+        }
+        self.instance_external_ips
+            .lock()
+            .await
+            .insert(instance_id, external_ips);
+        Ok(())
+    }
+
+    /// Returns the external IP addresses most recently provided for instance
+    /// `instance_id`, if it has ever been on this sled
+    pub async fn instance_external_ips(
+        &self,
+        instance_id: Uuid,
+    ) -> Option<Vec<IpAddr>> {
+        self.instance_external_ips.lock().await.get(&instance_id).cloned()
+    }
+
+    /// Replaces the firewall rules of a VPC on this sled
+    pub async fn vpc_firewall_rules_ensure(
+        &self,
+        vpc_id: Uuid,
+        rules: Vec<VpcFirewallRule>,
+    ) {
+        self.vpc_firewall_rules.lock().await.insert(vpc_id, rules);
+    }
+
+    /// Returns the firewall rules most recently provided for VPC `vpc_id`,
+    /// if any were ever sent to this sled
+    pub async fn vpc_firewall_rules(
+        &self,
+        vpc_id: Uuid,
+    ) -> Option<Vec<VpcFirewallRule>> {
+        self.vpc_firewall_rules.lock().await.get(&vpc_id).cloned()
+    }
+
+    /// Replaces the routes of a VPC on this sled
+    pub async fn vpc_routes_ensure(&self, vpc_id: Uuid, routes: Vec<VpcRoute>) {
+        self.vpc_routes.lock().await.insert(vpc_id, routes);
+    }
+
+    /// Returns the routes most recently provided for VPC `vpc_id`, if any were
+    /// ever sent to this sled
+    pub async fn vpc_routes(&self, vpc_id: Uuid) -> Option<Vec<VpcRoute>> {
+        self.vpc_routes.lock().await.get(&vpc_id).cloned()
+    }
+
     /// Idempotently ensures that the given API Disk (described by `api_disk`)
     /// is attached (or not) as specified.  This simulates disk attach and
     /// detach, similar to instance boot and halt.
@@ -258,6 +383,61 @@ impl SledAgent {
         self.disks.sim_poke(id).await;
     }
 
+    /// Returns the ids of the instances on this sled that have not been
+    /// destroyed
+    pub async fn instance_ids(&self) -> Vec<Uuid> {
+        self.instances.sim_live_ids().await
+    }
+
+    /// Returns the ids of the disks on this sled that have not been destroyed
+    pub async fn disk_ids(&self) -> Vec<Uuid> {
+        self.disks.sim_live_ids().await
+    }
+
+    /// Returns the resources used by instances and Crucible regions on this
+    /// sled
+    pub async fn usage(&self) -> SledUsage {
+        let (hardware_threads, physical_ram) = {
+            let hardware = self.hardware.lock().await;
+            (hardware.hardware_threads(), hardware.physical_ram())
+        };
+        SledUsage {
+            hardware_threads,
+            physical_ram,
+            zpools: self.storage.lock().await.zpool_usage().await,
+        }
+    }
+
+    /// Adds a rule for injecting faults into requests for objects of kind
+    /// `target`.  Rules are matched in the order in which they were added.
+    pub async fn fault_add(
+        &self,
+        target: SimFaultTarget,
+        rule: SimFaultRule,
+    ) -> Result<(), Error> {
+        match target {
+            SimFaultTarget::Instances => {
+                self.instances.sim_fault_add(rule).await
+            }
+            SimFaultTarget::Disks => self.disks.sim_fault_add(rule).await,
+            SimFaultTarget::Regions => {
+                self.region_faults.lock().await.add(rule)
+            }
+        }
+    }
+
+    /// Removes all rules for injecting faults into requests for objects of
+    /// kind `target`
+    pub async fn faults_clear(&self, target: SimFaultTarget) {
+        match target {
+            SimFaultTarget::Instances => {
+                self.instances.sim_faults_clear().await
+            }
+            SimFaultTarget::Disks => self.disks.sim_faults_clear().await,
+            SimFaultTarget::Regions => self.region_faults.lock().await.clear(),
+        }
+    }
+
     /// Adds a Zpool to the simulated sled agent.
     pub async fn create_zpool(&self, id: Uuid, size: u64) {
         self.storage.lock().await.insert_zpool(id, size).await;
@@ -8,6 +8,9 @@
 //! than the representation of "virtual disks" which would be presented
 //! through Nexus' external API.
 
+use super::capacity::{ResourceUsage, ZpoolUsage};
+use super::faults::SimFaultRule;
+use super::faults::SimFaults;
 use crate::nexus::NexusClient;
 use anyhow::{bail, Result};
 use chrono::prelude::*;
@@ -18,6 +21,8 @@ use futures::lock::Mutex;
 use nexus_client::types::{
     ByteCount, DatasetKind, DatasetPutRequest, ZpoolPutRequest,
 };
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ResourceType;
 use slog::Logger;
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
@@ -33,19 +38,37 @@ struct CrucibleDataInner {
     running_snapshots: HashMap<Uuid, HashMap<String, RunningSnapshot>>,
     on_create: Option<CreateCallback>,
     next_port: u16,
+    /// bytes available for regions
+    capacity: u64,
+}
+
+/// Returns the number of bytes occupied by a region
+fn region_bytes(block_size: u64, extent_size: u64, extent_count: u64) -> u64 {
+    block_size * extent_size * extent_count
 }
 
 impl CrucibleDataInner {
-    fn new(crucible_port: u16) -> Self {
+    fn new(crucible_port: u16, capacity: u64) -> Self {
         Self {
             regions: HashMap::new(),
             snapshots: HashMap::new(),
             running_snapshots: HashMap::new(),
             on_create: None,
             next_port: crucible_port,
+            capacity,
         }
     }
 
+    /// Returns the number of bytes occupied by regions that have not failed
+    /// or been destroyed
+    fn used_bytes(&self) -> u64 {
+        self.regions
+            .values()
+            .filter(|r| !matches!(r.state, State::Failed | State::Destroyed))
+            .map(|r| region_bytes(r.block_size, r.extent_size, r.extent_count))
+            .sum()
+    }
+
     fn set_create_callback(&mut self, callback: CreateCallback) {
         self.on_create = Some(callback);
     }
@@ -57,7 +80,18 @@ impl CrucibleDataInner {
     fn create(&mut self, params: CreateRegion) -> Region {
         let id = Uuid::from_str(&params.id.0).unwrap();
 
-        let state = if let Some(on_create) = &self.on_create {
+        // Like the real Crucible Agent, we accept requests for regions that
+        // don't fit, but the regions fail to be created.
+        let size = region_bytes(
+            params.block_size,
+            params.extent_size,
+            params.extent_count,
+        );
+        let fits = self.regions.contains_key(&id)
+            || self.used_bytes() + size <= self.capacity;
+        let state = if !fits {
+            State::Failed
+        } else if let Some(on_create) = &self.on_create {
             on_create(&params)
         } else {
             State::Requested
@@ -240,11 +274,45 @@ impl CrucibleDataInner {
 /// Represents a running Crucible Agent. Contains regions.
 pub struct CrucibleData {
     inner: Mutex<CrucibleDataInner>,
+    /// fault injection rules, shared by all of the sled's Crucible Agents
+    faults: Arc<Mutex<SimFaults>>,
 }
 
 impl CrucibleData {
-    fn new(crucible_port: u16) -> Self {
-        Self { inner: Mutex::new(CrucibleDataInner::new(crucible_port)) }
+    fn new(
+        crucible_port: u16,
+        capacity: u64,
+        faults: Arc<Mutex<SimFaults>>,
+    ) -> Self {
+        Self {
+            inner: Mutex::new(CrucibleDataInner::new(crucible_port, capacity)),
+            faults,
+        }
+    }
+
+    /// Adds a rule for injecting faults into requests to create or delete
+    /// regions.  Rules apply to every Crucible Agent on the sled.
+    pub async fn fault_add(&self, rule: SimFaultRule) -> Result<(), Error> {
+        self.faults.lock().await.add(rule)
+    }
+
+    /// Removes all fault injection rules
+    pub async fn faults_clear(&self) {
+        self.faults.lock().await.clear();
+    }
+
+    /// Injects whatever fault applies to a request for region `id`
+    pub async fn fault_inject(&self, id: &RegionId) -> Result<(), Error> {
+        let id = Uuid::from_str(&id.0).map_err(|_| {
+            Error::invalid_request(&format!("bad region id: {:?}", id.0))
+        })?;
+        let fault = self.faults.lock().await.take(&id);
+        // There's no resource type for regions, so a simulated "not found"
+        // error refers to the dataset that contains them.
+        match fault {
+            Some(fault) => fault.inject(ResourceType::Dataset, &id).await,
+            None => Ok(()),
+        }
     }
 
     pub async fn set_create_callback(&self, callback: CreateCallback) {
@@ -322,6 +390,12 @@ impl CrucibleData {
     pub async fn is_empty(&self) -> bool {
         self.inner.lock().await.is_empty()
     }
+
+    /// Returns the number of bytes occupied by regions that have not failed
+    /// or been destroyed
+    pub async fn used_bytes(&self) -> u64 {
+        self.inner.lock().await.used_bytes()
+    }
 }
 
 /// A simulated Crucible Dataset.
@@ -333,11 +407,17 @@ pub struct CrucibleServer {
 }
 
 impl CrucibleServer {
-    fn new(log: &Logger, crucible_ip: IpAddr, crucible_port: u16) -> Self {
+    fn new(
+        log: &Logger,
+        crucible_ip: IpAddr,
+        crucible_port: u16,
+        capacity: u64,
+        faults: Arc<Mutex<SimFaults>>,
+    ) -> Self {
         // SocketAddr::new with port set to 0 will grab any open port to host
         // the emulated crucible agent, but set the fake downstairs listen ports
         // to start at `crucible_port`.
-        let data = Arc::new(CrucibleData::new(crucible_port));
+        let data = Arc::new(CrucibleData::new(crucible_port, capacity, faults));
         let config = dropshot::ConfigDropshot {
             bind_address: SocketAddr::new(crucible_ip, 0),
             ..Default::default()
@@ -367,12 +447,14 @@ impl CrucibleServer {
 }
 
 struct Zpool {
+    /// size of the zpool in bytes
+    size: u64,
     datasets: HashMap<Uuid, CrucibleServer>,
 }
 
 impl Zpool {
-    fn new() -> Self {
-        Zpool { datasets: HashMap::new() }
+    fn new(size: u64) -> Self {
+        Zpool { size, datasets: HashMap::new() }
     }
 
     fn insert_dataset(
@@ -381,9 +463,20 @@ impl Zpool {
         id: Uuid,
         crucible_ip: IpAddr,
         crucible_port: u16,
+        faults: Arc<Mutex<SimFaults>>,
     ) -> &CrucibleServer {
-        self.datasets
-            .insert(id, CrucibleServer::new(log, crucible_ip, crucible_port));
+        // Each dataset may use the whole zpool.  This is accurate as long as
+        // there's only one dataset per zpool, which is all we create today.
+        self.datasets.insert(
+            id,
+            CrucibleServer::new(
+                log,
+                crucible_ip,
+                crucible_port,
+                self.size,
+                faults,
+            ),
+        );
         self.datasets
             .get(&id)
             .expect("Failed to get the dataset we just inserted")
@@ -429,6 +522,7 @@ pub struct Storage {
     zpools: HashMap<Uuid, Zpool>,
     crucible_ip: IpAddr,
     next_crucible_port: u16,
+    region_faults: Arc<Mutex<SimFaults>>,
 }
 
 impl Storage {
@@ -436,6 +530,7 @@ impl Storage {
         sled_id: Uuid,
         nexus_client: Arc<NexusClient>,
         crucible_ip: IpAddr,
+        region_faults: Arc<Mutex<SimFaults>>,
         log: Logger,
     ) -> Self {
         Self {
@@ -445,13 +540,14 @@ impl Storage {
             zpools: HashMap::new(),
             crucible_ip,
             next_crucible_port: 100,
+            region_faults,
         }
     }
 
     /// Adds a Zpool to the sled's simulated storage and notifies Nexus.
     pub async fn insert_zpool(&mut self, zpool_id: Uuid, size: u64) {
         // Update our local data
-        self.zpools.insert(zpool_id, Zpool::new());
+        self.zpools.insert(zpool_id, Zpool::new(size));
 
         // Notify Nexus
         let request = ZpoolPutRequest { size: ByteCount(size) };
@@ -473,6 +569,7 @@ impl Storage {
                 dataset_id,
                 self.crucible_ip,
                 self.next_crucible_port,
+                Arc::clone(&self.region_faults),
             );
 
         self.next_crucible_port += 100;
@@ -503,6 +600,23 @@ impl Storage {
             .clone()
     }
 
+    /// Returns the space used by Crucible regions in each zpool
+    pub async fn zpool_usage(&self) -> Vec<ZpoolUsage> {
+        let mut usage = Vec::with_capacity(self.zpools.len());
+        for (id, zpool) in &self.zpools {
+            let mut used = 0;
+            for dataset in zpool.datasets.values() {
+                used += dataset.data().used_bytes().await;
+            }
+            usage.push(ZpoolUsage {
+                id: *id,
+                size: ResourceUsage { total: zpool.size, used },
+            });
+        }
+        usage.sort_by_key(|zpool| zpool.id);
+        usage
+    }
+
     pub async fn get_dataset_for_region(
         &self,
         region_id: Uuid,
@@ -17,17 +17,18 @@ use crate::nexus::LazyNexusClient;
 use crate::params::{
     DatasetKind, DiskStateRequested, InstanceHardware, InstanceMigrateParams,
     InstanceRuntimeStateRequested, InstanceSerialConsoleData,
-    ServiceEnsureBody, VpcFirewallRule,
+    ServiceEnsureBody, VpcFirewallRule, VpcRoute,
 };
 use crate::services::{self, ServiceManager};
 use crate::storage_manager::StorageManager;
 use dropshot::HttpError;
 use futures::stream::{self, StreamExt, TryStreamExt};
 use omicron_common::api::{
-    internal::nexus::DiskRuntimeState, internal::nexus::InstanceRuntimeState,
-    internal::nexus::UpdateArtifact,
+    external, internal::nexus::DiskRuntimeState,
+    internal::nexus::InstanceRuntimeState, internal::nexus::UpdateArtifact,
 };
 use slog::Logger;
+use std::net::IpAddr;
 use std::net::SocketAddrV6;
 use std::process::Command;
 use uuid::Uuid;
@@ -355,6 +356,18 @@ impl SledAgent {
             .map_err(|e| Error::Instance(e))
     }
 
+    /// Replaces the external IP addresses of an Instance running on the sled.
+    pub async fn instance_external_ips_ensure(
+        &self,
+        instance_id: Uuid,
+        external_ips: Vec<IpAddr>,
+    ) -> Result<(), Error> {
+        self.instances
+            .instance_external_ips_ensure(instance_id, external_ips)
+            .await
+            .map_err(|e| Error::Instance(e))
+    }
+
     /// Idempotently ensures that the given virtual disk is attached (or not) as
     /// specified.
     ///
@@ -432,6 +445,15 @@ impl SledAgent {
     ) -> Result<(), Error> {
         self.instances.firewall_rules_ensure(rules).await.map_err(Error::from)
     }
+
+    pub async fn routes_ensure(
+        &self,
+        _vpc_id: Uuid,
+        vni: external::Vni,
+        routes: &[VpcRoute],
+    ) -> Result<(), Error> {
+        self.instances.routes_ensure(vni, routes).await.map_err(Error::from)
+    }
 }
 
 // Delete all underlay addresses created directly over the etherstub VNIC used